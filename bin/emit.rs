@@ -0,0 +1,73 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::{env, fs, path::Path, process};
+
+use front_end::{diagnostic::Diagnostic, emit};
+
+const USAGE: &str = "\
+usage:
+
+$ cargo run --bin emit -- --emit stage[,stage...] [--out-dir dir] path_to_script_file
+
+stages: tokens, ast, ast-json, hir, ir, module
+
+a single stage without `--out-dir` is printed to the standard output, otherwise
+each stage is written to `dir/{file_stem}.{stage_extension}` (the default `dir`
+is the directory of the script file).
+
+e.g.
+$ cargo run --bin emit -- --emit hir scripts/01-base-expression.xuan
+$ cargo run --bin emit -- --emit tokens,ast,hir --out-dir target/emit scripts/01-base-expression.xuan";
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let (stages, out_dir, file_path) = match args.as_slice() {
+        [_, flag, stages, file_path] if flag == "--emit" => (stages, None, file_path),
+        [_, flag, stages, out_flag, out_dir, file_path]
+            if flag == "--emit" && out_flag == "--out-dir" =>
+        {
+            (stages, Some(out_dir), file_path)
+        }
+        _ => {
+            println!("{}", USAGE);
+            process::exit(1);
+        }
+    };
+
+    let stages = emit::parse_stages(stages).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+
+    let source = fs::read_to_string(file_path).unwrap();
+
+    let outputs = emit::emit_stages(&source, &stages).unwrap_or_else(|e| {
+        eprint!("{}", Diagnostic::from(&e).render(file_path, &source));
+        process::exit(1);
+    });
+
+    if let ([(_, text)], None) = (outputs.as_slice(), out_dir) {
+        print!("{}", text);
+        return;
+    }
+
+    let source_path = Path::new(file_path);
+    let out_dir = match out_dir {
+        Some(dir) => Path::new(dir),
+        None => source_path.parent().unwrap_or(Path::new(".")),
+    };
+    fs::create_dir_all(out_dir).unwrap();
+
+    for (stage, text) in outputs {
+        let path = emit::output_path(source_path, out_dir, stage);
+        fs::write(&path, text).unwrap();
+        eprintln!("{}: {}", stage, path.display());
+    }
+}
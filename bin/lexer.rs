@@ -7,28 +7,37 @@ use std::{env, fs, process};
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.
  */
-use front_end::lexer;
+use front_end::{lexer, token};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() != 2 {
-        println!(
-            "\
+    // `tokens` 子命令以表格的形式输出 Token（见 `token::dump`）
+    let (is_table, file_path) = match args.as_slice() {
+        [_, file_path] => (false, file_path),
+        [_, command, file_path] if command == "tokens" => (true, file_path),
+        _ => {
+            println!(
+                "\
 usage:
 
-$ cargo run --bin lexer path_to_script_file
+$ cargo run --bin lexer [tokens] path_to_script_file
 e.g.
-$ cargo run --bin lexer scripts/01-base-expression.xuan"
-        );
-        process::exit(1);
-    }
+$ cargo run --bin lexer scripts/01-base-expression.xuan
+$ cargo run --bin lexer tokens scripts/01-base-expression.xuan"
+            );
+            process::exit(1);
+        }
+    };
 
-    let file_path = &args[1];
     let program = fs::read_to_string(file_path).unwrap();
     let result = lexer::tokenize(&program).unwrap();
 
-    for token in result {
-        println!("{:?}", token);
+    if is_table {
+        print!("{}", token::dump(&result));
+    } else {
+        for token in result {
+            println!("{:?}", token);
+        }
     }
 }
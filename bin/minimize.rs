@@ -0,0 +1,46 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::{env, fs, panic, process};
+
+use front_end::minimize;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let file_path = match args.as_slice() {
+        [_, file_path] => file_path,
+        _ => {
+            println!(
+                "\
+usage:
+
+$ cargo run --bin minimize path_to_failing_script_file
+e.g.
+$ cargo run --bin minimize crash.xuan > crash.min.xuan"
+            );
+            process::exit(1);
+        }
+    };
+
+    let source = fs::read_to_string(file_path).unwrap();
+
+    // 最小化的过程会多次触发 panic，不输出 panic 的信息
+    panic::set_hook(Box::new(|_| {}));
+
+    match minimize::minimize_parse_failure(&source) {
+        Some((minimized, failure)) => {
+            eprintln!("{}", failure);
+            println!("{}", minimized);
+        }
+        None => {
+            eprintln!("the source parses successfully, nothing to minimize");
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::{env, fs, process};
+
+use front_end::profile::{self, CountingAllocator};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let file_path = match args.as_slice() {
+        [_, file_path] => file_path,
+        _ => {
+            println!(
+                "\
+usage:
+
+$ cargo run --release --bin profile path_to_script_file
+e.g.
+$ cargo run --release --bin profile scripts/01-base-expression.xuan"
+            );
+            process::exit(1);
+        }
+    };
+
+    let source = fs::read_to_string(file_path).unwrap();
+
+    match profile::memory_stats(&source) {
+        Ok(stats) => print!("{}", stats),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
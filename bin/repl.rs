@@ -0,0 +1,37 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::io::{self, BufRead, Write};
+
+use front_end::repl::{Session, HELP_TEXT};
+
+fn main() {
+    println!("XiaoXuan REPL, type :help for help");
+
+    let mut session = Session::new();
+    let stdin = io::stdin();
+
+    loop {
+        print!("> ");
+        io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            // 输入结束（Ctrl+D）
+            println!();
+            break;
+        }
+
+        match line.trim() {
+            "" => continue,
+            ":quit" | ":q" => break,
+            ":help" => println!("{}", HELP_TEXT),
+            _ => println!("{}", session.eval_line(&line).trim_end()),
+        }
+    }
+}
@@ -0,0 +1,303 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::{env, fs, path::Path, process, thread, time::Duration};
+
+use front_end::{
+    backend::{CompilerOptions, DEFAULT_BACKEND},
+    diagnostic::Diagnostic,
+    driver::Compiler,
+    emit::{self, Stage},
+    lint::LintOptions,
+    query::Database,
+};
+
+const USAGE: &str = "\
+usage:
+
+$ xuanc check [--watch] [--env name=value]... [--max-complexity n] path_to_script_file...
+$ xuanc ast path_to_script_file
+$ xuanc tokens path_to_script_file
+$ xuanc build [--backend name] [--out-dir dir] [--env name=value]... [--max-complexity n] [--entry name | --lib] path_to_script_file...
+
+- check: check the source files and report the errors and warnings (lints,
+  e.g. the use of deprecated functions). with `--watch`, keep
+  running and check again whenever a source file changes, only the changed
+  files and the files importing a changed module interface (the module name
+  is the file name without extension) are analyzed again;
+- ast: print the AST of the source file;
+- tokens: print the tokens of the source file;
+- build: compile the source files, each artifact is written to
+  `dir/{file_stem}.{backend_extension}` (the default `dir` is the directory of
+  the source file, the default backend is `vm`). an executable program needs
+  an entry function (`main` by default, or the name given by `--entry`) in
+  one of the source files, `--lib` builds a library without entry function.
+
+`--env name=value` defines a build environment value, which is read by the
+`env(\"name\")` expression in the source files.
+
+`--max-complexity n` sets the limit of the cyclomatic complexity of a function
+(10 by default), functions exceeding it are reported as warnings.
+
+the exit code is 0 on success (warnings do not fail the command), 1 when there
+are errors in the source files, and 2 when the arguments are invalid.
+
+e.g.
+$ cargo run --bin xuanc -- check scripts/01-base-expression.xuan
+$ cargo run --bin xuanc -- build --lib --out-dir target/build scripts/01-base-expression.xuan";
+
+const EXIT_ERROR: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+// 监视模式检查源文件是否被修改的时间间隔
+const WATCH_INTERVAL: Duration = Duration::from_millis(300);
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.split_first() {
+        Some((command, options)) if command == "check" => check(options),
+        Some((command, [file])) if command == "ast" => dump(file, Stage::Ast),
+        Some((command, [file])) if command == "tokens" => dump(file, Stage::Tokens),
+        Some((command, options)) if command == "build" => build(options),
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(EXIT_USAGE);
+}
+
+// 读取 `--max-complexity n` 选项的值
+fn set_max_complexity(options: &mut LintOptions, value: Option<&String>) {
+    match value.and_then(|v| v.parse().ok()) {
+        Some(n) => options.max_complexity = n,
+        None => usage(),
+    }
+}
+
+// 读取 `--env name=value` 选项的值
+fn set_env(options: &mut CompilerOptions, definition: Option<&String>) {
+    match definition.and_then(|d| d.split_once('=')) {
+        Some((name, value)) if !name.is_empty() => options.environment.set(name, value),
+        _ => usage(),
+    }
+}
+
+fn check(options: &[String]) {
+    let mut compiler_options = CompilerOptions::default();
+    let mut lint_options = LintOptions::default();
+    let mut files = vec![];
+    let mut is_watch = false;
+
+    let mut iter = options.iter();
+    while let Some(option) = iter.next() {
+        match option.as_str() {
+            "--env" => set_env(&mut compiler_options, iter.next()),
+            "--max-complexity" => set_max_complexity(&mut lint_options, iter.next()),
+            "--watch" => is_watch = true,
+            _ if option.starts_with("--") => usage(),
+            _ => files.push(option),
+        }
+    }
+
+    if files.is_empty() {
+        usage();
+    }
+
+    let compiler = files
+        .iter()
+        .fold(
+            Compiler::new()
+                .with_options(compiler_options)
+                .with_lint_options(lint_options),
+            |compiler, file| compiler.add_source(file),
+        )
+        .analyze();
+
+    if is_watch {
+        watch(compiler);
+    }
+
+    // 没有错误时也输出警告
+    eprint!("{}", compiler.render_diagnostics());
+    if compiler.has_errors() {
+        process::exit(EXIT_ERROR);
+    }
+}
+
+// 监视模式：每隔一段时间检查源文件的修改时间，有源文件被修改时重新读取，然后清屏并重新输出所有的诊断信息。
+//
+// 只重新分析被修改的源文件（见 `Compiler::update_source_text`），以及导入了接口改变的模块的
+// 源文件（见 `Compiler::invalidate`）。源文件的模块名称为文件名（不包括扩展名），
+// 模块的接口以及源文件导入的模块由查询数据库计算（见 `query` 模块），
+// 只修改函数的主体时模块的接口不变，导入它的源文件也就不需要重新分析。
+fn watch(mut compiler: Compiler) -> ! {
+    let modified_time = |index: usize, compiler: &Compiler| {
+        let path = compiler.sources().path(index).unwrap();
+        fs::metadata(path).and_then(|m| m.modified()).ok()
+    };
+
+    let count = compiler.sources().len();
+    let mut times: Vec<_> = (0..count).map(|i| modified_time(i, &compiler)).collect();
+
+    let modules: Vec<String> = (0..count)
+        .map(|index| {
+            let path = compiler.sources().path(index).unwrap();
+            path.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let mut database = Database::new();
+    for index in 0..count {
+        if let Some(text) = compiler.sources().text(index) {
+            database.set_source(index, text);
+        }
+    }
+
+    loop {
+        // 清屏并把光标移到左上角
+        eprint!("\x1b[2J\x1b[H{}", compiler.render_diagnostics());
+        let errors = compiler
+            .diagnostics()
+            .iter()
+            .filter(|d| d.diagnostic.is_error())
+            .count();
+        eprintln!(
+            "[{} error(s), {} warning(s)] watching {} source file(s), press Ctrl+C to exit",
+            errors,
+            compiler.diagnostics().len() - errors,
+            count
+        );
+
+        // 接口改变了的模块的名称
+        let mut changed_modules = vec![];
+        let mut is_changed = false;
+        while !is_changed {
+            thread::sleep(WATCH_INTERVAL);
+
+            for (index, time) in times.iter_mut().enumerate() {
+                let current = modified_time(index, &compiler);
+                if current == *time {
+                    continue;
+                }
+                *time = current;
+
+                // 读取失败（比如文件正在被编辑器替换）时保留原来的内容，等待下一次修改
+                let path = compiler.sources().path(index).unwrap().to_path_buf();
+                let Ok(text) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                if !compiler.update_source_text(index, &text) {
+                    continue;
+                }
+                is_changed = true;
+
+                let interface = database.interface(index);
+                database.set_source(index, &text);
+                if database.interface(index) != interface {
+                    changed_modules.push(&modules[index]);
+                }
+            }
+        }
+
+        for index in 0..count {
+            let imports = database.imports(index);
+            if changed_modules.iter().any(|m| imports.contains(m)) {
+                compiler.invalidate(index);
+            }
+        }
+
+        compiler = compiler.analyze();
+    }
+}
+
+fn dump(file: &str, stage: Stage) {
+    let source = fs::read_to_string(file).unwrap_or_else(|e| {
+        eprintln!("failed to read file \"{}\": {}", file, e);
+        process::exit(EXIT_ERROR);
+    });
+
+    let text = emit::emit(&source, stage).unwrap_or_else(|e| {
+        eprint!("{}", Diagnostic::from(&e).render(file, &source));
+        process::exit(EXIT_ERROR);
+    });
+    print!("{}", text);
+}
+
+fn build(options: &[String]) {
+    let mut backend = DEFAULT_BACKEND;
+    let mut out_dir = None;
+    let mut compiler_options = CompilerOptions::default();
+    let mut lint_options = LintOptions::default();
+    let mut files = vec![];
+
+    let mut iter = options.iter();
+    while let Some(option) = iter.next() {
+        match option.as_str() {
+            "--backend" => backend = iter.next().unwrap_or_else(|| usage()),
+            "--out-dir" => out_dir = Some(Path::new(iter.next().unwrap_or_else(|| usage()))),
+            "--env" => set_env(&mut compiler_options, iter.next()),
+            "--max-complexity" => set_max_complexity(&mut lint_options, iter.next()),
+            "--entry" => {
+                compiler_options.entry = Some(iter.next().unwrap_or_else(|| usage()).clone())
+            }
+            "--lib" => compiler_options.entry = None,
+            _ if option.starts_with("--") => usage(),
+            _ => files.push(option),
+        }
+    }
+
+    if files.is_empty() {
+        usage();
+    }
+
+    let mut compiler = files.iter().fold(
+        Compiler::new()
+            .with_options(compiler_options)
+            .with_lint_options(lint_options),
+        |compiler, file| compiler.add_source(file),
+    );
+    let artifacts = compiler.emit(backend);
+
+    eprint!("{}", compiler.render_diagnostics());
+    if compiler.has_errors() {
+        process::exit(EXIT_ERROR);
+    }
+
+    // `emit` 成功时后端一定存在
+    let backend = compiler.registry().get(backend).unwrap();
+    let extension = backend.capabilities().extension;
+
+    for (index, artifact) in artifacts {
+        let source_path = compiler.sources().path(index).unwrap();
+        let dir = match out_dir {
+            Some(dir) => dir,
+            None => source_path.parent().unwrap_or(Path::new(".")),
+        };
+
+        let stem = source_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let path = dir.join(format!("{}.{}", stem, extension));
+
+        let result = fs::create_dir_all(dir)
+            .map_err(|e| format!("failed to create directory \"{}\": {}", dir.display(), e))
+            .and_then(|_| backend.emit(&artifact, &path).map_err(|e| e.to_string()));
+        if let Err(message) = result {
+            eprintln!("{}", message);
+            process::exit(EXIT_ERROR);
+        }
+
+        eprintln!("{}: {}", source_path.display(), path.display());
+    }
+}
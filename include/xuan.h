@@ -0,0 +1,56 @@
+/**
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// XiaoXuan 词法分析器和语法分析器的 C 语言接口
+//
+// 动态库需使用 `capi` 特性编译：
+// $ cargo build --release --features capi
+
+#ifndef XUAN_H
+#define XUAN_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define XUAN_OK 0
+#define XUAN_ERROR_LEXER 1
+#define XUAN_ERROR_PARSER 2
+#define XUAN_ERROR_INVALID_ARGUMENT 3
+#define XUAN_ERROR_INTERNAL 4
+
+typedef struct XuanError {
+    int32_t code;
+    char *message; // 当 code 为 XUAN_OK 时为 NULL
+    size_t file_id;
+    size_t start;
+    size_t end;
+} XuanError;
+
+typedef struct XuanResult {
+    char *json; // 当发生错误时为 NULL
+    XuanError error;
+} XuanResult;
+
+// 对源代码进行词法分析，以 JSON 数组的格式返回所有 Token
+XuanResult *xuan_tokenize(const char *source);
+
+// 对源代码进行语法分析，以 JSON 文档的格式返回 AST（格式见 `docs/ast_json_schema.md`）
+XuanResult *xuan_parse_to_json(const char *source);
+
+// 释放以上函数返回的结果
+void xuan_free_result(XuanResult *result);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // XUAN_H
@@ -9,6 +9,8 @@ use std::fmt::{Display, Write};
 
 use crate::token::Token;
 
+#[allow(clippy::large_enum_variant)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Node {
     Program(Program),
@@ -16,8 +18,13 @@ pub enum Node {
     Expression(Expression),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Program {
+    // 程序（模块）所属的命名空间路径，由程序开头的 `namespace` 语句指定，
+    // 没有 `namespace` 语句时为空
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub namespace: Vec<String>,
     pub body: Vec<Statement>,
     pub range: Range,
 }
@@ -58,13 +65,14 @@ impl Display for Node {
 //  | Expression
 //  ;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Statement {
     FunctionDeclaration(FunctionDeclaration),
     EmptyFunctionDeclaration(EmptyFunctionDeclaration), // 空函数
     PatternFunctionDeclaration(PatternFunctionDeclaration), // 模式函数
 
-    // NamespaceStatement(NamespaceStatement),
+    NamespaceStatement(NamespaceStatement),
     UseStatement(UseStatement),
     ConstDeclaration(ConstDeclaration),
 
@@ -77,11 +85,17 @@ pub enum Statement {
     ImplStatement(ImplStatement),
     AliasStatement(AliasStatement),
     Expression(Expression),
+
+    // 无法分析的语句，只出现在带错误恢复的语法分析的结果里（见 `parser::parse_with_recovery`）
+    Error(ErrorStatement),
 }
 
 // 函数的定义语句
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct FunctionDeclaration {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<Attribute>,
     pub name: String,
     pub generics: Vec<DataType>, // 泛型类型列表
     pub parameters: Vec<FunctionParameter>,
@@ -92,6 +106,7 @@ pub struct FunctionDeclaration {
 }
 
 // 普通函数的参数
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct FunctionParameter {
     pub data_type: DataType,
@@ -103,8 +118,11 @@ pub struct FunctionParameter {
 // 空函数定义语句
 //
 // 空函数无函数主体 `body`，也不支持 `where 从属表达式`，
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct EmptyFunctionDeclaration {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<Attribute>, // 比如外部函数的 `#[extern("wasi", "fd_write")]`
     pub name: String,
     pub generics: Vec<DataType>, // 泛型类型列表
     pub parameters: Vec<EmptyFunctionParameter>,
@@ -114,6 +132,7 @@ pub struct EmptyFunctionDeclaration {
 }
 
 // 空函数的参数需要指明名称，但不支持默认值
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct EmptyFunctionParameter {
     pub data_type: DataType,
@@ -122,6 +141,7 @@ pub struct EmptyFunctionParameter {
 }
 
 // 模式函数的定义语句
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct PatternFunctionDeclaration {
     pub name: String,
@@ -130,9 +150,31 @@ pub struct PatternFunctionDeclaration {
     pub return_data_type: Option<DataType>,
     pub only: Option<Expression>, // 在各个参数匹配后，模式函数的最后一道防线
     pub whiches: Vec<WhichEntry>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "PatternFunctionDeclaration::default_body")
+    )]
+    pub body: Expression,
     pub range: Range,
 }
 
+impl PatternFunctionDeclaration {
+    // 旧的 JSON 文档里没有函数主体，读取时使用空的表达式块
+    #[cfg(feature = "serde")]
+    fn default_body() -> Expression {
+        Expression::BlockExpression(BlockExpression {
+            is_explicit: false,
+            body: vec![],
+            range: Range {
+                file_id: 0,
+                start: 0,
+                end: 0,
+            },
+        })
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct PatternFunctionParameter {
     pub data_type: Option<DataType>,             // 变量的类型
@@ -144,61 +186,87 @@ pub struct PatternFunctionParameter {
     pub range: Range,
 }
 
-// #[derive(Debug, Clone, PartialEq)]
-// pub struct NamespaceStatement {
-//     pub dirs: Vec<String>,
-//     pub range: Range,
-// }
+// namespace name
+// namespace name::name::name
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamespaceStatement {
+    pub dirs: Vec<String>,
+    pub range: Range,
+}
 
 // use name
 // use name::name::name
-// use name::name{one, two, three::baz, four::{foo, bar}}
+// use name::name as other
+// use name::name::{one, two as other, three::baz, four::{foo, bar}}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct UseStatement {
     pub name_path: NamePath,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct NamePath {
     pub directories: Vec<NamePathItem>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub alias: Option<String>, // 导入之后使用的名称，即 `as` 后面的名称
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum NamePathItem {
     Name(String),
     Children(String, Vec<NamePath>),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct ConstDeclaration {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<Attribute>, // 比如 `#[deprecated]`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub data_type: Option<DataType>, // 省略时由常量的值推导
     pub name: String,
     pub value: Expression,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct MemberStructDeclaration {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<Attribute>, // 比如内存布局的 `#[packed]`
     pub name: String,
     pub members: Vec<StructMember>,
     pub generics: Vec<DataType>, // 泛型类型列表
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub whiches: Vec<WhichEntry>,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct TupleStructDeclaration {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<Attribute>,
     pub name: String,
     pub members: Vec<DataType>,
     pub generics: Vec<DataType>, // 泛型类型列表
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub whiches: Vec<WhichEntry>,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct EmptyStructDeclaration {
     pub name: String,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct StructMember {
     pub data_type: DataType,
@@ -206,13 +274,19 @@ pub struct StructMember {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct UnionDeclaration {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub name: String,
     pub members: Vec<UnionMember>,
     pub generics: Vec<DataType>, // 泛型类型列表
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub whiches: Vec<WhichEntry>,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum UnionMember {
     Struct(MemberStructDeclaration),
@@ -220,64 +294,116 @@ pub enum UnionMember {
     Empty(EmptyStructDeclaration),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct TraitDeclaration {
     pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub generics: Vec<DataType>, // 泛型类型列表
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub supertraits: Vec<DataType>, // 父特性列表，比如 `trait Ord limit Eq` 里的 `Eq`
     pub associated_types: Vec<AssociatedType>, // 关联类型
-    pub function_items: Vec<TraitFunctionItem>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub whiches: Vec<WhichEntry>,
+    pub function_items: Vec<TraitFunctionItem>, // 空函数为函数签名，普通函数为默认实现
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct AssociatedType {
     pub name: String,            // 关联类型名称
-    pub object_type: Identifier, // 默认类型
+    pub object_type: Option<Identifier>, // 特性里为默认类型，impl 语句里为具体类型
     pub range: Range,
 }
 
+#[allow(clippy::large_enum_variant)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum TraitFunctionItem {
     Function(FunctionDeclaration),
     EmptyFunction(EmptyFunctionDeclaration),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct ImplStatement {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub generics: Vec<DataType>, // 泛型类型列表，比如 `impl <T> List<T> trait ...` 里的 `T`
     pub object: Identifier,
     pub inherit: Identifier,                   // 一般是 trait 的名称
     pub associated_types: Vec<AssociatedType>, // 关联类型
     pub whiches: Vec<WhichEntry>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub function_items: Vec<FunctionDeclaration>,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct AliasStatement {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<Attribute>,
     pub name: String,
     pub data_type: DataType,
     pub generics: Vec<DataType>, // 泛型类型列表
     pub range: Range,
 }
 
-// 属性，目前仅支持标注在 `namespace` 以及 `function` 语句
+// 无法分析的语句，记录被跳过的 Token
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorStatement {
+    pub tokens: Vec<Token>,
+    pub range: Range,
+}
+
+// 属性，目前仅支持标注在 `namespace`、`function` 以及 `struct` 语句
 //
 // e.g.
 // `#[name(name1=value1, name2)]`
+// `#[deprecated("use bar instead")]`
 //
-// 其中变量值是可省的
+// 其中变量值是可省的，只有值的参数（比如上面的字符串）的名称为空字符串
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Attribute {
     pub name: String,                                 // 名称
     pub arguments: Vec<(String, Option<Expression>)>, // 变量名及值
 }
 
+impl Display for Attribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.arguments.is_empty() {
+            write!(f, "#[{}]", self.name)
+        } else {
+            let arguments = self
+                .arguments
+                .iter()
+                .map(|(name, value)| match value {
+                    Some(v) if name.is_empty() => v.to_string(),
+                    Some(v) => format!("{}={}", name, v),
+                    None => name.clone(),
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+            write!(f, "#[{}({})]", self.name, arguments)
+        }
+    }
+}
+
 impl Display for FunctionDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for attribute in &self.attributes {
+            writeln!(f, "{}", attribute)?;
+        }
+
         let mut segments = Vec::<String>::new();
 
         segments.push("function".to_string());
         segments.push(self.name.clone());
 
-        if self.generics.len() > 0 {
+        if !self.generics.is_empty() {
             segments.push(format!("<{}>", format_generics(&self.generics)));
         }
 
@@ -290,7 +416,7 @@ impl Display for FunctionDeclaration {
             segments.push(format!("type {}", d));
         }
 
-        if self.whiches.len() > 0 {
+        if !self.whiches.is_empty() {
             segments.push(format!(
                 "which {{\n{}\n}}",
                 format_which_entries(&self.whiches)
@@ -311,73 +437,311 @@ impl Display for FunctionDeclaration {
 
 impl Display for EmptyFunctionDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        for attribute in &self.attributes {
+            writeln!(f, "{}", attribute)?;
+        }
+
+        let mut segments = Vec::<String>::new();
+
+        segments.push("empty function".to_string());
+        segments.push(self.name.clone());
+
+        if !self.generics.is_empty() {
+            segments.push(format!("<{}>", format_generics(&self.generics)));
+        }
+
+        segments.push(format!(
+            "({})",
+            format_empty_function_parameters(&self.parameters)
+        ));
+
+        if let Some(d) = &self.return_data_type {
+            segments.push(format!("type {}", d));
+        }
+
+        if !self.whiches.is_empty() {
+            segments.push(format!(
+                "which {{\n{}\n}}",
+                format_which_entries(&self.whiches)
+            ));
+        }
+
+        writeln!(f, "{}", segments.join(" "))
     }
 }
 
 impl Display for PatternFunctionDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        let mut segments = Vec::<String>::new();
+
+        segments.push("pattern function".to_string());
+        segments.push(self.name.clone());
+
+        if !self.generics.is_empty() {
+            segments.push(format!("<{}>", format_generics(&self.generics)));
+        }
+
+        segments.push(format!(
+            "({})",
+            format_pattern_function_parameters(&self.parameters)
+        ));
+
+        if let Some(d) = &self.return_data_type {
+            segments.push(format!("type {}", d));
+        }
+
+        if !self.whiches.is_empty() {
+            segments.push(format!(
+                "which {{\n{}\n}}",
+                format_which_entries(&self.whiches)
+            ));
+        }
+
+        match &self.only {
+            // 标识符后面紧跟着函数主体的 `{` 会被当作结构体的实例化表达式，所以需要加上括号
+            Some(e @ Expression::Identifier(_)) => segments.push(format!("only ({})", e)),
+            Some(e) => segments.push(format!("only {}", e)),
+            None => {}
+        }
+
+        match &self.body {
+            Expression::BlockExpression(b) if !(b.is_explicit) => {
+                // 函数主体是隠式 do 表达式
+                segments.push(format!("{}", b))
+            }
+            _ => segments.push(format!("= {}", self.body)),
+        }
+
+        writeln!(f, "{}", segments.join(" "))
     }
 }
 
-// impl Display for NamespaceStatement {
-//     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-//         // todo!()
-//     }
-// }
+impl Display for NamespaceStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "namespace {}", self.dirs.join("::"))
+    }
+}
 
 impl Display for UseStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        writeln!(f, "use {}", self.name_path)
+    }
+}
+
+impl Display for NamePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let directories = self
+            .directories
+            .iter()
+            .map(|item| match item {
+                NamePathItem::Name(name) => name.clone(),
+                NamePathItem::Children(name, children) => format!(
+                    "{}::{{{}}}",
+                    name,
+                    children
+                        .iter()
+                        .map(|c| c.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+            })
+            .collect::<Vec<String>>()
+            .join("::");
+
+        match &self.alias {
+            Some(alias) => write!(f, "{} as {}", directories, alias),
+            None => write!(f, "{}", directories),
+        }
     }
 }
 
 impl Display for ConstDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        for attribute in &self.attributes {
+            writeln!(f, "{}", attribute)?;
+        }
+
+        match &self.data_type {
+            Some(d) => writeln!(f, "const {} {} = {}", d, self.name, self.value),
+            None => writeln!(f, "const {} = {}", self.name, self.value),
+        }
     }
 }
 
 impl Display for MemberStructDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        for attribute in &self.attributes {
+            writeln!(f, "{}", attribute)?;
+        }
+
+        writeln!(f, "struct {}", format_member_struct(self))
     }
 }
 
 impl Display for TupleStructDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        for attribute in &self.attributes {
+            writeln!(f, "{}", attribute)?;
+        }
+
+        writeln!(f, "struct {}", format_tuple_struct(self))
     }
 }
 
 impl Display for EmptyStructDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        writeln!(f, "struct {}", self.name)
     }
 }
 
 impl Display for UnionDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        let mut segments = Vec::<String>::new();
+
+        segments.push("union".to_string());
+        segments.push(self.name.clone());
+
+        if !self.generics.is_empty() {
+            segments.push(format!("<{}>", format_generics(&self.generics)));
+        }
+
+        if !self.whiches.is_empty() {
+            segments.push(format!(
+                "which {{\n{}\n}}",
+                format_which_entries(&self.whiches)
+            ));
+        }
+
+        let members = self
+            .members
+            .iter()
+            .map(|m| m.to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+        segments.push(format!("{{\n{}\n}}", members));
+
+        writeln!(f, "{}", segments.join(" "))
+    }
+}
+
+// 联合体的成员，跟结构体的定义相同，但没有关键字 `struct`
+impl Display for UnionMember {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnionMember::Struct(s) => write!(f, "{}", format_member_struct(s)),
+            UnionMember::Tuple(s) => write!(f, "{}", format_tuple_struct(s)),
+            UnionMember::Empty(s) => write!(f, "{}", s.name),
+        }
     }
 }
 
 impl Display for TraitDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        let mut segments = Vec::<String>::new();
+
+        segments.push("trait".to_string());
+        segments.push(self.name.clone());
+
+        if !self.generics.is_empty() {
+            segments.push(format!("<{}>", format_generics(&self.generics)));
+        }
+
+        if !self.supertraits.is_empty() {
+            let supertraits = self
+                .supertraits
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<String>>()
+                .join(" + ");
+            segments.push(format!("limit {}", supertraits));
+        }
+
+        if !self.associated_types.is_empty() {
+            segments.push(format_associated_types(&self.associated_types));
+        }
+
+        if !self.whiches.is_empty() {
+            segments.push(format!(
+                "which {{\n{}\n}}",
+                format_which_entries(&self.whiches)
+            ));
+        }
+
+        // 每个函数的输出都以换行符结尾
+        let items = self
+            .function_items
+            .iter()
+            .map(|item| match item {
+                TraitFunctionItem::Function(f) => f.to_string(),
+                TraitFunctionItem::EmptyFunction(f) => f.to_string(),
+            })
+            .collect::<String>();
+        segments.push(format!("{{\n{}}}", items));
+
+        writeln!(f, "{}", segments.join(" "))
     }
 }
 
 impl Display for ImplStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        let mut segments = Vec::<String>::new();
+
+        segments.push("impl".to_string());
+
+        if !self.generics.is_empty() {
+            segments.push(format!("<{}>", format_generics(&self.generics)));
+        }
+
+        segments.push(self.object.to_string());
+        segments.push(format!("trait {}", self.inherit));
+
+        if !self.associated_types.is_empty() {
+            segments.push(format_associated_types(&self.associated_types));
+        }
+
+        if !self.whiches.is_empty() {
+            segments.push(format!(
+                "which {{\n{}\n}}",
+                format_which_entries(&self.whiches)
+            ));
+        }
+
+        // 每个函数的输出都以换行符结尾
+        let items = self
+            .function_items
+            .iter()
+            .map(|f| f.to_string())
+            .collect::<String>();
+        segments.push(format!("{{\n{}}}", items));
+
+        writeln!(f, "{}", segments.join(" "))
     }
 }
 
 impl Display for AliasStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        for attribute in &self.attributes {
+            writeln!(f, "{}", attribute)?;
+        }
+
+        if self.generics.is_empty() {
+            writeln!(f, "alias {} = {}", self.name, self.data_type)
+        } else {
+            writeln!(
+                f,
+                "alias {} <{}> = {}",
+                self.name,
+                format_generics(&self.generics),
+                self.data_type
+            )
+        }
+    }
+}
+
+impl Display for ErrorStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let texts: Vec<String> = self.tokens.iter().map(|t| t.to_string()).collect();
+        writeln!(f, "{}", texts.join(" "))
     }
 }
 
@@ -387,7 +751,7 @@ impl Display for Statement {
             Statement::FunctionDeclaration(v) => write!(f, "{}", v),
             Statement::EmptyFunctionDeclaration(v) => write!(f, "{}", v),
             Statement::PatternFunctionDeclaration(v) => write!(f, "{}", v),
-            // Statement::NamespaceStatement(v) => write!(f, "{}", v),
+            Statement::NamespaceStatement(v) => write!(f, "{}", v),
             Statement::UseStatement(v) => write!(f, "{}", v),
             Statement::ConstDeclaration(v) => write!(f, "{}", v),
             Statement::MemberStructDeclaration(v) => write!(f, "{}", v),
@@ -398,8 +762,9 @@ impl Display for Statement {
             Statement::ImplStatement(v) => write!(f, "{}", v),
             Statement::AliasStatement(v) => write!(f, "{}", v),
             Statement::Expression(expression) => {
-                write!(f, "{}\n", expression)
+                writeln!(f, "{}", expression)
             }
+            Statement::Error(v) => write!(f, "{}", v),
         }
     }
 }
@@ -408,6 +773,7 @@ impl Display for Statement {
 // - 纯数据的类型，如基本数据类型、用户自定义类型（结构体和联合体）
 // - 特性（trait）
 // - 函数类型
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum DataType {
     Identifier(Identifier),
@@ -415,6 +781,17 @@ pub enum DataType {
     Sign(Sign),
 }
 
+impl DataType {
+    // 数据类型在源文件中的范围
+    pub fn range(&self) -> &Range {
+        match self {
+            DataType::Identifier(v) => &v.range,
+            DataType::Tuple(v) => &v.range,
+            DataType::Sign(v) => &v.range,
+        }
+    }
+}
+
 impl Display for DataType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -431,6 +808,7 @@ impl Display for DataType {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
     // general expressions
@@ -448,11 +826,13 @@ pub enum Expression {
 
     // operating expressions
     BinaryExpression(BinaryExpression),
+    NamedOperatorExpression(NamedOperatorExpression),
     UnaryExpression(UnaryExpression),
     FunctionCallExpression(FunctionCallExpression),
     MemberExpression(MemberExpression),
     SliceExpression(SliceExpression),
     ConstructorExpression(ConstructorExpression),
+    TupleConstructorExpression(TupleConstructorExpression),
 
     // primary expressions
     AnonymousFunction(AnonymousFunction),
@@ -466,6 +846,7 @@ pub enum Expression {
     Literal(Literal),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct BlockExpression {
     // 用于标记是 `do 表达式` 还是 `隠式 do 表达式`。
@@ -478,20 +859,25 @@ pub struct BlockExpression {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct JoinExpression {
     pub body: Vec<Expression>,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct LetExpression {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<Attribute>, // 只有语句级别的 `let` 才允许标注属性
     pub data_type: Option<DataType>, // 数据类型是可选的
     pub object: Box<Expression>,
     pub value: Box<Expression>,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct IfExpression {
     pub testing: Box<Expression>,
@@ -501,6 +887,7 @@ pub struct IfExpression {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct ForExpression {
     pub initializer: Box<LetExpression>,
@@ -508,12 +895,14 @@ pub struct ForExpression {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct NextExpression {
     pub value: Box<Expression>,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct EachExpression {
     pub variable: Box<Expression>,
@@ -522,6 +911,7 @@ pub struct EachExpression {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct BranchExpression {
     pub where_exp: Option<Box<Expression>>,
@@ -530,6 +920,7 @@ pub struct BranchExpression {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct BranchCase {
     pub where_exp: Option<Box<Expression>>,
@@ -538,6 +929,7 @@ pub struct BranchCase {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct MatchExpression {
     pub object: Box<Expression>,
@@ -547,6 +939,7 @@ pub struct MatchExpression {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct MatchCase {
     pub variable: Option<String>,                // 变量（可选）
@@ -557,6 +950,7 @@ pub struct MatchCase {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum PatternExpression {
     Primary(Expression),    // 普通模式表达式
@@ -567,6 +961,7 @@ pub enum PatternExpression {
 }
 
 // 函数的签名
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Sign {
     pub parameters: Vec<SignParameter>,
@@ -576,12 +971,14 @@ pub struct Sign {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum GenericName {
     Plain(String),
     Nested(Vec<GenericName>),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct SignParameter {
     pub data_type: DataType,
@@ -590,12 +987,14 @@ pub struct SignParameter {
 }
 
 // 函数数据类型的补充说明从属表达式
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum WhichEntry {
     Type(WhichEntryType),
     Limit(WhichEntryLimit),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct WhichEntryType {
     pub name: String,
@@ -603,6 +1002,7 @@ pub struct WhichEntryType {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct WhichEntryLimit {
     pub name: String,
@@ -610,21 +1010,73 @@ pub struct WhichEntryLimit {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct BinaryExpression {
-    pub operator: Token,
+    pub operator: BinaryOperator,
+    pub left: Box<Expression>,
+    pub right: Box<Expression>,
+    pub range: Range,
+}
+
+// 二元运算符
+//
+// 注：
+// 命名操作符（e.g. `:bitOr:`）需要携带名称，无法作为 Copy 类型的枚举成员，
+// 所以命名操作符的二元运算使用 NamedOperatorExpression 表示。
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Pipe,               // |
+    LogicOr,            // ||
+    LogicAnd,           // &&
+    Equal,              // ==
+    NotEqual,           // !=
+    GreaterThan,        // >
+    GreaterThanOrEqual, // >=
+    LessThan,           // <
+    LessThanOrEqual,    // <=
+    Concat,             // ++
+    Plus,               // +
+    Minus,              // -
+    Asterisk,           // *
+    Slash,              // /
+    OptionalOr,         // ??
+    OptionalAnd,        // ->
+    Combine,            // &
+}
+
+// 命名操作符的二元运算
+//
+// e.g.
+// left :bitOr: right
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedOperatorExpression {
+    pub name: String,
     pub left: Box<Expression>,
     pub right: Box<Expression>,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct UnaryExpression {
-    pub operator: Token,
+    pub operator: UnaryOperator,
     pub operand: Box<Expression>,
     pub range: Range,
 }
 
+// 一元运算符
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Cast,   // object^
+    Minus,  // -object
+    Unwrap, // object?
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct FunctionCallExpression {
     pub callee: Box<Expression>,
@@ -636,6 +1088,7 @@ pub struct FunctionCallExpression {
 //
 // e.g.
 // some_func(value1, value2, name1=name_value1, name2=name_value2)
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Argument {
     pub name: Option<String>,
@@ -643,12 +1096,14 @@ pub struct Argument {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum MemberExpression {
     Property(MemberProperty),
     Index(MemberIndex),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct MemberProperty {
     pub object: Box<Expression>,
@@ -656,6 +1111,7 @@ pub struct MemberProperty {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct MemberIndex {
     pub object: Box<Expression>,
@@ -663,6 +1119,7 @@ pub struct MemberIndex {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct SliceExpression {
     pub object: Box<Expression>,
@@ -671,6 +1128,7 @@ pub struct SliceExpression {
 }
 
 // 使用花括号方式的结构体实例化表达式
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct ConstructorExpression {
     pub object: Identifier,
@@ -678,6 +1136,19 @@ pub struct ConstructorExpression {
     pub range: Range,
 }
 
+// 使用括号方式的（元组型）结构体实例化表达式
+//
+// 语法跟函数调用的一样，由语法分析器产生 `FunctionCallExpression`，
+// 名称解析之后才转换为本节点（见 `resolve::rewrite_tuple_constructors`）
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TupleConstructorExpression {
+    pub object: Identifier,
+    pub value: Tuple,
+    pub range: Range,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct AnonymousFunction {
     pub parameters: Vec<AnonymousParameter>,
@@ -687,6 +1158,7 @@ pub struct AnonymousFunction {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct AnonymousParameter {
     pub data_type: Option<DataType>, // 匿名函数的数据类型允许省略
@@ -699,6 +1171,7 @@ pub struct AnonymousParameter {
 // 注意
 // - `let 表达式` 的左值是一个表达式，而不是 identifier
 // - 函数参数列表里的参数（形参，parameter）也不是 identifier
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Identifier {
     pub dirs: Vec<String>,
@@ -707,12 +1180,14 @@ pub struct Identifier {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct PrefixIdentifier {
     pub identifier: Identifier,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Ellipsis {
     pub name: Option<String>,
@@ -724,6 +1199,7 @@ pub struct Ellipsis {
 // e.g.
 // `0..10`
 // `0..=9`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Interval {
     pub is_inclusive: bool, // false == `..`（不包括 `to`）， true == `..=` （包括 `to`）
@@ -732,24 +1208,28 @@ pub struct Interval {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct List {
     pub elements: Vec<Expression>,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Tuple {
     pub elements: Vec<Expression>,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Map {
     pub elements: Vec<MapEntry>,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct MapEntry {
     // 映射表项目的键
@@ -774,7 +1254,7 @@ impl Display for BlockExpression {
             segments.push("do ".to_string());
         }
         segments.push("{".to_string());
-        if self.body.len() > 0 {
+        if !self.body.is_empty() {
             segments.push("\n".to_string());
             segments.push(format_expressions_with_new_line(&self.body));
             segments.push("\n".to_string());
@@ -791,7 +1271,7 @@ impl Display for JoinExpression {
         segments.push("join ".to_string());
 
         segments.push("{".to_string());
-        if self.body.len() > 0 {
+        if !self.body.is_empty() {
             segments.push("\n".to_string());
             segments.push(format_expressions_with_new_line(&self.body));
             segments.push("\n".to_string());
@@ -804,6 +1284,10 @@ impl Display for JoinExpression {
 
 impl Display for LetExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for attribute in &self.attributes {
+            writeln!(f, "{}", attribute)?;
+        }
+
         if let Some(d) = &self.data_type {
             write!(f, "let {} {} = {}", d, self.object, self.value)
         } else {
@@ -982,7 +1466,7 @@ impl Display for Sign {
 
         segments.push("sign".to_string());
 
-        if self.generics.len() > 0 {
+        if !self.generics.is_empty() {
             segments.push(format!("<{}>", format_generics(&self.generics)));
         }
 
@@ -992,7 +1476,7 @@ impl Display for Sign {
             segments.push(format!("type {}", dt));
         }
 
-        if self.whiches.len() > 0 {
+        if !self.whiches.is_empty() {
             segments.push(format!(
                 "which {{\n{}\n}}",
                 format_which_entries(&self.whiches)
@@ -1055,21 +1539,49 @@ impl Display for BinaryExpression {
     }
 }
 
+impl Display for BinaryOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            BinaryOperator::Pipe => "|",
+            BinaryOperator::LogicOr => "||",
+            BinaryOperator::LogicAnd => "&&",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::GreaterThan => ">",
+            BinaryOperator::GreaterThanOrEqual => ">=",
+            BinaryOperator::LessThan => "<",
+            BinaryOperator::LessThanOrEqual => "<=",
+            BinaryOperator::Concat => "++",
+            BinaryOperator::Plus => "+",
+            BinaryOperator::Minus => "-",
+            BinaryOperator::Asterisk => "*",
+            BinaryOperator::Slash => "/",
+            BinaryOperator::OptionalOr => "??",
+            BinaryOperator::OptionalAnd => "->",
+            BinaryOperator::Combine => "&",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl Display for NamedOperatorExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({} :{}: {})", self.left, self.name, self.right)
+    }
+}
+
 impl Display for UnaryExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.operator {
-            Token::Cast => {
+            UnaryOperator::Cast => {
                 write!(f, "{}^", self.operand)
             }
-            Token::Minus => {
+            UnaryOperator::Minus => {
                 write!(f, "-{}", self.operand)
             }
-            Token::Unwrap => {
+            UnaryOperator::Unwrap => {
                 write!(f, "{}?", self.operand)
             }
-            _ => {
-                panic!("invalid unary operator")
-            }
         }
     }
 }
@@ -1118,6 +1630,12 @@ impl Display for ConstructorExpression {
     }
 }
 
+impl Display for TupleConstructorExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.object, self.value)
+    }
+}
+
 impl Display for AnonymousFunction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut segments = Vec::<String>::new();
@@ -1132,7 +1650,7 @@ impl Display for AnonymousFunction {
             segments.push(format!("type {}", dt));
         }
 
-        if self.whiches.len() > 0 {
+        if !self.whiches.is_empty() {
             segments.push(format!(
                 "which {{\n{}\n}}",
                 format_which_entries(&self.whiches)
@@ -1144,7 +1662,7 @@ impl Display for AnonymousFunction {
         // }
 
         match self.body.as_ref() {
-            Expression::BlockExpression(e) if e.is_explicit == false => {
+            Expression::BlockExpression(e) if !e.is_explicit => {
                 segments.push(format!("{}", e))
             }
             _ => {
@@ -1163,7 +1681,7 @@ impl Display for Identifier {
         let mut segments = Vec::<String>::new();
 
         // 命名空间路径
-        if self.dirs.len() > 0 {
+        if !self.dirs.is_empty() {
             segments.push(self.dirs.join("::"));
             segments.push("::".to_string());
         }
@@ -1172,7 +1690,7 @@ impl Display for Identifier {
         segments.push(self.name.clone());
 
         // 泛型
-        if self.generics.len() > 0 {
+        if !self.generics.is_empty() {
             segments.push(format!("<{}>", format_generics(&self.generics)));
         }
 
@@ -1208,7 +1726,7 @@ impl Display for Interval {
 
 impl Display for Tuple {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.elements.len() == 0 {
+        if self.elements.is_empty() {
             write!(f, "()")
         } else {
             let text = format_expressions_with_comma(&self.elements);
@@ -1219,7 +1737,7 @@ impl Display for Tuple {
 
 impl Display for List {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.elements.len() == 0 {
+        if self.elements.is_empty() {
             write!(f, "[]")
         } else {
             let text = format_expressions_with_comma(&self.elements);
@@ -1254,44 +1772,1385 @@ impl Display for MapEntry {
     }
 }
 
-impl Display for Expression {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Expression::BlockExpression(v) => write!(f, "{}", v),
-            Expression::JoinExpression(v) => write!(f, "{}", v),
-            Expression::LetExpression(v) => write!(f, "{}", v),
-            Expression::IfExpression(v) => write!(f, "{}", v),
-            Expression::ForExpression(v) => write!(f, "{}", v),
-            Expression::NextExpression(v) => write!(f, "{}", v),
-            Expression::EachExpression(v) => write!(f, "{}", v),
-            Expression::BranchExpression(v) => write!(f, "{}", v),
-            Expression::MatchExpression(v) => write!(f, "{}", v),
-            Expression::Sign(v) => write!(f, "{}", v),
-            Expression::BinaryExpression(v) => write!(f, "{}", v),
-            Expression::UnaryExpression(v) => write!(f, "{}", v),
-            Expression::FunctionCallExpression(v) => write!(f, "{}", v),
-            Expression::MemberExpression(v) => write!(f, "{}", v),
-            Expression::SliceExpression(v) => write!(f, "{}", v),
-            Expression::ConstructorExpression(v) => write!(f, "{}", v),
-            Expression::AnonymousFunction(v) => write!(f, "{}", v),
-            Expression::Identifier(v) => write!(f, "{}", v),
-            Expression::PrefixIdentifier(v) => write!(f, "{}", v),
-            Expression::Ellipsis(v) => write!(f, "{}", v),
-            Expression::Interval(v) => write!(f, "{}", v),
-            Expression::Tuple(v) => write!(f, "{}", v),
-            Expression::List(v) => write!(f, "{}", v),
-            Expression::Map(v) => write!(f, "{}", v),
-            Expression::Literal(v) => write!(f, "{}", v),
+impl UseStatement {
+    // 导入的所有名称，每一项为名称的完整路径以及导入之后使用的名称，
+    // 比如 `use foo::{bar, sub::one as two}` 导入
+    // `(["foo", "bar"], "bar")` 以及 `(["foo", "sub", "one"], "two")`
+    pub fn imports(&self) -> Vec<(Vec<String>, String)> {
+        let mut imports = Vec::<(Vec<String>, String)>::new();
+        collect_imports(&self.name_path, &mut vec![], &mut imports);
+        imports
+    }
+}
+
+fn collect_imports(
+    name_path: &NamePath,
+    parents: &mut Vec<String>,
+    imports: &mut Vec<(Vec<String>, String)>,
+) {
+    let depth = parents.len();
+
+    for item in &name_path.directories {
+        match item {
+            NamePathItem::Name(name) => parents.push(name.clone()),
+            NamePathItem::Children(name, children) => {
+                parents.push(name.clone());
+                for child in children {
+                    collect_imports(child, parents, imports);
+                }
+            }
         }
     }
+
+    if let Some(NamePathItem::Name(name)) = name_path.directories.last() {
+        let local_name = name_path.alias.clone().unwrap_or_else(|| name.clone());
+        imports.push((parents.clone(), local_name));
+    }
+
+    parents.truncate(depth);
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum Literal {
-    Integer(Integer),
-    Float(Float),
-    Complex(Complex),
-    Bit(Bit),
+impl Statement {
+    // 语句直接包含的表达式（不包括表达式的子表达式）
+    pub fn expressions(&self) -> Vec<&Expression> {
+        match self {
+            Statement::FunctionDeclaration(v) => function_declaration_expressions(v),
+            Statement::PatternFunctionDeclaration(v) => {
+                let mut expressions = Vec::<&Expression>::new();
+                for p in &v.parameters {
+                    if let Some(pattern) = &p.pattern {
+                        expressions.extend(pattern.expression());
+                    }
+                    expressions.extend(p.where_exp.as_deref());
+                    expressions.extend(p.only.as_deref());
+                }
+                expressions.extend(v.only.as_ref());
+                expressions.push(&v.body);
+                expressions
+            }
+            Statement::ConstDeclaration(v) => vec![&v.value],
+            Statement::TraitDeclaration(v) => v
+                .function_items
+                .iter()
+                .flat_map(|item| match item {
+                    TraitFunctionItem::Function(f) => function_declaration_expressions(f),
+                    TraitFunctionItem::EmptyFunction(_) => vec![],
+                })
+                .collect(),
+            Statement::ImplStatement(v) => v
+                .function_items
+                .iter()
+                .flat_map(function_declaration_expressions)
+                .collect(),
+            Statement::Expression(v) => vec![v],
+            Statement::EmptyFunctionDeclaration(_)
+            | Statement::NamespaceStatement(_)
+            | Statement::UseStatement(_)
+            | Statement::MemberStructDeclaration(_)
+            | Statement::TupleStructDeclaration(_)
+            | Statement::EmptyStructDeclaration(_)
+            | Statement::UnionDeclaration(_)
+            | Statement::AliasStatement(_)
+            | Statement::Error(_) => vec![],
+        }
+    }
+
+    // 语句直接包含的表达式的可变引用
+    pub fn expressions_mut(&mut self) -> Vec<&mut Expression> {
+        match self {
+            Statement::FunctionDeclaration(v) => function_declaration_expressions_mut(v),
+            Statement::PatternFunctionDeclaration(v) => {
+                let mut expressions = Vec::<&mut Expression>::new();
+                for p in &mut v.parameters {
+                    if let Some(pattern) = &mut p.pattern {
+                        expressions.extend(pattern.expression_mut());
+                    }
+                    expressions.extend(p.where_exp.as_deref_mut());
+                    expressions.extend(p.only.as_deref_mut());
+                }
+                expressions.extend(v.only.as_mut());
+                expressions.push(&mut v.body);
+                expressions
+            }
+            Statement::ConstDeclaration(v) => vec![&mut v.value],
+            Statement::TraitDeclaration(v) => v
+                .function_items
+                .iter_mut()
+                .flat_map(|item| match item {
+                    TraitFunctionItem::Function(f) => function_declaration_expressions_mut(f),
+                    TraitFunctionItem::EmptyFunction(_) => vec![],
+                })
+                .collect(),
+            Statement::ImplStatement(v) => v
+                .function_items
+                .iter_mut()
+                .flat_map(function_declaration_expressions_mut)
+                .collect(),
+            Statement::Expression(v) => vec![v],
+            Statement::EmptyFunctionDeclaration(_)
+            | Statement::NamespaceStatement(_)
+            | Statement::UseStatement(_)
+            | Statement::MemberStructDeclaration(_)
+            | Statement::TupleStructDeclaration(_)
+            | Statement::EmptyStructDeclaration(_)
+            | Statement::UnionDeclaration(_)
+            | Statement::AliasStatement(_)
+            | Statement::Error(_) => vec![],
+        }
+    }
+}
+
+impl Expression {
+    // 表达式在源文件中的范围
+    pub fn range(&self) -> &Range {
+        match self {
+            Expression::BlockExpression(v) => &v.range,
+            Expression::JoinExpression(v) => &v.range,
+            Expression::LetExpression(v) => &v.range,
+            Expression::IfExpression(v) => &v.range,
+            Expression::ForExpression(v) => &v.range,
+            Expression::NextExpression(v) => &v.range,
+            Expression::EachExpression(v) => &v.range,
+            Expression::BranchExpression(v) => &v.range,
+            Expression::MatchExpression(v) => &v.range,
+            Expression::Sign(v) => &v.range,
+            Expression::BinaryExpression(v) => &v.range,
+            Expression::NamedOperatorExpression(v) => &v.range,
+            Expression::UnaryExpression(v) => &v.range,
+            Expression::FunctionCallExpression(v) => &v.range,
+            Expression::MemberExpression(MemberExpression::Property(v)) => &v.range,
+            Expression::MemberExpression(MemberExpression::Index(v)) => &v.range,
+            Expression::SliceExpression(v) => &v.range,
+            Expression::ConstructorExpression(v) => &v.range,
+            Expression::TupleConstructorExpression(v) => &v.range,
+            Expression::AnonymousFunction(v) => &v.range,
+            Expression::Identifier(v) => &v.range,
+            Expression::PrefixIdentifier(v) => &v.range,
+            Expression::Ellipsis(v) => &v.range,
+            Expression::Interval(v) => &v.range,
+            Expression::Tuple(v) => &v.range,
+            Expression::List(v) => &v.range,
+            Expression::Map(v) => &v.range,
+            Expression::Literal(v) => v.range(),
+        }
+    }
+
+    // 表达式的直接子表达式，按照在源代码里出现的顺序排列
+    //
+    // 注：数据类型（DataType）以及函数签名里的内容不属于子表达式。
+    pub fn children(&self) -> Vec<&Expression> {
+        let mut children = Vec::<&Expression>::new();
+
+        match self {
+            Expression::BlockExpression(v) => children.extend(&v.body),
+            Expression::JoinExpression(v) => children.extend(&v.body),
+            Expression::LetExpression(v) => {
+                children.push(&v.object);
+                children.push(&v.value);
+            }
+            Expression::IfExpression(v) => {
+                children.push(&v.testing);
+                children.extend(v.where_exp.as_deref());
+                children.push(&v.consequent);
+                children.extend(v.alternate.as_deref());
+            }
+            Expression::ForExpression(v) => {
+                children.push(&v.initializer.object);
+                children.push(&v.initializer.value);
+                children.push(&v.body);
+            }
+            Expression::NextExpression(v) => children.push(&v.value),
+            Expression::EachExpression(v) => {
+                children.push(&v.variable);
+                children.push(&v.object);
+                children.push(&v.body);
+            }
+            Expression::BranchExpression(v) => {
+                children.extend(v.where_exp.as_deref());
+                for c in &v.cases {
+                    children.extend(c.where_exp.as_deref());
+                    children.push(&c.testing);
+                    children.push(&c.consequent);
+                }
+                children.extend(v.default_exp.as_deref());
+            }
+            Expression::MatchExpression(v) => {
+                children.push(&v.object);
+                children.extend(v.where_exp.as_deref());
+                for c in &v.cases {
+                    if let Some(pattern) = &c.pattern {
+                        children.extend(pattern.expression());
+                    }
+                    children.extend(c.only.as_deref());
+                    children.extend(c.where_exp.as_deref());
+                    children.push(&c.consequent);
+                }
+                children.extend(v.default_exp.as_deref());
+            }
+            Expression::BinaryExpression(v) => {
+                children.push(&v.left);
+                children.push(&v.right);
+            }
+            Expression::NamedOperatorExpression(v) => {
+                children.push(&v.left);
+                children.push(&v.right);
+            }
+            Expression::UnaryExpression(v) => children.push(&v.operand),
+            Expression::FunctionCallExpression(v) => {
+                children.push(&v.callee);
+                children.extend(v.arguments.iter().map(|a| a.value.as_ref()));
+            }
+            Expression::MemberExpression(MemberExpression::Property(v)) => {
+                children.push(&v.object);
+                children.push(&v.property);
+            }
+            Expression::MemberExpression(MemberExpression::Index(v)) => {
+                children.push(&v.object);
+                children.push(&v.index);
+            }
+            Expression::SliceExpression(v) => {
+                children.push(&v.object);
+                children.push(&v.interval.from);
+                children.extend(v.interval.to.as_deref());
+            }
+            Expression::ConstructorExpression(v) => {
+                children.extend(map_entry_expressions(&v.value.elements))
+            }
+            Expression::TupleConstructorExpression(v) => children.extend(&v.value.elements),
+            Expression::AnonymousFunction(v) => children.push(&v.body),
+            Expression::Interval(v) => {
+                children.push(&v.from);
+                children.extend(v.to.as_deref());
+            }
+            Expression::Tuple(v) => children.extend(&v.elements),
+            Expression::List(v) => children.extend(&v.elements),
+            Expression::Map(v) => children.extend(map_entry_expressions(&v.elements)),
+            Expression::Sign(_)
+            | Expression::Identifier(_)
+            | Expression::PrefixIdentifier(_)
+            | Expression::Ellipsis(_)
+            | Expression::Literal(_) => {}
+        }
+
+        children
+    }
+
+    // 表达式的直接子表达式的可变引用
+    pub fn children_mut(&mut self) -> Vec<&mut Expression> {
+        let mut children = Vec::<&mut Expression>::new();
+
+        match self {
+            Expression::BlockExpression(v) => children.extend(&mut v.body),
+            Expression::JoinExpression(v) => children.extend(&mut v.body),
+            Expression::LetExpression(v) => {
+                children.push(&mut v.object);
+                children.push(&mut v.value);
+            }
+            Expression::IfExpression(v) => {
+                children.push(&mut v.testing);
+                children.extend(v.where_exp.as_deref_mut());
+                children.push(&mut v.consequent);
+                children.extend(v.alternate.as_deref_mut());
+            }
+            Expression::ForExpression(v) => {
+                children.push(&mut v.initializer.object);
+                children.push(&mut v.initializer.value);
+                children.push(&mut v.body);
+            }
+            Expression::NextExpression(v) => children.push(&mut v.value),
+            Expression::EachExpression(v) => {
+                children.push(&mut v.variable);
+                children.push(&mut v.object);
+                children.push(&mut v.body);
+            }
+            Expression::BranchExpression(v) => {
+                children.extend(v.where_exp.as_deref_mut());
+                for c in &mut v.cases {
+                    children.extend(c.where_exp.as_deref_mut());
+                    children.push(&mut c.testing);
+                    children.push(&mut c.consequent);
+                }
+                children.extend(v.default_exp.as_deref_mut());
+            }
+            Expression::MatchExpression(v) => {
+                children.push(&mut v.object);
+                children.extend(v.where_exp.as_deref_mut());
+                for c in &mut v.cases {
+                    if let Some(pattern) = &mut c.pattern {
+                        children.extend(pattern.expression_mut());
+                    }
+                    children.extend(c.only.as_deref_mut());
+                    children.extend(c.where_exp.as_deref_mut());
+                    children.push(&mut c.consequent);
+                }
+                children.extend(v.default_exp.as_deref_mut());
+            }
+            Expression::BinaryExpression(v) => {
+                children.push(&mut v.left);
+                children.push(&mut v.right);
+            }
+            Expression::NamedOperatorExpression(v) => {
+                children.push(&mut v.left);
+                children.push(&mut v.right);
+            }
+            Expression::UnaryExpression(v) => children.push(&mut v.operand),
+            Expression::FunctionCallExpression(v) => {
+                children.push(&mut v.callee);
+                children.extend(v.arguments.iter_mut().map(|a| a.value.as_mut()));
+            }
+            Expression::MemberExpression(MemberExpression::Property(v)) => {
+                children.push(&mut v.object);
+                children.push(&mut v.property);
+            }
+            Expression::MemberExpression(MemberExpression::Index(v)) => {
+                children.push(&mut v.object);
+                children.push(&mut v.index);
+            }
+            Expression::SliceExpression(v) => {
+                children.push(&mut v.object);
+                children.push(&mut v.interval.from);
+                children.extend(v.interval.to.as_deref_mut());
+            }
+            Expression::ConstructorExpression(v) => {
+                children.extend(map_entry_expressions_mut(&mut v.value.elements))
+            }
+            Expression::TupleConstructorExpression(v) => children.extend(&mut v.value.elements),
+            Expression::AnonymousFunction(v) => children.push(&mut v.body),
+            Expression::Interval(v) => {
+                children.push(&mut v.from);
+                children.extend(v.to.as_deref_mut());
+            }
+            Expression::Tuple(v) => children.extend(&mut v.elements),
+            Expression::List(v) => children.extend(&mut v.elements),
+            Expression::Map(v) => children.extend(map_entry_expressions_mut(&mut v.elements)),
+            Expression::Sign(_)
+            | Expression::Identifier(_)
+            | Expression::PrefixIdentifier(_)
+            | Expression::Ellipsis(_)
+            | Expression::Literal(_) => {}
+        }
+
+        children
+    }
+}
+
+impl PatternExpression {
+    // 模式表达式里的表达式
+    pub fn expression(&self) -> Option<&Expression> {
+        match self {
+            PatternExpression::Primary(e) | PatternExpression::In(e) => Some(e),
+            PatternExpression::Into(_, _)
+            | PatternExpression::Regular(_, _)
+            | PatternExpression::Template(_) => None,
+        }
+    }
+
+    pub fn expression_mut(&mut self) -> Option<&mut Expression> {
+        match self {
+            PatternExpression::Primary(e) | PatternExpression::In(e) => Some(e),
+            PatternExpression::Into(_, _)
+            | PatternExpression::Regular(_, _)
+            | PatternExpression::Template(_) => None,
+        }
+    }
+}
+
+fn function_declaration_expressions(function: &FunctionDeclaration) -> Vec<&Expression> {
+    let mut expressions: Vec<&Expression> =
+        function.parameters.iter().filter_map(|p| p.value.as_ref()).collect();
+    expressions.push(&function.body);
+    expressions
+}
+
+fn map_entry_expressions(entries: &[MapEntry]) -> Vec<&Expression> {
+    entries
+        .iter()
+        .flat_map(|entry| std::iter::once(entry.key.as_ref()).chain(entry.value.as_deref()))
+        .collect()
+}
+
+fn function_declaration_expressions_mut(
+    function: &mut FunctionDeclaration,
+) -> Vec<&mut Expression> {
+    let mut expressions: Vec<&mut Expression> = function
+        .parameters
+        .iter_mut()
+        .filter_map(|p| p.value.as_mut())
+        .collect();
+    expressions.push(&mut function.body);
+    expressions
+}
+
+fn map_entry_expressions_mut(entries: &mut [MapEntry]) -> Vec<&mut Expression> {
+    entries
+        .iter_mut()
+        .flat_map(|entry| std::iter::once(entry.key.as_mut()).chain(entry.value.as_deref_mut()))
+        .collect()
+}
+
+// AST 的访问者
+//
+// `Visitor` 的每个方法对应一种节点，默认实现调用对应的 `walk_*` 函数，
+// 按照在源代码里出现的顺序访问节点的子节点（表达式的访问顺序跟 `Expression::children` 一致），
+// 所以实现访问者时只需要覆盖关心的节点，在覆盖的方法里调用 `walk_*` 函数即可继续访问子节点：
+//
+// ```ignore
+// struct CallCounter(usize);
+//
+// impl Visitor for CallCounter {
+//     fn visit_function_call_expression(&mut self, v: &FunctionCallExpression) {
+//         self.0 += 1;
+//         walk_function_call_expression(self, v);
+//     }
+// }
+// ```
+//
+// 注：
+// - 嵌在节点里的子结构，比如 `for` 表达式的初始化表达式（`LetExpression`）、
+//   结构体实例化表达式的值（`Map`），会调用对应的方法，但不经过 `visit_expression`；
+// - 模式表达式通过 `visit_pattern` 访问；
+// - 数据类型（DataType）以及函数签名里的内容不会被访问。
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program)
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement)
+    }
+
+    fn visit_function_declaration(&mut self, v: &FunctionDeclaration) {
+        walk_function_declaration(self, v)
+    }
+
+    fn visit_empty_function_declaration(&mut self, _v: &EmptyFunctionDeclaration) {}
+
+    fn visit_pattern_function_declaration(&mut self, v: &PatternFunctionDeclaration) {
+        walk_pattern_function_declaration(self, v)
+    }
+
+    fn visit_namespace_statement(&mut self, _v: &NamespaceStatement) {}
+
+    fn visit_use_statement(&mut self, _v: &UseStatement) {}
+
+    fn visit_const_declaration(&mut self, v: &ConstDeclaration) {
+        walk_const_declaration(self, v)
+    }
+
+    fn visit_member_struct_declaration(&mut self, _v: &MemberStructDeclaration) {}
+
+    fn visit_tuple_struct_declaration(&mut self, _v: &TupleStructDeclaration) {}
+
+    fn visit_empty_struct_declaration(&mut self, _v: &EmptyStructDeclaration) {}
+
+    fn visit_union_declaration(&mut self, _v: &UnionDeclaration) {}
+
+    fn visit_trait_declaration(&mut self, v: &TraitDeclaration) {
+        walk_trait_declaration(self, v)
+    }
+
+    fn visit_impl_statement(&mut self, v: &ImplStatement) {
+        walk_impl_statement(self, v)
+    }
+
+    fn visit_alias_statement(&mut self, _v: &AliasStatement) {}
+
+    fn visit_error_statement(&mut self, _v: &ErrorStatement) {}
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression)
+    }
+
+    fn visit_block_expression(&mut self, v: &BlockExpression) {
+        walk_block_expression(self, v)
+    }
+
+    fn visit_join_expression(&mut self, v: &JoinExpression) {
+        walk_join_expression(self, v)
+    }
+
+    fn visit_let_expression(&mut self, v: &LetExpression) {
+        walk_let_expression(self, v)
+    }
+
+    fn visit_if_expression(&mut self, v: &IfExpression) {
+        walk_if_expression(self, v)
+    }
+
+    fn visit_for_expression(&mut self, v: &ForExpression) {
+        walk_for_expression(self, v)
+    }
+
+    fn visit_next_expression(&mut self, v: &NextExpression) {
+        walk_next_expression(self, v)
+    }
+
+    fn visit_each_expression(&mut self, v: &EachExpression) {
+        walk_each_expression(self, v)
+    }
+
+    fn visit_branch_expression(&mut self, v: &BranchExpression) {
+        walk_branch_expression(self, v)
+    }
+
+    fn visit_match_expression(&mut self, v: &MatchExpression) {
+        walk_match_expression(self, v)
+    }
+
+    fn visit_sign(&mut self, _v: &Sign) {}
+
+    fn visit_binary_expression(&mut self, v: &BinaryExpression) {
+        walk_binary_expression(self, v)
+    }
+
+    fn visit_named_operator_expression(&mut self, v: &NamedOperatorExpression) {
+        walk_named_operator_expression(self, v)
+    }
+
+    fn visit_unary_expression(&mut self, v: &UnaryExpression) {
+        walk_unary_expression(self, v)
+    }
+
+    fn visit_function_call_expression(&mut self, v: &FunctionCallExpression) {
+        walk_function_call_expression(self, v)
+    }
+
+    fn visit_member_expression(&mut self, v: &MemberExpression) {
+        walk_member_expression(self, v)
+    }
+
+    fn visit_slice_expression(&mut self, v: &SliceExpression) {
+        walk_slice_expression(self, v)
+    }
+
+    fn visit_constructor_expression(&mut self, v: &ConstructorExpression) {
+        walk_constructor_expression(self, v)
+    }
+
+    fn visit_tuple_constructor_expression(&mut self, v: &TupleConstructorExpression) {
+        walk_tuple_constructor_expression(self, v)
+    }
+
+    fn visit_anonymous_function(&mut self, v: &AnonymousFunction) {
+        walk_anonymous_function(self, v)
+    }
+
+    fn visit_identifier(&mut self, _v: &Identifier) {}
+
+    fn visit_prefix_identifier(&mut self, _v: &PrefixIdentifier) {}
+
+    fn visit_ellipsis(&mut self, _v: &Ellipsis) {}
+
+    fn visit_interval(&mut self, v: &Interval) {
+        walk_interval(self, v)
+    }
+
+    fn visit_tuple(&mut self, v: &Tuple) {
+        walk_tuple(self, v)
+    }
+
+    fn visit_list(&mut self, v: &List) {
+        walk_list(self, v)
+    }
+
+    fn visit_map(&mut self, v: &Map) {
+        walk_map(self, v)
+    }
+
+    fn visit_literal(&mut self, _v: &Literal) {}
+
+    fn visit_pattern(&mut self, pattern: &PatternExpression) {
+        walk_pattern(self, pattern)
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for statement in &program.body {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::FunctionDeclaration(v) => visitor.visit_function_declaration(v),
+        Statement::EmptyFunctionDeclaration(v) => visitor.visit_empty_function_declaration(v),
+        Statement::PatternFunctionDeclaration(v) => visitor.visit_pattern_function_declaration(v),
+        Statement::NamespaceStatement(v) => visitor.visit_namespace_statement(v),
+        Statement::UseStatement(v) => visitor.visit_use_statement(v),
+        Statement::ConstDeclaration(v) => visitor.visit_const_declaration(v),
+        Statement::MemberStructDeclaration(v) => visitor.visit_member_struct_declaration(v),
+        Statement::TupleStructDeclaration(v) => visitor.visit_tuple_struct_declaration(v),
+        Statement::EmptyStructDeclaration(v) => visitor.visit_empty_struct_declaration(v),
+        Statement::UnionDeclaration(v) => visitor.visit_union_declaration(v),
+        Statement::TraitDeclaration(v) => visitor.visit_trait_declaration(v),
+        Statement::ImplStatement(v) => visitor.visit_impl_statement(v),
+        Statement::AliasStatement(v) => visitor.visit_alias_statement(v),
+        Statement::Expression(v) => visitor.visit_expression(v),
+        Statement::Error(v) => visitor.visit_error_statement(v),
+    }
+}
+
+pub fn walk_function_declaration<V: Visitor + ?Sized>(visitor: &mut V, v: &FunctionDeclaration) {
+    for p in &v.parameters {
+        if let Some(value) = &p.value {
+            visitor.visit_expression(value);
+        }
+    }
+    visitor.visit_expression(&v.body);
+}
+
+pub fn walk_pattern_function_declaration<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    v: &PatternFunctionDeclaration,
+) {
+    for p in &v.parameters {
+        if let Some(pattern) = &p.pattern {
+            visitor.visit_pattern(pattern);
+        }
+        if let Some(e) = &p.where_exp {
+            visitor.visit_expression(e);
+        }
+        if let Some(e) = &p.only {
+            visitor.visit_expression(e);
+        }
+    }
+    if let Some(e) = &v.only {
+        visitor.visit_expression(e);
+    }
+    visitor.visit_expression(&v.body);
+}
+
+pub fn walk_const_declaration<V: Visitor + ?Sized>(visitor: &mut V, v: &ConstDeclaration) {
+    visitor.visit_expression(&v.value);
+}
+
+pub fn walk_trait_declaration<V: Visitor + ?Sized>(visitor: &mut V, v: &TraitDeclaration) {
+    for item in &v.function_items {
+        match item {
+            TraitFunctionItem::Function(f) => visitor.visit_function_declaration(f),
+            TraitFunctionItem::EmptyFunction(f) => visitor.visit_empty_function_declaration(f),
+        }
+    }
+}
+
+pub fn walk_impl_statement<V: Visitor + ?Sized>(visitor: &mut V, v: &ImplStatement) {
+    for f in &v.function_items {
+        visitor.visit_function_declaration(f);
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::BlockExpression(v) => visitor.visit_block_expression(v),
+        Expression::JoinExpression(v) => visitor.visit_join_expression(v),
+        Expression::LetExpression(v) => visitor.visit_let_expression(v),
+        Expression::IfExpression(v) => visitor.visit_if_expression(v),
+        Expression::ForExpression(v) => visitor.visit_for_expression(v),
+        Expression::NextExpression(v) => visitor.visit_next_expression(v),
+        Expression::EachExpression(v) => visitor.visit_each_expression(v),
+        Expression::BranchExpression(v) => visitor.visit_branch_expression(v),
+        Expression::MatchExpression(v) => visitor.visit_match_expression(v),
+        Expression::Sign(v) => visitor.visit_sign(v),
+        Expression::BinaryExpression(v) => visitor.visit_binary_expression(v),
+        Expression::NamedOperatorExpression(v) => visitor.visit_named_operator_expression(v),
+        Expression::UnaryExpression(v) => visitor.visit_unary_expression(v),
+        Expression::FunctionCallExpression(v) => visitor.visit_function_call_expression(v),
+        Expression::MemberExpression(v) => visitor.visit_member_expression(v),
+        Expression::SliceExpression(v) => visitor.visit_slice_expression(v),
+        Expression::ConstructorExpression(v) => visitor.visit_constructor_expression(v),
+        Expression::TupleConstructorExpression(v) => visitor.visit_tuple_constructor_expression(v),
+        Expression::AnonymousFunction(v) => visitor.visit_anonymous_function(v),
+        Expression::Identifier(v) => visitor.visit_identifier(v),
+        Expression::PrefixIdentifier(v) => visitor.visit_prefix_identifier(v),
+        Expression::Ellipsis(v) => visitor.visit_ellipsis(v),
+        Expression::Interval(v) => visitor.visit_interval(v),
+        Expression::Tuple(v) => visitor.visit_tuple(v),
+        Expression::List(v) => visitor.visit_list(v),
+        Expression::Map(v) => visitor.visit_map(v),
+        Expression::Literal(v) => visitor.visit_literal(v),
+    }
+}
+
+pub fn walk_block_expression<V: Visitor + ?Sized>(visitor: &mut V, v: &BlockExpression) {
+    for e in &v.body {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_join_expression<V: Visitor + ?Sized>(visitor: &mut V, v: &JoinExpression) {
+    for e in &v.body {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_let_expression<V: Visitor + ?Sized>(visitor: &mut V, v: &LetExpression) {
+    visitor.visit_expression(&v.object);
+    visitor.visit_expression(&v.value);
+}
+
+pub fn walk_if_expression<V: Visitor + ?Sized>(visitor: &mut V, v: &IfExpression) {
+    visitor.visit_expression(&v.testing);
+    if let Some(e) = &v.where_exp {
+        visitor.visit_expression(e);
+    }
+    visitor.visit_expression(&v.consequent);
+    if let Some(e) = &v.alternate {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_for_expression<V: Visitor + ?Sized>(visitor: &mut V, v: &ForExpression) {
+    visitor.visit_let_expression(&v.initializer);
+    visitor.visit_expression(&v.body);
+}
+
+pub fn walk_next_expression<V: Visitor + ?Sized>(visitor: &mut V, v: &NextExpression) {
+    visitor.visit_expression(&v.value);
+}
+
+pub fn walk_each_expression<V: Visitor + ?Sized>(visitor: &mut V, v: &EachExpression) {
+    visitor.visit_expression(&v.variable);
+    visitor.visit_expression(&v.object);
+    visitor.visit_expression(&v.body);
+}
+
+pub fn walk_branch_expression<V: Visitor + ?Sized>(visitor: &mut V, v: &BranchExpression) {
+    if let Some(e) = &v.where_exp {
+        visitor.visit_expression(e);
+    }
+    for c in &v.cases {
+        if let Some(e) = &c.where_exp {
+            visitor.visit_expression(e);
+        }
+        visitor.visit_expression(&c.testing);
+        visitor.visit_expression(&c.consequent);
+    }
+    if let Some(e) = &v.default_exp {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_match_expression<V: Visitor + ?Sized>(visitor: &mut V, v: &MatchExpression) {
+    visitor.visit_expression(&v.object);
+    if let Some(e) = &v.where_exp {
+        visitor.visit_expression(e);
+    }
+    for c in &v.cases {
+        if let Some(pattern) = &c.pattern {
+            visitor.visit_pattern(pattern);
+        }
+        if let Some(e) = &c.only {
+            visitor.visit_expression(e);
+        }
+        if let Some(e) = &c.where_exp {
+            visitor.visit_expression(e);
+        }
+        visitor.visit_expression(&c.consequent);
+    }
+    if let Some(e) = &v.default_exp {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_binary_expression<V: Visitor + ?Sized>(visitor: &mut V, v: &BinaryExpression) {
+    visitor.visit_expression(&v.left);
+    visitor.visit_expression(&v.right);
+}
+
+pub fn walk_named_operator_expression<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    v: &NamedOperatorExpression,
+) {
+    visitor.visit_expression(&v.left);
+    visitor.visit_expression(&v.right);
+}
+
+pub fn walk_unary_expression<V: Visitor + ?Sized>(visitor: &mut V, v: &UnaryExpression) {
+    visitor.visit_expression(&v.operand);
+}
+
+pub fn walk_function_call_expression<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    v: &FunctionCallExpression,
+) {
+    visitor.visit_expression(&v.callee);
+    for argument in &v.arguments {
+        visitor.visit_expression(&argument.value);
+    }
+}
+
+pub fn walk_member_expression<V: Visitor + ?Sized>(visitor: &mut V, v: &MemberExpression) {
+    match v {
+        MemberExpression::Property(p) => {
+            visitor.visit_expression(&p.object);
+            visitor.visit_expression(&p.property);
+        }
+        MemberExpression::Index(i) => {
+            visitor.visit_expression(&i.object);
+            visitor.visit_expression(&i.index);
+        }
+    }
+}
+
+pub fn walk_slice_expression<V: Visitor + ?Sized>(visitor: &mut V, v: &SliceExpression) {
+    visitor.visit_expression(&v.object);
+    visitor.visit_interval(&v.interval);
+}
+
+pub fn walk_constructor_expression<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    v: &ConstructorExpression,
+) {
+    visitor.visit_map(&v.value);
+}
+
+pub fn walk_tuple_constructor_expression<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    v: &TupleConstructorExpression,
+) {
+    visitor.visit_tuple(&v.value);
+}
+
+pub fn walk_anonymous_function<V: Visitor + ?Sized>(visitor: &mut V, v: &AnonymousFunction) {
+    visitor.visit_expression(&v.body);
+}
+
+pub fn walk_interval<V: Visitor + ?Sized>(visitor: &mut V, v: &Interval) {
+    visitor.visit_expression(&v.from);
+    if let Some(e) = &v.to {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_tuple<V: Visitor + ?Sized>(visitor: &mut V, v: &Tuple) {
+    for e in &v.elements {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_list<V: Visitor + ?Sized>(visitor: &mut V, v: &List) {
+    for e in &v.elements {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_map<V: Visitor + ?Sized>(visitor: &mut V, v: &Map) {
+    for entry in &v.elements {
+        visitor.visit_expression(&entry.key);
+        if let Some(e) = &entry.value {
+            visitor.visit_expression(e);
+        }
+    }
+}
+
+pub fn walk_pattern<V: Visitor + ?Sized>(visitor: &mut V, pattern: &PatternExpression) {
+    if let Some(e) = pattern.expression() {
+        visitor.visit_expression(e);
+    }
+}
+
+// 可以修改节点的访问者，用法跟 `Visitor` 相同，对应的函数为 `walk_*_mut`
+pub trait MutVisitor {
+    fn visit_program(&mut self, program: &mut Program) {
+        walk_program_mut(self, program)
+    }
+
+    fn visit_statement(&mut self, statement: &mut Statement) {
+        walk_statement_mut(self, statement)
+    }
+
+    fn visit_function_declaration(&mut self, v: &mut FunctionDeclaration) {
+        walk_function_declaration_mut(self, v)
+    }
+
+    fn visit_empty_function_declaration(&mut self, _v: &mut EmptyFunctionDeclaration) {}
+
+    fn visit_pattern_function_declaration(&mut self, v: &mut PatternFunctionDeclaration) {
+        walk_pattern_function_declaration_mut(self, v)
+    }
+
+    fn visit_namespace_statement(&mut self, _v: &mut NamespaceStatement) {}
+
+    fn visit_use_statement(&mut self, _v: &mut UseStatement) {}
+
+    fn visit_const_declaration(&mut self, v: &mut ConstDeclaration) {
+        walk_const_declaration_mut(self, v)
+    }
+
+    fn visit_member_struct_declaration(&mut self, _v: &mut MemberStructDeclaration) {}
+
+    fn visit_tuple_struct_declaration(&mut self, _v: &mut TupleStructDeclaration) {}
+
+    fn visit_empty_struct_declaration(&mut self, _v: &mut EmptyStructDeclaration) {}
+
+    fn visit_union_declaration(&mut self, _v: &mut UnionDeclaration) {}
+
+    fn visit_trait_declaration(&mut self, v: &mut TraitDeclaration) {
+        walk_trait_declaration_mut(self, v)
+    }
+
+    fn visit_impl_statement(&mut self, v: &mut ImplStatement) {
+        walk_impl_statement_mut(self, v)
+    }
+
+    fn visit_alias_statement(&mut self, _v: &mut AliasStatement) {}
+
+    fn visit_error_statement(&mut self, _v: &mut ErrorStatement) {}
+
+    fn visit_expression(&mut self, expression: &mut Expression) {
+        walk_expression_mut(self, expression)
+    }
+
+    fn visit_block_expression(&mut self, v: &mut BlockExpression) {
+        walk_block_expression_mut(self, v)
+    }
+
+    fn visit_join_expression(&mut self, v: &mut JoinExpression) {
+        walk_join_expression_mut(self, v)
+    }
+
+    fn visit_let_expression(&mut self, v: &mut LetExpression) {
+        walk_let_expression_mut(self, v)
+    }
+
+    fn visit_if_expression(&mut self, v: &mut IfExpression) {
+        walk_if_expression_mut(self, v)
+    }
+
+    fn visit_for_expression(&mut self, v: &mut ForExpression) {
+        walk_for_expression_mut(self, v)
+    }
+
+    fn visit_next_expression(&mut self, v: &mut NextExpression) {
+        walk_next_expression_mut(self, v)
+    }
+
+    fn visit_each_expression(&mut self, v: &mut EachExpression) {
+        walk_each_expression_mut(self, v)
+    }
+
+    fn visit_branch_expression(&mut self, v: &mut BranchExpression) {
+        walk_branch_expression_mut(self, v)
+    }
+
+    fn visit_match_expression(&mut self, v: &mut MatchExpression) {
+        walk_match_expression_mut(self, v)
+    }
+
+    fn visit_sign(&mut self, _v: &mut Sign) {}
+
+    fn visit_binary_expression(&mut self, v: &mut BinaryExpression) {
+        walk_binary_expression_mut(self, v)
+    }
+
+    fn visit_named_operator_expression(&mut self, v: &mut NamedOperatorExpression) {
+        walk_named_operator_expression_mut(self, v)
+    }
+
+    fn visit_unary_expression(&mut self, v: &mut UnaryExpression) {
+        walk_unary_expression_mut(self, v)
+    }
+
+    fn visit_function_call_expression(&mut self, v: &mut FunctionCallExpression) {
+        walk_function_call_expression_mut(self, v)
+    }
+
+    fn visit_member_expression(&mut self, v: &mut MemberExpression) {
+        walk_member_expression_mut(self, v)
+    }
+
+    fn visit_slice_expression(&mut self, v: &mut SliceExpression) {
+        walk_slice_expression_mut(self, v)
+    }
+
+    fn visit_constructor_expression(&mut self, v: &mut ConstructorExpression) {
+        walk_constructor_expression_mut(self, v)
+    }
+
+    fn visit_tuple_constructor_expression(&mut self, v: &mut TupleConstructorExpression) {
+        walk_tuple_constructor_expression_mut(self, v)
+    }
+
+    fn visit_anonymous_function(&mut self, v: &mut AnonymousFunction) {
+        walk_anonymous_function_mut(self, v)
+    }
+
+    fn visit_identifier(&mut self, _v: &mut Identifier) {}
+
+    fn visit_prefix_identifier(&mut self, _v: &mut PrefixIdentifier) {}
+
+    fn visit_ellipsis(&mut self, _v: &mut Ellipsis) {}
+
+    fn visit_interval(&mut self, v: &mut Interval) {
+        walk_interval_mut(self, v)
+    }
+
+    fn visit_tuple(&mut self, v: &mut Tuple) {
+        walk_tuple_mut(self, v)
+    }
+
+    fn visit_list(&mut self, v: &mut List) {
+        walk_list_mut(self, v)
+    }
+
+    fn visit_map(&mut self, v: &mut Map) {
+        walk_map_mut(self, v)
+    }
+
+    fn visit_literal(&mut self, _v: &mut Literal) {}
+
+    fn visit_pattern(&mut self, pattern: &mut PatternExpression) {
+        walk_pattern_mut(self, pattern)
+    }
+}
+
+pub fn walk_program_mut<V: MutVisitor + ?Sized>(visitor: &mut V, program: &mut Program) {
+    for statement in &mut program.body {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement_mut<V: MutVisitor + ?Sized>(visitor: &mut V, statement: &mut Statement) {
+    match statement {
+        Statement::FunctionDeclaration(v) => visitor.visit_function_declaration(v),
+        Statement::EmptyFunctionDeclaration(v) => visitor.visit_empty_function_declaration(v),
+        Statement::PatternFunctionDeclaration(v) => visitor.visit_pattern_function_declaration(v),
+        Statement::NamespaceStatement(v) => visitor.visit_namespace_statement(v),
+        Statement::UseStatement(v) => visitor.visit_use_statement(v),
+        Statement::ConstDeclaration(v) => visitor.visit_const_declaration(v),
+        Statement::MemberStructDeclaration(v) => visitor.visit_member_struct_declaration(v),
+        Statement::TupleStructDeclaration(v) => visitor.visit_tuple_struct_declaration(v),
+        Statement::EmptyStructDeclaration(v) => visitor.visit_empty_struct_declaration(v),
+        Statement::UnionDeclaration(v) => visitor.visit_union_declaration(v),
+        Statement::TraitDeclaration(v) => visitor.visit_trait_declaration(v),
+        Statement::ImplStatement(v) => visitor.visit_impl_statement(v),
+        Statement::AliasStatement(v) => visitor.visit_alias_statement(v),
+        Statement::Expression(v) => visitor.visit_expression(v),
+        Statement::Error(v) => visitor.visit_error_statement(v),
+    }
+}
+
+pub fn walk_function_declaration_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    v: &mut FunctionDeclaration,
+) {
+    for p in &mut v.parameters {
+        if let Some(value) = &mut p.value {
+            visitor.visit_expression(value);
+        }
+    }
+    visitor.visit_expression(&mut v.body);
+}
+
+pub fn walk_pattern_function_declaration_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    v: &mut PatternFunctionDeclaration,
+) {
+    for p in &mut v.parameters {
+        if let Some(pattern) = &mut p.pattern {
+            visitor.visit_pattern(pattern);
+        }
+        if let Some(e) = &mut p.where_exp {
+            visitor.visit_expression(e);
+        }
+        if let Some(e) = &mut p.only {
+            visitor.visit_expression(e);
+        }
+    }
+    if let Some(e) = &mut v.only {
+        visitor.visit_expression(e);
+    }
+    visitor.visit_expression(&mut v.body);
+}
+
+pub fn walk_const_declaration_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    v: &mut ConstDeclaration,
+) {
+    visitor.visit_expression(&mut v.value);
+}
+
+pub fn walk_trait_declaration_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    v: &mut TraitDeclaration,
+) {
+    for item in &mut v.function_items {
+        match item {
+            TraitFunctionItem::Function(f) => visitor.visit_function_declaration(f),
+            TraitFunctionItem::EmptyFunction(f) => visitor.visit_empty_function_declaration(f),
+        }
+    }
+}
+
+pub fn walk_impl_statement_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut ImplStatement) {
+    for f in &mut v.function_items {
+        visitor.visit_function_declaration(f);
+    }
+}
+
+pub fn walk_expression_mut<V: MutVisitor + ?Sized>(visitor: &mut V, expression: &mut Expression) {
+    match expression {
+        Expression::BlockExpression(v) => visitor.visit_block_expression(v),
+        Expression::JoinExpression(v) => visitor.visit_join_expression(v),
+        Expression::LetExpression(v) => visitor.visit_let_expression(v),
+        Expression::IfExpression(v) => visitor.visit_if_expression(v),
+        Expression::ForExpression(v) => visitor.visit_for_expression(v),
+        Expression::NextExpression(v) => visitor.visit_next_expression(v),
+        Expression::EachExpression(v) => visitor.visit_each_expression(v),
+        Expression::BranchExpression(v) => visitor.visit_branch_expression(v),
+        Expression::MatchExpression(v) => visitor.visit_match_expression(v),
+        Expression::Sign(v) => visitor.visit_sign(v),
+        Expression::BinaryExpression(v) => visitor.visit_binary_expression(v),
+        Expression::NamedOperatorExpression(v) => visitor.visit_named_operator_expression(v),
+        Expression::UnaryExpression(v) => visitor.visit_unary_expression(v),
+        Expression::FunctionCallExpression(v) => visitor.visit_function_call_expression(v),
+        Expression::MemberExpression(v) => visitor.visit_member_expression(v),
+        Expression::SliceExpression(v) => visitor.visit_slice_expression(v),
+        Expression::ConstructorExpression(v) => visitor.visit_constructor_expression(v),
+        Expression::TupleConstructorExpression(v) => visitor.visit_tuple_constructor_expression(v),
+        Expression::AnonymousFunction(v) => visitor.visit_anonymous_function(v),
+        Expression::Identifier(v) => visitor.visit_identifier(v),
+        Expression::PrefixIdentifier(v) => visitor.visit_prefix_identifier(v),
+        Expression::Ellipsis(v) => visitor.visit_ellipsis(v),
+        Expression::Interval(v) => visitor.visit_interval(v),
+        Expression::Tuple(v) => visitor.visit_tuple(v),
+        Expression::List(v) => visitor.visit_list(v),
+        Expression::Map(v) => visitor.visit_map(v),
+        Expression::Literal(v) => visitor.visit_literal(v),
+    }
+}
+
+pub fn walk_block_expression_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut BlockExpression) {
+    for e in &mut v.body {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_join_expression_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut JoinExpression) {
+    for e in &mut v.body {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_let_expression_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut LetExpression) {
+    visitor.visit_expression(&mut v.object);
+    visitor.visit_expression(&mut v.value);
+}
+
+pub fn walk_if_expression_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut IfExpression) {
+    visitor.visit_expression(&mut v.testing);
+    if let Some(e) = &mut v.where_exp {
+        visitor.visit_expression(e);
+    }
+    visitor.visit_expression(&mut v.consequent);
+    if let Some(e) = &mut v.alternate {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_for_expression_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut ForExpression) {
+    visitor.visit_let_expression(&mut v.initializer);
+    visitor.visit_expression(&mut v.body);
+}
+
+pub fn walk_next_expression_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut NextExpression) {
+    visitor.visit_expression(&mut v.value);
+}
+
+pub fn walk_each_expression_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut EachExpression) {
+    visitor.visit_expression(&mut v.variable);
+    visitor.visit_expression(&mut v.object);
+    visitor.visit_expression(&mut v.body);
+}
+
+pub fn walk_branch_expression_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    v: &mut BranchExpression,
+) {
+    if let Some(e) = &mut v.where_exp {
+        visitor.visit_expression(e);
+    }
+    for c in &mut v.cases {
+        if let Some(e) = &mut c.where_exp {
+            visitor.visit_expression(e);
+        }
+        visitor.visit_expression(&mut c.testing);
+        visitor.visit_expression(&mut c.consequent);
+    }
+    if let Some(e) = &mut v.default_exp {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_match_expression_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut MatchExpression) {
+    visitor.visit_expression(&mut v.object);
+    if let Some(e) = &mut v.where_exp {
+        visitor.visit_expression(e);
+    }
+    for c in &mut v.cases {
+        if let Some(pattern) = &mut c.pattern {
+            visitor.visit_pattern(pattern);
+        }
+        if let Some(e) = &mut c.only {
+            visitor.visit_expression(e);
+        }
+        if let Some(e) = &mut c.where_exp {
+            visitor.visit_expression(e);
+        }
+        visitor.visit_expression(&mut c.consequent);
+    }
+    if let Some(e) = &mut v.default_exp {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_binary_expression_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    v: &mut BinaryExpression,
+) {
+    visitor.visit_expression(&mut v.left);
+    visitor.visit_expression(&mut v.right);
+}
+
+pub fn walk_named_operator_expression_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    v: &mut NamedOperatorExpression,
+) {
+    visitor.visit_expression(&mut v.left);
+    visitor.visit_expression(&mut v.right);
+}
+
+pub fn walk_unary_expression_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut UnaryExpression) {
+    visitor.visit_expression(&mut v.operand);
+}
+
+pub fn walk_function_call_expression_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    v: &mut FunctionCallExpression,
+) {
+    visitor.visit_expression(&mut v.callee);
+    for argument in &mut v.arguments {
+        visitor.visit_expression(&mut argument.value);
+    }
+}
+
+pub fn walk_member_expression_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    v: &mut MemberExpression,
+) {
+    match v {
+        MemberExpression::Property(p) => {
+            visitor.visit_expression(&mut p.object);
+            visitor.visit_expression(&mut p.property);
+        }
+        MemberExpression::Index(i) => {
+            visitor.visit_expression(&mut i.object);
+            visitor.visit_expression(&mut i.index);
+        }
+    }
+}
+
+pub fn walk_slice_expression_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut SliceExpression) {
+    visitor.visit_expression(&mut v.object);
+    visitor.visit_interval(&mut v.interval);
+}
+
+pub fn walk_constructor_expression_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    v: &mut ConstructorExpression,
+) {
+    visitor.visit_map(&mut v.value);
+}
+
+pub fn walk_tuple_constructor_expression_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    v: &mut TupleConstructorExpression,
+) {
+    visitor.visit_tuple(&mut v.value);
+}
+
+pub fn walk_anonymous_function_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    v: &mut AnonymousFunction,
+) {
+    visitor.visit_expression(&mut v.body);
+}
+
+pub fn walk_interval_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut Interval) {
+    visitor.visit_expression(&mut v.from);
+    if let Some(e) = &mut v.to {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_tuple_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut Tuple) {
+    for e in &mut v.elements {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_list_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut List) {
+    for e in &mut v.elements {
+        visitor.visit_expression(e);
+    }
+}
+
+pub fn walk_map_mut<V: MutVisitor + ?Sized>(visitor: &mut V, v: &mut Map) {
+    for entry in &mut v.elements {
+        visitor.visit_expression(&mut entry.key);
+        if let Some(e) = &mut entry.value {
+            visitor.visit_expression(e);
+        }
+    }
+}
+
+pub fn walk_pattern_mut<V: MutVisitor + ?Sized>(visitor: &mut V, pattern: &mut PatternExpression) {
+    if let Some(e) = pattern.expression_mut() {
+        visitor.visit_expression(e);
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expression::BlockExpression(v) => write!(f, "{}", v),
+            Expression::JoinExpression(v) => write!(f, "{}", v),
+            Expression::LetExpression(v) => write!(f, "{}", v),
+            Expression::IfExpression(v) => write!(f, "{}", v),
+            Expression::ForExpression(v) => write!(f, "{}", v),
+            Expression::NextExpression(v) => write!(f, "{}", v),
+            Expression::EachExpression(v) => write!(f, "{}", v),
+            Expression::BranchExpression(v) => write!(f, "{}", v),
+            Expression::MatchExpression(v) => write!(f, "{}", v),
+            Expression::Sign(v) => write!(f, "{}", v),
+            Expression::BinaryExpression(v) => write!(f, "{}", v),
+            Expression::NamedOperatorExpression(v) => write!(f, "{}", v),
+            Expression::UnaryExpression(v) => write!(f, "{}", v),
+            Expression::FunctionCallExpression(v) => write!(f, "{}", v),
+            Expression::MemberExpression(v) => write!(f, "{}", v),
+            Expression::SliceExpression(v) => write!(f, "{}", v),
+            Expression::ConstructorExpression(v) => write!(f, "{}", v),
+            Expression::TupleConstructorExpression(v) => write!(f, "{}", v),
+            Expression::AnonymousFunction(v) => write!(f, "{}", v),
+            Expression::Identifier(v) => write!(f, "{}", v),
+            Expression::PrefixIdentifier(v) => write!(f, "{}", v),
+            Expression::Ellipsis(v) => write!(f, "{}", v),
+            Expression::Interval(v) => write!(f, "{}", v),
+            Expression::Tuple(v) => write!(f, "{}", v),
+            Expression::List(v) => write!(f, "{}", v),
+            Expression::Map(v) => write!(f, "{}", v),
+            Expression::Literal(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Integer(Integer),
+    Float(Float),
+    Complex(Complex),
+    Bit(Bit),
     Boolean(Boolean),
     Char(Char),
     GeneralString(GeneralString),
@@ -1300,18 +3159,39 @@ pub enum Literal {
     NamedOperator(NamedOperator),
 }
 
+impl Literal {
+    // 字面量在源文件中的范围
+    pub fn range(&self) -> &Range {
+        match self {
+            Literal::Integer(v) => &v.range,
+            Literal::Float(v) => &v.range,
+            Literal::Complex(v) => &v.range,
+            Literal::Bit(v) => &v.range,
+            Literal::Boolean(v) => &v.range,
+            Literal::Char(v) => &v.range,
+            Literal::GeneralString(v) => &v.range,
+            Literal::TemplateString(v) => &v.range,
+            Literal::HashString(v) => &v.range,
+            Literal::NamedOperator(v) => &v.range,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Integer {
     pub value: i64,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Float {
     pub value: f64,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Complex {
     pub real: f64,
@@ -1319,6 +3199,7 @@ pub struct Complex {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Bit {
     pub width: usize,
@@ -1326,24 +3207,28 @@ pub struct Bit {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Boolean {
     pub value: bool,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Char {
     pub value: char,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct GeneralString {
     pub value: String,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct TemplateString {
     pub fragments: Vec<String>,
@@ -1351,12 +3236,14 @@ pub struct TemplateString {
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct HashString {
     pub value: String,
     pub range: Range,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct NamedOperator {
     pub value: String,
@@ -1377,7 +3264,19 @@ impl Display for Float {
 
 impl Display for Complex {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}+{}i", self.real, self.imaginary)
+        // 复数字面量的实部和虚部均不能为负数，所以（经过常量折叠之后的）负数实部
+        // 需要加上括号，负数虚部则使用减法，比如 `(-3)-4i`，以便重新解析之后得到相同的值。
+        if self.real < 0.0 {
+            write!(f, "({})", self.real)?;
+        } else {
+            write!(f, "{}", self.real)?;
+        }
+
+        if self.imaginary < 0.0 {
+            write!(f, "-{}i", -self.imaginary)
+        } else {
+            write!(f, "+{}i", self.imaginary)
+        }
     }
 }
 
@@ -1482,14 +3381,66 @@ fn format_function_parameters(parameters: &[FunctionParameter]) -> String {
         .join(", ")
 }
 
-// 返回函数签名的所有参数以逗号 ", " 的拼接，不包含括号
+// 返回空函数的所有参数以逗号 ", " 的拼接，不包含括号
 // 注：空函数的参数不支持默认值
 fn format_empty_function_parameters(parameters: &[EmptyFunctionParameter]) -> String {
-    todo!()
+    parameters
+        .iter()
+        .map(|p| format!("{} {}", p.data_type, p.name))
+        .collect::<Vec<String>>()
+        .join(", ")
 }
 
+// 返回关联类型列表，包括关键字 `type`
+// 只有一个关联类型时省略括号，比如 `type Item` 以及 `type (Item, Error = String)`
+fn format_associated_types(associated_types: &[AssociatedType]) -> String {
+    let items = associated_types
+        .iter()
+        .map(|a| match &a.object_type {
+            Some(t) => format!("{} = {}", a.name, t),
+            None => a.name.clone(),
+        })
+        .collect::<Vec<String>>();
+
+    if let [item] = items.as_slice() {
+        format!("type {}", item)
+    } else {
+        format!("type ({})", items.join(", "))
+    }
+}
+
+// 返回模式函数的所有参数以逗号 ", " 的拼接，不包含括号
 fn format_pattern_function_parameters(parameters: &[PatternFunctionParameter]) -> String {
-    todo!()
+    parameters
+        .iter()
+        .map(|p| {
+            let mut segments = Vec::<String>::new();
+
+            if let Some(d) = &p.data_type {
+                segments.push(d.to_string());
+            }
+
+            if let Some(v) = &p.variable {
+                segments.push(format!("{} @", v));
+            }
+
+            if let Some(e) = &p.pattern {
+                segments.push(e.to_string());
+            }
+
+            // 先 only，后 where
+            if let Some(e) = &p.only {
+                segments.push(format!("only {}", e));
+            }
+
+            if let Some(e) = &p.where_exp {
+                segments.push(format!("where {}", e));
+            }
+
+            segments.join(" ")
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
 }
 
 // 返回函数签名的所有参数以逗号 ", " 的拼接，不包含括号
@@ -1519,7 +3470,7 @@ fn format_anonymous_parameters(parameters: &[AnonymousParameter]) -> String {
                 format!("{} {}", d, p.name)
             } else {
                 // 省略了参数数据类型
-                format!("{}", p.name)
+                p.name.to_string()
             }
         })
         .collect::<Vec<String>>()
@@ -1551,6 +3502,58 @@ fn format_generics(generics: &[DataType]) -> String {
         .join(", ")
 }
 
+// 返回成员结构体的定义，不包含属性以及关键字 `struct`
+fn format_member_struct(s: &MemberStructDeclaration) -> String {
+    let mut segments = Vec::<String>::new();
+
+    segments.push(s.name.clone());
+
+    if !s.generics.is_empty() {
+        segments.push(format!("<{}>", format_generics(&s.generics)));
+    }
+
+    if !s.whiches.is_empty() {
+        segments.push(format!(
+            "which {{\n{}\n}}",
+            format_which_entries(&s.whiches)
+        ));
+    }
+
+    segments.push(format!("{{\n{}\n}}", format_struct_members(&s.members)));
+    segments.join(" ")
+}
+
+// 返回元组结构体的定义，不包含属性以及关键字 `struct`
+fn format_tuple_struct(s: &TupleStructDeclaration) -> String {
+    let mut segments = Vec::<String>::new();
+
+    segments.push(s.name.clone());
+
+    if !s.generics.is_empty() {
+        segments.push(format!("<{}>", format_generics(&s.generics)));
+    }
+
+    segments.push(format!("({})", format_generics(&s.members)));
+
+    if !s.whiches.is_empty() {
+        segments.push(format!(
+            "which {{\n{}\n}}",
+            format_which_entries(&s.whiches)
+        ));
+    }
+
+    segments.join(" ")
+}
+
+// 返回结构体的所有成员以换行符 "\n" 的拼接，不包含花括号
+fn format_struct_members(members: &[StructMember]) -> String {
+    members
+        .iter()
+        .map(|m| format!("{} {}", m.data_type, m.name))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
 // 返回所有 WhichEntry 表达式以逗号 ", " 的拼接，不包含花括号
 fn format_which_entries(whiches: &[WhichEntry]) -> String {
     whiches
@@ -1580,12 +3583,417 @@ fn format_expressions_with_comma(expressions: &[Expression]) -> String {
         .join(", ")
 }
 
-// 记录 Node 在源文件中的位置（范围）
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Range {
-    pub file_id: usize, // 源文件 id
-    pub start: usize,   // 开始位置
-    pub end: usize,     // 结束位置（不包括）
+// 记录 Node 在源文件中的位置（范围）
+//
+// 范围由语法分析器根据 Node 所消耗的 Token 计算，`start` 以及 `end` 为 UTF-8 字节偏移量。
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    pub file_id: usize, // 源文件 id
+    pub start: usize,   // 开始位置
+    pub end: usize,     // 结束位置（不包括）
+}
+
+impl Range {
+    // 所有成员均为 0 的范围，用于不是来自源代码的 Node（比如测试里构造的 AST）
+    pub fn zero() -> Range {
+        Range {
+            file_id: 0,
+            start: 0,
+            end: 0,
+        }
+    }
+}
+
+impl Node {
+    // 把 Node 里所有的范围设置为 `Range::zero()`
+    //
+    // 比较 Node 时范围也参与比较，当只需比较两个 Node 的结构时（比如格式化前后的
+    // 源代码的 AST、模块接口里的数据类型），先清除两者的范围。
+    pub fn clear_ranges(&mut self) {
+        let mut clearer = RangeClearer;
+        match self {
+            Node::Program(v) => clearer.visit_program(v),
+            Node::Statement(v) => clearer.visit_statement(v),
+            Node::Expression(v) => clearer.visit_expression(v),
+        }
+    }
+}
+
+// 把所有的范围设置为 `Range::zero()` 的访问者，见 `Node::clear_ranges`
+//
+// 表达式由 `MutVisitor` 的默认实现遍历，这里只清除各个节点自身的范围，
+// 以及 `MutVisitor` 不会访问的部分（数据类型、属性、参数以及各个分支等）的范围。
+pub struct RangeClearer;
+
+impl RangeClearer {
+    pub fn clear_data_type(&mut self, data_type: &mut DataType) {
+        match data_type {
+            DataType::Identifier(v) => self.visit_identifier(v),
+            DataType::Tuple(v) => self.visit_tuple(v),
+            DataType::Sign(v) => self.visit_sign(v),
+        }
+    }
+
+    pub fn clear_attribute(&mut self, attribute: &mut Attribute) {
+        for (_, value) in &mut attribute.arguments {
+            if let Some(e) = value {
+                self.visit_expression(e);
+            }
+        }
+    }
+
+    fn clear_data_types<'a>(&mut self, data_types: impl IntoIterator<Item = &'a mut DataType>) {
+        for data_type in data_types {
+            self.clear_data_type(data_type);
+        }
+    }
+
+    fn clear_attributes(&mut self, attributes: &mut [Attribute]) {
+        for attribute in attributes {
+            self.clear_attribute(attribute);
+        }
+    }
+
+    fn clear_whiches(&mut self, whiches: &mut [WhichEntry]) {
+        for which in whiches {
+            match which {
+                WhichEntry::Type(v) => {
+                    self.clear_data_type(&mut v.data_type);
+                    v.range = Range::zero();
+                }
+                WhichEntry::Limit(v) => {
+                    self.clear_data_types(&mut v.data_types);
+                    v.range = Range::zero();
+                }
+            }
+        }
+    }
+
+    fn clear_associated_types(&mut self, associated_types: &mut [AssociatedType]) {
+        for associated_type in associated_types {
+            if let Some(object_type) = &mut associated_type.object_type {
+                self.visit_identifier(object_type);
+            }
+            associated_type.range = Range::zero();
+        }
+    }
+}
+
+impl MutVisitor for RangeClearer {
+    fn visit_program(&mut self, program: &mut Program) {
+        program.range = Range::zero();
+        walk_program_mut(self, program);
+    }
+
+    fn visit_function_declaration(&mut self, v: &mut FunctionDeclaration) {
+        self.clear_attributes(&mut v.attributes);
+        self.clear_data_types(&mut v.generics);
+        for p in &mut v.parameters {
+            self.clear_data_type(&mut p.data_type);
+            p.range = Range::zero();
+        }
+        self.clear_data_types(&mut v.return_data_type);
+        self.clear_whiches(&mut v.whiches);
+        v.range = Range::zero();
+        walk_function_declaration_mut(self, v);
+    }
+
+    fn visit_empty_function_declaration(&mut self, v: &mut EmptyFunctionDeclaration) {
+        self.clear_attributes(&mut v.attributes);
+        self.clear_data_types(&mut v.generics);
+        for p in &mut v.parameters {
+            self.clear_data_type(&mut p.data_type);
+            p.range = Range::zero();
+        }
+        self.clear_data_types(&mut v.return_data_type);
+        self.clear_whiches(&mut v.whiches);
+        v.range = Range::zero();
+    }
+
+    fn visit_pattern_function_declaration(&mut self, v: &mut PatternFunctionDeclaration) {
+        self.clear_data_types(&mut v.generics);
+        for p in &mut v.parameters {
+            self.clear_data_types(&mut p.data_type);
+            p.range = Range::zero();
+        }
+        self.clear_data_types(&mut v.return_data_type);
+        self.clear_whiches(&mut v.whiches);
+        v.range = Range::zero();
+        walk_pattern_function_declaration_mut(self, v);
+    }
+
+    fn visit_namespace_statement(&mut self, v: &mut NamespaceStatement) {
+        v.range = Range::zero();
+    }
+
+    fn visit_use_statement(&mut self, v: &mut UseStatement) {
+        v.range = Range::zero();
+    }
+
+    fn visit_const_declaration(&mut self, v: &mut ConstDeclaration) {
+        self.clear_attributes(&mut v.attributes);
+        self.clear_data_types(&mut v.data_type);
+        v.range = Range::zero();
+        walk_const_declaration_mut(self, v);
+    }
+
+    fn visit_member_struct_declaration(&mut self, v: &mut MemberStructDeclaration) {
+        self.clear_attributes(&mut v.attributes);
+        for m in &mut v.members {
+            self.clear_data_type(&mut m.data_type);
+            m.range = Range::zero();
+        }
+        self.clear_data_types(&mut v.generics);
+        self.clear_whiches(&mut v.whiches);
+        v.range = Range::zero();
+    }
+
+    fn visit_tuple_struct_declaration(&mut self, v: &mut TupleStructDeclaration) {
+        self.clear_attributes(&mut v.attributes);
+        self.clear_data_types(&mut v.members);
+        self.clear_data_types(&mut v.generics);
+        self.clear_whiches(&mut v.whiches);
+        v.range = Range::zero();
+    }
+
+    fn visit_empty_struct_declaration(&mut self, v: &mut EmptyStructDeclaration) {
+        v.range = Range::zero();
+    }
+
+    fn visit_union_declaration(&mut self, v: &mut UnionDeclaration) {
+        for member in &mut v.members {
+            match member {
+                UnionMember::Struct(s) => self.visit_member_struct_declaration(s),
+                UnionMember::Tuple(s) => self.visit_tuple_struct_declaration(s),
+                UnionMember::Empty(s) => self.visit_empty_struct_declaration(s),
+            }
+        }
+        self.clear_data_types(&mut v.generics);
+        self.clear_whiches(&mut v.whiches);
+        v.range = Range::zero();
+    }
+
+    fn visit_trait_declaration(&mut self, v: &mut TraitDeclaration) {
+        self.clear_data_types(&mut v.generics);
+        self.clear_data_types(&mut v.supertraits);
+        self.clear_associated_types(&mut v.associated_types);
+        self.clear_whiches(&mut v.whiches);
+        v.range = Range::zero();
+        walk_trait_declaration_mut(self, v);
+    }
+
+    fn visit_impl_statement(&mut self, v: &mut ImplStatement) {
+        self.clear_data_types(&mut v.generics);
+        self.visit_identifier(&mut v.object);
+        self.visit_identifier(&mut v.inherit);
+        self.clear_associated_types(&mut v.associated_types);
+        self.clear_whiches(&mut v.whiches);
+        v.range = Range::zero();
+        walk_impl_statement_mut(self, v);
+    }
+
+    fn visit_alias_statement(&mut self, v: &mut AliasStatement) {
+        self.clear_attributes(&mut v.attributes);
+        self.clear_data_type(&mut v.data_type);
+        self.clear_data_types(&mut v.generics);
+        v.range = Range::zero();
+    }
+
+    fn visit_error_statement(&mut self, v: &mut ErrorStatement) {
+        v.range = Range::zero();
+    }
+
+    fn visit_block_expression(&mut self, v: &mut BlockExpression) {
+        v.range = Range::zero();
+        walk_block_expression_mut(self, v);
+    }
+
+    fn visit_join_expression(&mut self, v: &mut JoinExpression) {
+        v.range = Range::zero();
+        walk_join_expression_mut(self, v);
+    }
+
+    fn visit_let_expression(&mut self, v: &mut LetExpression) {
+        self.clear_attributes(&mut v.attributes);
+        self.clear_data_types(&mut v.data_type);
+        v.range = Range::zero();
+        walk_let_expression_mut(self, v);
+    }
+
+    fn visit_if_expression(&mut self, v: &mut IfExpression) {
+        v.range = Range::zero();
+        walk_if_expression_mut(self, v);
+    }
+
+    fn visit_for_expression(&mut self, v: &mut ForExpression) {
+        v.range = Range::zero();
+        walk_for_expression_mut(self, v);
+    }
+
+    fn visit_next_expression(&mut self, v: &mut NextExpression) {
+        v.range = Range::zero();
+        walk_next_expression_mut(self, v);
+    }
+
+    fn visit_each_expression(&mut self, v: &mut EachExpression) {
+        v.range = Range::zero();
+        walk_each_expression_mut(self, v);
+    }
+
+    fn visit_branch_expression(&mut self, v: &mut BranchExpression) {
+        for c in &mut v.cases {
+            c.range = Range::zero();
+        }
+        v.range = Range::zero();
+        walk_branch_expression_mut(self, v);
+    }
+
+    fn visit_match_expression(&mut self, v: &mut MatchExpression) {
+        for c in &mut v.cases {
+            c.range = Range::zero();
+        }
+        v.range = Range::zero();
+        walk_match_expression_mut(self, v);
+    }
+
+    fn visit_sign(&mut self, v: &mut Sign) {
+        for p in &mut v.parameters {
+            self.clear_data_type(&mut p.data_type);
+            p.range = Range::zero();
+        }
+        if let Some(d) = &mut v.return_data_type {
+            self.clear_data_type(d);
+        }
+        self.clear_data_types(&mut v.generics);
+        self.clear_whiches(&mut v.whiches);
+        v.range = Range::zero();
+    }
+
+    fn visit_binary_expression(&mut self, v: &mut BinaryExpression) {
+        v.range = Range::zero();
+        walk_binary_expression_mut(self, v);
+    }
+
+    fn visit_named_operator_expression(&mut self, v: &mut NamedOperatorExpression) {
+        v.range = Range::zero();
+        walk_named_operator_expression_mut(self, v);
+    }
+
+    fn visit_unary_expression(&mut self, v: &mut UnaryExpression) {
+        v.range = Range::zero();
+        walk_unary_expression_mut(self, v);
+    }
+
+    fn visit_function_call_expression(&mut self, v: &mut FunctionCallExpression) {
+        for a in &mut v.arguments {
+            a.range = Range::zero();
+        }
+        v.range = Range::zero();
+        walk_function_call_expression_mut(self, v);
+    }
+
+    fn visit_member_expression(&mut self, v: &mut MemberExpression) {
+        match v {
+            MemberExpression::Property(p) => p.range = Range::zero(),
+            MemberExpression::Index(i) => i.range = Range::zero(),
+        }
+        walk_member_expression_mut(self, v);
+    }
+
+    fn visit_slice_expression(&mut self, v: &mut SliceExpression) {
+        v.range = Range::zero();
+        walk_slice_expression_mut(self, v);
+    }
+
+    fn visit_constructor_expression(&mut self, v: &mut ConstructorExpression) {
+        self.visit_identifier(&mut v.object);
+        v.range = Range::zero();
+        walk_constructor_expression_mut(self, v);
+    }
+
+    fn visit_tuple_constructor_expression(&mut self, v: &mut TupleConstructorExpression) {
+        self.visit_identifier(&mut v.object);
+        v.range = Range::zero();
+        walk_tuple_constructor_expression_mut(self, v);
+    }
+
+    fn visit_anonymous_function(&mut self, v: &mut AnonymousFunction) {
+        for p in &mut v.parameters {
+            self.clear_data_types(&mut p.data_type);
+            p.range = Range::zero();
+        }
+        self.clear_data_types(&mut v.return_data_type);
+        self.clear_whiches(&mut v.whiches);
+        v.range = Range::zero();
+        walk_anonymous_function_mut(self, v);
+    }
+
+    fn visit_identifier(&mut self, v: &mut Identifier) {
+        self.clear_data_types(&mut v.generics);
+        v.range = Range::zero();
+    }
+
+    fn visit_prefix_identifier(&mut self, v: &mut PrefixIdentifier) {
+        self.visit_identifier(&mut v.identifier);
+        v.range = Range::zero();
+    }
+
+    fn visit_ellipsis(&mut self, v: &mut Ellipsis) {
+        v.range = Range::zero();
+    }
+
+    fn visit_interval(&mut self, v: &mut Interval) {
+        v.range = Range::zero();
+        walk_interval_mut(self, v);
+    }
+
+    fn visit_tuple(&mut self, v: &mut Tuple) {
+        v.range = Range::zero();
+        walk_tuple_mut(self, v);
+    }
+
+    fn visit_list(&mut self, v: &mut List) {
+        v.range = Range::zero();
+        walk_list_mut(self, v);
+    }
+
+    fn visit_map(&mut self, v: &mut Map) {
+        for entry in &mut v.elements {
+            entry.range = Range::zero();
+        }
+        v.range = Range::zero();
+        walk_map_mut(self, v);
+    }
+
+    fn visit_literal(&mut self, v: &mut Literal) {
+        let range = match v {
+            Literal::Integer(v) => &mut v.range,
+            Literal::Float(v) => &mut v.range,
+            Literal::Complex(v) => &mut v.range,
+            Literal::Bit(v) => &mut v.range,
+            Literal::Boolean(v) => &mut v.range,
+            Literal::Char(v) => &mut v.range,
+            Literal::GeneralString(v) => &mut v.range,
+            Literal::TemplateString(v) => {
+                for e in &mut v.expressions {
+                    self.visit_expression(e);
+                }
+                &mut v.range
+            }
+            Literal::HashString(v) => &mut v.range,
+            Literal::NamedOperator(v) => &mut v.range,
+        };
+        *range = Range::zero();
+    }
+
+    fn visit_pattern(&mut self, pattern: &mut PatternExpression) {
+        match pattern {
+            PatternExpression::Into(d, _) => self.clear_data_type(d),
+            PatternExpression::Regular(_, t) => self.visit_tuple(t),
+            _ => walk_pattern_mut(self, pattern),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1595,17 +4003,21 @@ mod tests {
             Argument, Bit, Complex, Ellipsis, Expression, FunctionCallExpression, GeneralString,
             HashString, Identifier, JoinExpression, List, MatchCase, MatchExpression, MemberIndex,
             MemberProperty, NamedOperator, NextExpression, PatternExpression, PrefixIdentifier,
-            SignParameter, UnaryExpression, WhichEntry, WhichEntryLimit, WhichEntryType,
+            NamedOperatorExpression, SignParameter, UnaryExpression, UnaryOperator, WhichEntry,
+            WhichEntryLimit, WhichEntryType,
         },
-        token::Token,
     };
 
     use super::{
-        AnonymousFunction, AnonymousParameter, BinaryExpression, BlockExpression, Boolean,
-        BranchCase, BranchExpression, Char, ConstructorExpression, DataType, EachExpression, Float,
+        AliasStatement, AnonymousFunction, AnonymousParameter, BinaryExpression, BinaryOperator, BlockExpression, Boolean,
+        BranchCase, BranchExpression, Char, ConstDeclaration, ConstructorExpression, DataType, EachExpression, Float,
         ForExpression, FunctionDeclaration, FunctionParameter, IfExpression, Integer, Interval,
-        LetExpression, Literal, Map, MapEntry, MemberExpression, Range, Sign, SliceExpression,
-        Statement, TemplateString, Tuple,
+        LetExpression, Literal, Map, MapEntry, MemberExpression, NamePath, NamePathItem, Range, Sign,
+        SliceExpression, Statement, TemplateString, Tuple, UseStatement, AssociatedType, Attribute,
+        EmptyFunctionDeclaration, EmptyFunctionParameter, EmptyStructDeclaration, ImplStatement,
+        MemberStructDeclaration, NamespaceStatement, PatternFunctionDeclaration,
+        PatternFunctionParameter, StructMember, TraitDeclaration, TraitFunctionItem,
+        TupleStructDeclaration, UnionDeclaration, UnionMember,
     };
 
     // 辅助函数
@@ -1629,7 +4041,7 @@ mod tests {
 
     fn new_literal_integer(value: i64) -> Literal {
         Literal::Integer(Integer {
-            value: value,
+            value,
             range: new_range(),
         })
     }
@@ -1643,7 +4055,7 @@ mod tests {
 
     fn new_literal_boolean(value: bool) -> Literal {
         Literal::Boolean(Boolean {
-            value: value,
+            value,
             range: new_range(),
         })
     }
@@ -1674,7 +4086,7 @@ mod tests {
         Expression::BinaryExpression(BinaryExpression {
             left: Box::new(Expression::Literal(new_literal_integer(left))),
             right: Box::new(Expression::Literal(new_literal_integer(right))),
-            operator: Token::Plus,
+            operator: BinaryOperator::Plus,
             range: new_range(),
         })
     }
@@ -1683,13 +4095,14 @@ mod tests {
         Expression::BinaryExpression(BinaryExpression {
             left: Box::new(Expression::Literal(new_literal_integer(left))),
             right: Box::new(Expression::Literal(new_literal_integer(right))),
-            operator: Token::GreaterThan,
+            operator: BinaryOperator::GreaterThan,
             range: new_range(),
         })
     }
 
     fn new_let_expression(variable_name: &str, value: i64) -> Expression {
         Expression::LetExpression(LetExpression {
+            attributes: vec![],
             data_type: Some(DataType::Identifier(new_identifier("Int"))),
             object: Box::new(Expression::Identifier(new_identifier(variable_name))),
             value: Box::new(Expression::Literal(new_literal_integer(value))),
@@ -2025,7 +4438,7 @@ mod tests {
         assert_eq!(t4.to_string(), "(123, 456, (abc, def,),)");
 
         // 测试辅助函数
-        let t5 = new_tuple(&vec![8, 13, 21, 34]);
+        let t5 = new_tuple(&[8, 13, 21, 34]);
         assert_eq!(t5.to_string(), "(8, 13, 21, 34,)");
     }
 
@@ -2113,7 +4526,7 @@ mod tests {
         assert_eq!(l7.to_string(), "[1, 2, ...rest,]");
 
         // 检查辅助函数
-        let l8 = new_list(&vec![3, 5, 8, 13, 21]);
+        let l8 = new_list(&[3, 5, 8, 13, 21]);
         assert_eq!(l8.to_string(), "[3, 5, 8, 13, 21,]");
     }
 
@@ -2188,7 +4601,7 @@ mod tests {
     #[test]
     fn test_binary_expression() {
         let e1 = BinaryExpression {
-            operator: Token::Plus,
+            operator: BinaryOperator::Plus,
             left: Box::new(Expression::Literal(new_literal_integer(1))),
             right: Box::new(Expression::Literal(new_literal_integer(2))),
             range: new_range(),
@@ -2197,10 +4610,10 @@ mod tests {
 
         // 测试二元运算嵌套
         let e2 = BinaryExpression {
-            operator: Token::Asterisk,
+            operator: BinaryOperator::Asterisk,
             left: Box::new(Expression::Literal(new_literal_integer(1))),
             right: Box::new(Expression::BinaryExpression(BinaryExpression {
-                operator: Token::Minus,
+                operator: BinaryOperator::Minus,
                 left: Box::new(Expression::Literal(new_literal_integer(2))),
                 right: Box::new(Expression::Literal(new_literal_integer(3))),
                 range: new_range(),
@@ -2217,11 +4630,22 @@ mod tests {
         assert_eq!(e4.to_string(), "(6 > 3)");
     }
 
+    #[test]
+    fn test_named_operator_expression() {
+        let e1 = NamedOperatorExpression {
+            name: "bitOr".to_string(),
+            left: Box::new(Expression::Literal(new_literal_integer(1))),
+            right: Box::new(Expression::Literal(new_literal_integer(2))),
+            range: new_range(),
+        };
+        assert_eq!(e1.to_string(), "(1 :bitOr: 2)");
+    }
+
     #[test]
     fn test_unary_expression() {
         // 测试 `^` 运算
         let e1 = UnaryExpression {
-            operator: Token::Cast,
+            operator: UnaryOperator::Cast,
             operand: Box::new(Expression::Identifier(new_identifier("foo"))),
             range: new_range(),
         };
@@ -2229,7 +4653,7 @@ mod tests {
 
         // 测试表达式的 `^` 运算
         let e2 = UnaryExpression {
-            operator: Token::Cast,
+            operator: UnaryOperator::Cast,
             operand: Box::new(Expression::Tuple(Tuple {
                 elements: vec![
                     Expression::Literal(new_literal_integer(123)),
@@ -2243,7 +4667,7 @@ mod tests {
 
         // 测试 `-` 运算
         let e3 = UnaryExpression {
-            operator: Token::Minus,
+            operator: UnaryOperator::Minus,
             operand: Box::new(Expression::Literal(new_literal_integer(1))),
             range: new_range(),
         };
@@ -2251,7 +4675,7 @@ mod tests {
 
         // 测试表达式的 `-` 运算
         let e4 = UnaryExpression {
-            operator: Token::Minus,
+            operator: UnaryOperator::Minus,
             operand: Box::new(new_addition_expression(1, 2)),
             range: new_range(),
         };
@@ -2259,7 +4683,7 @@ mod tests {
 
         // 测试 `?` 运算
         let e5 = UnaryExpression {
-            operator: Token::Unwrap,
+            operator: UnaryOperator::Unwrap,
             operand: Box::new(Expression::Identifier(new_identifier("foo"))),
             range: new_range(),
         };
@@ -2267,7 +4691,7 @@ mod tests {
 
         // 测试表达式的 `?` 运算
         let e6 = UnaryExpression {
-            operator: Token::Unwrap,
+            operator: UnaryOperator::Unwrap,
             operand: Box::new(new_addition_expression(1, 2)),
             range: new_range(),
         };
@@ -2328,7 +4752,7 @@ mod tests {
         // callee 为表达式的情况
         let e5 = FunctionCallExpression {
             callee: Box::new(Expression::BinaryExpression(BinaryExpression {
-                operator: Token::Combine,
+                operator: BinaryOperator::Combine,
                 left: Box::new(Expression::Identifier(new_identifier("foo"))),
                 right: Box::new(Expression::Identifier(new_identifier("bar"))),
                 range: new_range(),
@@ -2361,7 +4785,7 @@ mod tests {
 
         // 属性为数字
         let e2 = MemberExpression::Property(MemberProperty {
-            object: Box::new(Expression::Tuple(new_tuple(&vec![1, 2, 3]))),
+            object: Box::new(Expression::Tuple(new_tuple(&[1, 2, 3]))),
             property: Box::new(Expression::Literal(new_literal_integer(1))),
             range: new_range(),
         });
@@ -2754,6 +5178,7 @@ mod tests {
     #[test]
     fn test_let_expression() {
         let e1 = LetExpression {
+            attributes: vec![],
             data_type: None,
             object: Box::new(Expression::Identifier(new_identifier("foo"))),
             value: Box::new(Expression::Literal(new_literal_integer(123))),
@@ -2762,6 +5187,7 @@ mod tests {
         assert_eq!(e1.to_string(), "let foo = 123");
 
         let e2 = LetExpression {
+            attributes: vec![],
             data_type: Some(DataType::Identifier(new_identifier("Int"))),
             object: Box::new(Expression::Identifier(new_identifier("foo"))),
             value: Box::new(Expression::Literal(new_literal_integer(123))),
@@ -2771,6 +5197,7 @@ mod tests {
 
         // 右手边值为表达式
         let e3 = LetExpression {
+            attributes: vec![],
             data_type: None,
             object: Box::new(Expression::Identifier(new_identifier("bar"))),
             value: Box::new(new_addition_expression(1, 2)),
@@ -2780,6 +5207,7 @@ mod tests {
 
         // 左手边值为元组
         let e4 = LetExpression {
+            attributes: vec![],
             data_type: Some(DataType::Tuple(Tuple {
                 elements: vec![
                     Expression::Identifier(new_identifier("Int")),
@@ -2869,6 +5297,7 @@ mod tests {
     fn test_for_expression() {
         let e1 = ForExpression {
             initializer: Box::new(LetExpression {
+                attributes: vec![],
                 data_type: None,
                 object: Box::new(Expression::Identifier(new_identifier("i"))),
                 value: Box::new(Expression::Literal(new_literal_integer(100))),
@@ -2895,6 +5324,7 @@ mod tests {
         // 测试 body 为表达式块
         let e4 = ForExpression {
             initializer: Box::new(LetExpression {
+                attributes: vec![],
                 data_type: None,
                 object: Box::new(Expression::Tuple(Tuple {
                     elements: vec![
@@ -2903,17 +5333,18 @@ mod tests {
                     ],
                     range: new_range(),
                 })),
-                value: Box::new(Expression::Tuple(new_tuple(&vec![0, 2]))),
+                value: Box::new(Expression::Tuple(new_tuple(&[0, 2]))),
                 range: new_range(),
             }),
             body: Box::new(Expression::BlockExpression(BlockExpression {
                 is_explicit: true,
                 body: vec![
                     Expression::LetExpression(LetExpression {
+                        attributes: vec![],
                         data_type: None,
                         object: Box::new(Expression::Identifier(new_identifier("i"))),
                         value: Box::new(Expression::BinaryExpression(BinaryExpression {
-                            operator: Token::Plus,
+                            operator: BinaryOperator::Plus,
                             left: Box::new(Expression::Identifier(new_identifier("sum"))),
                             right: Box::new(Expression::Identifier(new_identifier("step"))),
                             range: new_range(),
@@ -2922,7 +5353,7 @@ mod tests {
                     }),
                     Expression::IfExpression(IfExpression {
                         testing: Box::new(Expression::BinaryExpression(BinaryExpression {
-                            operator: Token::LessThan,
+                            operator: BinaryOperator::LessThan,
                             left: Box::new(Expression::Identifier(new_identifier("i"))),
                             right: Box::new(Expression::Literal(new_literal_integer(100))),
                             range: new_range(),
@@ -2961,7 +5392,7 @@ mod tests {
     fn test_each_expression() {
         let e1 = EachExpression {
             variable: Box::new(Expression::Identifier(new_identifier("i"))),
-            object: Box::new(Expression::List(new_list(&vec![1, 2, 3]))),
+            object: Box::new(Expression::List(new_list(&[1, 2, 3]))),
             body: Box::new(Expression::Literal(new_literal_integer(5))),
             range: new_range(),
         };
@@ -2970,7 +5401,7 @@ mod tests {
         // body 为 do 表达式
         let e2 = EachExpression {
             variable: Box::new(Expression::Identifier(new_identifier("i"))),
-            object: Box::new(Expression::List(new_list(&vec![1, 2, 3]))),
+            object: Box::new(Expression::List(new_list(&[1, 2, 3]))),
             body: Box::new(Expression::BlockExpression(BlockExpression {
                 is_explicit: true,
                 body: vec![Expression::FunctionCallExpression(FunctionCallExpression {
@@ -3034,7 +5465,7 @@ mod tests {
                 BranchCase {
                     where_exp: Some(Box::new(new_let_expression("i", 20))),
                     testing: Box::new(Expression::BinaryExpression(BinaryExpression {
-                        operator: Token::GreaterThan,
+                        operator: BinaryOperator::GreaterThan,
                         left: Box::new(Expression::Identifier(new_identifier("foo"))),
                         right: Box::new(Expression::Identifier(new_identifier("i"))),
                         range: new_range(),
@@ -3112,7 +5543,7 @@ mod tests {
                 MatchCase {
                     variable: Some("v".to_string()),
                     pattern: Some(Box::new(PatternExpression::In(Expression::List(new_list(
-                        &vec![1, 2, 3],
+                        &[1, 2, 3],
                     ))))),
                     only: None,
                     where_exp: None,
@@ -3125,12 +5556,13 @@ mod tests {
                         new_literal_boolean(true),
                     )))),
                     only: Some(Box::new(Expression::BinaryExpression(BinaryExpression {
-                        operator: Token::GreaterThan,
+                        operator: BinaryOperator::GreaterThan,
                         left: Box::new(Expression::Identifier(new_identifier("foo"))),
                         right: Box::new(Expression::Identifier(new_identifier("bar"))),
                         range: new_range(),
                     }))),
                     where_exp: Some(Box::new(Expression::LetExpression(LetExpression {
+                        attributes: vec![],
                         data_type: None,
                         object: Box::new(Expression::Identifier(new_identifier("bar"))),
                         value: Box::new(Expression::Literal(new_literal_integer(20))),
@@ -3505,6 +5937,7 @@ mod tests {
     #[test]
     fn test_function_declaration() {
         let s1 = FunctionDeclaration {
+            attributes: vec![],
             name: "test".to_string(),
             generics: vec![],
             parameters: vec![
@@ -3525,7 +5958,7 @@ mod tests {
             whiches: vec![],
             // where_exp: None,
             body: Expression::BinaryExpression(BinaryExpression {
-                operator: Token::Plus,
+                operator: BinaryOperator::Plus,
                 left: Box::new(Expression::Identifier(new_identifier("a"))),
                 right: Box::new(Expression::Identifier(new_identifier("b"))),
                 range: new_range(),
@@ -3539,6 +5972,7 @@ mod tests {
 
         // 测试泛型和 which 从属表达式
         let s2 = FunctionDeclaration {
+            attributes: vec![],
             name: "writeLine".to_string(),
             generics: vec![
                 DataType::Identifier(new_identifier("D")),
@@ -3582,7 +6016,7 @@ mod tests {
                     Argument {
                         name: None,
                         value: Box::new(Expression::BinaryExpression(BinaryExpression {
-                            operator: Token::Concat,
+                            operator: BinaryOperator::Concat,
                             left: Box::new(Expression::Identifier(new_identifier("data"))),
                             right: Box::new(Expression::Literal(new_literal_string("\\n"))),
                             range: new_range(),
@@ -3608,6 +6042,7 @@ mod tests {
 
         // 测试默认值和 where 从属表达式
         let s3 = FunctionDeclaration {
+            attributes: vec![],
             name: "test".to_string(),
             generics: vec![],
             parameters: vec![
@@ -3627,10 +6062,11 @@ mod tests {
             return_data_type: Some(DataType::Identifier(new_identifier("Int"))),
             whiches: vec![],
             // where_exp: Some(Expression::LetExpression(LetExpression {
+            //     attributes: vec![],
             //     data_type: None,
             //     object: Box::new(Expression::Identifier(new_identifier("c"))),
             //     value: Box::new(Expression::BinaryExpression(BinaryExpression {
-            //         operator: Token::Plus,
+            //         operator: BinaryOperator::Plus,
             //         left: Box::new(Expression::Identifier(new_identifier("a"))),
             //         right: Box::new(Expression::Identifier(new_identifier("b"))),
             //         range: new_range(),
@@ -3655,52 +6091,431 @@ mod tests {
         );
     }
 
+    #[test]
     fn test_empty_function_declaration() {
-        // todo::
+        let s1 = EmptyFunctionDeclaration {
+            attributes: vec![],
+            name: "size".to_string(),
+            generics: vec![],
+            parameters: vec![EmptyFunctionParameter {
+                data_type: DataType::Identifier(new_identifier("String")),
+                name: "s".to_string(),
+                range: new_range(),
+            }],
+            return_data_type: Some(DataType::Identifier(new_identifier("Int"))),
+            whiches: vec![],
+            range: new_range(),
+        };
+        assert_eq!(s1.to_string(), "empty function size (String s) type Int\n");
+
+        // 测试属性以及泛型
+        let s2 = EmptyFunctionDeclaration {
+            attributes: vec![Attribute {
+                name: "extern".to_string(),
+                arguments: vec![(
+                    "".to_string(),
+                    Some(Expression::Literal(new_literal_string("wasi"))),
+                )],
+            }],
+            name: "write".to_string(),
+            generics: vec![DataType::Identifier(new_identifier("T"))],
+            parameters: vec![],
+            return_data_type: None,
+            whiches: vec![WhichEntry::Limit(WhichEntryLimit {
+                name: "T".to_string(),
+                data_types: vec![DataType::Identifier(new_identifier("Display"))],
+                range: new_range(),
+            })],
+            range: new_range(),
+        };
+        assert_eq!(
+            s2.to_string(),
+            trim_left_margin(
+                "#[extern(\"wasi\")]
+                empty function write <T> () which {
+                    T: limit Display
+                }
+                "
+            )
+        );
     }
 
+    #[test]
     fn test_pattern_function_declarationzs() {
-        // todo::
+        let s1 = PatternFunctionDeclaration {
+            name: "f".to_string(),
+            generics: vec![],
+            parameters: vec![
+                PatternFunctionParameter {
+                    data_type: Some(DataType::Identifier(new_identifier("Int"))),
+                    variable: Some("i".to_string()),
+                    pattern: Some(Box::new(PatternExpression::In(Expression::List(new_list(
+                        &[1, 2],
+                    ))))),
+                    where_exp: None,
+                    only: Some(Box::new(new_relational_expression(1, 0))),
+                    range: new_range(),
+                },
+                PatternFunctionParameter {
+                    data_type: None,
+                    variable: None,
+                    pattern: Some(Box::new(PatternExpression::Primary(Expression::Tuple(
+                        new_tuple(&[1, 2]),
+                    )))),
+                    where_exp: None,
+                    only: None,
+                    range: new_range(),
+                },
+            ],
+            return_data_type: None,
+            only: Some(Expression::Identifier(new_identifier("x"))),
+            whiches: vec![],
+            body: Expression::Identifier(new_identifier("x")),
+            range: new_range(),
+        };
+        assert_eq!(
+            s1.to_string(),
+            "pattern function f (Int i @ in [1, 2,] only (1 > 0), (1, 2,)) only (x) = x\n"
+        );
     }
 
-    // fn test_namespace_statement() {
-    //     // todo::
-    // }
+    #[test]
+    fn test_namespace_statement() {
+        let s1 = NamespaceStatement {
+            dirs: vec!["std".to_string(), "io".to_string()],
+            range: new_range(),
+        };
+        assert_eq!(s1.to_string(), "namespace std::io\n");
+    }
 
+    #[test]
     fn test_use_statement() {
-        // todo::
+        let name = |n: &str| NamePathItem::Name(n.to_string());
+
+        let s1 = UseStatement {
+            name_path: NamePath {
+                directories: vec![name("std"), name("List")],
+                alias: None,
+            },
+            range: new_range(),
+        };
+        assert_eq!(s1.to_string(), "use std::List\n");
+        assert_eq!(
+            s1.imports(),
+            vec![(vec!["std".to_string(), "List".to_string()], "List".to_string())]
+        );
+
+        // use foo::{bar as baz, sub::{one, two}}
+        let s2 = UseStatement {
+            name_path: NamePath {
+                directories: vec![NamePathItem::Children(
+                    "foo".to_string(),
+                    vec![
+                        NamePath {
+                            directories: vec![name("bar")],
+                            alias: Some("baz".to_string()),
+                        },
+                        NamePath {
+                            directories: vec![NamePathItem::Children(
+                                "sub".to_string(),
+                                vec![
+                                    NamePath {
+                                        directories: vec![name("one")],
+                                        alias: None,
+                                    },
+                                    NamePath {
+                                        directories: vec![name("two")],
+                                        alias: None,
+                                    },
+                                ],
+                            )],
+                            alias: None,
+                        },
+                    ],
+                )],
+                alias: None,
+            },
+            range: new_range(),
+        };
+        assert_eq!(s2.to_string(), "use foo::{bar as baz, sub::{one, two}}\n");
+        assert_eq!(
+            s2.imports()
+                .iter()
+                .map(|(path, local_name)| format!("{} {}", path.join("::"), local_name))
+                .collect::<Vec<String>>(),
+            vec!["foo::bar baz", "foo::sub::one one", "foo::sub::two two"]
+        );
     }
 
+    #[test]
     fn test_const_declaration() {
-        // todo::
+        let s1 = ConstDeclaration {
+            attributes: vec![],
+            data_type: None,
+            name: "MAX".to_string(),
+            value: new_addition_expression(1, 2),
+            range: new_range(),
+        };
+        assert_eq!(s1.to_string(), "const MAX = (1 + 2)\n");
+
+        let s2 = ConstDeclaration {
+            data_type: Some(DataType::Identifier(new_identifier("Int"))),
+            ..s1
+        };
+        assert_eq!(s2.to_string(), "const Int MAX = (1 + 2)\n");
     }
 
+    #[test]
     fn test_member_struct_declaration() {
-        // todo::
+        let s1 = MemberStructDeclaration {
+            attributes: vec![],
+            name: "User".to_string(),
+            members: vec![
+                StructMember {
+                    data_type: DataType::Identifier(new_identifier("Int")),
+                    name: "id".to_string(),
+                    range: new_range(),
+                },
+                StructMember {
+                    data_type: DataType::Identifier(new_identifier("String")),
+                    name: "name".to_string(),
+                    range: new_range(),
+                },
+            ],
+            generics: vec![],
+            whiches: vec![],
+            range: new_range(),
+        };
+        assert_eq!(
+            s1.to_string(),
+            trim_left_margin(
+                "struct User {
+                    Int id
+                    String name
+                }
+                "
+            )
+        );
+
+        // 测试属性以及泛型
+        let s2 = MemberStructDeclaration {
+            attributes: vec![Attribute {
+                name: "packed".to_string(),
+                arguments: vec![],
+            }],
+            name: "Node".to_string(),
+            members: vec![StructMember {
+                data_type: DataType::Identifier(new_identifier("T")),
+                name: "value".to_string(),
+                range: new_range(),
+            }],
+            generics: vec![DataType::Identifier(new_identifier("T"))],
+            whiches: vec![],
+            range: new_range(),
+        };
+        assert_eq!(
+            s2.to_string(),
+            trim_left_margin(
+                "#[packed]
+                struct Node <T> {
+                    T value
+                }
+                "
+            )
+        );
     }
 
+    #[test]
     fn test_tuple_struct_declaration() {
-        // todo::
+        let s1 = TupleStructDeclaration {
+            attributes: vec![],
+            name: "Pair".to_string(),
+            members: vec![
+                DataType::Identifier(new_identifier("T")),
+                DataType::Identifier(new_identifier("Int")),
+            ],
+            generics: vec![DataType::Identifier(new_identifier("T"))],
+            whiches: vec![],
+            range: new_range(),
+        };
+        assert_eq!(s1.to_string(), "struct Pair <T> (T, Int)\n");
     }
 
+    #[test]
     fn test_empty_struct_declaration() {
-        // todo::
+        let s1 = EmptyStructDeclaration {
+            name: "Nothing".to_string(),
+            range: new_range(),
+        };
+        assert_eq!(s1.to_string(), "struct Nothing\n");
     }
 
+    #[test]
     fn test_union_declaration() {
-        // todo::
+        let s1 = UnionDeclaration {
+            name: "Shape".to_string(),
+            members: vec![
+                UnionMember::Struct(MemberStructDeclaration {
+                    attributes: vec![],
+                    name: "Rect".to_string(),
+                    members: vec![StructMember {
+                        data_type: DataType::Identifier(new_identifier("Int")),
+                        name: "width".to_string(),
+                        range: new_range(),
+                    }],
+                    generics: vec![],
+                    whiches: vec![],
+                    range: new_range(),
+                }),
+                UnionMember::Tuple(TupleStructDeclaration {
+                    attributes: vec![],
+                    name: "Circle".to_string(),
+                    members: vec![DataType::Identifier(new_identifier("T"))],
+                    generics: vec![],
+                    whiches: vec![],
+                    range: new_range(),
+                }),
+                UnionMember::Empty(EmptyStructDeclaration {
+                    name: "Point".to_string(),
+                    range: new_range(),
+                }),
+            ],
+            generics: vec![DataType::Identifier(new_identifier("T"))],
+            whiches: vec![],
+            range: new_range(),
+        };
+        assert_eq!(
+            s1.to_string(),
+            trim_left_margin(
+                "union Shape <T> {
+                    Rect {
+                        Int width
+                    }
+                    Circle (T)
+                    Point
+                }
+                "
+            )
+        );
     }
 
+    #[test]
     fn test_trait_declaration() {
-        // todo::
+        let s1 = TraitDeclaration {
+            name: "Display".to_string(),
+            generics: vec![],
+            supertraits: vec![DataType::Identifier(new_identifier("Debug"))],
+            associated_types: vec![AssociatedType {
+                name: "Output".to_string(),
+                object_type: None,
+                range: new_range(),
+            }],
+            whiches: vec![],
+            function_items: vec![
+                TraitFunctionItem::EmptyFunction(EmptyFunctionDeclaration {
+                    attributes: vec![],
+                    name: "show".to_string(),
+                    generics: vec![],
+                    parameters: vec![],
+                    return_data_type: Some(DataType::Identifier(new_identifier("String"))),
+                    whiches: vec![],
+                    range: new_range(),
+                }),
+                TraitFunctionItem::Function(FunctionDeclaration {
+                    attributes: vec![],
+                    name: "size".to_string(),
+                    generics: vec![],
+                    parameters: vec![],
+                    return_data_type: None,
+                    whiches: vec![],
+                    body: Expression::Literal(new_literal_integer(0)),
+                    range: new_range(),
+                }),
+            ],
+            range: new_range(),
+        };
+        assert_eq!(
+            s1.to_string(),
+            trim_left_margin(
+                "trait Display limit Debug type Output {
+                    empty function show () type String
+                    function size () = 0
+                }
+                "
+            )
+        );
     }
 
+    #[test]
     fn test_impl_statement() {
-        // todo::
+        let s1 = ImplStatement {
+            generics: vec![DataType::Identifier(new_identifier("T"))],
+            object: Identifier {
+                generics: vec![DataType::Identifier(new_identifier("T"))],
+                ..new_identifier("List")
+            },
+            inherit: new_identifier("Display"),
+            associated_types: vec![AssociatedType {
+                name: "Output".to_string(),
+                object_type: Some(new_identifier("String")),
+                range: new_range(),
+            }],
+            whiches: vec![],
+            function_items: vec![FunctionDeclaration {
+                attributes: vec![],
+                name: "size".to_string(),
+                generics: vec![],
+                parameters: vec![],
+                return_data_type: None,
+                whiches: vec![],
+                body: Expression::Literal(new_literal_integer(1)),
+                range: new_range(),
+            }],
+            range: new_range(),
+        };
+        assert_eq!(
+            s1.to_string(),
+            trim_left_margin(
+                "impl <T> List<T> trait Display type Output = String {
+                    function size () = 1
+                }
+                "
+            )
+        );
     }
 
+    #[test]
     fn test_alias_statement() {
-        // todo::
+        let s1 = AliasStatement {
+            attributes: vec![],
+            name: "Text".to_string(),
+            data_type: DataType::Identifier(new_identifier("String")),
+            generics: vec![],
+            range: new_range(),
+        };
+        assert_eq!(s1.to_string(), "alias Text = String\n");
+
+        let s2 = AliasStatement {
+            attributes: vec![],
+            name: "OkOnly".to_string(),
+            data_type: DataType::Identifier(Identifier {
+                dirs: vec![],
+                name: "Result".to_string(),
+                generics: vec![
+                    DataType::Identifier(new_identifier("T")),
+                    DataType::Identifier(Identifier {
+                        dirs: vec!["std".to_string()],
+                        name: "Unit".to_string(),
+                        generics: vec![],
+                        range: new_range(),
+                    }),
+                ],
+                range: new_range(),
+            }),
+            generics: vec![DataType::Identifier(new_identifier("T"))],
+            range: new_range(),
+        };
+        assert_eq!(s2.to_string(), "alias OkOnly <T> = Result<T, std::Unit>\n");
     }
 
     #[test]
@@ -3709,4 +6524,96 @@ mod tests {
         let s1 = Statement::Expression(e1);
         assert_eq!(s1.to_string(), "(1 + 2)\n");
     }
+
+    fn parse_program(source: &str) -> super::Program {
+        let token_details = crate::lexer::tokenize(source).unwrap();
+        match crate::parser::parse(&token_details).unwrap() {
+            super::Node::Program(program) => program,
+            _ => unreachable!(),
+        }
+    }
+
+    // 记录访问到的标识符以及模式表达式的数量
+    #[derive(Default)]
+    struct NameCollector {
+        names: Vec<String>,
+        patterns: usize,
+    }
+
+    impl super::Visitor for NameCollector {
+        fn visit_identifier(&mut self, v: &Identifier) {
+            self.names.push(v.name.clone());
+        }
+
+        fn visit_pattern(&mut self, pattern: &PatternExpression) {
+            self.patterns += 1;
+            super::walk_pattern(self, pattern);
+        }
+    }
+
+    #[test]
+    fn test_visitor() {
+        use super::Visitor;
+
+        let program = parse_program(
+            "function f(Int a, Int b = c) = a + b\n\
+            const d = User {id: e, name: g}\n\
+            let h = match i {\n\
+                case j @ [k, l]: k\n\
+                case in [m..n]: o\n\
+                default: p(q, r = s)\n\
+            }\n\
+            for let t = u {v.w[x]}\n\
+            impl Shape trait Draw {\n\
+                function draw(Int y) = y\n\
+            }",
+        );
+
+        let mut collector = NameCollector::default();
+        collector.visit_program(&program);
+        assert_eq!(collector.patterns, 2);
+
+        // 访问的顺序跟 `Expression::children` 一致
+        fn collect(expression: &Expression, names: &mut Vec<String>) {
+            if let Expression::Identifier(identifier) = expression {
+                names.push(identifier.name.clone());
+            }
+            for child in expression.children() {
+                collect(child, names);
+            }
+        }
+
+        let mut names = vec![];
+        for statement in &program.body {
+            for expression in statement.expressions() {
+                collect(expression, &mut names);
+            }
+        }
+        assert_eq!(collector.names, names);
+        assert_eq!(collector.names.first().map(|s| s.as_str()), Some("c"));
+        assert!(collector.names.contains(&"y".to_string()));
+    }
+
+    // 把标识符 `a` 改名为 `z`
+    struct Renamer;
+
+    impl super::MutVisitor for Renamer {
+        fn visit_identifier(&mut self, v: &mut Identifier) {
+            if v.name == "a" {
+                v.name = "z".to_string();
+            }
+        }
+    }
+
+    #[test]
+    fn test_mut_visitor() {
+        use super::MutVisitor;
+
+        let mut program = parse_program("let a = 1\nfunction f(Int b) = a + b\nwriteLine([a, b])");
+        Renamer.visit_program(&mut program);
+        assert_eq!(
+            program.to_string(),
+            "let z = 1\nfunction f (Int b) = (z + b)\n(writeLine)([z, b,])\n"
+        );
+    }
 }
@@ -0,0 +1,388 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 后端
+//
+// 编译流程的最后一步由后端完成：把 HIR（规范化之后的 AST，见 `normalize` 模块）
+// 降级（lower）为目标平台的产物（artifact），再把产物写入文件。
+//
+// 每个后端实现 `Backend` 特性，并注册到 `BackendRegistry`，编译时根据
+// `CompilerOptions::backend` 选择后端，所以第三方可以在不修改编译流程的情况下
+// 加入新的目标平台：
+//
+// ```ignore
+// let mut registry = BackendRegistry::with_builtin_backends();
+// registry.register(Box::new(MyBackend));
+//
+// let options = CompilerOptions { backend: "my".to_string(), ..CompilerOptions::default() };
+// let artifact = registry.lower(&hir, &options)?;
+// ```
+//
+// 内置的后端：
+//
+// - `vm`：XiaoXuan Core VM 的字节码；
+// - `wasm`：WebAssembly 模块；
+// - `c`：C 源代码。
+//
+// 注：
+// `vm` 后端目前启用全部优化把 HIR 降级为 IR（见 `ir::LowerOptions`），
+// 分配局部变量槽（见 `frame::allocate_ir_slots`）之后直接写入模块文件（见 `module` 模块），
+// `wasm` 以及 `c` 后端尚未实现代码生成（见 `docs/roadmap.md` 的 "后端"），
+// 目前只提供能力信息，降级时返回错误。
+
+use std::{fs, path::Path};
+
+use crate::{
+    ast::{Node, Program},
+    error::Error,
+    fold::BuildEnvironment,
+    frame,
+    ir::{self, LowerOptions},
+    layout::Target,
+    module::{self, ModuleFileError},
+    semantics::DEFAULT_ENTRY_NAME,
+    typed,
+};
+
+pub const DEFAULT_BACKEND: &str = "vm";
+
+// 后端的能力信息，编译流程根据能力信息决定是否需要进行某些转换
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    // 内存布局使用的目标平台（见 `layout` 模块），
+    // None 表示布局由后端自己决定（比如 C 后端由 C 编译器决定）
+    pub target: Option<Target>,
+
+    pub extension: &'static str, // 产物文件的扩展名
+    pub binary: bool,            // 产物是二进制数据还是文本
+    pub tail_call: bool,         // 目标平台是否支持尾调用，不支持时需要转换为循环
+}
+
+// 后端生成的产物
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    pub backend: String, // 生成产物的后端的名称
+    pub bytes: Vec<u8>,
+}
+
+pub trait Backend {
+    // 后端的名称，用于 `CompilerOptions::backend`，名称需要唯一
+    fn name(&self) -> &str;
+
+    fn capabilities(&self) -> Capabilities;
+
+    // 把 HIR 降级为产物
+    fn lower(&self, hir: &Program) -> Result<Artifact, Error>;
+
+    // 把产物写入文件
+    fn emit(&self, artifact: &Artifact, path: &Path) -> Result<(), Error> {
+        fs::write(path, &artifact.bytes).map_err(|e| {
+            Error::ParserError(format!(
+                "failed to write file \"{}\": {}",
+                path.to_string_lossy(),
+                e
+            ))
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompilerOptions {
+    pub backend: String,
+
+    // 构建环境，程序通过 `env("NAME")` 表达式读取（见 `fold::BuildEnvironment`）
+    pub environment: BuildEnvironment,
+
+    // 可执行程序的入口函数的名称（见 `semantics::check_entry_point`），
+    // None 表示编译为库，不需要入口函数
+    pub entry: Option<String>,
+}
+
+impl Default for CompilerOptions {
+    fn default() -> Self {
+        Self {
+            backend: DEFAULT_BACKEND.to_string(),
+            environment: BuildEnvironment::default(),
+            entry: Some(DEFAULT_ENTRY_NAME.to_string()),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BackendRegistry {
+    backends: Vec<Box<dyn Backend>>,
+}
+
+impl BackendRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 已注册内置后端的注册表
+    pub fn with_builtin_backends() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(VmBackend));
+        registry.register(Box::new(WasmBackend));
+        registry.register(Box::new(CBackend));
+        registry
+    }
+
+    // 注册一个后端，替换同名的后端
+    pub fn register(&mut self, backend: Box<dyn Backend>) {
+        self.backends.retain(|b| b.name() != backend.name());
+        self.backends.push(backend);
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.backends.iter().map(|b| b.name()).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Backend> {
+        self.backends
+            .iter()
+            .find(|b| b.name() == name)
+            .map(|b| b.as_ref())
+    }
+
+    // 选择编译选项指定的后端
+    pub fn select(&self, options: &CompilerOptions) -> Result<&dyn Backend, Error> {
+        self.get(&options.backend).ok_or_else(|| {
+            Error::ParserError(format!(
+                "unknown backend \"{}\", available backends: {}",
+                options.backend,
+                self.names().join(", ")
+            ))
+        })
+    }
+
+    // 使用编译选项指定的后端降级 HIR
+    pub fn lower(&self, hir: &Program, options: &CompilerOptions) -> Result<Artifact, Error> {
+        self.select(options)?.lower(hir)
+    }
+}
+
+fn unsupported_backend(name: &str) -> Error {
+    Error::ParserError(format!("the \"{}\" backend is not supported yet", name))
+}
+
+struct VmBackend;
+
+impl Backend for VmBackend {
+    fn name(&self) -> &str {
+        "vm"
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            target: Some(Target::XiaoXuanVm),
+            extension: "ancm",
+            binary: true,
+            tail_call: true,
+        }
+    }
+
+    fn lower(&self, hir: &Program) -> Result<Artifact, Error> {
+        let mut node = Node::Program(hir.clone());
+        typed::annotate_types(&mut node);
+        let mut module = match node {
+            Node::Program(program) => ir::lower_with_options(&program, &LowerOptions::optimized())?,
+            _ => unreachable!(),
+        };
+
+        // 不同时活跃的局部变量共用同一个槽
+        for function in &mut module.functions {
+            frame::allocate_ir_slots(function);
+        }
+
+        let bytes = module::write(&module).map_err(|e| match e {
+            ModuleFileError::InvalidDataType { ref range, .. } => Error::SemanticError {
+                message: e.to_string(),
+                related: vec![],
+                range: range.clone(),
+            },
+            e => Error::ParserError(e.to_string()),
+        })?;
+
+        Ok(Artifact {
+            backend: self.name().to_string(),
+            bytes,
+        })
+    }
+}
+
+struct WasmBackend;
+
+impl Backend for WasmBackend {
+    fn name(&self) -> &str {
+        "wasm"
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            target: Some(Target::Wasm32),
+            extension: "wasm",
+            binary: true,
+            tail_call: false,
+        }
+    }
+
+    fn lower(&self, _hir: &Program) -> Result<Artifact, Error> {
+        Err(unsupported_backend(self.name()))
+    }
+}
+
+struct CBackend;
+
+impl Backend for CBackend {
+    fn name(&self) -> &str {
+        "c"
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            target: None,
+            extension: "c",
+            binary: false,
+            tail_call: false,
+        }
+    }
+
+    fn lower(&self, _hir: &Program) -> Result<Artifact, Error> {
+        Err(unsupported_backend(self.name()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Node, Program, Statement},
+        error::Error,
+        ir::Expr,
+        layout::Target,
+        lexer, module, parser,
+    };
+
+    use super::{Artifact, Backend, BackendRegistry, Capabilities, CompilerOptions};
+
+    // 输出函数名称列表的后端，用于测试第三方后端
+    struct OutlineBackend;
+
+    impl Backend for OutlineBackend {
+        fn name(&self) -> &str {
+            "outline"
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                target: None,
+                extension: "txt",
+                binary: false,
+                tail_call: true,
+            }
+        }
+
+        fn lower(&self, hir: &Program) -> Result<Artifact, Error> {
+            let names: Vec<&str> = hir
+                .body
+                .iter()
+                .filter_map(|statement| match statement {
+                    Statement::FunctionDeclaration(f) => Some(f.name.as_str()),
+                    _ => None,
+                })
+                .collect();
+
+            Ok(Artifact {
+                backend: self.name().to_string(),
+                bytes: names.join("\n").into_bytes(),
+            })
+        }
+    }
+
+    fn parse(source: &str) -> Program {
+        let token_details = lexer::tokenize(source).unwrap();
+        match parser::parse(&token_details).unwrap() {
+            Node::Program(program) => program,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_builtin_backends() {
+        let registry = BackendRegistry::with_builtin_backends();
+        assert_eq!(registry.names(), vec!["vm", "wasm", "c"]);
+
+        let backend = registry.select(&CompilerOptions::default()).unwrap();
+        assert_eq!(backend.name(), "vm");
+        assert_eq!(backend.capabilities().target, Some(Target::XiaoXuanVm));
+
+        // `vm` 后端输出模块文件
+        let program = parse("function f(Int a) = a");
+        let artifact = registry
+            .lower(&program, &CompilerOptions::default())
+            .unwrap();
+        assert_eq!(artifact.backend, "vm");
+        let module = module::read(&artifact.bytes).unwrap();
+        assert_eq!(module.functions[0].name, "f");
+
+        // 模块文件里的局部变量为分配之后的槽
+        let program = parse("function f(Int a) = {\nlet x = a + 1\nlet y = x * 2\ny\n}");
+        let artifact = registry
+            .lower(&program, &CompilerOptions::default())
+            .unwrap();
+        let module = module::read(&artifact.bytes).unwrap();
+        assert_eq!(module.functions[0].locals.len(), 1);
+
+        // 循环不变量移到了循环之前
+        let program = parse("function f(Int a, List<Int> xs) = each x in xs {x + a * 2}");
+        let artifact = registry
+            .lower(&program, &CompilerOptions::default())
+            .unwrap();
+        let module = module::read(&artifact.bytes).unwrap();
+        assert!(matches!(
+            &module.functions[0].body,
+            Expr::Block(body) if matches!(body[1], Expr::If(..))
+        ));
+
+        let options = CompilerOptions {
+            backend: "wasm".to_string(),
+            ..CompilerOptions::default()
+        };
+        assert!(registry.lower(&program, &options).is_err());
+
+        let options = CompilerOptions {
+            backend: "llvm".to_string(),
+            ..CompilerOptions::default()
+        };
+        assert!(registry.select(&options).is_err());
+    }
+
+    #[test]
+    fn test_third_party_backend() {
+        let mut registry = BackendRegistry::with_builtin_backends();
+        registry.register(Box::new(OutlineBackend));
+
+        let options = CompilerOptions {
+            backend: "outline".to_string(),
+            ..CompilerOptions::default()
+        };
+        let program = parse("function f(Int a) = a\nfunction g(Int b) = b");
+        let artifact = registry.lower(&program, &options).unwrap();
+        assert_eq!(artifact.backend, "outline");
+        assert_eq!(artifact.bytes, b"f\ng");
+
+        // 使用默认的 `emit` 写入文件
+        let path = std::env::temp_dir().join("xiaoxuan_backend_test.txt");
+        registry
+            .select(&options)
+            .unwrap()
+            .emit(&artifact, &path)
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"f\ng");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
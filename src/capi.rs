@@ -0,0 +1,249 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// C 语言接口
+//
+// 供编辑器以及其他语言（通过动态库）嵌入词法分析器和语法分析器，
+// 对应的 C 头文件位于 `include/xuan.h`。
+//
+// 所有函数均返回一个在堆上分配的 XuanResult，
+// 调用者使用完毕之后必须调用 `xuan_free_result` 释放，
+// 不能使用 C 的 `free` 函数释放结果或者结果当中的字符串。
+//
+// 注：
+// 本模块的结构体和状态码属于稳定的二进制接口，
+// 只能在结构体末尾追加新的成员，不能修改已有成员的顺序和含义。
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use crate::{error::Error, json, lexer, parser};
+
+// 状态码
+pub const XUAN_OK: i32 = 0;
+pub const XUAN_ERROR_LEXER: i32 = 1;
+pub const XUAN_ERROR_PARSER: i32 = 2;
+pub const XUAN_ERROR_INVALID_ARGUMENT: i32 = 3; // 空指针或者源代码不是有效的 UTF-8 字符串
+pub const XUAN_ERROR_INTERNAL: i32 = 4; // 编译器内部错误
+
+#[repr(C)]
+pub struct XuanError {
+    pub code: i32,
+    pub message: *mut c_char, // 当 code 为 XUAN_OK 时为空指针
+    pub file_id: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[repr(C)]
+pub struct XuanResult {
+    pub json: *mut c_char, // 当发生错误时为空指针
+    pub error: XuanError,
+}
+
+/// 对源代码进行词法分析，以 JSON 数组的格式返回所有 Token。
+///
+/// # Safety
+///
+/// `source` 必须为空指针，或者指向一个以 NUL 结尾的字符串。
+#[no_mangle]
+pub unsafe extern "C" fn xuan_tokenize(source: *const c_char) -> *mut XuanResult {
+    let result = match read_source(source) {
+        Ok(text) => run(|| {
+            let token_details = lexer::tokenize(text)?;
+            Ok(serde_json::to_string(&token_details).unwrap())
+        }),
+        Err(result) => result,
+    };
+
+    Box::into_raw(Box::new(result))
+}
+
+/// 对源代码进行语法分析，以 JSON 文档的格式返回 AST（格式见 `docs/ast_json_schema.md`）。
+///
+/// # Safety
+///
+/// `source` 必须为空指针，或者指向一个以 NUL 结尾的字符串。
+#[no_mangle]
+pub unsafe extern "C" fn xuan_parse_to_json(source: *const c_char) -> *mut XuanResult {
+    let result = match read_source(source) {
+        Ok(text) => run(|| {
+            let token_details = lexer::tokenize(text)?;
+            let node = parser::parse(&token_details)?;
+            Ok(json::to_json(&node))
+        }),
+        Err(result) => result,
+    };
+
+    Box::into_raw(Box::new(result))
+}
+
+/// 释放 `xuan_tokenize` 和 `xuan_parse_to_json` 返回的结果。
+///
+/// # Safety
+///
+/// `result` 必须为空指针，或者是由本库函数返回且尚未释放的结果。
+#[no_mangle]
+pub unsafe extern "C" fn xuan_free_result(result: *mut XuanResult) {
+    if result.is_null() {
+        return;
+    }
+
+    let result = Box::from_raw(result);
+    free_string(result.json);
+    free_string(result.error.message);
+}
+
+unsafe fn read_source<'a>(source: *const c_char) -> Result<&'a str, XuanResult> {
+    if source.is_null() {
+        return Err(new_error_result(
+            XUAN_ERROR_INVALID_ARGUMENT,
+            "source is a null pointer",
+        ));
+    }
+
+    CStr::from_ptr(source).to_str().map_err(|_| {
+        new_error_result(
+            XUAN_ERROR_INVALID_ARGUMENT,
+            "source is not a valid UTF-8 string",
+        )
+    })
+}
+
+// 运行词法分析或者语法分析，并把结果或者错误转换为 XuanResult
+//
+// 不能让 panic 越过 C 接口的边界，所以这里捕获 panic 并转换为内部错误。
+fn run(f: impl FnOnce() -> Result<String, Error>) -> XuanResult {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(json)) => XuanResult {
+            json: new_c_string(&json),
+            error: XuanError {
+                code: XUAN_OK,
+                message: ptr::null_mut(),
+                file_id: 0,
+                start: 0,
+                end: 0,
+            },
+        },
+        Ok(Err(error)) => {
+            let code = if error.is_lexer_error() {
+                XUAN_ERROR_LEXER
+            } else {
+                XUAN_ERROR_PARSER
+            };
+
+            let mut result = new_error_result(code, &error.to_string());
+            if let Some(location) = error.location() {
+                result.error.file_id = location.file_id;
+                result.error.start = location.start;
+                result.error.end = location.end;
+            }
+            result
+        }
+        Err(_) => new_error_result(XUAN_ERROR_INTERNAL, "internal compiler error"),
+    }
+}
+
+fn new_error_result(code: i32, message: &str) -> XuanResult {
+    XuanResult {
+        json: ptr::null_mut(),
+        error: XuanError {
+            code,
+            message: new_c_string(message),
+            file_id: 0,
+            start: 0,
+            end: 0,
+        },
+    }
+}
+
+fn new_c_string(text: &str) -> *mut c_char {
+    // JSON 以及错误信息当中的 NUL 字符均已被转义，这里仅作保险处理
+    let bytes: Vec<u8> = text.bytes().filter(|b| *b != 0).collect();
+    CString::new(bytes).unwrap().into_raw()
+}
+
+unsafe fn free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{CStr, CString};
+
+    use super::{
+        xuan_free_result, xuan_parse_to_json, xuan_tokenize, XUAN_ERROR_INVALID_ARGUMENT,
+        XUAN_ERROR_LEXER, XUAN_ERROR_PARSER, XUAN_OK,
+    };
+
+    #[test]
+    fn test_tokenize() {
+        let source = CString::new("1+2").unwrap();
+
+        unsafe {
+            let r1 = xuan_tokenize(source.as_ptr());
+            assert_eq!((*r1).error.code, XUAN_OK);
+            assert!((*r1).error.message.is_null());
+
+            let json = CStr::from_ptr((*r1).json).to_str().unwrap();
+            assert!(json.starts_with('['));
+            assert!(json.contains("\"Plus\""));
+            xuan_free_result(r1);
+        }
+
+        let invalid = CString::new("1 $ 2").unwrap();
+
+        unsafe {
+            let r2 = xuan_tokenize(invalid.as_ptr());
+            assert_eq!((*r2).error.code, XUAN_ERROR_LEXER);
+            assert!((*r2).json.is_null());
+            assert!(!(*r2).error.message.is_null());
+            xuan_free_result(r2);
+        }
+    }
+
+    #[test]
+    fn test_parse_to_json() {
+        let source = CString::new("let a = 1 + 2").unwrap();
+
+        unsafe {
+            let r1 = xuan_parse_to_json(source.as_ptr());
+            assert_eq!((*r1).error.code, XUAN_OK);
+
+            let json = CStr::from_ptr((*r1).json).to_str().unwrap();
+            assert!(json.contains("\"Program\""));
+            assert!(json.contains("\"LetExpression\""));
+            xuan_free_result(r1);
+        }
+
+        let invalid = CString::new("let = 1").unwrap();
+
+        unsafe {
+            let r2 = xuan_parse_to_json(invalid.as_ptr());
+            assert_eq!((*r2).error.code, XUAN_ERROR_PARSER);
+            xuan_free_result(r2);
+        }
+    }
+
+    #[test]
+    fn test_invalid_argument() {
+        unsafe {
+            let r1 = xuan_parse_to_json(std::ptr::null());
+            assert_eq!((*r1).error.code, XUAN_ERROR_INVALID_ARGUMENT);
+            xuan_free_result(r1);
+
+            // 释放空指针是安全的
+            xuan_free_result(std::ptr::null_mut());
+        }
+    }
+}
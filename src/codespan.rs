@@ -0,0 +1,164 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 跟 codespan-reporting 的集成
+//
+// 将 AST 的 Range、Token 的 Location 以及错误转换为 codespan-reporting 的
+// Label 和 Diagnostic，以便下游工具直接使用 codespan-reporting 渲染源代码片段，
+// 而不必自己实现。
+//
+// Label 和 Diagnostic 的文件 id 即 Range/Location 里的 `file_id`，
+// 调用者需要使用相同的 id 把源文件加入到 codespan-reporting 的 `Files` 里。
+//
+// e.g.
+//
+// ```rust
+// let mut files = SimpleFiles::new();
+// let file_id = files.add("main.xuan", source);  // 第一个文件的 id 为 0
+// let diagnostic = codespan::error_to_diagnostic(&error);
+// let text = term::emit_into_string(&Config::default(), &files, &diagnostic)?;
+// ```
+
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+
+use crate::{ast::Range, error::Error, token::Location};
+
+impl From<&Range> for std::ops::Range<usize> {
+    fn from(range: &Range) -> Self {
+        range.start..range.end
+    }
+}
+
+impl From<&Location> for std::ops::Range<usize> {
+    fn from(location: &Location) -> Self {
+        location.start..location.end
+    }
+}
+
+// 主要标签，用于标示错误发生的位置
+pub fn primary_label(range: &Range) -> Label<usize> {
+    Label::primary(range.file_id, range)
+}
+
+// 次要标签，用于标示跟错误相关的其他位置
+pub fn secondary_label(range: &Range) -> Label<usize> {
+    Label::secondary(range.file_id, range)
+}
+
+// Token 位置的主要标签
+pub fn location_label(location: &Location) -> Label<usize> {
+    Label::primary(location.file_id, location)
+}
+
+pub fn error_to_diagnostic(error: &Error) -> Diagnostic<usize> {
+    let diagnostic = Diagnostic::error()
+        .with_code(error.code())
+        .with_message(error.to_string());
+
+    // 带有位置信息的错误，附加一个指向错误位置的标签
+    let diagnostic = match error.range() {
+        Some(range) => diagnostic.with_labels(vec![primary_label(&range)]),
+        None => diagnostic,
+    };
+
+    // 相关的位置，比如没有闭合的开始括号
+    let diagnostic = match error.note() {
+        Some((message, location)) => {
+            diagnostic.with_labels(vec![
+                Label::secondary(location.file_id, location).with_message(message)
+            ])
+        }
+        None => diagnostic,
+    };
+
+    // 相关的位置，比如调用重载函数时的各个候选函数
+    let diagnostic = diagnostic.with_labels(
+        error
+            .related()
+            .iter()
+            .map(|(message, range)| secondary_label(range).with_message(message))
+            .collect(),
+    );
+
+    match error.suggestion() {
+        Some(suggestion) => diagnostic.with_notes(vec![format!("help: {}", suggestion.message)]),
+        None => diagnostic,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codespan_reporting::{
+        diagnostic::LabelStyle,
+        files::SimpleFiles,
+        term::{self, Config},
+    };
+
+    use crate::{ast::Range, error::Error, token::Location};
+
+    use super::{error_to_diagnostic, location_label, primary_label, secondary_label};
+
+    #[test]
+    fn test_labels() {
+        let r1 = Range {
+            file_id: 1,
+            start: 4,
+            end: 7,
+        };
+
+        let l1 = primary_label(&r1);
+        assert_eq!(l1.style, LabelStyle::Primary);
+        assert_eq!(l1.file_id, 1);
+        assert_eq!(l1.range, 4..7);
+
+        let l2 = secondary_label(&r1);
+        assert_eq!(l2.style, LabelStyle::Secondary);
+
+        let l3 = location_label(&Location {
+            file_id: 2,
+            start: 0,
+            end: 3,
+            line: 1,
+            column: 1,
+        });
+        assert_eq!(l3.file_id, 2);
+        assert_eq!(l3.range, 0..3);
+    }
+
+    #[test]
+    fn test_render() {
+        let mut files = SimpleFiles::new();
+        let file_id = files.add("main.xuan", "let a = 1 $ 2\n");
+
+        let d1 = error_to_diagnostic(&Error::LexerError("invalid char '$'".to_string()))
+            .with_labels(vec![primary_label(&Range {
+                file_id,
+                start: 10,
+                end: 11,
+            })
+            .with_message("unexpected char")]);
+
+        let config = Config {
+            chars: term::Chars::ascii(),
+            ..Default::default()
+        };
+
+        let text = term::emit_into_string(&config, &files, &d1).unwrap();
+        assert_eq!(
+            text,
+            "\
+error[E0001]: invalid char '$'
+  --> main.xuan:1:11
+  |
+1 | let a = 1 $ 2
+  |           ^ unexpected char
+
+"
+        );
+    }
+}
@@ -0,0 +1,1221 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 紧凑格式的输出
+//
+// 直接根据 AST 输出紧凑（压缩）格式的源代码，用于嵌入生成的代码，以及语法的往返（round-trip）测试：
+//
+// - 语句之间以及表达式块里的表达式之间使用分号分隔（分号跟换行符是同一种 Token），
+//   所以整个程序输出为一行；
+// - 只在两个 Token 直接连接会改变词法分析的结果时才插入空格，比如两个单词之间，
+//   以及 `>` 和 `=` 之间；
+// - 根据运算符的优先级以及结合方向，只在需要时才为子表达式加上括号；
+// - 浮点数保持浮点数的格式（比如 `1.0`），字符串等字面量里的内容保持原样。
+//
+// 语法分析器产生的 AST 输出之后，重新进行语法分析总是得到相同的 AST。
+
+use crate::ast::{
+    AnonymousFunction, AssociatedType, Attribute, BinaryOperator, DataType,
+    EmptyFunctionDeclaration, Expression, FunctionDeclaration, Identifier, Literal, Map,
+    MemberExpression, MemberStructDeclaration, NamePath, NamePathItem, Node, PatternExpression,
+    PatternFunctionDeclaration, Sign, Statement, TraitFunctionItem, Tuple, TupleStructDeclaration,
+    UnaryOperator, UnionMember, WhichEntry,
+};
+
+// 以紧凑的格式输出 AST
+pub fn to_compact_string(node: &Node) -> String {
+    let mut emitter = Emitter::default();
+    match node {
+        Node::Program(program) => emitter.statements(&program.body),
+        Node::Statement(statement) => emitter.statement(statement),
+        Node::Expression(expression) => emitter.operand(expression, OPEN, false),
+    }
+    emitter.output
+}
+
+// 表达式的优先级，数值越大优先级越高（结合得越紧密）
+//
+// 关键字开始的表达式（以及匿名函数、函数签名）只能出现在完整表达式的位置，
+// 而且它们的末尾会吸收后面的所有运算，所以优先级最低。
+const OPEN: u8 = 0;
+const NAMED_OPERATOR: u8 = 6;
+const CONCAT: u8 = 7;
+const COMBINE: u8 = 12;
+const CAST: u8 = 13;
+const NEGATIVE: u8 = 14;
+const UNWRAP: u8 = 15;
+const CALL: u8 = 16;
+const MEMBER: u8 = 17;
+const PRIMARY: u8 = 18;
+
+fn binary_level(operator: &BinaryOperator) -> u8 {
+    match operator {
+        BinaryOperator::Pipe => 1,
+        BinaryOperator::LogicOr => 2,
+        BinaryOperator::LogicAnd => 3,
+        BinaryOperator::Equal | BinaryOperator::NotEqual => 4,
+        BinaryOperator::GreaterThan
+        | BinaryOperator::GreaterThanOrEqual
+        | BinaryOperator::LessThan
+        | BinaryOperator::LessThanOrEqual => 5,
+        BinaryOperator::Concat => CONCAT,
+        BinaryOperator::Plus | BinaryOperator::Minus => 8,
+        BinaryOperator::Asterisk | BinaryOperator::Slash => 9,
+        BinaryOperator::OptionalOr => 10,
+        BinaryOperator::OptionalAnd => 11,
+        BinaryOperator::Combine => COMBINE,
+    }
+}
+
+fn level(expression: &Expression) -> u8 {
+    match expression {
+        Expression::BinaryExpression(b) => binary_level(&b.operator),
+        Expression::NamedOperatorExpression(_) => NAMED_OPERATOR,
+        Expression::UnaryExpression(u) => match u.operator {
+            UnaryOperator::Cast => CAST,
+            UnaryOperator::Minus => NEGATIVE,
+            UnaryOperator::Unwrap => UNWRAP,
+        },
+        Expression::FunctionCallExpression(_) | Expression::TupleConstructorExpression(_) => CALL,
+        Expression::MemberExpression(_) | Expression::SliceExpression(_) => MEMBER,
+        Expression::ConstructorExpression(_)
+        | Expression::Identifier(_)
+        | Expression::PrefixIdentifier(_)
+        | Expression::Ellipsis(_)
+        | Expression::Interval(_)
+        | Expression::Tuple(_)
+        | Expression::List(_)
+        | Expression::Map(_)
+        | Expression::Literal(_) => PRIMARY,
+        _ => OPEN,
+    }
+}
+
+// 表达式后面如果还有其他运算，是否会被表达式的末尾吸收，
+// 比如 `&` 的右侧是一个完整的表达式，所以 `(a & b) + c` 的括号不能省略
+fn is_open(expression: &Expression) -> bool {
+    match expression {
+        Expression::BinaryExpression(b) => b.operator == BinaryOperator::Combine,
+        _ => level(expression) == OPEN,
+    }
+}
+
+// 表达式最右侧（不加括号）的子表达式
+fn right_edge(expression: &Expression) -> Option<&Expression> {
+    match expression {
+        Expression::BinaryExpression(b) => Some(&b.right),
+        Expression::NamedOperatorExpression(n) => Some(&n.right),
+        Expression::UnaryExpression(u) if u.operator == UnaryOperator::Minus => Some(&u.operand),
+        Expression::LetExpression(l) => Some(&l.value),
+        Expression::NextExpression(n) => Some(&n.value),
+        Expression::AnonymousFunction(f) => Some(&f.body),
+        Expression::ForExpression(f) => Some(&f.body),
+        Expression::EachExpression(e) => Some(&e.body),
+        Expression::IfExpression(i) => Some(i.alternate.as_ref().unwrap_or(&i.consequent)),
+        _ => None,
+    }
+}
+
+// 表达式是否以标识符结尾，标识符后面紧跟着 `{` 会被当作结构体的实例化表达式
+fn ends_with_identifier(expression: &Expression) -> bool {
+    match expression {
+        Expression::Identifier(_)
+        | Expression::PrefixIdentifier(_)
+        | Expression::Sign(_)
+        | Expression::MemberExpression(MemberExpression::Property(_)) => true,
+        Expression::Ellipsis(e) => e.name.is_some(),
+        _ => right_edge(expression).is_some_and(ends_with_identifier),
+    }
+}
+
+// 表达式是否以没有 `else` 的 if 表达式结尾，后面的 `else` 会被这个 if 表达式吸收
+fn ends_with_dangling_if(expression: &Expression) -> bool {
+    match expression {
+        Expression::IfExpression(i) if i.alternate.is_none() => true,
+        _ => right_edge(expression).is_some_and(ends_with_dangling_if),
+    }
+}
+
+// 表达式是否以 `{` 开始，在 `then` 等关键字后面 `{` 会被当作表达式块
+fn starts_with_brace(expression: &Expression) -> bool {
+    match expression {
+        Expression::Map(_) => true,
+        Expression::BinaryExpression(b) => starts_with_brace(&b.left),
+        Expression::NamedOperatorExpression(n) => starts_with_brace(&n.left),
+        Expression::UnaryExpression(u) if u.operator != UnaryOperator::Minus => {
+            starts_with_brace(&u.operand)
+        }
+        Expression::FunctionCallExpression(c) => starts_with_brace(&c.callee),
+        Expression::MemberExpression(MemberExpression::Property(p)) => starts_with_brace(&p.object),
+        Expression::MemberExpression(MemberExpression::Index(i)) => starts_with_brace(&i.object),
+        Expression::SliceExpression(s) => starts_with_brace(&s.object),
+        _ => false,
+    }
+}
+
+// 比较表达式 `a < b` 的 `<` 紧跟在标识符后面时，有可能跟后面的 `>` 一起被当作泛型，
+// 比如 `f(a < b, c > d)`
+fn is_ambiguous_comparison(expression: &Expression) -> bool {
+    matches!(expression, Expression::BinaryExpression(b)
+        if b.operator == BinaryOperator::LessThan && ends_with_identifier(&b.left))
+}
+
+#[derive(Default, PartialEq)]
+enum Previous {
+    #[default]
+    Other,
+    Integer,
+    Number, // 浮点数、复数以及比特数
+}
+
+#[derive(Default)]
+struct Emitter {
+    output: String,
+    previous: Previous,
+}
+
+impl Emitter {
+    fn token(&mut self, text: &str) {
+        if let (Some(left), Some(right)) = (self.output.chars().last(), text.chars().next()) {
+            // 数字后面紧跟着 `.` 会被当作小数点（整数后面的范围符号 `..` 除外）
+            let is_decimal_point = right == '.'
+                && match self.previous {
+                    Previous::Integer => !text.starts_with(".."),
+                    Previous::Number => true,
+                    Previous::Other => false,
+                };
+
+            // 路径分隔符 `::` 后面的标识符不会被当作命名操作符
+            let is_path = self.output.ends_with("::") && (right.is_alphabetic() || right == '_');
+
+            if is_decimal_point || (needs_space(left, right) && !is_path) {
+                self.output.push(' ');
+            }
+        }
+
+        self.output.push_str(text);
+        self.previous = Previous::Other;
+    }
+
+    fn number(&mut self, text: &str, previous: Previous) {
+        self.token(text);
+        self.previous = previous;
+    }
+
+    fn separated<T>(&mut self, items: &[T], separator: &str, mut f: impl FnMut(&mut Self, &T)) {
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                self.token(separator);
+            }
+            f(self, item);
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        self.separated(statements, ";", Self::statement);
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::FunctionDeclaration(f) => self.function(f),
+            Statement::EmptyFunctionDeclaration(f) => self.empty_function(f),
+            Statement::PatternFunctionDeclaration(f) => self.pattern_function(f),
+            Statement::NamespaceStatement(n) => {
+                self.token("namespace");
+                self.token(&n.dirs.join("::"));
+            }
+            Statement::UseStatement(u) => {
+                self.token("use");
+                self.name_path(&u.name_path);
+            }
+            Statement::ConstDeclaration(c) => {
+                self.attributes(&c.attributes);
+                self.token("const");
+                if let Some(d) = &c.data_type {
+                    self.data_type(d);
+                }
+                self.token(&c.name);
+                self.token("=");
+                self.operand(&c.value, OPEN, false);
+            }
+            Statement::MemberStructDeclaration(s) => {
+                self.attributes(&s.attributes);
+                self.token("struct");
+                self.member_struct(s);
+            }
+            Statement::TupleStructDeclaration(s) => {
+                self.attributes(&s.attributes);
+                self.token("struct");
+                self.tuple_struct(s);
+            }
+            Statement::EmptyStructDeclaration(s) => {
+                self.token("struct");
+                self.token(&s.name);
+            }
+            Statement::UnionDeclaration(u) => {
+                self.token("union");
+                self.token(&u.name);
+                self.generics(&u.generics);
+                self.whiches(&u.whiches);
+                self.token("{");
+                self.separated(&u.members, ";", |e, member| match member {
+                    UnionMember::Struct(s) => e.member_struct(s),
+                    UnionMember::Tuple(s) => e.tuple_struct(s),
+                    UnionMember::Empty(s) => e.token(&s.name),
+                });
+                self.token("}");
+            }
+            Statement::TraitDeclaration(t) => {
+                self.token("trait");
+                self.token(&t.name);
+                self.generics(&t.generics);
+                if !t.supertraits.is_empty() {
+                    self.token("limit");
+                    self.separated(&t.supertraits, "+", Self::data_type);
+                }
+                self.associated_types(&t.associated_types);
+                self.whiches(&t.whiches);
+                self.token("{");
+                self.separated(&t.function_items, ";", |e, item| match item {
+                    TraitFunctionItem::Function(f) => e.function(f),
+                    TraitFunctionItem::EmptyFunction(f) => e.empty_function(f),
+                });
+                self.token("}");
+            }
+            Statement::ImplStatement(i) => {
+                self.token("impl");
+                self.generics(&i.generics);
+                self.identifier(&i.object);
+                self.token("trait");
+                self.identifier(&i.inherit);
+                self.associated_types(&i.associated_types);
+                self.whiches(&i.whiches);
+                self.token("{");
+                self.separated(&i.function_items, ";", Self::function);
+                self.token("}");
+            }
+            Statement::AliasStatement(a) => {
+                self.attributes(&a.attributes);
+                self.token("alias");
+                self.token(&a.name);
+                self.generics(&a.generics);
+                self.token("=");
+                self.data_type(&a.data_type);
+            }
+            Statement::Expression(e) => self.operand(e, OPEN, false),
+            Statement::Error(e) => {
+                for token in &e.tokens {
+                    self.token(&token.to_string());
+                }
+            }
+        }
+    }
+
+    fn attributes(&mut self, attributes: &[Attribute]) {
+        for attribute in attributes {
+            // 属性是一个 Token，其中的参数在语法分析时重新进行词法分析
+            let mut inner = Emitter::default();
+            inner.token(&attribute.name);
+            if !attribute.arguments.is_empty() {
+                inner.token("(");
+                inner.separated(&attribute.arguments, ",", |e, (name, value)| {
+                    if !name.is_empty() {
+                        e.token(name);
+                    }
+                    if let Some(v) = value {
+                        if !name.is_empty() {
+                            e.token("=");
+                        }
+                        e.operand(v, OPEN, false);
+                    }
+                });
+                inner.token(")");
+            }
+            self.token(&format!("#[{}]", inner.output));
+        }
+    }
+
+    fn function(&mut self, f: &FunctionDeclaration) {
+        self.attributes(&f.attributes);
+        self.token("function");
+        self.token(&f.name);
+        self.generics(&f.generics);
+        self.token("(");
+        self.separated(&f.parameters, ",", |e, p| {
+            e.data_type(&p.data_type);
+            e.token(&p.name);
+            if let Some(v) = &p.value {
+                e.token("=");
+                e.operand(v, OPEN, false);
+            }
+        });
+        self.token(")");
+        self.return_data_type(f.return_data_type.as_ref());
+        self.whiches(&f.whiches);
+        self.function_body(&f.body);
+    }
+
+    fn empty_function(&mut self, f: &EmptyFunctionDeclaration) {
+        self.attributes(&f.attributes);
+        self.token("empty");
+        self.token("function");
+        self.token(&f.name);
+        self.generics(&f.generics);
+        self.token("(");
+        self.separated(&f.parameters, ",", |e, p| {
+            e.data_type(&p.data_type);
+            e.token(&p.name);
+        });
+        self.token(")");
+        self.return_data_type(f.return_data_type.as_ref());
+        self.whiches(&f.whiches);
+    }
+
+    fn pattern_function(&mut self, f: &PatternFunctionDeclaration) {
+        self.token("pattern");
+        self.token("function");
+        self.token(&f.name);
+        self.generics(&f.generics);
+        self.token("(");
+        self.separated(&f.parameters, ",", |e, p| {
+            if let Some(d) = &p.data_type {
+                e.data_type(d);
+            }
+            e.pattern_parts(&p.variable, &p.pattern, &p.only, &p.where_exp);
+        });
+        self.token(")");
+        self.return_data_type(f.return_data_type.as_ref());
+        self.whiches(&f.whiches);
+        if let Some(e) = &f.only {
+            self.token("only");
+            self.clause(e, is_implicit_block(&f.body));
+        }
+        self.function_body(&f.body);
+    }
+
+    // 函数主体，隠式 do 表达式直接跟在函数签名后面，其他表达式需要加上 `=`
+    fn function_body(&mut self, body: &Expression) {
+        if !is_implicit_block(body) {
+            self.token("=");
+        }
+        self.clause(body, false);
+    }
+
+    fn return_data_type(&mut self, data_type: Option<&DataType>) {
+        if let Some(d) = data_type {
+            self.token("type");
+            self.data_type(d);
+        }
+    }
+
+    fn generics(&mut self, generics: &[DataType]) {
+        if !generics.is_empty() {
+            self.token("<");
+            self.separated(generics, ",", Self::data_type);
+            self.token(">");
+        }
+    }
+
+    fn whiches(&mut self, whiches: &[WhichEntry]) {
+        if whiches.is_empty() {
+            return;
+        }
+
+        self.token("which");
+        self.token("{");
+        self.separated(whiches, ";", |e, which| match which {
+            WhichEntry::Type(t) => {
+                e.token(&t.name);
+                e.token(":");
+                e.data_type(&t.data_type);
+            }
+            WhichEntry::Limit(l) => {
+                e.token(&l.name);
+                e.token(":");
+                e.token("limit");
+                e.separated(&l.data_types, "+", Self::data_type);
+            }
+        });
+        self.token("}");
+    }
+
+    // 只有一个关联类型时省略括号，比如 `type Item` 以及 `type (Item, Error=String)`
+    fn associated_types(&mut self, associated_types: &[AssociatedType]) {
+        let item = |e: &mut Self, a: &AssociatedType| {
+            e.token(&a.name);
+            if let Some(t) = &a.object_type {
+                e.token("=");
+                e.identifier(t);
+            }
+        };
+
+        match associated_types {
+            [] => {}
+            [a] => {
+                self.token("type");
+                item(self, a);
+            }
+            _ => {
+                self.token("type");
+                self.token("(");
+                self.separated(associated_types, ",", item);
+                self.token(")");
+            }
+        }
+    }
+
+    fn member_struct(&mut self, s: &MemberStructDeclaration) {
+        self.token(&s.name);
+        self.generics(&s.generics);
+        self.whiches(&s.whiches);
+        self.token("{");
+        self.separated(&s.members, ";", |e, m| {
+            e.data_type(&m.data_type);
+            e.token(&m.name);
+        });
+        self.token("}");
+    }
+
+    fn tuple_struct(&mut self, s: &TupleStructDeclaration) {
+        self.token(&s.name);
+        self.generics(&s.generics);
+        self.token("(");
+        self.separated(&s.members, ",", Self::data_type);
+        self.token(")");
+        self.whiches(&s.whiches);
+    }
+
+    fn name_path(&mut self, name_path: &NamePath) {
+        self.separated(&name_path.directories, "::", |e, item| match item {
+            NamePathItem::Name(name) => e.token(name),
+            NamePathItem::Children(name, children) => {
+                e.token(name);
+                e.token("::");
+                e.token("{");
+                e.separated(children, ",", Self::name_path);
+                e.token("}");
+            }
+        });
+
+        if let Some(alias) = &name_path.alias {
+            self.token("as");
+            self.token(alias);
+        }
+    }
+
+    fn data_type(&mut self, data_type: &DataType) {
+        match data_type {
+            DataType::Identifier(i) => self.identifier(i),
+            DataType::Tuple(t) => self.tuple(t),
+            DataType::Sign(s) => self.sign(s),
+        }
+    }
+
+    fn identifier(&mut self, identifier: &Identifier) {
+        if identifier.dirs.is_empty() {
+            self.token(&identifier.name);
+        } else {
+            self.token(&format!(
+                "{}::{}",
+                identifier.dirs.join("::"),
+                identifier.name
+            ));
+        }
+        self.generics(&identifier.generics);
+    }
+
+    fn sign(&mut self, sign: &Sign) {
+        self.token("sign");
+        self.generics(&sign.generics);
+        self.token("(");
+        self.separated(&sign.parameters, ",", |e, p| {
+            e.data_type(&p.data_type);
+            if let Some(n) = &p.name {
+                e.token(n);
+            }
+        });
+        self.token(")");
+        self.return_data_type(sign.return_data_type.as_deref());
+        self.whiches(&sign.whiches);
+    }
+
+    fn anonymous_function(&mut self, f: &AnonymousFunction) {
+        self.token("fn");
+        self.token("(");
+        self.separated(&f.parameters, ",", |e, p| {
+            if let Some(d) = &p.data_type {
+                e.data_type(d);
+            }
+            e.token(&p.name);
+        });
+        self.token(")");
+        self.return_data_type(f.return_data_type.as_ref());
+        self.whiches(&f.whiches);
+        self.function_body(&f.body);
+    }
+
+    // 输出作为运算对象的表达式，优先级低于 `min_level` 时加上括号，
+    // `followed` 表示表达式后面还有同一个表达式的其他运算
+    fn operand(&mut self, expression: &Expression, min_level: u8, followed: bool) {
+        if level(expression) < min_level
+            || (followed && is_open(expression))
+            || is_ambiguous_comparison(expression)
+        {
+            self.parenthesized(expression);
+        } else {
+            self.expression(expression, followed);
+        }
+    }
+
+    fn parenthesized(&mut self, expression: &Expression) {
+        self.token("(");
+        self.expression(expression, false);
+        self.token(")");
+    }
+
+    // 输出 `then`、`else`、`where`、`only` 等关键字以及函数签名后面的表达式或者表达式块，
+    // 这些位置的 `{` 会被当作表达式块的开始，
+    // `before_brace` 表示表达式后面紧跟着表达式块或者 match 等表达式的主体
+    fn clause(&mut self, expression: &Expression, before_brace: bool) {
+        match expression {
+            Expression::BlockExpression(b) if !b.is_explicit => self.block(&b.body),
+            _ if starts_with_brace(expression) => self.parenthesized(expression),
+            _ => {
+                self.operand(expression, OPEN, false);
+                self.brace_separator(before_brace && ends_with_identifier(expression));
+            }
+        }
+    }
+
+    // 标识符后面紧跟着 `{` 会被当作结构体的实例化（即使标识符加上了括号），
+    // 而这些位置的 `{` 前面允许有空行，所以使用分号分隔
+    fn brace_separator(&mut self, is_required: bool) {
+        if is_required {
+            self.token(";");
+        }
+    }
+
+    // 输出 match、for 以及 each 的目标对象，以及随后的主体
+    //
+    // 单独一个标识符后面的 `{` 会被当作主体（而不是结构体的实例化），
+    // 主体不是表达式块时使用分号分隔，以防主体的开始（比如括号）被目标对象吸收
+    fn object_and_body(&mut self, object: &Expression, body: &Expression) {
+        if is_implicit_block(body) {
+            self.object(object);
+            self.block_of(body);
+        } else {
+            self.operand(object, OPEN, false);
+            self.token(";");
+            self.clause(body, false);
+        }
+    }
+
+    fn object(&mut self, object: &Expression) {
+        match object {
+            Expression::Identifier(i) => self.identifier(i),
+            _ => {
+                self.operand(object, OPEN, false);
+                self.brace_separator(ends_with_identifier(object));
+            }
+        }
+    }
+
+    fn block_of(&mut self, expression: &Expression) {
+        if let Expression::BlockExpression(b) = expression {
+            self.block(&b.body);
+        }
+    }
+
+    fn block(&mut self, body: &[Expression]) {
+        self.token("{");
+        self.separated(body, ";", |e, item| e.operand(item, OPEN, false));
+        self.token("}");
+    }
+
+    fn pattern_parts(
+        &mut self,
+        variable: &Option<String>,
+        pattern: &Option<Box<PatternExpression>>,
+        only: &Option<Box<Expression>>,
+        where_exp: &Option<Box<Expression>>,
+    ) {
+        if let Some(v) = variable {
+            self.token(v);
+            self.token("@");
+        }
+
+        if let Some(p) = pattern {
+            match p.as_ref() {
+                PatternExpression::Primary(e) => self.operand(e, CALL, false),
+                PatternExpression::In(e) => {
+                    self.token("in");
+                    self.operand(e, PRIMARY, false);
+                }
+                PatternExpression::Into(d, n) => {
+                    self.token("into");
+                    self.data_type(d);
+                    self.token(n);
+                }
+                PatternExpression::Regular(s, t) => {
+                    self.token("regular");
+                    self.string(s);
+                    self.tuple(t);
+                }
+                PatternExpression::Template(s) => {
+                    self.token("template");
+                    self.string(s);
+                }
+            }
+        }
+
+        // 先 only，后 where
+        if let Some(e) = only {
+            self.token("only");
+            self.clause(e, false);
+        }
+
+        if let Some(e) = where_exp {
+            self.token("where");
+            self.clause(e, false);
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression, followed: bool) {
+        match expression {
+            Expression::BlockExpression(b) => {
+                if b.is_explicit {
+                    self.token("do");
+                }
+                self.block(&b.body);
+            }
+            Expression::JoinExpression(j) => {
+                self.token("join");
+                self.block(&j.body);
+            }
+            Expression::LetExpression(l) => {
+                self.attributes(&l.attributes);
+                self.token("let");
+                match &l.data_type {
+                    Some(d) => {
+                        self.data_type(d);
+                        self.operand(&l.object, PRIMARY, true);
+                    }
+                    None => self.operand(&l.object, CALL, true),
+                }
+                self.token("=");
+                self.operand(&l.value, OPEN, false);
+            }
+            Expression::IfExpression(i) => {
+                self.token("if");
+                self.operand(&i.testing, OPEN, false);
+                if let Some(w) = &i.where_exp {
+                    self.token("where");
+                    self.clause(w, false);
+                }
+                self.token("then");
+                if i.alternate.is_some() && ends_with_dangling_if(&i.consequent) {
+                    self.parenthesized(&i.consequent);
+                } else {
+                    self.clause(&i.consequent, false);
+                }
+                if let Some(e) = &i.alternate {
+                    self.token("else");
+                    self.clause(e, false);
+                }
+            }
+            Expression::ForExpression(f) => {
+                let l = &f.initializer;
+                self.token("for");
+                self.token("let");
+                match &l.data_type {
+                    Some(d) => {
+                        self.data_type(d);
+                        self.operand(&l.object, PRIMARY, true);
+                    }
+                    None => self.operand(&l.object, CALL, true),
+                }
+                self.token("=");
+                self.object_and_body(&l.value, &f.body);
+            }
+            Expression::NextExpression(n) => {
+                self.token("next");
+                self.operand(&n.value, OPEN, false);
+            }
+            Expression::EachExpression(e) => {
+                self.token("each");
+                self.operand(&e.variable, CALL, true);
+                self.token("in");
+                self.object_and_body(&e.object, &e.body);
+            }
+            Expression::BranchExpression(b) => {
+                self.token("branch");
+                if let Some(w) = &b.where_exp {
+                    self.token("where");
+                    self.clause(w, true);
+                }
+                self.token("{");
+                self.separated(&b.cases, ";", |e, c| {
+                    e.token("case");
+                    e.operand(&c.testing, OPEN, false);
+                    if let Some(w) = &c.where_exp {
+                        e.token("where");
+                        e.clause(w, false);
+                    }
+                    e.token(":");
+                    e.clause(&c.consequent, false);
+                });
+                self.default_case(b.cases.is_empty(), b.default_exp.as_deref());
+                self.token("}");
+            }
+            Expression::MatchExpression(m) => {
+                self.token("match");
+                self.object(&m.object);
+                if let Some(w) = &m.where_exp {
+                    self.token("where");
+                    self.clause(w, true);
+                }
+                self.token("{");
+                self.separated(&m.cases, ";", |e, c| {
+                    e.token("case");
+                    e.pattern_parts(&c.variable, &c.pattern, &c.only, &c.where_exp);
+                    e.token(":");
+                    e.clause(&c.consequent, false);
+                });
+                self.default_case(m.cases.is_empty(), m.default_exp.as_deref());
+                self.token("}");
+            }
+            Expression::Sign(s) => self.sign(s),
+            Expression::BinaryExpression(b) => {
+                let level = binary_level(&b.operator);
+                if b.operator == BinaryOperator::Combine {
+                    // 从右向左结合，右侧是一个完整的表达式
+                    self.operand(&b.left, CAST, true);
+                    self.token(&b.operator.to_string());
+                    self.operand(&b.right, OPEN, followed);
+                } else {
+                    self.operand(&b.left, level, true);
+                    self.token(&b.operator.to_string());
+                    self.operand(&b.right, level + 1, followed);
+                }
+            }
+            Expression::NamedOperatorExpression(n) => {
+                // 命名操作符不能连续使用
+                self.operand(&n.left, CONCAT, true);
+                self.token(&format!(":{}:", n.name));
+                self.operand(&n.right, CONCAT, followed);
+            }
+            Expression::UnaryExpression(u) => match u.operator {
+                UnaryOperator::Cast => {
+                    self.operand(&u.operand, NEGATIVE, true);
+                    self.token("^");
+                }
+                UnaryOperator::Minus => {
+                    self.token("-");
+                    self.operand(&u.operand, UNWRAP, followed);
+                }
+                UnaryOperator::Unwrap => {
+                    self.operand(&u.operand, CALL, true);
+                    self.token("?");
+                }
+            },
+            Expression::FunctionCallExpression(c) => {
+                self.operand(&c.callee, CALL, true);
+                self.token("(");
+                self.separated(&c.arguments, ",", |e, a| {
+                    if let Some(n) = &a.name {
+                        e.token(n);
+                        e.token("=");
+                    }
+                    e.operand(&a.value, OPEN, false);
+                });
+                self.token(")");
+            }
+            Expression::MemberExpression(MemberExpression::Property(p)) => {
+                self.operand(&p.object, MEMBER, true);
+                self.token(".");
+                self.operand(&p.property, PRIMARY, false);
+            }
+            Expression::MemberExpression(MemberExpression::Index(i)) => {
+                self.operand(&i.object, MEMBER, true);
+                self.token("[");
+                self.operand(&i.index, OPEN, false);
+                self.token("]");
+            }
+            Expression::SliceExpression(s) => {
+                self.operand(&s.object, MEMBER, true);
+                self.token("[");
+                self.expression(&Expression::Interval(s.interval.clone()), false);
+                self.token("]");
+            }
+            Expression::ConstructorExpression(c) => {
+                self.identifier(&c.object);
+                self.map(&c.value);
+            }
+            Expression::TupleConstructorExpression(c) => {
+                self.identifier(&c.object);
+                self.token("(");
+                self.separated(&c.value.elements, ",", |e, item| {
+                    e.operand(item, OPEN, false)
+                });
+                self.token(")");
+            }
+            Expression::AnonymousFunction(f) => self.anonymous_function(f),
+            Expression::Identifier(i) => self.identifier(i),
+            Expression::PrefixIdentifier(p) => {
+                self.token("!");
+                self.identifier(&p.identifier);
+            }
+            Expression::Ellipsis(e) => {
+                self.token("...");
+                if let Some(n) = &e.name {
+                    self.token(n);
+                }
+            }
+            Expression::Interval(i) => {
+                self.operand(&i.from, OPEN, false);
+                self.token(if i.is_inclusive { "..=" } else { ".." });
+                if let Some(t) = &i.to {
+                    self.operand(t, OPEN, false);
+                }
+            }
+            Expression::Tuple(t) => self.tuple(t),
+            Expression::List(l) => {
+                self.token("[");
+                self.separated(&l.elements, ",", |e, item| e.operand(item, OPEN, false));
+                self.token("]");
+            }
+            Expression::Map(m) => self.map(m),
+            Expression::Literal(l) => self.literal(l),
+        }
+    }
+
+    fn default_case(&mut self, is_first: bool, default_exp: Option<&Expression>) {
+        if let Some(e) = default_exp {
+            if !is_first {
+                self.token(";");
+            }
+            self.token("default");
+            self.token(":");
+            self.clause(e, false);
+        }
+    }
+
+    // 单独一个元素的元组需要保留末尾的逗号，否则会被当作括号表达式
+    fn tuple(&mut self, tuple: &Tuple) {
+        self.token("(");
+        self.separated(&tuple.elements, ",", |e, item| e.operand(item, OPEN, false));
+        if tuple.elements.len() == 1 {
+            self.token(",");
+        }
+        self.token(")");
+    }
+
+    fn map(&mut self, map: &Map) {
+        self.token("{");
+        self.separated(&map.elements, ",", |e, entry| {
+            e.operand(&entry.key, OPEN, false);
+            if let Some(v) = &entry.value {
+                e.token(":");
+                e.operand(v, OPEN, false);
+            }
+        });
+        self.token("}");
+    }
+
+    fn literal(&mut self, literal: &Literal) {
+        match literal {
+            Literal::Integer(i) => {
+                self.signed_number(i.value < 0, &i.value.to_string(), Previous::Integer)
+            }
+            Literal::Float(f) => {
+                self.signed_number(f.value < 0.0, &format!("{:?}", f.value), Previous::Number)
+            }
+            Literal::Complex(c) => {
+                // 总是输出实部，以防虚部跟前面的整数（比如 `3 + 4i` 里的 `3`）组合成另一个复数
+                self.signed_number(c.real < 0.0, &format!("{:?}", c.real), Previous::Number);
+                if c.imaginary < 0.0 {
+                    self.token("-");
+                    self.number(&format!("{:?}i", -c.imaginary), Previous::Number);
+                } else {
+                    self.token("+");
+                    self.number(&format!("{:?}i", c.imaginary), Previous::Number);
+                }
+            }
+            Literal::Bit(b) => self.number(&b.to_string(), Previous::Number),
+            Literal::Boolean(b) => self.token(&b.to_string()),
+            Literal::Char(c) => match c.value {
+                // 目前词法分析器把转义字符的第一个字符（即反斜杠）作为字符的值
+                '\\' => self.token("'\\x5c'"),
+                '\'' => self.token("'\\''"),
+                v => self.token(&format!("'{}'", v)),
+            },
+            Literal::GeneralString(s) => self.string(&s.value),
+            Literal::TemplateString(t) => {
+                let mut text = String::from("`");
+                let mut expressions = t.expressions.iter();
+                for fragment in &t.fragments {
+                    text.push_str(fragment);
+                    if let Some(e) = expressions.next() {
+                        let mut inner = Emitter::default();
+                        inner.operand(e, OPEN, false);
+                        text.push_str(&format!("{{{{{}}}}}", inner.output));
+                    }
+                }
+                text.push('`');
+                self.token(&text);
+            }
+            Literal::HashString(h) => self.token(&format!("#{}", h.value)),
+            Literal::NamedOperator(n) => self.token(&format!(":{}:", n.value)),
+        }
+    }
+
+    // 负数（只出现在常量折叠之后的 AST）需要加上括号
+    fn signed_number(&mut self, is_negative: bool, text: &str, previous: Previous) {
+        if is_negative {
+            self.token("(");
+            self.number(text, previous);
+            self.token(")");
+        } else {
+            self.number(text, previous);
+        }
+    }
+
+    // 字符串的值保留了转义字符，所以直接放在引号之间，
+    // 但原始字符串 `"""..."""` 的值有可能包含未转义的双引号，这时仍然输出为原始字符串
+    fn string(&mut self, value: &str) {
+        let mut is_escaped = false;
+        let mut has_quote = false;
+        for c in value.chars() {
+            has_quote |= c == '"' && !is_escaped;
+            is_escaped = c == '\\' && !is_escaped;
+        }
+
+        if (has_quote || is_escaped) && !value.contains("\"\"\"") && !value.ends_with('"') {
+            self.token(&format!("\"\"\"{}\"\"\"", value));
+        } else {
+            self.token(&format!("\"{}\"", value));
+        }
+    }
+}
+
+fn is_implicit_block(expression: &Expression) -> bool {
+    matches!(expression, Expression::BlockExpression(b) if !b.is_explicit)
+}
+
+// 两个字符之间是否需要空格，即相邻的两个单词（标识符、关键字、数字、字面量）之间，
+// 以及连接之后会变成另外一个运算符（或者注释）的两个符号之间需要空格，
+// 冒号后面紧跟着标识符时有可能被当作命名操作符（比如 `:name:`）的开始，所以也需要空格
+fn needs_space(left: char, right: char) -> bool {
+    let is_word =
+        |c: char| c.is_alphanumeric() || matches!(c, '_' | '"' | '\'' | '`' | '#') || !c.is_ascii();
+
+    const JOINED_SYMBOLS: [&str; 13] = [
+        "||", "&&", "==", "!=", ">=", "<=", "++", "??", "->", "..", "::", "//", "/*",
+    ];
+
+    (is_word(left) && is_word(right))
+        || (left == ':' && (right.is_alphabetic() || right == '_'))
+        || JOINED_SYMBOLS.contains(&format!("{}{}", left, right).as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Node, lexer, parser};
+
+    use super::to_compact_string;
+
+    fn parse(source: &str) -> Node {
+        let token_details = lexer::tokenize(source).unwrap();
+        parser::parse(&token_details).unwrap_or_else(|e| panic!("{:?}: {:?}", source, e))
+    }
+
+    // 输出紧凑格式，并检查重新分析之后得到跟原来相同的 AST
+    //
+    // 紧凑格式里 Node 的位置跟原来的不同，所以比较之前清除两者的范围
+    fn compact(source: &str) -> String {
+        let mut node = parse(source);
+        let text = to_compact_string(&node);
+
+        let token_details = lexer::tokenize(&text)
+            .unwrap_or_else(|e| panic!("{:?} is compacted to {:?}: {}", source, text, e));
+        let mut reparsed = parser::parse(&token_details)
+            .unwrap_or_else(|e| panic!("{:?} is compacted to {:?}: {}", source, text, e));
+        node.clear_ranges();
+        reparsed.clear_ranges();
+        assert_eq!(reparsed, node, "{:?} is compacted to {:?}", source, text);
+        text
+    }
+
+    #[test]
+    fn test_compact() {
+        assert_eq!(compact("let a = 1 + 2 * 3"), "let a=1+2*3");
+        assert_eq!(compact("let a = (1 + 2) * 3"), "let a=(1+2)*3");
+        assert_eq!(compact("let a = 1 - (2 - 3)"), "let a=1-(2-3)");
+        assert_eq!(compact("let x = a - 1\nlet y = -b"), "let x=a-1;let y=-b");
+        assert_eq!(
+            compact("let z = do {\n    let a = 1\n    a\n}"),
+            "let z=do{let a=1;a}"
+        );
+        assert_eq!(compact("let m = {\n#a: 1\n#b: 2\n}"), "let m={#a:1,#b:2}");
+        assert_eq!(compact("if a then {b} else {c}"), "if a then{b}else{c}");
+        assert_eq!(
+            compact("match x {\ncase #foo: 1\ndefault: 0\n}"),
+            "match x{case #foo:1;default:0}"
+        );
+        assert_eq!(
+            compact("let f = fn (Int a) type Int = a + 1"),
+            "let f=fn(Int a)type Int=a+1"
+        );
+        assert_eq!(
+            compact("let v = foo.bar[0](1, b = 2)"),
+            "let v=foo.bar[0](1,b=2)"
+        );
+    }
+
+    #[test]
+    fn test_compact_literals() {
+        // 浮点数保持浮点数的格式
+        assert_eq!(compact("let a = 1.0"), "let a=1.0");
+        assert_eq!(compact("let a = 1.5e-7 + 2e20"), "let a=1.5e-7+2e20");
+        assert_eq!(compact("let c = 3+4i"), "let c=3.0+4.0i");
+
+        // 字面量里的空白保持原样
+        assert_eq!(
+            compact("let s = \"foo\n  bar\" ++ 'a' ++ \"x\\\" y\""),
+            "let s=\"foo\n  bar\"++'a'++\"x\\\" y\""
+        );
+    }
+
+    #[test]
+    fn test_compact_separators() {
+        // 数字后面的 `.` 以及 `..=`
+        assert_eq!(compact("let a = [1.5 ..= 2.5]"), "let a=[1.5 ..=2.5]");
+        assert_eq!(compact("let a = [1..10]"), "let a=[1..10]");
+        assert_eq!(compact("let a = t.0 .1"), "let a=t.0 .1");
+
+        // 主体不是表达式块时，使用分号分隔目标对象和主体
+        assert_eq!(compact("each i in items i + 1"), "each i in items;i+1");
+        assert_eq!(compact("each i in items {i}"), "each i in items{i}");
+        assert_eq!(compact("each i in (a.items) {i}"), "each i in a.items;{i}");
+    }
+
+    // 往返测试的语料
+    const CORPUS: [&str; 40] = [
+        "let a = 1.0\nlet b = -0.5\nlet c = 1e100",
+        "let a = [1.5 ..= 2.5]\nlet b = [1..]\nlet c = [1, 2, ...rest]",
+        "each i in items i + 1",
+        "each i in items; (i, i)",
+        "for let i = 0; -i",
+        "for let i = (a + b) {next i + 1}",
+        "let a = (b & c) + d\nlet e = x * (y & z) + 1\nlet f = a & b & c",
+        "let a = (1 + 2) * (3 - 4) / (5 ++ 6)",
+        "let a = 1 - (2 + 3)\nlet b = (a || b) && (c | d)",
+        "let a = (x :bit_or: y) :bit_and: z\nlet b = x :add: y ++ z",
+        "let a = -(-x)\nlet b = (-x)^\nlet c = -x^\nlet d = (x?)?\nlet e = (x^)^",
+        "let a = (-x)?\nlet b = -x?\nlet c = (a + b)?",
+        "let a = f.b[3](1)(2)\nlet c = (f(1)).b\nlet d = (fn x = x)(1)",
+        "let a = (a < b) > c\nlet b = f((a < b), c > d)\nlet c = List<Int>",
+        "let a = a ?? b -> c\nlet b = (a ?? b) -> c",
+        "let p = Point {x: 1, y: 2}\nlet q = Point(1, 2)\nlet r = (1,)\nlet s = ()",
+        "let (a, b) = (1, 2)\nlet [a, ...b] = c\nlet Int a = 1",
+        "let f = fn (Int a, b) type Int which {T: Int} {a + b}\nlet s = sign (Int, Int x) type Int",
+        "if a then b else c\nif a then if b then c else d\nif a then (if b then c) else d",
+        "if a where b then c else if d then e else f",
+        "let a = if x then {1} else {2}\nlet b = 1 + (if x then 1 else 2)",
+        "let a = join {\n f(1)\n g(2)\n}\nlet b = do {}",
+        "branch {\ncase a > 1: 1\ncase b where c: {2}\ndefault: 3\n}",
+        "branch where a; {\ncase a: 1\n}\nbranch where (a + b.c) {\ncase a: 1\n}",
+        "match x {\ncase v @ Point {x, y} only x > y: 1\ncase in [1..10]: 2\ncase into Email e: 3\ncase regular \"a(b)\" (one,): 4\ncase template \"a{{b}}\": 5\n}",
+        "match x + y.z where w\n{\ncase 1: {a}\ndefault: {b}\n}",
+        "match x {\ncase 1: a :op: b\ncase 2: ({x: 1})\n}",
+        "let a = !foo\nlet b = x.y.z\nlet c = x[1..2]\nlet d = x[3..]",
+        "let a = 'a' ++ '\\n' ++ \"\"\"a \"quoted\" text\"\"\"\nlet b = #foo\nlet c = 8'x0f\nlet d = true",
+        "let a = t.0\nlet b = 1 .x\nlet c = 2i\nlet d = 1.5+2.5i\nlet e = a + 2i\nlet f = 3 + (4 + 5i)",
+        "function f(Int a, Int b = 1) type Int = a + b",
+        "function f<T>(T a) type T which {T: limit Display + Clone} {\nlet b = a\nb\n}",
+        "#[inline]\n#[deprecated(\"use g\", since = 1)]\nfunction f() = ({x: 1})",
+        "empty function f(Int a) type Int\n#[extern(\"wasi\", \"fd_write\")]\nempty function g()",
+        "pattern function f(Int i @ in [1..10] only i > 0, Point (x, y) where x > y, regular \"a\" (one,)) only a\n{1}",
+        "namespace foo::bar\nuse std::{io, fmt::Display as D}\nuse a::b as c",
+        "const Int A = 1\n#[deprecated]\nconst B = A + 1\n#[allow(naming_convention)]\nlet badName = B",
+        "struct Point {\nInt x\nInt y\n}\nstruct Pair<T>(T, T)\nstruct Empty\n#[packed]\nstruct P which {T: Int} {T a}",
+        "union Option<T> {\nSome(T)\nNone\nRect {Int w}\n}\nalias Name<T> = List<T>\n#[deprecated(\"use Int\")]\nalias I = Int",
+        "trait Shape<T> type (Item, Error = String) {\nempty function area(T a) type Int\nfunction name() = \"shape\"\n}\nimpl <T> Circle<T> trait Shape type Item = Int {\nfunction area() = 1\n}",
+    ];
+
+    #[test]
+    fn test_compact_round_trip() {
+        for source in CORPUS {
+            compact(source);
+        }
+
+        compact(include_str!("../scripts/01-base-expression.xuan"));
+    }
+
+    // 随机生成由各种运算组成的表达式，检查输出之后重新分析得到相同的 AST
+    #[test]
+    fn test_compact_random_expressions() {
+        const BINARY: [&str; 17] = [
+            "|", "||", "&&", "==", "!=", ">", ">=", "<", "<=", "++", "+", "-", "*", "/", "??",
+            "->", "&",
+        ];
+
+        struct Random(u64);
+
+        impl Random {
+            fn next(&mut self, bound: usize) -> usize {
+                // xorshift64
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                (self.0 % bound as u64) as usize
+            }
+        }
+
+        fn generate(random: &mut Random, depth: usize) -> String {
+            if depth == 0 {
+                return match random.next(6) {
+                    0 => "a".to_string(),
+                    1 => "b.c".to_string(),
+                    2 => "1".to_string(),
+                    3 => "2.5".to_string(),
+                    4 => "f(x)".to_string(),
+                    _ => "[1, 2]".to_string(),
+                };
+            }
+
+            let operand = generate(random, depth - 1);
+            match random.next(12) {
+                0 => format!("-({})", operand),
+                1 => format!("({})^", operand),
+                2 => format!("({})?", operand),
+                3 => format!("({}) :op: ({})", operand, generate(random, depth - 1)),
+                4 => format!("({}).y({})", operand, generate(random, depth - 1)),
+                5 => format!("({}).x", operand),
+                6 => format!("(fn x = {})", operand),
+                7 => format!(
+                    "(if {} then {} else x)",
+                    operand,
+                    generate(random, depth - 1)
+                ),
+                _ => format!(
+                    "({}) {} ({})",
+                    operand,
+                    BINARY[random.next(BINARY.len())],
+                    generate(random, depth - 1)
+                ),
+            }
+        }
+
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+        for _ in 0..2000 {
+            let depth = random.next(5) + 1;
+            compact(&format!("let v = {}", generate(&mut random, depth)));
+        }
+    }
+}
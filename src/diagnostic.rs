@@ -0,0 +1,340 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 诊断信息
+//
+// 将错误转换为结构化的诊断信息，包括错误代码、信息、主要位置、次要标签、
+// 期望的 Token 以及帮助信息，并且可以渲染为带有标注的源代码片段，比如：
+//
+// ```text
+// error[E0102]: expected "," or ")", found "3"
+//  --> main.xuan:1:7
+//   |
+// 1 | (1, 2 3)
+//   |       ^ expected "," or ")"
+//   |
+//   = help: insert a comma if this is the next item
+// ```
+//
+// 检查项目（见 `lint` 模块）也可以转换为诊断信息，级别为 `deny` 的检查项目是错误，
+// 其余的是警告，渲染时以 `warning[检查项目的名称]` 开头。
+//
+// 渲染时只使用标签范围里的字节偏移量，行号和列号由 `SourceMap` 换算，
+// 所以标签的 `file_id` 需跟被渲染的源代码一致。
+
+use crate::{
+    ast::Range,
+    error::{join_expected, location_to_range, Error},
+    lint::{Lint, LintLevel},
+    source_map::SourceMap,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+// 标示源代码的一个范围，以及对该范围的说明（可以为空）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub range: Range,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str, // 错误代码，见 `Error::code`，检查项目则为其名称
+    pub message: String,    // 错误信息
+    pub primary: Option<Label>, // 错误发生的位置，为 None 时表示位置未知
+    pub secondary: Vec<Label>, // 跟错误相关的其他位置，比如没有闭合的开始括号
+    pub expected: Vec<String>, // 期望的 Token（或者语法成分）的集合
+    pub help: Option<String>, // 帮助信息，比如修改建议
+}
+
+impl From<&Error> for Diagnostic {
+    fn from(error: &Error) -> Self {
+        let expected = error.expected().to_vec();
+
+        let primary = error.range().map(|range| Label {
+            range,
+            message: if expected.is_empty() {
+                String::new()
+            } else {
+                format!("expected {}", join_expected(&expected))
+            },
+        });
+
+        let secondary = error
+            .note()
+            .map(|(message, location)| Label {
+                range: location_to_range(location),
+                message,
+            })
+            .into_iter()
+            .chain(error.related().iter().map(|(message, range)| Label {
+                range: range.clone(),
+                message: message.clone(),
+            }))
+            .collect();
+
+        Diagnostic {
+            severity: Severity::Error,
+            code: error.code(),
+            message: error.to_string(),
+            primary,
+            secondary,
+            expected,
+            help: error.suggestion().map(|s| s.message.clone()),
+        }
+    }
+}
+
+impl From<&Lint> for Diagnostic {
+    fn from(lint: &Lint) -> Self {
+        Diagnostic {
+            severity: match lint.level {
+                LintLevel::Deny => Severity::Error,
+                _ => Severity::Warning,
+            },
+            code: lint.name,
+            message: lint.message.clone(),
+            primary: Some(Label {
+                range: lint.range.clone(),
+                message: String::new(),
+            }),
+            secondary: lint
+                .related_range
+                .iter()
+                .map(|range| Label {
+                    range: range.clone(),
+                    message: "declared here".to_string(),
+                })
+                .collect(),
+            expected: vec![],
+            help: lint.suggestion.as_ref().map(|s| s.message.clone()),
+        }
+    }
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    // 渲染为带有标注的源代码片段，file_name 仅用于显示
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        let source_map = SourceMap::new(source);
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let mut text = format!("{}[{}]: {}\n", severity, self.code, self.message);
+
+        // 按照位置排列的标签，主要标签使用 `^` 标示，次要标签使用 `-` 标示
+        let mut labels: Vec<(usize, &Label, char)> = self
+            .primary
+            .iter()
+            .map(|label| (label, '^'))
+            .chain(self.secondary.iter().map(|label| (label, '-')))
+            .map(|(label, mark)| (source_map.position(label.range.start).line, label, mark))
+            .collect();
+        labels.sort_by_key(|(line, label, _)| (*line, label.range.start));
+
+        let gutter_width = labels
+            .iter()
+            .map(|(line, _, _)| line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let gutter = " ".repeat(gutter_width);
+
+        // 位置未知时显示第一个次要标签的位置
+        if let Some(label) = self.primary.as_ref().or(self.secondary.first()) {
+            let position = source_map.position(label.range.start);
+            text.push_str(&format!(
+                "{}--> {}:{}:{}\n",
+                gutter, file_name, position.line, position.display_column
+            ));
+            text.push_str(&format!("{} |\n", gutter));
+        }
+
+        let mut last_line = 0;
+        for (line, label, mark) in &labels {
+            if *line != last_line {
+                let line_text = source_map.line_text(*line).unwrap_or_default();
+                text.push_str(&format!(
+                    "{:>width$} | {}\n",
+                    line,
+                    expand_tabs(line_text, source_map.tab_width()),
+                    width = gutter_width
+                ));
+                last_line = *line;
+            }
+
+            let carets = source_map
+                .caret_line(label.range.start, label.range.end)
+                .replace('^', &mark.to_string());
+            let annotation = format!("{} {}", carets, label.message);
+            text.push_str(&format!("{} | {}\n", gutter, annotation.trim_end()));
+        }
+
+        if let Some(help) = &self.help {
+            if !labels.is_empty() {
+                text.push_str(&format!("{} |\n", gutter));
+            }
+            text.push_str(&format!("{} = help: {}\n", gutter, help));
+        }
+
+        text
+    }
+}
+
+// 将制表符替换为空格，以便跟下划线（`^^^`）对齐
+fn expand_tabs(text: &str, tab_width: usize) -> String {
+    let mut expanded = String::new();
+    for c in text.chars() {
+        if c == '\t' {
+            let width = expanded.chars().count();
+            expanded.push_str(&" ".repeat((width / tab_width + 1) * tab_width - width));
+        } else {
+            expanded.push(c);
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Node, lexer, lint, parser};
+
+    use super::{Diagnostic, Severity};
+
+    fn parse_error(source: &str) -> Diagnostic {
+        let error = lexer::tokenize(source)
+            .and_then(|token_details| parser::parse(&token_details))
+            .unwrap_err();
+        Diagnostic::from(&error)
+    }
+
+    fn render(source: &str) -> String {
+        parse_error(source).render("main.xuan", source)
+    }
+
+    #[test]
+    fn test_from_error() {
+        let d1 = parse_error("(1, 2 3)");
+        assert_eq!(d1.code, "E0102");
+        assert_eq!(d1.expected, ["\",\"", "\")\""]);
+        assert_eq!(
+            d1.help.as_deref(),
+            Some("insert a comma if this is the next item")
+        );
+
+        let primary = d1.primary.unwrap();
+        assert_eq!((primary.range.start, primary.range.end), (6, 7));
+        assert_eq!(primary.message, "expected \",\" or \")\"");
+        assert!(d1.secondary.is_empty());
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render("(1, 2 3)"),
+            "\
+error[E0102]: expected \",\" or \")\", found \"3\"
+ --> main.xuan:1:7
+  |
+1 | (1, 2 3)
+  |       ^ expected \",\" or \")\"
+  |
+  = help: insert a comma if this is the next item
+"
+        );
+
+        // 词法错误
+        assert_eq!(
+            render("let a = 1\n\tlet b = 1 $ 2"),
+            "\
+error[E0002]: invalid char '$'
+ --> main.xuan:2:15
+  |
+2 |     let b = 1 $ 2
+  |               ^
+"
+        );
+
+        // 带有次要标签的错误
+        assert_eq!(
+            render("[1, (2]"),
+            "\
+error[E0103]: unclosed \"(\", found mismatched \"]\"
+ --> main.xuan:1:7
+  |
+1 | [1, (2]
+  |     - unclosed \"(\" opened here
+  |       ^
+  |
+  = help: insert the closing \")\"
+"
+        );
+
+        // 已到达源代码的末尾的错误没有主要标签
+        assert_eq!(
+            render("(1, 2"),
+            "\
+error[E0103]: unclosed \"(\", found end of file
+ --> main.xuan:1:1
+  |
+1 | (1, 2
+  | - unclosed \"(\" opened here
+  |
+  = help: insert the closing \")\"
+"
+        );
+
+        // 没有任何标签的错误只渲染错误信息
+        assert_eq!(
+            render("const a ="),
+            "error[E0102]: expected expression, found end of file\n"
+        );
+    }
+
+    #[test]
+    fn test_from_lint() {
+        let lint_source = |source: &str| -> Vec<Diagnostic> {
+            let token_details = lexer::tokenize(source).unwrap();
+            let node: Node = parser::parse(&token_details).unwrap();
+            lint::check(&node).iter().map(Diagnostic::from).collect()
+        };
+
+        let source = "#[deprecated]\nconst MAX = 1\nlet lineNo = MAX";
+        let d1 = lint_source(source);
+        assert_eq!(d1.len(), 2);
+        assert!(d1.iter().all(|d| !d.is_error()));
+        assert_eq!(
+            d1[1].render("main.xuan", source),
+            "\
+warning[deprecated]: const \"MAX\" is deprecated
+ --> main.xuan:3:14
+  |
+1 | #[deprecated]
+  | ------------- declared here
+3 | let lineNo = MAX
+  |              ^^^
+"
+        );
+
+        // 级别为 deny 的检查项目是错误
+        let d2 = lint_source("#[deny(naming_convention)]\nlet lineNo = 1");
+        assert_eq!(d2[0].severity, Severity::Error);
+        assert!(d2[0]
+            .render("main.xuan", "")
+            .starts_with("error[naming_convention]: "));
+    }
+}
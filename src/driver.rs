@@ -0,0 +1,797 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 编译器的驱动程序
+//
+// 把编译过程的各个阶段串联起来，供命令行工具以及其他外部工具以库的方式使用：
+//
+// - `parse`：词法分析以及语法分析；
+// - `analyze`：语义检查（见 `semantics::check`）、构建环境值以及静态断言的检查
+//   （见 `semantics::check_build_environment` 和 `semantics::check_static_assertions`）
+//   以及名称解析（见 `resolve` 模块），最后运行各个检查项目（见 `lint` 模块）；
+// - `emit`：生成可执行程序时检查入口函数（见 `semantics::check_entry_point`），
+//   展开编译时内置函数（见 `fold::expand_intrinsics`），规范化为 HIR
+//   （见 `normalize` 模块），再由后端（见 `backend` 模块）降级为产物。
+//
+// ```ignore
+// let mut compiler = Compiler::new()
+//     .add_source("src/main.xuan")
+//     .parse()
+//     .analyze();
+// let artifacts = compiler.emit("vm");
+// print!("{}", compiler.render_diagnostics());
+// ```
+//
+// 构建环境以及入口函数的名称等选项由 `with_options` 指定（见 `CompilerOptions`），
+// 检查项目的选项（比如圈复杂度的上限以及名称的风格）由 `with_lint_options` 指定，
+// 编译时内置函数 `env` 以及 `static_assert` 包括在默认的预置名称里（见 `DEFAULT_PRELUDE`）。
+//
+// 源文件由 `SourceManager` 保存并分配文件 id，诊断信息根据文件 id 渲染对应的源代码片段。
+//
+// 每个阶段都会处理所有的源文件，错误被累积为诊断信息（见 `diagnostic` 模块），
+// 而不是在第一个错误处停止。检查项目的警告也被记录为诊断信息，但不算作错误
+// （见 `has_errors`），级别为 `deny` 的检查项目则跟语义错误一样使源文件分析失败。后一个阶段只处理前面的阶段没有错误的源文件，
+// 并且会先补做尚未运行的前面的阶段，比如直接调用 `emit` 也会先分析源文件。
+// `emit` 只在所有源文件都没有错误时才生成产物。
+//
+// 每个源文件的分析结果（Token 列表、AST 以及诊断信息）被保留下来，源文件被修改之后
+// （见 `update_source_text`），只有被修改的源文件需要重新分析，并且词法分析只重新分析
+// 被修改的部分（见 `lexer::relex`）。导入了某个模块的源文件在该模块的接口改变时
+// 也需要重新分析（见 `invalidate`），命令行工具的监视模式（`xuanc check --watch`）
+// 由查询数据库（见 `query` 模块）判断哪些模块的接口改变了。
+
+use std::path::Path;
+
+use crate::{
+    ast::{Node, Program, Range, Statement},
+    backend::{Artifact, BackendRegistry, CompilerOptions},
+    diagnostic::Diagnostic,
+    error::Error,
+    fold, ice,
+    lint::{self, Lint, LintLevel, LintOptions},
+    normalize, overload, parser, resolve, semantics,
+    source_manager::SourceManager,
+    token::TokenDetail,
+};
+
+// 默认预置的名称，即内置的数据类型以及函数，它们不需要声明或者导入即可使用
+pub const DEFAULT_PRELUDE: [&str; 15] = [
+    "Int",
+    "Float",
+    "Complex",
+    "Bit",
+    "Boolean",
+    "Char",
+    "String",
+    "List",
+    "Map",
+    "Range",
+    "Any",
+    "writeLine",
+    "writeLineFormat",
+    fold::ENV_FUNCTION_NAME,
+    fold::STATIC_ASSERT_FUNCTION_NAME,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceDiagnostic {
+    // 源文件的 id（见 `Compiler::sources`），为 None 时表示跟源文件无关的错误，比如未知的后端
+    pub source: Option<usize>,
+    pub diagnostic: Diagnostic,
+}
+
+// 源文件已经完成的阶段
+enum State {
+    Added,
+    Parsed(Program),
+    Analyzed(Program),
+    Failed, // 读取文件失败，或者某个阶段有错误
+}
+
+pub struct Compiler {
+    registry: BackendRegistry,
+    options: CompilerOptions,
+    lint_options: LintOptions,
+    prelude: Vec<String>,
+    sources: SourceManager,
+    states: Vec<State>,                    // 索引即源文件的 id
+    tokens: Vec<Option<Vec<TokenDetail>>>, // 源文件当前的文本的 Token 列表，用于增量词法分析
+    diagnostics: Vec<SourceDiagnostic>,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Self {
+            registry: BackendRegistry::with_builtin_backends(),
+            options: CompilerOptions::default(),
+            lint_options: LintOptions::default(),
+            prelude: DEFAULT_PRELUDE.iter().map(|s| s.to_string()).collect(),
+            sources: SourceManager::new(),
+            states: vec![],
+            tokens: vec![],
+            diagnostics: vec![],
+        }
+    }
+
+    // 使用指定的后端注册表，比如注册了第三方后端的注册表
+    pub fn with_registry(mut self, registry: BackendRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    // 指定编译器选项，其中的后端由 `emit` 的参数决定
+    pub fn with_options(mut self, options: CompilerOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    // 指定检查项目的选项
+    pub fn with_lint_options(mut self, lint_options: LintOptions) -> Self {
+        self.lint_options = lint_options;
+        self
+    }
+
+    // 替换预置的名称，默认为 `DEFAULT_PRELUDE`
+    pub fn with_prelude(mut self, prelude: &[&str]) -> Self {
+        self.prelude = prelude.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    // 加入源文件，读取失败时记录一条诊断信息
+    pub fn add_source(mut self, path: impl AsRef<Path>) -> Self {
+        match self.sources.add_file(&path) {
+            Ok(_) => self.states.push(State::Added),
+            Err(e) => {
+                let file_id = self.sources.add(path, "");
+                self.states.push(State::Failed);
+                self.report(Some(file_id), &e);
+            }
+        }
+        self.tokens.push(None);
+        self
+    }
+
+    // 加入源代码，path 仅用于显示诊断信息以及决定产物的文件名
+    pub fn add_source_text(mut self, path: impl AsRef<Path>, text: &str) -> Self {
+        self.sources.add(path, text);
+        self.states.push(State::Added);
+        self.tokens.push(None);
+        self
+    }
+
+    // 修改源文件的文本，比如文件被修改之后重新读取，返回文本是否改变。
+    //
+    // 被修改的源文件的诊断信息被清除，并回到刚加入时的状态，之后的各个阶段只重新分析
+    // 这个源文件，其他源文件保留原来的分析结果。读取失败的源文件也可以通过这种方式重新加入。
+    pub fn update_source_text(&mut self, index: usize, text: &str) -> bool {
+        if self.sources.text(index).is_none_or(|old| old == text) {
+            return false;
+        }
+
+        let edit = self.sources.set_text(index, text).unwrap();
+        // 增量词法分析失败（即新的文本有词法错误）时，由语法分析阶段重新分析并报告错误
+        self.tokens[index] = self.tokens[index]
+            .take()
+            .and_then(|previous| self.sources.relex(index, &previous, &edit).ok());
+        self.invalidate(index);
+        true
+    }
+
+    // 在文本没有改变的情况下重新分析源文件，比如它导入的模块的接口改变了。
+    //
+    // 源文件的诊断信息被清除，并回到刚加入时的状态，Token 列表被保留。
+    pub fn invalidate(&mut self, index: usize) {
+        if index >= self.states.len() {
+            return;
+        }
+
+        self.states[index] = State::Added;
+        self.diagnostics.retain(|d| d.source != Some(index));
+    }
+
+    pub fn parse(mut self) -> Self {
+        self.parse_sources();
+        self
+    }
+
+    pub fn analyze(mut self) -> Self {
+        self.analyze_sources();
+        self
+    }
+
+    // 使用指定名称的后端生成产物，返回源文件的 id 以及对应的产物，
+    // 有任何错误时返回空列表（错误见 `diagnostics`）
+    pub fn emit(&mut self, backend: &str) -> Vec<(usize, Artifact)> {
+        self.analyze_sources();
+        if self.has_errors() {
+            return vec![];
+        }
+
+        let options = CompilerOptions {
+            backend: backend.to_string(),
+            ..self.options.clone()
+        };
+        if let Err(e) = self.registry.select(&options) {
+            self.report(None, &e);
+            return vec![];
+        }
+
+        if let Some(entry_name) = &options.entry {
+            self.check_entry(entry_name);
+            if self.has_errors() {
+                return vec![];
+            }
+        }
+
+        let mut artifacts = vec![];
+        for index in 0..self.sources.len() {
+            let State::Analyzed(program) = &self.states[index] else {
+                continue;
+            };
+
+            let result = ice::catch_ice("lower", || {
+                let mut program = program.clone();
+                fold::expand_intrinsics(&mut program, &options.environment);
+
+                let mut node = Node::Program(program);
+                normalize::normalize(&mut node);
+                match node {
+                    Node::Program(hir) => self.registry.lower(&hir, &options),
+                    _ => unreachable!(),
+                }
+            });
+
+            match result {
+                Ok(artifact) => artifacts.push((index, artifact)),
+                Err(e) => self.report(Some(index), &e),
+            }
+        }
+
+        if self.has_errors() {
+            vec![]
+        } else {
+            artifacts
+        }
+    }
+
+    pub fn sources(&self) -> &SourceManager {
+        &self.sources
+    }
+
+    // 源文件的 AST，语法分析失败或者尚未分析时为 None
+    pub fn program(&self, index: usize) -> Option<&Program> {
+        match self.states.get(index)? {
+            State::Parsed(program) | State::Analyzed(program) => Some(program),
+            _ => None,
+        }
+    }
+
+    pub fn options(&self) -> &CompilerOptions {
+        &self.options
+    }
+
+    pub fn lint_options(&self) -> &LintOptions {
+        &self.lint_options
+    }
+
+    pub fn registry(&self) -> &BackendRegistry {
+        &self.registry
+    }
+
+    pub fn diagnostics(&self) -> &[SourceDiagnostic] {
+        &self.diagnostics
+    }
+
+    // 是否有错误，检查项目的警告不算错误
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.diagnostic.is_error())
+    }
+
+    // 渲染所有的诊断信息，格式见 `Diagnostic::render`
+    pub fn render_diagnostics(&self) -> String {
+        self.diagnostics
+            .iter()
+            .map(|d| match d.source {
+                Some(file_id) => {
+                    let file = self.sources.get(file_id).unwrap();
+                    d.diagnostic
+                        .render(&file.path.to_string_lossy(), &file.text)
+                }
+                None => d.diagnostic.render("", ""),
+            })
+            .collect()
+    }
+
+    fn report(&mut self, source: Option<usize>, error: &Error) {
+        self.diagnostics.push(SourceDiagnostic {
+            source,
+            diagnostic: Diagnostic::from(error),
+        });
+    }
+
+    fn report_lint(&mut self, source: usize, lint: &Lint) {
+        self.diagnostics.push(SourceDiagnostic {
+            source: Some(source),
+            diagnostic: Diagnostic::from(lint),
+        });
+    }
+
+    // 检查可执行程序的入口函数，入口函数可以位于任意一个源文件，但只能有一个，
+    // 所有源文件都没有入口函数时，错误记录在第一个源文件
+    fn check_entry(&mut self, entry_name: &str) {
+        let find_entry = |program: &Program| {
+            program.body.iter().find_map(|statement| match statement {
+                Statement::FunctionDeclaration(f) if f.name == entry_name => Some(f.range.clone()),
+                _ => None,
+            })
+        };
+
+        let candidates: Vec<(usize, Range)> = (0..self.sources.len())
+            .filter_map(|index| Some((index, find_entry(self.program(index)?)?)))
+            .collect();
+
+        if let [_, (index, range), ..] = candidates.as_slice() {
+            let error = Error::SemanticError {
+                message: format!(
+                    "the entry function \"{}\" is defined in {} source files",
+                    entry_name,
+                    candidates.len()
+                ),
+                related: vec![],
+                range: range.clone(),
+            };
+            self.report(Some(*index), &error);
+            return;
+        }
+
+        let index = candidates.first().map_or(0, |(index, _)| *index);
+        let Some(program) = self.program(index) else {
+            return;
+        };
+        if let Err(e) = semantics::check_entry_point(&Node::Program(program.clone()), entry_name) {
+            self.report(Some(index), &e);
+        }
+    }
+
+    fn parse_sources(&mut self) {
+        for index in 0..self.sources.len() {
+            if !matches!(self.states[index], State::Added) {
+                continue;
+            }
+
+            // 源文件被修改之后，词法分析的结果已经由 `update_source_text` 增量地更新
+            let sources = &self.sources;
+            let result = match self.tokens[index].take() {
+                Some(token_details) => Ok(token_details),
+                None => ice::catch_ice("tokenize", || sources.tokenize(index)),
+            }
+            .and_then(|token_details| {
+                let result = ice::catch_ice("parse", || parser::parse(&token_details));
+                self.tokens[index] = Some(token_details);
+                match result? {
+                    Node::Program(program) => Ok(program),
+                    _ => unreachable!(),
+                }
+            });
+
+            self.states[index] = match result {
+                Ok(program) => State::Parsed(program),
+                Err(e) => {
+                    self.report(Some(index), &e);
+                    State::Failed
+                }
+            };
+        }
+    }
+
+    fn analyze_sources(&mut self) {
+        self.parse_sources();
+
+        for index in 0..self.sources.len() {
+            if !matches!(self.states[index], State::Parsed(_)) {
+                continue;
+            }
+
+            // 分析失败时保持为 `Failed`
+            let State::Parsed(program) = std::mem::replace(&mut self.states[index], State::Failed)
+            else {
+                unreachable!()
+            };
+
+            let result = ice::catch_ice("analyze", || {
+                let mut program = program;
+                let prelude: Vec<&str> = self.prelude.iter().map(|s| s.as_str()).collect();
+                let node = Node::Program(program.clone());
+                let mut errors = semantics::check(&node);
+                errors.extend(semantics::check_build_environment(
+                    &node,
+                    &self.options.environment,
+                ));
+                errors.extend(semantics::check_static_assertions(
+                    &node,
+                    &self.options.environment,
+                ));
+                let mut resolution = resolve::resolve(&program, &prelude);
+                errors.append(&mut resolution.errors);
+                errors.extend(overload::resolve_overloads(&program, &mut resolution));
+                errors.extend(resolve::rewrite_tuple_constructors(
+                    &mut program,
+                    &resolution,
+                ));
+                let lints = lint::check_with_options(&node, &self.lint_options);
+                Ok((program, errors, lints))
+            });
+
+            match result {
+                Ok((program, errors, lints)) => {
+                    for e in &errors {
+                        self.report(Some(index), e);
+                    }
+                    for l in &lints {
+                        self.report_lint(index, l);
+                    }
+
+                    let is_denied = lints.iter().any(|l| l.level == LintLevel::Deny);
+                    if errors.is_empty() && !is_denied {
+                        self.states[index] = State::Analyzed(program);
+                    }
+                }
+                Err(e) => self.report(Some(index), &e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        backend::{BackendRegistry, CompilerOptions},
+        fold::BuildEnvironment,
+        lint::{LintOptions, NamingCase},
+        module,
+    };
+
+    use super::Compiler;
+
+    #[test]
+    fn test_compile() {
+        let mut compiler = Compiler::new()
+            .add_source_text(
+                "main.xuan",
+                "function f(Int a) = a + 1\nfunction main() = writeLine(f(1))",
+            )
+            .add_source_text("lib.xuan", "const PI = 3.14")
+            .parse()
+            .analyze();
+        assert!(compiler.program(0).is_some());
+
+        // 每个源文件的 AST 带有各自的文件 id（见 `source_manager` 模块）
+        assert_eq!(compiler.program(1).unwrap().range.file_id, 1);
+
+        let artifacts = compiler.emit("vm");
+        assert!(!compiler.has_errors());
+        assert_eq!(artifacts.len(), 2);
+        assert_eq!(artifacts[1].0, 1);
+
+        let m1 = module::read(&artifacts[0].1.bytes).unwrap();
+        assert_eq!(m1.functions[0].name, "f");
+    }
+
+    #[test]
+    fn test_accumulated_diagnostics() {
+        // 各个源文件的错误都被记录，没有错误的源文件也不会生成产物
+        let mut compiler = Compiler::new()
+            .add_source_text("a.xuan", "let a = (")
+            .add_source_text("b.xuan", "writeLine(foo)\nwriteLine(bar)")
+            .add_source_text("c.xuan", "let c = 1")
+            .add_source("not_exists.xuan");
+        assert!(compiler.emit("vm").is_empty());
+
+        let sources: Vec<Option<usize>> = compiler.diagnostics().iter().map(|d| d.source).collect();
+        assert_eq!(sources, vec![Some(3), Some(0), Some(1), Some(1)]);
+        assert!(compiler.program(2).is_some());
+
+        let text = compiler.render_diagnostics();
+        assert!(text.contains("failed to read file \"not_exists.xuan\""));
+        assert!(text.contains(" --> b.xuan:2:11\n"));
+        assert!(text.contains("undefined name \"bar\""));
+
+        // 预置的名称
+        let compiler = Compiler::new()
+            .with_prelude(&["writeLine", "foo", "bar"])
+            .add_source_text("b.xuan", "writeLine(foo)\nwriteLine(bar)")
+            .analyze();
+        assert!(!compiler.has_errors());
+    }
+
+    #[test]
+    fn test_build_environment() {
+        let source = "const VERSION = env(\"VERSION\")\nfunction main() = writeLine(VERSION)";
+
+        let mut compiler = Compiler::new().add_source_text("main.xuan", source);
+        assert!(compiler.emit("vm").is_empty());
+        assert_eq!(compiler.diagnostics().len(), 1);
+        assert!(compiler
+            .render_diagnostics()
+            .contains("build environment value \"VERSION\" is not defined"));
+
+        let mut environment = BuildEnvironment::default();
+        environment.set("VERSION", "1.0.0");
+        let mut compiler = Compiler::new()
+            .with_options(CompilerOptions {
+                environment,
+                ..CompilerOptions::default()
+            })
+            .add_source_text("main.xuan", source);
+        let artifacts = compiler.emit("vm");
+        assert!(!compiler.has_errors());
+
+        // `env` 表达式被替换为字符串常量
+        assert!(artifacts[0].1.bytes.windows(5).any(|w| w == b"1.0.0"));
+    }
+
+    #[test]
+    fn test_static_assertions() {
+        let mut compiler = Compiler::new().add_source_text(
+            "main.xuan",
+            "static_assert(1 + 1 == 3, \"math is broken\")\nstatic_assert(true)",
+        );
+        assert!(compiler.emit("vm").is_empty());
+        assert_eq!(compiler.diagnostics().len(), 1);
+        assert!(compiler.render_diagnostics().starts_with(
+            "error[E0300]: static assertion failed: math is broken\n --> main.xuan:1:15\n"
+        ));
+
+        // 断言的条件可以读取构建环境值，通过的断言不产生代码
+        let source = "static_assert(env(\"MODE\") == \"release\")\n\
+            function f(Int a) = do {\nstatic_assert(2 * 4 == 8, \"size\")\na\n}\n\
+            function main() = writeLine(f(1))";
+        let mut compiler = Compiler::new().add_source_text("main.xuan", source);
+        assert!(compiler.emit("vm").is_empty());
+        assert!(compiler
+            .render_diagnostics()
+            .contains("build environment value \"MODE\" is not defined"));
+
+        let mut environment = BuildEnvironment::default();
+        environment.set("MODE", "release");
+        let mut compiler = Compiler::new()
+            .with_options(CompilerOptions {
+                environment,
+                ..CompilerOptions::default()
+            })
+            .add_source_text("main.xuan", source);
+        let artifacts = compiler.emit("vm");
+        assert!(!compiler.has_errors());
+
+        let m1 = module::read(&artifacts[0].1.bytes).unwrap();
+        assert!(m1.globals.is_empty());
+        assert!(!artifacts[0]
+            .1
+            .bytes
+            .windows(13)
+            .any(|w| w == b"static_assert"));
+    }
+
+    #[test]
+    fn test_analyze_intrinsics() {
+        // 分析阶段（即 `xuanc check`）就会检查编译时内置函数，并且它们不是未定义的名称
+        let source = "static_assert(2 > 1)\nconst V = env(\"V\")";
+        let compiler = Compiler::new().add_source_text("a.xuan", source).analyze();
+        let text = compiler.render_diagnostics();
+        assert_eq!(compiler.diagnostics().len(), 1);
+        assert!(text.contains("build environment value \"V\" is not defined"));
+        assert!(!text.contains("undefined name"));
+
+        let compiler = Compiler::new()
+            .add_source_text("a.xuan", "static_assert(1 > 2, \"unreachable\")")
+            .analyze();
+        assert!(compiler
+            .render_diagnostics()
+            .contains("static assertion failed: unreachable"));
+        assert!(compiler.program(0).is_none());
+    }
+
+    #[test]
+    fn test_entry_point() {
+        // 可执行程序需要入口函数
+        let mut compiler = Compiler::new().add_source_text("main.xuan", "writeLine(1)");
+        assert!(compiler.emit("vm").is_empty());
+        assert!(compiler
+            .render_diagnostics()
+            .starts_with("error[E0300]: no entry function \"main\" found"));
+
+        let mut compiler = Compiler::new().add_source_text("main.xuan", "function main(Int a) = a");
+        assert!(compiler.emit("vm").is_empty());
+        assert!(compiler
+            .render_diagnostics()
+            .contains("must not have required parameters"));
+
+        // 入口函数可以位于任意一个源文件，但只能有一个
+        let mut compiler = Compiler::new()
+            .add_source_text("lib.xuan", "function f() = 1")
+            .add_source_text("main.xuan", "function main() = writeLine(1)");
+        assert_eq!(compiler.emit("vm").len(), 2);
+
+        let mut compiler = Compiler::new()
+            .add_source_text("a.xuan", "function main() = 1")
+            .add_source_text("b.xuan", "function main() = 2");
+        assert!(compiler.emit("vm").is_empty());
+        assert_eq!(compiler.diagnostics()[0].source, Some(1));
+        assert!(compiler
+            .render_diagnostics()
+            .starts_with("error[E0300]: the entry function \"main\" is defined in 2 source files"));
+
+        // 库不需要入口函数，以及指定入口函数的名称
+        let mut compiler = Compiler::new()
+            .with_options(CompilerOptions {
+                entry: None,
+                ..CompilerOptions::default()
+            })
+            .add_source_text("lib.xuan", "const PI = 3.14");
+        assert_eq!(compiler.emit("vm").len(), 1);
+
+        let mut compiler = Compiler::new()
+            .with_options(CompilerOptions {
+                entry: Some("start".to_string()),
+                ..CompilerOptions::default()
+            })
+            .add_source_text("main.xuan", "function start() = 0");
+        assert_eq!(compiler.emit("vm").len(), 1);
+    }
+
+    #[test]
+    fn test_lints() {
+        // 检查项目的警告不影响生成产物
+        let source = "#[deprecated(\"use g instead\")]\nfunction f() = 1\n\
+            function main() = writeLine(f())";
+        let mut compiler = Compiler::new().add_source_text("main.xuan", source);
+        assert_eq!(compiler.emit("vm").len(), 1);
+        assert!(!compiler.has_errors());
+        assert_eq!(compiler.diagnostics().len(), 1);
+        assert_eq!(
+            compiler.render_diagnostics(),
+            [
+                "warning[deprecated]: function \"f\" is deprecated: use g instead",
+                " --> main.xuan:3:29",
+                "  |",
+                "1 | #[deprecated(\"use g instead\")]",
+                "  | ------------------------------ declared here",
+                "3 | function main() = writeLine(f())",
+                "  |                             ^",
+                "",
+            ]
+            .join("\n")
+        );
+
+        // 级别为 deny 的检查项目是错误
+        let source = "function main() = do {\nlet lineNo = 1\nwriteLine(lineNo)\n}";
+        let mut compiler = Compiler::new().add_source_text("main.xuan", source);
+        assert_eq!(compiler.emit("vm").len(), 1);
+        assert!(compiler
+            .render_diagnostics()
+            .starts_with("warning[naming_convention]: "));
+
+        let mut compiler = Compiler::new().add_source_text(
+            "main.xuan",
+            &format!("#[deny(naming_convention)]\n{}", source),
+        );
+        assert!(compiler.emit("vm").is_empty());
+        assert!(compiler.has_errors());
+        assert!(compiler.program(0).is_none());
+        assert!(compiler
+            .render_diagnostics()
+            .starts_with("error[naming_convention]: "));
+    }
+
+    #[test]
+    fn test_lint_options() {
+        let source = "function writeLine2(Int a) = if a > 0 then 1 else if a < 0 then -1 else 0\n\
+            function main() = writeLine(writeLine2(1))";
+        let names = |compiler: &Compiler| -> Vec<&'static str> {
+            compiler
+                .diagnostics()
+                .iter()
+                .map(|d| d.diagnostic.code)
+                .collect()
+        };
+
+        // 默认的选项
+        let compiler = Compiler::new()
+            .add_source_text("main.xuan", source)
+            .analyze();
+        assert_eq!(names(&compiler), vec!["naming_convention"]);
+
+        // 圈复杂度的上限以及名称的风格
+        let mut compiler = Compiler::new()
+            .with_lint_options(LintOptions {
+                max_complexity: 2,
+                function_case: NamingCase::CamelCase,
+                ..LintOptions::default()
+            })
+            .add_source_text("main.xuan", source);
+        assert_eq!(compiler.emit("vm").len(), 1);
+        assert_eq!(names(&compiler), vec!["cyclomatic_complexity"]);
+        assert_eq!(compiler.lint_options().max_complexity, 2);
+        assert!(compiler.render_diagnostics().starts_with(
+            "warning[cyclomatic_complexity]: function \"writeLine2\" has a cyclomatic complexity of 3, \
+            which exceeds the limit of 2\n"
+        ));
+    }
+
+    #[test]
+    fn test_select_backend() {
+        let mut compiler = Compiler::new().add_source_text("a.xuan", "let a = 1");
+        assert!(compiler.emit("llvm").is_empty());
+        assert_eq!(compiler.diagnostics()[0].source, None);
+        assert!(compiler
+            .render_diagnostics()
+            .starts_with("error[E0100]: unknown backend \"llvm\""));
+
+        let compiler = Compiler::new().with_registry(BackendRegistry::new());
+        assert!(compiler.registry().names().is_empty());
+    }
+
+    #[test]
+    fn test_update_source() {
+        let sources = |compiler: &Compiler| -> Vec<Option<usize>> {
+            compiler.diagnostics().iter().map(|d| d.source).collect()
+        };
+
+        let mut compiler = Compiler::new()
+            .add_source_text("a.xuan", "function f(Int a) = a + 1")
+            .add_source_text("b.xuan", "writeLine(foo)")
+            .analyze();
+        assert_eq!(sources(&compiler), vec![Some(1)]);
+
+        // 文本没有改变
+        assert!(!compiler.update_source_text(0, "function f(Int a) = a + 1"));
+        assert!(!compiler.update_source_text(2, ""));
+
+        // 文本没有改变时也可以重新分析，比如它导入的模块的接口改变了
+        compiler.invalidate(1);
+        assert!(sources(&compiler).is_empty());
+        compiler = compiler.analyze();
+        assert_eq!(sources(&compiler), vec![Some(1)]);
+
+        // 只有被修改的源文件被重新分析，其他源文件的诊断信息保持不变
+        assert!(compiler.update_source_text(0, "function f(Int a) = \"a"));
+        compiler = compiler.analyze();
+        assert_eq!(sources(&compiler), vec![Some(1), Some(0)]);
+        assert!(compiler
+            .render_diagnostics()
+            .contains("unterminated string literal"));
+
+        assert!(compiler.update_source_text(1, "writeLine(1)"));
+        compiler = compiler.analyze();
+        assert_eq!(sources(&compiler), vec![Some(0)]);
+
+        // 增量词法分析的结果跟完整的分析相同
+        assert!(compiler.update_source_text(0, "function f(Int a) = a + 2"));
+        assert!(compiler.update_source_text(0, "function f(Int a, Int b) = a + b"));
+        compiler = compiler.analyze();
+        assert!(!compiler.has_errors());
+
+        let fresh = Compiler::new()
+            .add_source_text("a.xuan", "function f(Int a, Int b) = a + b")
+            .analyze();
+        assert_eq!(compiler.program(0), fresh.program(0));
+
+        // 读取失败的源文件
+        let mut compiler = Compiler::new().add_source("not_exists.xuan");
+        assert!(compiler.update_source_text(0, "let a = 1"));
+        compiler = compiler.analyze();
+        assert!(!compiler.has_errors());
+        assert!(compiler.program(0).is_some());
+    }
+}
@@ -0,0 +1,382 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 输出编译过程中各个阶段的结果
+//
+// 以稳定的文本格式输出各个阶段的中间表示，以便编译器的开发者检查每个阶段的结果，
+// 比如诊断错误的编译结果（miscompile）：
+//
+// - `tokens`：Token 列表，格式见 `token::dump`；
+// - `ast`：语法分析得到的 AST；
+// - `ast-json`：JSON 格式的 AST，格式见 `json` 模块（需要 `serde` 特性）；
+// - `hir`：高层中间表示，目前即规范化（见 `normalize` 模块）之后的 AST；
+// - `ir`：低层中间表示，即带类型标注的 HIR 降级（见 `ir` 模块）之后的结果；
+// - `module`：最终的模块，即 `vm` 后端（见 `backend` 模块）生成的模块文件，
+//   以十六进制转储（hex dump）的格式输出，每行 16 个字节。
+//
+// 各个阶段意外的 panic 会被转换为 `Error::InternalCompilerError`（见 `ice` 模块）。
+//
+// 同一次运行可以输出多个阶段的结果（见 `emit_stages`），编译过程只运行一次，
+// 每个阶段输出到一个文件，文件名由源文件的主文件名以及阶段的扩展名组成（见 `output_path`）。
+//
+// 除了 `tokens` 以及 `module`，文本格式即 AST（或者 IR）的 Display 输出。
+//
+// 输出是可重现的：同样的源码以及选项总是得到逐字节相同的结果，
+// 为此各个阶段都不使用遍历顺序随机的 HashMap 以及 HashSet（见 `clippy.toml`），
+// 结果的顺序只依赖于源码里的顺序。
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    ast::Node,
+    backend::{BackendRegistry, CompilerOptions},
+    error::Error,
+    ice, ir, lexer, normalize, parser, token, typed,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Tokens,
+    Ast,
+    AstJson, // 需要 `serde` 特性
+    Hir,
+    Ir,
+    Module, // 最终的模块（字节码）
+}
+
+impl Stage {
+    pub const ALL: [Stage; 6] = [
+        Stage::Tokens,
+        Stage::Ast,
+        Stage::AstJson,
+        Stage::Hir,
+        Stage::Ir,
+        Stage::Module,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Tokens => "tokens",
+            Stage::Ast => "ast",
+            Stage::AstJson => "ast-json",
+            Stage::Hir => "hir",
+            Stage::Ir => "ir",
+            Stage::Module => "module",
+        }
+    }
+
+    // 输出文件的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Stage::Tokens => "tokens",
+            Stage::Ast => "ast",
+            Stage::AstJson => "ast.json",
+            Stage::Hir => "hir",
+            Stage::Ir => "ir",
+            Stage::Module => "ancm.hex",
+        }
+    }
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Stage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Stage::ALL
+            .into_iter()
+            .find(|stage| stage.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Stage::ALL.iter().map(|stage| stage.name()).collect();
+                format!(
+                    "unknown stage \"{}\", expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+// 解析以逗号分隔的阶段列表，比如 `tokens,ast,hir`，重复的阶段只保留一个
+pub fn parse_stages(text: &str) -> Result<Vec<Stage>, String> {
+    let mut stages = Vec::<Stage>::new();
+    for name in text.split(',').map(str::trim) {
+        let stage = name.parse::<Stage>()?;
+        if !stages.contains(&stage) {
+            stages.push(stage);
+        }
+    }
+    Ok(stages)
+}
+
+// 阶段的输出文件的路径，即在源文件的主文件名后面加上阶段的扩展名，
+// 比如 `src/main.xuan` 的 `hir` 阶段输出到 `{output_dir}/main.hir`。
+pub fn output_path(source_path: &Path, output_dir: &Path, stage: Stage) -> PathBuf {
+    let stem = source_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    output_dir.join(format!("{}.{}", stem, stage.extension()))
+}
+
+// 输出源码在指定阶段的结果
+pub fn emit(source: &str, stage: Stage) -> Result<String, Error> {
+    let mut outputs = emit_stages(source, &[stage])?;
+    Ok(outputs.remove(0).1)
+}
+
+// 只运行一次编译过程，输出源码在多个阶段的结果，结果的顺序跟 `stages` 一致
+pub fn emit_stages(source: &str, stages: &[Stage]) -> Result<Vec<(Stage, String)>, Error> {
+    // 先检查不支持的阶段，以免只输出一部分的结果
+    for stage in stages {
+        check_supported(*stage)?;
+    }
+
+    let token_details = ice::catch_ice("tokenize", || lexer::tokenize(source))?;
+    let has_stage = |list: &[Stage]| stages.iter().any(|stage| list.contains(stage));
+
+    let ast = if has_stage(&[
+        Stage::Ast,
+        Stage::AstJson,
+        Stage::Hir,
+        Stage::Ir,
+        Stage::Module,
+    ]) {
+        Some(ice::catch_ice("parse", || parser::parse(&token_details))?)
+    } else {
+        None
+    };
+
+    let hir = match &ast {
+        Some(node) if has_stage(&[Stage::Hir, Stage::Ir, Stage::Module]) => {
+            Some(ice::catch_ice("normalize", || {
+                let mut node = node.clone();
+                normalize::normalize(&mut node);
+                Ok(node)
+            })?)
+        }
+        _ => None,
+    };
+
+    let ir = match &hir {
+        Some(node) if has_stage(&[Stage::Ir]) => Some(ice::catch_ice("lower", || {
+            let mut node = node.clone();
+            typed::annotate_types(&mut node);
+            match node {
+                Node::Program(program) => ir::lower(&program),
+                _ => unreachable!(),
+            }
+        })?),
+        _ => None,
+    };
+
+    let module = match &hir {
+        Some(Node::Program(program)) if has_stage(&[Stage::Module]) => {
+            Some(ice::catch_ice("module", || {
+                BackendRegistry::with_builtin_backends()
+                    .lower(program, &CompilerOptions::default())
+                    .map(|artifact| artifact.bytes)
+            })?)
+        }
+        _ => None,
+    };
+
+    let outputs = stages
+        .iter()
+        .map(|stage| {
+            let text = match stage {
+                Stage::Tokens => token::dump(&token_details),
+                Stage::Ast => ast.as_ref().unwrap().to_string(),
+                Stage::AstJson => ast_json(ast.as_ref().unwrap()),
+                Stage::Hir => hir.as_ref().unwrap().to_string(),
+                Stage::Ir => ir.as_ref().unwrap().to_string(),
+                Stage::Module => hex_dump(module.as_ref().unwrap()),
+            };
+            (*stage, text)
+        })
+        .collect();
+
+    Ok(outputs)
+}
+
+fn check_supported(stage: Stage) -> Result<(), Error> {
+    match stage {
+        Stage::AstJson if cfg!(not(feature = "serde")) => Err(Error::ParserError(format!(
+            "the \"{}\" stage requires the \"serde\" feature",
+            stage
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// 每行依次为偏移量、16 个字节的十六进制值以及对应的可打印字符，比如：
+//
+// ```text
+// 00000000  61 6e 63 6d 02 00 00 00  03 00 00 00 06 01 00 00  |ancm............|
+// ```
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for (index, line) in bytes.chunks(16).enumerate() {
+        let mut hex = String::new();
+        for (position, byte) in line.iter().enumerate() {
+            if position == 8 {
+                hex.push(' ');
+            }
+            hex.push_str(&format!("{:02x} ", byte));
+        }
+        let chars: String = line
+            .iter()
+            .map(|byte| {
+                if byte.is_ascii_graphic() || *byte == b' ' {
+                    *byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        text.push_str(&format!("{:08x}  {:<49} |{}|\n", index * 16, hex, chars));
+    }
+    text
+}
+
+#[cfg(feature = "serde")]
+fn ast_json(node: &crate::ast::Node) -> String {
+    crate::json::to_json(node)
+}
+
+#[cfg(not(feature = "serde"))]
+fn ast_json(_node: &crate::ast::Node) -> String {
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::module::MODULE_FILE_VERSION;
+
+    use super::{emit, emit_stages, output_path, parse_stages, Stage};
+
+    #[test]
+    fn test_stage_names() {
+        for stage in Stage::ALL {
+            assert_eq!(stage.name().parse::<Stage>(), Ok(stage));
+        }
+        assert!("mir".parse::<Stage>().is_err());
+
+        assert_eq!(
+            parse_stages("tokens, hir,tokens"),
+            Ok(vec![Stage::Tokens, Stage::Hir])
+        );
+        assert!(parse_stages("ast,foo").is_err());
+
+        assert_eq!(
+            output_path(Path::new("src/main.xuan"), Path::new("out"), Stage::AstJson),
+            Path::new("out/main.ast.json")
+        );
+    }
+
+    #[test]
+    fn test_emit() {
+        let source = "let a = if b then {c} else {d}";
+
+        assert!(emit(source, Stage::Tokens).unwrap().contains("let"));
+        assert_eq!(
+            emit(source, Stage::Ast).unwrap(),
+            "let a = if b then {\nc\n} else {\nd\n}\n"
+        );
+        assert_eq!(
+            emit(source, Stage::Hir).unwrap(),
+            "let a = if b then c else d\n"
+        );
+
+        assert_eq!(
+            emit(source, Stage::Ir).unwrap(),
+            "let a\n\nfunction $init () {\n    (set @a\n        (if\n            @b\n            @c\n            @d))\n}\n"
+        );
+        // 模块文件的十六进制转储，开头是魔数以及格式的版本号
+        let module = emit(source, Stage::Module).unwrap();
+        assert!(module.starts_with(&format!(
+            "00000000  61 6e 63 6d {:02x} 00 00 00  ",
+            MODULE_FILE_VERSION
+        )));
+        assert!(module.contains("  |ancm"));
+        assert!(module.lines().all(|line| line.len() <= 78));
+        assert_eq!(
+            emit("const (#Int, String) Pair = (1, \"a\")", Stage::Module)
+                .unwrap_err()
+                .to_string(),
+            "invalid data type \"(#Int, String,)\", \
+            the members of a tuple data type should be data types"
+        );
+        assert!(emit("let a = (", Stage::Hir).is_err());
+
+        // 结果的顺序跟指定的顺序一致
+        let outputs = emit_stages(source, &[Stage::Hir, Stage::Ast]).unwrap();
+        assert_eq!(outputs[0], (Stage::Hir, emit(source, Stage::Hir).unwrap()));
+        assert_eq!(outputs[1], (Stage::Ast, emit(source, Stage::Ast).unwrap()));
+
+        #[cfg(feature = "serde")]
+        assert!(emit(source, Stage::AstJson)
+            .unwrap()
+            .starts_with("{\"version\":"));
+    }
+
+    #[test]
+    fn test_deterministic_output() {
+        let source = "\
+function f(Int a, Int b = 2) type Int = a + b
+function g(Any x) = match x {
+    case #foo: 1
+    case #bar: 2
+    default: 0
+}
+let m = {#a: 1, #b: 2.5, #c: \"s\"}
+let (p, [q, ...r]) = (1, [2, 3, 4])";
+
+        let stages: Vec<Stage> = Stage::ALL
+            .into_iter()
+            .filter(|stage| emit(source, *stage).is_ok())
+            .collect();
+        assert!(stages.contains(&Stage::Hir));
+        assert!(stages.contains(&Stage::Ir));
+
+        // 分别在两次独立的运行里输出
+        let first = emit_stages(source, &stages).unwrap();
+        let second = emit_stages(source, &stages).unwrap();
+        assert_eq!(first, second);
+
+        // 模块接口文件
+        #[cfg(feature = "serde")]
+        {
+            use crate::{
+                ast::Node,
+                interface::{module_interface, to_interface_file},
+                lexer, parser,
+            };
+
+            let interface_file = || {
+                let token_details = lexer::tokenize(source).unwrap();
+                match parser::parse(&token_details).unwrap() {
+                    Node::Program(program) => to_interface_file("foo", &module_interface(&program)),
+                    _ => unreachable!(),
+                }
+            };
+            assert_eq!(interface_file(), interface_file());
+        }
+    }
+}
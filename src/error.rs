@@ -5,9 +5,435 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.
  */
+use std::fmt::Display;
+
+use crate::{
+    ast::Range,
+    limit::ResourceLimit,
+    token::{Location, Token},
+};
 
 #[derive(Debug)]
 pub enum Error {
+    // 其他的词法错误
     LexerError(String),
+
+    // 其他的语法错误
     ParserError(String),
+
+    // 带有位置的其他词法错误，比如无效的字符
+    InvalidToken {
+        message: String,
+        location: Location,
+    },
+
+    // 带有位置的其他语法错误，比如无效的结构体名称
+    SyntaxError {
+        message: String,
+        range: Range,
+    },
+
+    // 字符串、字符、注释等缺少结束符号
+    UnterminatedLiteral {
+        kind: LiteralKind,
+        location: Location,
+    },
+
+    // 遇到了不符合预期的 Token
+    UnexpectedToken {
+        expected: Vec<String>, // 期望的 Token（或者语法成分）的集合，比如 `","` 和 `")"`
+        found: Option<Token>,  // 为 None 时表示已到了源代码的末尾
+        location: Location,
+        suggestion: Option<Box<Suggestion>>, // 修改建议（可选）
+    },
+
+    // 括号没有闭合：到达了源代码的末尾，或者遇到了不对应的结束括号（比如 `(a]`）
+    UnclosedDelimiter {
+        delimiter: Token,           // 没有闭合的开始括号
+        opening_location: Box<Location>, // 开始括号的位置
+        found: Option<Token>,       // 不对应的结束括号，为 None 时表示已到了源代码的末尾
+        location: Location,
+        suggestion: Option<Box<Suggestion>>, // 在合适的位置插入结束括号
+    },
+
+    // 无效的数字，比如数值超出了范围、格式错误等
+    InvalidNumber {
+        message: String,
+        location: Location,
+        source: Option<Box<dyn std::error::Error + Send + Sync>>, // 引起这个错误的底层错误
+    },
+
+    // 超出了资源限制（见 `limit` 模块）
+    LimitExceeded {
+        limit: ResourceLimit,
+        max: usize,
+        location: Location,
+    },
+
+    // 语义错误（见 `semantics` 模块）
+    SemanticError {
+        message: String,
+        related: Vec<(String, Range)>, // 跟错误相关的其他位置及其说明，比如所在的函数
+        range: Range,
+    },
+
+    // 调用重载函数时没有唯一匹配的函数（见 `overload` 模块）
+    OverloadError {
+        message: String,
+        candidates: Vec<(String, Range)>, // 候选函数的签名以及位置
+        range: Range,
+    },
+
+    // 编译器内部错误，即编译过程中意外的 panic（见 `ice` 模块），
+    // 这是编译器的缺陷而不是源代码的错误
+    InternalCompilerError {
+        stage: String,              // 发生错误的阶段，比如 "parse"
+        message: String,            // panic 的信息
+        location: Option<Location>, // 发生错误时正在处理的源代码的位置（如果已知）
+    },
+}
+
+// 警告，即不影响编译结果、但可能需要用户留意的问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub message: String,
+    pub location: Location,
+}
+
+// 修改建议，即在 range 所指的位置用 replacement 替换原有的文本，
+// 当 range 的开始和结束位置相同时，表示插入文本。
+//
+// 编辑器可以据此提供 "一键修复"（quick fix）。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub message: String,
+    pub range: Range,
+    pub replacement: String,
+}
+
+// 缺少结束符号的字面量（以及注释、属性等）的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiteralKind {
+    BlockComment,
+    DocumentComment,
+    Char,
+    String,
+    RawString,
+    TemplateString,
+    NamedOperator,
+    Attribute,
+}
+
+impl Error {
+    // 是否词法分析阶段的错误
+    pub fn is_lexer_error(&self) -> bool {
+        match self {
+            Error::LexerError(_)
+            | Error::InvalidToken { .. }
+            | Error::UnterminatedLiteral { .. }
+            | Error::InvalidNumber { .. } => true,
+            Error::LimitExceeded { limit, .. } => limit.is_lexical(),
+            _ => false,
+        }
+    }
+
+    // 错误代码，用于在文档里查找错误的详细说明
+    //
+    // - E00xx 词法错误
+    // - E01xx 语法错误
+    // - E02xx 资源限制
+    // - E03xx 语义错误
+    // - E9999 编译器内部错误
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::LexerError(_) => "E0001",
+            Error::InvalidToken { .. } => "E0002",
+            Error::UnterminatedLiteral { .. } => "E0003",
+            Error::InvalidNumber { .. } => "E0004",
+            Error::ParserError(_) => "E0100",
+            Error::SyntaxError { .. } => "E0101",
+            Error::UnexpectedToken { .. } => "E0102",
+            Error::UnclosedDelimiter { .. } => "E0103",
+            Error::LimitExceeded { .. } => "E0200",
+            Error::SemanticError { .. } => "E0300",
+            Error::OverloadError { .. } => "E0301",
+            Error::InternalCompilerError { .. } => "E9999",
+        }
+    }
+
+    // 期望的 Token 的集合，只有 UnexpectedToken 错误才有
+    pub fn expected(&self) -> &[String] {
+        match self {
+            Error::UnexpectedToken { expected, .. } => expected,
+            _ => &[],
+        }
+    }
+
+    // 修改建议
+    pub fn suggestion(&self) -> Option<&Suggestion> {
+        match self {
+            Error::UnexpectedToken {
+                suggestion: Some(suggestion),
+                ..
+            }
+            | Error::UnclosedDelimiter {
+                suggestion: Some(suggestion),
+                ..
+            } => Some(suggestion),
+            _ => None,
+        }
+    }
+
+    // 跟错误相关的其他位置及其说明，比如没有闭合的开始括号的位置
+    pub fn note(&self) -> Option<(String, &Location)> {
+        match self {
+            Error::UnclosedDelimiter {
+                delimiter,
+                opening_location,
+                ..
+            } => Some((
+                format!("unclosed \"{}\" opened here", delimiter),
+                opening_location,
+            )),
+            _ => None,
+        }
+    }
+
+    // 跟错误相关的其他 AST 节点的说明及其位置，比如调用重载函数时的各个候选函数，
+    // 或者语义错误所在的函数
+    pub fn related(&self) -> &[(String, Range)] {
+        match self {
+            Error::OverloadError { candidates, .. } => candidates,
+            Error::SemanticError { related, .. } => related,
+            _ => &[],
+        }
+    }
+
+    // 错误发生的位置
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::LexerError(_)
+            | Error::ParserError(_)
+            | Error::SyntaxError { .. }
+            | Error::SemanticError { .. }
+            | Error::OverloadError { .. } => None,
+            Error::InternalCompilerError { location, .. } => location.as_ref(),
+            Error::InvalidToken { location, .. }
+            | Error::UnterminatedLiteral { location, .. }
+            | Error::UnexpectedToken { location, .. }
+            | Error::UnclosedDelimiter { location, .. }
+            | Error::InvalidNumber { location, .. }
+            | Error::LimitExceeded { location, .. } => Some(location),
+        }
+    }
+
+    // 错误发生的范围，包括语法错误以及语义错误的 AST 节点的范围，
+    // 行号为 0 的位置（即未知的位置，比如源代码的末尾）视为没有范围
+    pub fn range(&self) -> Option<Range> {
+        match self {
+            Error::SyntaxError { range, .. }
+            | Error::SemanticError { range, .. }
+            | Error::OverloadError { range, .. } => Some(range.clone()),
+            _ => self
+                .location()
+                .filter(|location| location.line > 0)
+                .map(location_to_range),
+        }
+    }
+}
+
+pub(crate) fn location_to_range(location: &Location) -> Range {
+    Range {
+        file_id: location.file_id,
+        start: location.start,
+        end: location.end,
+    }
+}
+
+// 把期望的 Token 的集合连接为文本，比如 `"," or ")"`
+pub(crate) fn join_expected(expected: &[String]) -> String {
+    match expected.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => "nothing".to_string(),
+    }
+}
+
+impl Display for LiteralKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            LiteralKind::BlockComment => "block comment",
+            LiteralKind::DocumentComment => "document comment",
+            LiteralKind::Char => "char literal",
+            LiteralKind::String => "string literal",
+            LiteralKind::RawString => "raw string literal",
+            LiteralKind::TemplateString => "template string literal",
+            LiteralKind::NamedOperator => "named operator",
+            LiteralKind::Attribute => "attribute",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "warning: {}", self.message)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::LexerError(message) => write!(f, "{}", message),
+            Error::ParserError(message) => write!(f, "{}", message),
+            Error::InvalidToken { message, .. } => write!(f, "{}", message),
+            Error::SyntaxError { message, .. } => write!(f, "{}", message),
+            Error::UnterminatedLiteral { kind, .. } => write!(f, "unterminated {}", kind),
+            Error::UnexpectedToken {
+                expected, found, ..
+            } => {
+                let expected = join_expected(expected);
+                match found {
+                    Some(Token::NewLine) => write!(f, "expected {}, found new line", expected),
+                    Some(token) => write!(f, "expected {}, found \"{}\"", expected, token),
+                    None => write!(f, "expected {}, found end of file", expected),
+                }
+            }
+            Error::UnclosedDelimiter {
+                delimiter, found, ..
+            } => match found {
+                Some(token) => write!(
+                    f,
+                    "unclosed \"{}\", found mismatched \"{}\"",
+                    delimiter, token
+                ),
+                None => write!(f, "unclosed \"{}\", found end of file", delimiter),
+            },
+            Error::InvalidNumber { message, .. } => write!(f, "{}", message),
+            Error::LimitExceeded { limit, max, .. } => {
+                write!(f, "exceeded the maximum number of {} ({})", limit, max)
+            }
+            Error::SemanticError { message, .. } => write!(f, "{}", message),
+            Error::OverloadError { message, .. } => write!(f, "{}", message),
+            Error::InternalCompilerError { stage, message, .. } => write!(
+                f,
+                "internal compiler error in {}: {}, this is a bug in the compiler, \
+                please file a bug report with the source code",
+                stage, message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InvalidNumber {
+                source: Some(source),
+                ..
+            } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+
+    use crate::token::{Location, Token};
+
+    use super::{Error, LiteralKind};
+
+    fn new_location() -> Location {
+        Location {
+            file_id: 0,
+            start: 0,
+            end: 0,
+            line: 0,
+            column: 0,
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let e1 = Error::UnterminatedLiteral {
+            kind: LiteralKind::TemplateString,
+            location: new_location(),
+        };
+        assert_eq!(e1.to_string(), "unterminated template string literal");
+
+        let e2 = Error::UnexpectedToken {
+            expected: vec!["\")\"".to_string()],
+            found: Some(Token::Comma),
+            location: new_location(),
+            suggestion: None,
+        };
+        assert_eq!(e2.to_string(), "expected \")\", found \",\"");
+
+        let e3 = Error::UnexpectedToken {
+            expected: vec!["expression".to_string()],
+            found: None,
+            location: new_location(),
+            suggestion: None,
+        };
+        assert_eq!(e3.to_string(), "expected expression, found end of file");
+
+        let e4 = Error::UnexpectedToken {
+            expected: vec!["\"=\"".to_string()],
+            found: Some(Token::NewLine),
+            location: new_location(),
+            suggestion: None,
+        };
+        assert_eq!(e4.to_string(), "expected \"=\", found new line");
+        assert_eq!(e4.code(), "E0102");
+
+        // 期望多个 Token
+        let e6 = Error::UnexpectedToken {
+            expected: vec![
+                "\",\"".to_string(),
+                "\")\"".to_string(),
+                "\"]\"".to_string(),
+            ],
+            found: Some(Token::Integer(3)),
+            location: new_location(),
+            suggestion: None,
+        };
+        assert_eq!(
+            e6.to_string(),
+            "expected \",\", \")\" or \"]\", found \"3\""
+        );
+        assert_eq!(e6.expected().len(), 3);
+
+        // 未知的位置没有范围
+        assert!(e6.range().is_none());
+
+        let e5 = Error::UnclosedDelimiter {
+            delimiter: Token::LeftBracket,
+            opening_location: Box::new(new_location()),
+            found: Some(Token::RightParen),
+            location: new_location(),
+            suggestion: None,
+        };
+        assert_eq!(e5.to_string(), "unclosed \"[\", found mismatched \")\"");
+        assert_eq!(e5.note().unwrap().0, "unclosed \"[\" opened here");
+    }
+
+    #[test]
+    fn test_source() {
+        let cause = "99999999999999999999".parse::<i64>().unwrap_err();
+        let e1 = Error::InvalidNumber {
+            message: "invalid integer number \"99999999999999999999\"".to_string(),
+            location: new_location(),
+            source: Some(Box::new(cause.clone())),
+        };
+
+        assert!(e1.is_lexer_error());
+        assert_eq!(e1.source().map(|e| e.to_string()), Some(cause.to_string()));
+
+        let e2 = Error::ParserError("invalid literal".to_string());
+        assert!(!e2.is_lexer_error());
+        assert!(e2.source().is_none());
+        assert!(e2.location().is_none());
+    }
 }
@@ -0,0 +1,567 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 常量折叠
+//
+// 在编译时计算只由字面量组成的表达式的值，比如 `-(1 + 2) * 3`。
+// 语义检查使用折叠的结果检查范围表达式的边界等。
+//
+// 目前支持：
+//
+// - 整数、浮点数、复数、比特、布尔值、字符以及字符串字面量；
+// - 整数、浮点数和复数的取负，以及加、减、乘、除运算；
+// - 布尔值的逻辑与、逻辑或运算；
+// - 相同类型的常量的相等比较，整数、浮点数、字符以及字符串的大小比较；
+// - 字符串以及比特的连接运算 `++`；
+// - 读取构建环境值的 `env("NAME")` 表达式（见 `BuildEnvironment`）。
+//
+// 静态断言 `static_assert(condition, "message")` 的条件也由常量折叠求值，
+// 见 `semantics::check_static_assertions`。
+//
+// 整数运算溢出或者除以零时不折叠（由运行时处理）。
+//
+// 数值类型的运算规则：
+//
+// - 整数只能跟整数运算，浮点数只能跟浮点数运算（需要显式转换）；
+// - 复数可以跟整数、浮点数以及复数运算，整数和浮点数被当作虚部为 0 的复数，
+//   结果为复数（复数字面量 `3+4i` 本身就是整数跟虚数的加法）；
+// - 复数不能比较大小。
+//
+// 比特类型的运算规则：
+//
+// - 两个比特值之间的运算要求宽度相同，宽度不同时需要先使用 `^` 显式转换；
+// - 连接运算 `++` 的结果宽度为两者宽度之和，左操作数为高位；
+// - 移位运算（右操作数为整数）的结果宽度等于左操作数的宽度。
+
+use std::{cmp::Ordering, collections::BTreeMap, fmt::Display};
+
+use crate::ast::{
+    self, Argument, BinaryOperator, Expression, FunctionCallExpression, GeneralString, Literal,
+    Program, Range, Statement, Tuple, UnaryOperator,
+};
+
+// 读取构建环境值的内置函数的名称
+pub const ENV_FUNCTION_NAME: &str = "env";
+
+// 静态断言的内置函数的名称
+pub const STATIC_ASSERT_FUNCTION_NAME: &str = "static_assert";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Integer(i64),
+    Float(f64),
+    Complex(f64, f64),   // 实部和虚部
+    Bit(usize, Vec<u8>), // 宽度以及数值（高位在前）
+    Boolean(bool),
+    Char(char),
+    String(String),
+}
+
+impl Constant {
+    // 常量的数据类型名称
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Constant::Integer(_) => "Int",
+            Constant::Float(_) => "Float",
+            Constant::Complex(_, _) => "Complex",
+            Constant::Bit(_, _) => "Bit",
+            Constant::Boolean(_) => "Boolean",
+            Constant::Char(_) => "Char",
+            Constant::String(_) => "String",
+        }
+    }
+
+    // 是否数值（整数、浮点数、复数）
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Constant::Integer(_) | Constant::Float(_) | Constant::Complex(_, _)
+        )
+    }
+
+    fn is_complex(&self) -> bool {
+        matches!(self, Constant::Complex(_, _))
+    }
+
+    // 转换为复数，非数值返回 None
+    fn to_complex(&self) -> Option<(f64, f64)> {
+        match self {
+            Constant::Integer(v) => Some((*v as f64, 0.0)),
+            Constant::Float(v) => Some((*v, 0.0)),
+            Constant::Complex(real, imaginary) => Some((*real, *imaginary)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Constant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constant::Integer(v) => write!(f, "{}", v),
+            Constant::Float(v) => write!(f, "{:?}", v),
+            Constant::Complex(real, imaginary) => {
+                // 使用跟复数字面量相同的格式，以便重新解析
+                let complex = ast::Complex {
+                    real: *real,
+                    imaginary: *imaginary,
+                    range: new_range(),
+                };
+                write!(f, "{}", complex)
+            }
+            Constant::Bit(width, bytes) => {
+                let bit = ast::Bit {
+                    width: *width,
+                    bytes: bytes.clone(),
+                    range: new_range(),
+                };
+                write!(f, "{}", bit)
+            }
+            Constant::Boolean(v) => write!(f, "{}", v),
+            Constant::Char(v) => write!(f, "{:?}", v),
+            Constant::String(v) => write!(f, "{:?}", v),
+        }
+    }
+}
+
+// 构建环境
+//
+// 由编译器选项（比如 `--env VERSION=1.0.0`）在编译时注入的值，
+// 程序通过 `env("VERSION")` 表达式读取，其结果为字符串常量，比如：
+//
+// `const VERSION = env("VERSION")`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildEnvironment {
+    values: BTreeMap<String, String>,
+}
+
+impl BuildEnvironment {
+    pub fn set(&mut self, name: &str, value: &str) {
+        self.values.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|v| v.as_str())
+    }
+}
+
+// 是否调用内置函数 `env`
+pub fn is_env_call(call: &FunctionCallExpression) -> bool {
+    matches!(call.callee.as_ref(), Expression::Identifier(i)
+        if i.dirs.is_empty() && i.generics.is_empty() && i.name == ENV_FUNCTION_NAME)
+}
+
+// 读取 `env("NAME")` 表达式的名称，名称必须是唯一的一个（不带参数名称的）字符串字面量
+pub fn env_name(call: &FunctionCallExpression) -> Option<&str> {
+    if !is_env_call(call) {
+        return None;
+    }
+
+    match call.arguments.as_slice() {
+        [argument] if argument.name.is_none() => match argument.value.as_ref() {
+            Expression::Literal(Literal::GeneralString(s)) => Some(s.value.as_str()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// 是否调用内置函数 `static_assert`
+pub fn is_static_assert_call(call: &FunctionCallExpression) -> bool {
+    matches!(call.callee.as_ref(), Expression::Identifier(i)
+        if i.dirs.is_empty() && i.generics.is_empty() && i.name == STATIC_ASSERT_FUNCTION_NAME)
+}
+
+// 读取 `static_assert(condition, "message")` 表达式的条件以及信息，
+// 参数必须是一个条件以及可选的一个字符串字面量，并且都不带参数名称
+pub fn static_assertion(call: &FunctionCallExpression) -> Option<(&Argument, Option<&str>)> {
+    if !is_static_assert_call(call) || call.arguments.iter().any(|a| a.name.is_some()) {
+        return None;
+    }
+
+    match call.arguments.as_slice() {
+        [condition] => Some((condition, None)),
+        [condition, message] => match message.value.as_ref() {
+            Expression::Literal(Literal::GeneralString(s)) => Some((condition, Some(&s.value))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// 展开程序里的编译时内置函数，展开之后的程序可以交给后端降级：
+//
+// - `env("NAME")` 表达式替换为构建环境值的字符串字面量；
+// - 静态断言不产生运行时的代码，作为语句时被删除，作为子表达式时替换为空元组 `()`。
+//
+// 注：名称未定义的 `env` 表达式由 `semantics::check_build_environment` 报告，
+// 失败的静态断言由 `semantics::check_static_assertions` 报告，本函数不再检查。
+pub fn expand_intrinsics(program: &mut Program, environment: &BuildEnvironment) {
+    program.body.retain(|statement| {
+        !matches!(statement, Statement::Expression(Expression::FunctionCallExpression(v))
+            if is_static_assert_call(v))
+    });
+
+    for statement in &mut program.body {
+        for expression in statement.expressions_mut() {
+            expand_expression(expression, environment);
+        }
+    }
+}
+
+fn expand_expression(expression: &mut Expression, environment: &BuildEnvironment) {
+    if let Expression::FunctionCallExpression(v) = expression {
+        if let Some(value) = env_name(v).and_then(|name| environment.get(name)) {
+            *expression = Expression::Literal(Literal::GeneralString(GeneralString {
+                value: value.to_string(),
+                range: v.range.clone(),
+            }));
+            return;
+        }
+
+        if is_static_assert_call(v) {
+            *expression = Expression::Tuple(Tuple {
+                elements: vec![],
+                range: v.range.clone(),
+            });
+            return;
+        }
+    }
+
+    for child in expression.children_mut() {
+        expand_expression(child, environment);
+    }
+}
+
+// 计算表达式的常量值，表达式不是常量（或者无法在编译时计算）时返回 None
+//
+// 注：`env("NAME")` 表达式在没有构建环境时不是常量，见 `fold_with_environment`。
+pub fn fold(expression: &Expression) -> Option<Constant> {
+    fold_with_environment(expression, &BuildEnvironment::default())
+}
+
+// 计算表达式的常量值，`env("NAME")` 表达式的值从 `environment` 读取，
+// 名称未定义时表达式不是常量（由语义检查报告）。
+pub fn fold_with_environment(
+    expression: &Expression,
+    environment: &BuildEnvironment,
+) -> Option<Constant> {
+    let fold = |e: &Expression| fold_with_environment(e, environment);
+
+    match expression {
+        Expression::Literal(literal) => fold_literal(literal),
+        Expression::FunctionCallExpression(v) => {
+            let value = environment.get(env_name(v)?)?;
+            Some(Constant::String(value.to_string()))
+        }
+        Expression::UnaryExpression(v) if v.operator == UnaryOperator::Minus => {
+            match fold(&v.operand)? {
+                Constant::Integer(i) => i.checked_neg().map(Constant::Integer),
+                Constant::Float(f) => Some(Constant::Float(-f)),
+                Constant::Complex(real, imaginary) => Some(Constant::Complex(-real, -imaginary)),
+                _ => None,
+            }
+        }
+        Expression::BinaryExpression(v) => fold_binary(v.operator, fold(&v.left)?, fold(&v.right)?),
+        _ => None,
+    }
+}
+
+fn fold_literal(literal: &Literal) -> Option<Constant> {
+    let constant = match literal {
+        Literal::Integer(v) => Constant::Integer(v.value),
+        Literal::Float(v) => Constant::Float(v.value),
+        Literal::Complex(v) => Constant::Complex(v.real, v.imaginary),
+        Literal::Bit(v) => Constant::Bit(v.width, v.bytes.clone()),
+        Literal::Boolean(v) => Constant::Boolean(v.value),
+        Literal::Char(v) => Constant::Char(v.value),
+        Literal::GeneralString(v) => Constant::String(v.value.clone()),
+        _ => return None,
+    };
+
+    Some(constant)
+}
+
+fn fold_binary(operator: BinaryOperator, left: Constant, right: Constant) -> Option<Constant> {
+    if let Some(result) = fold_comparison(operator, &left, &right) {
+        return Some(Constant::Boolean(result));
+    }
+
+    if matches!(left, Constant::Complex(_, _)) || matches!(right, Constant::Complex(_, _)) {
+        return fold_complex_binary(operator, left.to_complex()?, right.to_complex()?);
+    }
+
+    let constant = match (left, right) {
+        (Constant::Integer(l), Constant::Integer(r)) => Constant::Integer(match operator {
+            BinaryOperator::Plus => l.checked_add(r)?,
+            BinaryOperator::Minus => l.checked_sub(r)?,
+            BinaryOperator::Asterisk => l.checked_mul(r)?,
+            BinaryOperator::Slash => l.checked_div(r)?,
+            _ => return None,
+        }),
+        (Constant::Float(l), Constant::Float(r)) => Constant::Float(match operator {
+            BinaryOperator::Plus => l + r,
+            BinaryOperator::Minus => l - r,
+            BinaryOperator::Asterisk => l * r,
+            BinaryOperator::Slash => l / r,
+            _ => return None,
+        }),
+        (Constant::Boolean(l), Constant::Boolean(r)) => Constant::Boolean(match operator {
+            BinaryOperator::LogicAnd => l && r,
+            BinaryOperator::LogicOr => l || r,
+            _ => return None,
+        }),
+        (Constant::String(l), Constant::String(r)) if operator == BinaryOperator::Concat => {
+            Constant::String(l + &r)
+        }
+        (Constant::Bit(l_width, l_bytes), Constant::Bit(r_width, r_bytes))
+            if operator == BinaryOperator::Concat =>
+        {
+            let mut bits = to_bits(l_width, &l_bytes);
+            bits.extend(to_bits(r_width, &r_bytes));
+            Constant::Bit(l_width + r_width, from_bits(&bits))
+        }
+        _ => return None,
+    };
+
+    Some(constant)
+}
+
+// 比较运算，不是比较运算或者常量不能比较时返回 None
+fn fold_comparison(operator: BinaryOperator, left: &Constant, right: &Constant) -> Option<bool> {
+    let ordering = match (left, right) {
+        (Constant::Integer(l), Constant::Integer(r)) => l.partial_cmp(r),
+        (Constant::Float(l), Constant::Float(r)) => l.partial_cmp(r),
+        (Constant::Char(l), Constant::Char(r)) => l.partial_cmp(r),
+        (Constant::String(l), Constant::String(r)) => l.partial_cmp(r),
+        _ => None,
+    };
+
+    match operator {
+        BinaryOperator::Equal | BinaryOperator::NotEqual => {
+            // 复数可以跟整数、浮点数比较是否相等
+            let equal = match (left.to_complex(), right.to_complex()) {
+                (Some(l), Some(r)) if left.is_complex() || right.is_complex() => l == r,
+                _ if left.type_name() == right.type_name() => match (left, right) {
+                    (Constant::Bit(l_width, l_bytes), Constant::Bit(r_width, r_bytes)) => {
+                        l_width == r_width
+                            && to_bits(*l_width, l_bytes) == to_bits(*r_width, r_bytes)
+                    }
+                    _ => left == right,
+                },
+                _ => return None,
+            };
+            Some(equal == (operator == BinaryOperator::Equal))
+        }
+        BinaryOperator::GreaterThan => ordering.map(Ordering::is_gt),
+        BinaryOperator::GreaterThanOrEqual => ordering.map(Ordering::is_ge),
+        BinaryOperator::LessThan => ordering.map(Ordering::is_lt),
+        BinaryOperator::LessThanOrEqual => ordering.map(Ordering::is_le),
+        _ => None,
+    }
+}
+
+fn fold_complex_binary(
+    operator: BinaryOperator,
+    (a, b): (f64, f64),
+    (c, d): (f64, f64),
+) -> Option<Constant> {
+    let (real, imaginary) = match operator {
+        BinaryOperator::Plus => (a + c, b + d),
+        BinaryOperator::Minus => (a - c, b - d),
+        BinaryOperator::Asterisk => (a * c - b * d, a * d + b * c),
+        BinaryOperator::Slash => {
+            let denominator = c * c + d * d;
+            if denominator == 0.0 {
+                return None;
+            }
+            ((a * c + b * d) / denominator, (b * c - a * d) / denominator)
+        }
+        _ => return None,
+    };
+
+    Some(Constant::Complex(real, imaginary))
+}
+
+// 把比特值转换为指定宽度的二进制位（高位在前），超出宽度的高位被丢弃
+fn to_bits(width: usize, bytes: &[u8]) -> Vec<bool> {
+    let all_bits: Vec<bool> = bytes
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| byte & (1 << i) != 0))
+        .collect();
+
+    if all_bits.len() >= width {
+        all_bits[all_bits.len() - width..].to_vec()
+    } else {
+        let mut bits = vec![false; width - all_bits.len()];
+        bits.extend(all_bits);
+        bits
+    }
+}
+
+// 把二进制位（高位在前）转换为字节，不足 8 位的部分在高位补 0
+fn from_bits(bits: &[bool]) -> Vec<u8> {
+    let padding = (8 - bits.len() % 8) % 8;
+    let padded: Vec<bool> = std::iter::repeat_n(false, padding)
+        .chain(bits.iter().copied())
+        .collect();
+
+    padded
+        .chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .fold(0u8, |byte, bit| (byte << 1) | (*bit as u8))
+        })
+        .collect()
+}
+
+fn new_range() -> Range {
+    Range {
+        file_id: 0,
+        start: 0,
+        end: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{BinaryExpression, BinaryOperator, Bit, Expression, Literal, Node, Statement},
+        lexer, parser,
+    };
+
+    use super::{fold, fold_with_environment, new_range, BuildEnvironment, Constant};
+
+    fn fold_source(text: &str) -> Option<Constant> {
+        let token_details = lexer::tokenize(text).unwrap();
+        match parser::parse(&token_details).unwrap() {
+            Node::Program(program) => match &program.body[0] {
+                Statement::Expression(e) => fold(e),
+                _ => panic!("expected an expression"),
+            },
+            _ => panic!("expected a program"),
+        }
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold_source("-(1 + 2) * 3"), Some(Constant::Integer(-9)));
+        assert_eq!(fold_source("7 / 2"), Some(Constant::Integer(3)));
+        assert_eq!(fold_source("1.5 * 2.0"), Some(Constant::Float(3.0)));
+        assert_eq!(fold_source("true && false"), Some(Constant::Boolean(false)));
+        assert_eq!(
+            fold_source("\"foo\" ++ \"bar\""),
+            Some(Constant::String("foobar".to_string()))
+        );
+
+        // 复数
+        assert_eq!(fold_source("3+4i"), Some(Constant::Complex(3.0, 4.0)));
+        assert_eq!(
+            fold_source("(1+2i) * (3-4i)"),
+            Some(Constant::Complex(11.0, 2.0))
+        );
+        assert_eq!(fold_source("2i + 1.5"), Some(Constant::Complex(1.5, 2.0)));
+        assert_eq!(fold_source("(4+2i) / 2"), Some(Constant::Complex(2.0, 1.0)));
+        assert_eq!(fold_source("-(1+2i)"), Some(Constant::Complex(-1.0, -2.0)));
+        assert_eq!(fold_source("(1+2i) / 0"), None);
+        assert_eq!(fold_source("(1+2i) < 3"), None);
+        assert_eq!(fold_source("(1+2i) + true"), None);
+
+        // 比较
+        assert_eq!(fold_source("1 + 2 == 3"), Some(Constant::Boolean(true)));
+        assert_eq!(fold_source("1.5 < 1.0"), Some(Constant::Boolean(false)));
+        assert_eq!(fold_source("'a' <= 'b'"), Some(Constant::Boolean(true)));
+        assert_eq!(
+            fold_source("\"foo\" != \"bar\""),
+            Some(Constant::Boolean(true))
+        );
+        assert_eq!(fold_source("(1+0i) == 1"), Some(Constant::Boolean(true)));
+        assert_eq!(fold_source("true > false"), None);
+        assert_eq!(fold_source("1 == 1.0"), None);
+
+        // 不是常量
+        assert_eq!(fold_source("a + 1"), None);
+
+        // 类型不同、除以零以及溢出
+        assert_eq!(fold_source("1 + 2.0"), None);
+        assert_eq!(fold_source("1 / 0"), None);
+        assert_eq!(fold_source("9223372036854775807 + 1"), None);
+    }
+
+    #[test]
+    fn test_fold_bit() {
+        let new_bit = |width: usize, bytes: Vec<u8>| {
+            Box::new(Expression::Literal(Literal::Bit(Bit {
+                width,
+                bytes,
+                range: new_range(),
+            })))
+        };
+
+        // 4'xa ++ 8'x0f == 12'xa0f
+        let e1 = Expression::BinaryExpression(BinaryExpression {
+            operator: BinaryOperator::Concat,
+            left: new_bit(4, vec![0xa]),
+            right: new_bit(8, vec![0x0f]),
+            range: new_range(),
+        });
+        assert_eq!(fold(&e1), Some(Constant::Bit(12, vec![0x0a, 0x0f])));
+        assert_eq!(fold(&e1).unwrap().to_string(), "12'x0a0f");
+
+        // 1'x1 ++ 1'x0 == 2'x2
+        let e2 = Expression::BinaryExpression(BinaryExpression {
+            operator: BinaryOperator::Concat,
+            left: new_bit(1, vec![0x1]),
+            right: new_bit(1, vec![0x0]),
+            range: new_range(),
+        });
+        assert_eq!(fold(&e2), Some(Constant::Bit(2, vec![0x2])));
+    }
+
+    #[test]
+    fn test_fold_env() {
+        let token_details =
+            lexer::tokenize("\"v\" ++ env(\"VERSION\") ++ env(\"SUFFIX\")").unwrap();
+        let expression = match parser::parse(&token_details).unwrap() {
+            Node::Program(program) => match &program.body[0] {
+                Statement::Expression(e) => e.clone(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        let mut environment = BuildEnvironment::default();
+        environment.set("VERSION", "1.0.0");
+
+        // 没有构建环境，以及名称未定义
+        assert_eq!(fold(&expression), None);
+        assert_eq!(fold_with_environment(&expression, &environment), None);
+
+        environment.set("SUFFIX", "-beta");
+        assert_eq!(
+            fold_with_environment(&expression, &environment),
+            Some(Constant::String("v1.0.0-beta".to_string()))
+        );
+
+        // 不是 `env` 函数，或者参数不是字符串字面量
+        assert_eq!(fold_source("envs(\"VERSION\")"), None);
+        assert_eq!(fold_source("env(\"VER\" ++ \"SION\")"), None);
+    }
+
+    #[test]
+    fn test_complex_round_trip() {
+        for text in ["(1+2i) * (3-4i)", "1i * 1i", "0 - (3+4i)", "-(1.5+2.5i)"] {
+            let c1 = fold_source(text).unwrap();
+            let c2 = fold_source(&c1.to_string()).unwrap();
+            assert_eq!(c1, c2, "{} => {}", text, c1);
+        }
+
+        assert_eq!(fold_source("1i * 1i").unwrap().to_string(), "(-1)+0i");
+        assert_eq!(fold_source("0 - (3+4i)").unwrap().to_string(), "(-3)-4i");
+    }
+}
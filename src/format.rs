@@ -0,0 +1,308 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 源代码的格式化
+//
+// 基于 Token 以及琐碎内容（见 `lexer::tokenize_with_trivia`）调整空白，注释原样保留：
+//
+// - 每行的缩进为 4 个空格乘以所在的括号（`{`、`(` 以及 `[`）的层数，以结束括号开始的行
+//   少缩进一层；上一行以赋值符号、二元运算符或者 `then`、`else`、`do` 结束时，
+//   该行是上一行的延续，多缩进一层；
+// - Token 之间连续的空白替换为一个空格，删除行尾的空白；
+// - 不增加或者删除 Token（包括换行符），所以格式化之后的 Token 列表跟原来的相同；
+// - 跨行的字符串以及注释的内容保持原样。
+//
+// 除了格式化整个源代码（`format`），还可以只格式化某个范围或者某一条语句（`format_range`，
+// 对应 LSP 的 `textDocument/rangeFormatting`），此时缩进根据范围之前的源代码计算，
+// 范围之外的文本保持不变。
+
+use crate::{
+    ast::Range,
+    error::Error,
+    lexer::{self, TextEdit},
+    token::{Token, TokenOrTrivia, Trivia},
+};
+
+const INDENT: &str = "    ";
+
+// 格式化整个源代码
+pub fn format(source: &str) -> Result<String, Error> {
+    Ok(Formatter::run(source)?.output)
+}
+
+// 只格式化 range 所覆盖的行，返回对这些行的替换
+//
+// 范围会扩展到所在行的开头以及结尾（跨行的字符串以及注释视为同一行），
+// 格式化一条语句时，使用语句的范围即可。
+pub fn format_range(source: &str, range: &Range) -> Result<TextEdit, Error> {
+    let formatter = Formatter::run(source)?;
+    let lines = &formatter.lines;
+
+    // 第一项总是 (0, 0)
+    let (start, output_start) = *lines
+        .iter()
+        .rev()
+        .find(|(offset, _)| *offset <= range.start)
+        .unwrap();
+    let (end, output_end) = lines
+        .iter()
+        .find(|(offset, _)| *offset >= range.end && *offset > start)
+        .copied()
+        .unwrap_or((source.len(), formatter.output.len()));
+
+    Ok(TextEdit {
+        start,
+        end,
+        text: formatter.output[output_start..output_end].to_string(),
+    })
+}
+
+#[derive(Default)]
+struct Formatter {
+    output: String,
+    lines: Vec<(usize, usize)>, // 每一行的开头在源代码以及输出里的位置
+    depth: usize,               // 括号的层数
+    is_line_start: bool,
+    is_continuation: bool, // 当前行是否上一行的延续
+    has_space: bool,       // 下一个 Token 之前是否有空白
+    last_token: Option<Token>,
+}
+
+impl Formatter {
+    fn run(source: &str) -> Result<Self, Error> {
+        let mut formatter = Formatter {
+            lines: vec![(0, 0)],
+            is_line_start: true,
+            ..Formatter::default()
+        };
+
+        for item in lexer::tokenize_with_trivia(source)? {
+            match item {
+                TokenOrTrivia::Trivia(detail) => match &detail.trivia {
+                    Trivia::Whitespace(_) => formatter.has_space = true,
+                    // 行注释不包括换行符，但包括行尾的空白
+                    Trivia::LineComment(text) => formatter.write(text.trim_end(), None),
+                    trivia => formatter.write(&trivia.to_string(), None),
+                },
+                TokenOrTrivia::Token(detail) => {
+                    let location = &detail.location;
+                    let text = &source[location.start..location.end];
+                    if detail.token == Token::NewLine && text != ";" {
+                        formatter.new_line(text, location.end);
+                    } else {
+                        formatter.write(text, Some(detail.token));
+                    }
+                }
+            }
+        }
+
+        Ok(formatter)
+    }
+
+    fn new_line(&mut self, text: &str, end: usize) {
+        self.output.push_str(text);
+        self.lines.push((end, self.output.len()));
+        self.is_line_start = true;
+        self.has_space = false;
+        self.is_continuation = self.last_token.as_ref().is_some_and(is_continued);
+        self.last_token = None;
+    }
+
+    // 输出 Token 或者注释（token 为 None）
+    fn write(&mut self, text: &str, token: Option<Token>) {
+        let is_closing = matches!(
+            token,
+            Some(Token::RightBrace | Token::RightParen | Token::RightBracket)
+        );
+
+        if self.is_line_start {
+            let level = self.depth - usize::from(is_closing && self.depth > 0)
+                + usize::from(self.is_continuation);
+            self.output.push_str(&INDENT.repeat(level));
+            self.is_line_start = false;
+        } else if self.has_space {
+            self.output.push(' ');
+        }
+        self.has_space = false;
+        self.output.push_str(text);
+
+        match token {
+            Some(Token::LeftBrace | Token::LeftParen | Token::LeftBracket) => self.depth += 1,
+            _ if is_closing => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
+
+        // 行尾的注释不影响下一行是否上一行的延续
+        if token.is_some() {
+            self.last_token = token;
+        }
+    }
+}
+
+// 以该 Token 结束的行，下一行是否为其延续
+//
+// 注：`>` 不包括在内，因为它经常是泛型类型列表的结束符号，比如 `type List<Int>`。
+fn is_continued(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Assign
+            | Token::Pipe
+            | Token::LogicOr
+            | Token::LogicAnd
+            | Token::Equal
+            | Token::NotEqual
+            | Token::GreaterThanOrEqual
+            | Token::LessThanOrEqual
+            | Token::Concat
+            | Token::Plus
+            | Token::Minus
+            | Token::Asterisk
+            | Token::Slash
+            | Token::OptionalOr
+            | Token::OptionalAnd
+            | Token::Combine
+            | Token::Then
+            | Token::Else
+            | Token::Do
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Range,
+        lexer::{self, TextEdit},
+        token::Token,
+    };
+
+    use super::{format, format_range};
+
+    fn tokens(source: &str) -> Vec<Token> {
+        lexer::tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.token)
+            .collect()
+    }
+
+    fn range(start: usize, end: usize) -> Range {
+        Range {
+            file_id: 0,
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let source = [
+            "function  add(Int a,",
+            "\tInt b)  =",
+            "a +   b   // 求和  ",
+            "",
+            "let   list = [",
+            "1, /* 一 */ 2,",
+            "        [3,",
+            "4]",
+            "      ]",
+            "let s = \"多行",
+            "  字符串\"; let t = 1",
+        ]
+        .join("\n");
+        assert_eq!(
+            format(&source).unwrap(),
+            [
+                "function add(Int a,",
+                "    Int b) =",
+                "    a + b // 求和",
+                "",
+                "let list = [",
+                "    1, /* 一 */ 2,",
+                "    [3,",
+                "        4]",
+                "]",
+                "let s = \"多行",
+                "  字符串\"; let t = 1",
+            ]
+            .join("\n")
+        );
+
+        // 格式化不改变 Token，并且是幂等的
+        for source in [&source, include_str!("../scripts/01-base-expression.xuan")] {
+            let formatted = format(source).unwrap();
+            assert_eq!(tokens(&formatted), tokens(source));
+            assert_eq!(format(&formatted).unwrap(), formatted);
+        }
+
+        // 泛型类型列表之后的行不是延续
+        assert_eq!(
+            format("empty function f() type List<Int>\n  let a = 1").unwrap(),
+            "empty function f() type List<Int>\nlet a = 1"
+        );
+    }
+
+    #[test]
+    fn test_format_range() {
+        let source = [
+            "let a  =  1",
+            "function f() = {",
+            "let b  =  2",
+            "        let c =",
+            "3",
+            "}",
+            "let d  =  4",
+        ]
+        .join("\n");
+        let source = source.as_str();
+
+        // 只格式化第三行，缩进根据之前的源代码计算
+        let start = source.find("let b").unwrap();
+        let edit = format_range(source, &range(start + 2, start + 3)).unwrap();
+        assert_eq!(
+            edit,
+            TextEdit {
+                start,
+                end: start + 12,
+                text: "    let b = 2\n".to_string(),
+            }
+        );
+
+        // 跨越多行的范围（比如一条语句），范围之外的文本保持不变
+        let start = source.find("        let c").unwrap();
+        let end = source.find("\n}").unwrap();
+        let edit = format_range(source, &range(start, end)).unwrap();
+        assert_eq!(edit.text, "    let c =\n        3\n");
+        let text = format!(
+            "{}{}{}",
+            &source[..edit.start],
+            edit.text,
+            &source[edit.end..]
+        );
+        assert_eq!(
+            text,
+            [
+                "let a  =  1",
+                "function f() = {",
+                "let b  =  2",
+                "    let c =",
+                "        3",
+                "}",
+                "let d  =  4",
+            ]
+            .join("\n")
+        );
+
+        // 最后一行，以及空的范围
+        let edit = format_range(source, &range(source.len(), source.len())).unwrap();
+        assert_eq!(edit.text, "let d = 4");
+        assert_eq!(edit.end, source.len());
+        let edit = format_range(source, &range(0, 0)).unwrap();
+        assert_eq!((edit.start, edit.end), (0, 12));
+        assert_eq!(edit.text, "let a = 1\n");
+    }
+}
@@ -0,0 +1,578 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 函数的栈帧（frame）大小
+//
+// 估算每个函数运行时需要的操作数栈（operand stack）的最大深度，以及局部变量槽（slot）的数量，
+// 以便 XiaoXuan Core VM 在调用函数时一次分配足够大的栈帧，而不需要在运行时检查栈的增长。
+//
+// 估算基于栈式虚拟机的求值模型：
+//
+// - 名称、字面量等基本表达式把一个值压入栈；
+// - 运算、函数调用、元组、列表等表达式依次求值各个子表达式，先求值的子表达式的值
+//   留在栈上直到运算完成，所以第 i 个（从 0 开始）子表达式求值时栈上已经有 i 个值；
+// - 表达式块、条件表达式以及循环等控制结构的各个子表达式的值不会同时留在栈上，
+//   取其中的最大值；`match` 表达式在比较各个分支时，被匹配的对象一直留在栈上。
+//
+// 匿名函数的主体属于另外一个函数（闭包），不计入所在函数的栈帧。
+//
+// 局部变量槽的分配基于活跃变量分析：一个绑定从被绑定开始，到最后一次被使用为止是活跃的，
+// 不同时活跃的绑定可以共用同一个槽（见 `allocate_slots`）。`vm` 后端在写入模块文件之前
+// 以同样的方式为 IR 的局部变量分配槽（见 `allocate_ir_slots`）。
+
+use crate::{
+    ast::{Expression, FunctionDeclaration, PatternExpression},
+    ir::{Expr, Function, Local, LocalId},
+    semantics::{binding_events, BindingEvent},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameSize {
+    pub max_stack: usize, // 操作数栈的最大深度
+    pub locals: usize,    // 局部变量槽的数量（包括参数）
+}
+
+// 估算函数的栈帧大小
+//
+// 局部变量槽的数量为槽分配（见 `allocate_slots`）之后的槽的数量。
+pub fn frame_size(function: &FunctionDeclaration) -> FrameSize {
+    // 参数的默认值在调用者的栈帧里求值
+    FrameSize {
+        max_stack: stack_depth(&function.body),
+        locals: allocate_slots(function).slot_count,
+    }
+}
+
+// 局部变量槽的分配结果
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SlotAllocation {
+    pub slots: Vec<(String, usize)>, // 各个绑定的名称以及槽的编号，按照绑定的顺序排列
+    pub slot_count: usize,
+}
+
+// 为函数的参数以及函数主体里的绑定分配局部变量槽
+//
+// - 参数按照顺序占用最前面的槽；
+// - 一个绑定从被绑定开始，到最后一次被使用为止是活跃的；在循环之前绑定、
+//   在循环里使用的名称，一直活跃到循环结束，因为下一次迭代还会使用它；
+// - 使用线性扫描（linear scan）分配：每个绑定使用编号最小的、
+//   其原来的绑定已经不再活跃的槽。
+//
+// 匿名函数里的绑定属于闭包的栈帧，不在这里分配，匿名函数使用的外层名称视为
+// 在匿名函数里使用。
+pub fn allocate_slots(function: &FunctionDeclaration) -> SlotAllocation {
+    let parameter_names: Vec<&str> = function
+        .parameters
+        .iter()
+        .map(|p| p.name.as_str())
+        .collect();
+    let events = binding_events(&parameter_names, &function.body);
+    allocate_event_slots(parameter_names.len(), &events)
+}
+
+// 根据绑定事件分配局部变量槽，最前面的 parameter_count 个绑定为参数
+fn allocate_event_slots(parameter_count: usize, events: &[BindingEvent]) -> SlotAllocation {
+    // 所在函数的绑定的名称、开始位置以及结束位置（事件的序号）
+    let mut bindings: Vec<(usize, String, usize, usize)> = vec![];
+    let mut loops: Vec<(usize, Vec<usize>)> = vec![]; // 循环的开始位置，以及需要延长的绑定
+    let mut function_depth = 0;
+
+    for (position, event) in events.iter().enumerate() {
+        match event {
+            BindingEvent::Bind { id, name } if function_depth == 0 => {
+                bindings.push((*id, name.clone(), position, position));
+            }
+            BindingEvent::Use(id) => {
+                if let Some(binding) = bindings.iter_mut().find(|b| b.0 == *id) {
+                    binding.3 = position;
+                    for (start, extended) in loops.iter_mut() {
+                        if binding.2 < *start {
+                            extended.push(*id);
+                        }
+                    }
+                }
+            }
+            BindingEvent::EnterLoop if function_depth == 0 => loops.push((position, vec![])),
+            BindingEvent::LeaveLoop if function_depth == 0 => {
+                let (_, extended) = loops.pop().unwrap();
+                for binding in bindings.iter_mut().filter(|b| extended.contains(&b.0)) {
+                    binding.3 = binding.3.max(position);
+                }
+            }
+            BindingEvent::EnterFunction => function_depth += 1,
+            BindingEvent::LeaveFunction => function_depth -= 1,
+            _ => {}
+        }
+    }
+
+    // 参数在函数开始时同时有效
+    for binding in bindings.iter_mut().take(parameter_count) {
+        binding.3 = binding.3.max(parameter_count - 1);
+    }
+
+    // 各个槽的当前绑定的结束位置
+    let mut slot_ends: Vec<usize> = vec![];
+    let mut slots = vec![];
+
+    for (_, name, start, end) in bindings {
+        let slot = match slot_ends.iter().position(|slot_end| *slot_end < start) {
+            Some(slot) => {
+                slot_ends[slot] = end;
+                slot
+            }
+            None => {
+                slot_ends.push(end);
+                slot_ends.len() - 1
+            }
+        };
+        slots.push((name, slot));
+    }
+
+    SlotAllocation {
+        slots,
+        slot_count: slot_ends.len(),
+    }
+}
+
+// 为 IR 函数的局部变量分配槽，并把局部变量的编号替换为槽的编号
+//
+// 规则跟 `allocate_slots` 相同，编译器生成的临时变量也参与分配，提升之后的匿名函数
+// 是单独的函数，闭包捕获的局部变量视为在创建闭包时被使用。
+// 共用一个槽的局部变量的名称取第一个局部变量的名称，数据类型不同时为 None。
+pub fn allocate_ir_slots(function: &mut Function) {
+    let mut events: Vec<BindingEvent> = (0..function.parameter_count)
+        .map(|id| BindingEvent::Bind {
+            id,
+            name: function.locals[id].name.clone(),
+        })
+        .collect();
+    ir_binding_events(&function.body, &function.locals, &mut events);
+
+    let allocation = allocate_event_slots(function.parameter_count, &events);
+
+    // 绑定事件跟分配结果都按照绑定的顺序排列
+    let ids = events.iter().filter_map(|event| match event {
+        BindingEvent::Bind { id, .. } => Some(*id),
+        _ => None,
+    });
+    let mut slot_of: Vec<LocalId> = (0..function.locals.len()).collect();
+    let mut locals: Vec<Option<Local>> = vec![None; allocation.slot_count];
+    for (id, (_, slot)) in ids.zip(&allocation.slots) {
+        slot_of[id] = *slot;
+        let local = &function.locals[id];
+        match &mut locals[*slot] {
+            Some(shared) if shared.data_type != local.data_type => shared.data_type = None,
+            Some(_) => {}
+            entry => *entry = Some(local.clone()),
+        }
+    }
+
+    renumber_locals(&mut function.body, &slot_of);
+    function.locals = locals.into_iter().map(Option::unwrap).collect();
+}
+
+// 按照求值的顺序列出 IR 里局部变量的绑定以及使用
+fn ir_binding_events(expr: &Expr, locals: &[Local], events: &mut Vec<BindingEvent>) {
+    let bind = |id: LocalId| BindingEvent::Bind {
+        id,
+        name: locals[id].name.clone(),
+    };
+
+    match expr {
+        Expr::Local(id) => events.push(BindingEvent::Use(*id)),
+        Expr::Let(id, value) => {
+            ir_binding_events(value, locals, events);
+            events.push(bind(*id));
+        }
+        Expr::Loop(variables, body) => {
+            for (_, value) in variables {
+                ir_binding_events(value, locals, events);
+            }
+            events.extend(variables.iter().map(|(id, _)| bind(*id)));
+            events.push(BindingEvent::EnterLoop);
+            ir_binding_events(body, locals, events);
+            events.push(BindingEvent::LeaveLoop);
+        }
+        Expr::Closure(_, captures) => {
+            events.extend(captures.iter().map(|id| BindingEvent::Use(*id)));
+        }
+        Expr::SetGlobal(_, value) => ir_binding_events(value, locals, events),
+        Expr::Block(exprs) | Expr::Next(exprs) | Expr::Operation(_, exprs) => {
+            for expr in exprs {
+                ir_binding_events(expr, locals, events);
+            }
+        }
+        Expr::If(testing, consequent, alternate) => {
+            for expr in [testing, consequent, alternate] {
+                ir_binding_events(expr, locals, events);
+            }
+        }
+        Expr::Call(callee, arguments) => {
+            ir_binding_events(callee, locals, events);
+            for expr in arguments {
+                ir_binding_events(expr, locals, events);
+            }
+        }
+        Expr::Constant(_) | Expr::HashString(_) | Expr::Unit | Expr::Global(_) => {}
+    }
+}
+
+fn renumber_locals(expr: &mut Expr, slot_of: &[LocalId]) {
+    match expr {
+        Expr::Local(id) => *id = slot_of[*id],
+        Expr::Let(id, value) => {
+            *id = slot_of[*id];
+            renumber_locals(value, slot_of);
+        }
+        Expr::Loop(variables, body) => {
+            for (id, value) in variables {
+                *id = slot_of[*id];
+                renumber_locals(value, slot_of);
+            }
+            renumber_locals(body, slot_of);
+        }
+        Expr::Closure(_, captures) => {
+            for id in captures {
+                *id = slot_of[*id];
+            }
+        }
+        Expr::SetGlobal(_, value) => renumber_locals(value, slot_of),
+        Expr::Block(exprs) | Expr::Next(exprs) | Expr::Operation(_, exprs) => {
+            for expr in exprs {
+                renumber_locals(expr, slot_of);
+            }
+        }
+        Expr::If(testing, consequent, alternate) => {
+            for expr in [testing, consequent, alternate] {
+                renumber_locals(expr, slot_of);
+            }
+        }
+        Expr::Call(callee, arguments) => {
+            renumber_locals(callee, slot_of);
+            for expr in arguments {
+                renumber_locals(expr, slot_of);
+            }
+        }
+        Expr::Constant(_) | Expr::HashString(_) | Expr::Unit | Expr::Global(_) => {}
+    }
+}
+
+// 求值表达式时操作数栈的最大深度
+pub fn stack_depth(expression: &Expression) -> usize {
+    match expression {
+        Expression::BlockExpression(v) => max_depth(v.body.iter()),
+        Expression::LetExpression(v) => stack_depth(&v.value),
+        Expression::IfExpression(v) => max_depth(
+            v.where_exp
+                .iter()
+                .chain([&v.testing, &v.consequent])
+                .chain(v.alternate.iter())
+                .map(|e| e.as_ref()),
+        ),
+        Expression::ForExpression(v) => stack_depth(&v.initializer.value).max(stack_depth(&v.body)),
+        Expression::EachExpression(v) => {
+            // 遍历时被遍历的对象（迭代器）一直留在栈上
+            stack_depth(&v.object).max(1 + stack_depth(&v.body))
+        }
+        Expression::BranchExpression(v) => max_depth(
+            v.where_exp
+                .iter()
+                .map(|e| e.as_ref())
+                .chain(v.cases.iter().flat_map(|c| {
+                    c.where_exp
+                        .iter()
+                        .map(|e| e.as_ref())
+                        .chain([c.testing.as_ref(), c.consequent.as_ref()])
+                }))
+                .chain(v.default_exp.iter().map(|e| e.as_ref())),
+        ),
+        Expression::MatchExpression(v) => {
+            let cases = v.cases.iter().map(|c| {
+                let pattern = match c.pattern.as_deref() {
+                    // 比较模式时，模式的值压在被匹配的对象之上
+                    Some(PatternExpression::Primary(e)) | Some(PatternExpression::In(e)) => {
+                        1 + stack_depth(e)
+                    }
+                    Some(_) => 1,
+                    None => 0,
+                };
+                let clauses = max_depth(
+                    c.where_exp
+                        .iter()
+                        .chain(c.only.iter())
+                        .chain([&c.consequent])
+                        .map(|e| e.as_ref()),
+                );
+                pattern.max(clauses)
+            });
+            let default_depth = v.default_exp.as_deref().map_or(0, stack_depth);
+
+            stack_depth(&v.object).max(
+                1 + max_depth(v.where_exp.iter().map(|e| e.as_ref()))
+                    .max(cases.max().unwrap_or(0))
+                    .max(default_depth),
+            )
+        }
+        Expression::FunctionCallExpression(v) => {
+            // 被调用的函数是名称时使用直接调用，否则先求值被调用者
+            let callee = match v.callee.as_ref() {
+                Expression::Identifier(_) | Expression::PrefixIdentifier(_) => vec![],
+                callee => vec![callee],
+            };
+            sequential_depth(
+                callee
+                    .into_iter()
+                    .chain(v.arguments.iter().map(|a| a.value.as_ref())),
+            )
+        }
+        Expression::AnonymousFunction(_) => 1,
+        _ => sequential_depth(expression.children().into_iter()),
+    }
+}
+
+// 求值 IR 表达式时操作数栈的最大深度，规则跟 `stack_depth` 相同，
+// 循环变量以及 `let` 绑定的值在求值之后存入局部变量槽，不留在栈上
+pub fn ir_stack_depth(expr: &Expr) -> usize {
+    match expr {
+        Expr::Let(_, value) | Expr::SetGlobal(_, value) => ir_stack_depth(value),
+        Expr::Block(exprs) => exprs.iter().map(ir_stack_depth).max().unwrap_or(0),
+        Expr::If(testing, consequent, alternate) => [testing, consequent, alternate]
+            .into_iter()
+            .map(|e| ir_stack_depth(e))
+            .max()
+            .unwrap(),
+        Expr::Loop(variables, body) => variables
+            .iter()
+            .map(|(_, value)| ir_stack_depth(value))
+            .chain([ir_stack_depth(body)])
+            .max()
+            .unwrap(),
+        Expr::Next(exprs) | Expr::Operation(_, exprs) => ir_sequential_depth(exprs.iter()),
+        Expr::Call(callee, arguments) => match callee.as_ref() {
+            // 被调用的函数是全局名称时使用直接调用，否则先求值被调用者
+            Expr::Global(_) => ir_sequential_depth(arguments.iter()),
+            callee => ir_sequential_depth(std::iter::once(callee).chain(arguments.iter())),
+        },
+        Expr::Closure(_, captures) => captures.len().max(1),
+        Expr::Constant(_) | Expr::HashString(_) | Expr::Unit | Expr::Local(_) | Expr::Global(_) => {
+            1
+        }
+    }
+}
+
+fn ir_sequential_depth<'a>(exprs: impl Iterator<Item = &'a Expr>) -> usize {
+    exprs
+        .enumerate()
+        .map(|(index, e)| index + ir_stack_depth(e))
+        .max()
+        .unwrap_or(0)
+        .max(1)
+}
+
+// 各个表达式的值不同时留在栈上
+fn max_depth<'a>(expressions: impl Iterator<Item = &'a Expression>) -> usize {
+    expressions.map(stack_depth).max().unwrap_or(0)
+}
+
+// 依次求值各个表达式，并且值都留在栈上，最后至少得到一个值
+fn sequential_depth<'a>(expressions: impl Iterator<Item = &'a Expression>) -> usize {
+    expressions
+        .enumerate()
+        .map(|(index, e)| index + stack_depth(e))
+        .max()
+        .unwrap_or(0)
+        .max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Node, Statement},
+        ir::{self, Function},
+        lexer, parser, typed,
+    };
+
+    use super::{
+        allocate_ir_slots, allocate_slots, frame_size, ir_stack_depth, stack_depth, FrameSize,
+        SlotAllocation,
+    };
+
+    fn parse_statement(source: &str) -> Statement {
+        let token_details = lexer::tokenize(source).unwrap();
+        match parser::parse(&token_details).unwrap() {
+            Node::Program(program) => program.body.into_iter().next().unwrap(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn depth(source: &str) -> usize {
+        match parse_statement(source) {
+            Statement::Expression(expression) => stack_depth(&expression),
+            _ => panic!("expected an expression"),
+        }
+    }
+
+    fn function_frame_size(source: &str) -> FrameSize {
+        match parse_statement(source) {
+            Statement::FunctionDeclaration(function) => frame_size(&function),
+            _ => panic!("expected a function"),
+        }
+    }
+
+    fn function_slots(source: &str) -> SlotAllocation {
+        match parse_statement(source) {
+            Statement::FunctionDeclaration(function) => allocate_slots(&function),
+            _ => panic!("expected a function"),
+        }
+    }
+
+    fn lower_function(source: &str) -> Function {
+        let token_details = lexer::tokenize(source).unwrap();
+        let mut node = parser::parse(&token_details).unwrap();
+        typed::annotate_types(&mut node);
+        match node {
+            Node::Program(program) => ir::lower(&program).unwrap().functions.remove(0),
+            _ => unreachable!(),
+        }
+    }
+
+    fn slots(names: &[(&str, usize)], slot_count: usize) -> SlotAllocation {
+        SlotAllocation {
+            slots: names.iter().map(|(n, s)| (n.to_string(), *s)).collect(),
+            slot_count,
+        }
+    }
+
+    #[test]
+    fn test_stack_depth() {
+        assert_eq!(depth("a"), 1);
+        assert_eq!(depth("a + b"), 2);
+        assert_eq!(depth("a + (b + c)"), 3);
+        assert_eq!(depth("(a + b) + c"), 2);
+        assert_eq!(depth("f(a, b + c)"), 3);
+        assert_eq!(depth("f()"), 1);
+        assert_eq!(depth("[a, b, c]"), 3);
+        assert_eq!(depth("if a > b then c + (d + e) else f"), 3);
+        assert_eq!(depth("do {\nlet x = a + b\nx\n}"), 2);
+        assert_eq!(depth("each i in items i + 1"), 3);
+        assert_eq!(depth("fn (Int x) x + (x + x)"), 1);
+    }
+
+    #[test]
+    fn test_ir_stack_depth() {
+        let depth = |source: &str| ir_stack_depth(&lower_function(source).body);
+        assert_eq!(depth("function f(Int a) = a"), 1);
+        assert_eq!(depth("function f(Int a, Int b) = a + (b + a)"), 3);
+        assert_eq!(depth("function f(Int a) = g(a, a + 1)"), 3);
+        assert_eq!(depth("function f(Int a) = {\nlet x = a + 1\nx * 2\n}"), 2);
+        assert_eq!(
+            depth("function f(Int a) = if a > 1 then [a, a, a] else 0"),
+            3
+        );
+
+        // 匿名函数的主体属于另外一个函数（排在外层函数之前）
+        assert_eq!(depth("function f(Int a) = fn (Int x) x + (x + a)"), 3);
+    }
+
+    #[test]
+    fn test_allocate_slots() {
+        // 不再活跃的绑定的槽被重复使用
+        assert_eq!(
+            function_slots("function f(Int a, Int b) = {\nlet x = a + b\nlet y = x * 2\ny\n}"),
+            slots(&[("a", 0), ("b", 1), ("x", 0), ("y", 0)], 2)
+        );
+
+        // 未被使用的参数也占用槽
+        assert_eq!(
+            function_slots("function f(Int a, Int b) = {\nlet x = 1\nx\n}"),
+            slots(&[("a", 0), ("b", 1), ("x", 0)], 2)
+        );
+
+        // 同时活跃的绑定
+        assert_eq!(
+            function_slots("function f(Int a) = {\nlet (x, y) = (a, a)\nx + y\n}"),
+            slots(&[("a", 0), ("x", 0), ("y", 1)], 2)
+        );
+
+        // 在循环里使用的、循环之前的绑定一直活跃到循环结束
+        assert_eq!(
+            function_slots(
+                "function f(Int n) = {\nlet s = n * 2\nfor let i = 0 {\nlet t = i + s\nnext t\n}\n}"
+            ),
+            slots(&[("n", 0), ("s", 0), ("i", 1), ("t", 2)], 3)
+        );
+
+        // 匿名函数里的绑定不在所在的函数里分配，
+        // `a` 在创建闭包时被捕获，之后不再活跃
+        assert_eq!(
+            function_slots("function f(Int a) = {\nlet g = fn (Int x) x + a\ng\n}"),
+            slots(&[("a", 0), ("g", 0)], 1)
+        );
+    }
+
+    #[test]
+    fn test_allocate_ir_slots() {
+        // 不再活跃的局部变量的槽被重复使用，槽的数量减少
+        let mut f1 = lower_function(
+            "function f(Int a, Int b) = {\nlet x = a + b\nlet y = x * 2\nlet z = y + 1\nz\n}",
+        );
+        assert_eq!(f1.locals.len(), 5);
+        allocate_ir_slots(&mut f1);
+        assert_eq!(f1.locals.len(), 2);
+        assert_eq!(
+            f1.to_string(),
+            [
+                "function f (Int $0 a, Int $1 b) type Int {",
+                "    (let $0 (add $0 $1))",
+                "    (let $0 (mul $0 2))",
+                "    (let $0 (add $0 1))",
+                "    $0",
+                "}",
+                "",
+            ]
+            .join("\n")
+        );
+
+        // 在循环里使用的、循环之前的局部变量一直活跃到循环结束
+        let mut f2 = lower_function(
+            "function f(Int n) = {\nlet s = n * 2\nfor let i = 0 {\nlet t = i + s\nnext t\n}\n}",
+        );
+        assert_eq!(f2.locals.len(), 4);
+        allocate_ir_slots(&mut f2);
+        assert_eq!(f2.locals.len(), 3);
+
+        // 共用一个槽的局部变量的数据类型不同
+        let mut f3 =
+            lower_function("function f(Int a) = {\nlet Float x = 1.5\nlet String y = \"a\"\ny\n}");
+        allocate_ir_slots(&mut f3);
+        assert_eq!(f3.locals.len(), 1);
+        assert_eq!(f3.locals[0].data_type, None);
+    }
+
+    #[test]
+    fn test_frame_size() {
+        assert_eq!(
+            function_frame_size(
+                "function f(Int a, Int b) = {\nlet (x, y) = (a, b)\neach i in [x, y] i * a\n}"
+            ),
+            FrameSize {
+                max_stack: 3,
+                locals: 3
+            }
+        );
+
+        // 匿名函数里的绑定不属于所在的函数
+        assert_eq!(
+            function_frame_size("function g() = fn (Int x) {\nlet y = x\ny\n}"),
+            FrameSize {
+                max_stack: 1,
+                locals: 0
+            }
+        );
+    }
+}
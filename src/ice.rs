@@ -0,0 +1,119 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 编译器内部错误（internal compiler error，ICE）的捕获
+//
+// 编译过程中意外的 panic（比如尚未实现的 `todo!()`、数组下标越界等）会被捕获并转换为
+// `Error::InternalCompilerError`，其中记录了发生错误的阶段、panic 的信息，
+// 以及当时正在处理的源代码的位置，而不是终止宿主进程（比如编辑器、语言服务器）。
+//
+// 正在处理的位置由各个阶段通过 `set_location` 记录，比如语法分析器在开始
+// 分析每个语句时记录语句的第一个 Token 的位置。
+//
+// 注意 panic 的信息仍然会由当前的 panic hook 输出，如果不需要可以先使用
+// `std::panic::set_hook` 替换。
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    panic::{self, AssertUnwindSafe},
+};
+
+use crate::{error::Error, token::Location};
+
+thread_local! {
+    static CURRENT_LOCATION: RefCell<Option<Location>> = const { RefCell::new(None) };
+}
+
+// 记录当前正在处理的源代码的位置
+pub(crate) fn set_location(location: &Location) {
+    CURRENT_LOCATION.with(|cell| *cell.borrow_mut() = Some(location.clone()));
+}
+
+// 运行编译过程的一个阶段，并把其中的 panic 转换为 `Error::InternalCompilerError`
+pub fn catch_ice<T>(stage: &str, f: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+    CURRENT_LOCATION.with(|cell| *cell.borrow_mut() = None);
+
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => Err(Error::InternalCompilerError {
+            stage: stage.to_string(),
+            message: panic_message(payload.as_ref()),
+            location: CURRENT_LOCATION.with(|cell| cell.borrow_mut().take()),
+        }),
+    }
+}
+
+// panic 的信息
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic;
+
+    use crate::{error::Error, token::Location};
+
+    use super::{catch_ice, set_location};
+
+    #[test]
+    fn test_catch_ice() {
+        assert_eq!(catch_ice("parse", || Ok(1)).unwrap(), 1);
+        assert!(matches!(
+            catch_ice::<()>("parse", || Err(Error::ParserError("foo".to_string()))),
+            Err(Error::ParserError(_))
+        ));
+
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+
+        let location = Location {
+            file_id: 0,
+            start: 3,
+            end: 5,
+            line: 1,
+            column: 4,
+        };
+
+        let result = catch_ice("parse", || {
+            set_location(&location);
+            let items: Vec<i32> = vec![];
+            Ok(items[1])
+        });
+
+        let e1 = catch_ice::<()>("normalize", || todo!()).unwrap_err();
+
+        panic::set_hook(hook);
+
+        let e0 = result.unwrap_err();
+        assert!(matches!(
+            &e0,
+            Error::InternalCompilerError { stage, message, .. }
+                if stage == "parse" && message.contains("out of bounds")
+        ));
+        assert_eq!(e0.location(), Some(&location));
+        assert!(e0
+            .to_string()
+            .starts_with("internal compiler error in parse: index out of bounds"));
+
+        // 位置不会遗留到下一次运行
+        assert_eq!(e1.location(), None);
+        assert_eq!(
+            e1.to_string(),
+            "internal compiler error in normalize: not yet implemented, \
+            this is a bug in the compiler, please file a bug report with the source code"
+        );
+    }
+}
@@ -0,0 +1,110 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// IDE 模式的语法分析
+//
+// 用户在编辑器里输入的过程中，源代码大部分时间都是不完整或者有错误的，
+// 为了让语言服务器的功能（比如大纲、跳转到定义、补全）仍然可用，IDE 模式的语法分析
+// 总是返回一个 `Program` 以及诊断信息（错误列表），而不会返回 Err：
+//
+// - 无法分析的语句以 `Statement::Error` 的形式保留在程序里，其余的语句正常分析
+//   （见 `parser::parse_with_recovery`），比如一个函数的错误不会影响其他函数；
+// - 不完整的输入（比如缺少结束括号的表达式）同样作为无法分析的语句处理；
+// - 词法错误时，只分析出错的行之前的源代码；
+// - 分析过程中的 panic 被转换为编译器内部错误（见 `ice` 模块）。
+//
+// 诊断信息为空时，得到的程序跟 `parser::parse` 的结果相同。
+
+use crate::{ast::Program, error::Error, ice, lexer, parser, token::TokenDetail};
+
+#[derive(Debug)]
+pub struct IdeParse {
+    pub program: Program,
+    pub diagnostics: Vec<Error>,
+}
+
+pub fn parse(source: &str) -> IdeParse {
+    let (token_details, lexer_error) = tokenize_prefix(source);
+    let (program, errors) = parser::parse_with_recovery(&token_details);
+
+    IdeParse {
+        program,
+        diagnostics: lexer_error.into_iter().chain(errors).collect(),
+    }
+}
+
+// 对源代码进行词法分析，出错时对出错的行之前（尽可能长）的源代码进行词法分析
+fn tokenize_prefix(source: &str) -> (Vec<TokenDetail>, Option<Error>) {
+    let tokenize = |text: &str| ice::catch_ice("tokenize", || lexer::tokenize(text));
+
+    let error = match tokenize(source) {
+        Ok(token_details) => return (token_details, None),
+        Err(error) => error,
+    };
+
+    // 二分查找能够通过词法分析的最多的行数，
+    // 对于跨行的字面量（比如多行字符串）结果可能不是最长的
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let mut low = 0; // 前 low 行能够通过词法分析
+    let mut high = lines.len(); // 前 high 行不能通过词法分析
+
+    while high - low > 1 {
+        let middle = (low + high) / 2;
+        if tokenize(&lines[..middle].concat()).is_ok() {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+
+    let token_details = tokenize(&lines[..low].concat()).unwrap_or_default();
+    (token_details, Some(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Statement, error::Error};
+
+    use super::parse;
+
+    #[test]
+    fn test_parse() {
+        let source = "let a = 1\nlet b = 2";
+        let result = parse(source);
+        assert!(result.diagnostics.is_empty());
+        assert_eq!(result.program.to_string(), "let a = 1\nlet b = 2\n");
+
+        // 错误的语句不影响其他语句
+        let result = parse("let a = 1\nlet b = 2 3\nlet c = 4");
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(
+            result.program.to_string(),
+            "let a = 1\nlet b = 2 3\nlet c = 4\n"
+        );
+        assert!(matches!(result.program.body[1], Statement::Error(_)));
+
+        // 不完整的输入
+        let result = parse("let a = 1\nlet b = [1, 2");
+        assert_eq!(result.program.body.len(), 2);
+        assert!(matches!(
+            result.diagnostics[0],
+            Error::UnclosedDelimiter { .. }
+        ));
+
+        // 词法错误
+        let result = parse("let a = 1\nlet b = \"foo\nlet c = 3");
+        assert_eq!(result.program.to_string(), "let a = 1\n");
+        assert!(result.diagnostics[0].is_lexer_error());
+
+        // 跨行的错误语句整个被跳过
+        let result = parse("struct User {\nInt id 1\n}\nlet a = 1");
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.program.body.len(), 2);
+        assert_eq!(result.program.body[1].to_string(), "let a = 1\n");
+    }
+}
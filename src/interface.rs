@@ -0,0 +1,528 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 模块的公开接口
+//
+// 列出模块（程序）里所有顶层声明的接口部分，不包括函数主体、常量的值等实现部分：
+//
+// - 函数（包括空函数）的签名；
+// - 结构体及其成员；
+// - 常量及其类型；
+// - 类型别名。
+//
+// 文档生成器、`use` 语句的解析以及模块接口文件都使用这里的数据。
+//
+// 模块接口文件
+//
+// 启用 `serde` 特性时，`to_interface_file` 把模块接口转换为紧凑的、带版本号的
+// JSON 文档，`from_interface_file` 读取该文档，以便分开编译（separate compilation）
+// 的时候不需要重新分析依赖模块的源代码。`use` 语句加载接口文件的计划见 `docs/roadmap.md`。
+//
+// 文档的结构：
+//
+// ```json
+// {
+//     "version": 1,
+//     "module": "foo",
+//     "interface": {"items": [...]}
+// }
+// ```
+//
+// 注：
+// - 语言目前没有可见性修饰符，所有顶层声明都是公开的；
+// - 名称解析以及类型推导尚未实现，所以类型按照源代码里的写法保存，
+//   常量的类型只在其值能够被求值（见 `fold` 模块）时才能确定。
+
+use std::fmt::Display;
+
+use crate::{
+    ast::{Attribute, DataType, Program, RangeClearer, Statement},
+    fold,
+};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModuleInterface {
+    pub items: Vec<InterfaceItem>,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterfaceItem {
+    Function(FunctionSignature),
+    Struct(StructInterface),
+    Const(ConstInterface),
+    Alias(AliasInterface),
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionSignature {
+    pub attributes: Vec<Attribute>,
+    pub name: String,
+    pub generics: Vec<DataType>, // 泛型类型列表
+    pub parameters: Vec<ParameterSignature>,
+    pub return_data_type: Option<DataType>,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterSignature {
+    pub data_type: DataType,
+    pub name: String,
+    pub has_default: bool, // 是否有默认值（默认值属于实现部分，不被记录）
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructInterface {
+    pub name: String,
+    pub generics: Vec<DataType>, // 泛型类型列表
+    pub fields: StructFields,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructFields {
+    Named(Vec<(DataType, String)>), // 成员的类型和名称
+    Tuple(Vec<DataType>),
+    Empty,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstInterface {
+    pub name: String,
+    pub data_type: Option<String>, // 无法求值时为 None
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AliasInterface {
+    pub name: String,
+    pub generics: Vec<DataType>, // 泛型类型列表
+    pub data_type: DataType,     // 源类型
+}
+
+impl ModuleInterface {
+    pub fn find(&self, name: &str) -> Option<&InterfaceItem> {
+        self.items.iter().find(|item| item.name() == name)
+    }
+}
+
+impl InterfaceItem {
+    pub fn name(&self) -> &str {
+        match self {
+            InterfaceItem::Function(f) => &f.name,
+            InterfaceItem::Struct(s) => &s.name,
+            InterfaceItem::Const(c) => &c.name,
+            InterfaceItem::Alias(a) => &a.name,
+        }
+    }
+}
+
+// 获取模块的公开接口，各个项目按照声明的顺序排列
+//
+// 接口里的数据类型等不记录在源文件中的位置，所以修改函数主体等实现部分
+// （使得后面的声明的位置改变）不会改变模块的接口。
+pub fn module_interface(program: &Program) -> ModuleInterface {
+    let items = program
+        .body
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::FunctionDeclaration(f) => Some(InterfaceItem::Function(FunctionSignature {
+                attributes: attributes_without_ranges(&f.attributes),
+                name: f.name.clone(),
+                generics: data_types_without_ranges(&f.generics),
+                parameters: f
+                    .parameters
+                    .iter()
+                    .map(|p| ParameterSignature {
+                        data_type: without_ranges(&p.data_type),
+                        name: p.name.clone(),
+                        has_default: p.value.is_some(),
+                    })
+                    .collect(),
+                return_data_type: f.return_data_type.as_ref().map(without_ranges),
+            })),
+            Statement::EmptyFunctionDeclaration(f) => {
+                Some(InterfaceItem::Function(FunctionSignature {
+                    attributes: attributes_without_ranges(&f.attributes),
+                    name: f.name.clone(),
+                    generics: data_types_without_ranges(&f.generics),
+                    parameters: f
+                        .parameters
+                        .iter()
+                        .map(|p| ParameterSignature {
+                            data_type: without_ranges(&p.data_type),
+                            name: p.name.clone(),
+                            has_default: false,
+                        })
+                        .collect(),
+                    return_data_type: f.return_data_type.as_ref().map(without_ranges),
+                }))
+            }
+            Statement::MemberStructDeclaration(s) => Some(InterfaceItem::Struct(StructInterface {
+                name: s.name.clone(),
+                generics: data_types_without_ranges(&s.generics),
+                fields: StructFields::Named(
+                    s.members
+                        .iter()
+                        .map(|m| (without_ranges(&m.data_type), m.name.clone()))
+                        .collect(),
+                ),
+            })),
+            Statement::TupleStructDeclaration(s) => Some(InterfaceItem::Struct(StructInterface {
+                name: s.name.clone(),
+                generics: data_types_without_ranges(&s.generics),
+                fields: StructFields::Tuple(data_types_without_ranges(&s.members)),
+            })),
+            Statement::EmptyStructDeclaration(s) => Some(InterfaceItem::Struct(StructInterface {
+                name: s.name.clone(),
+                generics: vec![],
+                fields: StructFields::Empty,
+            })),
+            Statement::ConstDeclaration(c) => Some(InterfaceItem::Const(ConstInterface {
+                name: c.name.clone(),
+                data_type: match &c.data_type {
+                    Some(d) => Some(d.to_string()),
+                    None => fold::fold(&c.value).map(|v| v.type_name().to_string()),
+                },
+            })),
+            Statement::AliasStatement(a) => Some(InterfaceItem::Alias(AliasInterface {
+                name: a.name.clone(),
+                generics: data_types_without_ranges(&a.generics),
+                data_type: without_ranges(&a.data_type),
+            })),
+            _ => None,
+        })
+        .collect();
+
+    ModuleInterface { items }
+}
+
+fn without_ranges(data_type: &DataType) -> DataType {
+    let mut data_type = data_type.clone();
+    RangeClearer.clear_data_type(&mut data_type);
+    data_type
+}
+
+fn data_types_without_ranges(data_types: &[DataType]) -> Vec<DataType> {
+    data_types.iter().map(without_ranges).collect()
+}
+
+fn attributes_without_ranges(attributes: &[Attribute]) -> Vec<Attribute> {
+    let mut attributes = attributes.to_vec();
+    for attribute in &mut attributes {
+        RangeClearer.clear_attribute(attribute);
+    }
+    attributes
+}
+
+impl Display for ModuleInterface {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for item in &self.items {
+            writeln!(f, "{}", item)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for InterfaceItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterfaceItem::Function(v) => write!(f, "{}", v),
+            InterfaceItem::Struct(v) => write!(f, "{}", v),
+            InterfaceItem::Const(v) => write!(f, "{}", v),
+            InterfaceItem::Alias(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl Display for FunctionSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for attribute in &self.attributes {
+            writeln!(f, "{}", attribute)?;
+        }
+
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|p| {
+                if p.has_default {
+                    format!("{} {} = ...", p.data_type, p.name)
+                } else {
+                    format!("{} {}", p.data_type, p.name)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        write!(
+            f,
+            "function {}{} ({})",
+            self.name,
+            format_generics(&self.generics),
+            parameters
+        )?;
+
+        if let Some(d) = &self.return_data_type {
+            write!(f, " type {}", d)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for StructInterface {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "struct {}{}", self.name, format_generics(&self.generics))?;
+
+        match &self.fields {
+            StructFields::Named(members) => {
+                let text = members
+                    .iter()
+                    .map(|(data_type, name)| format!("{} {}", data_type, name))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, " {{{}}}", text)
+            }
+            StructFields::Tuple(members) => {
+                let text = members
+                    .iter()
+                    .map(|data_type| data_type.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, " ({})", text)
+            }
+            StructFields::Empty => Ok(()),
+        }
+    }
+}
+
+impl Display for ConstInterface {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.data_type {
+            Some(data_type) => write!(f, "const {} type {}", self.name, data_type),
+            None => write!(f, "const {}", self.name),
+        }
+    }
+}
+
+impl Display for AliasInterface {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "alias {}{} = {}",
+            self.name,
+            format_generics(&self.generics),
+            self.data_type
+        )
+    }
+}
+
+// 当前模块接口文件格式的版本号，只有在发生不兼容的修改时才需要增加
+#[cfg(feature = "serde")]
+pub const INTERFACE_FILE_VERSION: u32 = 1;
+
+#[cfg(feature = "serde")]
+#[derive(Debug, PartialEq)]
+pub enum InterfaceFileError {
+    // 不是有效的 JSON 文档，或者文档结构跟接口文件的格式不符
+    InvalidDocument(String),
+
+    // 文档的版本号跟当前程序支持的版本号不同
+    UnsupportedVersion(u32),
+}
+
+#[cfg(feature = "serde")]
+impl Display for InterfaceFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterfaceFileError::InvalidDocument(message) => {
+                write!(f, "invalid module interface file: {}", message)
+            }
+            InterfaceFileError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported module interface file version {}, the supported version is {}",
+                version, INTERFACE_FILE_VERSION
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct InterfaceDocument<'a> {
+    version: u32,
+    module: &'a str,
+    interface: &'a ModuleInterface,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct InterfaceDocumentHeader {
+    version: u32,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct OwnedInterfaceDocument {
+    module: String,
+    interface: ModuleInterface,
+}
+
+// 将模块接口转换为接口文件的内容
+#[cfg(feature = "serde")]
+pub fn to_interface_file(module_name: &str, interface: &ModuleInterface) -> String {
+    let document = InterfaceDocument {
+        version: INTERFACE_FILE_VERSION,
+        module: module_name,
+        interface,
+    };
+
+    serde_json::to_string(&document).unwrap()
+}
+
+// 读取接口文件，返回模块的名称以及接口
+//
+// 接口文件是编译器生成的中间产物，跟 AST 的 JSON 格式不同，
+// 不提供向后兼容，版本号不同时需要重新编译依赖模块。
+#[cfg(feature = "serde")]
+pub fn from_interface_file(text: &str) -> Result<(String, ModuleInterface), InterfaceFileError> {
+    let header: InterfaceDocumentHeader = serde_json::from_str(text)
+        .map_err(|e| InterfaceFileError::InvalidDocument(e.to_string()))?;
+
+    if header.version != INTERFACE_FILE_VERSION {
+        return Err(InterfaceFileError::UnsupportedVersion(header.version));
+    }
+
+    let document: OwnedInterfaceDocument = serde_json::from_str(text)
+        .map_err(|e| InterfaceFileError::InvalidDocument(e.to_string()))?;
+
+    Ok((document.module, document.interface))
+}
+
+fn format_generics(generics: &[DataType]) -> String {
+    if generics.is_empty() {
+        "".to_string()
+    } else {
+        let text = generics
+            .iter()
+            .map(|g| g.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("<{}>", text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{DataType, Identifier, Node, Range},
+        lexer, parser,
+    };
+
+    use super::{module_interface, InterfaceItem, StructFields};
+
+    fn new_range() -> Range {
+        Range {
+            file_id: 0,
+            start: 0,
+            end: 0,
+        }
+    }
+
+    fn new_data_type(name: &str) -> DataType {
+        DataType::Identifier(Identifier {
+            dirs: vec![],
+            name: name.to_string(),
+            generics: vec![],
+            range: new_range(),
+        })
+    }
+
+    #[test]
+    fn test_module_interface() {
+        let token_details = lexer::tokenize(
+            "#[deprecated]\nfunction add(Int a, Int b = 1) type Int = a + b\n\
+            function show<T>(T value) {\nwriteLine(value)\n}\n\
+            1 + 2\n\
+            struct User {Int id}\n\
+            struct Pair<T> (T, T)\n\
+            const MAX = 100\n\
+            alias Pairs<T> = List<Pair<T>>",
+        )
+        .unwrap();
+        let program = match parser::parse(&token_details).unwrap() {
+            Node::Program(program) => program,
+            _ => unreachable!(),
+        };
+
+        let interface = module_interface(&program);
+        assert_eq!(
+            interface.to_string(),
+            "#[deprecated]\n\
+            function add (Int a, Int b = ...) type Int\n\
+            function show<T> (T value)\n\
+            struct User {Int id}\n\
+            struct Pair<T> (T, T)\n\
+            const MAX type Int\n\
+            alias Pairs<T> = List<Pair<T>>\n"
+        );
+
+        assert!(matches!(
+            interface.find("User"),
+            Some(InterfaceItem::Struct(s)) if s.fields == StructFields::Named(vec![
+                (new_data_type("Int"), "id".to_string())
+            ])
+        ));
+        assert!(interface.find("writeLine").is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_interface_file() {
+        use super::{from_interface_file, to_interface_file, InterfaceFileError, ModuleInterface};
+
+        let token_details = lexer::tokenize(
+            "function add(Int a, Int b = 1) type Int = a + b\n\
+            function show<T>(T value) {\nwriteLine(value)\n}",
+        )
+        .unwrap();
+        let program = match parser::parse(&token_details).unwrap() {
+            Node::Program(program) => program,
+            _ => unreachable!(),
+        };
+
+        let i1 = module_interface(&program);
+        let text = to_interface_file("math", &i1);
+
+        // 接口文件不包含函数主体
+        assert!(!text.contains("writeLine"));
+        assert!(!text.contains('\n'));
+
+        assert_eq!(from_interface_file(&text), Ok(("math".to_string(), i1)));
+
+        let t2 = text.replacen(r#""version":1"#, r#""version":2"#, 1);
+        assert_eq!(
+            from_interface_file(&t2),
+            Err(InterfaceFileError::UnsupportedVersion(2))
+        );
+        assert!(matches!(
+            from_interface_file(r#"{"version":1,"module":"math"}"#),
+            Err(InterfaceFileError::InvalidDocument(_))
+        ));
+
+        let empty = to_interface_file("empty", &ModuleInterface::default());
+        assert_eq!(
+            empty,
+            r#"{"version":1,"module":"empty","interface":{"items":[]}}"#
+        );
+    }
+}
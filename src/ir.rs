@@ -0,0 +1,2097 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 低层中间表示（IR）
+//
+// IR 是树形的表达式，跟源代码的语法解耦，优化以及代码生成都以 IR 为目标，
+// 而不需要理解 `branch`、`each`、管道运算等语法结构。
+//
+// - 局部变量（包括参数以及编译器生成的临时变量）使用函数内的编号表示（`$0`、`$1` ...），
+//   名称解析按照作用域规则在降级时完成，同名的不同绑定有不同的编号；
+// - 未被绑定的名称（函数、常量、顶层的 `let` 表达式定义的名称以及其他模块的名称）
+//   使用全局名称表示（`@name`）；
+// - 运算符、数据的构建以及读取等都使用 `Operation` 表示，其中逻辑与、逻辑或运算
+//   转换为条件表达式（短路求值）；
+// - 循环只有 `Loop` 以及 `Next` 两种节点：`for` 表达式直接对应，
+//   `each` 表达式转换为以索引以及结果列表为循环变量的循环，遍历整数范围（比如
+//   `each x in [1..100]`）时则以计数器代替索引，不构造被遍历的列表；
+// - 匿名函数提升为模块里的函数（名称为 `{所在的函数}$lambda{编号}`），
+//   捕获的局部变量作为开头的参数（见 `Expr::Closure`）；
+// - 顶层的表达式以及常量、全局变量的初始化放在名为 `$init` 的函数里；
+// - 每个函数都有一个唯一的符号名称（见 `mangle` 模块），其中包括参数的类型，
+//   以便区分重载的函数，调用重载的函数时按照实参的数量以及名称选择其中一个，
+//   并使用它的符号名称引用它（`@_X3addPT3IntE`）。
+//
+// 降级时可以同时进行以下优化（见 `LowerOptions`，`vm` 后端启用全部优化）：
+//
+// - 循环不变量外提：`for`、`each` 表达式的主体里的循环不变量（见 `optimize::loop_invariants`）
+//   在循环之前求值并保存到临时变量，`each` 表达式只在被遍历的对象不为空时才求值；
+// - 公共子表达式消除：函数（包括匿名函数）以及循环的主体里的公共子表达式
+//   （见 `optimize::common_subexpressions`）在第一次出现时保存到临时变量，
+//   之后的各次出现直接使用该临时变量。
+//
+// 降级的输入是带类型标注的 AST（见 `typed::annotate_types`），参数以及局部变量的
+// 数据类型来自标注，无法确定类型的保持为 None。
+//
+// 暂不支持的语法结构（比如模式函数，以及常量、单独的名称以及 `in` 模式之外的
+// `match` 分支模式）在降级时返回错误。
+
+use std::fmt::Display;
+
+use crate::{
+    ast::{
+        AnonymousFunction, Argument, BinaryOperator, BranchExpression, DataType, EachExpression,
+        Expression, ForExpression, FunctionDeclaration, FunctionParameter, Identifier,
+        IfExpression, LetExpression, Literal, MatchExpression, MemberExpression, PatternExpression,
+        Program, Range, Statement, TraitFunctionItem, UnaryOperator,
+    },
+    error::Error,
+    fold::{self, Constant},
+    frame,
+    mangle::{self, MangledType, Symbol},
+    optimize, semantics,
+};
+
+pub const INIT_FUNCTION_NAME: &str = "$init";
+
+pub type LocalId = usize;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub globals: Vec<Global>,
+    pub functions: Vec<Function>, // 包括 `$init` 函数以及提升的匿名函数，按照源代码的顺序排列
+}
+
+// 常量以及顶层的 `let` 表达式定义的全局变量
+#[derive(Debug, Clone, PartialEq)]
+pub struct Global {
+    pub name: String,
+    pub data_type: Option<DataType>,
+    pub is_const: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,           // 方法的名称为 `Type::name`
+    pub symbol: String,         // 修饰后的名称，`$init` 以及匿名函数的符号名称跟名称相同
+    pub parameter_count: usize, // `locals` 的前 parameter_count 个为参数
+    pub max_stack: usize,       // 操作数栈的最大深度（见 `frame::ir_stack_depth`）
+    pub locals: Vec<Local>,
+    pub return_data_type: Option<DataType>,
+    pub body: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Local {
+    pub name: String, // 编译器生成的临时变量的名称为空字符串
+    pub data_type: Option<DataType>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Constant(Constant),
+    HashString(String),
+    Unit, // 没有值，比如没有 `else` 部分的条件表达式
+    Local(LocalId),
+    Global(String),
+
+    Let(LocalId, Box<Expr>),      // 绑定局部变量
+    SetGlobal(String, Box<Expr>), // 初始化全局变量
+    Block(Vec<Expr>),             // 依次求值，值为最后一个表达式的值
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+    Loop(Vec<(LocalId, Expr)>, Box<Expr>), // 循环变量及其初始值，以及循环体
+    Next(Vec<Expr>),                       // 以新的值更新最近的循环的循环变量，并再次执行循环体
+
+    Call(Box<Expr>, Vec<Expr>), // 参数已经按照形参的顺序排列，并补全了默认值
+    Operation(Operation, Vec<Expr>),
+    Closure(String, Vec<LocalId>), // 提升之后的函数名称以及捕获的局部变量
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Negate,
+    Equal,
+    NotEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+    Concat,      // `++`
+    Combine,     // `&`
+    OptionalOr,  // `??`
+    OptionalAnd, // `->`
+    Unwrap,      // `?`
+    Cast,        // `^`
+
+    Tuple,
+    List,
+    Map,                         // 参数依次为各项的键和值
+    Struct(String, Vec<String>), // 结构体名称以及各个参数对应的成员名称
+    TupleStruct(String),
+    Interval(bool), // 是否包括结束值，没有结束值时第二个参数为 Unit
+    Field(String),
+    Element(usize), // 元组（以及元组结构体）的成员
+    Index,
+    Slice, // 参数为对象以及范围
+    Length,
+    Append,   // 在列表的末尾添加一个元素，返回新的列表
+    Contains, // 范围或者列表是否包含某个值，用于 `in` 模式
+    Join,     // 把各个参数转换为字符串并拼接
+}
+
+// 降级时进行的优化，默认均不启用，以便 IR 跟源代码一一对应
+#[derive(Debug, Clone, Default)]
+pub struct LowerOptions {
+    pub hoist_loop_invariants: bool,
+    pub eliminate_common_subexpressions: bool,
+}
+
+impl LowerOptions {
+    // 启用全部优化
+    pub fn optimized() -> Self {
+        Self {
+            hoist_loop_invariants: true,
+            eliminate_common_subexpressions: true,
+        }
+    }
+}
+
+// 把程序降级为 IR
+pub fn lower(program: &Program) -> Result<Module, Error> {
+    lower_with_options(program, &LowerOptions::default())
+}
+
+pub fn lower_with_options(program: &Program, options: &LowerOptions) -> Result<Module, Error> {
+    let mut lowerer = Lowerer {
+        program,
+        options,
+        globals: vec![],
+        functions: vec![],
+        context: FunctionContext {
+            name: INIT_FUNCTION_NAME.to_string(),
+            ..Default::default()
+        },
+        lambda_count: 0,
+    };
+
+    let mut init_body: Vec<Expr> = vec![];
+    for statement in &program.body {
+        match statement {
+            Statement::FunctionDeclaration(f) => lowerer.lower_function(f, &f.name, None)?,
+            Statement::TraitDeclaration(t) => {
+                let owner = MangledType::Named {
+                    dirs: vec![],
+                    name: t.name.clone(),
+                    generics: vec![],
+                };
+                for item in &t.function_items {
+                    if let TraitFunctionItem::Function(f) = item {
+                        let name = format!("{}::{}", t.name, f.name);
+                        lowerer.lower_function(f, &name, Some(owner.clone()))?;
+                    }
+                }
+            }
+            Statement::ImplStatement(i) => {
+                let owner =
+                    MangledType::from_data_type(&DataType::Identifier(i.object.clone())).ok();
+                for f in &i.function_items {
+                    let name = format!("{}::{}", i.object.name, f.name);
+                    lowerer.lower_function(f, &name, owner.clone())?;
+                }
+            }
+            Statement::ConstDeclaration(c) => {
+                lowerer.globals.push(Global {
+                    name: c.name.clone(),
+                    data_type: c.data_type.clone(),
+                    is_const: true,
+                });
+                let value = lowerer.lower_expression(&c.value)?;
+                init_body.push(Expr::SetGlobal(c.name.clone(), Box::new(value)));
+            }
+            Statement::Expression(Expression::LetExpression(l)) => {
+                let value = lowerer.lower_expression(&l.value)?;
+                lowerer.bind_pattern(
+                    &l.object,
+                    l.data_type.as_ref(),
+                    value,
+                    true,
+                    &mut init_body,
+                )?;
+            }
+            Statement::Expression(e) => {
+                let value = lowerer.lower_expression(e)?;
+                init_body.push(value);
+            }
+            Statement::PatternFunctionDeclaration(f) => {
+                return Err(unsupported("pattern function", &f.range))
+            }
+            Statement::Error(e) => return Err(unsupported("erroneous statement", &e.range)),
+            // 声明以及导入等语句不产生代码
+            _ => {}
+        }
+    }
+
+    let context = std::mem::take(&mut lowerer.context);
+    let body = new_block(init_body);
+    lowerer.functions.push(Function {
+        name: INIT_FUNCTION_NAME.to_string(),
+        symbol: INIT_FUNCTION_NAME.to_string(),
+        parameter_count: 0,
+        max_stack: frame::ir_stack_depth(&body),
+        locals: context.locals,
+        return_data_type: None,
+        body,
+    });
+
+    Ok(Module {
+        globals: lowerer.globals,
+        functions: lowerer.functions,
+    })
+}
+
+// 范围的边界是否为整数：至少一个边界为整数常量，并且另一个边界不是其他类型的常量，
+// 两个边界的类型均无法确定时（可能是浮点数或者字符）返回 false
+fn is_integer_interval(from: &Expression, to: &Expression) -> bool {
+    let bounds = [fold::fold(from), fold::fold(to)];
+    bounds
+        .iter()
+        .any(|c| matches!(c, Some(Constant::Integer(_))))
+        && bounds
+            .iter()
+            .all(|c| matches!(c, None | Some(Constant::Integer(_))))
+}
+
+// 函数的符号名称，包括所属的类型以及参数的类型，
+// 参数的类型无法修饰（比如泛型的函数类型）时不包括签名
+fn function_symbol(owner: Option<MangledType>, function: &FunctionDeclaration) -> String {
+    let parameters = function
+        .parameters
+        .iter()
+        .map(|p| MangledType::from_data_type(&p.data_type))
+        .collect::<Result<Vec<MangledType>, String>>()
+        .ok();
+    mangle::mangle(&Symbol {
+        owner,
+        parameters,
+        ..Symbol::new(&[], &function.name)
+    })
+}
+
+// 函数能否接受这些实参：实参的数量不超过形参，命名参数都存在，
+// 并且未提供的形参都有默认值
+fn accepts(function: &FunctionDeclaration, arguments: &[Argument]) -> bool {
+    let parameters = &function.parameters;
+    let positional = arguments.iter().filter(|a| a.name.is_none()).count();
+    positional <= parameters.len()
+        && arguments.iter().all(|a| match &a.name {
+            Some(name) => parameters[positional..].iter().any(|p| p.name == *name),
+            None => true,
+        })
+        && parameters.iter().enumerate().all(|(index, p)| {
+            index < positional
+                || p.value.is_some()
+                || arguments.iter().any(|a| a.name.as_ref() == Some(&p.name))
+        })
+}
+
+fn unsupported(what: &str, range: &Range) -> Error {
+    Error::SemanticError {
+        message: format!("lowering of {} is not supported yet", what),
+        related: vec![],
+        range: range.clone(),
+    }
+}
+
+// 只有一个表达式的块直接使用该表达式，空的块为 Unit
+fn new_block(mut body: Vec<Expr>) -> Expr {
+    match body.len() {
+        0 => Expr::Unit,
+        1 => body.remove(0),
+        _ => Expr::Block(body),
+    }
+}
+
+fn new_operation(operation: Operation, arguments: Vec<Expr>) -> Expr {
+    Expr::Operation(operation, arguments)
+}
+
+#[derive(Debug, Default)]
+struct FunctionContext {
+    name: String,
+    locals: Vec<Local>,
+    scope: Vec<(String, LocalId)>, // 当前可见的局部变量，后面的遮盖前面的同名变量
+    loops: Vec<LoopKind>,
+    hoisted: Vec<(*const Expression, LocalId)>, // 已经移到循环之前求值的表达式以及保存值的临时变量
+    subexpressions: Vec<(*const Expression, usize)>, // 公共子表达式的各次出现以及所在的组
+    subexpression_locals: Vec<Option<LocalId>>, // 每组公共子表达式的临时变量，第一次出现之后才分配
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LoopKind {
+    For,
+    Each,
+}
+
+struct Lowerer<'a> {
+    program: &'a Program,
+    options: &'a LowerOptions,
+    globals: Vec<Global>,
+    functions: Vec<Function>,
+    context: FunctionContext,
+    lambda_count: usize,
+}
+
+impl Lowerer<'_> {
+    fn lower_function(
+        &mut self,
+        function: &FunctionDeclaration,
+        name: &str,
+        owner: Option<MangledType>,
+    ) -> Result<(), Error> {
+        let outer = std::mem::take(&mut self.context);
+        self.context.name = name.to_string();
+
+        for parameter in &function.parameters {
+            self.bind_local(&parameter.name, Some(&parameter.data_type));
+        }
+        self.find_common_subexpressions(&function.body);
+        let body = self.lower_expression(&function.body);
+
+        let context = std::mem::replace(&mut self.context, outer);
+        let body = body?;
+        self.functions.push(Function {
+            name: name.to_string(),
+            symbol: function_symbol(owner, function),
+            parameter_count: function.parameters.len(),
+            max_stack: frame::ir_stack_depth(&body),
+            locals: context.locals,
+            return_data_type: function.return_data_type.clone(),
+            body,
+        });
+        Ok(())
+    }
+
+    fn lower_anonymous_function(&mut self, function: &AnonymousFunction) -> Result<Expr, Error> {
+        // 捕获的局部变量，即匿名函数里未被绑定、而在当前作用域里可见的名称
+        let parameter_names: Vec<&str> = function
+            .parameters
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        let mut captures: Vec<(String, LocalId)> = vec![];
+        for free in semantics::unbound_names(&parameter_names, &[], &[&function.body]) {
+            if let Some(id) = self.lookup_local(&free.name) {
+                if !captures.iter().any(|(name, _)| *name == free.name) {
+                    captures.push((free.name, id));
+                }
+            }
+        }
+
+        let name = format!("{}$lambda{}", self.context.name, self.lambda_count);
+        self.lambda_count += 1;
+
+        let outer = std::mem::take(&mut self.context);
+        self.context.name = name.clone();
+        for (capture_name, id) in &captures {
+            let data_type = outer.locals[*id].data_type.clone();
+            self.bind_local(capture_name, data_type.as_ref());
+        }
+        for parameter in &function.parameters {
+            self.bind_local(&parameter.name, parameter.data_type.as_ref());
+        }
+        self.find_common_subexpressions(&function.body);
+        let body = self.lower_expression(&function.body);
+
+        let context = std::mem::replace(&mut self.context, outer);
+        let body = body?;
+        self.functions.push(Function {
+            name: name.clone(),
+            symbol: name.clone(),
+            parameter_count: captures.len() + function.parameters.len(),
+            max_stack: frame::ir_stack_depth(&body),
+            locals: context.locals,
+            return_data_type: function.return_data_type.clone(),
+            body,
+        });
+
+        Ok(Expr::Closure(
+            name,
+            captures.into_iter().map(|(_, id)| id).collect(),
+        ))
+    }
+
+    fn lower_expression(&mut self, expression: &Expression) -> Result<Expr, Error> {
+        if let Some((_, id)) = self
+            .context
+            .hoisted
+            .iter()
+            .find(|(hoisted, _)| std::ptr::eq(*hoisted, expression))
+        {
+            return Ok(Expr::Local(*id));
+        }
+
+        let group = self
+            .context
+            .subexpressions
+            .iter()
+            .find(|(occurrence, _)| std::ptr::eq(*occurrence, expression))
+            .map(|(_, group)| *group);
+        if let Some(group) = group {
+            if let Some(id) = self.context.subexpression_locals[group] {
+                return Ok(Expr::Local(id));
+            }
+        }
+
+        let expr = match expression {
+            Expression::BlockExpression(v) => {
+                let mark = self.context.scope.len();
+                let body = self.lower_sequence(&v.body);
+                self.context.scope.truncate(mark);
+                new_block(body?)
+            }
+            Expression::JoinExpression(v) => {
+                let mark = self.context.scope.len();
+                let parts = self.lower_list(&v.body);
+                self.context.scope.truncate(mark);
+                new_operation(Operation::Join, parts?)
+            }
+            Expression::LetExpression(v) => {
+                // 不在表达式块里的 `let` 表达式，绑定的名称在当前作用域里有效
+                let mut body = vec![];
+                self.lower_let(v, &mut body)?;
+                new_block(body)
+            }
+            Expression::IfExpression(v) => self.lower_if(v)?,
+            Expression::ForExpression(v) => {
+                let invariants = self.loop_invariants(expression);
+                self.lower_for(v, &invariants)?
+            }
+            Expression::NextExpression(v) => match self.context.loops.last() {
+                Some(LoopKind::For) => Expr::Next(vec![self.lower_expression(&v.value)?]),
+                Some(LoopKind::Each) => return Err(unsupported("\"next\" in \"each\"", &v.range)),
+                None => return Err(unsupported("\"next\" outside of \"for\"", &v.range)),
+            },
+            Expression::EachExpression(v) => {
+                let invariants = self.loop_invariants(expression);
+                self.lower_each(v, &invariants)?
+            }
+            Expression::BranchExpression(v) => self.lower_branch(v)?,
+            Expression::MatchExpression(v) => self.lower_match(v)?,
+            Expression::Sign(v) => return Err(unsupported("function signature", &v.range)),
+            Expression::BinaryExpression(v) => {
+                let left = self.lower_expression(&v.left)?;
+                let right = self.lower_expression(&v.right)?;
+                lower_binary(v.operator, left, right)
+            }
+            Expression::NamedOperatorExpression(v) => {
+                let left = self.lower_expression(&v.left)?;
+                let right = self.lower_expression(&v.right)?;
+                Expr::Call(Box::new(Expr::Global(v.name.clone())), vec![left, right])
+            }
+            Expression::UnaryExpression(v) => {
+                let operand = self.lower_expression(&v.operand)?;
+                let operation = match v.operator {
+                    UnaryOperator::Cast => Operation::Cast,
+                    UnaryOperator::Minus => Operation::Negate,
+                    UnaryOperator::Unwrap => Operation::Unwrap,
+                };
+                new_operation(operation, vec![operand])
+            }
+            Expression::FunctionCallExpression(v) => {
+                let mut callee = self.lower_expression(&v.callee)?;
+                let arguments = self.lower_arguments(&mut callee, &v.arguments, &v.range)?;
+                Expr::Call(Box::new(callee), arguments)
+            }
+            Expression::MemberExpression(MemberExpression::Property(v)) => {
+                let object = self.lower_expression(&v.object)?;
+                let operation = match v.property.as_ref() {
+                    Expression::Identifier(i) => Operation::Field(i.name.clone()),
+                    Expression::Literal(Literal::Integer(i)) => {
+                        Operation::Element(i.value as usize)
+                    }
+                    property => return Err(unsupported("this property", property.range())),
+                };
+                new_operation(operation, vec![object])
+            }
+            Expression::MemberExpression(MemberExpression::Index(v)) => {
+                let object = self.lower_expression(&v.object)?;
+                let index = self.lower_expression(&v.index)?;
+                new_operation(Operation::Index, vec![object, index])
+            }
+            Expression::SliceExpression(v) => {
+                let object = self.lower_expression(&v.object)?;
+                let from = self.lower_expression(&v.interval.from)?;
+                let to = self.lower_optional(v.interval.to.as_deref())?;
+                let interval =
+                    new_operation(Operation::Interval(v.interval.is_inclusive), vec![from, to]);
+                new_operation(Operation::Slice, vec![object, interval])
+            }
+            Expression::ConstructorExpression(v) => {
+                let mut names = vec![];
+                let mut values = vec![];
+                for entry in &v.value.elements {
+                    let Expression::Identifier(key) = entry.key.as_ref() else {
+                        return Err(unsupported("this struct field", entry.key.range()));
+                    };
+                    names.push(key.name.clone());
+                    values.push(match &entry.value {
+                        Some(value) => self.lower_expression(value)?,
+                        None => self.lower_identifier(key),
+                    });
+                }
+                new_operation(Operation::Struct(full_name(&v.object), names), values)
+            }
+            Expression::TupleConstructorExpression(v) => {
+                let values = self.lower_list(&v.value.elements)?;
+                new_operation(Operation::TupleStruct(full_name(&v.object)), values)
+            }
+            Expression::AnonymousFunction(v) => self.lower_anonymous_function(v)?,
+            Expression::Identifier(v) => self.lower_identifier(v),
+            Expression::PrefixIdentifier(v) => Expr::Global(full_name(&v.identifier)),
+            Expression::Ellipsis(v) => return Err(unsupported("ellipsis", &v.range)),
+            Expression::Interval(v) => {
+                let from = self.lower_expression(&v.from)?;
+                let to = self.lower_optional(v.to.as_deref())?;
+                new_operation(Operation::Interval(v.is_inclusive), vec![from, to])
+            }
+            Expression::Tuple(v) => new_operation(Operation::Tuple, self.lower_list(&v.elements)?),
+            Expression::List(v) => new_operation(Operation::List, self.lower_list(&v.elements)?),
+            Expression::Map(v) => {
+                let mut values = vec![];
+                for entry in &v.elements {
+                    values.push(self.lower_expression(&entry.key)?);
+                    values.push(match &entry.value {
+                        Some(value) => self.lower_expression(value)?,
+                        None => self.lower_expression(&entry.key)?,
+                    });
+                }
+                new_operation(Operation::Map, values)
+            }
+            Expression::Literal(v) => self.lower_literal(v)?,
+        };
+
+        // 公共子表达式第一次出现时求值并保存到临时变量
+        if let Some(group) = group {
+            let temporary = self.new_temporary();
+            self.context.subexpression_locals[group] = Some(temporary);
+            return Ok(Expr::Block(vec![
+                Expr::Let(temporary, Box::new(expr)),
+                Expr::Local(temporary),
+            ]));
+        }
+        Ok(expr)
+    }
+
+    // 记录表达式（函数或者循环的主体）里的公共子表达式，未启用该优化时不做任何事情
+    fn find_common_subexpressions(&mut self, expression: &Expression) {
+        if !self.options.eliminate_common_subexpressions {
+            return;
+        }
+
+        for occurrences in optimize::common_subexpressions(expression) {
+            let group = self.context.subexpression_locals.len();
+            self.context.subexpression_locals.push(None);
+            self.context.subexpressions.extend(
+                occurrences
+                    .into_iter()
+                    .map(|occurrence| (occurrence as *const Expression, group)),
+            );
+        }
+    }
+
+    // 依次降级表达式，`let` 表达式绑定的名称在后面的表达式里有效
+    fn lower_sequence(&mut self, expressions: &[Expression]) -> Result<Vec<Expr>, Error> {
+        let mut body = vec![];
+        for expression in expressions {
+            match expression {
+                Expression::LetExpression(l) => self.lower_let(l, &mut body)?,
+                _ => body.push(self.lower_expression(expression)?),
+            }
+        }
+        Ok(body)
+    }
+
+    fn lower_list(&mut self, expressions: &[Expression]) -> Result<Vec<Expr>, Error> {
+        expressions
+            .iter()
+            .map(|e| self.lower_expression(e))
+            .collect()
+    }
+
+    fn lower_optional(&mut self, expression: Option<&Expression>) -> Result<Expr, Error> {
+        match expression {
+            Some(e) => self.lower_expression(e),
+            None => Ok(Expr::Unit),
+        }
+    }
+
+    // `where` 从属表达式定义的名称在所属的表达式里有效，所以表达式块不产生新的作用域
+    fn lower_where(&mut self, where_exp: Option<&Expression>) -> Result<Vec<Expr>, Error> {
+        match where_exp {
+            Some(Expression::BlockExpression(block)) => self.lower_sequence(&block.body),
+            Some(e) => self.lower_sequence(std::slice::from_ref(e)),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn lower_let(&mut self, expression: &LetExpression, body: &mut Vec<Expr>) -> Result<(), Error> {
+        let value = self.lower_expression(&expression.value)?;
+        self.bind_pattern(
+            &expression.object,
+            expression.data_type.as_ref(),
+            value,
+            false,
+            body,
+        )
+    }
+
+    fn lower_if(&mut self, expression: &IfExpression) -> Result<Expr, Error> {
+        let mark = self.context.scope.len();
+        let mut body = self.lower_where(expression.where_exp.as_deref())?;
+        let testing = self.lower_expression(&expression.testing)?;
+        let consequent = self.lower_expression(&expression.consequent)?;
+        let alternate = self.lower_optional(expression.alternate.as_deref())?;
+        self.context.scope.truncate(mark);
+
+        body.push(Expr::If(
+            Box::new(testing),
+            Box::new(consequent),
+            Box::new(alternate),
+        ));
+        Ok(new_block(body))
+    }
+
+    // 需要移到循环之前的循环不变量，未启用该优化时返回空列表
+    fn loop_invariants<'e>(&self, expression: &'e Expression) -> Vec<&'e Expression> {
+        if self.options.hoist_loop_invariants {
+            optimize::loop_invariants(expression)
+        } else {
+            vec![]
+        }
+    }
+
+    // 在循环之前求值循环不变量并保存到临时变量，之后降级循环的主体时，
+    // 循环不变量直接使用临时变量。
+    //
+    // guard 为循环的主体至少执行一次的条件，只在条件成立时才求值循环不变量，
+    // 以免求值原本不会被求值的、可能出错的表达式。
+    fn hoist_loop_invariants(
+        &mut self,
+        invariants: &[&Expression],
+        guard: Option<Expr>,
+        block: &mut Vec<Expr>,
+    ) -> Result<(), Error> {
+        let mut lets = vec![];
+        for invariant in invariants {
+            let value = self.lower_expression(invariant)?;
+            let temporary = self.new_temporary();
+            lets.push(Expr::Let(temporary, Box::new(value)));
+            self.context
+                .hoisted
+                .push((*invariant as *const Expression, temporary));
+        }
+
+        match guard {
+            Some(testing) if !lets.is_empty() => block.push(Expr::If(
+                Box::new(testing),
+                Box::new(new_block(lets)),
+                Box::new(Expr::Unit),
+            )),
+            _ => block.extend(lets),
+        }
+        Ok(())
+    }
+
+    fn lower_for(
+        &mut self,
+        expression: &ForExpression,
+        invariants: &[&Expression],
+    ) -> Result<Expr, Error> {
+        let initializer = &expression.initializer;
+        let mut value = self.lower_expression(&initializer.value)?;
+
+        // `for` 表达式的主体至少执行一次，循环不变量在循环变量的初始值之后求值
+        let mut block = vec![];
+        if !invariants.is_empty() && !optimize::is_pure(&initializer.value) {
+            let temporary = self.new_temporary();
+            block.push(Expr::Let(temporary, Box::new(value)));
+            value = Expr::Local(temporary);
+        }
+        self.hoist_loop_invariants(invariants, None, &mut block)?;
+
+        let mark = self.context.scope.len();
+        let mut body = vec![];
+        let variable = match initializer.object.as_ref() {
+            Expression::Identifier(i) => self.bind_local(&i.name, initializer.data_type.as_ref()),
+            pattern => {
+                // 解构的模式使用临时变量作为循环变量，每次迭代时重新解构
+                let variable = self.new_temporary();
+                self.bind_pattern(pattern, None, Expr::Local(variable), false, &mut body)?;
+                variable
+            }
+        };
+
+        self.context.loops.push(LoopKind::For);
+        self.find_common_subexpressions(&expression.body);
+        let loop_body = self.lower_expression(&expression.body);
+        self.context.loops.pop();
+        self.context.scope.truncate(mark);
+
+        body.push(loop_body?);
+        block.push(Expr::Loop(
+            vec![(variable, value)],
+            Box::new(new_block(body)),
+        ));
+        Ok(new_block(block))
+    }
+
+    // `each variable in object body` 转换为：
+    //
+    // ```text
+    // let $o = object
+    // loop ($i = 0, $r = []) {
+    //     if $i < length($o) then {
+    //         let variable = $o[$i]
+    //         next ($i + 1, append($r, body))
+    //     } else $r
+    // }
+    // ```
+    //
+    // 循环不变量在 `let $o = object` 之后、并且 `$o` 不为空时求值。
+    fn lower_each(
+        &mut self,
+        expression: &EachExpression,
+        invariants: &[&Expression],
+    ) -> Result<Expr, Error> {
+        if let Expression::List(list) = expression.object.as_ref() {
+            if let [Expression::Interval(interval)] = list.elements.as_slice() {
+                if let Some(to) = &interval.to {
+                    if is_integer_interval(&interval.from, to) {
+                        return self.lower_each_interval(
+                            expression,
+                            &interval.from,
+                            to,
+                            interval.is_inclusive,
+                            invariants,
+                        );
+                    }
+                }
+            }
+        }
+
+        let object_value = self.lower_expression(&expression.object)?;
+        let object = self.new_temporary();
+        let index = self.new_temporary();
+        let result = self.new_temporary();
+
+        let mut block = vec![Expr::Let(object, Box::new(object_value))];
+        let not_empty = new_operation(
+            Operation::LessThan,
+            vec![
+                Expr::Constant(Constant::Integer(0)),
+                new_operation(Operation::Length, vec![Expr::Local(object)]),
+            ],
+        );
+        self.hoist_loop_invariants(invariants, Some(not_empty), &mut block)?;
+
+        let mark = self.context.scope.len();
+        let mut body = vec![];
+        let element = new_operation(
+            Operation::Index,
+            vec![Expr::Local(object), Expr::Local(index)],
+        );
+        self.bind_pattern(&expression.variable, None, element, false, &mut body)?;
+
+        self.context.loops.push(LoopKind::Each);
+        self.find_common_subexpressions(&expression.body);
+        let value = self.lower_expression(&expression.body);
+        self.context.loops.pop();
+        self.context.scope.truncate(mark);
+
+        body.push(Expr::Next(vec![
+            new_operation(
+                Operation::Add,
+                vec![Expr::Local(index), Expr::Constant(Constant::Integer(1))],
+            ),
+            new_operation(Operation::Append, vec![Expr::Local(result), value?]),
+        ]));
+
+        let testing = new_operation(
+            Operation::LessThan,
+            vec![
+                Expr::Local(index),
+                new_operation(Operation::Length, vec![Expr::Local(object)]),
+            ],
+        );
+
+        block.push(Expr::Loop(
+            vec![
+                (index, Expr::Constant(Constant::Integer(0))),
+                (result, new_operation(Operation::List, vec![])),
+            ],
+            Box::new(Expr::If(
+                Box::new(testing),
+                Box::new(Expr::Block(body)),
+                Box::new(Expr::Local(result)),
+            )),
+        ));
+        Ok(Expr::Block(block))
+    }
+
+    // 被遍历的对象是只包含一个整数范围的列表时（比如 `each x in [1..100] body`），
+    // 转换为计数循环，而不构造列表：
+    //
+    // ```text
+    // let $t = to
+    // loop ($c = from, $r = []) {
+    //     if $c < $t then {          // 闭区间为 `$c <= $t`
+    //         let variable = $c
+    //         next ($c + 1, append($r, body))
+    //     } else $r
+    // }
+    // ```
+    //
+    // 常量的边界直接写入循环的初始值以及结束条件，两个边界均为常量并且范围为空时
+    // （已由语义检查报告）不生成循环，值为空的列表。
+    //
+    // 循环不变量在范围不为空时求值，这时不是常量的起始值也保存到临时变量。
+    fn lower_each_interval(
+        &mut self,
+        expression: &EachExpression,
+        from: &Expression,
+        to: &Expression,
+        is_inclusive: bool,
+        invariants: &[&Expression],
+    ) -> Result<Expr, Error> {
+        let from_constant = fold::fold(from);
+        let to_constant = fold::fold(to);
+        if let (Some(Constant::Integer(l)), Some(Constant::Integer(r))) =
+            (&from_constant, &to_constant)
+        {
+            if (is_inclusive && l > r) || (!is_inclusive && l >= r) {
+                return Ok(new_operation(Operation::List, vec![]));
+            }
+        }
+
+        let mut from_value = match from_constant {
+            Some(c) => Expr::Constant(c),
+            None => self.lower_expression(from)?,
+        };
+
+        let mut block = vec![];
+        let to_value = match to_constant {
+            Some(c) => Expr::Constant(c),
+            None => {
+                let value = self.lower_expression(to)?;
+                let temporary = self.new_temporary();
+                block.push(Expr::Let(temporary, Box::new(value)));
+                Expr::Local(temporary)
+            }
+        };
+
+        let comparison = if is_inclusive {
+            Operation::LessThanOrEqual
+        } else {
+            Operation::LessThan
+        };
+        if !invariants.is_empty() {
+            if !matches!(from_value, Expr::Constant(_)) {
+                let temporary = self.new_temporary();
+                block.push(Expr::Let(temporary, Box::new(from_value)));
+                from_value = Expr::Local(temporary);
+            }
+            let not_empty = new_operation(
+                comparison.clone(),
+                vec![from_value.clone(), to_value.clone()],
+            );
+            self.hoist_loop_invariants(invariants, Some(not_empty), &mut block)?;
+        }
+
+        let counter = self.new_temporary();
+        let result = self.new_temporary();
+
+        let mark = self.context.scope.len();
+        let mut body = vec![];
+        self.bind_pattern(
+            &expression.variable,
+            None,
+            Expr::Local(counter),
+            false,
+            &mut body,
+        )?;
+
+        self.context.loops.push(LoopKind::Each);
+        self.find_common_subexpressions(&expression.body);
+        let value = self.lower_expression(&expression.body);
+        self.context.loops.pop();
+        self.context.scope.truncate(mark);
+
+        body.push(Expr::Next(vec![
+            new_operation(
+                Operation::Add,
+                vec![Expr::Local(counter), Expr::Constant(Constant::Integer(1))],
+            ),
+            new_operation(Operation::Append, vec![Expr::Local(result), value?]),
+        ]));
+
+        let testing = new_operation(comparison, vec![Expr::Local(counter), to_value]);
+
+        block.push(Expr::Loop(
+            vec![
+                (counter, from_value),
+                (result, new_operation(Operation::List, vec![])),
+            ],
+            Box::new(Expr::If(
+                Box::new(testing),
+                Box::new(Expr::Block(body)),
+                Box::new(Expr::Local(result)),
+            )),
+        ));
+        Ok(new_block(block))
+    }
+
+    // `branch` 表达式转换为嵌套的条件表达式
+    fn lower_branch(&mut self, expression: &BranchExpression) -> Result<Expr, Error> {
+        let mark = self.context.scope.len();
+        let mut body = self.lower_where(expression.where_exp.as_deref())?;
+
+        // 各个分支依次降级，以便局部变量的编号按照源代码的顺序分配
+        let mut cases = vec![];
+        for case in &expression.cases {
+            let case_mark = self.context.scope.len();
+            let where_body = self.lower_where(case.where_exp.as_deref())?;
+            let testing = self.lower_expression(&case.testing)?;
+            let consequent = self.lower_expression(&case.consequent)?;
+            self.context.scope.truncate(case_mark);
+            cases.push((where_body, testing, consequent));
+        }
+        let mut chain = self.lower_optional(expression.default_exp.as_deref())?;
+        self.context.scope.truncate(mark);
+
+        for (mut where_body, testing, consequent) in cases.into_iter().rev() {
+            where_body.push(Expr::If(
+                Box::new(testing),
+                Box::new(consequent),
+                Box::new(chain),
+            ));
+            chain = new_block(where_body);
+        }
+
+        body.push(chain);
+        Ok(new_block(body))
+    }
+
+    // `match` 表达式转换为嵌套的条件表达式，每个分支依次求值：
+    // `where` 从属表达式、变量的绑定、模式的测试，以及 `only` 从属表达式
+    fn lower_match(&mut self, expression: &MatchExpression) -> Result<Expr, Error> {
+        let object_value = self.lower_expression(&expression.object)?;
+        let object = self.new_temporary();
+
+        let mark = self.context.scope.len();
+        let mut body = vec![Expr::Let(object, Box::new(object_value))];
+        body.extend(self.lower_where(expression.where_exp.as_deref())?);
+
+        let mut cases = vec![];
+        for case in &expression.cases {
+            let case_mark = self.context.scope.len();
+            let mut case_body = self.lower_where(case.where_exp.as_deref())?;
+            if let Some(variable) = &case.variable {
+                let id = self.bind_local(variable, None);
+                case_body.push(Expr::Let(id, Box::new(Expr::Local(object))));
+            }
+
+            let mut testing = match case.pattern.as_deref() {
+                Some(pattern) => {
+                    self.lower_match_pattern(pattern, object, &mut case_body, &case.range)?
+                }
+                None => Expr::Constant(Constant::Boolean(true)),
+            };
+            if let Some(only) = &case.only {
+                let only = self.lower_expression(only)?;
+                testing = if testing == Expr::Constant(Constant::Boolean(true)) {
+                    only
+                } else {
+                    Expr::If(
+                        Box::new(testing),
+                        Box::new(only),
+                        Box::new(Expr::Constant(Constant::Boolean(false))),
+                    )
+                };
+            }
+
+            let consequent = self.lower_expression(&case.consequent)?;
+            self.context.scope.truncate(case_mark);
+            cases.push((case_body, testing, consequent));
+        }
+        let mut chain = self.lower_optional(expression.default_exp.as_deref())?;
+        self.context.scope.truncate(mark);
+
+        for (mut case_body, testing, consequent) in cases.into_iter().rev() {
+            case_body.push(Expr::If(
+                Box::new(testing),
+                Box::new(consequent),
+                Box::new(chain),
+            ));
+            chain = new_block(case_body);
+        }
+
+        body.push(chain);
+        Ok(Expr::Block(body))
+    }
+
+    // 降级分支的模式，返回测试的条件，模式绑定的名称添加到 body
+    //
+    // 支持常量（包括哈希字符串）、单独一个名称以及 `in` 模式。
+    fn lower_match_pattern(
+        &mut self,
+        pattern: &PatternExpression,
+        object: LocalId,
+        body: &mut Vec<Expr>,
+        range: &Range,
+    ) -> Result<Expr, Error> {
+        match pattern {
+            PatternExpression::Primary(Expression::Identifier(i)) if i.dirs.is_empty() => {
+                let id = self.bind_local(&i.name, None);
+                body.push(Expr::Let(id, Box::new(Expr::Local(object))));
+                Ok(Expr::Constant(Constant::Boolean(true)))
+            }
+            PatternExpression::Primary(Expression::Literal(Literal::HashString(h))) => {
+                Ok(new_operation(
+                    Operation::Equal,
+                    vec![Expr::Local(object), Expr::HashString(h.value.clone())],
+                ))
+            }
+            PatternExpression::Primary(e) => match fold::fold(e) {
+                Some(constant) => Ok(new_operation(
+                    Operation::Equal,
+                    vec![Expr::Local(object), Expr::Constant(constant)],
+                )),
+                None => Err(unsupported("this pattern", e.range())),
+            },
+            PatternExpression::In(e) => {
+                let collection = self.lower_expression(e)?;
+                Ok(new_operation(
+                    Operation::Contains,
+                    vec![collection, Expr::Local(object)],
+                ))
+            }
+            PatternExpression::Into(..) => Err(unsupported("\"into\" pattern", range)),
+            PatternExpression::Regular(..) => Err(unsupported("\"regular\" pattern", range)),
+            PatternExpression::Template(_) => Err(unsupported("\"template\" pattern", range)),
+        }
+    }
+
+    // 把值绑定到 `let` 表达式的左手边值（以及 `for`、`each` 的变量）等模式，
+    // global 为 true 时名称绑定为全局变量（顶层的 `let` 表达式）。
+    fn bind_pattern(
+        &mut self,
+        pattern: &Expression,
+        data_type: Option<&DataType>,
+        value: Expr,
+        global: bool,
+        body: &mut Vec<Expr>,
+    ) -> Result<(), Error> {
+        if let Expression::Identifier(i) = pattern {
+            if global {
+                self.globals.push(Global {
+                    name: i.name.clone(),
+                    data_type: data_type.cloned(),
+                    is_const: false,
+                });
+                body.push(Expr::SetGlobal(i.name.clone(), Box::new(value)));
+            } else {
+                let id = self.bind_local(&i.name, data_type);
+                body.push(Expr::Let(id, Box::new(value)));
+            }
+            return Ok(());
+        }
+
+        // 解构的模式：先把值保存到临时变量，然后逐个绑定各个成员
+        let elements: Vec<(Operation, &Expression)> = match pattern {
+            Expression::Tuple(t) => {
+                element_patterns(&t.elements, Operation::Element, pattern.range())?
+            }
+            Expression::TupleConstructorExpression(c) => {
+                element_patterns(&c.value.elements, Operation::Element, pattern.range())?
+            }
+            Expression::List(l) => {
+                element_patterns(&l.elements, |_| Operation::Index, pattern.range())?
+            }
+            Expression::ConstructorExpression(c) => {
+                let mut elements = vec![];
+                for entry in &c.value.elements {
+                    let Expression::Identifier(key) = entry.key.as_ref() else {
+                        return Err(unsupported("this struct pattern", entry.key.range()));
+                    };
+                    elements.push((
+                        Operation::Field(key.name.clone()),
+                        entry.value.as_deref().unwrap_or(&entry.key),
+                    ));
+                }
+                elements
+            }
+            _ => return Err(unsupported("this pattern", pattern.range())),
+        };
+
+        let temporary = self.new_temporary();
+        body.push(Expr::Let(temporary, Box::new(value)));
+
+        for (index, (operation, element)) in elements.into_iter().enumerate() {
+            // 列表的剩余部分
+            if let Expression::Ellipsis(e) = element {
+                if let Some(name) = &e.name {
+                    let interval = new_operation(
+                        Operation::Interval(false),
+                        vec![Expr::Constant(Constant::Integer(index as i64)), Expr::Unit],
+                    );
+                    let rest =
+                        new_operation(Operation::Slice, vec![Expr::Local(temporary), interval]);
+                    self.bind_name(name, rest, global, body);
+                }
+                continue;
+            }
+
+            let element_value = if operation == Operation::Index {
+                new_operation(
+                    Operation::Index,
+                    vec![
+                        Expr::Local(temporary),
+                        Expr::Constant(Constant::Integer(index as i64)),
+                    ],
+                )
+            } else {
+                new_operation(operation, vec![Expr::Local(temporary)])
+            };
+            self.bind_pattern(element, None, element_value, global, body)?;
+        }
+
+        Ok(())
+    }
+
+    fn bind_name(&mut self, name: &str, value: Expr, global: bool, body: &mut Vec<Expr>) {
+        if global {
+            self.globals.push(Global {
+                name: name.to_string(),
+                data_type: None,
+                is_const: false,
+            });
+            body.push(Expr::SetGlobal(name.to_string(), Box::new(value)));
+        } else {
+            let id = self.bind_local(name, None);
+            body.push(Expr::Let(id, Box::new(value)));
+        }
+    }
+
+    // 按照形参的顺序排列实参，并补全默认值
+    //
+    // 只有调用本模块的（未被遮盖的）顶层函数时才能使用命名参数以及省略有默认值的参数，
+    // 默认值在调用者里求值。
+    //
+    // 被调用的函数是重载的函数时，选择能够接受这些实参的那个，并把被调用者
+    // 替换为它的符号名称，有多个或者没有能够接受的函数时返回错误。
+    fn lower_arguments(
+        &mut self,
+        callee: &mut Expr,
+        arguments: &[Argument],
+        range: &Range,
+    ) -> Result<Vec<Expr>, Error> {
+        let program = self.program;
+        let overloads: Vec<&FunctionDeclaration> = match callee {
+            Expr::Global(name) => program
+                .body
+                .iter()
+                .filter_map(|statement| match statement {
+                    Statement::FunctionDeclaration(f) if f.name == *name => Some(f),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        };
+
+        let function = match overloads[..] {
+            [] => None,
+            [function] => Some(function),
+            _ => {
+                let candidates: Vec<&FunctionDeclaration> = overloads
+                    .into_iter()
+                    .filter(|f| accepts(f, arguments))
+                    .collect();
+                let [function] = candidates[..] else {
+                    return Err(unsupported("call of overloaded function", range));
+                };
+                *callee = Expr::Global(function_symbol(None, function));
+                Some(function)
+            }
+        };
+
+        let Some(function) = function else {
+            if let Some(argument) = arguments.iter().find(|a| a.name.is_some()) {
+                return Err(unsupported(
+                    "named arguments of an unknown function",
+                    &argument.range,
+                ));
+            }
+            return arguments
+                .iter()
+                .map(|a| self.lower_expression(&a.value))
+                .collect();
+        };
+
+        let mut slots: Vec<Option<&Expression>> = vec![None; function.parameters.len()];
+        for (index, argument) in arguments.iter().enumerate() {
+            let position = match &argument.name {
+                Some(name) => function.parameters.iter().position(|p| p.name == *name),
+                None => (index < slots.len()).then_some(index),
+            };
+            match position {
+                Some(position) => slots[position] = Some(&argument.value),
+                None => return Err(unsupported("this argument", &argument.range)),
+            }
+        }
+
+        let mut values = vec![];
+        for (slot, FunctionParameter { name, value, .. }) in
+            slots.into_iter().zip(&function.parameters)
+        {
+            match slot.or(value.as_ref()) {
+                Some(e) => values.push(self.lower_expression(e)?),
+                None => {
+                    return Err(Error::SemanticError {
+                        message: format!(
+                            "missing argument \"{}\" of function \"{}\"",
+                            name, function.name
+                        ),
+                        related: vec![],
+                        range: range.clone(),
+                    })
+                }
+            }
+        }
+        Ok(values)
+    }
+
+    fn lower_identifier(&self, identifier: &Identifier) -> Expr {
+        if identifier.dirs.is_empty() {
+            if let Some(id) = self.lookup_local(&identifier.name) {
+                return Expr::Local(id);
+            }
+        }
+        Expr::Global(full_name(identifier))
+    }
+
+    fn lower_literal(&mut self, literal: &Literal) -> Result<Expr, Error> {
+        let expr = match literal {
+            Literal::HashString(h) => Expr::HashString(h.value.clone()),
+            Literal::NamedOperator(n) => Expr::Global(n.value.clone()),
+            Literal::TemplateString(t) => {
+                // 模板字符串转换为片段以及表达式的拼接
+                let mut parts = vec![];
+                for (index, fragment) in t.fragments.iter().enumerate() {
+                    if !fragment.is_empty() {
+                        parts.push(Expr::Constant(Constant::String(fragment.clone())));
+                    }
+                    if let Some(e) = t.expressions.get(index) {
+                        parts.push(self.lower_expression(e)?);
+                    }
+                }
+                new_operation(Operation::Join, parts)
+            }
+            _ => match fold::fold(&Expression::Literal(literal.clone())) {
+                Some(constant) => Expr::Constant(constant),
+                None => return Err(unsupported("this literal", literal.range())),
+            },
+        };
+        Ok(expr)
+    }
+
+    fn lookup_local(&self, name: &str) -> Option<LocalId> {
+        self.context
+            .scope
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, id)| *id)
+    }
+
+    fn bind_local(&mut self, name: &str, data_type: Option<&DataType>) -> LocalId {
+        let id = self.context.locals.len();
+        self.context.locals.push(Local {
+            name: name.to_string(),
+            data_type: data_type.cloned(),
+        });
+        self.context.scope.push((name.to_string(), id));
+        id
+    }
+
+    fn new_temporary(&mut self) -> LocalId {
+        let id = self.context.locals.len();
+        self.context.locals.push(Local {
+            name: String::new(),
+            data_type: None,
+        });
+        id
+    }
+}
+
+fn lower_binary(operator: BinaryOperator, left: Expr, right: Expr) -> Expr {
+    let operation = match operator {
+        // 管道运算 `a | f` 即 `f(a)`
+        BinaryOperator::Pipe => return Expr::Call(Box::new(right), vec![left]),
+        // 逻辑运算转换为条件表达式，以实现短路求值
+        BinaryOperator::LogicOr => {
+            return Expr::If(
+                Box::new(left),
+                Box::new(Expr::Constant(Constant::Boolean(true))),
+                Box::new(right),
+            )
+        }
+        BinaryOperator::LogicAnd => {
+            return Expr::If(
+                Box::new(left),
+                Box::new(right),
+                Box::new(Expr::Constant(Constant::Boolean(false))),
+            )
+        }
+        BinaryOperator::Equal => Operation::Equal,
+        BinaryOperator::NotEqual => Operation::NotEqual,
+        BinaryOperator::GreaterThan => Operation::GreaterThan,
+        BinaryOperator::GreaterThanOrEqual => Operation::GreaterThanOrEqual,
+        BinaryOperator::LessThan => Operation::LessThan,
+        BinaryOperator::LessThanOrEqual => Operation::LessThanOrEqual,
+        BinaryOperator::Concat => Operation::Concat,
+        BinaryOperator::Plus => Operation::Add,
+        BinaryOperator::Minus => Operation::Subtract,
+        BinaryOperator::Asterisk => Operation::Multiply,
+        BinaryOperator::Slash => Operation::Divide,
+        BinaryOperator::OptionalOr => Operation::OptionalOr,
+        BinaryOperator::OptionalAnd => Operation::OptionalAnd,
+        BinaryOperator::Combine => Operation::Combine,
+    };
+    new_operation(operation, vec![left, right])
+}
+
+// 元组以及列表的模式的各个成员，剩余部分（`...`）只能位于末尾
+fn element_patterns<'e>(
+    elements: &'e [Expression],
+    operation: impl Fn(usize) -> Operation,
+    range: &Range,
+) -> Result<Vec<(Operation, &'e Expression)>, Error> {
+    if let Some(position) = elements
+        .iter()
+        .position(|e| matches!(e, Expression::Ellipsis(_)))
+    {
+        if position != elements.len() - 1 {
+            return Err(unsupported("rest elements before the last element", range));
+        }
+    }
+
+    Ok(elements
+        .iter()
+        .enumerate()
+        .map(|(index, e)| (operation(index), e))
+        .collect())
+}
+
+// 标识符的完整名称（不包括泛型）
+fn full_name(identifier: &Identifier) -> String {
+    identifier
+        .dirs
+        .iter()
+        .chain(std::iter::once(&identifier.name))
+        .cloned()
+        .collect::<Vec<String>>()
+        .join("::")
+}
+
+// 文本格式
+//
+// ```text
+// const ONE
+//
+// function add (Int $0 a, Int $1 b) type Int {
+//     (add $0 $1)
+// }
+// ```
+//
+// 局部变量使用编号以及名称表示，参数之外的局部变量在函数的开头列出；
+// 表达式使用 S 表达式的格式，包含表达式块、条件以及循环的表达式分多行输出。
+
+impl Display for Module {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for global in &self.globals {
+            let keyword = if global.is_const { "const" } else { "let" };
+            match &global.data_type {
+                Some(data_type) => writeln!(f, "{} {} {}", keyword, data_type, global.name)?,
+                None => writeln!(f, "{} {}", keyword, global.name)?,
+            }
+        }
+
+        for (index, function) in self.functions.iter().enumerate() {
+            if index > 0 || !self.globals.is_empty() {
+                writeln!(f)?;
+            }
+            write!(f, "{}", function)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parameters: Vec<String> = self.locals[..self.parameter_count]
+            .iter()
+            .enumerate()
+            .map(|(id, local)| format_local(id, local))
+            .collect();
+        write!(f, "function {} ({})", self.name, parameters.join(", "))?;
+        if let Some(data_type) = &self.return_data_type {
+            write!(f, " type {}", data_type)?;
+        }
+        writeln!(f, " {{")?;
+
+        for (id, local) in self.locals.iter().enumerate().skip(self.parameter_count) {
+            writeln!(f, "    local {}", format_local(id, local))?;
+        }
+
+        let body = match &self.body {
+            Expr::Block(body) => body.iter().collect(),
+            expr => vec![expr],
+        };
+        for expr in body {
+            writeln!(f, "    {}", format_expr(expr, 1))?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+fn format_local(id: LocalId, local: &Local) -> String {
+    let mut segments = vec![];
+    if let Some(data_type) = &local.data_type {
+        segments.push(data_type.to_string());
+    }
+    segments.push(format!("${}", id));
+    if !local.name.is_empty() {
+        segments.push(local.name.clone());
+    }
+    segments.join(" ")
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format_expr(self, 0))
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Operation::Add => "add",
+            Operation::Subtract => "sub",
+            Operation::Multiply => "mul",
+            Operation::Divide => "div",
+            Operation::Negate => "neg",
+            Operation::Equal => "eq",
+            Operation::NotEqual => "ne",
+            Operation::GreaterThan => "gt",
+            Operation::GreaterThanOrEqual => "ge",
+            Operation::LessThan => "lt",
+            Operation::LessThanOrEqual => "le",
+            Operation::Concat => "concat",
+            Operation::Combine => "combine",
+            Operation::OptionalOr => "optional_or",
+            Operation::OptionalAnd => "optional_and",
+            Operation::Unwrap => "unwrap",
+            Operation::Cast => "cast",
+            Operation::Tuple => "tuple",
+            Operation::List => "list",
+            Operation::Map => "map",
+            Operation::Struct(name, fields) => {
+                return write!(f, "struct {} {{{}}}", name, fields.join(", "))
+            }
+            Operation::TupleStruct(name) => return write!(f, "tuple_struct {}", name),
+            Operation::Interval(true) => "interval_inclusive",
+            Operation::Interval(false) => "interval",
+            Operation::Field(name) => return write!(f, "field .{}", name),
+            Operation::Element(index) => return write!(f, "element .{}", index),
+            Operation::Index => "index",
+            Operation::Slice => "slice",
+            Operation::Length => "length",
+            Operation::Append => "append",
+            Operation::Contains => "contains",
+            Operation::Join => "join",
+        };
+        f.write_str(name)
+    }
+}
+
+// 输出 S 表达式，包含多行表达式的节点的子节点分行输出，indent 为当前的缩进层级
+fn format_expr(expr: &Expr, indent: usize) -> String {
+    let (head, children): (String, Vec<&Expr>) = match expr {
+        Expr::Constant(c) => return c.to_string(),
+        Expr::HashString(h) => return format!("#{}", h),
+        Expr::Unit => return "()".to_string(),
+        Expr::Local(id) => return format!("${}", id),
+        Expr::Global(name) => return format!("@{}", name),
+        Expr::Closure(name, captures) => {
+            let mut segments = vec!["closure".to_string(), format!("@{}", name)];
+            segments.extend(captures.iter().map(|id| format!("${}", id)));
+            return format!("({})", segments.join(" "));
+        }
+        Expr::Let(id, value) => (format!("let ${}", id), vec![value]),
+        Expr::SetGlobal(name, value) => (format!("set @{}", name), vec![value]),
+        Expr::Block(body) => ("block".to_string(), body.iter().collect()),
+        Expr::If(testing, consequent, alternate) => {
+            ("if".to_string(), vec![testing, consequent, alternate])
+        }
+        Expr::Loop(variables, body) => {
+            let variables: Vec<String> = variables
+                .iter()
+                .map(|(id, value)| format!("(${} {})", id, format_expr(value, indent + 1)))
+                .collect();
+            (format!("loop ({})", variables.join(" ")), vec![body])
+        }
+        Expr::Next(values) => ("next".to_string(), values.iter().collect()),
+        Expr::Call(callee, arguments) => {
+            let mut children = vec![callee.as_ref()];
+            children.extend(arguments);
+            ("call".to_string(), children)
+        }
+        Expr::Operation(operation, arguments) => {
+            (operation.to_string(), arguments.iter().collect())
+        }
+    };
+
+    let is_multiline = matches!(expr, Expr::Block(_) | Expr::If(..) | Expr::Loop(..))
+        || children.iter().any(|c| is_multiline(c));
+
+    if is_multiline {
+        let padding = "    ".repeat(indent + 1);
+        let mut text = format!("({}", head);
+        for child in children {
+            text.push('\n');
+            text.push_str(&padding);
+            text.push_str(&format_expr(child, indent + 1));
+        }
+        text.push(')');
+        text
+    } else {
+        let mut segments = vec![head];
+        segments.extend(children.iter().map(|c| format_expr(c, indent)));
+        format!("({})", segments.join(" "))
+    }
+}
+
+fn is_multiline(expr: &Expr) -> bool {
+    match expr {
+        Expr::Block(_) | Expr::If(..) | Expr::Loop(..) => true,
+        Expr::Let(_, value) | Expr::SetGlobal(_, value) => is_multiline(value),
+        Expr::Next(values) | Expr::Operation(_, values) => values.iter().any(is_multiline),
+        Expr::Call(callee, arguments) => is_multiline(callee) || arguments.iter().any(is_multiline),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Node, error::Error, lexer, parser, typed};
+
+    use super::{lower_with_options, Expr, LowerOptions, Module};
+
+    fn lower_from_string(source: &str) -> Result<Module, Error> {
+        lower_from_string_with_options(source, &LowerOptions::default())
+    }
+
+    fn lower_from_string_with_options(
+        source: &str,
+        options: &LowerOptions,
+    ) -> Result<Module, Error> {
+        let token_details = lexer::tokenize(source).unwrap();
+        let mut node = parser::parse(&token_details).unwrap();
+        typed::annotate_types(&mut node);
+        match node {
+            Node::Program(program) => lower_with_options(&program, options),
+            _ => unreachable!(),
+        }
+    }
+
+    // 输出指定名称的函数
+    fn function_text(source: &str, name: &str) -> String {
+        let module = lower_from_string(source).unwrap();
+        module
+            .functions
+            .iter()
+            .find(|f| f.name == name)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_lower_function() {
+        assert_eq!(
+            function_text("function add(Int a, Int b) type Int = a + b", "add"),
+            "\
+function add (Int $0 a, Int $1 b) type Int {
+    (add $0 $1)
+}
+"
+        );
+
+        // 局部变量、同名变量的遮盖以及管道运算
+        assert_eq!(
+            function_text(
+                "function f(Int a) = {\nlet a = a * 2\nlet b = a | inc\nb}",
+                "f"
+            ),
+            "\
+function f (Int $0 a) {
+    local Int $1 a
+    local $2 b
+    (let $1 (mul $0 2))
+    (let $2 (call @inc $1))
+    $2
+}
+"
+        );
+
+        // 命名参数以及默认值
+        assert_eq!(
+            function_text(
+                "function add(Int a, Int b = 2) = a + b\n\
+                function f() = add(b = 3, a = 1) + add(4)",
+                "f"
+            ),
+            "\
+function f () type Int {
+    (add (call @add 1 3) (call @add 4 2))
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_lower_control_flow() {
+        // `branch` 表达式以及短路求值的逻辑运算
+        assert_eq!(
+            function_text(
+                "function f(Int n) = branch {\n\
+                case n > 10 && n < 20: 1\n\
+                default: 0\n\
+                }",
+                "f"
+            ),
+            "\
+function f (Int $0 n) {
+    (if
+        (if
+            (gt $0 10)
+            (lt $0 20)
+            false)
+        1
+        0)
+}
+"
+        );
+
+        // `each` 表达式
+        assert_eq!(
+            function_text("function f(List<Int> xs) = each x in xs {x + 1}", "f"),
+            "\
+function f (List<Int> $0 xs) {
+    local $1
+    local $2
+    local $3
+    local $4 x
+    (let $1 $0)
+    (loop (($2 0) ($3 (list)))
+        (if
+            (lt $2 (length $1))
+            (block
+                (let $4 (index $1 $2))
+                (next (add $2 1) (append $3 (add $4 1))))
+            $3))
+}
+"
+        );
+
+        // 遍历整数范围的 `each` 表达式转换为计数循环，常量的边界被折叠
+        assert_eq!(
+            function_text("function f() = each x in [1..=2 * 50] {x * x}", "f"),
+            "\
+function f () {
+    local $0
+    local $1
+    local $2 x
+    (loop (($0 1) ($1 (list)))
+        (if
+            (le $0 100)
+            (block
+                (let $2 $0)
+                (next (add $0 1) (append $1 (mul $2 $2))))
+            $1))
+}
+"
+        );
+        assert_eq!(
+            function_text("function f(Int n) = each x in [0..n] {x}", "f"),
+            "\
+function f (Int $0 n) {
+    local $1
+    local $2
+    local $3
+    local $4 x
+    (let $1 $0)
+    (loop (($2 0) ($3 (list)))
+        (if
+            (lt $2 $1)
+            (block
+                (let $4 $2)
+                (next (add $2 1) (append $3 $4)))
+            $3))
+}
+"
+        );
+
+        // 空的范围不生成循环，其他类型的范围以及多个元素的列表仍然按照列表遍历
+        assert_eq!(
+            function_text("function f() = each x in [5..5] {x}", "f"),
+            "\
+function f () {
+    (list)
+}
+"
+        );
+        assert!(
+            function_text("function f() = each c in ['a'..='z'] {c}", "f").contains("(length $0)")
+        );
+        assert!(
+            function_text("function f() = each x in [0, 1..10] {x}", "f").contains("(length $0)")
+        );
+
+        // `for` 表达式
+        assert_eq!(
+            function_text(
+                "function f(Int n) = for let i = 0 if i < n then {next i + 1} else i",
+                "f"
+            ),
+            "\
+function f (Int $0 n) {
+    local Int $1 i
+    (loop (($1 0))
+        (if
+            (lt $1 $0)
+            (next (add $1 1))
+            $1))
+}
+"
+        );
+
+        // `match` 表达式
+        assert_eq!(
+            function_text(
+                "function f(Any x) = match x {\n\
+                case #foo: 1\n\
+                case y only y > 100: y\n\
+                default: 0\n\
+                }",
+                "f"
+            ),
+            "\
+function f (Any $0 x) {
+    local $1
+    local $2 y
+    (let $1 $0)
+    (if
+        (eq $1 #foo)
+        1
+        (block
+            (let $2 $1)
+            (if
+                (gt $2 100)
+                $2
+                0)))
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_lower_module() {
+        let module = lower_from_string(
+            "const ONE = 1\n\
+            function f(Int n) = fn (Int x) = x + n + ONE\n\
+            let (p, [q, ...r]) = (1, [2, 3])",
+        )
+        .unwrap();
+
+        let names: Vec<&str> = module.globals.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["ONE", "p", "q", "r"]);
+
+        // 匿名函数提升为模块里的函数
+        let names: Vec<&str> = module.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["f$lambda0", "f", "$init"]);
+        assert_eq!(
+            module.functions[1].body,
+            Expr::Closure("f$lambda0".to_string(), vec![0])
+        );
+        assert_eq!(
+            module.functions[0].to_string(),
+            "\
+function f$lambda0 (Int $0 n, Int $1 x) {
+    (add (add $1 $0) @ONE)
+}
+"
+        );
+
+        // 顶层的表达式
+        assert_eq!(
+            module.functions[2].to_string(),
+            "\
+function $init () {
+    local $0
+    local $1
+    (set @ONE 1)
+    (let $0 (tuple 1 (list 2 3)))
+    (set @p (element .0 $0))
+    (let $1 (element .1 $0))
+    (set @q (index $1 0))
+    (set @r (slice $1 (interval 1 ())))
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_hoist_loop_invariants() {
+        let options = LowerOptions {
+            hoist_loop_invariants: true,
+            ..LowerOptions::default()
+        };
+        let text = |source: &str| {
+            lower_from_string_with_options(source, &options)
+                .unwrap()
+                .functions[0]
+                .to_string()
+        };
+
+        assert_eq!(
+            text(
+                "function f(Int a, Int b) = for let i = g() {\n\
+                    let t = i + a * b\n\
+                    if t > 100 then t else next i + (a - b)\n\
+                }"
+            ),
+            "\
+function f (Int $0 a, Int $1 b) {
+    local $2
+    local $3
+    local $4 i
+    local $5 t
+    (let $2 (call @g))
+    (let $3 (mul $0 $1))
+    (loop (($4 $2))
+        (block
+            (let $5 (add $4 $3))
+            (if
+                (gt $5 100)
+                $5
+                (next (add $4 (sub $0 $1))))))
+}
+"
+        );
+        assert_eq!(
+            text("function f(Int a, List<Int> xs) = each x in xs {x + a * 2}"),
+            "\
+function f (Int $0 a, List<Int> $1 xs) {
+    local $2
+    local $3
+    local $4
+    local $5
+    local $6 x
+    (let $2 $1)
+    (if
+        (lt 0 (length $2))
+        (let $5 (mul $0 2))
+        ())
+    (loop (($3 0) ($4 (list)))
+        (if
+            (lt $3 (length $2))
+            (block
+                (let $6 (index $2 $3))
+                (next (add $3 1) (append $4 (add $6 $5))))
+            $4))
+}
+"
+        );
+        assert_eq!(
+            text("function f(Int a, Int b) = each y in [a + 1..=10] {y * (a + b)}"),
+            "\
+function f (Int $0 a, Int $1 b) {
+    local $2
+    local $3
+    local $4
+    local $5
+    local $6 y
+    (let $2 (add $0 1))
+    (if
+        (le $2 10)
+        (let $3 (add $0 $1))
+        ())
+    (loop (($4 $2) ($5 (list)))
+        (if
+            (le $4 10)
+            (block
+                (let $6 $4)
+                (next (add $4 1) (append $5 (mul $6 $3))))
+            $5))
+}
+"
+        );
+
+        // 未启用优化
+        assert_eq!(
+            lower_from_string("function f(Int a, List<Int> xs) = each x in xs {x + a * 2}")
+                .unwrap()
+                .functions[0]
+                .to_string(),
+            "\
+function f (Int $0 a, List<Int> $1 xs) {
+    local $2
+    local $3
+    local $4
+    local $5 x
+    (let $2 $1)
+    (loop (($3 0) ($4 (list)))
+        (if
+            (lt $3 (length $2))
+            (block
+                (let $5 (index $2 $3))
+                (next (add $3 1) (append $4 (add $5 (mul $0 2)))))
+            $4))
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_eliminate_common_subexpressions() {
+        let options = LowerOptions {
+            eliminate_common_subexpressions: true,
+            ..LowerOptions::default()
+        };
+        let text = |source: &str| {
+            lower_from_string_with_options(source, &options)
+                .unwrap()
+                .functions[0]
+                .to_string()
+        };
+
+        assert_eq!(
+            text("function f(Int a, Int b) = (a + b) * 2 + (a + b) * 3"),
+            "\
+function f (Int $0 a, Int $1 b) type Int {
+    local $2
+    (add
+        (mul
+            (block
+                (let $2 (add $0 $1))
+                $2)
+            2)
+        (mul $2 3))
+}
+"
+        );
+
+        // 名称被重新绑定之后不再是同一个子表达式
+        assert_eq!(
+            text("function f(Int a) = {\nlet x = a * 2\nlet a = 1\nx + a * 2\n}"),
+            "\
+function f (Int $0 a) type Int {
+    local Int $1 x
+    local Int $2 a
+    (let $1 (mul $0 2))
+    (let $2 1)
+    (add $1 (mul $2 2))
+}
+"
+        );
+
+        // 循环的主体
+        assert_eq!(
+            text("function f(List<Int> xs) = each x in xs {x.0 + x.0}"),
+            "\
+function f (List<Int> $0 xs) {
+    local $1
+    local $2
+    local $3
+    local $4 x
+    local $5
+    (let $1 $0)
+    (loop (($2 0) ($3 (list)))
+        (if
+            (lt $2 (length $1))
+            (block
+                (let $4 (index $1 $2))
+                (next
+                    (add $2 1)
+                    (append
+                        $3
+                        (add
+                            (block
+                                (let $5 (element .0 $4))
+                                $5)
+                            $5))))
+            $3))
+}
+"
+        );
+
+        // 匿名函数
+        assert_eq!(
+            lower_from_string_with_options("function f() = fn (Int x) x * x + x * x", &options)
+                .unwrap()
+                .functions[0]
+                .to_string(),
+            "\
+function f$lambda0 (Int $0 x) type Int {
+    local $1
+    (add
+        (block
+            (let $1 (mul $0 $0))
+            $1)
+        $1)
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_lower_errors() {
+        let message = |source: &str| lower_from_string(source).unwrap_err().to_string();
+
+        assert_eq!(
+            message("pattern function f(Int x @ in [1..10]) = x"),
+            "lowering of pattern function is not supported yet"
+        );
+        assert_eq!(
+            message("function f(Int x) = x\nfunction f(String s) = 0\nf(1)"),
+            "lowering of call of overloaded function is not supported yet"
+        );
+        assert_eq!(
+            message("function f(Any x) = match x {case into Int i: i}"),
+            "lowering of \"into\" pattern is not supported yet"
+        );
+        assert_eq!(
+            message("function add(Int a) = a\nadd()"),
+            "missing argument \"a\" of function \"add\""
+        );
+    }
+}
@@ -0,0 +1,192 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// AST 的 JSON 格式
+//
+// 外部工具（编辑器插件、代码检查工具等）应该通过本模块的 `to_json` 和 `from_json`
+// 读写 AST，而不要直接使用 serde 序列化 AST 结构体，
+// 因为本模块输出的 JSON 文档带有版本号，并且其格式受兼容性规则的约束，
+// 格式的详细说明以及兼容性规则见 `docs/ast_json_schema.md`。
+//
+// 文档的结构：
+//
+// ```json
+// {
+//     "version": 1,
+//     "ast": {"Program": {...}}
+// }
+// ```
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ast::Node;
+
+// 当前 JSON 格式的版本号
+//
+// 注：
+// 只有在发生不兼容的修改时（比如删除或者重命名了字段、改变了某个字段的类型）
+// 才需要增加版本号，新增可选字段不需要增加版本号，详细见 `docs/ast_json_schema.md`。
+pub const AST_JSON_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq)]
+pub enum JsonError {
+    // 不是有效的 JSON 文档，或者文档结构跟 AST 格式不符
+    InvalidDocument(String),
+
+    // 文档的版本号比当前程序支持的版本号更高
+    UnsupportedVersion(u32),
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonError::InvalidDocument(message) => {
+                write!(f, "invalid AST JSON document: {}", message)
+            }
+            JsonError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported AST JSON version {}, the latest supported version is {}",
+                version, AST_JSON_VERSION
+            ),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u32,
+    ast: &'a Node,
+}
+
+#[derive(Deserialize)]
+struct DocumentHeader {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct OwnedDocument {
+    ast: Node,
+}
+
+// 将 AST 转换为带版本号的 JSON 文档
+pub fn to_json(node: &Node) -> String {
+    let document = Document {
+        version: AST_JSON_VERSION,
+        ast: node,
+    };
+
+    // AST 当中只有浮点数的无穷大和 NaN 无法表示为 JSON（会输出为 `null`），
+    // 除此之外序列化不会失败。
+    serde_json::to_string(&document).unwrap()
+}
+
+// 从带版本号的 JSON 文档读取 AST
+//
+// 接受所有不高于 AST_JSON_VERSION 的版本，文档当中未知的字段会被忽略。
+pub fn from_json(text: &str) -> Result<Node, JsonError> {
+    // 先读取版本号，以便对不支持的版本给出明确的错误信息，
+    // 而不是一个难以理解的结构不符的错误
+    let header: DocumentHeader =
+        serde_json::from_str(text).map_err(|e| JsonError::InvalidDocument(e.to_string()))?;
+
+    if header.version == 0 || header.version > AST_JSON_VERSION {
+        return Err(JsonError::UnsupportedVersion(header.version));
+    }
+
+    let document: OwnedDocument =
+        serde_json::from_str(text).map_err(|e| JsonError::InvalidDocument(e.to_string()))?;
+
+    Ok(document.ast)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Node, lexer, parser};
+
+    use super::{from_json, to_json, JsonError, AST_JSON_VERSION};
+
+    fn parse_from_string(text: &str) -> Node {
+        let token_details = lexer::tokenize(text).unwrap();
+        parser::parse(&token_details).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let sources = [
+            "1 + 2 * 3",
+            "let (a, b) = (1, 2.5)",
+            "function add(Int a, Int b = 1) type Int = a + b",
+            "if a > 0 then -a? else b^",
+            "let p = {x: 1, y: 2}",
+            "match v { case in [1, 2]: 'a' default: \"b\" }",
+            "each i in [1, 2] writeLine(i)",
+            "namespace foo::bar\nuse std::List",
+        ];
+
+        for source in sources {
+            let n1 = parse_from_string(source);
+            let text = to_json(&n1);
+            let n2 = from_json(&text).unwrap();
+            assert_eq!(n1, n2);
+        }
+    }
+
+    #[test]
+    fn test_document_format() {
+        // 这个测试用于防止意外地改变了 JSON 格式，
+        // 如果修改了 AST 结构体导致这个测试失败，请先阅读 `docs/ast_json_schema.md`
+        // 里的兼容性规则。
+        let n1 = parse_from_string("a + 1");
+        assert_eq!(
+            to_json(&n1),
+            concat!(
+                r#"{"version":1,"ast":{"Program":{"namespace":[],"#,
+                r#""body":[{"Expression":{"BinaryExpression":{"#,
+                r#""operator":"Plus","#,
+                r#""left":{"Identifier":{"dirs":[],"name":"a","generics":[],"#,
+                r#""range":{"file_id":0,"start":0,"end":1}}},"#,
+                r#""right":{"Literal":{"Integer":{"value":1,"#,
+                r#""range":{"file_id":0,"start":4,"end":5}}}},"#,
+                r#""range":{"file_id":0,"start":0,"end":5}}}}],"#,
+                r#""range":{"file_id":0,"start":0,"end":5}}}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_version() {
+        let n1 = parse_from_string("1");
+        let text = to_json(&n1);
+
+        // 忽略未知的字段
+        let t1 = text.replacen('{', r#"{"generator":"some tool","#, 1);
+        assert_eq!(from_json(&t1).unwrap(), n1);
+
+        // 更高的版本号
+        let t2 = text.replacen(
+            &format!(r#""version":{}"#, AST_JSON_VERSION),
+            r#""version":99"#,
+            1,
+        );
+        assert_eq!(from_json(&t2), Err(JsonError::UnsupportedVersion(99)));
+
+        // 缺少版本号
+        assert!(matches!(
+            from_json(r#"{"ast":{}}"#),
+            Err(JsonError::InvalidDocument(_))
+        ));
+
+        // 结构不符
+        assert!(matches!(
+            from_json(r#"{"version":1,"ast":{"Foo":1}}"#),
+            Err(JsonError::InvalidDocument(_))
+        ));
+    }
+}
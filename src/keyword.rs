@@ -0,0 +1,167 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 关键字表
+//
+// 以数据的形式列出每个语言版本（edition）的关键字，
+// 词法分析器根据这个表以及当前的语言版本决定一个单词是关键字还是标识符。
+//
+// 新增关键字时，应该先以 "较新的版本" 的保留字加入这个表，
+// 这样旧版本的代码如果使用了这个单词作为标识符，词法分析器只会给出警告而不是报错，
+// 或者把它作为上下文关键字（KeywordKind::Contextual）加入，这样它仍然可以用作标识符。
+//
+// 注：
+// 词法分析器实际使用 `lexer::lookup_keyword` 查找当前版本的保留字（出于速度的考虑），
+// 修改这个表时需要同步修改 `lookup_keyword`，单元测试会检查两者是否一致。
+
+use crate::token::Token;
+
+// 语言版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Edition {
+    #[default]
+    Edition2022,
+    Edition2024,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeywordKind {
+    // 保留字，在任何位置都不能用作标识符
+    Reserved,
+
+    // 上下文关键字，只在特定的语法位置具有关键字的含义，
+    // 词法分析器将其视为标识符，由语法分析器根据上下文识别。
+    Contextual,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyword {
+    pub name: &'static str,
+    pub kind: KeywordKind,
+    pub since: Edition,       // 从哪个版本开始成为关键字
+    pub token: Option<Token>, // 为 None 时表示关键字已保留，但相应的语法尚未实现
+}
+
+impl Keyword {
+    // 在指定的语言版本当中是否为保留字
+    pub fn is_reserved_in(&self, edition: Edition) -> bool {
+        self.kind == KeywordKind::Reserved && self.since <= edition
+    }
+}
+
+impl std::fmt::Display for Edition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Edition::Edition2022 => "2022",
+            Edition::Edition2024 => "2024",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+const fn reserved(name: &'static str, token: Token) -> Keyword {
+    Keyword {
+        name,
+        kind: KeywordKind::Reserved,
+        since: Edition::Edition2022,
+        token: Some(token),
+    }
+}
+
+#[allow(dead_code)] // 目前没有为将来的版本保留的关键字
+pub(crate) const fn reserved_for_future(name: &'static str, since: Edition) -> Keyword {
+    Keyword {
+        name,
+        kind: KeywordKind::Reserved,
+        since,
+        token: None,
+    }
+}
+
+pub static KEYWORDS: &[Keyword] = &[
+    reserved("true", Token::Boolean(true)),
+    reserved("false", Token::Boolean(false)),
+    reserved("do", Token::Do),
+    reserved("join", Token::Join),
+    reserved("let", Token::Let),
+    reserved("fn", Token::Fn),
+    reserved("sign", Token::Sign),
+    reserved("if", Token::If),
+    reserved("then", Token::Then),
+    reserved("else", Token::Else),
+    reserved("for", Token::For),
+    reserved("next", Token::Next),
+    reserved("each", Token::Each),
+    reserved("in", Token::In),
+    reserved("branch", Token::Branch),
+    reserved("match", Token::Match),
+    reserved("case", Token::Case),
+    reserved("default", Token::Default),
+    reserved("where", Token::Where),
+    reserved("only", Token::Only),
+    reserved("into", Token::Into),
+    reserved("regular", Token::Regular),
+    reserved("template", Token::Template),
+    reserved("function", Token::Function),
+    reserved("type", Token::Type),
+    reserved("which", Token::Which),
+    reserved("empty", Token::Empty),
+    reserved("pattern", Token::Pattern),
+    reserved("limit", Token::Limit),
+    reserved("use", Token::Use),
+    reserved("const", Token::Const),
+    reserved("enum", Token::Enum),
+    reserved("struct", Token::Struct),
+    reserved("union", Token::Union),
+    reserved("trait", Token::Trait),
+    reserved("impl", Token::Impl),
+    reserved("alias", Token::Alias),
+    reserved("as", Token::As),
+    reserved("namespace", Token::Namespace),
+    // 为将来的版本保留的关键字（目前没有）
+];
+
+pub fn find_keyword(name: &str) -> Option<&'static Keyword> {
+    KEYWORDS.iter().find(|keyword| keyword.name == name)
+}
+
+// 列出指定语言版本的所有保留字
+pub fn reserved_keywords(edition: Edition) -> impl Iterator<Item = &'static Keyword> {
+    KEYWORDS
+        .iter()
+        .filter(move |keyword| keyword.is_reserved_in(edition))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_keyword, reserved_for_future, reserved_keywords, Edition, KEYWORDS};
+
+    #[test]
+    fn test_keyword_table() {
+        // 关键字不能重复
+        for (index, keyword) in KEYWORDS.iter().enumerate() {
+            assert!(KEYWORDS[index + 1..].iter().all(|k| k.name != keyword.name));
+        }
+
+        let k1 = find_keyword("namespace").unwrap();
+        assert!(k1.is_reserved_in(Edition::Edition2022));
+        assert!(k1.is_reserved_in(Edition::Edition2024));
+
+        let k2 = reserved_for_future("module", Edition::Edition2024);
+        assert!(!k2.is_reserved_in(Edition::Edition2022));
+        assert!(k2.is_reserved_in(Edition::Edition2024));
+
+        assert!(find_keyword("foo").is_none());
+
+        assert!(reserved_keywords(Edition::Edition2022).all(|k| k.token.is_some()));
+        assert_eq!(
+            reserved_keywords(Edition::Edition2024).count(),
+            reserved_keywords(Edition::Edition2022).count()
+        );
+    }
+}
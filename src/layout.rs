@@ -0,0 +1,928 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 内存布局
+//
+// 计算结构体、元组以及联合体在各个后端目标平台上的大小、对齐以及成员的偏移量，
+// 供代码生成阶段读写成员以及分配内存。
+//
+// 布局规则：
+//
+// - 基本数据类型按照其大小对齐（见 `Target::primitive_layout`）；
+// - 其他类型（比如 `String`、`List`，以及其他模块里的类型）以及函数都是引用，
+//   大小为目标平台的指针大小；
+// - 结构体以及元组按照成员的声明顺序排列，每个成员按照其对齐要求放置，
+//   整体的对齐为成员的最大对齐，大小向上取整到对齐的倍数；
+//   标注了 `#[packed]` 属性的结构体不插入填充，对齐为 1；
+// - 联合体由标签（tag）以及各个成员共用的存储区（payload）组成，
+//   标签位于开头，存储区的大小为各个成员的最大大小；标签使用能够容纳所有成员的
+//   最小的整数（1、2 或者 4 个字节），只有一个成员的联合体不需要标签；
+// - 小生境（niche）优化：类型里不可能出现的值（比如引用的 0，`Boolean` 的 2 到 255）
+//   可以用来表示别的信息。当联合体只有一个成员有数据，其他成员都是空结构体，
+//   并且有数据的成员有足够的小生境时（比如 `Option` 之类的、包着一个引用的联合体），
+//   空成员使用小生境的值表示，而不需要标签（见 `TagEncoding`）；
+// - 结构体以及联合体直接包含（而不是引用）其成员，所以直接或者间接地包含自身的类型
+//   大小是无穷大，这是错误的。
+
+use std::fmt::Display;
+
+use crate::ast::{
+    Attribute, DataType, Expression, MemberStructDeclaration, Program, Statement,
+    TupleStructDeclaration, UnionDeclaration, UnionMember,
+};
+
+const PACKED: &str = "packed";
+
+// 后端的目标平台
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    XiaoXuanVm, // XiaoXuan Core VM，64 位
+    Wasm32,
+}
+
+impl Target {
+    pub fn pointer_size(&self) -> usize {
+        match self {
+            Target::XiaoXuanVm => 8,
+            Target::Wasm32 => 4,
+        }
+    }
+
+    // 基本数据类型的布局，其他类型返回 None
+    //
+    // `Int` 在所有平台上都是 64 位整数（WASM 的 `i64`）。
+    pub fn primitive_layout(&self, name: &str) -> Option<Layout> {
+        let size = match name {
+            "Int" | "Float" => 8,
+            "Complex" => 16,
+            "Char" => 4,
+            "Boolean" | "Byte" => 1,
+            _ => return None,
+        };
+        let niche = match name {
+            "Boolean" => Some(Niche::new(0, 1, 2, 254)),
+            "Char" => Some(Niche::new(0, 4, 0x11_0000, 0x1_0000_0000 - 0x11_0000)),
+            _ => None,
+        };
+        Some(Layout {
+            niche,
+            ..Layout::scalar(size, size.min(8))
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub size: usize,
+    pub align: usize,
+    pub fields: Vec<FieldLayout>, // 结构体以及元组的成员，其他类型为空列表
+    pub niche: Option<Niche>,     // 可用的小生境
+}
+
+impl Layout {
+    fn scalar(size: usize, align: usize) -> Self {
+        Layout {
+            size,
+            align,
+            fields: vec![],
+            niche: None,
+        }
+    }
+}
+
+// 小生境，即位于偏移量 `offset` 的、`size` 个字节的整数不可能出现的值，
+// 从 `start` 开始共 `count` 个
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Niche {
+    pub offset: usize,
+    pub size: usize,
+    pub start: u64,
+    pub count: u64,
+}
+
+impl Niche {
+    fn new(offset: usize, size: usize, start: u64, count: u64) -> Self {
+        Niche {
+            offset,
+            size,
+            start,
+            count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldLayout {
+    pub name: String, // 元组以及元组结构体的成员的名称为其序号，比如 "0"
+    pub offset: usize,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnionLayout {
+    pub size: usize,
+    pub align: usize,
+    pub tag: TagEncoding,
+    pub payload_offset: usize, // 各个成员的存储区的偏移量
+    pub variants: Vec<VariantLayout>,
+    pub niche: Option<Niche>,
+}
+
+// 联合体的成员的表示方式
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagEncoding {
+    // 只有一个成员（或者没有成员），不需要标签
+    None,
+
+    // 标签位于偏移量 0，成员的标签值为其序号
+    Direct {
+        size: usize,
+    },
+
+    // 没有标签，序号为 `dataful_variant` 的成员保存数据，
+    // 其他（空）成员使用该成员的小生境里的值表示
+    Niche {
+        offset: usize,
+        size: usize,
+        dataful_variant: usize,
+        values: Vec<(usize, u64)>, // 空成员的序号以及对应的值
+    },
+}
+
+impl Display for TagEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TagEncoding::None => write!(f, "no tag"),
+            TagEncoding::Direct { size } => write!(f, "{}-byte tag at offset 0", size),
+            TagEncoding::Niche {
+                offset,
+                size,
+                dataful_variant,
+                values,
+            } => {
+                let values = values
+                    .iter()
+                    .map(|(variant, value)| format!("#{} = {}", variant, value))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(
+                    f,
+                    "niche in variant #{}, {}-byte value at offset {}: {}",
+                    dataful_variant, size, offset, values
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantLayout {
+    pub name: String,
+    pub tag: usize,
+    pub layout: Layout, // 成员的偏移量相对于存储区的开始位置
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutError {
+    RecursiveType(String),
+    GenericArgumentMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    InvalidDataType(String),
+    InvalidAttribute {
+        name: String,
+        reason: String,
+    },
+}
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutError::RecursiveType(name) => {
+                write!(f, "type \"{}\" contains itself and has infinite size", name)
+            }
+            LayoutError::GenericArgumentMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "type \"{}\" expects {} type arguments, found {}",
+                name, expected, found
+            ),
+            LayoutError::InvalidDataType(text) => write!(f, "\"{}\" is not a data type", text),
+            LayoutError::InvalidAttribute { name, reason } => {
+                write!(f, "invalid attributes of type \"{}\": {}", name, reason)
+            }
+        }
+    }
+}
+
+enum TypeDeclaration<'a> {
+    Member(&'a MemberStructDeclaration),
+    Tuple(&'a TupleStructDeclaration),
+    Union(&'a UnionDeclaration),
+}
+
+impl TypeDeclaration<'_> {
+    fn name(&self) -> &str {
+        match self {
+            TypeDeclaration::Member(s) => &s.name,
+            TypeDeclaration::Tuple(s) => &s.name,
+            TypeDeclaration::Union(u) => &u.name,
+        }
+    }
+
+    fn generics(&self) -> &[DataType] {
+        match self {
+            TypeDeclaration::Member(s) => &s.generics,
+            TypeDeclaration::Tuple(s) => &s.generics,
+            TypeDeclaration::Union(u) => &u.generics,
+        }
+    }
+}
+
+// 泛型参数的名称以及对应的实际类型的布局
+type Substitutions = Vec<(String, Layout)>;
+
+// 计算程序里声明的类型的布局
+pub struct LayoutContext<'a> {
+    target: Target,
+    declarations: Vec<TypeDeclaration<'a>>,
+}
+
+impl<'a> LayoutContext<'a> {
+    pub fn new(target: Target, program: &'a Program) -> Self {
+        let declarations = program
+            .body
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::MemberStructDeclaration(s) => Some(TypeDeclaration::Member(s)),
+                Statement::TupleStructDeclaration(s) => Some(TypeDeclaration::Tuple(s)),
+                Statement::UnionDeclaration(u) => Some(TypeDeclaration::Union(u)),
+                _ => None,
+            })
+            .collect();
+
+        LayoutContext {
+            target,
+            declarations,
+        }
+    }
+
+    // 数据类型的布局，泛型类型需要给出所有类型参数，比如 `Pair<Int>`
+    pub fn layout_of(&self, data_type: &DataType) -> Result<Layout, LayoutError> {
+        self.data_type_layout(data_type, &vec![], &mut vec![])
+    }
+
+    // 联合体的布局，数据类型不是联合体时返回 None
+    pub fn union_layout_of(
+        &self,
+        data_type: &DataType,
+    ) -> Result<Option<UnionLayout>, LayoutError> {
+        let DataType::Identifier(identifier) = data_type else {
+            return Ok(None);
+        };
+
+        match self.find_declaration(&identifier.dirs, &identifier.name) {
+            Some(TypeDeclaration::Union(union)) => {
+                let mut visiting = vec![];
+                let substitutions = self.substitutions(
+                    &union.generics,
+                    &identifier.generics,
+                    &union.name,
+                    &vec![],
+                    &mut visiting,
+                )?;
+                visiting.push(union.name.clone());
+                self.union_layout(union, &substitutions, &mut visiting)
+                    .map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn find_declaration(&self, dirs: &[String], name: &str) -> Option<&TypeDeclaration<'a>> {
+        if !dirs.is_empty() {
+            return None;
+        }
+        self.declarations.iter().find(|d| d.name() == name)
+    }
+
+    fn data_type_layout(
+        &self,
+        data_type: &DataType,
+        substitutions: &Substitutions,
+        visiting: &mut Vec<String>,
+    ) -> Result<Layout, LayoutError> {
+        match data_type {
+            DataType::Identifier(identifier) => {
+                if identifier.dirs.is_empty() && identifier.generics.is_empty() {
+                    if let Some((_, layout)) = substitutions
+                        .iter()
+                        .find(|(name, _)| *name == identifier.name)
+                    {
+                        return Ok(layout.clone());
+                    }
+
+                    if let Some(layout) = self.target.primitive_layout(&identifier.name) {
+                        return Ok(layout);
+                    }
+                }
+
+                let Some(declaration) = self.find_declaration(&identifier.dirs, &identifier.name)
+                else {
+                    // 其他类型都是引用
+                    return Ok(self.pointer_layout());
+                };
+
+                let name = declaration.name().to_string();
+                if visiting.contains(&name) {
+                    return Err(LayoutError::RecursiveType(name));
+                }
+
+                // 类型参数在当前的上下文里计算
+                let arguments = self.substitutions(
+                    declaration.generics(),
+                    &identifier.generics,
+                    &name,
+                    substitutions,
+                    visiting,
+                )?;
+
+                visiting.push(name);
+                let layout = match declaration {
+                    TypeDeclaration::Member(s) => {
+                        self.member_struct_layout(s, &arguments, visiting)
+                    }
+                    TypeDeclaration::Tuple(s) => self.tuple_struct_layout(s, &arguments, visiting),
+                    TypeDeclaration::Union(u) => {
+                        self.union_layout(u, &arguments, visiting).map(|u| Layout {
+                            niche: u.niche,
+                            ..Layout::scalar(u.size, u.align)
+                        })
+                    }
+                };
+                visiting.pop();
+                layout
+            }
+            DataType::Tuple(t) => {
+                let fields = t
+                    .elements
+                    .iter()
+                    .enumerate()
+                    .map(|(index, e)| {
+                        let layout =
+                            self.data_type_layout(&element_data_type(e)?, substitutions, visiting)?;
+                        Ok((index.to_string(), layout))
+                    })
+                    .collect::<Result<Vec<_>, LayoutError>>()?;
+                Ok(sequential_layout(fields, false))
+            }
+            DataType::Sign(_) => Ok(self.pointer_layout()),
+        }
+    }
+
+    fn pointer_layout(&self) -> Layout {
+        // 引用不能为空，所以 0 是小生境
+        let size = self.target.pointer_size();
+        Layout {
+            niche: Some(Niche::new(0, size, 0, 1)),
+            ..Layout::scalar(size, size)
+        }
+    }
+
+    fn substitutions(
+        &self,
+        parameters: &[DataType],
+        arguments: &[DataType],
+        name: &str,
+        substitutions: &Substitutions,
+        visiting: &mut Vec<String>,
+    ) -> Result<Substitutions, LayoutError> {
+        if parameters.len() != arguments.len() {
+            return Err(LayoutError::GenericArgumentMismatch {
+                name: name.to_string(),
+                expected: parameters.len(),
+                found: arguments.len(),
+            });
+        }
+
+        parameters
+            .iter()
+            .zip(arguments)
+            .map(|(parameter, argument)| {
+                let layout = self.data_type_layout(argument, substitutions, visiting)?;
+                Ok((parameter.to_string(), layout))
+            })
+            .collect()
+    }
+
+    fn member_struct_layout(
+        &self,
+        declaration: &MemberStructDeclaration,
+        substitutions: &Substitutions,
+        visiting: &mut Vec<String>,
+    ) -> Result<Layout, LayoutError> {
+        let packed = is_packed(&declaration.name, &declaration.attributes)?;
+        let fields = declaration
+            .members
+            .iter()
+            .map(|m| {
+                let layout = self.data_type_layout(&m.data_type, substitutions, visiting)?;
+                Ok((m.name.clone(), layout))
+            })
+            .collect::<Result<Vec<_>, LayoutError>>()?;
+        Ok(sequential_layout(fields, packed))
+    }
+
+    fn tuple_struct_layout(
+        &self,
+        declaration: &TupleStructDeclaration,
+        substitutions: &Substitutions,
+        visiting: &mut Vec<String>,
+    ) -> Result<Layout, LayoutError> {
+        let packed = is_packed(&declaration.name, &declaration.attributes)?;
+        let fields = declaration
+            .members
+            .iter()
+            .enumerate()
+            .map(|(index, data_type)| {
+                let layout = self.data_type_layout(data_type, substitutions, visiting)?;
+                Ok((index.to_string(), layout))
+            })
+            .collect::<Result<Vec<_>, LayoutError>>()?;
+        Ok(sequential_layout(fields, packed))
+    }
+
+    fn union_layout(
+        &self,
+        declaration: &UnionDeclaration,
+        substitutions: &Substitutions,
+        visiting: &mut Vec<String>,
+    ) -> Result<UnionLayout, LayoutError> {
+        let variants = declaration
+            .members
+            .iter()
+            .enumerate()
+            .map(|(tag, member)| {
+                let (name, layout) = match member {
+                    UnionMember::Struct(s) => (
+                        s.name.clone(),
+                        self.member_struct_layout(s, substitutions, visiting)?,
+                    ),
+                    UnionMember::Tuple(s) => (
+                        s.name.clone(),
+                        self.tuple_struct_layout(s, substitutions, visiting)?,
+                    ),
+                    UnionMember::Empty(s) => (s.name.clone(), Layout::scalar(0, 1)),
+                };
+                Ok(VariantLayout { name, tag, layout })
+            })
+            .collect::<Result<Vec<_>, LayoutError>>()?;
+
+        Ok(union_layout(variants))
+    }
+}
+
+fn union_layout(variants: Vec<VariantLayout>) -> UnionLayout {
+    let payload_align = variants.iter().map(|v| v.layout.align).max().unwrap_or(1);
+    let payload_size = variants.iter().map(|v| v.layout.size).max().unwrap_or(0);
+
+    if variants.len() <= 1 {
+        let niche = variants.first().and_then(|v| v.layout.niche);
+        return UnionLayout {
+            size: align_to(payload_size, payload_align),
+            align: payload_align,
+            tag: TagEncoding::None,
+            payload_offset: 0,
+            variants,
+            niche,
+        };
+    }
+
+    // 小生境优化
+    let dataful: Vec<&VariantLayout> = variants.iter().filter(|v| v.layout.size > 0).collect();
+    let empty_count = (variants.len() - 1) as u64;
+
+    if let [dataful_variant] = dataful.as_slice() {
+        if let Some(niche) = dataful_variant
+            .layout
+            .niche
+            .filter(|niche| niche.count >= empty_count)
+        {
+            let values = variants
+                .iter()
+                .filter(|v| v.tag != dataful_variant.tag)
+                .zip(niche.start..)
+                .map(|(v, value)| (v.tag, value))
+                .collect();
+            let remaining = (niche.count > empty_count).then(|| Niche {
+                start: niche.start + empty_count,
+                count: niche.count - empty_count,
+                ..niche
+            });
+
+            return UnionLayout {
+                size: dataful_variant.layout.size,
+                align: dataful_variant.layout.align,
+                tag: TagEncoding::Niche {
+                    offset: niche.offset,
+                    size: niche.size,
+                    dataful_variant: dataful_variant.tag,
+                    values,
+                },
+                payload_offset: 0,
+                variants,
+                niche: remaining,
+            };
+        }
+    }
+
+    // 使用能够容纳所有成员的最小的标签，未使用的标签值也是小生境
+    let tag_size = match variants.len() {
+        0..=0x100 => 1,
+        0x101..=0x1_0000 => 2,
+        _ => 4,
+    };
+    let payload_offset = align_to(tag_size, payload_align);
+    let align = payload_align.max(tag_size);
+    let count = variants.len() as u64;
+
+    UnionLayout {
+        size: align_to(payload_offset + payload_size, align),
+        align,
+        tag: TagEncoding::Direct { size: tag_size },
+        payload_offset,
+        variants,
+        niche: Some(Niche::new(
+            0,
+            tag_size,
+            count,
+            (1u64 << (tag_size * 8)) - count,
+        )),
+    }
+}
+
+// 元组的成员是表达式，转换为数据类型
+fn element_data_type(element: &Expression) -> Result<DataType, LayoutError> {
+    match element {
+        Expression::Identifier(i) => Ok(DataType::Identifier(i.clone())),
+        Expression::Tuple(t) => Ok(DataType::Tuple(t.clone())),
+        Expression::Sign(s) => Ok(DataType::Sign(s.clone())),
+        _ => Err(LayoutError::InvalidDataType(element.to_string())),
+    }
+}
+
+fn is_packed(name: &str, attributes: &[Attribute]) -> Result<bool, LayoutError> {
+    match attributes.iter().find(|a| a.name == PACKED) {
+        Some(attribute) if !attribute.arguments.is_empty() => Err(LayoutError::InvalidAttribute {
+            name: name.to_string(),
+            reason: "attribute \"packed\" does not take arguments".to_string(),
+        }),
+        Some(_) => Ok(true),
+        None => Ok(false),
+    }
+}
+
+// 按照顺序排列成员
+fn sequential_layout(fields: Vec<(String, Layout)>, packed: bool) -> Layout {
+    let mut offset = 0;
+    let mut align = 1;
+    let mut field_layouts = vec![];
+    let mut niche: Option<Niche> = None;
+
+    for (name, layout) in fields {
+        let field_align = if packed { 1 } else { layout.align };
+        offset = align_to(offset, field_align);
+
+        // 使用可用的值最多的小生境
+        if let Some(field_niche) = layout.niche {
+            if niche.is_none_or(|n| field_niche.count > n.count) {
+                niche = Some(Niche {
+                    offset: offset + field_niche.offset,
+                    ..field_niche
+                });
+            }
+        }
+
+        field_layouts.push(FieldLayout {
+            name,
+            offset,
+            size: layout.size,
+        });
+        offset += layout.size;
+        align = align.max(field_align);
+    }
+
+    Layout {
+        size: align_to(offset, align),
+        align,
+        fields: field_layouts,
+        niche,
+    }
+}
+
+fn align_to(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{
+        Attribute, DataType, EmptyStructDeclaration, Expression, Identifier,
+        MemberStructDeclaration, Program, Range, Statement, StructMember, Tuple,
+        TupleStructDeclaration, UnionDeclaration, UnionMember,
+    };
+
+    use super::{FieldLayout, Layout, LayoutContext, LayoutError, TagEncoding, Target};
+
+    fn new_range() -> Range {
+        Range {
+            file_id: 0,
+            start: 0,
+            end: 0,
+        }
+    }
+
+    fn new_identifier(name: &str, generics: Vec<DataType>) -> Identifier {
+        Identifier {
+            dirs: vec![],
+            name: name.to_string(),
+            generics,
+            range: new_range(),
+        }
+    }
+
+    fn new_data_type(name: &str) -> DataType {
+        DataType::Identifier(new_identifier(name, vec![]))
+    }
+
+    fn new_member_struct(name: &str, members: &[(&str, DataType)], packed: bool) -> Statement {
+        Statement::MemberStructDeclaration(MemberStructDeclaration {
+            attributes: if packed {
+                vec![Attribute {
+                    name: "packed".to_string(),
+                    arguments: vec![],
+                }]
+            } else {
+                vec![]
+            },
+            name: name.to_string(),
+            members: members
+                .iter()
+                .map(|(name, data_type)| StructMember {
+                    data_type: data_type.clone(),
+                    name: name.to_string(),
+                    range: new_range(),
+                })
+                .collect(),
+            generics: vec![],
+            whiches: vec![],
+            range: new_range(),
+        })
+    }
+
+    fn new_tuple_struct(
+        name: &str,
+        members: Vec<DataType>,
+        generics: &[&str],
+    ) -> TupleStructDeclaration {
+        TupleStructDeclaration {
+            attributes: vec![],
+            name: name.to_string(),
+            members,
+            generics: generics.iter().map(|g| new_data_type(g)).collect(),
+            whiches: vec![],
+            range: new_range(),
+        }
+    }
+
+    fn new_program(body: Vec<Statement>) -> Program {
+        Program {
+            namespace: vec![],
+            body,
+            range: new_range(),
+        }
+    }
+
+    fn offsets(layout: &Layout) -> Vec<(&str, usize)> {
+        layout
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.offset))
+            .collect()
+    }
+
+    #[test]
+    fn test_struct_layout() {
+        let program = new_program(vec![
+            new_member_struct(
+                "User",
+                &[
+                    ("active", new_data_type("Boolean")),
+                    ("id", new_data_type("Int")),
+                    ("initial", new_data_type("Char")),
+                    ("name", new_data_type("String")),
+                ],
+                false,
+            ),
+            new_member_struct(
+                "Header",
+                &[
+                    ("flag", new_data_type("Boolean")),
+                    ("id", new_data_type("Int")),
+                ],
+                true,
+            ),
+            Statement::TupleStructDeclaration(new_tuple_struct(
+                "Pair",
+                vec![new_data_type("T"), new_data_type("T")],
+                &["T"],
+            )),
+        ]);
+
+        // XiaoXuan VM
+        let context = LayoutContext::new(Target::XiaoXuanVm, &program);
+        let user = context.layout_of(&new_data_type("User")).unwrap();
+        assert_eq!(
+            offsets(&user),
+            vec![("active", 0), ("id", 8), ("initial", 16), ("name", 24)]
+        );
+        assert_eq!((user.size, user.align), (32, 8));
+
+        // WASM32 的指针是 4 个字节
+        let context = LayoutContext::new(Target::Wasm32, &program);
+        let user = context.layout_of(&new_data_type("User")).unwrap();
+        assert_eq!(
+            offsets(&user),
+            vec![("active", 0), ("id", 8), ("initial", 16), ("name", 20)]
+        );
+        assert_eq!((user.size, user.align), (24, 8));
+
+        // `#[packed]`
+        let header = context.layout_of(&new_data_type("Header")).unwrap();
+        assert_eq!(offsets(&header), vec![("flag", 0), ("id", 1)]);
+        assert_eq!((header.size, header.align), (9, 1));
+
+        // 泛型
+        let pair = context
+            .layout_of(&DataType::Identifier(new_identifier(
+                "Pair",
+                vec![new_data_type("Char")],
+            )))
+            .unwrap();
+        assert_eq!(
+            pair.fields,
+            vec![
+                FieldLayout {
+                    name: "0".to_string(),
+                    offset: 0,
+                    size: 4
+                },
+                FieldLayout {
+                    name: "1".to_string(),
+                    offset: 4,
+                    size: 4
+                }
+            ]
+        );
+        assert_eq!(
+            context
+                .layout_of(&new_data_type("Pair"))
+                .unwrap_err()
+                .to_string(),
+            "type \"Pair\" expects 1 type arguments, found 0"
+        );
+
+        // 元组
+        let tuple = context
+            .layout_of(&DataType::Tuple(Tuple {
+                elements: vec![
+                    Expression::Identifier(new_identifier("Char", vec![])),
+                    Expression::Identifier(new_identifier("Header", vec![])),
+                    Expression::Identifier(new_identifier("Float", vec![])),
+                ],
+                range: new_range(),
+            }))
+            .unwrap();
+        assert_eq!(offsets(&tuple), vec![("0", 0), ("1", 4), ("2", 16)]);
+        assert_eq!(tuple.size, 24);
+    }
+
+    #[test]
+    fn test_union_layout() {
+        let program = new_program(vec![
+            Statement::UnionDeclaration(UnionDeclaration {
+                name: "Shape".to_string(),
+                members: vec![
+                    UnionMember::Tuple(new_tuple_struct(
+                        "Circle",
+                        vec![new_data_type("Float")],
+                        &[],
+                    )),
+                    UnionMember::Tuple(new_tuple_struct(
+                        "Rect",
+                        vec![new_data_type("Float"), new_data_type("Float")],
+                        &[],
+                    )),
+                    UnionMember::Empty(EmptyStructDeclaration {
+                        name: "Empty".to_string(),
+                        range: new_range(),
+                    }),
+                ],
+                generics: vec![],
+                whiches: vec![],
+                range: new_range(),
+            }),
+            new_member_struct("Node", &[("next", new_data_type("Node"))], false),
+        ]);
+
+        let context = LayoutContext::new(Target::XiaoXuanVm, &program);
+        let shape = context
+            .union_layout_of(&new_data_type("Shape"))
+            .unwrap()
+            .unwrap();
+        assert_eq!((shape.size, shape.align, shape.payload_offset), (24, 8, 8));
+        assert_eq!(shape.tag, TagEncoding::Direct { size: 1 });
+        assert_eq!(
+            shape
+                .variants
+                .iter()
+                .map(|v| (v.name.as_str(), v.tag, v.layout.size))
+                .collect::<Vec<_>>(),
+            vec![("Circle", 0, 8), ("Rect", 1, 16), ("Empty", 2, 0)]
+        );
+        assert_eq!(context.layout_of(&new_data_type("Shape")).unwrap().size, 24);
+        assert_eq!(context.union_layout_of(&new_data_type("Int")), Ok(None));
+
+        // 包含自身的类型
+        assert_eq!(
+            context.layout_of(&new_data_type("Node")),
+            Err(LayoutError::RecursiveType("Node".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_niche_layout() {
+        // union Option<T> {Some(T), None}
+        let program = new_program(vec![Statement::UnionDeclaration(UnionDeclaration {
+            name: "Option".to_string(),
+            members: vec![
+                UnionMember::Tuple(new_tuple_struct("Some", vec![new_data_type("T")], &[])),
+                UnionMember::Empty(EmptyStructDeclaration {
+                    name: "None".to_string(),
+                    range: new_range(),
+                }),
+            ],
+            generics: vec![new_data_type("T")],
+            whiches: vec![],
+            range: new_range(),
+        })]);
+        let context = LayoutContext::new(Target::XiaoXuanVm, &program);
+        let option_of =
+            |data_type: DataType| DataType::Identifier(new_identifier("Option", vec![data_type]));
+
+        // 引用不能为空
+        let option = context
+            .union_layout_of(&option_of(new_data_type("String")))
+            .unwrap()
+            .unwrap();
+        assert_eq!((option.size, option.align), (8, 8));
+        assert_eq!(
+            option.tag.to_string(),
+            "niche in variant #0, 8-byte value at offset 0: #1 = 0"
+        );
+
+        // 整数没有小生境，需要标签
+        let option = context
+            .union_layout_of(&option_of(new_data_type("Int")))
+            .unwrap()
+            .unwrap();
+        assert_eq!((option.size, option.payload_offset), (16, 8));
+        assert_eq!(option.tag.to_string(), "1-byte tag at offset 0");
+
+        // 嵌套的联合体使用剩余的小生境
+        let option = context
+            .union_layout_of(&option_of(option_of(new_data_type("Boolean"))))
+            .unwrap()
+            .unwrap();
+        assert_eq!(option.size, 1);
+        assert_eq!(
+            option.tag,
+            TagEncoding::Niche {
+                offset: 0,
+                size: 1,
+                dataful_variant: 0,
+                values: vec![(1, 3)]
+            }
+        );
+    }
+}
@@ -8,411 +8,820 @@
 use std::char;
 
 use crate::error::Error;
+use crate::error::LiteralKind;
+use crate::error::Warning;
+use crate::keyword::find_keyword;
+use crate::keyword::Edition;
+use crate::keyword::Keyword;
+use crate::keyword::KeywordKind;
+use crate::limit::check_limit;
+use crate::limit::Limits;
+use crate::limit::ResourceLimit;
 use crate::token::Location;
 use crate::token::Token;
 use crate::token::TokenDetail;
+use crate::token::TokenOrTrivia;
+use crate::token::Trivia;
+use crate::token::TriviaDetail;
+
+// 比特数的最大宽度（位）
+pub const MAX_BIT_WIDTH: usize = 65536;
+
+// 琐碎内容及其所在的位置（即在它之前的 Token 的数量）
+type TriviaList = Vec<(usize, TriviaDetail)>;
+
+// 词法分析的选项
+#[derive(Debug, Clone, Default)]
+pub struct LexerOptions {
+    pub file_id: usize, // 源文件 id，记录在每个 Token 的位置里
+    pub edition: Edition,
+    pub limits: Limits, // 只检查字符串字面量的长度以及模板字符串的嵌套层数
+}
 
 pub fn tokenize(text: &str) -> Result<Vec<TokenDetail>, Error> {
+    let mut warnings: Vec<Warning> = vec![];
+    lex(text, &LexerOptions::default(), None, &mut warnings)
+}
+
+// 按照指定的选项进行词法分析，同时返回警告
+//
+// 比如当标识符跟较新版本的保留字同名时，会产生一个警告。
+pub fn tokenize_with_options(
+    text: &str,
+    options: &LexerOptions,
+) -> Result<(Vec<TokenDetail>, Vec<Warning>), Error> {
+    let mut warnings: Vec<Warning> = vec![];
+    let token_details = lex(text, options, None, &mut warnings)?;
+    Ok((token_details, warnings))
+}
+
+// 词法分析，并保留空白和注释
+//
+// 空白和注释以琐碎内容（Trivia）的形式，按照其在源代码里的顺序穿插在 Token 之间，
+// 供格式化工具、文档提取工具以及保留格式的重构工具使用。
+pub fn tokenize_with_trivia(text: &str) -> Result<Vec<TokenOrTrivia>, Error> {
+    let mut trivias: TriviaList = vec![];
+    let mut warnings: Vec<Warning> = vec![];
+    let token_details = lex(
+        text,
+        &LexerOptions::default(),
+        Some(&mut trivias),
+        &mut warnings,
+    )?;
+
+    let mut items: Vec<TokenOrTrivia> = vec![];
+    let mut trivia_iter = trivias.into_iter().peekable();
+
+    for (index, token_detail) in token_details.into_iter().enumerate() {
+        while let Some((_, trivia_detail)) = trivia_iter.next_if(|(pos, _)| *pos == index) {
+            items.push(TokenOrTrivia::Trivia(trivia_detail));
+        }
+        items.push(TokenOrTrivia::Token(token_detail));
+    }
+
+    // 位于最后一个 Token 之后的琐碎内容
+    items.extend(trivia_iter.map(|(_, trivia_detail)| TokenOrTrivia::Trivia(trivia_detail)));
+
+    Ok(items)
+}
+
+// 对源代码的一次编辑，即把原来的源代码里 [start, end) 范围（字节偏移值）的文本替换为 text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+impl TextEdit {
+    // 把 old 修改为 new 的最小的一次编辑，即去掉两者相同的开头和结尾之后的部分
+    pub fn between(old: &str, new: &str) -> Self {
+        let prefix = old
+            .char_indices()
+            .zip(new.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(old.len().min(new.len()), |((index, _), _)| index);
+
+        // 相同的结尾不能跟相同的开头重叠
+        let suffix = old[prefix..]
+            .chars()
+            .rev()
+            .zip(new[prefix..].chars().rev())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .sum::<usize>();
+
+        Self {
+            start: prefix,
+            end: old.len() - suffix,
+            text: new[prefix..new.len() - suffix].to_string(),
+        }
+    }
+}
+
+// 增量词法分析
+//
+// `text` 是编辑之后的源代码，`previous` 是编辑之前的源代码的 Token 列表（由 `tokenize` 产生）。
+// 从编辑位置之前最近的安全边界开始重新分析，直到产生的 Token 跟原列表里（位置按照编辑引起的
+// 长度变化调整之后的）某个 Token 重新同步，然后跟原列表里未受影响的部分拼接起来，
+// 结果跟 `tokenize(text)` 相同。
+//
+// 安全边界是跟编辑位置相接或者相交的第一个 Token 的前一个 Token 的起点，因为 Token 之间
+// 没有其他状态（字符串、模板字符串以及注释都是一个整体），从任意 Token 的起点都可以开始分析；
+// 多退一个 Token 是因为有的 Token 需要往后查看多个字符，比如 `:name:`。
+pub fn relex(
+    text: &str,
+    previous: &[TokenDetail],
+    edit: &TextEdit,
+) -> Result<Vec<TokenDetail>, Error> {
+    let (token_details, _) = relex_with_options(text, previous, edit, &LexerOptions::default())?;
+    Ok(token_details)
+}
+
+// 按照指定的选项进行增量词法分析，同时返回警告
+//
+// `previous` 须由相同的选项产生，返回的警告只包括重新分析的部分产生的警告。
+pub fn relex_with_options(
+    text: &str,
+    previous: &[TokenDetail],
+    edit: &TextEdit,
+    options: &LexerOptions,
+) -> Result<(Vec<TokenDetail>, Vec<Warning>), Error> {
+    let first = previous
+        .iter()
+        .position(|t| t.location.end >= edit.start)
+        .unwrap_or(previous.len());
+    let boundary = first.saturating_sub(1);
+
+    // 编辑涉及第一个 Token 时，第一个 Token 之前可能是注释（比如行注释之后的换行符），
+    // 从第一个 Token 的起点开始分析会遗漏编辑对注释的影响，所以从源代码的开头开始分析
+    let origin = match previous.get(boundary) {
+        Some(TokenDetail { location, .. }) if first > 0 && location.start <= edit.start => Origin {
+            offset: location.start,
+            line: location.line,
+            column: location.column,
+        },
+        _ => Origin::default(),
+    };
+
+    // 编辑引起的长度变化，以及新的文本在编辑之后的源代码里的结束位置
+    let delta = edit.text.len() as isize - (edit.end - edit.start) as isize;
+    let new_end = edit.start + edit.text.len();
+
+    // 重新同步的位置，即原列表里跟新产生的某个 Token 相同的 Token 的序号
+    let mut sync: Option<usize> = None;
+    let mut candidate = first;
+
+    let mut warnings: Vec<Warning> = vec![];
+    let token_details = lex_from(
+        &text[origin.offset..],
+        origin,
+        options,
+        None,
+        &mut warnings,
+        |token_detail| {
+            let location = &token_detail.location;
+            if location.start < new_end {
+                return false;
+            }
+
+            while let Some(old) = previous.get(candidate) {
+                let start = old.location.start as isize + delta;
+                if start > location.start as isize {
+                    break;
+                }
+                if start == location.start as isize
+                    && old.location.end as isize + delta == location.end as isize
+                    && old.token == token_detail.token
+                {
+                    sync = Some(candidate);
+                    return true;
+                }
+                candidate += 1;
+            }
+            false
+        },
+    )?;
+
+    let mut result = previous[..boundary].to_vec();
+
+    match sync {
+        Some(index) => {
+            // 同步点之后的 Token 的位置：字节偏移值加上长度变化，行号加上行数的变化，
+            // 跟同步点位于同一行的 Token 的列号还需要加上列号的变化
+            let synced = &token_details.last().unwrap().location;
+            let old_synced = &previous[index].location;
+            let line_delta = synced.line as isize - old_synced.line as isize;
+            let column_delta = synced.column as isize - old_synced.column as isize;
+
+            result.extend(token_details);
+            result.extend(previous[index + 1..].iter().map(|old| {
+                let mut token_detail = old.clone();
+                let location = &mut token_detail.location;
+                if location.line == old_synced.line {
+                    location.column = (location.column as isize + column_delta) as usize;
+                }
+                location.start = (location.start as isize + delta) as usize;
+                location.end = (location.end as isize + delta) as usize;
+                location.line = (location.line as isize + line_delta) as usize;
+                token_detail
+            }));
+        }
+        None => {
+            // 没有重新同步，即一直分析到了源代码的末尾
+            result.extend(token_details);
+        }
+    }
+
+    Ok((result, warnings))
+}
+
+// 被分析的文本的开头在整个源代码里的位置（字节偏移值、行号以及列号）
+#[derive(Debug, Clone, Copy)]
+struct Origin {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Default for Origin {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+// 当 trivias 为 None 时不保留空白和注释
+fn lex(
+    text: &str,
+    options: &LexerOptions,
+    trivias: Option<&mut TriviaList>,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<TokenDetail>, Error> {
+    lex_from(text, Origin::default(), options, trivias, warnings, |_| {
+        false
+    })
+}
+
+// 从 origin 所指的位置开始分析 text，产生的 Token 使 stop 返回 true 时停止
+fn lex_from(
+    text: &str,
+    origin: Origin,
+    options: &LexerOptions,
+    mut trivias: Option<&mut TriviaList>,
+    warnings: &mut Vec<Warning>,
+    mut stop: impl FnMut(&TokenDetail) -> bool,
+) -> Result<Vec<TokenDetail>, Error> {
     let vec_char: Vec<char> = text.chars().collect();
+    let positions = CharPositions::new(options.file_id, &vec_char, origin);
 
     let mut chars = &vec_char[..];
     let mut token_details: Vec<TokenDetail> = vec![];
 
-    loop {
-        match chars.split_first() {
-            Some((first, rest)) => {
-                chars = match *first {
-                    ' ' | '\t' => {
-                        // whitespace
-                        rest
-                    }
+    while let Some((first, rest)) = chars.split_first() {
+        // 当前字符的序号，以及本轮产生 Token 之前 Token 和警告的数量，
+        // 每一轮最多产生一个 Token，产生的 Token 以及警告的位置在本轮的最后设置
+        let start = vec_char.len() - chars.len();
+        let token_count = token_details.len();
+        let warning_count = warnings.len();
+
+        // 从当前字符到 rest 为止（不包括）的位置
+        let location_to = |rest: &[char]| positions.location(start, vec_char.len() - rest.len());
+
+        // 为子过程产生的错误设置位置：未结束的字面量从当前字符一直到源代码的末尾，
+        // 其他错误为当前字符
+        let locate = |error: Error| {
+            let end = match error {
+                Error::UnterminatedLiteral { .. } => vec_char.len(),
+                _ => start + 1,
+            };
+            locate_error(error, positions.location(start, end))
+        };
 
-                    '\r' => {
-                        // new line
-                        if is_char('\n', rest) {
-                            add_token_detail(&mut token_details, new_token_detail(Token::NewLine));
-                            move_forword(rest, 1)
-                        } else {
-                            add_token_detail(&mut token_details, new_token_detail(Token::NewLine));
-                            rest
-                        }
-                    }
+        chars = match *first {
+            ' ' | '\t' => {
+                // whitespace
+                if let Some(trivias) = trivias.as_deref_mut() {
+                    add_whitespace(trivias, token_details.len(), *first, location_to(rest));
+                }
+                rest
+            }
 
-                    '\n' | ';' => {
-                        // new line
-                        add_token_detail(&mut token_details, new_token_detail(Token::NewLine));
-                        rest
-                    }
+            '\r' => {
+                // new line
+                if is_char('\n', rest) {
+                    add_token_detail(&mut token_details, new_token_detail(Token::NewLine));
+                    move_forword(rest, 1)
+                } else {
+                    add_token_detail(&mut token_details, new_token_detail(Token::NewLine));
+                    rest
+                }
+            }
 
-                    '/' => {
-                        if is_char('/', rest) {
-                            // line comment
-                            let post_rest = skip_line_comment(rest);
-                            post_rest
-                        } else if is_char('*', rest) {
-                            /* comment */
-                            let post_rest = skip_comment(rest)?;
-                            post_rest
-                        } else {
-                            // `/`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Slash));
-                            rest
-                        }
-                    }
+            '\n' | ';' => {
+                // new line
+                add_token_detail(&mut token_details, new_token_detail(Token::NewLine));
+                rest
+            }
 
-                    '{' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::LeftBrace));
-                        rest
-                    }
-                    '}' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::RightBrace));
-                        rest
-                    }
-                    '=' => {
-                        if is_char('=', rest) {
-                            // `==`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Equal));
-                            move_forword(rest, 1)
-                        } else {
-                            // `=`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Assign));
-                            rest
-                        }
-                    }
-                    '>' => {
-                        if is_char('=', rest) {
-                            // `>=`
-                            add_token_detail(
-                                &mut token_details,
-                                new_token_detail(Token::GreaterThanOrEqual),
-                            );
-                            move_forword(rest, 1)
-                        } else {
-                            // `>`
-                            add_token_detail(
-                                &mut token_details,
-                                new_token_detail(Token::GreaterThan),
-                            );
-                            rest
-                        }
-                    }
-                    '|' => {
-                        if is_char('|', rest) {
-                            // `||`
-                            add_token_detail(&mut token_details, new_token_detail(Token::LogicOr));
-                            move_forword(rest, 1)
-                        } else {
-                            // `|`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Pipe));
-                            rest
-                        }
-                    }
-                    '&' => {
-                        if is_char('&', rest) {
-                            // `&&`
-                            add_token_detail(&mut token_details, new_token_detail(Token::LogicAnd));
-                            move_forword(rest, 1)
-                        } else {
-                            // `&`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Combine));
-                            rest
-                        }
-                    }
-                    '!' => {
-                        if is_char('=', rest) {
-                            // `!=`
-                            add_token_detail(&mut token_details, new_token_detail(Token::NotEqual));
-                            move_forword(rest, 1)
-                        } else {
-                            // `!`
-                            add_token_detail(
-                                &mut token_details,
-                                new_token_detail(Token::Exclamation),
-                            );
-                            rest
-                        }
-                    }
-                    '<' => {
-                        if is_char('=', rest) {
-                            // `<=`
-                            add_token_detail(
-                                &mut token_details,
-                                new_token_detail(Token::LessThanOrEqual),
-                            );
-                            move_forword(rest, 1)
-                        } else {
-                            // `<`
-                            add_token_detail(&mut token_details, new_token_detail(Token::LessThan));
-                            rest
-                        }
+            '/' => {
+                if is_char('/', rest) {
+                    // line comment
+                    let post_rest = skip_line_comment(rest);
+                    if let Some(trivias) = trivias.as_deref_mut() {
+                        let text = get_consumed_text(chars, post_rest);
+                        add_trivia(
+                            trivias,
+                            token_details.len(),
+                            Trivia::LineComment(text),
+                            location_to(post_rest),
+                        );
                     }
-                    '+' => {
-                        if is_char('+', rest) {
-                            // `++`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Concat));
-                            move_forword(rest, 1)
-                        } else {
-                            // `+`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Plus));
-                            rest
-                        }
+                    post_rest
+                } else if is_char('*', rest) {
+                    /* comment */
+                    let post_rest = skip_comment(rest).map_err(locate)?;
+                    if let Some(trivias) = trivias.as_deref_mut() {
+                        let text = get_consumed_text(chars, post_rest);
+                        add_trivia(
+                            trivias,
+                            token_details.len(),
+                            Trivia::BlockComment(text),
+                            location_to(post_rest),
+                        );
                     }
-                    '-' => {
-                        if is_char('>', rest) {
-                            add_token_detail(
-                                &mut token_details,
-                                new_token_detail(Token::OptionalAnd),
-                            );
-                            move_forword(rest, 1)
-                        } else {
-                            add_token_detail(&mut token_details, new_token_detail(Token::Minus));
-                            rest
-                        }
+                    post_rest
+                } else {
+                    // `/`
+                    add_token_detail(&mut token_details, new_token_detail(Token::Slash));
+                    rest
+                }
+            }
+
+            '{' => {
+                add_token_detail(&mut token_details, new_token_detail(Token::LeftBrace));
+                rest
+            }
+            '}' => {
+                add_token_detail(&mut token_details, new_token_detail(Token::RightBrace));
+                rest
+            }
+            '=' => {
+                if is_char('=', rest) {
+                    // `==`
+                    add_token_detail(&mut token_details, new_token_detail(Token::Equal));
+                    move_forword(rest, 1)
+                } else {
+                    // `=`
+                    add_token_detail(&mut token_details, new_token_detail(Token::Assign));
+                    rest
+                }
+            }
+            '>' => {
+                if is_char('=', rest) {
+                    // `>=`
+                    add_token_detail(
+                        &mut token_details,
+                        new_token_detail(Token::GreaterThanOrEqual),
+                    );
+                    move_forword(rest, 1)
+                } else {
+                    // `>`
+                    add_token_detail(&mut token_details, new_token_detail(Token::GreaterThan));
+                    rest
+                }
+            }
+            '|' => {
+                if is_char('|', rest) {
+                    // `||`
+                    add_token_detail(&mut token_details, new_token_detail(Token::LogicOr));
+                    move_forword(rest, 1)
+                } else {
+                    // `|`
+                    add_token_detail(&mut token_details, new_token_detail(Token::Pipe));
+                    rest
+                }
+            }
+            '&' => {
+                if is_char('&', rest) {
+                    // `&&`
+                    add_token_detail(&mut token_details, new_token_detail(Token::LogicAnd));
+                    move_forword(rest, 1)
+                } else {
+                    // `&`
+                    add_token_detail(&mut token_details, new_token_detail(Token::Combine));
+                    rest
+                }
+            }
+            '!' => {
+                if is_char('=', rest) {
+                    // `!=`
+                    add_token_detail(&mut token_details, new_token_detail(Token::NotEqual));
+                    move_forword(rest, 1)
+                } else {
+                    // `!`
+                    add_token_detail(&mut token_details, new_token_detail(Token::Exclamation));
+                    rest
+                }
+            }
+            '<' => {
+                if is_char('=', rest) {
+                    // `<=`
+                    add_token_detail(&mut token_details, new_token_detail(Token::LessThanOrEqual));
+                    move_forword(rest, 1)
+                } else {
+                    // `<`
+                    add_token_detail(&mut token_details, new_token_detail(Token::LessThan));
+                    rest
+                }
+            }
+            '+' => {
+                if is_char('+', rest) {
+                    // `++`
+                    add_token_detail(&mut token_details, new_token_detail(Token::Concat));
+                    move_forword(rest, 1)
+                } else {
+                    // `+`
+                    add_token_detail(&mut token_details, new_token_detail(Token::Plus));
+                    rest
+                }
+            }
+            '-' => {
+                if is_char('>', rest) {
+                    add_token_detail(&mut token_details, new_token_detail(Token::OptionalAnd));
+                    move_forword(rest, 1)
+                } else {
+                    add_token_detail(&mut token_details, new_token_detail(Token::Minus));
+                    rest
+                }
+            }
+            '*' => {
+                add_token_detail(&mut token_details, new_token_detail(Token::Asterisk));
+                rest
+            }
+            '?' => {
+                if is_char('?', rest) {
+                    // `??`
+                    add_token_detail(&mut token_details, new_token_detail(Token::OptionalOr));
+                    move_forword(rest, 1)
+                } else {
+                    // `?`
+                    add_token_detail(&mut token_details, new_token_detail(Token::Unwrap));
+                    rest
+                }
+            }
+            '^' => {
+                add_token_detail(&mut token_details, new_token_detail(Token::Cast));
+                rest
+            }
+            '@' => {
+                add_token_detail(&mut token_details, new_token_detail(Token::At));
+                rest
+            }
+            '.' => {
+                if is_chars(['.', '.'], rest) {
+                    // `...`
+                    add_token_detail(&mut token_details, new_token_detail(Token::Ellipsis));
+                    move_forword(rest, 2)
+                } else if is_chars(['.', '='], rest) {
+                    // `..=`
+                    add_token_detail(
+                        &mut token_details,
+                        new_token_detail(Token::IntervalInclusive),
+                    );
+                    move_forword(rest, 2)
+                } else if is_char('.', rest) {
+                    // `..`
+                    add_token_detail(&mut token_details, new_token_detail(Token::Interval));
+                    move_forword(rest, 1)
+                } else {
+                    // `.`
+                    add_token_detail(&mut token_details, new_token_detail(Token::Dot));
+                    rest
+                }
+            }
+            '[' => {
+                add_token_detail(&mut token_details, new_token_detail(Token::LeftBracket));
+                rest
+            }
+            ']' => {
+                add_token_detail(&mut token_details, new_token_detail(Token::RightBracket));
+                rest
+            }
+            '(' => {
+                add_token_detail(&mut token_details, new_token_detail(Token::LeftParen));
+                rest
+            }
+            ')' => {
+                add_token_detail(&mut token_details, new_token_detail(Token::RightParen));
+                rest
+            }
+
+            ',' => {
+                add_token_detail(&mut token_details, new_token_detail(Token::Comma));
+                rest
+            }
+
+            '\'' => {
+                if is_chars(['\'', '\''], rest) {
+                    // `'''...'''` 文档注释
+                    let (_, post_rest) = lex_document_comment(rest).map_err(locate)?;
+                    if let Some(trivias) = trivias.as_deref_mut() {
+                        let text = get_consumed_text(chars, post_rest);
+                        add_trivia(
+                            trivias,
+                            token_details.len(),
+                            Trivia::DocumentComment(text),
+                            location_to(post_rest),
+                        );
                     }
-                    '*' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::Asterisk));
+                    post_rest
+                } else {
+                    // `'char'`
+                    let (token_detail, post_rest) = lex_char(rest).map_err(locate)?;
+                    add_token_detail(&mut token_details, token_detail);
+                    post_rest
+                }
+            }
+
+            '"' => {
+                if is_chars(['"', '"'], rest) {
+                    // `"""..."""`
+                    let (token_detail, post_rest) = lex_raw_string(rest).map_err(locate)?;
+                    check_string_limits(&token_detail.token, &options.limits)
+                        .map_err(|error| locate_error(error, location_to(post_rest)))?;
+                    add_token_detail(&mut token_details, token_detail);
+                    post_rest
+                } else {
+                    // `"string"`
+                    let (token_detail, post_rest) = lex_string(rest).map_err(locate)?;
+                    check_string_limits(&token_detail.token, &options.limits)
+                        .map_err(|error| locate_error(error, location_to(post_rest)))?;
+                    add_token_detail(&mut token_details, token_detail);
+                    post_rest
+                }
+            }
+
+            '`' => {
+                // `template string`
+                let (token_detail, post_rest) = lex_template_string(rest).map_err(locate)?;
+                check_string_limits(&token_detail.token, &options.limits)
+                    .map_err(|error| locate_error(error, location_to(post_rest)))?;
+                add_token_detail(&mut token_details, token_detail);
+                post_rest
+            }
+
+            '0' => {
+                if is_char('x', rest) {
+                    // `0x...`， 十六进制整数
+                    let (token_detail, post_rest) = lex_16_radix_integer(rest).map_err(locate)?;
+                    add_token_detail(&mut token_details, token_detail);
+                    post_rest
+                } else if is_char('b', rest) {
+                    // `0b...`， 二进制整数
+                    let (token_detail, post_rest) = lex_2_radix_integer(rest).map_err(locate)?;
+                    add_token_detail(&mut token_details, token_detail);
+                    post_rest
+                } else if is_char('.', rest) {
+                    if is_chars(['.', '.'], rest) {
+                        // 遇到范围符号，此时的 `0` 作为普通整数
+                        add_token_detail(&mut token_details, new_token_detail(Token::Integer(0)));
                         rest
+                    } else {
+                        // `0.xx`， 整数部分为 0 的浮点数
+                        let (token_detail, post_rest) =
+                            lex_zero_point_float(rest).map_err(locate)?;
+                        add_token_detail(&mut token_details, token_detail);
+                        post_rest
                     }
-                    '?' => {
-                        if is_char('?', rest) {
-                            // `??`
-                            add_token_detail(
-                                &mut token_details,
-                                new_token_detail(Token::OptionalOr),
-                            );
-                            move_forword(rest, 1)
-                        } else {
-                            // `?`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Unwrap));
-                            rest
+                } else if is_char('\'', rest) {
+                    // `0'...`，宽度为 0 的比特数（报告宽度超出范围的错误）
+                    let (token_detail, post_rest) =
+                        continue_lex_bit_number(vec!['0'], &rest[1..]).map_err(locate)?;
+                    add_token_detail(&mut token_details, token_detail);
+                    post_rest
+                } else if is_char('i', rest)
+                    && !rest
+                        .get(1)
+                        .is_some_and(|c| is_valid_letter_of_identifier_or_keyword(*c))
+                {
+                    // `0i`，值为 0 的虚数
+                    let (token_detail, post_rest) =
+                        continue_lex_imaginary_number(vec!['0'], &rest[1..]).map_err(locate)?;
+                    add_token_detail(&mut token_details, token_detail);
+                    post_rest
+                } else {
+                    match rest.first() {
+                        Some(second_char)
+                            if is_valid_letter_of_identifier_or_keyword(*second_char) =>
+                        {
+                            // 数字 0 开头的符号（不是合法的标识符，所以抛出错误）
+                            return Err(locate(Error::LexerError(
+                                "invalid identifier".to_string(),
+                            )));
                         }
-                    }
-                    '^' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::Cast));
-                        rest
-                    }
-                    '@' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::At));
-                        rest
-                    }
-                    '.' => {
-                        if is_chars(['.', '.'], rest) {
-                            // `...`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Ellipsis));
-                            move_forword(rest, 2)
-                        } else if is_chars(['.', '='], rest) {
-                            // `..=`
+                        _ => {
+                            // 普通整数 0
                             add_token_detail(
                                 &mut token_details,
-                                new_token_detail(Token::IntervalInclusive),
+                                new_token_detail(Token::Integer(0)),
                             );
-                            move_forword(rest, 2)
-                        } else if is_char('.', rest) {
-                            // `..`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Interval));
-                            move_forword(rest, 1)
-                        } else {
-                            // `.`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Dot));
                             rest
                         }
                     }
-                    '[' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::LeftBracket));
-                        rest
-                    }
-                    ']' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::RightBracket));
-                        rest
-                    }
-                    '(' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::LeftParen));
-                        rest
-                    }
-                    ')' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::RightParen));
-                        rest
-                    }
-
-                    ',' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::Comma));
-                        rest
-                    }
-
-                    '\'' => {
-                        if is_chars(['\'', '\''], rest) {
-                            // `'''...'''` 文档注释
-                            let (_, post_rest) = lex_document_comment(rest)?;
-                            post_rest
-                        } else {
-                            // `'char'`
-                            let (token_detail, post_rest) = lex_char(rest)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        }
-                    }
+                }
+            }
 
-                    '"' => {
-                        if is_chars(['"', '"'], rest) {
-                            // `"""..."""`
-                            let (token_detail, post_rest) = lex_raw_string(rest)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        } else {
-                            // `"string"`
-                            let (token_detail, post_rest) = lex_string(rest)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        }
+            '#' => {
+                match rest.first() {
+                    Some(second_char)
+                        if is_valid_first_letter_of_identifier_or_keyword(*second_char) =>
+                    {
+                        // `#hash_string`
+                        let (token_detail, post_rest) = lex_hash_string(rest).map_err(locate)?;
+                        add_token_detail(&mut token_details, token_detail);
+                        post_rest
                     }
-
-                    '`' => {
-                        // `template string`
-                        let (token_detail, post_rest) = lex_template_string(rest)?;
+                    Some(second_char) if *second_char == '[' => {
+                        // `#[...]`
+                        let (token_detail, post_rest) = lex_attribute(rest).map_err(locate)?;
                         add_token_detail(&mut token_details, token_detail);
                         post_rest
                     }
+                    _ => return Err(locate(Error::LexerError("invalid char '#'".to_string()))),
+                }
+            }
 
-                    '0' => {
-                        if is_char('x', rest) {
-                            // `0x...`， 十六进制整数
-                            let (token_detail, post_rest) = lex_16_radix_integer(rest)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        } else if is_char('b', rest) {
-                            // `0b...`， 二进制整数
-                            let (token_detail, post_rest) = lex_2_radix_integer(rest)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        } else if is_char('.', rest) {
-                            if is_char('.', rest) {
-                                // 遇到范围符号，此时的 `0` 作为普通整数
-                                add_token_detail(
-                                    &mut token_details,
-                                    new_token_detail(Token::Integer(0)),
-                                );
-                                rest
-                            } else {
-                                // `0.xx`， 整数部分为 0 的浮点数
-                                let (token_detail, post_rest) = lex_zero_point_float(rest)?;
-                                add_token_detail(&mut token_details, token_detail);
-                                post_rest
-                            }
-                        } else {
-                            match rest.first() {
-                                Some(second_char)
-                                    if is_valid_letter_of_identifier_or_keyword(*second_char) =>
-                                {
-                                    // 数字 0 开头的符号（不是合法的标识符，所以抛出错误）
-                                    return Err(Error::LexerError(
-                                        "invalid identifier".to_string(),
-                                    ));
-                                }
-                                _ => {
-                                    // 普通整数 0
-                                    add_token_detail(
-                                        &mut token_details,
-                                        new_token_detail(Token::Integer(0)),
-                                    );
-                                    rest
+            ':' => {
+                match rest.first() {
+                    Some(second_char) => {
+                        if *second_char == ':' {
+                            // `::`
+                            add_token_detail(
+                                &mut token_details,
+                                new_token_detail(Token::Separator),
+                            );
+                            move_forword(rest, 1)
+                        } else if is_valid_first_letter_of_identifier_or_keyword(*second_char) {
+                            match lex_named_operator(rest) {
+                                Ok((token_detail, post_rest)) => {
+                                    // `:name_operator:`
+                                    add_token_detail(&mut token_details, token_detail);
+                                    post_rest
                                 }
-                            }
-                        }
-                    }
-
-                    '#' => {
-                        match rest.first() {
-                            Some(second_char)
-                                if is_valid_first_letter_of_identifier_or_keyword(*second_char) =>
-                            {
-                                // `#hash_string`
-                                let (token_detail, post_rest) = lex_hash_string(rest)?;
-                                add_token_detail(&mut token_details, token_detail);
-                                post_rest
-                            }
-                            Some(second_char) if *second_char == '[' => {
-                                // `#[...]`
-                                let (token_detail, post_rest) = lex_attribute(rest)?;
-                                add_token_detail(&mut token_details, token_detail);
-                                post_rest
-                            }
-                            _ => return Err(Error::LexerError("invalid char '#'".to_string())),
-                        }
-                    }
-
-                    ':' => {
-                        match rest.first() {
-                            Some(second_char) => {
-                                if *second_char == ':' {
-                                    // `::`
-                                    add_token_detail(
-                                        &mut token_details,
-                                        new_token_detail(Token::Separator),
-                                    );
-                                    move_forword(rest, 1)
-                                } else if is_valid_first_letter_of_identifier_or_keyword(
-                                    *second_char,
-                                ) {
-                                    match lex_named_operator(rest) {
-                                        Ok((token_detail, post_rest)) => {
-                                            // `:name_operator:`
-                                            add_token_detail(&mut token_details, token_detail);
-                                            post_rest
-                                        }
-                                        Err(_) => {
-                                            // `:`
-                                            add_token_detail(
-                                                &mut token_details,
-                                                new_token_detail(Token::Colon),
-                                            ); // ":"
-                                            rest
-                                        }
-                                    }
-                                } else {
+                                Err(_) => {
                                     // `:`
                                     add_token_detail(
                                         &mut token_details,
                                         new_token_detail(Token::Colon),
-                                    );
+                                    ); // ":"
                                     rest
                                 }
                             }
-                            None => {
-                                // `:`
-                                add_token_detail(
-                                    &mut token_details,
-                                    new_token_detail(Token::Colon),
-                                );
-                                rest
-                            }
+                        } else {
+                            // `:`
+                            add_token_detail(&mut token_details, new_token_detail(Token::Colon));
+                            rest
                         }
                     }
+                    None => {
+                        // `:`
+                        add_token_detail(&mut token_details, new_token_detail(Token::Colon));
+                        rest
+                    }
+                }
+            }
 
-                    _ => {
-                        if is_none_zero_number(*first) {
-                            // 整数、浮点数或者比特数
-                            let (token_detail, post_rest) = lex_number(chars)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        } else if is_valid_first_letter_of_identifier_or_keyword(*first) {
-                            // 标识符或者关键字
-                            let (token_detail, post_rest) = lex_identifier_or_keyword(chars)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        } else {
-                            // 未预料的符号
-                            return Err(Error::LexerError(format!("invalid char '{}'", first)));
-                        }
+            _ => {
+                if is_none_zero_number(*first) {
+                    // 整数、浮点数或者比特数
+                    let (token_detail, post_rest) = lex_number(chars).map_err(locate)?;
+                    add_token_detail(&mut token_details, token_detail);
+                    post_rest
+                } else if is_valid_first_letter_of_identifier_or_keyword(*first) {
+                    // 标识符或者关键字
+                    let (token_detail, post_rest) =
+                        lex_identifier_or_keyword(chars).map_err(locate)?;
+                    if let Token::Identifier(name) = &token_detail.token {
+                        check_reserved_identifier(name, options.edition, warnings)
+                            .map_err(locate)?;
                     }
-                };
+                    add_token_detail(&mut token_details, token_detail);
+                    post_rest
+                } else {
+                    // 未预料的符号
+                    return Err(locate(Error::LexerError(format!(
+                        "invalid char '{}'",
+                        first
+                    ))));
+                }
             }
-            None => break,
         };
+
+        let location = location_to(chars);
+        for token_detail in &mut token_details[token_count..] {
+            token_detail.location = location.clone();
+        }
+        for warning in &mut warnings[warning_count..] {
+            warning.location = location.clone();
+        }
+
+        if token_details[token_count..].iter().any(&mut stop) {
+            break;
+        }
     }
 
     Ok(token_details)
 }
 
+// 每个字符在源代码里的位置，用于计算 Token 以及琐碎内容的位置
+struct CharPositions {
+    file_id: usize,
+    offsets: Vec<usize>,               // 每个字符以及源代码末尾的字节偏移值
+    line_columns: Vec<(usize, usize)>, // 每个字符以及源代码末尾的行号和列号
+}
+
+impl CharPositions {
+    fn new(file_id: usize, chars: &[char], origin: Origin) -> Self {
+        let mut offsets = Vec::with_capacity(chars.len() + 1);
+        let mut line_columns = Vec::with_capacity(chars.len() + 1);
+
+        let Origin {
+            mut offset,
+            mut line,
+            mut column,
+        } = origin;
+
+        for (index, c) in chars.iter().enumerate() {
+            offsets.push(offset);
+            line_columns.push((line, column));
+
+            offset += c.len_utf8();
+
+            // 换行符可以是 `\r\n`、`\n` 或者 `\r`，
+            // 注意分号虽然也是 Token::NewLine，但并不换行
+            let is_line_break = match c {
+                '\n' => true,
+                '\r' => chars.get(index + 1) != Some(&'\n'),
+                _ => false,
+            };
+
+            if is_line_break {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+
+        offsets.push(offset);
+        line_columns.push((line, column));
+
+        Self {
+            file_id,
+            offsets,
+            line_columns,
+        }
+    }
+
+    // 从第 start 个字符开始到第 end 个字符为止（不包括）的位置
+    fn location(&self, start: usize, end: usize) -> Location {
+        let (line, column) = self.line_columns[start];
+        Location {
+            file_id: self.file_id,
+            start: self.offsets[start],
+            end: self.offsets[end],
+            line,
+            column,
+        }
+    }
+}
+
+// 替换错误的位置，没有位置的其他词法错误转换为带有位置的 InvalidToken
+fn locate_error(error: Error, location: Location) -> Error {
+    match error {
+        Error::LexerError(message) => Error::InvalidToken { message, location },
+        Error::InvalidToken { message, .. } => Error::InvalidToken { message, location },
+        Error::UnterminatedLiteral { kind, .. } => Error::UnterminatedLiteral { kind, location },
+        Error::InvalidNumber {
+            message, source, ..
+        } => Error::InvalidNumber {
+            message,
+            location,
+            source,
+        },
+        Error::LimitExceeded { limit, max, .. } => Error::LimitExceeded {
+            limit,
+            max,
+            location,
+        },
+        error => error,
+    }
+}
+
 fn skip_line_comment(source_chars: &[char]) -> &[char] {
     // 行注释
     // 跳过所有字符直到：
@@ -481,9 +890,10 @@ fn skip_comment(source_chars: &[char]) -> Result<&[char], Error> {
             },
             None => {
                 // 到了末尾仍未找到结束符
-                return Err(Error::LexerError(
-                    "expected comment ending symbol".to_string(),
-                ));
+                return Err(Error::UnterminatedLiteral {
+                    kind: LiteralKind::BlockComment,
+                    location: new_location(),
+                });
             }
         }
     }
@@ -528,9 +938,10 @@ fn lex_document_comment(source_chars: &[char]) -> Result<(String, &[char]), Erro
             }
             None => {
                 // 到了末尾仍未找到结束字符
-                return Err(Error::LexerError(
-                    "expected document comment ending symbol".to_string(),
-                ));
+                return Err(Error::UnterminatedLiteral {
+                    kind: LiteralKind::DocumentComment,
+                    location: new_location(),
+                });
             }
         }
     }
@@ -586,15 +997,21 @@ fn lex_char(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
             }
             None => {
                 // 到了末尾仍未找到结束字符
-                return Err(Error::LexerError(
-                    "expected char literal ending symbol".to_string(),
-                ));
+                return Err(Error::UnterminatedLiteral {
+                    kind: LiteralKind::Char,
+                    location: new_location(),
+                });
             }
         }
     }
 
     let value_chars = &source_chars[..end_pos];
 
+    // 空的字符字面量 `''`
+    if value_chars.is_empty() {
+        return Err(Error::LexerError("empty char literal".to_string()));
+    }
+
     // todo:: 处理转义字符
     // todo:: 验证字符的有效性
 
@@ -642,9 +1059,10 @@ fn lex_string(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
             }
             None => {
                 // 到了末尾仍未找到结束字符
-                return Err(Error::LexerError(
-                    "expected string literal ending symbol".to_string(),
-                ));
+                return Err(Error::UnterminatedLiteral {
+                    kind: LiteralKind::String,
+                    location: new_location(),
+                });
             }
         }
     }
@@ -694,9 +1112,10 @@ fn lex_raw_string(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error
             }
             None => {
                 // 到了末尾仍未找到结束字符
-                return Err(Error::LexerError(
-                    "expected raw string literal ending symbol".to_string(),
-                ));
+                return Err(Error::UnterminatedLiteral {
+                    kind: LiteralKind::RawString,
+                    location: new_location(),
+                });
             }
         }
     }
@@ -749,9 +1168,10 @@ fn lex_template_string(source_chars: &[char]) -> Result<(TokenDetail, &[char]),
             }
             None => {
                 // 到了末尾仍未找到结束字符
-                return Err(Error::LexerError(
-                    "expected template string literal ending symbol".to_string(),
-                ));
+                return Err(Error::UnterminatedLiteral {
+                    kind: LiteralKind::TemplateString,
+                    location: new_location(),
+                });
             }
         }
     }
@@ -836,9 +1256,10 @@ fn lex_named_operator(source_chars: &[char]) -> Result<(TokenDetail, &[char]), E
             }
             None => {
                 // 到了末尾仍未找到结束字符
-                return Err(Error::LexerError(
-                    "expected named operator ending symbol".to_string(),
-                ));
+                return Err(Error::UnterminatedLiteral {
+                    kind: LiteralKind::NamedOperator,
+                    location: new_location(),
+                });
             }
         }
     }
@@ -867,9 +1288,10 @@ fn lex_attribute(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error>
         Some(pos) => pos,
         None => {
             // 到了末尾仍未找到结束字符
-            return Err(Error::LexerError(
-                "expected attribute ending symbol".to_string(),
-            ));
+            return Err(Error::UnterminatedLiteral {
+                kind: LiteralKind::Attribute,
+                location: new_location(),
+            });
         }
     };
 
@@ -883,15 +1305,83 @@ fn lex_attribute(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error>
 }
 
 fn lex_16_radix_integer(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
-    todo!()
+    // 十六进制整数
+    //
+    // e.g.
+    // 0x1F_FF
+    //  ^------- 当前所在的位置（前缀 `0` 已被消除）
+
+    lex_radix_integer(source_chars, 16, "hexadecimal")
 }
 
 fn lex_2_radix_integer(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
-    todo!()
+    // 二进制整数
+    //
+    // e.g.
+    // 0b1010_0001
+    //  ^------- 当前所在的位置（前缀 `0` 已被消除）
+
+    lex_radix_integer(source_chars, 2, "binary")
+}
+
+// 解析带有前缀（`0x` 或者 `0b`）的整数，source_chars 从前缀的第二个字符开始
+fn lex_radix_integer<'a>(
+    source_chars: &'a [char],
+    radix: u32,
+    radix_name: &str,
+) -> Result<(TokenDetail, &'a [char]), Error> {
+    let prefix = format!("0{}", source_chars[0]);
+
+    // 跳过前缀的第二个字符，然后查找连续的数字（以及分隔符），
+    // 其中也包括字母，以便对 `0xfg`、`0b12` 等报告无效的数字，而不是把后面的部分当作标识符
+    let chars = move_forword(source_chars, 1);
+    let end_pos = chars
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+        .count();
+
+    let value_chars = &chars[..end_pos];
+    let text = format!("{}{}", prefix, value_chars.iter().collect::<String>());
+
+    if value_chars.is_empty() {
+        return Err(new_number_format_error(&format!(
+            "missing digits after \"{}\"",
+            prefix
+        )));
+    }
+
+    if let Some(c) = value_chars
+        .iter()
+        .find(|c| **c != '_' && !c.is_digit(radix))
+    {
+        return Err(new_number_format_error(&format!(
+            "invalid digit '{}' in {} integer \"{}\"",
+            c, radix_name, text
+        )));
+    }
+
+    let value_string = remove_numeric_separators(value_chars, radix)?;
+
+    // 将字符串转换为数字，超出 Int（i64）的范围时报告错误
+    let value = i64::from_str_radix(&value_string, radix)
+        .map_err(|e| new_invalid_number_error(format!("invalid integer number \"{}\"", text), e))?;
+
+    let rest = move_forword(chars, end_pos);
+
+    Ok((new_token_detail(Token::Integer(value)), rest))
 }
 
 fn lex_zero_point_float(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
-    todo!()
+    // 整数部分为 0 的浮点数（以及虚数）
+    //
+    // e.g.
+    // 0.5
+    // 0.5e-3
+    // 0.25i
+    //  ^------- 当前所在的位置（整数部分 `0` 已被消除）
+
+    // 跳过小数点，然后跟普通的浮点数一样解析小数点后面的部分
+    continue_lex_float_number(vec!['0'], move_forword(source_chars, 1))
 }
 
 fn lex_number(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
@@ -970,15 +1460,12 @@ fn lex_number(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
     }
 
     let value_chars = &source_chars[..end_pos];
-    let value_string = value_chars
-        .iter()
-        .filter(|c| **c != '_') // 移除字符串当中的下划线
-        .collect::<String>();
+    let value_string = remove_numeric_separators(value_chars, 10)?;
 
     // 将字符串转换为数字
-    let value: i64 = value_string
-        .parse()
-        .map_err(|_| Error::LexerError("invalid integer number".to_string()))?;
+    let value: i64 = value_string.parse().map_err(|e| {
+        new_invalid_number_error(format!("invalid integer number \"{}\"", value_string), e)
+    })?;
 
     // 当前 end_pos 处于标识符的最后一个数字位置
     // 剩余的字符应该从数字位置之后开始，即跳过 end_pos 个字符即可。
@@ -987,6 +1474,64 @@ fn lex_number(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
     Ok((new_token_detail(Token::Integer(value)), rest))
 }
 
+// 检查并移除数字当中的分隔符（下划线）
+//
+// 分隔符只能位于两个数字之间，比如 `1_000`、`3.141_592`、`6.626e-3_4`，
+// 不能连续出现（`1__2`），也不能位于数字的开头或者末尾，以及紧挨着
+// 小数点、指数符号 `e`、负号和虚数符号 `i`（比如 `1_`、`1_.5`、`1._5`、`1_e5`、`1e_5`、`1_i`）。
+//
+// 注：以下划线开头的 `_1` 是标识符而不是数字。
+//
+// radix 为数字的进制，比如十六进制整数 `0xff_ff` 的分隔符两边可以是字母 `a` 到 `f`。
+fn remove_numeric_separators(chars: &[char], radix: u32) -> Result<String, Error> {
+    for (index, c) in chars.iter().enumerate() {
+        if *c != '_' {
+            continue;
+        }
+
+        let text = chars.iter().collect::<String>();
+
+        if chars.get(index + 1) == Some(&'_') {
+            return Err(new_number_format_error(&format!(
+                "invalid numeric separator in \"{}\", consecutive underscores are not allowed",
+                text
+            )));
+        }
+
+        let is_previous_digit = index > 0 && chars[index - 1].is_digit(radix);
+        let is_next_digit = chars.get(index + 1).is_some_and(|c| c.is_digit(radix));
+        if !is_previous_digit || !is_next_digit {
+            return Err(new_number_format_error(&format!(
+                "invalid numeric separator in \"{}\", underscores must be between digits",
+                text
+            )));
+        }
+    }
+
+    Ok(chars.iter().filter(|c| **c != '_').collect::<String>())
+}
+
+// 数字转换失败的错误
+fn new_invalid_number_error(
+    message: String,
+    source: impl std::error::Error + Send + Sync + 'static,
+) -> Error {
+    Error::InvalidNumber {
+        message,
+        location: new_location(),
+        source: Some(Box::new(source)),
+    }
+}
+
+// 数字格式错误
+fn new_number_format_error(message: &str) -> Error {
+    Error::InvalidNumber {
+        message: message.to_string(),
+        location: new_location(),
+        source: None,
+    }
+}
+
 fn extend_vec_with_with_separator_and_char_slice(
     mut left: Vec<char>,
     separator: char,
@@ -1018,8 +1563,8 @@ fn continue_lex_float_number(
                         end_pos += 1;
                         rest
                     }
-                    '.' => return Err(Error::LexerError("invalid float number".to_string())),
-                    '\'' => return Err(Error::LexerError("invalid bit number".to_string())),
+                    '.' => return Err(new_number_format_error("invalid float number")),
+                    '\'' => return Err(new_number_format_error("invalid bit number")),
                     'i' => {
                         let extend_chars = extend_vec_with_with_separator_and_char_slice(
                             previous_chars,
@@ -1055,15 +1600,12 @@ fn continue_lex_float_number(
         &remain_chars[..end_pos],
     );
 
-    let value_string = value_chars
-        .iter()
-        .filter(|c| **c != '_') // 移除字符串当中的下划线
-        .collect::<String>();
+    let value_string = remove_numeric_separators(&value_chars, 10)?;
 
     // 将字符串转换为数字
-    let value: f64 = value_string
-        .parse()
-        .map_err(|_| Error::LexerError("invalid float number".to_string()))?;
+    let value: f64 = value_string.parse().map_err(|e| {
+        new_invalid_number_error(format!("invalid float number \"{}\"", value_string), e)
+    })?;
 
     // 当前 end_pos 处于数字的最后一个字符位置
     // 剩余的字符应该从数字位置之后开始，即跳过 end_pos 个字符即可。
@@ -1081,24 +1623,118 @@ fn continue_lex_imaginary_number(
     // ___ ___ remain_chars
     //   |____ previous_chars
 
-    let value_string = previous_chars
-        .iter()
-        .filter(|c| **c != '_') // 移除字符串当中的下划线
-        .collect::<String>();
+    let value_string = remove_numeric_separators(&previous_chars, 10)?;
 
     // 将字符串转换为数字
-    let value: f64 = value_string
-        .parse()
-        .map_err(|_| Error::LexerError("invalid float number".to_string()))?;
+    let value: f64 = value_string.parse().map_err(|e| {
+        new_invalid_number_error(format!("invalid float number \"{}\"", value_string), e)
+    })?;
 
     Ok((new_token_detail(Token::Imaginary(value)), remain_chars))
 }
 
-fn continue_lex_bit_number(
-    previous_chars: Vec<char>,
-    remain_chars: &[char],
-) -> Result<(TokenDetail, &[char]), Error> {
-    todo!()
+fn continue_lex_bit_number(
+    previous_chars: Vec<char>,
+    remain_chars: &[char],
+) -> Result<(TokenDetail, &[char]), Error> {
+    // 比特数
+    //
+    // 格式为 `宽度'进制数值`，进制可以是 `x`（十六进制）、`b`（二进制）或者 `d`（十进制），
+    // 数值超出宽度时报告错误。
+    //
+    // e.g.
+    // 8'xff
+    // 4'b01_10
+    // 8'd10
+    //   ^------- 当前所在的位置（宽度以及符号 `'` 已被消除）
+
+    let width_string = remove_numeric_separators(&previous_chars, 10)?;
+    let width: usize = width_string.parse().map_err(|e| {
+        new_invalid_number_error(format!("invalid bit width \"{}\"", width_string), e)
+    })?;
+
+    // 宽度决定了需要分配的字节数，所以需要先检查宽度的范围
+    if width == 0 || width > MAX_BIT_WIDTH {
+        return Err(new_number_format_error(&format!(
+            "bit width {} is out of range, the width should be between 1 and {}",
+            width_string, MAX_BIT_WIDTH
+        )));
+    }
+
+    let (radix, digit_chars) = match remain_chars.split_first() {
+        Some(('x', rest)) => (16, rest),
+        Some(('b', rest)) => (2, rest),
+        Some(('d', rest)) => (10, rest),
+        _ => return Err(new_number_format_error("invalid bit number")),
+    };
+
+    let end_pos = digit_chars
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+        .count();
+    let value_chars = &digit_chars[..end_pos];
+    let text = format!(
+        "{}'{}{}",
+        width_string,
+        remain_chars[0],
+        value_chars.iter().collect::<String>()
+    );
+
+    if value_chars.is_empty() {
+        return Err(new_number_format_error(&format!(
+            "missing digits in bit number \"{}\"",
+            text
+        )));
+    }
+
+    if let Some(c) = value_chars
+        .iter()
+        .find(|c| **c != '_' && !c.is_digit(radix))
+    {
+        return Err(new_number_format_error(&format!(
+            "invalid digit '{}' in bit number \"{}\"",
+            c, text
+        )));
+    }
+
+    let value_string = remove_numeric_separators(value_chars, radix)?;
+    let bytes = digits_to_bytes(&value_string, radix, width).ok_or_else(|| {
+        new_number_format_error(&format!(
+            "bit number \"{}\" exceeds the width of {} bits",
+            text, width
+        ))
+    })?;
+
+    let rest = move_forword(digit_chars, end_pos);
+
+    Ok((new_token_detail(Token::Bit(width, bytes)), rest))
+}
+
+// 将数字字符串转换为 width 位的字节（高位在前），字节的数量为 width 除以 8 向上取整，
+// 数值超出宽度时返回 None。
+fn digits_to_bytes(digits: &str, radix: u32, width: usize) -> Option<Vec<u8>> {
+    let mut bytes = vec![0u8; width.div_ceil(8)];
+
+    for c in digits.chars() {
+        // 逐个数字累加：bytes = bytes * radix + digit
+        let mut carry = c.to_digit(radix)?;
+        for byte in bytes.iter_mut().rev() {
+            let value = (*byte as u32) * radix + carry;
+            *byte = value as u8;
+            carry = value >> 8;
+        }
+
+        if carry != 0 {
+            return None;
+        }
+    }
+
+    // 检查最高位的字节里超出宽度的部分
+    let unused_bits = bytes.len() * 8 - width;
+    match bytes.first() {
+        Some(first) if unused_bits > 0 && (first >> (8 - unused_bits)) != 0 => None,
+        _ => Some(bytes),
+    }
 }
 
 fn continue_lex_float_number_exponent(
@@ -1122,7 +1758,7 @@ fn continue_lex_float_number_exponent(
                             end_pos += 1;
                             rest
                         } else {
-                            return Err(Error::LexerError("invalid exponent number".to_string()));
+                            return Err(new_number_format_error("invalid exponent number"));
                         }
                     }
                     '0'..='9' | '_' => {
@@ -1136,10 +1772,10 @@ fn continue_lex_float_number_exponent(
                             break;
                         } else {
                             // 不支持 "指数值为小数" 的浮点数
-                            return Err(Error::LexerError("unsupport float exponent".to_string()));
+                            return Err(new_number_format_error("unsupported float exponent"));
                         }
                     }
-                    '\'' => return Err(Error::LexerError("invalid bit number".to_string())),
+                    '\'' => return Err(new_number_format_error("invalid bit number")),
                     'i' => {
                         let extend_chars = extend_vec_with_with_separator_and_char_slice(
                             previous_chars,
@@ -1148,7 +1784,7 @@ fn continue_lex_float_number_exponent(
                         );
                         return continue_lex_imaginary_number(extend_chars, rest);
                     }
-                    'e' => return Err(Error::LexerError("invalid exponent number".to_string())),
+                    'e' => return Err(new_number_format_error("invalid exponent number")),
                     _ => {
                         // 遇到了一个非数字
                         break;
@@ -1168,15 +1804,12 @@ fn continue_lex_float_number_exponent(
         &remain_chars[..end_pos],
     );
 
-    let value_string = value_chars
-        .iter()
-        .filter(|c| **c != '_') // 移除字符串当中的下划线
-        .collect::<String>();
+    let value_string = remove_numeric_separators(&value_chars, 10)?;
 
     // 将字符串转换为数字
-    let value: f64 = value_string
-        .parse()
-        .map_err(|_| Error::LexerError("invalid float number".to_string()))?;
+    let value: f64 = value_string.parse().map_err(|e| {
+        new_invalid_number_error(format!("invalid float number \"{}\"", value_string), e)
+    })?;
 
     // 当前 end_pos 处于数字的最后一个字符位置
     // 剩余的字符应该从数字位置之后开始，即跳过 end_pos 个字符即可。
@@ -1231,33 +1864,21 @@ fn lex_identifier_or_keyword(source_chars: &[char]) -> Result<(TokenDetail, &[ch
 }
 
 fn is_none_zero_number(c: char) -> bool {
-    match c {
-        '1'..='9' => true,
-        _ => false,
-    }
+    matches!(c, '1'..='9')
 }
 
 // 可以作为标识符或者关键字的首位的文字
 fn is_valid_first_letter_of_identifier_or_keyword(c: char) -> bool {
-    match c {
-        'a'..='z' | 'A'..='Z' | '_' => true,
-        _ => false,
-    }
+    matches!(c, 'a'..='z' | 'A'..='Z' | '_')
 }
 
 // 可以作为标识符或者关键字的文字（数字、字母、中文文字等）
 fn is_valid_letter_of_identifier_or_keyword(c: char) -> bool {
-    match c {
-        'a'..='z' | 'A'..='Z' | '_' | '0'..='9' => true,
-        _ => false,
-    }
+    matches!(c, 'a'..='z' | 'A'..='Z' | '_' | '0'..='9')
 }
 
 fn is_char(expected: char, source_chars: &[char]) -> bool {
-    match source_chars.first() {
-        Some(first_char) if *first_char == expected => true,
-        _ => false,
-    }
+    matches!(source_chars.first(), Some(first_char) if *first_char == expected)
 }
 
 fn is_chars(expected: [char; 2], source_chars: &[char]) -> bool {
@@ -1279,6 +1900,39 @@ fn add_token_detail(
     token_details
 }
 
+fn add_trivia(trivias: &mut TriviaList, position: usize, trivia: Trivia, location: Location) {
+    trivias.push((position, TriviaDetail { location, trivia }));
+}
+
+// 添加一个空白字符，连续的空白字符会合并为一项琐碎内容
+fn add_whitespace(trivias: &mut TriviaList, position: usize, c: char, location: Location) {
+    match trivias.last_mut() {
+        Some((
+            last_position,
+            TriviaDetail {
+                trivia: Trivia::Whitespace(text),
+                location: last_location,
+            },
+        )) if *last_position == position => {
+            text.push(c);
+            last_location.end = location.end;
+        }
+        _ => add_trivia(
+            trivias,
+            position,
+            Trivia::Whitespace(c.to_string()),
+            location,
+        ),
+    }
+}
+
+// 获取从 source_chars 开始到 rest 为止（不包括）已被消耗的文本
+fn get_consumed_text(source_chars: &[char], rest: &[char]) -> String {
+    source_chars[..source_chars.len() - rest.len()]
+        .iter()
+        .collect()
+}
+
 fn new_token_detail(token: Token) -> TokenDetail {
     TokenDetail {
         location: new_location(),
@@ -1293,74 +1947,198 @@ fn new_location() -> Location {
         file_id: 0,
         start: 0,
         end: 0,
+        line: 0,
+        column: 0,
+    }
+}
+
+// 检查标识符是否跟保留字同名
+//
+// - 跟当前版本的保留字同名（相应的语法尚未实现）时，返回错误；
+// - 跟较新版本的保留字同名时，产生一个警告，以便用户在升级版本之前修改标识符。
+fn check_reserved_identifier(
+    name: &str,
+    edition: Edition,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), Error> {
+    check_reserved_keyword(find_keyword(name), edition, warnings)
+}
+
+fn check_reserved_keyword(
+    keyword: Option<&Keyword>,
+    edition: Edition,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), Error> {
+    match keyword {
+        Some(keyword) if keyword.is_reserved_in(edition) => Err(Error::LexerError(format!(
+            "\"{}\" is a reserved keyword",
+            keyword.name
+        ))),
+        Some(keyword) if keyword.kind == KeywordKind::Reserved => {
+            warnings.push(Warning {
+                message: format!(
+                    "\"{}\" is a reserved keyword in edition {}",
+                    keyword.name, keyword.since
+                ),
+                location: new_location(),
+            });
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+// 检查字符串字面量的长度以及模板字符串占位符的嵌套层数
+fn check_string_limits(token: &Token, limits: &Limits) -> Result<(), Error> {
+    match token {
+        Token::GeneralString(value) => check_limit(
+            ResourceLimit::StringLength,
+            limits.max_string_length,
+            value.chars().count(),
+        ),
+        Token::TemplateString(value) => {
+            check_limit(
+                ResourceLimit::StringLength,
+                limits.max_string_length,
+                value.chars().count(),
+            )?;
+            check_limit(
+                ResourceLimit::TemplateNesting,
+                limits.max_template_nesting,
+                get_template_nesting_depth(value),
+            )
+        }
+        _ => Ok(()),
+    }
+}
+
+// 计算模板字符串占位符 `{{...}}` 的最大嵌套层数
+fn get_template_nesting_depth(value: &str) -> usize {
+    let mut chars = value.chars().peekable();
+    let mut depth: usize = 0;
+    let mut max_depth: usize = 0;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.next_if_eq(&'{').is_some() => {
+                depth += 1;
+                max_depth = max_depth.max(depth);
+            }
+            '}' if chars.next_if_eq(&'}').is_some() => {
+                depth = depth.saturating_sub(1);
+            }
+            _ => {}
+        }
     }
+
+    max_depth
 }
 
 // 用于检测字符是关键字还是标识符
+// 查找关键字
+//
+// 词法分析时每个标识符都需要查找一次关键字，所以这里先按照单词的长度
+// 以及首个字母进行分派，然后才比较整个单词，
+// 这样每个标识符最多只需比较少数几个候选关键字（相当于一个手写的完美哈希表），
+// 而不必跟所有关键字依次比较（对比的基准测试见 `tests::bench_lookup_keyword`）。
+//
+// 注：
+// - 这里只包括当前版本（Edition2022）的保留字，关键字的完整列表见 `keyword::KEYWORDS`；
+// - 新增关键字时，需要放在对应 "长度" 和 "首字母" 的分支里。
 fn lookup_keyword(name: &str) -> Option<Token> {
-    match name {
-        // 字面量
-        "true" => Some(Token::Boolean(true)),
-        "false" => Some(Token::Boolean(false)),
-
-        // 关键字
-        "do" => Some(Token::Do),
-        "join" => Some(Token::Join),
-
-        "let" => Some(Token::Let),
-        "fn" => Some(Token::Fn),
-        "sign" => Some(Token::Sign),
-
-        "if" => Some(Token::If),
-        "then" => Some(Token::Then),
-        "else" => Some(Token::Else),
-        "for" => Some(Token::For),
-        "next" => Some(Token::Next),
-        "each" => Some(Token::Each),
-        "in" => Some(Token::In),
-
-        "branch" => Some(Token::Branch),
-        "match" => Some(Token::Match),
-        "case" => Some(Token::Case),
-        "default" => Some(Token::Default),
-        "where" => Some(Token::Where),
-        "only" => Some(Token::Only),
-        // "as" => Some(Token::As),
-        "into" => Some(Token::Into),
-        "regular" => Some(Token::Regular),
-        "template" => Some(Token::Template),
-
-        "function" => Some(Token::Function),
-        "type" => Some(Token::Type),
-        "which" => Some(Token::Which),
-        "empty" => Some(Token::Empty),
-        "pattern" => Some(Token::Pattern),
-        "limit" => Some(Token::Limit),
-
-        // "namespace" => Some(Token::Namespace),
-        "use" => Some(Token::Use),
-        "const" => Some(Token::Const),
-        "enum" => Some(Token::Enum),
-        "struct" => Some(Token::Struct),
-        "union" => Some(Token::Union),
-        "trait" => Some(Token::Trait),
-        "impl" => Some(Token::Impl),
-        "alias" => Some(Token::Alias),
-        _ => None,
-    }
+    let first = *name.as_bytes().first()?;
+
+    let token = match (name.len(), first) {
+        // 2 个字母
+        (2, b'd') if name == "do" => Token::Do,
+        (2, b'f') if name == "fn" => Token::Fn,
+        (2, b'i') if name == "if" => Token::If,
+        (2, b'i') if name == "in" => Token::In,
+        (2, b'a') if name == "as" => Token::As,
+
+        // 3 个字母
+        (3, b'l') if name == "let" => Token::Let,
+        (3, b'f') if name == "for" => Token::For,
+        (3, b'u') if name == "use" => Token::Use,
+
+        // 4 个字母
+        (4, b't') if name == "true" => Token::Boolean(true),
+        (4, b't') if name == "then" => Token::Then,
+        (4, b't') if name == "type" => Token::Type,
+        (4, b'j') if name == "join" => Token::Join,
+        (4, b's') if name == "sign" => Token::Sign,
+        (4, b'e') if name == "else" => Token::Else,
+        (4, b'e') if name == "each" => Token::Each,
+        (4, b'e') if name == "enum" => Token::Enum,
+        (4, b'n') if name == "next" => Token::Next,
+        (4, b'c') if name == "case" => Token::Case,
+        (4, b'o') if name == "only" => Token::Only,
+        (4, b'i') if name == "into" => Token::Into,
+        (4, b'i') if name == "impl" => Token::Impl,
+
+        // 5 个字母
+        (5, b'f') if name == "false" => Token::Boolean(false),
+        (5, b'm') if name == "match" => Token::Match,
+        (5, b'w') if name == "where" => Token::Where,
+        (5, b'w') if name == "which" => Token::Which,
+        (5, b'e') if name == "empty" => Token::Empty,
+        (5, b'l') if name == "limit" => Token::Limit,
+        (5, b'c') if name == "const" => Token::Const,
+        (5, b'u') if name == "union" => Token::Union,
+        (5, b't') if name == "trait" => Token::Trait,
+        (5, b'a') if name == "alias" => Token::Alias,
+
+        // 6 个字母
+        (6, b'b') if name == "branch" => Token::Branch,
+        (6, b's') if name == "struct" => Token::Struct,
+
+        // 7 个字母
+        (7, b'd') if name == "default" => Token::Default,
+        (7, b'r') if name == "regular" => Token::Regular,
+        (7, b'p') if name == "pattern" => Token::Pattern,
+
+        // 8 个字母
+        (8, b't') if name == "template" => Token::Template,
+        (8, b'f') if name == "function" => Token::Function,
+
+        // 9 个字母
+        (9, b'n') if name == "namespace" => Token::Namespace,
+        _ => return None,
+    };
+
+    Some(token)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::error::Error as StdError;
+
     use crate::{
-        lexer::new_location,
-        token::{Token, TokenDetail},
+        error::{Error, LiteralKind},
+        keyword::{reserved_for_future, Edition, KeywordKind, KEYWORDS},
+        limit::{Limits, ResourceLimit},
+        token::{Location, Token, TokenDetail, TokenOrTrivia},
     };
 
-    use super::tokenize;
+    use super::{
+        check_reserved_keyword, get_template_nesting_depth, lookup_keyword, relex,
+        relex_with_options, tokenize, tokenize_with_options, tokenize_with_trivia, LexerOptions,
+        TextEdit,
+    };
 
     // 辅助函数
 
+    // 位于第一行的位置
+    fn new_location_at(start: usize, end: usize) -> Location {
+        Location {
+            file_id: 0,
+            start,
+            end,
+            line: 1,
+            column: start + 1,
+        }
+    }
+
     fn token_details_to_string(token_details: &[TokenDetail]) -> Vec<String> {
         let strings: Vec<String> = token_details.iter().map(|t| t.token.to_string()).collect();
         strings
@@ -1417,7 +2195,7 @@ mod tests {
             tokens1,
             vec![TokenDetail {
                 token: Token::Integer(123),
-                location: new_location()
+                location: new_location_at(0, 3)
             }]
         );
         assert_eq!(token_details_to_string(&tokens1), vec!["123"]);
@@ -1428,17 +2206,58 @@ mod tests {
             vec!["1", "100", "1234", "123"]
         );
 
-        // todo:: 测试 16 进制和 2 进制表示法的整数
+        // 16 进制和 2 进制表示法的整数
+        let tokens3 = tokenize("0x1F_FF 0xab 0b1010_0001 0b0").unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens3),
+            vec!["8191", "171", "161", "0"]
+        );
+        assert_eq!(tokens3[0].location, new_location_at(0, 7));
+
+        let tokens4 = tokenize("0x7fff_ffff_ffff_ffff..0b11").unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens4),
+            vec!["9223372036854775807", "..", "3"]
+        );
+
+        assert_eq!(
+            tokenize("0x").unwrap_err().to_string(),
+            "missing digits after \"0x\""
+        );
+        assert_eq!(
+            tokenize("0xfg").unwrap_err().to_string(),
+            "invalid digit 'g' in hexadecimal integer \"0xfg\""
+        );
+        assert_eq!(
+            tokenize("0b102").unwrap_err().to_string(),
+            "invalid digit '2' in binary integer \"0b102\""
+        );
+
+        // 超出 Int 的范围
+        let e1 = tokenize("0x1_0000_0000_0000_0000").unwrap_err();
+        assert!(matches!(e1, Error::InvalidNumber { .. }));
+        assert_eq!(
+            e1.to_string(),
+            "invalid integer number \"0x1_0000_0000_0000_0000\""
+        );
+
+        for source in ["0x_1", "0xff_", "0b1__0"] {
+            assert!(matches!(
+                tokenize(source).unwrap_err(),
+                Error::InvalidNumber { .. }
+            ));
+        }
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_float_literal() {
         let tokens1 = tokenize("3.14").unwrap();
         assert_eq!(
             tokens1,
             vec![TokenDetail {
                 token: Token::Float(3.14),
-                location: new_location()
+                location: new_location_at(0, 4)
             }]
         );
         assert_eq!(token_details_to_string(&tokens1), vec!["3.14"]);
@@ -1467,6 +2286,28 @@ mod tests {
             token_details_to_string(&tokens8),
             vec!["[", "0", "..", "100", "]"]
         );
+
+        // 整数部分为 0 的浮点数
+        let tokens9 = tokenize("0.5").unwrap();
+        assert_eq!(
+            tokens9,
+            vec![TokenDetail {
+                token: Token::Float(0.5),
+                location: new_location_at(0, 3)
+            }]
+        );
+
+        let tokens10 = tokenize("0.5e-3 0.125_5 0.0 0.25i").unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens10),
+            vec!["0.0005", "0.1255", "0", "0.25i"]
+        );
+
+        let tokens11 = tokenize("0..=9").unwrap();
+        assert_eq!(token_details_to_string(&tokens11), vec!["0", "..=", "9"]);
+
+        assert!(tokenize("0.5.1").is_err());
+        assert!(tokenize("0._5").is_err());
     }
 
     #[test]
@@ -1476,7 +2317,7 @@ mod tests {
             tokens1,
             vec![TokenDetail {
                 token: Token::Imaginary(5.0),
-                location: new_location()
+                location: new_location_at(0, 2)
             }]
         );
         assert_eq!(token_details_to_string(&tokens1), vec!["5i"]);
@@ -1492,11 +2333,78 @@ mod tests {
 
         let tokens5 = tokenize("1.6e-2i").unwrap();
         assert_eq!(token_details_to_string(&tokens5), vec!["0.016i"]);
+
+        let tokens6 = tokenize("1+0i").unwrap();
+        assert_eq!(token_details_to_string(&tokens6), vec!["1", "+", "0i"]);
+        assert!(tokenize("0in").is_err());
     }
 
     #[test]
     fn test_bit_literal() {
-        // todo::
+        let tokens1 = tokenize("8'xff").unwrap();
+        assert_eq!(
+            tokens1,
+            vec![TokenDetail {
+                token: Token::Bit(8, vec![0xff]),
+                location: new_location_at(0, 5)
+            }]
+        );
+
+        let tokens2 = tokenize("4'b01_10 16'x08cd 12'd300 1_6'xab_cd").unwrap();
+        assert_eq!(
+            tokens2
+                .iter()
+                .map(|t| t.token.clone())
+                .collect::<Vec<Token>>(),
+            vec![
+                Token::Bit(4, vec![0x6]),
+                Token::Bit(16, vec![0x08, 0xcd]),
+                Token::Bit(12, vec![0x01, 0x2c]),
+                Token::Bit(16, vec![0xab, 0xcd]),
+            ]
+        );
+
+        // 前导的 0 不算超出宽度
+        let tokens3 = tokenize("4'x06 3'b0111").unwrap();
+        assert_eq!(token_details_to_string(&tokens3), vec!["4'x06", "3'x07"]);
+
+        assert_eq!(
+            tokenize("8'x1ff").unwrap_err().to_string(),
+            "bit number \"8'x1ff\" exceeds the width of 8 bits"
+        );
+        assert_eq!(
+            tokenize("4'b10000").unwrap_err().to_string(),
+            "bit number \"4'b10000\" exceeds the width of 4 bits"
+        );
+        assert_eq!(
+            tokenize("4'b012").unwrap_err().to_string(),
+            "invalid digit '2' in bit number \"4'b012\""
+        );
+        assert_eq!(
+            tokenize("8'x").unwrap_err().to_string(),
+            "missing digits in bit number \"8'x\""
+        );
+
+        // 宽度的范围
+        assert_eq!(
+            tokenize("0'x0").unwrap_err().to_string(),
+            "bit width 0 is out of range, the width should be between 1 and 65536"
+        );
+        for source in ["65537'x1", "99999999'x1", "18446744073709551615'x1"] {
+            assert!(matches!(
+                tokenize(source).unwrap_err(),
+                Error::InvalidNumber { .. }
+            ));
+        }
+        let tokens4 = tokenize("65536'x1").unwrap();
+        assert!(matches!(&tokens4[0].token, Token::Bit(65536, bytes) if bytes.len() == 8192));
+
+        for source in ["8'q12", "8'x_ff", "8'xff_"] {
+            assert!(matches!(
+                tokenize(source).unwrap_err(),
+                Error::InvalidNumber { .. }
+            ));
+        }
     }
 
     #[test]
@@ -1506,7 +2414,7 @@ mod tests {
             tokens1,
             vec![TokenDetail {
                 token: Token::Boolean(true),
-                location: new_location()
+                location: new_location_at(0, 4)
             }]
         );
         assert_eq!(token_details_to_string(&tokens1), vec!["true"]);
@@ -1520,9 +2428,31 @@ mod tests {
         let tokens1 = tokenize("'a' 'b'").unwrap();
         assert_eq!(token_details_to_string(&tokens1), vec!["'a'", "'b'"]);
 
+        // 空的字符字面量
+        assert!(matches!(
+            tokenize("let c = ''"),
+            Err(Error::InvalidToken { message, location })
+                if message == "empty char literal" && location.start == 8
+        ));
+
         // todo:: 测试转义字符
     }
 
+    #[test]
+    fn test_malformed_input_does_not_panic() {
+        // 曾经（或者可能）导致词法分析器 panic 的输入，只需返回错误而不能 panic
+        let corpus = [
+            "''", "'", "'a", "'\\", "'\\'", "0'", "8'", "8'x", "0'x", "0'x0", "65537'x1", "\"",
+            "\"\"\"", "\"\"\"a", "`", "#", "#[", "1e", "1e+", "0x", "0b", "1_", "1.", ".", "1..",
+            "\\", "/*", "/* /*", "r\"", "\u{0}",
+        ];
+
+        for source in corpus {
+            let result = std::panic::catch_unwind(|| tokenize(source));
+            assert!(result.is_ok(), "tokenize {:?} panicked", source);
+        }
+    }
+
     #[test]
     fn test_generall_string_literal() {
         let tokens1 = tokenize(r#""foo" "b'a`r" "a\"b""#).unwrap();
@@ -1602,15 +2532,246 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_lookup_keyword() {
+        let keywords = [
+            ("true", Token::Boolean(true)),
+            ("false", Token::Boolean(false)),
+            ("do", Token::Do),
+            ("join", Token::Join),
+            ("let", Token::Let),
+            ("fn", Token::Fn),
+            ("sign", Token::Sign),
+            ("if", Token::If),
+            ("then", Token::Then),
+            ("else", Token::Else),
+            ("for", Token::For),
+            ("next", Token::Next),
+            ("each", Token::Each),
+            ("in", Token::In),
+            ("branch", Token::Branch),
+            ("match", Token::Match),
+            ("case", Token::Case),
+            ("default", Token::Default),
+            ("where", Token::Where),
+            ("only", Token::Only),
+            ("into", Token::Into),
+            ("regular", Token::Regular),
+            ("template", Token::Template),
+            ("function", Token::Function),
+            ("type", Token::Type),
+            ("which", Token::Which),
+            ("empty", Token::Empty),
+            ("pattern", Token::Pattern),
+            ("limit", Token::Limit),
+            ("use", Token::Use),
+            ("const", Token::Const),
+            ("enum", Token::Enum),
+            ("struct", Token::Struct),
+            ("union", Token::Union),
+            ("trait", Token::Trait),
+            ("impl", Token::Impl),
+            ("alias", Token::Alias),
+            ("as", Token::As),
+            ("namespace", Token::Namespace),
+        ];
+
+        for (name, token) in keywords {
+            assert_eq!(lookup_keyword(name), Some(token));
+        }
+
+        // 长度或者首字母相同，但并非关键字的标识符
+        for name in [
+            "",
+            "a",
+            "ass",
+            "d",
+            "dx",
+            "ifs",
+            "iff",
+            "Then",
+            "types",
+            "functions",
+            "templatE",
+            "namespaces",
+        ] {
+            assert_eq!(lookup_keyword(name), None);
+        }
+
+        let tokens1 = tokenize("in into inside").unwrap();
+        assert_eq!(
+            tokens1
+                .iter()
+                .map(|t| t.token.clone())
+                .collect::<Vec<Token>>(),
+            vec![
+                Token::In,
+                Token::Into,
+                Token::Identifier("inside".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keyword_table() {
+        // `lookup_keyword` 应该跟关键字表里当前版本的保留字一致
+        for keyword in KEYWORDS {
+            if keyword.kind == KeywordKind::Reserved && keyword.since == Edition::Edition2022 {
+                assert_eq!(lookup_keyword(keyword.name), keyword.token);
+            } else {
+                assert_eq!(lookup_keyword(keyword.name), None);
+            }
+        }
+    }
+
+    // 关键字查找的基准测试，跟依次比较关键字表里每个关键字的查找方式对比，
+    // 需要以 release 模式单独运行：
+    //
+    // $ cargo test --release --lib bench_lookup_keyword -- --ignored --nocapture
+    #[test]
+    #[ignore = "benchmark, run manually in release mode"]
+    fn bench_lookup_keyword() {
+        use std::{hint::black_box, time::Instant};
+
+        // 关键字密集的单词列表，其中一半是关键字，另一半是跟关键字长度或者首字母相同的标识符
+        let source = include_str!("../scripts/01-base-expression.xuan");
+        let mut words = KEYWORDS.iter().map(|k| k.name).collect::<Vec<&str>>();
+        words.extend([
+            "each_item",
+            "ifs",
+            "types",
+            "functions",
+            "letter",
+            "match_all",
+            "value",
+            "index",
+        ]);
+        words.extend(
+            source
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .filter(|w| !w.is_empty()),
+        );
+
+        let linear_lookup = |name: &str| {
+            KEYWORDS
+                .iter()
+                .find(|k| k.name == name && k.is_reserved_in(Edition::Edition2022))
+                .and_then(|k| k.token.clone())
+        };
+
+        const ROUNDS: usize = 200_000;
+        let measure = |lookup: &dyn Fn(&str) -> Option<Token>| {
+            let start = Instant::now();
+            let mut count = 0;
+            for _ in 0..ROUNDS {
+                for word in &words {
+                    count += lookup(black_box(word)).is_some() as usize;
+                }
+            }
+            (start.elapsed(), count)
+        };
+
+        let (linear_time, linear_count) = measure(&linear_lookup);
+        let (dispatch_time, dispatch_count) = measure(&lookup_keyword);
+        assert_eq!(linear_count, dispatch_count);
+
+        let lookups = (ROUNDS * words.len()) as f64;
+        println!(
+            "linear: {:.2} ns/lookup, dispatch: {:.2} ns/lookup",
+            linear_time.as_nanos() as f64 / lookups,
+            dispatch_time.as_nanos() as f64 / lookups
+        );
+    }
+
+    #[test]
+    fn test_reserved_identifier() {
+        // 目前没有为将来的版本保留的关键字，所以使用一个虚构的关键字
+        let keyword = reserved_for_future("module", Edition::Edition2024);
+
+        // 跟较新版本的保留字同名的标识符
+        let mut warnings1 = vec![];
+        assert!(
+            check_reserved_keyword(Some(&keyword), Edition::Edition2022, &mut warnings1).is_ok()
+        );
+        assert_eq!(
+            warnings1
+                .iter()
+                .map(|w| w.message.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["\"module\" is a reserved keyword in edition 2024"]
+        );
+
+        // 在新版本里不能再用作标识符
+        assert_eq!(
+            check_reserved_keyword(Some(&keyword), Edition::Edition2024, &mut vec![])
+                .unwrap_err()
+                .to_string(),
+            "\"module\" is a reserved keyword"
+        );
+
+        let options2 = LexerOptions {
+            edition: Edition::Edition2024,
+            ..LexerOptions::default()
+        };
+        let (tokens3, warnings3) = tokenize_with_options("a + namespace", &options2).unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens3),
+            vec!["a", "+", "namespace"]
+        );
+        assert_eq!(tokens3[2].token, Token::Namespace);
+        assert!(warnings3.is_empty());
+    }
+
+    #[test]
+    fn test_string_limits() {
+        let options = LexerOptions {
+            limits: Limits {
+                max_string_length: Some(12),
+                max_template_nesting: Some(1),
+                ..Limits::default()
+            },
+            ..LexerOptions::default()
+        };
+
+        assert!(tokenize_with_options("\"hello\" `{{a}}`", &options).is_ok());
+
+        let e1 = tokenize_with_options("\"hello, world!\"", &options).unwrap_err();
+        assert!(e1.is_lexer_error());
+        assert!(matches!(
+            e1,
+            Error::LimitExceeded {
+                limit: ResourceLimit::StringLength,
+                max: 12,
+                ..
+            }
+        ));
+
+        let e2 = tokenize_with_options("`{{ {{a}} }}`", &options).unwrap_err();
+        assert!(matches!(
+            e2,
+            Error::LimitExceeded {
+                limit: ResourceLimit::TemplateNesting,
+                ..
+            }
+        ));
+
+        assert_eq!(
+            get_template_nesting_depth("a {{b}} {{c {{d}} {{e {{f}}}}}}"),
+            3
+        );
+        assert_eq!(get_template_nesting_depth("{a} }}"), 0);
+    }
+
     #[test]
     fn test_keywords() {
         let tokens1 =
-            tokenize("do join let fn if then else for next each in branch match case default").unwrap();
+            tokenize("do join let fn if then else for next each in branch match case default")
+                .unwrap();
         assert_eq!(
             token_details_to_string(&tokens1),
             vec![
-                "do", "join", "let", "fn", "if", "then", "else", "for", "next", "each", "in", "branch", "match",
-                "case", "default",
+                "do", "join", "let", "fn", "if", "then", "else", "for", "next", "each", "in",
+                "branch", "match", "case", "default",
             ]
         );
 
@@ -1626,11 +2787,12 @@ mod tests {
             vec!["function", "type", "which", "empty", "pattern", "limit",]
         );
 
-        let tokens4 = tokenize("use const enum struct union trait impl alias").unwrap();
+        let tokens4 =
+            tokenize("namespace use const enum struct union trait impl alias as").unwrap();
         assert_eq!(
             token_details_to_string(&tokens4),
             vec![
-                //"namespace",
+                "namespace",
                 "use",
                 "const",
                 "enum",
@@ -1639,7 +2801,354 @@ mod tests {
                 "trait",
                 "impl",
                 "alias",
+                "as",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_with_trivia() {
+        let source = "let  a = 1 // foo\n/* bar */ '''baz''' a\t";
+        let items = tokenize_with_trivia(source).unwrap();
+
+        let texts: Vec<String> = items
+            .iter()
+            .map(|item| match item {
+                TokenOrTrivia::Token(t) => t.token.to_string(),
+                TokenOrTrivia::Trivia(t) => format!("[{}]", t.trivia),
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                "let",
+                "[  ]",
+                "a",
+                "[ ]",
+                "=",
+                "[ ]",
+                "1",
+                "[ ]",
+                "[// foo]",
+                "\n",
+                "[/* bar */]",
+                "[ ]",
+                "['''baz''']",
+                "[ ]",
+                "a",
+                "[\t]",
+            ]
+        );
+
+        // 除去琐碎内容之后，应该跟 tokenize 的结果一致
+        let tokens: Vec<TokenDetail> = items
+            .into_iter()
+            .filter_map(|item| match item {
+                TokenOrTrivia::Token(t) => Some(t),
+                TokenOrTrivia::Trivia(_) => None,
+            })
+            .collect();
+        assert_eq!(tokens, tokenize(source).unwrap());
+    }
+
+    #[test]
+    fn test_location() {
+        let locations = |source: &str| -> Vec<(usize, usize, usize, usize)> {
+            tokenize(source)
+                .unwrap()
+                .iter()
+                .map(|t| {
+                    (
+                        t.location.start,
+                        t.location.end,
+                        t.location.line,
+                        t.location.column,
+                    )
+                })
+                .collect()
+        };
+
+        // 字节偏移值、行号以及列号
+        assert_eq!(
+            locations("let a = 10\n  a + 1"),
+            vec![
+                (0, 3, 1, 1),    // let
+                (4, 5, 1, 5),    // a
+                (6, 7, 1, 7),    // =
+                (8, 10, 1, 9),   // 10
+                (10, 11, 1, 11), // \n
+                (13, 14, 2, 3),  // a
+                (15, 16, 2, 5),  // +
+                (17, 18, 2, 7),  // 1
+            ]
+        );
+
+        // 多字节字符：偏移值按照字节计算，列号按照字符计算
+        assert_eq!(locations("\"中文\" a"), vec![(0, 8, 1, 1), (9, 10, 1, 6)]);
+
+        // `\r\n` 只换一行，分号不换行，多行的字面量
+        assert_eq!(
+            locations("a\r\nb;c\r\"x\ny\" d"),
+            vec![
+                (0, 1, 1, 1),
+                (1, 3, 1, 2),
+                (3, 4, 2, 1),
+                (4, 5, 2, 2),
+                (5, 6, 2, 3),
+                (6, 7, 2, 4),
+                (7, 12, 3, 1),
+                (13, 14, 4, 4),
             ]
         );
+
+        // 源文件 id
+        let options = LexerOptions {
+            file_id: 7,
+            ..LexerOptions::default()
+        };
+        let (tokens, _) = tokenize_with_options("a", &options).unwrap();
+        assert_eq!(tokens[0].location.file_id, 7);
+
+        // 琐碎内容的位置
+        let items = tokenize_with_trivia("a  // b\n").unwrap();
+        let trivia_locations: Vec<(usize, usize)> = items
+            .iter()
+            .filter_map(|item| match item {
+                TokenOrTrivia::Trivia(t) => Some((t.location.start, t.location.end)),
+                TokenOrTrivia::Token(_) => None,
+            })
+            .collect();
+        assert_eq!(trivia_locations, vec![(1, 3), (3, 7)]);
+
+        // 错误的位置
+        let e1 = tokenize("a\n  \"foo").unwrap_err();
+        let l1 = e1.location().unwrap();
+        assert_eq!((l1.start, l1.end, l1.line, l1.column), (4, 8, 2, 3));
+    }
+
+    #[test]
+    fn test_unterminated_literal_error() {
+        let kinds = [
+            ("/* foo", LiteralKind::BlockComment),
+            ("'a", LiteralKind::Char),
+            ("\"foo", LiteralKind::String),
+            ("`foo", LiteralKind::TemplateString),
+            ("#[test", LiteralKind::Attribute),
+        ];
+
+        for (source, expected) in kinds {
+            assert!(matches!(
+                tokenize(source),
+                Err(Error::UnterminatedLiteral { kind, .. }) if kind == expected
+            ));
+        }
+
+        assert_eq!(
+            tokenize("\"foo").unwrap_err().to_string(),
+            "unterminated string literal"
+        );
+    }
+
+    #[test]
+    fn test_invalid_number_error() {
+        let e1 = tokenize("99999999999999999999").unwrap_err();
+        assert!(matches!(e1, Error::InvalidNumber { .. }));
+        assert_eq!(
+            e1.to_string(),
+            "invalid integer number \"99999999999999999999\""
+        );
+        assert!(e1.source().is_some());
+
+        let e2 = tokenize("1.2.3").unwrap_err();
+        assert!(matches!(e2, Error::InvalidNumber { .. }));
+        assert!(e2.source().is_none());
+    }
+
+    #[test]
+    fn test_numeric_separator() {
+        let tokens1 = tokenize("1_000 3.141_592 6.626e-3_4 1_0i").unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens1),
+            vec![
+                "1000",
+                "3.141592",
+                "0.0000000000000000000000000000000006626",
+                "10i"
+            ]
+        );
+
+        assert_eq!(
+            tokenize("1__2").unwrap_err().to_string(),
+            "invalid numeric separator in \"1__2\", consecutive underscores are not allowed"
+        );
+        for source in [
+            "1_", "1_.5", "1._5", "1_e5", "1e_5", "1e-_5", "1.5_e2", "1_i",
+        ] {
+            let e1 = tokenize(source).unwrap_err();
+            assert!(matches!(e1, Error::InvalidNumber { .. }));
+            assert!(e1
+                .to_string()
+                .ends_with("underscores must be between digits"));
+        }
+    }
+
+    // 对 old 应用编辑之后增量分析，检查结果跟完整分析的结果相同，返回新的源代码
+    fn check_relex(old: &str, start: usize, end: usize, replacement: &str) -> String {
+        let text = format!("{}{}{}", &old[..start], replacement, &old[end..]);
+        let edit = TextEdit {
+            start,
+            end,
+            text: replacement.to_string(),
+        };
+
+        let previous = tokenize(old).unwrap();
+        match (relex(&text, &previous, &edit), tokenize(&text)) {
+            (Ok(t1), Ok(t2)) => assert_eq!(t1, t2, "{:?} -> {:?}", old, text),
+            (r1, r2) => assert_eq!(format!("{:?}", r1), format!("{:?}", r2)),
+        }
+        text
+    }
+
+    #[test]
+    fn test_relex() {
+        // 修改标识符、插入以及删除
+        check_relex("let abc = 1 + 2\nlet d = abc", 4, 7, "x");
+        check_relex("let a = 1\nlet b = 2", 9, 9, "\n\nlet c = 3");
+        check_relex("let a = 1\nlet b = 2", 0, 10, "");
+        check_relex("", 0, 0, "a + b");
+        check_relex("a + b", 0, 5, "");
+
+        // 编辑位于第一个 Token 之前、最后一个 Token 之后以及注释里
+        check_relex("  a + b", 0, 0, "c ");
+        check_relex("a + b  ", 7, 7, "\nc");
+        check_relex("a /* x */ + b", 5, 6, "y");
+
+        // 编辑涉及第一个 Token，并且第一个 Token 之前是注释
+        check_relex("// c\na + b", 4, 4, "a");
+        check_relex("// c\na + b", 4, 5, "");
+        check_relex("/* x */a b", 7, 8, "/");
+        check_relex("let a = 1", 0, 3, "const");
+
+        // 编辑改变了前面的 Token
+        check_relex("a :foo b", 6, 6, ":");
+        check_relex("a = 1", 5, 5, ".5");
+        check_relex("a = b", 4, 4, "=");
+
+        // 编辑改变了后面所有的 Token
+        check_relex("a = 1\nb = 2\nc = 3", 4, 4, "\"");
+        check_relex("a = 1\nb = 2 */\nc = 3", 4, 4, "/*");
+        check_relex("a = \"x\"\nb = 2", 4, 7, "");
+    }
+
+    #[test]
+    fn test_relex_location() {
+        let old = "let a = 1\nlet b = a + 2";
+        let text = check_relex(old, 4, 5, "first\n");
+        let tokens = relex(
+            &text,
+            &tokenize(old).unwrap(),
+            &TextEdit {
+                start: 4,
+                end: 5,
+                text: "first\n".to_string(),
+            },
+        )
+        .unwrap();
+
+        // 编辑之后的 Token 的行号加一，原来跟编辑位于同一行的 Token 的列号也随之改变
+        let assign = &tokens[3];
+        assert_eq!(assign.token, Token::Assign);
+        assert_eq!(
+            assign.location,
+            Location {
+                file_id: 0,
+                start: 11,
+                end: 12,
+                line: 2,
+                column: 2,
+            }
+        );
+        let last = tokens.last().unwrap();
+        assert_eq!(last.token, Token::Integer(2));
+        assert_eq!((last.location.line, last.location.column), (3, 13));
+    }
+
+    #[test]
+    fn test_relex_with_options() {
+        let options = LexerOptions {
+            file_id: 3,
+            ..LexerOptions::default()
+        };
+        let (previous, _) = tokenize_with_options("let a = 1\nlet b = 2", &options).unwrap();
+        let edit = TextEdit::between("let a = 1\nlet b = 2", "let a = 10\nlet b = 2");
+        let (tokens, _) =
+            relex_with_options("let a = 10\nlet b = 2", &previous, &edit, &options).unwrap();
+        assert!(tokens.iter().all(|t| t.location.file_id == 3));
+        assert_eq!(
+            tokens,
+            tokenize_with_options("let a = 10\nlet b = 2", &options)
+                .unwrap()
+                .0
+        );
+    }
+
+    #[test]
+    fn test_text_edit_between() {
+        let edit = |old: &str, new: &str| {
+            let e = TextEdit::between(old, new);
+            (e.start, e.end, e.text)
+        };
+
+        assert_eq!(edit("let a = 1", "let a = 12"), (9, 9, "2".to_string()));
+        assert_eq!(edit("let a = 1", "let abc = 1"), (5, 5, "bc".to_string()));
+        assert_eq!(edit("a + b", "a - b"), (2, 3, "-".to_string()));
+        assert_eq!(edit("aaa", "aa"), (2, 3, "".to_string()));
+        assert_eq!(edit("abc", "abc"), (3, 3, "".to_string()));
+        assert_eq!(edit("", "abc"), (0, 0, "abc".to_string()));
+
+        // 多字节的字符
+        assert_eq!(edit("\"中文\"", "\"中国\""), (4, 7, "国".to_string()));
+    }
+
+    // 随机编辑示例程序，检查增量分析的结果跟完整分析的结果相同
+    #[test]
+    fn test_relex_random_edits() {
+        const SNIPPETS: [&str; 16] = [
+            "", "a", "1", ".5", " ", "\n", "\r", "\"", "'", "`", "/*", "*/", "//", ":", "{{", "=",
+        ];
+
+        struct Random(u64);
+
+        impl Random {
+            fn next(&mut self, bound: usize) -> usize {
+                // xorshift64
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                (self.0 % bound as u64) as usize
+            }
+        }
+
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+        let mut text = include_str!("../scripts/01-base-expression.xuan").to_string();
+
+        for _ in 0..500 {
+            let boundaries: Vec<usize> = text
+                .char_indices()
+                .map(|(i, _)| i)
+                .chain([text.len()])
+                .collect();
+            let start = boundaries[random.next(boundaries.len())];
+            let end = (start + random.next(4)).min(text.len());
+            let end = boundaries.iter().copied().find(|b| *b >= end).unwrap();
+            let replacement = SNIPPETS[random.next(SNIPPETS.len())];
+
+            let new_text = check_relex(&text, start, end, replacement);
+
+            // 只在编辑之后的源代码仍然合法时继续编辑，否则撤销编辑
+            if tokenize(&new_text).is_ok() {
+                text = new_text;
+            }
+        }
     }
 }
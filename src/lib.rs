@@ -1,4 +1,4 @@
-/**
+/*
  * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
  *
  * This Source Code Form is subject to the terms of the Mozilla Public
@@ -7,7 +7,54 @@
  */
 
 pub mod error;
+pub mod diagnostic;
+pub mod ice;
 pub mod token;
+pub mod keyword;
 pub mod lexer;
 pub mod ast;
-pub mod parser;
\ No newline at end of file
+pub mod parser;
+pub mod ide;
+pub mod limit;
+pub mod lint;
+pub mod normalize;
+pub mod fold;
+pub mod repl;
+pub mod semantics;
+pub mod resolve;
+pub mod overload;
+pub mod interface;
+pub mod symbols;
+pub mod query;
+pub mod mangle;
+pub mod minimize;
+pub mod optimize;
+pub mod frame;
+pub mod layout;
+pub mod typed;
+pub mod ir;
+pub mod module;
+pub mod compact;
+pub mod rewrite;
+pub mod format;
+pub mod backend;
+pub mod driver;
+pub mod emit;
+pub mod profile;
+pub mod source_map;
+pub mod source_manager;
+
+#[cfg(feature = "serde")]
+pub mod json;
+
+#[cfg(feature = "capi")]
+pub mod capi;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "python")]
+pub mod python;
+
+#[cfg(feature = "codespan")]
+pub mod codespan;
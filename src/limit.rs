@@ -0,0 +1,272 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 资源限制
+//
+// 在处理不受信任的输入时（比如在线演练场、语言服务器、嵌入到其他程序），
+// 为了防止恶意构造的源代码消耗过多的 CPU 时间或者内存，可以限制：
+//
+// - 语法分析器处理的 Token 数量以及创建的节点数量（即 "燃料"）；
+// - 字符串字面量的长度以及模板字符串占位符的嵌套层数（由词法分析器检查）；
+// - 列表、元组、映射表的元素数量。
+//
+// 超出限制时返回 Error::LimitExceeded 错误。
+//
+// 默认没有任何限制。
+
+use std::{cell::Cell, fmt::Display};
+
+use crate::{error::Error, token::Location};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceLimit {
+    Tokens,          // Token 的数量
+    Nodes,           // 语法节点的数量
+    StringLength,    // 字符串字面量的长度（字符数）
+    Elements,        // 列表、元组、映射表的元素数量
+    TemplateNesting, // 模板字符串占位符的嵌套层数
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub max_tokens: Option<usize>,
+    pub max_nodes: Option<usize>,
+    pub max_string_length: Option<usize>,
+    pub max_elements: Option<usize>,
+    pub max_template_nesting: Option<usize>,
+}
+
+impl ResourceLimit {
+    // 是否由词法分析器检查的限制
+    pub fn is_lexical(&self) -> bool {
+        matches!(
+            self,
+            ResourceLimit::StringLength | ResourceLimit::TemplateNesting
+        )
+    }
+}
+
+impl Display for ResourceLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ResourceLimit::Tokens => "tokens",
+            ResourceLimit::Nodes => "syntax nodes",
+            ResourceLimit::StringLength => "characters in a string literal",
+            ResourceLimit::Elements => "elements in a list, tuple or map",
+            ResourceLimit::TemplateNesting => "nested placeholders in a template string",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// 语法分析期间的限制以及已经创建的节点数量
+#[derive(Debug, Clone, Copy)]
+struct State {
+    limits: Limits,
+    used_nodes: usize,
+}
+
+thread_local! {
+    // 为 None 时表示没有限制
+    static STATE: Cell<Option<State>> = const { Cell::new(None) };
+}
+
+// 在限制之内运行 f（即语法分析），运行结束后（包括 panic）恢复原先的设置
+pub(crate) fn with_limits<T>(limits: &Limits, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<State>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            STATE.with(|cell| cell.set(self.0));
+        }
+    }
+
+    let state = State {
+        limits: *limits,
+        used_nodes: 0,
+    };
+    let _restore = Restore(STATE.with(|cell| cell.replace(Some(state))));
+    f()
+}
+
+// 消耗一个节点的燃料
+pub(crate) fn consume_node_fuel() -> Result<(), Error> {
+    STATE.with(|cell| match cell.get() {
+        Some(mut state) => {
+            state.used_nodes += 1;
+            cell.set(Some(state));
+            check_limit(
+                ResourceLimit::Nodes,
+                state.limits.max_nodes,
+                state.used_nodes,
+            )
+        }
+        None => Ok(()),
+    })
+}
+
+// 检查列表、元组、映射表的元素数量
+pub(crate) fn check_element_count(count: usize) -> Result<(), Error> {
+    STATE.with(|cell| match cell.get() {
+        Some(state) => check_limit(ResourceLimit::Elements, state.limits.max_elements, count),
+        None => Ok(()),
+    })
+}
+
+// 检查数量是否超出限制
+pub(crate) fn check_limit(
+    limit: ResourceLimit,
+    max: Option<usize>,
+    value: usize,
+) -> Result<(), Error> {
+    match max {
+        Some(max) if value > max => Err(Error::LimitExceeded {
+            limit,
+            max,
+            location: new_location(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn new_location() -> Location {
+    // todo::
+    // Location 各成员值应该由参数传入
+    Location {
+        file_id: 0,
+        start: 0,
+        end: 0,
+        line: 0,
+        column: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::Error,
+        lexer::{self, LexerOptions},
+        parser,
+    };
+
+    use super::{check_limit, Limits, ResourceLimit};
+
+    fn lex(source: &str, limits: Limits) -> Result<(), Error> {
+        let options = LexerOptions {
+            limits,
+            ..LexerOptions::default()
+        };
+        lexer::tokenize_with_options(source, &options).map(|_| ())
+    }
+
+    fn parse(source: &str, limits: Limits) -> Result<(), Error> {
+        let token_details = lexer::tokenize(source).unwrap();
+        parser::parse_with_limits(&token_details, &limits).map(|_| ())
+    }
+
+    // 恰好达到限制时没有错误，超出限制一个单位时返回错误
+    fn check_boundary(
+        analyze: impl Fn(Limits) -> Result<(), Error>,
+        at_limit: Limits,
+        beyond_limit: Limits,
+    ) -> Error {
+        assert!(analyze(at_limit).is_ok());
+        analyze(beyond_limit).unwrap_err()
+    }
+
+    fn assert_limit_exceeded(error: Error, expected_limit: ResourceLimit, expected_max: usize) {
+        match error {
+            Error::LimitExceeded { limit, max, .. } => {
+                assert_eq!(limit, expected_limit);
+                assert_eq!(max, expected_max);
+            }
+            _ => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_check_limit() {
+        assert!(check_limit(ResourceLimit::Tokens, None, usize::MAX).is_ok());
+        assert!(check_limit(ResourceLimit::Tokens, Some(3), 3).is_ok());
+        assert_limit_exceeded(
+            check_limit(ResourceLimit::Tokens, Some(3), 4).unwrap_err(),
+            ResourceLimit::Tokens,
+            3,
+        );
+    }
+
+    #[test]
+    fn test_tokens_limit() {
+        // `let a = [1, 2, 3]` 有 10 个 Token
+        let limits = |max| Limits {
+            max_tokens: Some(max),
+            ..Limits::default()
+        };
+        let error = check_boundary(|l| parse("let a = [1, 2, 3]", l), limits(10), limits(9));
+        assert_limit_exceeded(error, ResourceLimit::Tokens, 9);
+    }
+
+    #[test]
+    fn test_nodes_limit() {
+        // `1 + 2` 的语法分析消耗 4 个节点的燃料
+        let limits = |max| Limits {
+            max_nodes: Some(max),
+            ..Limits::default()
+        };
+        let error = check_boundary(|l| parse("1 + 2", l), limits(4), limits(3));
+        assert_limit_exceeded(error, ResourceLimit::Nodes, 3);
+        assert_eq!(
+            parse("1 + 2", limits(3)).unwrap_err().to_string(),
+            "exceeded the maximum number of syntax nodes (3)"
+        );
+    }
+
+    #[test]
+    fn test_string_length_limit() {
+        let limits = |max| Limits {
+            max_string_length: Some(max),
+            ..Limits::default()
+        };
+
+        // 按照字符计算长度
+        for source in ["\"你好，世界\"", "`你好，世界`"] {
+            let error = check_boundary(|l| lex(source, l), limits(5), limits(4));
+            assert!(error.is_lexer_error());
+            assert_limit_exceeded(error, ResourceLimit::StringLength, 4);
+        }
+    }
+
+    #[test]
+    fn test_elements_limit() {
+        let limits = |max| Limits {
+            max_elements: Some(max),
+            ..Limits::default()
+        };
+
+        for source in ["[1, 2, 3]", "(1, 2, 3)", "{a: 1, b: 2, c: 3}"] {
+            let error = check_boundary(|l| parse(source, l), limits(3), limits(2));
+            assert_limit_exceeded(error, ResourceLimit::Elements, 2);
+        }
+    }
+
+    #[test]
+    fn test_template_nesting_limit() {
+        let limits = |max| Limits {
+            max_template_nesting: Some(max),
+            ..Limits::default()
+        };
+
+        let error = check_boundary(|l| lex("`a {{ b {{c}} }}`", l), limits(2), limits(1));
+        assert!(error.is_lexer_error());
+        assert_limit_exceeded(error, ResourceLimit::TemplateNesting, 1);
+
+        // 并列的占位符不算嵌套
+        let error = check_boundary(|l| lex("`{{a}} {{b}}`", l), limits(1), limits(0));
+        assert_limit_exceeded(error, ResourceLimit::TemplateNesting, 0);
+    }
+}
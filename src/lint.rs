@@ -0,0 +1,1129 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 代码检查（lint）
+//
+// 检查语法正确、但很可能存在问题的代码，并给出警告以及修改建议。
+//
+// 目前包括的检查项目：
+//
+// - missing_default
+//   `branch` 表达式缺少 `default` 分支，并且各个 `case` 分支的条件无法证明
+//   覆盖了所有情况，在运行时有可能没有任何分支被匹配。
+// - deprecated
+//   使用了标注为 `#[deprecated]` 或者 `#[deprecated("use bar instead")]` 的函数、常量、
+//   结构体或者类型别名，检查项目的 `related_range` 为被弃用的声明的位置。
+//   函数以及常量的使用是表达式里的名称（被参数或者局部变量遮蔽的名称不算作使用），
+//   结构体以及类型别名的使用是数据类型里的名称以及结构体的实例化表达式。
+//   注：目前只检查同一个程序里的声明，被弃用的声明内部的使用（比如递归调用）不需要警告。
+// - cyclomatic_complexity
+//   函数的圈复杂度（cyclomatic complexity）超过了上限（见 `LintOptions`），
+//   圈复杂度为 1 加上函数主体（包括其中的匿名函数）里的判断的数量：
+//   每个 `if`、`for`、`each` 表达式以及 `branch`、`match` 表达式的每个 `case` 分支各算一个。
+// - naming_convention
+//   名称的风格跟约定的不一致（可以通过 `LintOptions` 修改），默认的约定为：
+//   函数、参数以及变量使用 snake_case，结构体、特性、类型别名以及泛型类型使用 PascalCase，
+//   常量使用 SCREAMING_SNAKE_CASE。
+//   注：语句目前没有记录名称本身的位置，所以只有变量（`let`、`for`、`each` 绑定的名称）
+//   的检查项目带有重命名的修改建议（只替换绑定的位置，使用的位置由编辑器的重命名功能处理），
+//   模式里省略了值的键（比如 `{x}`）同时是成员名称，不被检查。
+//
+// 检查项目的级别默认为警告（warn），可以使用语句（函数、空函数、常量、结构体、类型别名
+// 以及 `let` 语句）的属性改变该语句里的检查项目的级别：
+//
+// - `#[allow(name1, name2)]` 忽略
+// - `#[warn(name1, name2)]`  警告
+// - `#[deny(name1, name2)]`  报告为错误
+//
+// 同一个检查项目出现在多个属性里时，以最后一个属性为准。
+
+use std::fmt::Display;
+
+use crate::{
+    ast::{
+        Attribute, BinaryOperator, BranchExpression, DataType, Expression, FunctionDeclaration,
+        Identifier, Literal, Node, Range, Statement,
+    },
+    error::Suggestion,
+    semantics,
+};
+
+pub const MISSING_DEFAULT: &str = "missing_default";
+pub const DEPRECATED: &str = "deprecated";
+pub const CYCLOMATIC_COMPLEXITY: &str = "cyclomatic_complexity";
+pub const NAMING_CONVENTION: &str = "naming_convention";
+
+// 检查项目的选项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintOptions {
+    pub max_complexity: usize, // 函数的圈复杂度的上限
+
+    // 名称的风格
+    pub function_case: NamingCase, // 函数
+    pub variable_case: NamingCase, // 参数以及变量
+    pub type_case: NamingCase,     // 结构体、特性、类型别名以及泛型类型
+    pub const_case: NamingCase,    // 常量
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        LintOptions {
+            max_complexity: 10,
+            function_case: NamingCase::SnakeCase,
+            variable_case: NamingCase::SnakeCase,
+            type_case: NamingCase::PascalCase,
+            const_case: NamingCase::ScreamingSnakeCase,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamingCase {
+    SnakeCase,          // `write_line`
+    CamelCase,          // `writeLine`
+    PascalCase,         // `WriteLine`
+    ScreamingSnakeCase, // `WRITE_LINE`
+}
+
+impl Display for NamingCase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            NamingCase::SnakeCase => "snake_case",
+            NamingCase::CamelCase => "camelCase",
+            NamingCase::PascalCase => "PascalCase",
+            NamingCase::ScreamingSnakeCase => "SCREAMING_SNAKE_CASE",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl NamingCase {
+    // 名称是否符合风格，名称开头的下划线（表示有意不使用的名称）被忽略
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.trim_start_matches('_');
+        let mut chars = name.chars();
+        match self {
+            NamingCase::SnakeCase => name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'),
+            NamingCase::ScreamingSnakeCase => name
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'),
+            NamingCase::CamelCase => {
+                chars.next().is_none_or(|c| c.is_ascii_lowercase())
+                    && chars.all(|c| c.is_ascii_alphanumeric())
+            }
+            NamingCase::PascalCase => {
+                chars.next().is_none_or(|c| c.is_ascii_uppercase())
+                    && chars.all(|c| c.is_ascii_alphanumeric())
+            }
+        }
+    }
+
+    // 把名称转换为这种风格，名称开头的下划线被保留
+    pub fn convert(&self, name: &str) -> String {
+        let trimmed = name.trim_start_matches('_');
+        let prefix = &name[..name.len() - trimmed.len()];
+        let words = split_words(trimmed);
+
+        let capitalize = |word: &str| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        };
+
+        let converted = match self {
+            NamingCase::SnakeCase => words.join("_"),
+            NamingCase::ScreamingSnakeCase => words.join("_").to_ascii_uppercase(),
+            NamingCase::PascalCase => words.iter().map(|w| capitalize(w)).collect(),
+            NamingCase::CamelCase => words
+                .iter()
+                .enumerate()
+                .map(|(index, w)| if index == 0 { w.clone() } else { capitalize(w) })
+                .collect(),
+        };
+
+        format!("{}{}", prefix, converted)
+    }
+}
+
+// 把名称拆分为小写的单词，单词之间以下划线或者大小写的变化分隔，
+// 比如 `parseHTTPRequest2` 拆分为 `parse`、`http`、`request2`。
+fn split_words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = Vec::<String>::new();
+    let mut word = String::new();
+
+    for (index, c) in chars.iter().enumerate() {
+        if *c == '_' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+
+        if c.is_ascii_uppercase() && !word.is_empty() {
+            let previous = chars[index - 1];
+            let next_is_lower = chars.get(index + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if !previous.is_ascii_uppercase() || next_is_lower {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+
+        word.push(c.to_ascii_lowercase());
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub name: &'static str, // 检查项目的名称
+    pub level: LintLevel,
+    pub message: String,
+    pub range: Range,
+    pub suggestion: Option<Suggestion>,
+    pub related_range: Option<Range>, // 相关的位置，比如被弃用的函数的声明
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let level = match self.level {
+            LintLevel::Deny => "error",
+            _ => "warning",
+        };
+        write!(f, "{}[{}]: {}", level, self.name, self.message)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\nhelp: {}", suggestion.message)?;
+        }
+        Ok(())
+    }
+}
+
+// 检查整个程序（或者语句、表达式）
+pub fn check(node: &Node) -> Vec<Lint> {
+    check_with_options(node, &LintOptions::default())
+}
+
+pub fn check_with_options(node: &Node, options: &LintOptions) -> Vec<Lint> {
+    let mut lints = Vec::<Lint>::new();
+
+    match node {
+        Node::Program(program) => {
+            let deprecations: Vec<Deprecation> = program
+                .body
+                .iter()
+                .filter_map(Deprecation::from_statement)
+                .collect();
+
+            for statement in &program.body {
+                check_statement(statement, &deprecations, options, &mut lints);
+            }
+        }
+        Node::Statement(statement) => check_statement(statement, &[], options, &mut lints),
+        Node::Expression(expression) => check_expression(expression, &mut lints),
+    }
+
+    lints
+}
+
+// 标注为 `#[deprecated]` 的声明
+struct Deprecation<'a> {
+    kind: &'static str, // 用于检查项目的信息，比如 "function"
+    name: &'a str,
+    attributes: &'a [Attribute],
+    is_type: bool, // 结构体以及类型别名在数据类型里使用，函数以及常量在表达式里使用
+    range: &'a Range,
+}
+
+impl<'a> Deprecation<'a> {
+    fn from_statement(statement: &'a Statement) -> Option<Self> {
+        let (kind, name, attributes, is_type, range) = match statement {
+            Statement::FunctionDeclaration(f) => {
+                ("function", &f.name, &f.attributes, false, &f.range)
+            }
+            Statement::ConstDeclaration(c) => ("const", &c.name, &c.attributes, false, &c.range),
+            Statement::MemberStructDeclaration(s) => {
+                ("struct", &s.name, &s.attributes, true, &s.range)
+            }
+            Statement::TupleStructDeclaration(s) => {
+                ("struct", &s.name, &s.attributes, true, &s.range)
+            }
+            Statement::AliasStatement(a) => ("type alias", &a.name, &a.attributes, true, &a.range),
+            _ => return None,
+        };
+
+        attributes
+            .iter()
+            .any(|a| a.name == DEPRECATED)
+            .then_some(Deprecation {
+                kind,
+                name,
+                attributes,
+                is_type,
+                range,
+            })
+    }
+}
+
+fn check_statement(
+    statement: &Statement,
+    deprecations: &[Deprecation],
+    options: &LintOptions,
+    lints: &mut Vec<Lint>,
+) {
+    let mut statement_lints = Vec::<Lint>::new();
+    for expression in statement.expressions() {
+        check_expression(expression, &mut statement_lints);
+        check_variable_names(expression, options, &mut statement_lints);
+    }
+
+    check_declaration_names(statement, options, &mut statement_lints);
+
+    check_deprecated_uses(statement, deprecations, &mut statement_lints);
+
+    if let Statement::FunctionDeclaration(f) = statement {
+        check_complexity(f, options.max_complexity, &mut statement_lints);
+    }
+
+    apply_lint_levels(statement_attributes(statement), &mut statement_lints);
+
+    lints.append(&mut statement_lints);
+}
+
+fn statement_attributes(statement: &Statement) -> &[Attribute] {
+    match statement {
+        Statement::FunctionDeclaration(f) => &f.attributes,
+        Statement::EmptyFunctionDeclaration(f) => &f.attributes,
+        Statement::ConstDeclaration(c) => &c.attributes,
+        Statement::MemberStructDeclaration(s) => &s.attributes,
+        Statement::TupleStructDeclaration(s) => &s.attributes,
+        Statement::AliasStatement(a) => &a.attributes,
+        Statement::Expression(Expression::LetExpression(l)) => &l.attributes,
+        _ => &[],
+    }
+}
+
+fn check_deprecated_uses(
+    statement: &Statement,
+    deprecations: &[Deprecation],
+    lints: &mut Vec<Lint>,
+) {
+    // 函数的参数会遮蔽同名的函数以及常量
+    let bound_names: Vec<&str> = match statement {
+        Statement::FunctionDeclaration(f) => f.parameters.iter().map(|p| p.name.as_str()).collect(),
+        _ => vec![],
+    };
+
+    let mut uses: Vec<(String, Range, bool)> = vec![]; // 名称、位置以及是否在数据类型里使用
+    for expression in statement.expressions() {
+        for variable in semantics::free_variables_of(expression) {
+            if !bound_names.contains(&variable.name.as_str()) {
+                uses.push((variable.name, variable.range, false));
+            }
+        }
+    }
+
+    let mut type_names = vec![];
+    collect_statement_type_names(statement, &mut type_names);
+    for identifier in type_names {
+        if identifier.dirs.is_empty() {
+            uses.push((identifier.name.clone(), identifier.range.clone(), true));
+        }
+    }
+    uses.sort_by_key(|(_, range, _)| range.start);
+
+    for (name, range, is_type) in uses {
+        let Some(deprecation) = deprecations
+            .iter()
+            .find(|d| d.name == name && d.is_type == is_type && !range_contains(d.range, &range))
+        else {
+            continue;
+        };
+
+        let message = match deprecation_note(deprecation.attributes) {
+            Some(note) => format!(
+                "{} \"{}\" is deprecated: {}",
+                deprecation.kind, deprecation.name, note
+            ),
+            None => format!(
+                "{} \"{}\" is deprecated",
+                deprecation.kind, deprecation.name
+            ),
+        };
+
+        lints.push(Lint {
+            name: DEPRECATED,
+            level: LintLevel::Warn,
+            message,
+            range,
+            suggestion: None,
+            related_range: Some(deprecation.range.clone()),
+        });
+    }
+}
+
+fn range_contains(outer: &Range, inner: &Range) -> bool {
+    outer.file_id == inner.file_id && outer.start <= inner.start && inner.end <= outer.end
+}
+
+// 收集语句里作为数据类型使用的名称，包括声明的签名、结构体的成员、类型别名，
+// 以及表达式里的 `let` 表达式的类型标注、匿名函数的签名以及结构体的实例化表达式
+fn collect_statement_type_names<'a>(statement: &'a Statement, names: &mut Vec<&'a Identifier>) {
+    match statement {
+        Statement::FunctionDeclaration(f) => {
+            for p in &f.parameters {
+                collect_type_names(&p.data_type, names);
+            }
+            f.return_data_type
+                .iter()
+                .for_each(|d| collect_type_names(d, names));
+        }
+        Statement::EmptyFunctionDeclaration(f) => {
+            for p in &f.parameters {
+                collect_type_names(&p.data_type, names);
+            }
+            f.return_data_type
+                .iter()
+                .for_each(|d| collect_type_names(d, names));
+        }
+        Statement::PatternFunctionDeclaration(f) => {
+            for p in &f.parameters {
+                p.data_type
+                    .iter()
+                    .for_each(|d| collect_type_names(d, names));
+            }
+            f.return_data_type
+                .iter()
+                .for_each(|d| collect_type_names(d, names));
+        }
+        Statement::ConstDeclaration(c) => {
+            c.data_type
+                .iter()
+                .for_each(|d| collect_type_names(d, names));
+        }
+        Statement::MemberStructDeclaration(s) => {
+            for m in &s.members {
+                collect_type_names(&m.data_type, names);
+            }
+        }
+        Statement::TupleStructDeclaration(s) => {
+            for m in &s.members {
+                collect_type_names(m, names);
+            }
+        }
+        Statement::AliasStatement(a) => collect_type_names(&a.data_type, names),
+        _ => {}
+    }
+
+    for expression in statement.expressions() {
+        collect_expression_type_names(expression, names);
+    }
+}
+
+fn collect_expression_type_names<'a>(expression: &'a Expression, names: &mut Vec<&'a Identifier>) {
+    match expression {
+        Expression::LetExpression(l) => {
+            l.data_type
+                .iter()
+                .for_each(|d| collect_type_names(d, names));
+        }
+        Expression::AnonymousFunction(f) => {
+            for p in &f.parameters {
+                p.data_type
+                    .iter()
+                    .for_each(|d| collect_type_names(d, names));
+            }
+            f.return_data_type
+                .iter()
+                .for_each(|d| collect_type_names(d, names));
+        }
+        Expression::ConstructorExpression(c) => names.push(&c.object),
+        Expression::TupleConstructorExpression(c) => names.push(&c.object),
+        _ => {}
+    }
+
+    for child in expression.children() {
+        collect_expression_type_names(child, names);
+    }
+}
+
+fn collect_type_names<'a>(data_type: &'a DataType, names: &mut Vec<&'a Identifier>) {
+    match data_type {
+        DataType::Identifier(i) => {
+            names.push(i);
+            for g in &i.generics {
+                collect_type_names(g, names);
+            }
+        }
+        DataType::Tuple(t) => collect_tuple_type_names(&t.elements, names),
+        DataType::Sign(s) => {
+            for p in &s.parameters {
+                collect_type_names(&p.data_type, names);
+            }
+            s.return_data_type
+                .iter()
+                .for_each(|d| collect_type_names(d, names));
+        }
+    }
+}
+
+// 元组类型的成员以表达式表示
+fn collect_tuple_type_names<'a>(elements: &'a [Expression], names: &mut Vec<&'a Identifier>) {
+    for element in elements {
+        match element {
+            Expression::Identifier(i) => {
+                names.push(i);
+                for g in &i.generics {
+                    collect_type_names(g, names);
+                }
+            }
+            Expression::Tuple(t) => collect_tuple_type_names(&t.elements, names),
+            _ => {}
+        }
+    }
+}
+
+fn check_complexity(function: &FunctionDeclaration, max_complexity: usize, lints: &mut Vec<Lint>) {
+    let complexity = cyclomatic_complexity(function);
+    if complexity > max_complexity {
+        lints.push(Lint {
+            name: CYCLOMATIC_COMPLEXITY,
+            level: LintLevel::Warn,
+            message: format!(
+                "function \"{}\" has a cyclomatic complexity of {}, which exceeds the limit of {}",
+                function.name, complexity, max_complexity
+            ),
+            range: function.range.clone(),
+            suggestion: None,
+            related_range: None,
+        });
+    }
+}
+
+// 函数的圈复杂度
+pub fn cyclomatic_complexity(function: &FunctionDeclaration) -> usize {
+    1 + count_decisions(&function.body)
+}
+
+fn count_decisions(expression: &Expression) -> usize {
+    let count = match expression {
+        Expression::IfExpression(_)
+        | Expression::ForExpression(_)
+        | Expression::EachExpression(_) => 1,
+        Expression::BranchExpression(v) => v.cases.len(),
+        Expression::MatchExpression(v) => v.cases.len(),
+        _ => 0,
+    };
+
+    count
+        + expression
+            .children()
+            .into_iter()
+            .map(count_decisions)
+            .sum::<usize>()
+}
+
+fn check_declaration_names(statement: &Statement, options: &LintOptions, lints: &mut Vec<Lint>) {
+    let mut names = Vec::<(&str, &str, NamingCase, &Range)>::new(); // 种类、名称、风格以及位置
+    let mut generics: (&[DataType], Option<&Range>) = (&[], None);
+
+    match statement {
+        Statement::FunctionDeclaration(f) => {
+            names.push(("function", &f.name, options.function_case, &f.range));
+            for p in &f.parameters {
+                names.push(("parameter", &p.name, options.variable_case, &p.range));
+            }
+            generics = (&f.generics, Some(&f.range));
+        }
+        Statement::EmptyFunctionDeclaration(f) => {
+            names.push(("function", &f.name, options.function_case, &f.range));
+            for p in &f.parameters {
+                names.push(("parameter", &p.name, options.variable_case, &p.range));
+            }
+            generics = (&f.generics, Some(&f.range));
+        }
+        Statement::ConstDeclaration(c) => {
+            names.push(("const", &c.name, options.const_case, &c.range));
+        }
+        Statement::MemberStructDeclaration(s) => {
+            names.push(("struct", &s.name, options.type_case, &s.range));
+            generics = (&s.generics, Some(&s.range));
+        }
+        Statement::TupleStructDeclaration(s) => {
+            names.push(("struct", &s.name, options.type_case, &s.range));
+            generics = (&s.generics, Some(&s.range));
+        }
+        Statement::EmptyStructDeclaration(s) => {
+            names.push(("struct", &s.name, options.type_case, &s.range));
+        }
+        Statement::TraitDeclaration(t) => {
+            names.push(("trait", &t.name, options.type_case, &t.range));
+        }
+        Statement::AliasStatement(a) => {
+            names.push(("type alias", &a.name, options.type_case, &a.range));
+            generics = (&a.generics, Some(&a.range));
+        }
+        _ => {}
+    }
+
+    if let (generics, Some(range)) = generics {
+        for generic in generics {
+            if let DataType::Identifier(i) = generic {
+                names.push(("generic type", &i.name, options.type_case, range));
+            }
+        }
+    }
+
+    for (kind, name, case, range) in names {
+        if !case.matches(name) {
+            lints.push(new_naming_lint(kind, name, case, range, None));
+        }
+    }
+}
+
+fn check_variable_names(expression: &Expression, options: &LintOptions, lints: &mut Vec<Lint>) {
+    let case = options.variable_case;
+
+    match expression {
+        Expression::LetExpression(v) => check_pattern_names(&v.object, case, lints),
+        Expression::ForExpression(v) => check_pattern_names(&v.initializer.object, case, lints),
+        Expression::EachExpression(v) => check_pattern_names(&v.variable, case, lints),
+        Expression::AnonymousFunction(v) => {
+            for p in &v.parameters {
+                if !case.matches(&p.name) {
+                    lints.push(new_naming_lint("parameter", &p.name, case, &p.range, None));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    for child in expression.children() {
+        check_variable_names(child, options, lints);
+    }
+}
+
+fn check_pattern_names(pattern: &Expression, case: NamingCase, lints: &mut Vec<Lint>) {
+    match pattern {
+        Expression::Identifier(i) if !case.matches(&i.name) => {
+            let suggestion = Suggestion {
+                message: format!("rename to \"{}\"", case.convert(&i.name)),
+                range: i.range.clone(),
+                replacement: case.convert(&i.name),
+            };
+            lints.push(new_naming_lint(
+                "variable",
+                &i.name,
+                case,
+                &i.range,
+                Some(suggestion),
+            ));
+        }
+        Expression::Ellipsis(e) => {
+            if let Some(name) = e.name.as_deref().filter(|name| !case.matches(name)) {
+                lints.push(new_naming_lint("variable", name, case, &e.range, None));
+            }
+        }
+        Expression::Tuple(t) => t
+            .elements
+            .iter()
+            .for_each(|e| check_pattern_names(e, case, lints)),
+        Expression::List(l) => l
+            .elements
+            .iter()
+            .for_each(|e| check_pattern_names(e, case, lints)),
+        Expression::Map(m) => m
+            .elements
+            .iter()
+            .filter_map(|entry| entry.value.as_deref())
+            .for_each(|value| check_pattern_names(value, case, lints)),
+        Expression::ConstructorExpression(c) => c
+            .value
+            .elements
+            .iter()
+            .filter_map(|entry| entry.value.as_deref())
+            .for_each(|value| check_pattern_names(value, case, lints)),
+        Expression::FunctionCallExpression(f) => f
+            .arguments
+            .iter()
+            .for_each(|a| check_pattern_names(&a.value, case, lints)),
+        Expression::TupleConstructorExpression(c) => c
+            .value
+            .elements
+            .iter()
+            .for_each(|e| check_pattern_names(e, case, lints)),
+        _ => {}
+    }
+}
+
+fn new_naming_lint(
+    kind: &str,
+    name: &str,
+    case: NamingCase,
+    range: &Range,
+    suggestion: Option<Suggestion>,
+) -> Lint {
+    Lint {
+        name: NAMING_CONVENTION,
+        level: LintLevel::Warn,
+        message: format!(
+            "{} \"{}\" should be in {}, e.g. \"{}\"",
+            kind,
+            name,
+            case,
+            case.convert(name)
+        ),
+        range: range.clone(),
+        suggestion,
+        related_range: None,
+    }
+}
+
+// `#[deprecated("...")]` 里的说明文字
+fn deprecation_note(attributes: &[Attribute]) -> Option<&str> {
+    attributes
+        .iter()
+        .filter(|attribute| attribute.name == DEPRECATED)
+        .flat_map(|attribute| &attribute.arguments)
+        .find_map(|argument| match argument {
+            (name, Some(Expression::Literal(Literal::GeneralString(s)))) if name.is_empty() => {
+                Some(s.value.as_str())
+            }
+            _ => None,
+        })
+}
+
+// 根据属性 `#[allow(...)]`、`#[warn(...)]` 以及 `#[deny(...)]` 调整检查项目的级别，
+// 并移除被忽略的检查项目
+fn apply_lint_levels(attributes: &[Attribute], lints: &mut Vec<Lint>) {
+    for lint in lints.iter_mut() {
+        if let Some(level) = find_lint_level(attributes, lint.name) {
+            lint.level = level;
+        }
+    }
+
+    lints.retain(|lint| lint.level != LintLevel::Allow);
+}
+
+fn find_lint_level(attributes: &[Attribute], name: &str) -> Option<LintLevel> {
+    attributes.iter().rev().find_map(|attribute| {
+        let level = match attribute.name.as_str() {
+            "allow" => LintLevel::Allow,
+            "warn" => LintLevel::Warn,
+            "deny" => LintLevel::Deny,
+            _ => return None,
+        };
+
+        attribute
+            .arguments
+            .iter()
+            .any(|(argument, value)| argument == name && value.is_none())
+            .then_some(level)
+    })
+}
+
+fn check_expression(expression: &Expression, lints: &mut Vec<Lint>) {
+    if let Expression::BranchExpression(branch) = expression {
+        check_missing_default(branch, lints);
+    }
+
+    for child in expression.children() {
+        check_expression(child, lints);
+    }
+}
+
+fn check_missing_default(branch: &BranchExpression, lints: &mut Vec<Lint>) {
+    if branch.default_exp.is_some() || is_exhaustive(branch) {
+        return;
+    }
+
+    // 在结束符号 `}` 之前插入 `default` 分支
+    let insert_position = branch.range.end.saturating_sub(1);
+
+    lints.push(Lint {
+        name: MISSING_DEFAULT,
+        level: LintLevel::Warn,
+        message: "branch expression has no default arm, and its cases may not cover all conditions"
+            .to_string(),
+        range: branch.range.clone(),
+        suggestion: Some(Suggestion {
+            message: "add a `default` arm".to_string(),
+            range: Range {
+                file_id: branch.range.file_id,
+                start: insert_position,
+                end: insert_position,
+            },
+            replacement: "default: \n".to_string(),
+        }),
+        related_range: None,
+    });
+}
+
+// 判断各个 case 分支的条件是否（可以证明）覆盖了所有情况：
+//
+// - 存在条件为 `true` 的分支；
+// - 存在两个条件互补的分支，比如 `a > b` 和 `a <= b`。
+fn is_exhaustive(branch: &BranchExpression) -> bool {
+    let testings: Vec<&Expression> = branch.cases.iter().map(|c| c.testing.as_ref()).collect();
+
+    testings.iter().enumerate().any(|(index, testing)| {
+        matches!(testing, Expression::Literal(Literal::Boolean(b)) if b.value)
+            || testings[index + 1..]
+                .iter()
+                .any(|other| is_complementary(testing, other))
+    })
+}
+
+fn is_complementary(left: &Expression, right: &Expression) -> bool {
+    match (left, right) {
+        (Expression::BinaryExpression(l), Expression::BinaryExpression(r)) => {
+            let same_operands = l.left.to_string() == r.left.to_string()
+                && l.right.to_string() == r.right.to_string();
+            same_operands && negate_operator(l.operator) == Some(r.operator)
+        }
+        _ => false,
+    }
+}
+
+fn negate_operator(operator: BinaryOperator) -> Option<BinaryOperator> {
+    let negated = match operator {
+        BinaryOperator::Equal => BinaryOperator::NotEqual,
+        BinaryOperator::NotEqual => BinaryOperator::Equal,
+        BinaryOperator::GreaterThan => BinaryOperator::LessThanOrEqual,
+        BinaryOperator::LessThanOrEqual => BinaryOperator::GreaterThan,
+        BinaryOperator::LessThan => BinaryOperator::GreaterThanOrEqual,
+        BinaryOperator::GreaterThanOrEqual => BinaryOperator::LessThan,
+        _ => return None,
+    };
+
+    Some(negated)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Node, Statement},
+        lexer, parser,
+    };
+
+    use super::{
+        check, check_with_options, cyclomatic_complexity, Lint, LintLevel, LintOptions, NamingCase,
+        CYCLOMATIC_COMPLEXITY, DEPRECATED, MISSING_DEFAULT, NAMING_CONVENTION,
+    };
+
+    fn check_source(text: &str) -> Vec<Lint> {
+        let token_details = lexer::tokenize(text).unwrap();
+        let node: Node = parser::parse(&token_details).unwrap();
+        check(&node)
+    }
+
+    fn check_deprecated_source(text: &str) -> Vec<Lint> {
+        let mut lints = check_source(text);
+        lints.retain(|l| l.name == DEPRECATED);
+        lints
+    }
+
+    #[test]
+    fn test_missing_default() {
+        let l1 = check_source("branch {\ncase a > 0: 1\ncase a < 0: 2\n}");
+        assert_eq!(l1.len(), 1);
+        assert_eq!(l1[0].name, MISSING_DEFAULT);
+        assert_eq!(
+            l1[0].suggestion.as_ref().unwrap().replacement,
+            "default: \n"
+        );
+
+        // 存在 default 分支
+        assert!(check_source("branch {\ncase a > 0: 1\ndefault: 2\n}").is_empty());
+
+        // 条件互补
+        assert!(check_source("branch {\ncase a > 0: 1\ncase a <= 0: 2\n}").is_empty());
+
+        // 存在条件为 `true` 的分支
+        assert!(check_source("branch {\ncase a > 0: 1\ncase true: 2\n}").is_empty());
+
+        // 嵌套在其他表达式里
+        let l2 = check_source("function f(Int a) = {\nlet b = branch {\ncase a == 1: 1\n}\nb\n}");
+        assert_eq!(l2.len(), 1);
+    }
+
+    #[test]
+    fn test_deprecated() {
+        let l1 = check_source(
+            "#[deprecated(\"use bar instead\")]\nfunction foo(Int a) = foo(a - 1)\n\
+            function bar(Int a) = a\n\
+            function baz(Int a) = foo(a) + bar(a)\n\
+            foo(1)",
+        );
+        assert_eq!(l1.len(), 2);
+        assert_eq!(l1[0].name, DEPRECATED);
+        assert_eq!(
+            l1[0].to_string(),
+            "warning[deprecated]: function \"foo\" is deprecated: use bar instead"
+        );
+        assert!(l1[0].related_range.is_some());
+
+        // 没有说明文字
+        let l2 = check_source("#[deprecated]\nfunction foo() = 1\nfoo()");
+        assert_eq!(
+            l2[0].to_string(),
+            "warning[deprecated]: function \"foo\" is deprecated"
+        );
+
+        // 被参数或者局部变量遮蔽
+        assert!(check_source(
+            "#[deprecated]\nfunction foo() = 1\n\
+            function bar(Int foo) = foo\n\
+            do {\nlet foo = 2\nfoo\n}"
+        )
+        .is_empty());
+
+        // 使用属性忽略
+        assert!(check_source(
+            "#[deprecated]\nfunction foo() = 1\n#[allow(deprecated)]\nfunction bar() = foo()"
+        )
+        .is_empty());
+
+        // 常量
+        let l3 = check_deprecated_source(
+            "#[deprecated(\"use LIMIT instead\")]\nconst MAX = 10\n\
+            function f(Int a) = a + MAX\n\
+            function g(Int MAX) = MAX",
+        );
+        assert_eq!(l3.len(), 1);
+        assert_eq!(
+            l3[0].to_string(),
+            "warning[deprecated]: const \"MAX\" is deprecated: use LIMIT instead"
+        );
+
+        // 结构体：数据类型以及实例化表达式，结构体内部的使用不需要警告
+        let source = "#[deprecated]\nstruct User {Int id\nList<User> friends}\n\
+            function f(User u) type (Int, User) = {\n\
+            let List<User> a = [User {id: 1}]\n\
+            (1, u)\n\
+            }";
+        let l4 = check_deprecated_source(source);
+        let uses: Vec<&str> = l4
+            .iter()
+            .map(|l| &source[l.range.start..l.range.end])
+            .collect();
+        assert_eq!(uses, vec!["User"; 4]);
+        assert_eq!(
+            l4[0].to_string(),
+            "warning[deprecated]: struct \"User\" is deprecated"
+        );
+
+        // 类型别名，同名的函数（值）不受影响
+        let l5 = check_deprecated_source(
+            "#[deprecated]\nalias Text = String\n\
+            function Text() = 1\n\
+            empty function show(Text t) type sign (Text) type Text\n\
+            Text()",
+        );
+        assert_eq!(l5.len(), 3);
+        assert_eq!(
+            l5[0].to_string(),
+            "warning[deprecated]: type alias \"Text\" is deprecated"
+        );
+    }
+
+    #[test]
+    fn test_lint_levels() {
+        let source = "function f(Int a) = branch {\ncase a == 1: 1\n}";
+
+        let l1 = check_source(source);
+        assert_eq!(l1[0].level, LintLevel::Warn);
+        assert!(l1[0].to_string().starts_with("warning[missing_default]: "));
+
+        assert!(check_source(&format!("#[allow(missing_default)]\n{}", source)).is_empty());
+        assert!(check_source(&format!("#[allow(unused_variable)]\n{}", source)).len() == 1);
+
+        let l2 = check_source(&format!("#[deny(missing_default)]\n{}", source));
+        assert_eq!(l2[0].level, LintLevel::Deny);
+        assert!(l2[0].to_string().starts_with("error[missing_default]: "));
+
+        // 以最后一个属性为准
+        let l3 = check_source(&format!(
+            "#[allow(missing_default)]\n#[warn(missing_default)]\n{}",
+            source
+        ));
+        assert_eq!(l3[0].level, LintLevel::Warn);
+
+        // 常量、结构体、类型别名、空函数以及 let 语句的属性
+        let sources = [
+            "const Int maxSize = 1",
+            "struct user_info {Int id}",
+            "struct user_pair(Int, Int)",
+            "alias user_name = String",
+            "empty function writeLine(Int a)",
+            "let lineNo = 1",
+        ];
+        for source in sources {
+            let l4 = check_source(source);
+            assert_eq!(l4.len(), 1, "{}", source);
+            assert_eq!(l4[0].level, LintLevel::Warn);
+
+            assert!(
+                check_source(&format!("#[allow(naming_convention)]\n{}", source)).is_empty(),
+                "{}",
+                source
+            );
+
+            let l5 = check_source(&format!("#[deny(naming_convention)]\n{}", source));
+            assert_eq!(l5.len(), 1, "{}", source);
+            assert_eq!(l5[0].level, LintLevel::Deny);
+        }
+
+        // 属性只影响被标注的语句
+        let l6 = check_source("#[allow(naming_convention)]\nlet lineNo = 1\nlet pageNo = 2");
+        assert_eq!(l6.len(), 1);
+        assert_eq!(
+            l6[0].message,
+            "variable \"pageNo\" should be in snake_case, e.g. \"page_no\""
+        );
+    }
+
+    #[test]
+    fn test_cyclomatic_complexity() {
+        let source = "function f(Int a) = {\n\
+            let b = if a > 0 then 1 else 2\n\
+            let c = match a {\ncase 1: 1\ncase 2: 2\ndefault: 3\n}\n\
+            each i in [1, 2] writeLine(i)\n\
+            b + c\n\
+            }";
+
+        let token_details = lexer::tokenize(source).unwrap();
+        let node = parser::parse(&token_details).unwrap();
+        let complexity = match &node {
+            Node::Program(program) => match &program.body[0] {
+                Statement::FunctionDeclaration(f) => cyclomatic_complexity(f),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        assert_eq!(complexity, 5);
+
+        // 默认的上限
+        assert!(check(&node).is_empty());
+
+        let options = LintOptions {
+            max_complexity: 4,
+            ..LintOptions::default()
+        };
+        let l1 = check_with_options(&node, &options);
+        assert_eq!(l1.len(), 1);
+        assert_eq!(l1[0].name, CYCLOMATIC_COMPLEXITY);
+        assert_eq!(
+            l1[0].to_string(),
+            "warning[cyclomatic_complexity]: function \"f\" has a cyclomatic complexity of 5, \
+            which exceeds the limit of 4"
+        );
+
+        assert!(check_with_options(
+            &node,
+            &LintOptions {
+                max_complexity: 5,
+                ..LintOptions::default()
+            }
+        )
+        .is_empty());
+
+        // 使用属性忽略
+        let allowed = format!("#[allow(cyclomatic_complexity)]\n{}", source);
+        let token_details = lexer::tokenize(&allowed).unwrap();
+        let node = parser::parse(&token_details).unwrap();
+        assert!(check_with_options(&node, &options).is_empty());
+    }
+
+    #[test]
+    fn test_naming_case() {
+        assert_eq!(
+            NamingCase::SnakeCase.convert("parseHTTPRequest2"),
+            "parse_http_request2"
+        );
+        assert_eq!(NamingCase::CamelCase.convert("write_line"), "writeLine");
+        assert_eq!(NamingCase::PascalCase.convert("user_info"), "UserInfo");
+        assert_eq!(
+            NamingCase::ScreamingSnakeCase.convert("maxValue"),
+            "MAX_VALUE"
+        );
+        assert_eq!(NamingCase::SnakeCase.convert("_FooBar"), "_foo_bar");
+
+        assert!(NamingCase::SnakeCase.matches("_unused"));
+        assert!(NamingCase::PascalCase.matches("Utf8Decoder"));
+        assert!(!NamingCase::PascalCase.matches("Utf8_Decoder"));
+        assert!(NamingCase::CamelCase.matches("writeLine"));
+        assert!(!NamingCase::ScreamingSnakeCase.matches("Max"));
+    }
+
+    #[test]
+    fn test_naming_convention() {
+        let l1 = check_source(
+            "function writeLine<t>(Int lineNo) = do {\n\
+            let (firstName, _) = (1, 2)\n\
+            each Item in [1] Item\n\
+            }",
+        );
+        let messages: Vec<String> = l1.iter().map(|l| l.message.clone()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "variable \"firstName\" should be in snake_case, e.g. \"first_name\"",
+                "variable \"Item\" should be in snake_case, e.g. \"item\"",
+                "function \"writeLine\" should be in snake_case, e.g. \"write_line\"",
+                "parameter \"lineNo\" should be in snake_case, e.g. \"line_no\"",
+                "generic type \"t\" should be in PascalCase, e.g. \"T\"",
+            ]
+        );
+        assert!(l1.iter().all(|l| l.name == NAMING_CONVENTION));
+        assert_eq!(l1[0].suggestion.as_ref().unwrap().replacement, "first_name");
+        assert!(l1[2].suggestion.is_none());
+
+        // 符合约定的名称，以及模式里省略了值的键
+        assert!(check_source(
+            "function write_line<T>(T value, Int _unused) = do {\n\
+            let {firstName} = {firstName: 1}\n\
+            firstName\n\
+            }"
+        )
+        .is_empty());
+
+        // 修改约定
+        let token_details = lexer::tokenize("function writeLine(Int lineNo) = lineNo").unwrap();
+        let node = parser::parse(&token_details).unwrap();
+        let options = LintOptions {
+            function_case: NamingCase::CamelCase,
+            variable_case: NamingCase::CamelCase,
+            ..LintOptions::default()
+        };
+        assert!(check_with_options(&node, &options).is_empty());
+
+        let l2 = check_source("const Int maxSize = 1");
+        assert_eq!(
+            l2[0].message,
+            "const \"maxSize\" should be in SCREAMING_SNAKE_CASE, e.g. \"MAX_SIZE\""
+        );
+
+        // 使用属性忽略
+        assert!(check_source("#[allow(naming_convention)]\nfunction writeLine() = 1").is_empty());
+    }
+}
@@ -0,0 +1,550 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 名称修饰（name mangling）
+//
+// 把函数、方法、泛型函数的实例以及命名空间里的名称转换为唯一的、只包含
+// ASCII 字母、数字以及下划线的符号名称，所有后端都应该使用本模块生成符号名称，
+// 以便符号名称在每次编译时都保持一致，并且能够跟运行时库链接。
+//
+// 修饰后的名称只由符号本身决定，不包含哈希值、编号等跟编译过程有关的信息。
+//
+// 格式：
+//
+// ```text
+// symbol     := "_X" namespace? ("M" type)? ident generics? signature?
+// namespace  := "N" ident+ "E"
+// generics   := "I" type+ "E"
+// signature  := "P" type* "E"                      // 参数的类型，用于区分重载的函数
+// type       := "T" namespace? ident generics?     // 具名类型，比如 `Int`、`List<T>`
+//             | "U" type* "E"                      // 元组类型
+//             | "F" type* ("R" type)? "E"          // 函数类型（sign）
+// ident      := 名称的长度（十进制） 名称
+// ```
+//
+// 示例：
+//
+// - `add` => `_X3add`
+// - `math::add` => `_XN4mathE3add`
+// - `show<Int>` => `_X4showIT3IntE`
+// - `List<Int>` 的方法 `push` => `_XMT4ListIT3IntE4push`
+// - 重载的函数 `add(Int, Int)` => `_X3addPT3IntT3IntE`
+//
+// 标识符只能由 ASCII 字母、数字以及下划线组成，并且不能以数字开始（见 `lexer` 模块），
+// 所以名称的长度与名称之间以及各个标记之间不会产生歧义。
+
+use std::fmt::Display;
+
+use crate::ast::{DataType, Expression, Identifier, Sign};
+
+const PREFIX: &str = "_X";
+
+// 需要修饰的符号
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub dirs: Vec<String>,          // 命名空间
+    pub owner: Option<MangledType>, // 方法所属的类型（即 `impl` 的对象）
+    pub name: String,
+    pub generics: Vec<MangledType>,           // 泛型实例的具体类型
+    pub parameters: Option<Vec<MangledType>>, // 参数的类型，None 表示不包括签名
+}
+
+// 修饰名称里的数据类型
+#[derive(Debug, Clone, PartialEq)]
+pub enum MangledType {
+    Named {
+        dirs: Vec<String>,
+        name: String,
+        generics: Vec<MangledType>,
+    },
+    Tuple(Vec<MangledType>),
+    Function {
+        parameters: Vec<MangledType>,
+        return_type: Option<Box<MangledType>>,
+    },
+}
+
+impl Symbol {
+    pub fn new(dirs: &[&str], name: &str) -> Self {
+        Symbol {
+            dirs: dirs.iter().map(|d| d.to_string()).collect(),
+            owner: None,
+            name: name.to_string(),
+            generics: vec![],
+            parameters: None,
+        }
+    }
+}
+
+impl MangledType {
+    // 转换 AST 里的数据类型，泛型实例的具体类型不应该包含
+    // 函数签名的参数名称、`which` 从属表达式等信息，这些信息会被忽略。
+    pub fn from_data_type(data_type: &DataType) -> Result<MangledType, String> {
+        match data_type {
+            DataType::Identifier(i) => from_identifier(i),
+            DataType::Tuple(t) => from_tuple_elements(&t.elements),
+            DataType::Sign(s) => from_sign(s),
+        }
+    }
+}
+
+fn from_identifier(identifier: &Identifier) -> Result<MangledType, String> {
+    Ok(MangledType::Named {
+        dirs: identifier.dirs.clone(),
+        name: identifier.name.clone(),
+        generics: identifier
+            .generics
+            .iter()
+            .map(MangledType::from_data_type)
+            .collect::<Result<Vec<MangledType>, String>>()?,
+    })
+}
+
+fn from_tuple_elements(elements: &[Expression]) -> Result<MangledType, String> {
+    let members = elements
+        .iter()
+        .map(|e| match e {
+            Expression::Identifier(i) => from_identifier(i),
+            Expression::Tuple(t) => from_tuple_elements(&t.elements),
+            Expression::Sign(s) => from_sign(s),
+            _ => Err(format!("\"{}\" is not a data type", e)),
+        })
+        .collect::<Result<Vec<MangledType>, String>>()?;
+    Ok(MangledType::Tuple(members))
+}
+
+fn from_sign(sign: &Sign) -> Result<MangledType, String> {
+    if !sign.generics.is_empty() {
+        return Err(format!(
+            "generic function type \"{}\" cannot be mangled",
+            sign
+        ));
+    }
+
+    Ok(MangledType::Function {
+        parameters: sign
+            .parameters
+            .iter()
+            .map(|p| MangledType::from_data_type(&p.data_type))
+            .collect::<Result<Vec<MangledType>, String>>()?,
+        return_type: match &sign.return_data_type {
+            Some(d) => Some(Box::new(MangledType::from_data_type(d)?)),
+            None => None,
+        },
+    })
+}
+
+pub fn mangle(symbol: &Symbol) -> String {
+    let mut text = PREFIX.to_string();
+    write_namespace(&mut text, &symbol.dirs);
+    if let Some(owner) = &symbol.owner {
+        text.push('M');
+        write_type(&mut text, owner);
+    }
+    write_ident(&mut text, &symbol.name);
+    write_generics(&mut text, &symbol.generics);
+    if let Some(parameters) = &symbol.parameters {
+        text.push('P');
+        for parameter in parameters {
+            write_type(&mut text, parameter);
+        }
+        text.push('E');
+    }
+    text
+}
+
+fn write_ident(text: &mut String, name: &str) {
+    text.push_str(&name.len().to_string());
+    text.push_str(name);
+}
+
+fn write_namespace(text: &mut String, dirs: &[String]) {
+    if !dirs.is_empty() {
+        text.push('N');
+        for dir in dirs {
+            write_ident(text, dir);
+        }
+        text.push('E');
+    }
+}
+
+fn write_generics(text: &mut String, generics: &[MangledType]) {
+    if !generics.is_empty() {
+        text.push('I');
+        for generic in generics {
+            write_type(text, generic);
+        }
+        text.push('E');
+    }
+}
+
+fn write_type(text: &mut String, data_type: &MangledType) {
+    match data_type {
+        MangledType::Named {
+            dirs,
+            name,
+            generics,
+        } => {
+            text.push('T');
+            write_namespace(text, dirs);
+            write_ident(text, name);
+            write_generics(text, generics);
+        }
+        MangledType::Tuple(members) => {
+            text.push('U');
+            for member in members {
+                write_type(text, member);
+            }
+            text.push('E');
+        }
+        MangledType::Function {
+            parameters,
+            return_type,
+        } => {
+            text.push('F');
+            for parameter in parameters {
+                write_type(text, parameter);
+            }
+            if let Some(r) = return_type {
+                text.push('R');
+                write_type(text, r);
+            }
+            text.push('E');
+        }
+    }
+}
+
+// 还原修饰后的名称，当 `text` 不是有效的修饰名称时返回 None
+pub fn demangle(text: &str) -> Option<Symbol> {
+    let mut reader = Reader {
+        chars: text.strip_prefix(PREFIX)?.as_bytes(),
+        index: 0,
+    };
+
+    let dirs = reader.read_namespace()?;
+    let owner = if reader.consume(b'M') {
+        Some(reader.read_type()?)
+    } else {
+        None
+    };
+    let name = reader.read_ident()?;
+    let generics = reader.read_generics()?;
+    let parameters = if reader.consume(b'P') {
+        let mut parameters = vec![];
+        while !reader.consume(b'E') {
+            parameters.push(reader.read_type()?);
+        }
+        Some(parameters)
+    } else {
+        None
+    };
+
+    if reader.index != reader.chars.len() {
+        return None;
+    }
+
+    Some(Symbol {
+        dirs,
+        owner,
+        name,
+        generics,
+        parameters,
+    })
+}
+
+struct Reader<'a> {
+    chars: &'a [u8],
+    index: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn consume(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn read_ident(&mut self) -> Option<String> {
+        let start = self.index;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.index += 1;
+        }
+
+        // 长度不能为 0，也不能以 0 开始
+        let digits = std::str::from_utf8(&self.chars[start..self.index]).ok()?;
+        if digits.is_empty() || digits.starts_with('0') {
+            return None;
+        }
+
+        let length: usize = digits.parse().ok()?;
+        let end = self.index.checked_add(length)?;
+        let name = std::str::from_utf8(self.chars.get(self.index..end)?).ok()?;
+
+        let mut chars = name.chars();
+        if !matches!(chars.next(), Some('a'..='z' | 'A'..='Z' | '_'))
+            || !chars.all(|c| matches!(c, 'a'..='z' | 'A'..='Z' | '_' | '0'..='9'))
+        {
+            return None;
+        }
+
+        self.index = end;
+        Some(name.to_string())
+    }
+
+    fn read_namespace(&mut self) -> Option<Vec<String>> {
+        let mut dirs = vec![];
+        if self.consume(b'N') {
+            while !self.consume(b'E') {
+                dirs.push(self.read_ident()?);
+            }
+            if dirs.is_empty() {
+                return None;
+            }
+        }
+        Some(dirs)
+    }
+
+    fn read_generics(&mut self) -> Option<Vec<MangledType>> {
+        let mut generics = vec![];
+        if self.consume(b'I') {
+            while !self.consume(b'E') {
+                generics.push(self.read_type()?);
+            }
+            if generics.is_empty() {
+                return None;
+            }
+        }
+        Some(generics)
+    }
+
+    fn read_type(&mut self) -> Option<MangledType> {
+        if self.consume(b'T') {
+            let dirs = self.read_namespace()?;
+            let name = self.read_ident()?;
+            let generics = self.read_generics()?;
+            Some(MangledType::Named {
+                dirs,
+                name,
+                generics,
+            })
+        } else if self.consume(b'U') {
+            let mut members = vec![];
+            while !self.consume(b'E') {
+                members.push(self.read_type()?);
+            }
+            Some(MangledType::Tuple(members))
+        } else if self.consume(b'F') {
+            let mut parameters = vec![];
+            let mut return_type = None;
+            loop {
+                if self.consume(b'E') {
+                    break;
+                } else if self.consume(b'R') {
+                    return_type = Some(Box::new(self.read_type()?));
+                    if !self.consume(b'E') {
+                        return None;
+                    }
+                    break;
+                } else {
+                    parameters.push(self.read_type()?);
+                }
+            }
+            Some(MangledType::Function {
+                parameters,
+                return_type,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for dir in &self.dirs {
+            write!(f, "{}::", dir)?;
+        }
+        if let Some(owner) = &self.owner {
+            write!(f, "{}::", owner)?;
+        }
+        write!(f, "{}{}", self.name, format_generics(&self.generics))?;
+        if let Some(parameters) = &self.parameters {
+            write!(f, "({})", join_types(parameters))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for MangledType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MangledType::Named {
+                dirs,
+                name,
+                generics,
+            } => {
+                for dir in dirs {
+                    write!(f, "{}::", dir)?;
+                }
+                write!(f, "{}{}", name, format_generics(generics))
+            }
+            MangledType::Tuple(members) => write!(f, "({})", join_types(members)),
+            MangledType::Function {
+                parameters,
+                return_type,
+            } => {
+                write!(f, "sign ({})", join_types(parameters))?;
+                if let Some(r) = return_type {
+                    write!(f, " type {}", r)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn join_types(types: &[MangledType]) -> String {
+    types
+        .iter()
+        .map(|t| t.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn format_generics(generics: &[MangledType]) -> String {
+    if generics.is_empty() {
+        "".to_string()
+    } else {
+        format!("<{}>", join_types(generics))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{demangle, mangle, MangledType, Symbol};
+
+    fn new_type(name: &str, generics: Vec<MangledType>) -> MangledType {
+        MangledType::Named {
+            dirs: vec![],
+            name: name.to_string(),
+            generics,
+        }
+    }
+
+    fn assert_round_trip(symbol: Symbol, mangled: &str, display: &str) {
+        assert_eq!(mangle(&symbol), mangled);
+        assert_eq!(symbol.to_string(), display);
+        assert_eq!(demangle(mangled), Some(symbol));
+    }
+
+    #[test]
+    fn test_mangle() {
+        assert_round_trip(Symbol::new(&[], "add"), "_X3add", "add");
+        assert_round_trip(
+            Symbol::new(&["std", "math"], "add"),
+            "_XN3std4mathE3add",
+            "std::math::add",
+        );
+
+        // 泛型函数的实例
+        let mut s1 = Symbol::new(&[], "show");
+        s1.generics = vec![
+            new_type("Int", vec![]),
+            MangledType::Tuple(vec![new_type("String", vec![]), MangledType::Tuple(vec![])]),
+        ];
+        assert_round_trip(s1, "_X4showIT3IntUT6StringUEEE", "show<Int, (String, ())>");
+
+        // 方法
+        let mut s2 = Symbol::new(&["collection"], "push");
+        s2.owner = Some(new_type("List", vec![new_type("Int", vec![])]));
+        assert_round_trip(
+            s2,
+            "_XN10collectionEMT4ListIT3IntE4push",
+            "collection::List<Int>::push",
+        );
+
+        // 函数类型
+        let mut s3 = Symbol::new(&[], "map");
+        s3.generics = vec![
+            MangledType::Function {
+                parameters: vec![new_type("Int", vec![])],
+                return_type: Some(Box::new(new_type("Int", vec![]))),
+            },
+            MangledType::Function {
+                parameters: vec![],
+                return_type: None,
+            },
+        ];
+        assert_round_trip(
+            s3,
+            "_X3mapIFT3IntRT3IntEFEE",
+            "map<sign (Int) type Int, sign ()>",
+        );
+
+        // 重载的函数的签名
+        let mut s4 = Symbol::new(&[], "add");
+        s4.parameters = Some(vec![new_type("Int", vec![]), new_type("Int", vec![])]);
+        assert_round_trip(s4, "_X3addPT3IntT3IntE", "add(Int, Int)");
+
+        let mut s5 = Symbol::new(&[], "now");
+        s5.owner = Some(new_type("Clock", vec![]));
+        s5.parameters = Some(vec![]);
+        assert_round_trip(s5, "_XMT5Clock3nowPE", "Clock::now()");
+    }
+
+    #[test]
+    fn test_demangle_invalid() {
+        for text in [
+            "add",
+            "_X",
+            "_X0",
+            "_X03add",
+            "_X4add",
+            "_X3addE",
+            "_X31a2",
+            "_XNE3add",
+            "_X3addIE",
+            "_X3addIT3Int",
+            "_XM3add",
+            "_X3addPT3Int",
+            "_X3addP3IntE",
+            "_X99999999999999999999999a",
+        ] {
+            assert_eq!(demangle(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_from_data_type() {
+        use crate::{ast::Node, ast::Statement, lexer, parser};
+
+        let token_details =
+            lexer::tokenize("function f(Map<String, (Int, sign (Int) type Int)> a) = a").unwrap();
+        let data_type = match parser::parse(&token_details).unwrap() {
+            Node::Program(program) => match &program.body[0] {
+                Statement::FunctionDeclaration(f) => f.parameters[0].data_type.clone(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        let t1 = MangledType::from_data_type(&data_type).unwrap();
+        assert_eq!(t1.to_string(), "Map<String, (Int, sign (Int) type Int)>");
+
+        let mut s1 = Symbol::new(&[], "f");
+        s1.generics = vec![t1];
+        assert_eq!(mangle(&s1), "_X1fIT3MapIT6StringUT3IntFT3IntRT3IntEEEE");
+    }
+}
@@ -0,0 +1,179 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 导致失败的源码的最小化
+//
+// 把导致词法分析器或者语法分析器出错（或者 panic）的源码，在保持同样的失败的前提下，
+// 逐步删除其中的行以及单词，得到一段尽可能短的源码，以便于报告语法分析器的缺陷，
+// 或者整理模糊测试（fuzzing）发现的问题。
+//
+// 删除的过程是一个简化的 delta debugging：
+//
+// - 先以行为单位，从一半开始尝试删除连续的若干行，如果删除之后仍然失败，
+//   则保留删除的结果，否则尝试删除下一段，所有段都尝试过之后把段的长度减半，
+//   直到段的长度为 1 并且再也无法删除为止；
+// - 然后以单词（以空白字符分隔的文本）为单位重复上述过程。
+//
+// 单词删除之后，同一行的单词使用一个空格连接，所以最小化的结果可能跟原来的
+// 缩进和空白不同。
+
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::{ice, lexer, parser};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Failure {
+    Error(String), // 词法分析或者语法分析的错误信息
+    Panic(String), // panic 的信息
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Failure::Error(message) => write!(f, "error: {}", message),
+            Failure::Panic(message) => write!(f, "panic: {}", message),
+        }
+    }
+}
+
+/// 对源码进行词法分析以及语法分析，返回失败的信息，如果分析成功则返回 None。
+///
+/// 分析过程中的 panic 会被捕获并转换为 `Failure::Panic`，不过 panic 的信息仍然会
+/// 由当前的 panic hook 输出，如果不需要可以先使用 `std::panic::set_hook` 替换。
+pub fn parse_failure(source: &str) -> Option<Failure> {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        lexer::tokenize(source).and_then(|token_details| parser::parse(&token_details))
+    }));
+
+    match result {
+        Ok(Ok(_)) => None,
+        Ok(Err(error)) => Some(Failure::Error(error.to_string())),
+        Err(payload) => Some(Failure::Panic(ice::panic_message(payload.as_ref()))),
+    }
+}
+
+/// 最小化导致词法分析或者语法分析失败的源码，结果跟原来的源码有相同的失败信息。
+///
+/// 如果源码能够成功分析则返回 None。
+pub fn minimize_parse_failure(source: &str) -> Option<(String, Failure)> {
+    let failure = parse_failure(source)?;
+    let minimized = minimize(source, |s| parse_failure(s).as_ref() == Some(&failure));
+    Some((minimized, failure))
+}
+
+/// 在 `is_failing` 一直返回 true 的前提下，尽可能地删除源码里的行以及单词。
+///
+/// `is_failing` 对原来的源码应该返回 true，否则原样返回源码。
+pub fn minimize(source: &str, is_failing: impl Fn(&str) -> bool) -> String {
+    if !is_failing(source) {
+        return source.to_string();
+    }
+
+    // 以行为单位
+    let lines: Vec<&str> = source.lines().collect();
+    let lines = reduce(lines, |units| units.join("\n"), &is_failing);
+
+    // 以单词为单位，单词记录所在的行的序号
+    let words: Vec<(usize, &str)> = lines
+        .iter()
+        .enumerate()
+        .flat_map(|(index, line)| line.split_whitespace().map(move |word| (index, word)))
+        .collect();
+    let words = reduce(words, join_words, &is_failing);
+
+    // 单词以一个空格重新连接之后（比如字符串里的空白被改变）可能不再失败
+    let minimized = join_words(&words);
+    if is_failing(&minimized) {
+        minimized
+    } else {
+        lines.join("\n")
+    }
+}
+
+fn join_words(words: &[(usize, &str)]) -> String {
+    let mut text = String::new();
+    let mut last_index: Option<usize> = None;
+
+    for (index, word) in words {
+        match last_index {
+            Some(last) if last == *index => text.push(' '),
+            Some(_) => text.push('\n'),
+            None => {}
+        }
+        text.push_str(word);
+        last_index = Some(*index);
+    }
+
+    text
+}
+
+fn reduce<T: Clone>(
+    mut units: Vec<T>,
+    render: impl Fn(&[T]) -> String,
+    is_failing: &impl Fn(&str) -> bool,
+) -> Vec<T> {
+    let mut chunk_size = units.len().div_ceil(2);
+
+    while chunk_size > 0 {
+        let mut removed = false;
+        let mut start = 0;
+
+        while start < units.len() {
+            let end = (start + chunk_size).min(units.len());
+            let mut candidate = units[..start].to_vec();
+            candidate.extend_from_slice(&units[end..]);
+
+            if is_failing(&render(&candidate)) {
+                // 删除之后仍然失败，保留删除的结果，在同一位置继续尝试
+                units = candidate;
+                removed = true;
+            } else {
+                start = end;
+            }
+        }
+
+        if !removed {
+            chunk_size /= 2;
+        }
+    }
+
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{minimize, minimize_parse_failure, parse_failure, Failure};
+
+    #[test]
+    fn test_minimize() {
+        // 保留包含 "bad" 的行里的 "bad"
+        let source = "let a = 1\nlet b = 2\nlet c = bad + 3\nlet d = 4";
+        assert_eq!(minimize(source, |s| s.contains("bad")), "bad");
+
+        // 同时需要两行里的单词
+        assert_eq!(
+            minimize(source, |s| s.contains('1') && s.contains('4')),
+            "1\n4"
+        );
+
+        // 源码本身没有失败
+        assert_eq!(minimize(source, |s| s.contains("none")), source);
+    }
+
+    #[test]
+    fn test_minimize_parse_failure() {
+        assert_eq!(parse_failure("let a = 1"), None);
+        assert_eq!(minimize_parse_failure("let a = 1"), None);
+
+        let source = "let a = 1\nlet b = 2 + 3\nlet c = (1, 2 3)\nlet d = a + b";
+        let (minimized, failure) = minimize_parse_failure(source).unwrap();
+        assert!(matches!(failure, Failure::Error(_)));
+        assert_eq!(parse_failure(&minimized), Some(failure));
+        assert!(minimized.len() < "let c = (1, 2 3)".len());
+    }
+}
@@ -0,0 +1,1064 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 模块文件
+//
+// 编译之后的模块（即 IR，见 `ir` 模块）的二进制格式，XiaoXuan 运行时可以直接加载
+// 模块文件，而不需要重新分析源代码。
+//
+// 文件由以下几个部分依次组成，整数均为小端序，`u32` 以及 `i64` 为定长的整数：
+//
+// - 文件头：魔数 `ancm`（4 个字节）以及格式的版本号（u32）；
+// - 常量池：常量的数量（u32）以及各个常量，每个常量由类型标签（u8）以及值组成，
+//   名称（函数、全局变量、局部变量、成员等的名称）也作为字符串常量保存在常量池里，
+//   文件的其他部分使用常量的索引（u32）引用常量，同一个常量只保存一份；
+// - 全局变量表：全局变量的数量（u32），以及每个全局变量的名称、是否常量以及数据类型；
+// - 函数表：函数的数量（u32），以及每个函数的名称、符号名称（包括参数的类型，
+//   用于区分重载的函数，同一个模块里的符号名称不能重复）、参数的数量、
+//   操作数栈的最大深度（u32）、局部变量、返回值的数据类型，
+//   以及主体在代码段里的偏移量和长度（u32）；
+// - 代码段：代码的长度（u32）以及各个函数的主体，主体是前序遍历的 IR 表达式树，
+//   每个节点由操作码（u8）、操作数以及子节点组成。
+//
+// 数据类型保存为结构（而不是文本），其中的位置信息（`Range`）保存为文件编号、
+// 开始以及结束的位置（u32），所以读取得到的模块跟写入的模块完全相同。
+//
+// 模块文件是编译器生成的产物，跟接口文件一样不提供向后兼容，
+// 版本号不同时需要重新编译（见 `interface::INTERFACE_FILE_VERSION`）。
+
+use std::fmt::Display;
+
+use crate::{
+    ast::{
+        DataType, Expression, Identifier, Range, Sign, SignParameter, Tuple, WhichEntry,
+        WhichEntryLimit, WhichEntryType,
+    },
+    fold::Constant,
+    ir::{Expr, Function, Global, Local, Module, Operation},
+};
+
+pub const MAGIC: [u8; 4] = *b"ancm";
+
+// 当前模块文件格式的版本号，只有在发生不兼容的修改时才需要增加
+pub const MODULE_FILE_VERSION: u32 = 2;
+
+// 表达式树的最大嵌套深度，用于拒绝恶意构造的文件
+const MAX_DEPTH: usize = 10_000;
+
+#[derive(Debug, PartialEq)]
+pub enum ModuleFileError {
+    // 文件开头不是模块文件的魔数
+    InvalidMagic,
+
+    // 文件的版本号跟当前程序支持的版本号不同
+    UnsupportedVersion(u32),
+
+    // 文件被截断，或者内容跟格式不符
+    Malformed(String),
+
+    // 写入模块文件时遇到无效的数据类型，range 为无效成员的位置
+    InvalidDataType { data_type: String, range: Range },
+}
+
+impl Display for ModuleFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModuleFileError::InvalidMagic => write!(f, "not a module file"),
+            ModuleFileError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported module file version {}, the supported version is {}",
+                version, MODULE_FILE_VERSION
+            ),
+            ModuleFileError::Malformed(message) => write!(f, "malformed module file: {}", message),
+            ModuleFileError::InvalidDataType { data_type, .. } => write!(
+                f,
+                "invalid data type \"{}\", the members of a tuple data type should be data types",
+                data_type
+            ),
+        }
+    }
+}
+
+// 常量的类型标签
+const CONSTANT_INTEGER: u8 = 0;
+const CONSTANT_FLOAT: u8 = 1;
+const CONSTANT_COMPLEX: u8 = 2;
+const CONSTANT_BIT: u8 = 3;
+const CONSTANT_BOOLEAN: u8 = 4;
+const CONSTANT_CHAR: u8 = 5;
+const CONSTANT_STRING: u8 = 6;
+
+// 数据类型的标签
+const TYPE_IDENTIFIER: u8 = 0;
+const TYPE_TUPLE: u8 = 1;
+const TYPE_SIGN: u8 = 2;
+
+// 表达式的操作码
+const OP_CONSTANT: u8 = 0x00;
+const OP_HASH_STRING: u8 = 0x01;
+const OP_UNIT: u8 = 0x02;
+const OP_LOCAL: u8 = 0x03;
+const OP_GLOBAL: u8 = 0x04;
+const OP_LET: u8 = 0x10;
+const OP_SET_GLOBAL: u8 = 0x11;
+const OP_BLOCK: u8 = 0x12;
+const OP_IF: u8 = 0x13;
+const OP_LOOP: u8 = 0x14;
+const OP_NEXT: u8 = 0x15;
+const OP_CALL: u8 = 0x20;
+const OP_OPERATION: u8 = 0x21;
+const OP_CLOSURE: u8 = 0x22;
+
+// 把模块转换为模块文件的内容，模块里有无效的数据类型时返回错误
+pub fn write(module: &Module) -> Result<Vec<u8>, ModuleFileError> {
+    let mut writer = Writer::default();
+
+    let mut globals = vec![];
+    put_u32(&mut globals, module.globals.len());
+    for global in &module.globals {
+        writer.write_name(&mut globals, &global.name);
+        globals.push(global.is_const as u8);
+        writer.write_optional_data_type(&mut globals, global.data_type.as_ref())?;
+    }
+
+    let mut code = vec![];
+    let mut functions = vec![];
+    put_u32(&mut functions, module.functions.len());
+    for function in &module.functions {
+        writer.write_name(&mut functions, &function.name);
+        writer.write_name(&mut functions, &function.symbol);
+        put_u32(&mut functions, function.parameter_count);
+        put_u32(&mut functions, function.max_stack);
+        put_u32(&mut functions, function.locals.len());
+        for local in &function.locals {
+            writer.write_name(&mut functions, &local.name);
+            writer.write_optional_data_type(&mut functions, local.data_type.as_ref())?;
+        }
+        writer.write_optional_data_type(&mut functions, function.return_data_type.as_ref())?;
+
+        let offset = code.len();
+        writer.write_expr(&mut code, &function.body);
+        put_u32(&mut functions, offset);
+        put_u32(&mut functions, code.len() - offset);
+    }
+
+    let mut bytes = MAGIC.to_vec();
+    put_u32(&mut bytes, MODULE_FILE_VERSION as usize);
+
+    put_u32(&mut bytes, writer.constants.len());
+    for constant in &writer.constants {
+        write_constant(&mut bytes, constant);
+    }
+
+    bytes.extend(globals);
+    bytes.extend(functions);
+    put_u32(&mut bytes, code.len());
+    bytes.extend(code);
+    Ok(bytes)
+}
+
+// 读取模块文件
+pub fn read(bytes: &[u8]) -> Result<Module, ModuleFileError> {
+    if !bytes.starts_with(&MAGIC) {
+        return Err(ModuleFileError::InvalidMagic);
+    }
+
+    let mut reader = Reader {
+        bytes,
+        position: MAGIC.len(),
+        constants: vec![],
+        depth: 0,
+    };
+
+    let version = reader.read_u32()?;
+    if version != MODULE_FILE_VERSION as usize {
+        return Err(ModuleFileError::UnsupportedVersion(version as u32));
+    }
+
+    for _ in 0..reader.read_u32()? {
+        let constant = reader.read_constant_value()?;
+        reader.constants.push(constant);
+    }
+
+    let mut globals = vec![];
+    for _ in 0..reader.read_u32()? {
+        globals.push(Global {
+            name: reader.read_name()?,
+            is_const: reader.read_bool()?,
+            data_type: reader.read_optional_data_type()?,
+        });
+    }
+
+    let mut headers: Vec<(Function, usize, usize)> = vec![];
+    for _ in 0..reader.read_u32()? {
+        let name = reader.read_name()?;
+        let symbol = reader.read_name()?;
+        if headers.iter().any(|(f, _, _)| f.symbol == symbol) {
+            return Err(malformed(format!(
+                "duplicate function symbol \"{}\"",
+                symbol
+            )));
+        }
+        let parameter_count = reader.read_u32()?;
+        let max_stack = reader.read_u32()?;
+        let mut locals = vec![];
+        for _ in 0..reader.read_u32()? {
+            locals.push(Local {
+                name: reader.read_name()?,
+                data_type: reader.read_optional_data_type()?,
+            });
+        }
+        if parameter_count > locals.len() {
+            return Err(malformed(format!(
+                "function \"{}\" has more parameters than locals",
+                name
+            )));
+        }
+        let return_data_type = reader.read_optional_data_type()?;
+        let offset = reader.read_u32()?;
+        let length = reader.read_u32()?;
+        let function = Function {
+            name,
+            symbol,
+            parameter_count,
+            max_stack,
+            locals,
+            return_data_type,
+            body: Expr::Unit, // 读取代码段之后再填写
+        };
+        headers.push((function, offset, length));
+    }
+
+    let code_length = reader.read_u32()?;
+    let code_start = reader.position;
+    reader.read_bytes(code_length)?;
+    if reader.position != bytes.len() {
+        return Err(malformed(
+            "unexpected data after the code section".to_string(),
+        ));
+    }
+
+    let mut functions = vec![];
+    for (mut function, offset, length) in headers {
+        if offset
+            .checked_add(length)
+            .is_none_or(|end| end > code_length)
+        {
+            return Err(malformed(format!(
+                "the code of function \"{}\" is out of the code section",
+                function.name
+            )));
+        }
+
+        reader.position = code_start + offset;
+        function.body = reader.read_expr()?;
+        if reader.position != code_start + offset + length {
+            return Err(malformed(format!(
+                "the code length of function \"{}\" does not match",
+                function.name
+            )));
+        }
+        functions.push(function);
+    }
+
+    Ok(Module { globals, functions })
+}
+
+fn malformed(message: String) -> ModuleFileError {
+    ModuleFileError::Malformed(message)
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: usize) {
+    bytes.extend((value as u32).to_le_bytes());
+}
+
+fn put_range(bytes: &mut Vec<u8>, range: &Range) {
+    put_u32(bytes, range.file_id);
+    put_u32(bytes, range.start);
+    put_u32(bytes, range.end);
+}
+
+fn write_constant(bytes: &mut Vec<u8>, constant: &Constant) {
+    match constant {
+        Constant::Integer(v) => {
+            bytes.push(CONSTANT_INTEGER);
+            bytes.extend(v.to_le_bytes());
+        }
+        Constant::Float(v) => {
+            bytes.push(CONSTANT_FLOAT);
+            bytes.extend(v.to_le_bytes());
+        }
+        Constant::Complex(real, imaginary) => {
+            bytes.push(CONSTANT_COMPLEX);
+            bytes.extend(real.to_le_bytes());
+            bytes.extend(imaginary.to_le_bytes());
+        }
+        Constant::Bit(width, value) => {
+            bytes.push(CONSTANT_BIT);
+            put_u32(bytes, *width);
+            put_u32(bytes, value.len());
+            bytes.extend(value);
+        }
+        Constant::Boolean(v) => {
+            bytes.push(CONSTANT_BOOLEAN);
+            bytes.push(*v as u8);
+        }
+        Constant::Char(v) => {
+            bytes.push(CONSTANT_CHAR);
+            put_u32(bytes, *v as usize);
+        }
+        Constant::String(v) => {
+            bytes.push(CONSTANT_STRING);
+            put_u32(bytes, v.len());
+            bytes.extend(v.as_bytes());
+        }
+    }
+}
+
+fn operation_code(operation: &Operation) -> u8 {
+    match operation {
+        Operation::Add => 0,
+        Operation::Subtract => 1,
+        Operation::Multiply => 2,
+        Operation::Divide => 3,
+        Operation::Negate => 4,
+        Operation::Equal => 5,
+        Operation::NotEqual => 6,
+        Operation::GreaterThan => 7,
+        Operation::GreaterThanOrEqual => 8,
+        Operation::LessThan => 9,
+        Operation::LessThanOrEqual => 10,
+        Operation::Concat => 11,
+        Operation::Combine => 12,
+        Operation::OptionalOr => 13,
+        Operation::OptionalAnd => 14,
+        Operation::Unwrap => 15,
+        Operation::Cast => 16,
+        Operation::Tuple => 17,
+        Operation::List => 18,
+        Operation::Map => 19,
+        Operation::Struct(..) => 20,
+        Operation::TupleStruct(_) => 21,
+        Operation::Interval(_) => 22,
+        Operation::Field(_) => 23,
+        Operation::Element(_) => 24,
+        Operation::Index => 25,
+        Operation::Slice => 26,
+        Operation::Length => 27,
+        Operation::Append => 28,
+        Operation::Contains => 29,
+        Operation::Join => 30,
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    constants: Vec<Constant>,
+}
+
+impl Writer {
+    // 返回常量在常量池里的索引，常量不存在时先加入常量池
+    fn constant_index(&mut self, constant: &Constant) -> usize {
+        match self.constants.iter().position(|c| c == constant) {
+            Some(index) => index,
+            None => {
+                self.constants.push(constant.clone());
+                self.constants.len() - 1
+            }
+        }
+    }
+
+    fn write_constant(&mut self, bytes: &mut Vec<u8>, constant: &Constant) {
+        let index = self.constant_index(constant);
+        put_u32(bytes, index);
+    }
+
+    fn write_name(&mut self, bytes: &mut Vec<u8>, name: &str) {
+        self.write_constant(bytes, &Constant::String(name.to_string()));
+    }
+
+    fn write_optional_data_type(
+        &mut self,
+        bytes: &mut Vec<u8>,
+        data_type: Option<&DataType>,
+    ) -> Result<(), ModuleFileError> {
+        match data_type {
+            Some(data_type) => {
+                bytes.push(1);
+                self.write_data_type(bytes, data_type)?;
+            }
+            None => bytes.push(0),
+        }
+        Ok(())
+    }
+
+    fn write_data_type(
+        &mut self,
+        bytes: &mut Vec<u8>,
+        data_type: &DataType,
+    ) -> Result<(), ModuleFileError> {
+        match data_type {
+            DataType::Identifier(identifier) => self.write_identifier_type(bytes, identifier),
+            DataType::Tuple(tuple) => self.write_tuple_type(bytes, tuple),
+            DataType::Sign(sign) => self.write_sign_type(bytes, sign),
+        }
+    }
+
+    fn write_identifier_type(
+        &mut self,
+        bytes: &mut Vec<u8>,
+        identifier: &Identifier,
+    ) -> Result<(), ModuleFileError> {
+        bytes.push(TYPE_IDENTIFIER);
+        put_range(bytes, &identifier.range);
+        put_u32(bytes, identifier.dirs.len());
+        for dir in &identifier.dirs {
+            self.write_name(bytes, dir);
+        }
+        self.write_name(bytes, &identifier.name);
+        put_u32(bytes, identifier.generics.len());
+        for generic in &identifier.generics {
+            self.write_data_type(bytes, generic)?;
+        }
+        Ok(())
+    }
+
+    // 元组类型的成员应该是标识符、元组或者函数签名，但语法分析器把元组类型当作
+    // 元组表达式解析，所以成员可能是其他表达式（比如 `(#Int, String)`），此时返回错误
+    fn write_tuple_type(
+        &mut self,
+        bytes: &mut Vec<u8>,
+        tuple: &Tuple,
+    ) -> Result<(), ModuleFileError> {
+        bytes.push(TYPE_TUPLE);
+        put_range(bytes, &tuple.range);
+        put_u32(bytes, tuple.elements.len());
+        for element in &tuple.elements {
+            match element {
+                Expression::Identifier(identifier) => {
+                    self.write_identifier_type(bytes, identifier)?
+                }
+                Expression::Tuple(tuple) => self.write_tuple_type(bytes, tuple)?,
+                Expression::Sign(sign) => self.write_sign_type(bytes, sign)?,
+                _ => {
+                    return Err(ModuleFileError::InvalidDataType {
+                        data_type: tuple.to_string(),
+                        range: element.range().clone(),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_sign_type(&mut self, bytes: &mut Vec<u8>, sign: &Sign) -> Result<(), ModuleFileError> {
+        bytes.push(TYPE_SIGN);
+        put_range(bytes, &sign.range);
+        put_u32(bytes, sign.parameters.len());
+        for parameter in &sign.parameters {
+            put_range(bytes, &parameter.range);
+            self.write_data_type(bytes, &parameter.data_type)?;
+            match &parameter.name {
+                Some(name) => {
+                    bytes.push(1);
+                    self.write_name(bytes, name);
+                }
+                None => bytes.push(0),
+            }
+        }
+        self.write_optional_data_type(bytes, sign.return_data_type.as_deref())?;
+
+        put_u32(bytes, sign.generics.len());
+        for generic in &sign.generics {
+            self.write_data_type(bytes, generic)?;
+        }
+
+        put_u32(bytes, sign.whiches.len());
+        for which in &sign.whiches {
+            match which {
+                WhichEntry::Type(entry) => {
+                    bytes.push(0);
+                    put_range(bytes, &entry.range);
+                    self.write_name(bytes, &entry.name);
+                    self.write_data_type(bytes, &entry.data_type)?;
+                }
+                WhichEntry::Limit(entry) => {
+                    bytes.push(1);
+                    put_range(bytes, &entry.range);
+                    self.write_name(bytes, &entry.name);
+                    put_u32(bytes, entry.data_types.len());
+                    for data_type in &entry.data_types {
+                        self.write_data_type(bytes, data_type)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_exprs(&mut self, bytes: &mut Vec<u8>, exprs: &[Expr]) {
+        put_u32(bytes, exprs.len());
+        for expr in exprs {
+            self.write_expr(bytes, expr);
+        }
+    }
+
+    fn write_expr(&mut self, bytes: &mut Vec<u8>, expr: &Expr) {
+        match expr {
+            Expr::Constant(constant) => {
+                bytes.push(OP_CONSTANT);
+                self.write_constant(bytes, constant);
+            }
+            Expr::HashString(value) => {
+                bytes.push(OP_HASH_STRING);
+                self.write_name(bytes, value);
+            }
+            Expr::Unit => bytes.push(OP_UNIT),
+            Expr::Local(id) => {
+                bytes.push(OP_LOCAL);
+                put_u32(bytes, *id);
+            }
+            Expr::Global(name) => {
+                bytes.push(OP_GLOBAL);
+                self.write_name(bytes, name);
+            }
+            Expr::Let(id, value) => {
+                bytes.push(OP_LET);
+                put_u32(bytes, *id);
+                self.write_expr(bytes, value);
+            }
+            Expr::SetGlobal(name, value) => {
+                bytes.push(OP_SET_GLOBAL);
+                self.write_name(bytes, name);
+                self.write_expr(bytes, value);
+            }
+            Expr::Block(body) => {
+                bytes.push(OP_BLOCK);
+                self.write_exprs(bytes, body);
+            }
+            Expr::If(testing, consequent, alternate) => {
+                bytes.push(OP_IF);
+                self.write_expr(bytes, testing);
+                self.write_expr(bytes, consequent);
+                self.write_expr(bytes, alternate);
+            }
+            Expr::Loop(variables, body) => {
+                bytes.push(OP_LOOP);
+                put_u32(bytes, variables.len());
+                for (id, value) in variables {
+                    put_u32(bytes, *id);
+                    self.write_expr(bytes, value);
+                }
+                self.write_expr(bytes, body);
+            }
+            Expr::Next(values) => {
+                bytes.push(OP_NEXT);
+                self.write_exprs(bytes, values);
+            }
+            Expr::Call(callee, arguments) => {
+                bytes.push(OP_CALL);
+                self.write_expr(bytes, callee);
+                self.write_exprs(bytes, arguments);
+            }
+            Expr::Operation(operation, arguments) => {
+                bytes.push(OP_OPERATION);
+                bytes.push(operation_code(operation));
+                match operation {
+                    Operation::Struct(name, fields) => {
+                        self.write_name(bytes, name);
+                        put_u32(bytes, fields.len());
+                        for field in fields {
+                            self.write_name(bytes, field);
+                        }
+                    }
+                    Operation::TupleStruct(name) | Operation::Field(name) => {
+                        self.write_name(bytes, name)
+                    }
+                    Operation::Interval(is_inclusive) => bytes.push(*is_inclusive as u8),
+                    Operation::Element(index) => put_u32(bytes, *index),
+                    _ => {}
+                }
+                self.write_exprs(bytes, arguments);
+            }
+            Expr::Closure(name, captures) => {
+                bytes.push(OP_CLOSURE);
+                self.write_name(bytes, name);
+                put_u32(bytes, captures.len());
+                for id in captures {
+                    put_u32(bytes, *id);
+                }
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    constants: Vec<Constant>,
+    depth: usize, // 当前表达式（以及数据类型）的嵌套深度
+}
+
+impl Reader<'_> {
+    fn read_bytes(&mut self, length: usize) -> Result<&[u8], ModuleFileError> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| malformed("unexpected end of file".to_string()))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], ModuleFileError> {
+        Ok(self.read_bytes(N)?.try_into().unwrap())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, ModuleFileError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    fn read_bool(&mut self) -> Result<bool, ModuleFileError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            v => Err(malformed(format!("invalid boolean value {}", v))),
+        }
+    }
+
+    fn read_u32(&mut self) -> Result<usize, ModuleFileError> {
+        Ok(u32::from_le_bytes(self.read_array()?) as usize)
+    }
+
+    fn read_f64(&mut self) -> Result<f64, ModuleFileError> {
+        Ok(f64::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_constant_value(&mut self) -> Result<Constant, ModuleFileError> {
+        let constant = match self.read_u8()? {
+            CONSTANT_INTEGER => Constant::Integer(i64::from_le_bytes(self.read_array()?)),
+            CONSTANT_FLOAT => Constant::Float(self.read_f64()?),
+            CONSTANT_COMPLEX => Constant::Complex(self.read_f64()?, self.read_f64()?),
+            CONSTANT_BIT => {
+                let width = self.read_u32()?;
+                let length = self.read_u32()?;
+                Constant::Bit(width, self.read_bytes(length)?.to_vec())
+            }
+            CONSTANT_BOOLEAN => Constant::Boolean(self.read_bool()?),
+            CONSTANT_CHAR => {
+                let value = self.read_u32()? as u32;
+                Constant::Char(
+                    char::from_u32(value)
+                        .ok_or_else(|| malformed(format!("invalid char value {}", value)))?,
+                )
+            }
+            CONSTANT_STRING => {
+                let length = self.read_u32()?;
+                let text = std::str::from_utf8(self.read_bytes(length)?)
+                    .map_err(|_| malformed("invalid UTF-8 string constant".to_string()))?;
+                Constant::String(text.to_string())
+            }
+            tag => return Err(malformed(format!("invalid constant tag {}", tag))),
+        };
+        Ok(constant)
+    }
+
+    // 读取常量的索引，返回对应的常量
+    fn read_constant(&mut self) -> Result<Constant, ModuleFileError> {
+        let index = self.read_u32()?;
+        self.constants
+            .get(index)
+            .cloned()
+            .ok_or_else(|| malformed(format!("constant index {} out of range", index)))
+    }
+
+    fn read_name(&mut self) -> Result<String, ModuleFileError> {
+        match self.read_constant()? {
+            Constant::String(name) => Ok(name),
+            constant => Err(malformed(format!(
+                "expected a string constant, found {}",
+                constant
+            ))),
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), ModuleFileError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(malformed("nested too deeply".to_string()));
+        }
+        Ok(())
+    }
+
+    fn read_range(&mut self) -> Result<Range, ModuleFileError> {
+        Ok(Range {
+            file_id: self.read_u32()?,
+            start: self.read_u32()?,
+            end: self.read_u32()?,
+        })
+    }
+
+    fn read_optional_data_type(&mut self) -> Result<Option<DataType>, ModuleFileError> {
+        match self.read_bool()? {
+            true => Ok(Some(self.read_data_type()?)),
+            false => Ok(None),
+        }
+    }
+
+    fn read_data_type(&mut self) -> Result<DataType, ModuleFileError> {
+        self.enter()?;
+        let data_type = match self.read_u8()? {
+            TYPE_IDENTIFIER => {
+                let range = self.read_range()?;
+                let mut dirs = vec![];
+                for _ in 0..self.read_u32()? {
+                    dirs.push(self.read_name()?);
+                }
+                let name = self.read_name()?;
+                DataType::Identifier(Identifier {
+                    dirs,
+                    name,
+                    generics: self.read_data_types()?,
+                    range,
+                })
+            }
+            TYPE_TUPLE => {
+                let range = self.read_range()?;
+                let elements = self
+                    .read_data_types()?
+                    .into_iter()
+                    .map(|data_type| match data_type {
+                        DataType::Identifier(v) => Expression::Identifier(v),
+                        DataType::Tuple(v) => Expression::Tuple(v),
+                        DataType::Sign(v) => Expression::Sign(v),
+                    })
+                    .collect();
+                DataType::Tuple(Tuple { elements, range })
+            }
+            TYPE_SIGN => {
+                let range = self.read_range()?;
+                let mut parameters = vec![];
+                for _ in 0..self.read_u32()? {
+                    let parameter_range = self.read_range()?;
+                    let data_type = self.read_data_type()?;
+                    let name = match self.read_bool()? {
+                        true => Some(self.read_name()?),
+                        false => None,
+                    };
+                    parameters.push(SignParameter {
+                        data_type,
+                        name,
+                        range: parameter_range,
+                    });
+                }
+                let return_data_type = self.read_optional_data_type()?.map(Box::new);
+                let generics = self.read_data_types()?;
+
+                let mut whiches = vec![];
+                for _ in 0..self.read_u32()? {
+                    let which = match self.read_u8()? {
+                        0 => {
+                            let range = self.read_range()?;
+                            WhichEntry::Type(WhichEntryType {
+                                name: self.read_name()?,
+                                data_type: self.read_data_type()?,
+                                range,
+                            })
+                        }
+                        1 => {
+                            let range = self.read_range()?;
+                            WhichEntry::Limit(WhichEntryLimit {
+                                name: self.read_name()?,
+                                data_types: self.read_data_types()?,
+                                range,
+                            })
+                        }
+                        tag => return Err(malformed(format!("invalid which entry tag {}", tag))),
+                    };
+                    whiches.push(which);
+                }
+
+                DataType::Sign(Sign {
+                    parameters,
+                    return_data_type,
+                    generics,
+                    whiches,
+                    range,
+                })
+            }
+            tag => return Err(malformed(format!("invalid data type tag {}", tag))),
+        };
+        self.depth -= 1;
+        Ok(data_type)
+    }
+
+    fn read_data_types(&mut self) -> Result<Vec<DataType>, ModuleFileError> {
+        let mut data_types = vec![];
+        for _ in 0..self.read_u32()? {
+            data_types.push(self.read_data_type()?);
+        }
+        Ok(data_types)
+    }
+
+    fn read_exprs(&mut self) -> Result<Vec<Expr>, ModuleFileError> {
+        let mut exprs = vec![];
+        for _ in 0..self.read_u32()? {
+            exprs.push(self.read_expr()?);
+        }
+        Ok(exprs)
+    }
+
+    fn read_boxed_expr(&mut self) -> Result<Box<Expr>, ModuleFileError> {
+        Ok(Box::new(self.read_expr()?))
+    }
+
+    fn read_expr(&mut self) -> Result<Expr, ModuleFileError> {
+        self.enter()?;
+        let expr = match self.read_u8()? {
+            OP_CONSTANT => Expr::Constant(self.read_constant()?),
+            OP_HASH_STRING => Expr::HashString(self.read_name()?),
+            OP_UNIT => Expr::Unit,
+            OP_LOCAL => Expr::Local(self.read_u32()?),
+            OP_GLOBAL => Expr::Global(self.read_name()?),
+            OP_LET => Expr::Let(self.read_u32()?, self.read_boxed_expr()?),
+            OP_SET_GLOBAL => Expr::SetGlobal(self.read_name()?, self.read_boxed_expr()?),
+            OP_BLOCK => Expr::Block(self.read_exprs()?),
+            OP_IF => Expr::If(
+                self.read_boxed_expr()?,
+                self.read_boxed_expr()?,
+                self.read_boxed_expr()?,
+            ),
+            OP_LOOP => {
+                let mut variables = vec![];
+                for _ in 0..self.read_u32()? {
+                    variables.push((self.read_u32()?, self.read_expr()?));
+                }
+                Expr::Loop(variables, self.read_boxed_expr()?)
+            }
+            OP_NEXT => Expr::Next(self.read_exprs()?),
+            OP_CALL => Expr::Call(self.read_boxed_expr()?, self.read_exprs()?),
+            OP_OPERATION => {
+                let operation = self.read_operation()?;
+                Expr::Operation(operation, self.read_exprs()?)
+            }
+            OP_CLOSURE => {
+                let name = self.read_name()?;
+                let mut captures = vec![];
+                for _ in 0..self.read_u32()? {
+                    captures.push(self.read_u32()?);
+                }
+                Expr::Closure(name, captures)
+            }
+            code => return Err(malformed(format!("invalid operation code 0x{:02x}", code))),
+        };
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn read_operation(&mut self) -> Result<Operation, ModuleFileError> {
+        let operation = match self.read_u8()? {
+            0 => Operation::Add,
+            1 => Operation::Subtract,
+            2 => Operation::Multiply,
+            3 => Operation::Divide,
+            4 => Operation::Negate,
+            5 => Operation::Equal,
+            6 => Operation::NotEqual,
+            7 => Operation::GreaterThan,
+            8 => Operation::GreaterThanOrEqual,
+            9 => Operation::LessThan,
+            10 => Operation::LessThanOrEqual,
+            11 => Operation::Concat,
+            12 => Operation::Combine,
+            13 => Operation::OptionalOr,
+            14 => Operation::OptionalAnd,
+            15 => Operation::Unwrap,
+            16 => Operation::Cast,
+            17 => Operation::Tuple,
+            18 => Operation::List,
+            19 => Operation::Map,
+            20 => {
+                let name = self.read_name()?;
+                let mut fields = vec![];
+                for _ in 0..self.read_u32()? {
+                    fields.push(self.read_name()?);
+                }
+                Operation::Struct(name, fields)
+            }
+            21 => Operation::TupleStruct(self.read_name()?),
+            22 => Operation::Interval(self.read_bool()?),
+            23 => Operation::Field(self.read_name()?),
+            24 => Operation::Element(self.read_u32()?),
+            25 => Operation::Index,
+            26 => Operation::Slice,
+            27 => Operation::Length,
+            28 => Operation::Append,
+            29 => Operation::Contains,
+            30 => Operation::Join,
+            code => return Err(malformed(format!("invalid operation {}", code))),
+        };
+        Ok(operation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Node,
+        ir::{self, Module},
+        lexer, parser, typed,
+    };
+
+    use super::{read, write, ModuleFileError, MAGIC, MODULE_FILE_VERSION};
+
+    fn compile(source: &str) -> Module {
+        let token_details = lexer::tokenize(source).unwrap();
+        let node = parser::parse(&token_details).unwrap();
+        lower(node)
+    }
+
+    fn lower(mut node: Node) -> Module {
+        typed::annotate_types(&mut node);
+        match node {
+            Node::Program(program) => ir::lower(&program).unwrap(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let module = compile(
+            "const PI = 3.14\n\
+            const NAME = \"foo\"\n\
+            function add(Int a, Int b = 2) type Int = a + b\n\
+            function f(List<(Int, String)> xs, sign (Int x) type Int g) = each x in xs {\n\
+                g(x.0) ++ x.1\n\
+            }\n\
+            function h(Any v) = match v {\n\
+                case #red: 'r'\n\
+                case in [1..=10]: 4'b1010\n\
+                default: User {id: 1, name: NAME}\n\
+            }\n\
+            let (a, [b, ...c]) = (1, [2, 3])\n\
+            let k = fn (Int x) = x + a\n\
+            writeLine(add(1) | inc, 1+2i)",
+        );
+
+        let bytes = write(&module).unwrap();
+        assert_eq!(&bytes[..4], &MAGIC);
+        assert_eq!(read(&bytes), Ok(module.clone()));
+        assert_eq!(read(&bytes).unwrap().to_string(), module.to_string());
+
+        // 输出是可重现的
+        assert_eq!(write(&module).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_overloaded_functions() {
+        let module = compile(
+            "function add(Int a) = a\n\
+            function add(Int a, Int b) = a + (b * 2)\n\
+            add(1, 2)",
+        );
+
+        let bytes = write(&module).unwrap();
+        let restored = read(&bytes).unwrap();
+        assert_eq!(restored, module);
+
+        let headers: Vec<(&str, &str, usize)> = restored
+            .functions
+            .iter()
+            .map(|f| (f.name.as_str(), f.symbol.as_str(), f.max_stack))
+            .collect();
+        assert_eq!(
+            headers,
+            vec![
+                ("add", "_X3addPT3IntE", 1),
+                ("add", "_X3addPT3IntT3IntE", 3),
+                ("$init", "$init", 2),
+            ]
+        );
+        assert_eq!(
+            restored.functions[2].to_string(),
+            "function $init () {\n    (call @_X3addPT3IntT3IntE 1 2)\n}\n"
+        );
+
+        // 符号名称重复的模块文件
+        let mut duplicate = module.clone();
+        duplicate.functions[1].symbol = duplicate.functions[0].symbol.clone();
+        assert_eq!(
+            read(&write(&duplicate).unwrap()).unwrap_err().to_string(),
+            "malformed module file: duplicate function symbol \"_X3addPT3IntE\""
+        );
+    }
+
+    #[test]
+    fn test_constant_pool() {
+        // 同一个常量以及名称只保存一份
+        let m1 = compile("function f(Int a) = a + 1\nf(1)\nf(1)");
+        let m2 = compile("function f(Int a) = a + 1\nf(1)");
+        let b1 = write(&m1).unwrap();
+        let b2 = write(&m2).unwrap();
+        assert!(b1.len() > b2.len());
+
+        // 文件头之后是常量的数量
+        let constant_count = |bytes: &[u8]| u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        assert_eq!(constant_count(&b1), constant_count(&b2));
+    }
+
+    #[test]
+    fn test_invalid_module_file() {
+        let bytes = write(&compile("function f(Int a) = a")).unwrap();
+
+        assert_eq!(read(b"abcd\x01\0\0\0"), Err(ModuleFileError::InvalidMagic));
+
+        let mut b1 = bytes.clone();
+        b1[4] = 9;
+        assert_eq!(read(&b1), Err(ModuleFileError::UnsupportedVersion(9)));
+        assert_eq!(
+            read(&b1).unwrap_err().to_string(),
+            format!(
+                "unsupported module file version 9, the supported version is {}",
+                MODULE_FILE_VERSION
+            )
+        );
+
+        // 被截断的文件
+        for length in 4..bytes.len() {
+            assert!(matches!(
+                read(&bytes[..length]),
+                Err(ModuleFileError::Malformed(_))
+            ));
+        }
+
+        // 多余的内容
+        let mut b2 = bytes.clone();
+        b2.push(0);
+        assert_eq!(
+            read(&b2).unwrap_err().to_string(),
+            "malformed module file: unexpected data after the code section"
+        );
+    }
+
+    #[test]
+    fn test_invalid_data_type() {
+        // 语法分析器接受任意表达式作为元组类型的成员
+        let module = compile("const (#Int, String) Pair = (1, \"a\")");
+        let error = write(&module).unwrap_err();
+        assert!(matches!(
+            &error,
+            ModuleFileError::InvalidDataType { range, .. } if range.start == 7 && range.end == 11
+        ));
+        assert_eq!(
+            error.to_string(),
+            "invalid data type \"(#Int, String,)\", \
+            the members of a tuple data type should be data types"
+        );
+    }
+}
@@ -0,0 +1,128 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// AST 规范化
+//
+// 把语义相同、但写法不同的语法结构转换为同一种表示，以便语义比较、
+// 缓存的键以及格式化器对结构的理解保持一致。
+//
+// 语法分析器已经完成的规范化：
+//
+// - 括号表达式 `(a + b)` 不产生节点，直接使用括号里面的表达式；
+// - 列表、元组、映射表以及参数列表末尾的逗号不会被记录。
+//
+// 本模块完成的规范化：
+//
+// - 只包含单独一个表达式（且不是 `let` 表达式）的表达式块 `{...}` 以及 `do {...}`
+//   转换为该表达式本身，比如 `if a then {b} else {c}` 转换为 `if a then b else c`；
+// - 结构体实例化表达式里键和值同名的项目 `User {id: id}` 转换为省略值的形式 `User {id}`。
+//
+// 注意 `let` 表达式定义的名称只在表达式块之内有效，所以只包含 `let` 表达式的
+// 表达式块不会被展开。
+
+use crate::ast::{Expression, Node};
+
+pub fn normalize(node: &mut Node) {
+    match node {
+        Node::Program(program) => {
+            for statement in &mut program.body {
+                for expression in statement.expressions_mut() {
+                    normalize_expression(expression);
+                }
+            }
+        }
+        Node::Statement(statement) => {
+            for expression in statement.expressions_mut() {
+                normalize_expression(expression);
+            }
+        }
+        Node::Expression(expression) => normalize_expression(expression),
+    }
+}
+
+pub fn normalize_expression(expression: &mut Expression) {
+    // 先规范化子表达式，这样嵌套的单表达式块可以被逐层展开
+    for child in expression.children_mut() {
+        normalize_expression(child);
+    }
+
+    match expression {
+        Expression::BlockExpression(block)
+            if block.body.len() == 1 && !matches!(block.body[0], Expression::LetExpression(_)) =>
+        {
+            let inner = block.body.remove(0);
+            *expression = inner;
+        }
+        Expression::ConstructorExpression(constructor) => {
+            for entry in &mut constructor.value.elements {
+                let is_same_name = match (entry.key.as_ref(), entry.value.as_deref()) {
+                    (Expression::Identifier(key), Some(Expression::Identifier(value))) => {
+                        [key, value]
+                            .iter()
+                            .all(|i| i.dirs.is_empty() && i.generics.is_empty())
+                            && key.name == value.name
+                    }
+                    _ => false,
+                };
+
+                if is_same_name {
+                    entry.value = None;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Node, lexer, parser};
+
+    use super::normalize;
+
+    // 比较的是规范化之后的结构，所以清除范围
+    fn normalize_source(text: &str) -> Node {
+        let token_details = lexer::tokenize(text).unwrap();
+        let mut node = parser::parse(&token_details).unwrap();
+        normalize(&mut node);
+        node.clear_ranges();
+        node
+    }
+
+    #[test]
+    fn test_normalize() {
+        // 单表达式块
+        assert_eq!(
+            normalize_source("if a then {b} else do {c}"),
+            normalize_source("if a then b else c")
+        );
+        assert_eq!(
+            normalize_source("function f(Int x) = {\ndo {x + 1}\n}"),
+            normalize_source("function f(Int x) = x + 1")
+        );
+
+        // 括号以及末尾的逗号
+        assert_eq!(
+            normalize_source("[(1 + 2), (a),]"),
+            normalize_source("[1 + 2, a]")
+        );
+
+        // 结构体实例化表达式
+        assert_eq!(
+            normalize_source("User {id: id, name: \"foo\"}"),
+            normalize_source("User {id, name: \"foo\"}")
+        );
+
+        // 不展开包含 `let` 表达式或者多个表达式的表达式块
+        assert_eq!(
+            normalize_source("{let a = 1}").to_string(),
+            "{\nlet a = 1\n}\n"
+        );
+        assert_eq!(normalize_source("do {a\nb}").to_string(), "do {\na\nb\n}\n");
+    }
+}
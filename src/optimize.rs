@@ -0,0 +1,356 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 优化分析
+//
+// 在 AST 上进行的、供中间表示（IR）的优化使用的分析。
+//
+// 目前包括：
+//
+// - 纯表达式
+//   求值没有副作用，并且结果只由其中的名称的值决定的表达式（见 `is_pure`）。
+// - 循环不变量
+//   `for`、`each` 表达式的主体里，操作数在各次迭代之间都不会改变的纯表达式，
+//   可以移到循环之前只求值一次（见 `loop_invariants`）。
+// - 公共子表达式
+//   多次出现的、值相同的纯表达式，可以只求值一次然后重复使用（见 `common_subexpressions`）。
+
+use crate::{
+    ast::{BinaryOperator, Expression},
+    fold,
+    semantics::{free_variables_of, pattern_names},
+};
+
+// 表达式是否为纯表达式
+//
+// 函数调用（包括管道运算 `|` 以及命名操作符）可能有副作用，在副作用分析实现之前
+// 都视为非纯表达式；`env("NAME")` 在编译时求值，不属于函数调用。
+// `next` 表达式会改变控制流，也不是纯表达式。
+pub fn is_pure(expression: &Expression) -> bool {
+    let is_self_pure = match expression {
+        Expression::FunctionCallExpression(v) => fold::is_env_call(v),
+        Expression::BinaryExpression(v) => v.operator != BinaryOperator::Pipe,
+        Expression::NamedOperatorExpression(_) | Expression::NextExpression(_) => false,
+        _ => true,
+    };
+
+    is_self_pure && expression.children().into_iter().all(is_pure)
+}
+
+// 列出 `for` 或者 `each` 表达式的主体里的循环不变量，
+// 按照在源代码里出现的顺序排列，其他表达式返回空列表。
+//
+// 循环不变量是满足以下条件的（最大的）子表达式：
+//
+// - 是纯表达式；
+// - 其中的名称都不是在循环里（包括循环变量）绑定的；
+// - 位于每次迭代都会被求值的位置，即不在条件表达式的分支、逻辑运算的右侧、
+//   内层循环的主体以及匿名函数的主体里，以免移到循环之前之后，
+//   求值原本不会被求值的、可能出错的表达式（比如除以 0）；
+// - 不是名称、字面量以及能够求值为常量的表达式（这些由常量折叠处理，不需要移动）。
+//
+// 注：`each` 表达式的主体可能一次也不执行，优化时应该把循环不变量移到
+// 只有在被遍历的对象不为空时才执行的位置（见 `ir::LowerOptions`）。
+pub fn loop_invariants(expression: &Expression) -> Vec<&Expression> {
+    let (variable, body) = match expression {
+        Expression::ForExpression(v) => (v.initializer.object.as_ref(), v.body.as_ref()),
+        Expression::EachExpression(v) => (v.variable.as_ref(), v.body.as_ref()),
+        _ => return vec![],
+    };
+
+    let mut variant_names = pattern_names(variable);
+    collect_bound_names(body, &mut variant_names);
+
+    let mut invariants = vec![];
+    collect_loop_invariants(body, &variant_names, &mut invariants);
+    invariants
+}
+
+// 收集在表达式里绑定的所有名称（不区分作用域）
+fn collect_bound_names(expression: &Expression, names: &mut Vec<String>) {
+    match expression {
+        Expression::LetExpression(v) => names.extend(pattern_names(&v.object)),
+        Expression::EachExpression(v) => names.extend(pattern_names(&v.variable)),
+        _ => {}
+    }
+
+    for child in expression.children() {
+        collect_bound_names(child, names);
+    }
+}
+
+fn collect_loop_invariants<'a>(
+    expression: &'a Expression,
+    variant_names: &[String],
+    invariants: &mut Vec<&'a Expression>,
+) {
+    if is_hoistable(expression)
+        && is_pure(expression)
+        && free_variables_of(expression)
+            .iter()
+            .all(|v| !variant_names.contains(&v.name))
+    {
+        invariants.push(expression);
+        return;
+    }
+
+    for child in unconditional_children(expression) {
+        collect_loop_invariants(child, variant_names, invariants);
+    }
+}
+
+// 值得提取为临时变量（移到循环之前或者重复使用）的表达式
+fn is_hoistable(expression: &Expression) -> bool {
+    match expression {
+        Expression::BinaryExpression(_)
+        | Expression::UnaryExpression(_)
+        | Expression::MemberExpression(_)
+        | Expression::SliceExpression(_)
+        | Expression::ConstructorExpression(_)
+        | Expression::TupleConstructorExpression(_)
+        | Expression::Interval(_)
+        | Expression::Tuple(_)
+        | Expression::List(_)
+        | Expression::Map(_) => fold::fold(expression).is_none(),
+        _ => false,
+    }
+}
+
+// 列出表达式里的公共子表达式，每一组为值相同的各次出现，
+// 按照在源代码里出现的顺序排列。
+//
+// 只比较位于每次求值都会被求值的位置（见 `loop_invariants`）的纯表达式，
+// 所以第一次出现之后的各次出现都可以使用第一次的值；两次出现之间如果有 `let` 表达式
+// 重新绑定了其中的名称，则它们不再是同一个子表达式。
+//
+// 只列出最大的公共子表达式，比如 `(a + b) * c` 出现两次时，
+// 不会再单独列出 `a + b`（除非 `a + b` 在别的地方另外出现）。
+pub fn common_subexpressions(expression: &Expression) -> Vec<Vec<&Expression>> {
+    let mut collector = SubexpressionCollector::default();
+    collector.visit(expression);
+    let SubexpressionCollector {
+        active,
+        mut finished,
+    } = collector;
+    finished.extend(active);
+
+    let groups: Vec<Vec<&Expression>> = finished
+        .into_iter()
+        .map(|candidate| candidate.occurrences)
+        .filter(|occurrences| occurrences.len() > 1)
+        .collect();
+
+    // 排除各次出现都位于其他公共子表达式里的组
+    let mut enclosed: Vec<&Expression> = vec![];
+    for occurrence in groups.iter().flatten() {
+        collect_descendants(occurrence, &mut enclosed);
+    }
+
+    let mut groups: Vec<Vec<&Expression>> = groups
+        .into_iter()
+        .filter(|occurrences| {
+            !occurrences
+                .iter()
+                .all(|e| enclosed.iter().any(|d| std::ptr::eq(*d, *e)))
+        })
+        .collect();
+
+    let order = collect_order(expression);
+    groups.sort_by_key(|occurrences| order.iter().position(|e| std::ptr::eq(*e, occurrences[0])));
+    groups
+}
+
+struct Candidate<'a> {
+    key: String, // 表达式的文本，文本相同的纯表达式的值相同
+    names: Vec<String>,
+    occurrences: Vec<&'a Expression>,
+}
+
+#[derive(Default)]
+struct SubexpressionCollector<'a> {
+    active: Vec<Candidate<'a>>,
+    finished: Vec<Candidate<'a>>, // 其中的名称已被重新绑定，不再接受新的出现
+}
+
+impl<'a> SubexpressionCollector<'a> {
+    fn visit(&mut self, expression: &'a Expression) {
+        if let Expression::LetExpression(v) = expression {
+            // 名称在右手边值之后才被重新绑定
+            self.visit(&v.value);
+            self.rebind(&pattern_names(&v.object));
+            return;
+        }
+
+        if is_hoistable(expression) && is_pure(expression) {
+            let key = expression.to_string();
+            match self.active.iter_mut().find(|c| c.key == key) {
+                Some(candidate) => candidate.occurrences.push(expression),
+                None => self.active.push(Candidate {
+                    key,
+                    names: free_variables_of(expression)
+                        .into_iter()
+                        .map(|v| v.name)
+                        .collect(),
+                    occurrences: vec![expression],
+                }),
+            }
+        }
+
+        for child in unconditional_children(expression) {
+            self.visit(child);
+        }
+    }
+
+    fn rebind(&mut self, names: &[String]) {
+        let (killed, active): (Vec<Candidate>, Vec<Candidate>) = self
+            .active
+            .drain(..)
+            .partition(|c| c.names.iter().any(|n| names.contains(n)));
+        self.active = active;
+        self.finished.extend(killed);
+    }
+}
+
+fn collect_descendants<'a>(expression: &'a Expression, descendants: &mut Vec<&'a Expression>) {
+    for child in expression.children() {
+        descendants.push(child);
+        collect_descendants(child, descendants);
+    }
+}
+
+// 表达式以及所有子表达式，按照在源代码里出现的顺序排列
+fn collect_order(expression: &Expression) -> Vec<&Expression> {
+    let mut expressions = vec![expression];
+    collect_descendants(expression, &mut expressions);
+    expressions
+}
+
+// 每次求值表达式时都一定会被求值的直接子表达式
+fn unconditional_children(expression: &Expression) -> Vec<&Expression> {
+    match expression {
+        Expression::IfExpression(v) => v
+            .where_exp
+            .iter()
+            .map(|e| e.as_ref())
+            .chain([v.testing.as_ref()])
+            .collect(),
+        Expression::BranchExpression(v) => v.where_exp.iter().map(|e| e.as_ref()).collect(),
+        Expression::MatchExpression(v) => [v.object.as_ref()]
+            .into_iter()
+            .chain(v.where_exp.iter().map(|e| e.as_ref()))
+            .collect(),
+        Expression::ForExpression(v) => vec![v.initializer.value.as_ref()],
+        Expression::EachExpression(v) => vec![v.object.as_ref()],
+        Expression::AnonymousFunction(_) => vec![],
+        Expression::BinaryExpression(v)
+            if matches!(
+                v.operator,
+                BinaryOperator::LogicOr
+                    | BinaryOperator::LogicAnd
+                    | BinaryOperator::OptionalOr
+                    | BinaryOperator::OptionalAnd
+            ) =>
+        {
+            vec![v.left.as_ref()]
+        }
+        _ => expression.children(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Expression, Node, Statement},
+        lexer, parser,
+    };
+
+    use super::{common_subexpressions, is_pure, loop_invariants};
+
+    fn parse_expression(source: &str) -> Expression {
+        let token_details = lexer::tokenize(source).unwrap();
+        match parser::parse(&token_details).unwrap() {
+            Node::Program(program) => match program.body.into_iter().next().unwrap() {
+                Statement::Expression(expression) => expression,
+                _ => panic!("expected an expression"),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    fn invariant_texts(source: &str) -> Vec<String> {
+        let expression = parse_expression(source);
+        loop_invariants(&expression)
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_is_pure() {
+        assert!(is_pure(&parse_expression("a + b.c * 2")));
+        assert!(is_pure(&parse_expression("(a, [b, c], -d)")));
+        assert!(is_pure(&parse_expression("env(\"VERSION\") ++ a")));
+        assert!(!is_pure(&parse_expression("a + f(b)")));
+        assert!(!is_pure(&parse_expression("a | f")));
+        assert!(!is_pure(&parse_expression("a :bitOr: b")));
+    }
+
+    fn common_texts(source: &str) -> Vec<(String, usize)> {
+        let expression = parse_expression(source);
+        common_subexpressions(&expression)
+            .iter()
+            .map(|occurrences| (occurrences[0].to_string(), occurrences.len()))
+            .collect()
+    }
+
+    #[test]
+    fn test_common_subexpressions() {
+        assert_eq!(
+            common_texts("do {\nlet x = a.b + c\nlet y = a.b * 2\nx + y + a.b\n}"),
+            vec![("(a.b)".to_string(), 3)]
+        );
+
+        // 只列出最大的公共子表达式
+        assert_eq!(
+            common_texts("do {\nlet x = (a + b) * c\nlet y = (a + b) * c\nlet z = a + b\n}"),
+            vec![("((a + b) * c)".to_string(), 2), ("(a + b)".to_string(), 3)]
+        );
+
+        // 名称被重新绑定
+        assert!(common_texts("do {\nlet x = a + 1\nlet a = 2\nlet y = a + 1\n}").is_empty());
+
+        // 非纯表达式以及条件分支里的表达式
+        assert!(common_texts("do {\nlet x = f(a) + 1\nlet y = f(a) + 1\n}").is_empty());
+        assert!(common_texts("if a > 0 then a + 1 else a + 1").is_empty());
+    }
+
+    #[test]
+    fn test_loop_invariants() {
+        assert_eq!(
+            invariant_texts("each i in items {\nlet t = i * (a + b)\nt + c.d\n}"),
+            vec!["(a + b)", "(c.d)"]
+        );
+
+        // 循环变量以及循环里绑定的名称
+        assert_eq!(
+            invariant_texts("for let i = 0 {\nlet s = a * 2\nnext i + s * k\n}"),
+            vec!["(a * 2)"]
+        );
+
+        // 非纯表达式，常量，以及条件分支里的表达式
+        assert!(invariant_texts("each i in items {\nf(a + i)\n1 + 2\n}").is_empty());
+        assert_eq!(
+            invariant_texts("each i in items do {\nif a > b then c / i else d / e\n}"),
+            vec!["(a > b)"]
+        );
+        assert!(invariant_texts("each i in items do {\ni > 0 && a / b > 1\n}").is_empty());
+
+        // 不是循环
+        assert!(invariant_texts("a + b").is_empty());
+    }
+}
@@ -0,0 +1,500 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 函数重载的解析
+//
+// 程序里可以有多个同名、但参数的类型不同的函数，模式函数的子句也可以跟参数个数不同的
+// 同名函数构成重载（见 `resolve::is_compatible_definition`）。名称解析把调用处的名称
+// 解析到第一个同名的定义，本模块在名称解析之后，根据调用的参数从同名的函数（候选函数）
+// 里选择一个：
+//
+// 1. 按照参数的个数过滤：位置参数依次对应函数的参数，命名参数按照名称对应，
+//    没有对应的参数的函数参数必须有默认值；模式函数只接受位置参数，并且个数必须相同；
+// 2. 按照参数的类型过滤：参数的类型能够确定时（目前只有常量表达式，见 `fold` 模块），
+//    必须跟函数参数的类型相同，类型无法确定的参数以及泛型类型的函数参数不参与过滤；
+// 3. 剩下一个候选函数时解析成功，调用处的名称改为指向该函数（见 `Resolution::references`），
+//    剩下多个时报告 "调用有歧义"，一个都没有时报告 "没有匹配的函数"，
+//    两种错误都列出所有候选函数的签名以及位置（见 `Error::OverloadError`）。
+//
+// 参数个数相同的模式函数子句在运行时按照模式分派，所以作为一个候选函数；
+// 跟定义的参数类型相同的前向声明（空函数）不作为单独的候选函数。
+//
+// 注：
+// 类型检查实现之后，参数的类型改为使用推导的类型，并允许已定义的隐式转换。
+
+use crate::{
+    ast::{Argument, DataType, Expression, Program, Range, Statement},
+    error::Error,
+    fold,
+    resolve::{Resolution, Target},
+    symbols::SymbolKind,
+};
+
+// 候选函数
+struct Candidate {
+    symbol: usize, // 定义（模式函数为第一个子句）在 `SymbolTable::globals` 里的索引
+    definitions: Vec<usize>, // 所有定义（包括前向声明以及模式函数的其他子句）的索引
+    parameters: Vec<Parameter>,
+    is_pattern: bool,
+    signature: String, // 用于诊断信息，比如 `add(Int, Int)`
+    range: Range,
+}
+
+struct Parameter {
+    name: Option<String>,      // 模式函数的参数为 None
+    data_type: Option<String>, // 泛型类型以及没有标注类型时为 None
+    has_default_value: bool,
+}
+
+// 同名的候选函数
+struct OverloadSet {
+    name: String,
+    candidates: Vec<Candidate>,
+}
+
+// 解析所有调用重载函数的地方，并更新名称解析的结果，resolution 须为同一个程序的名称解析结果
+pub fn resolve_overloads(program: &Program, resolution: &mut Resolution) -> Vec<Error> {
+    let sets = collect_overload_sets(program, resolution);
+    if sets.is_empty() {
+        return vec![];
+    }
+
+    let mut selections: Vec<(Range, usize)> = vec![]; // 被调用者的范围以及选中的函数
+    let mut errors: Vec<Error> = vec![];
+    for statement in &program.body {
+        for expression in statement.expressions() {
+            resolve_calls(expression, &sets, resolution, &mut selections, &mut errors);
+        }
+    }
+
+    for reference in &mut resolution.references {
+        if let Some((_, symbol)) = selections.iter().find(|(r, _)| *r == reference.range) {
+            reference.target = Target::Symbol(*symbol);
+        }
+    }
+
+    resolution.overloads = sets
+        .into_iter()
+        .flat_map(|s| s.candidates.into_iter().map(|c| c.definitions))
+        .collect();
+
+    errors
+}
+
+fn collect_overload_sets(program: &Program, resolution: &Resolution) -> Vec<OverloadSet> {
+    let globals = &resolution.symbols.globals;
+    let find_symbol = |range: &Range| {
+        globals.iter().position(|s| {
+            s.kind == SymbolKind::Function && s.container.is_none() && s.range == *range
+        })
+    };
+
+    let mut sets: Vec<OverloadSet> = vec![];
+    for statement in &program.body {
+        let range = match statement {
+            Statement::FunctionDeclaration(f) => &f.range,
+            Statement::EmptyFunctionDeclaration(f) => &f.range,
+            Statement::PatternFunctionDeclaration(f) => &f.range,
+            _ => continue,
+        };
+
+        // 没有对应的符号的函数（比如手工构造的，或者清除了位置信息的节点）不参与重载解析
+        let Some(symbol) = find_symbol(range) else {
+            continue;
+        };
+
+        let (name, candidate) = match statement {
+            Statement::FunctionDeclaration(f) => (
+                &f.name,
+                Candidate {
+                    symbol,
+                    definitions: vec![symbol],
+                    parameters: f
+                        .parameters
+                        .iter()
+                        .map(|p| Parameter {
+                            name: Some(p.name.clone()),
+                            data_type: type_name(&p.data_type, &f.generics),
+                            has_default_value: p.value.is_some(),
+                        })
+                        .collect(),
+                    is_pattern: false,
+                    signature: signature(&f.name, f.parameters.iter().map(|p| &p.data_type)),
+                    range: f.range.clone(),
+                },
+            ),
+            Statement::EmptyFunctionDeclaration(f) => (
+                &f.name,
+                Candidate {
+                    symbol,
+                    definitions: vec![symbol],
+                    parameters: f
+                        .parameters
+                        .iter()
+                        .map(|p| Parameter {
+                            name: Some(p.name.clone()),
+                            data_type: type_name(&p.data_type, &f.generics),
+                            has_default_value: false,
+                        })
+                        .collect(),
+                    is_pattern: false,
+                    signature: signature(&f.name, f.parameters.iter().map(|p| &p.data_type)),
+                    range: f.range.clone(),
+                },
+            ),
+            Statement::PatternFunctionDeclaration(f) => (
+                &f.name,
+                Candidate {
+                    symbol,
+                    definitions: vec![symbol],
+                    parameters: f
+                        .parameters
+                        .iter()
+                        .map(|p| Parameter {
+                            name: None,
+                            data_type: p.data_type.as_ref().and_then(|t| type_name(t, &f.generics)),
+                            has_default_value: false,
+                        })
+                        .collect(),
+                    is_pattern: true,
+                    signature: format!(
+                        "pattern {}({})",
+                        f.name,
+                        f.parameters
+                            .iter()
+                            .map(|p| p
+                                .data_type
+                                .as_ref()
+                                .map_or("_".to_string(), |t| t.to_string()))
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    ),
+                    range: f.range.clone(),
+                },
+            ),
+            _ => continue,
+        };
+
+        let set = match sets.iter_mut().find(|s| s.name == *name) {
+            Some(set) => set,
+            None => {
+                sets.push(OverloadSet {
+                    name: name.clone(),
+                    candidates: vec![],
+                });
+                sets.last_mut().unwrap()
+            }
+        };
+
+        // 参数个数相同的模式函数子句、前向声明以及定义只保留第一个
+        let is_same = |c: &Candidate| {
+            c.is_pattern == candidate.is_pattern
+                && c.parameters.len() == candidate.parameters.len()
+                && (candidate.is_pattern
+                    || c.parameters
+                        .iter()
+                        .zip(&candidate.parameters)
+                        .all(|(a, b)| a.data_type == b.data_type))
+        };
+        match set.candidates.iter_mut().find(|c| is_same(c)) {
+            Some(c) => {
+                c.definitions.push(symbol);
+
+                // 前向声明之后的定义：诊断信息指向定义
+                if !c.is_pattern && matches!(statement, Statement::FunctionDeclaration(_)) {
+                    let definitions = std::mem::take(&mut c.definitions);
+                    *c = Candidate {
+                        definitions,
+                        ..candidate
+                    };
+                }
+            }
+            None => set.candidates.push(candidate),
+        }
+    }
+
+    sets.retain(|s| s.candidates.len() > 1);
+    sets
+}
+
+fn resolve_calls(
+    expression: &Expression,
+    sets: &[OverloadSet],
+    resolution: &Resolution,
+    selections: &mut Vec<(Range, usize)>,
+    errors: &mut Vec<Error>,
+) {
+    for child in expression.children() {
+        resolve_calls(child, sets, resolution, selections, errors);
+    }
+
+    let Expression::FunctionCallExpression(call) = expression else {
+        return;
+    };
+    let Expression::Identifier(callee) = call.callee.as_ref() else {
+        return;
+    };
+
+    // 被局部名称遮盖的函数名称不会被解析到全局符号
+    let is_resolved = resolution.references.iter().any(|r| {
+        r.range == callee.range
+            && matches!(r.target, Target::Symbol(index)
+                if resolution.symbols.globals[index].kind == SymbolKind::Function)
+    });
+    if !is_resolved || !callee.dirs.is_empty() {
+        return;
+    }
+    let Some(set) = sets.iter().find(|s| s.name == callee.name) else {
+        return;
+    };
+
+    let matches: Vec<&Candidate> = set
+        .candidates
+        .iter()
+        .filter(|c| is_applicable(c, &call.arguments))
+        .collect();
+
+    let message = match matches.as_slice() {
+        [candidate] => {
+            selections.push((callee.range.clone(), candidate.symbol));
+            return;
+        }
+        [] => format!(
+            "no matching function for call to \"{}\" with {} argument(s)",
+            set.name,
+            call.arguments.len()
+        ),
+        _ => format!(
+            "ambiguous call to overloaded function \"{}\", {} candidates match",
+            set.name,
+            matches.len()
+        ),
+    };
+
+    // 有歧义时只列出匹配的候选函数
+    let listed = if matches.is_empty() {
+        set.candidates.iter().collect()
+    } else {
+        matches
+    };
+    errors.push(Error::OverloadError {
+        message,
+        candidates: listed
+            .iter()
+            .map(|c| (format!("candidate: {}", c.signature), c.range.clone()))
+            .collect(),
+        range: call.range.clone(),
+    });
+}
+
+// 候选函数是否接受调用的参数（个数、名称以及能够确定的类型）
+fn is_applicable(candidate: &Candidate, arguments: &[Argument]) -> bool {
+    let parameters = &candidate.parameters;
+    let mut assigned: Vec<Option<&Argument>> = vec![None; parameters.len()];
+
+    let mut position = 0;
+    for argument in arguments {
+        let index = match &argument.name {
+            // 模式函数只接受位置参数
+            Some(_) if candidate.is_pattern => return false,
+            Some(name) => parameters
+                .iter()
+                .position(|p| p.name.as_deref() == Some(name.as_str())),
+            None => {
+                position += 1;
+                Some(position - 1).filter(|index| *index < parameters.len())
+            }
+        };
+
+        match index {
+            Some(index) if assigned[index].is_none() => assigned[index] = Some(argument),
+            _ => return false,
+        }
+    }
+
+    parameters
+        .iter()
+        .zip(&assigned)
+        .all(|(parameter, argument)| match argument {
+            Some(argument) => {
+                let argument_type = fold::fold(&argument.value).map(|c| c.type_name());
+                match (&parameter.data_type, argument_type) {
+                    (Some(expected), Some(actual)) => expected == actual,
+                    _ => true,
+                }
+            }
+            None => parameter.has_default_value,
+        })
+}
+
+// 参数的类型名称，泛型类型为 None
+fn type_name(data_type: &DataType, generics: &[DataType]) -> Option<String> {
+    let name = data_type.to_string();
+    (!generics.iter().any(|g| g.to_string() == name)).then_some(name)
+}
+
+fn signature<'a>(name: &str, data_types: impl Iterator<Item = &'a DataType>) -> String {
+    format!(
+        "{}({})",
+        name,
+        data_types
+            .map(|t| t.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{MutVisitor, Node, Program, RangeClearer},
+        diagnostic::Diagnostic,
+        error::Error,
+        lexer, parser,
+        resolve::{self, Resolution, Target},
+    };
+
+    use super::resolve_overloads;
+
+    fn parse(source: &str) -> Program {
+        let token_details = lexer::tokenize(source).unwrap();
+        match parser::parse(&token_details).unwrap() {
+            Node::Program(program) => program,
+            _ => unreachable!(),
+        }
+    }
+
+    fn resolve_from_string(source: &str) -> (Resolution, Vec<Error>) {
+        let program = parse(source);
+        let mut resolution = resolve::resolve(&program, &["writeLine", "Int", "String"]);
+        assert!(resolution.errors.is_empty());
+        let errors = resolve_overloads(&program, &mut resolution);
+        (resolution, errors)
+    }
+
+    // 每个调用 `name` 的地方被解析到的函数定义在源代码里的行号（从 1 开始）
+    fn selected_lines(source: &str, name: &str) -> Vec<usize> {
+        let (resolution, errors) = resolve_from_string(source);
+        assert!(errors.is_empty(), "{:?}", errors);
+        resolution
+            .references
+            .iter()
+            .filter(|r| r.name == name)
+            .map(|r| match r.target {
+                Target::Symbol(index) => {
+                    let start = resolution.symbols.globals[index].range.start;
+                    source[..start].matches('\n').count() + 1
+                }
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_by_arity() {
+        let source = "function area(Int r) = r * r * 3\n\
+            function area(Int w, Int h) = w * h\n\
+            function f(Int a) = area(a) + area(a, 2)";
+        assert_eq!(selected_lines(source, "area"), vec![1, 2]);
+
+        // 默认值以及命名参数
+        let source = "function show(Int a) = a\n\
+            function show(Int a, Int b, Int c = 0) = a\n\
+            function f(Int x) = show(x, b = 1) + show(x, 1, 2) + show(a = x)";
+        assert_eq!(selected_lines(source, "show"), vec![2, 2, 1]);
+
+        // 模式函数的子句按照参数的个数分组
+        let source = "pattern function len(Int i @ in [0..10]) = 0\n\
+            pattern function len(Int i) = 1\n\
+            function len(Int a, Int b) = a\n\
+            function f(Int x) = len(x) + len(x, x)";
+        assert_eq!(selected_lines(source, "len"), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_resolve_by_type() {
+        let source = "function show(Int a) = a\n\
+            function show(String s) = 0\n\
+            show(1)\n\
+            show(\"foo\")\n\
+            show(1 + 2)";
+        assert_eq!(selected_lines(source, "show"), vec![1, 2, 1]);
+
+        // 泛型类型的参数接受任意类型，前向声明跟定义作为同一个候选函数
+        let source = "empty function id(Int a) type Int\n\
+            function id(Int a) type Int = a\n\
+            function id<T>(T a, T b) type T = a\n\
+            id(1)\n\
+            id(\"a\", \"b\")";
+        assert_eq!(selected_lines(source, "id"), vec![2, 3]);
+
+        // 被局部名称遮盖的函数
+        let source = "function show(Int a) = a\n\
+            function show(String s) = 0\n\
+            function f(Int show) = show";
+        assert!(selected_lines(source, "show").is_empty());
+    }
+
+    #[test]
+    fn test_overload_errors() {
+        let source = "function show(Int a) = a\n\
+            function show(String s) = 0\n\
+            function f(Int x) = show(x)";
+        let (_, errors) = resolve_from_string(source);
+        assert_eq!(
+            errors[0].to_string(),
+            "ambiguous call to overloaded function \"show\", 2 candidates match"
+        );
+        assert_eq!(
+            Diagnostic::from(&errors[0]).render("a.xuan", source),
+            "\
+error[E0301]: ambiguous call to overloaded function \"show\", 2 candidates match
+ --> a.xuan:3:21
+  |
+1 | function show(Int a) = a
+  | ------------------------ candidate: show(Int)
+2 | function show(String s) = 0
+  | --------------------------- candidate: show(String)
+3 | function f(Int x) = show(x)
+  |                     ^^^^^^^
+"
+        );
+
+        // 没有匹配的函数时列出所有的候选函数
+        let (_, errors) = resolve_from_string(
+            "function show(Int a) = a\n\
+            function show(Int a, Int b) = a\n\
+            show(\"foo\")\n\
+            show(1, c = 2)",
+        );
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "no matching function for call to \"show\" with 1 argument(s)",
+                "no matching function for call to \"show\" with 2 argument(s)"
+            ]
+        );
+        assert_eq!(errors[0].related().len(), 2);
+
+        // 签名相同的函数仍然是重复的定义
+        let program = parse("function f(Int a) = a\nfunction f(Int b) = b");
+        let resolution = resolve::resolve(&program, &["Int"]);
+        assert_eq!(
+            resolution.errors[0].to_string(),
+            "duplicate definition \"f\""
+        );
+
+        // 没有对应的符号的函数（比如清除了位置信息的节点）不参与重载解析
+        let program = parse("function show(Int a) = a\nfunction show(String s) = 0\nshow(1)");
+        let mut resolution = resolve::resolve(&program, &["Int", "String"]);
+        let mut cleared = program.clone();
+        RangeClearer.visit_program(&mut cleared);
+        assert!(resolve_overloads(&cleared, &mut resolution).is_empty());
+    }
+}
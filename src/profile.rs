@@ -0,0 +1,227 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 词法分析以及语法分析的内存统计
+//
+// 统计处理大型源文件（比如数 MB 的源文件）时的：
+//
+// - 源码的字节数、字符数，以及词法分析器的字符缓冲区（`Vec<char>`）的字节数；
+// - Token 的数量以及 Token 列表占用的字节数（不包括字符串等在堆上的内容）；
+// - AST 的节点（语句以及表达式）数量；
+// - 词法分析以及语法分析期间的内存分配峰值。
+//
+// 内存分配峰值需要程序使用 `CountingAllocator` 作为全局的内存分配器，比如：
+//
+// ```ignore
+// #[global_allocator]
+// static ALLOCATOR: front_end::profile::CountingAllocator = front_end::profile::CountingAllocator;
+// ```
+//
+// 否则峰值为 None。
+//
+// 统计结果用于评估字符缓冲区以及 AST 节点的内存布局（比如 arena 分配）等优化。
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Display,
+    mem,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    ast::{Expression, Node},
+    error::Error,
+    lexer, parser,
+    token::TokenDetail,
+};
+
+static CURRENT_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+// 记录当前已分配的字节数以及峰值的内存分配器，实际的分配由系统分配器完成
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            CURRENT_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+fn record_alloc(size: usize) {
+    let current = CURRENT_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_BYTES.fetch_max(current, Ordering::Relaxed);
+}
+
+impl CountingAllocator {
+    // 是否已作为全局的内存分配器，程序在进入 main 之前总会分配内存
+    pub fn is_installed() -> bool {
+        PEAK_BYTES.load(Ordering::Relaxed) > 0
+    }
+
+    pub fn current_bytes() -> usize {
+        CURRENT_BYTES.load(Ordering::Relaxed)
+    }
+
+    pub fn peak_bytes() -> usize {
+        PEAK_BYTES.load(Ordering::Relaxed)
+    }
+
+    // 把峰值重置为当前已分配的字节数，以便统计接下来的一个阶段
+    pub fn reset_peak() {
+        PEAK_BYTES.store(CURRENT_BYTES.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub source_bytes: usize,
+    pub chars: usize,
+    pub char_buffer_bytes: usize, // 词法分析器的字符缓冲区（`Vec<char>`）
+    pub tokens: usize,
+    pub token_bytes: usize, // Token 列表本身的大小，不包括在堆上的内容
+    pub nodes: usize,       // 语句以及表达式的数量
+
+    // 阶段期间分配的内存的峰值（相对于阶段开始时已分配的字节数），
+    // 没有使用 `CountingAllocator` 时为 None
+    pub tokenize_peak_bytes: Option<usize>,
+    pub parse_peak_bytes: Option<usize>,
+}
+
+impl Display for MemoryStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let peak = |bytes: Option<usize>| match bytes {
+            Some(bytes) => format!("{} bytes", bytes),
+            None => "unknown".to_string(),
+        };
+
+        writeln!(
+            f,
+            "source: {} bytes, {} chars ({} bytes buffered)",
+            self.source_bytes, self.chars, self.char_buffer_bytes
+        )?;
+        writeln!(f, "tokens: {} ({} bytes)", self.tokens, self.token_bytes)?;
+        writeln!(f, "nodes: {}", self.nodes)?;
+        writeln!(
+            f,
+            "peak memory of tokenize: {}",
+            peak(self.tokenize_peak_bytes)
+        )?;
+        writeln!(f, "peak memory of parse: {}", peak(self.parse_peak_bytes))
+    }
+}
+
+// 对源码进行词法分析以及语法分析，并统计内存的使用情况
+pub fn memory_stats(source: &str) -> Result<MemoryStats, Error> {
+    let (token_details, tokenize_peak_bytes) = measure(|| lexer::tokenize(source));
+    let token_details = token_details?;
+
+    let (node, parse_peak_bytes) = measure(|| parser::parse(&token_details));
+    let node = node?;
+
+    let chars = source.chars().count();
+
+    Ok(MemoryStats {
+        source_bytes: source.len(),
+        chars,
+        char_buffer_bytes: chars * mem::size_of::<char>(),
+        tokens: token_details.len(),
+        token_bytes: token_details.len() * mem::size_of::<TokenDetail>(),
+        nodes: count_nodes(&node),
+        tokenize_peak_bytes,
+        parse_peak_bytes,
+    })
+}
+
+fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<usize>) {
+    if !CountingAllocator::is_installed() {
+        return (f(), None);
+    }
+
+    CountingAllocator::reset_peak();
+    let base = CountingAllocator::current_bytes();
+    let result = f();
+    let peak = CountingAllocator::peak_bytes().saturating_sub(base);
+    (result, Some(peak))
+}
+
+fn count_nodes(node: &Node) -> usize {
+    match node {
+        Node::Program(program) => program
+            .body
+            .iter()
+            .map(|statement| {
+                1 + statement
+                    .expressions()
+                    .into_iter()
+                    .map(count_expression_nodes)
+                    .sum::<usize>()
+            })
+            .sum(),
+        Node::Statement(statement) => {
+            1 + statement
+                .expressions()
+                .into_iter()
+                .map(count_expression_nodes)
+                .sum::<usize>()
+        }
+        Node::Expression(expression) => count_expression_nodes(expression),
+    }
+}
+
+fn count_expression_nodes(expression: &Expression) -> usize {
+    1 + expression
+        .children()
+        .into_iter()
+        .map(count_expression_nodes)
+        .sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::memory_stats;
+
+    #[test]
+    fn test_memory_stats() {
+        let stats = memory_stats("let a = 1 + 2\nlet s = \"中文\"").unwrap();
+
+        assert_eq!(stats.source_bytes, 30);
+        assert_eq!(stats.chars, 26);
+        assert_eq!(stats.char_buffer_bytes, 104);
+
+        // let a = 1 + 2 NEW_LINE let s = "中文"
+        assert_eq!(stats.tokens, 11);
+        assert!(stats.token_bytes > 0);
+
+        // 语句 2 个，`let` 表达式 2 个，`a`、`1 + 2`、`1`、`2`、`s` 以及字符串各 1 个
+        assert_eq!(stats.nodes, 10);
+
+        // 测试程序没有使用 `CountingAllocator`
+        assert_eq!(stats.tokenize_peak_bytes, None);
+        assert_eq!(stats.parse_peak_bytes, None);
+
+        assert!(memory_stats("let a = (").is_err());
+    }
+}
@@ -0,0 +1,107 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// Python 接口
+//
+// 提供 `xuanparser` 模块，供基于脚本的工具（比如代码检查工具、
+// 课程作业自动评分程序等）在 Python 里调用词法分析器和语法分析器。
+//
+// e.g.
+//
+// ```python
+// import json
+// import xuanparser
+//
+// tokens = json.loads(xuanparser.tokenize("1 + 2"))
+// ast = xuanparser.parse("let a = 1 + 2")
+// print(ast)                      # let a = (1 + 2)
+// tree = json.loads(ast.to_json())
+// ```
+
+use pyo3::{create_exception, exceptions::PyValueError, prelude::*};
+
+use crate::{ast::Node, error::Error, json, lexer, parser};
+
+create_exception!(xuanparser, LexerError, PyValueError);
+create_exception!(xuanparser, ParserError, PyValueError);
+
+// 语法分析的结果
+#[pyclass(name = "Ast", frozen)]
+pub struct PyAst {
+    node: Node,
+}
+
+#[pymethods]
+impl PyAst {
+    // 以 JSON 文档的格式返回 AST（格式见 `docs/ast_json_schema.md`）
+    fn to_json(&self) -> String {
+        json::to_json(&self.node)
+    }
+
+    fn __str__(&self) -> String {
+        self.node.to_string()
+    }
+}
+
+// 对源代码进行词法分析，以 JSON 数组的格式返回所有 Token
+#[pyfunction]
+fn tokenize(source: &str) -> PyResult<String> {
+    let token_details = lexer::tokenize(source).map_err(convert_error)?;
+    Ok(serde_json::to_string(&token_details).unwrap())
+}
+
+// 对源代码进行语法分析
+#[pyfunction]
+fn parse(source: &str) -> PyResult<PyAst> {
+    let node = parse_source(source).map_err(convert_error)?;
+    Ok(PyAst { node })
+}
+
+// 对源代码进行语法分析，以 JSON 文档的格式返回 AST（格式见 `docs/ast_json_schema.md`）
+#[pyfunction]
+fn parse_to_json(source: &str) -> PyResult<String> {
+    let node = parse_source(source).map_err(convert_error)?;
+    Ok(json::to_json(&node))
+}
+
+#[pymodule]
+fn xuanparser(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(tokenize, m)?)?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_to_json, m)?)?;
+    m.add_class::<PyAst>()?;
+    m.add("LexerError", m.py().get_type::<LexerError>())?;
+    m.add("ParserError", m.py().get_type::<ParserError>())?;
+    Ok(())
+}
+
+fn parse_source(source: &str) -> Result<Node, Error> {
+    let token_details = lexer::tokenize(source)?;
+    parser::parse(&token_details)
+}
+
+fn convert_error(error: Error) -> PyErr {
+    if error.is_lexer_error() {
+        LexerError::new_err(error.to_string())
+    } else {
+        ParserError::new_err(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_source;
+
+    #[test]
+    fn test_parse_source() {
+        let n1 = parse_source("let a = 1 + 2").unwrap();
+        assert_eq!(n1.to_string(), "let a = (1 + 2)\n");
+
+        assert!(parse_source("let = 1").is_err());
+    }
+}
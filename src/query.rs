@@ -0,0 +1,442 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 增量编译的查询数据库
+//
+// 编译过程由一组查询（query）组成，每个查询的结果都会被缓存（memoization），
+// 并记录它依赖的其他查询，输入（源文件）改变之后只重新计算依赖于已改变内容的查询，
+// 这是语言服务器能够快速响应的基础。
+//
+// 目前的查询以及依赖关系：
+//
+// ```text
+// source(file)                   输入，由 `set_source` 设置
+//  └─ parse(file)                语法分析
+//      ├─ function(file, name)   指定名称的函数定义
+//      │   └─ function_errors(file, name)   函数的语义错误
+//      ├─ interface(file)        模块的公开接口
+//      ├─ imports(file)          导入的模块的名称
+//      └─ expression_types(file) 表达式的类型（见 `typed` 模块），`type_at(file, offset)` 据此查询
+// ```
+//
+// 每个输入以及查询的结果记录两个修订号（revision）：
+//
+// - changed_at：结果最后一次改变时的修订号；
+// - verified_at：结果最后一次被确认有效时的修订号。
+//
+// 读取查询的结果时，如果依赖的查询的 changed_at 不大于当前结果的 verified_at，
+// 说明依赖没有改变，直接使用缓存的结果，否则重新计算。重新计算得到的结果如果跟
+// 原来的结果相同，则保留原来的 changed_at（即 "提前截止"，early cutoff），
+// 所以修改一个函数的主体之后，虽然 `parse` 以及所有的 `function` 查询都会重新计算，
+// 但位置没有改变的其他函数的 `function_errors` 不会重新计算。因为语义错误包含位置，
+// 后面的函数的位置改变时，它们的 `function_errors` 仍需重新计算，而模块接口
+// 不记录位置（见 `interface::module_interface`），所以仍然可以提前截止。
+//
+// 注：
+// 名称解析（`resolve(module)`）以及类型推导（`type_of(def)`）尚未实现，
+// 实现之后作为新的查询加入，见 `docs/roadmap.md`。
+
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use crate::{
+    ast::{DataType, FunctionDeclaration, Node, Program, Range, Statement},
+    interface::{self, ModuleInterface},
+    lexer, parser, semantics, typed,
+};
+
+type FunctionKey = (usize, String); // 文件 id 以及函数名称
+type ExpressionTypes = Vec<(Range, DataType)>; // 表达式的范围以及类型
+
+struct Memo<V> {
+    value: Rc<V>,
+    changed_at: u64,
+    verified_at: u64,
+}
+
+#[derive(Default)]
+pub struct Database {
+    revision: u64,
+    // 源文件的内容以及 changed_at，已删除的源文件的内容为 None
+    sources: BTreeMap<usize, (Option<Rc<String>>, u64)>,
+
+    parses: RefCell<BTreeMap<usize, Memo<Result<Program, String>>>>,
+    functions: RefCell<BTreeMap<FunctionKey, Memo<Option<FunctionDeclaration>>>>,
+    function_errors: RefCell<BTreeMap<FunctionKey, Memo<Vec<String>>>>,
+    interfaces: RefCell<BTreeMap<usize, Memo<ModuleInterface>>>,
+    imports: RefCell<BTreeMap<usize, Memo<Vec<String>>>>,
+    expression_types: RefCell<BTreeMap<usize, Memo<ExpressionTypes>>>,
+
+    // 重新计算过的查询，比如 "parse(0)"，用于调试以及测试
+    executions: RefCell<Vec<String>>,
+}
+
+impl Database {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    // 设置源文件的内容，内容跟原来的相同时不会产生新的修订
+    pub fn set_source(&mut self, file_id: usize, text: &str) {
+        if matches!(self.sources.get(&file_id), Some((Some(old), _)) if old.as_str() == text) {
+            return;
+        }
+
+        self.revision += 1;
+        self.sources
+            .insert(file_id, (Some(Rc::new(text.to_string())), self.revision));
+    }
+
+    pub fn remove_source(&mut self, file_id: usize) {
+        if let Some((text @ Some(_), changed_at)) = self.sources.get_mut(&file_id) {
+            self.revision += 1;
+            *text = None;
+            *changed_at = self.revision;
+        }
+    }
+
+    pub fn source(&self, file_id: usize) -> Option<Rc<String>> {
+        self.sources
+            .get(&file_id)
+            .and_then(|(text, _)| text.clone())
+    }
+
+    // 取出并清空重新计算过的查询的列表
+    pub fn take_executions(&self) -> Vec<String> {
+        self.executions.take()
+    }
+
+    // 语法分析的结果，错误以错误信息表示，不存在的源文件视为空的源文件
+    pub fn parse(&self, file_id: usize) -> Rc<Result<Program, String>> {
+        self.parse_query(file_id).0
+    }
+
+    // 指定名称的函数定义
+    pub fn function(&self, file_id: usize, name: &str) -> Rc<Option<FunctionDeclaration>> {
+        self.function_query(file_id, name).0
+    }
+
+    // 函数的语义错误（见 `semantics` 模块）
+    pub fn function_errors(&self, file_id: usize, name: &str) -> Rc<Vec<String>> {
+        let key = (file_id, name.to_string());
+        let label = format!("function_errors({}, {})", file_id, name);
+
+        self.memoize(
+            &self.function_errors,
+            key,
+            label,
+            || self.function_query(file_id, name),
+            |function| match function.as_ref() {
+                Some(f) => {
+                    semantics::check(&Node::Statement(Statement::FunctionDeclaration(f.clone())))
+                        .iter()
+                        .map(|e| e.to_string())
+                        .collect()
+                }
+                None => vec![],
+            },
+        )
+        .0
+    }
+
+    // 模块的公开接口，语法分析出错时为空的接口
+    pub fn interface(&self, file_id: usize) -> Rc<ModuleInterface> {
+        self.memoize(
+            &self.interfaces,
+            file_id,
+            format!("interface({})", file_id),
+            || self.parse_query(file_id),
+            |program| match program.as_ref() {
+                Ok(program) => interface::module_interface(program),
+                Err(_) => ModuleInterface::default(),
+            },
+        )
+        .0
+    }
+
+    // 源文件导入的模块的名称，即 `use` 语句的路径的第一个名称（已排序并去重），
+    // 模块的接口改变时，导入它的源文件需要重新检查。语法分析出错时为空
+    pub fn imports(&self, file_id: usize) -> Rc<Vec<String>> {
+        self.memoize(
+            &self.imports,
+            file_id,
+            format!("imports({})", file_id),
+            || self.parse_query(file_id),
+            |program| {
+                let mut names: Vec<String> = match program.as_ref() {
+                    Ok(program) => program
+                        .body
+                        .iter()
+                        .filter_map(|statement| match statement {
+                            Statement::UseStatement(u) => Some(u.imports()),
+                            _ => None,
+                        })
+                        .flatten()
+                        .filter_map(|(path, _)| path.into_iter().next())
+                        .collect(),
+                    Err(_) => vec![],
+                };
+                names.sort();
+                names.dedup();
+                names
+            },
+        )
+        .0
+    }
+
+    // 已确定类型的表达式的范围以及类型，语法分析出错时为空
+    pub fn expression_types(&self, file_id: usize) -> Rc<ExpressionTypes> {
+        self.memoize(
+            &self.expression_types,
+            file_id,
+            format!("expression_types({})", file_id),
+            || self.parse_query(file_id),
+            |program| match program.as_ref() {
+                Ok(program) => typed::expression_types(program),
+                Err(_) => vec![],
+            },
+        )
+        .0
+    }
+
+    // 覆盖字节偏移量 offset 的最内层的、已确定类型的表达式的类型（见 `typed::type_at`）
+    pub fn type_at(&self, file_id: usize, offset: usize) -> Option<DataType> {
+        typed::type_at(&self.expression_types(file_id), offset).cloned()
+    }
+
+    fn parse_query(&self, file_id: usize) -> (Rc<Result<Program, String>>, u64) {
+        let (text, changed_at) = match self.sources.get(&file_id) {
+            Some((Some(text), changed_at)) => (text.clone(), *changed_at),
+            Some((None, changed_at)) => (Rc::new(String::new()), *changed_at),
+            None => (Rc::new(String::new()), 0),
+        };
+
+        self.memoize(
+            &self.parses,
+            file_id,
+            format!("parse({})", file_id),
+            || (text, changed_at),
+            |text| {
+                let token_details = lexer::tokenize(&text).map_err(|e| e.to_string())?;
+                match parser::parse(&token_details).map_err(|e| e.to_string())? {
+                    Node::Program(program) => Ok(program),
+                    _ => unreachable!(),
+                }
+            },
+        )
+    }
+
+    fn function_query(&self, file_id: usize, name: &str) -> (Rc<Option<FunctionDeclaration>>, u64) {
+        self.memoize(
+            &self.functions,
+            (file_id, name.to_string()),
+            format!("function({}, {})", file_id, name),
+            || self.parse_query(file_id),
+            |program| {
+                program.as_ref().as_ref().ok().and_then(|program| {
+                    program.body.iter().find_map(|statement| match statement {
+                        Statement::FunctionDeclaration(f) if f.name == name => Some(f.clone()),
+                        _ => None,
+                    })
+                })
+            },
+        )
+    }
+
+    // 读取查询的结果，必要时重新计算
+    //
+    // `dependency` 返回依赖的值以及它的 changed_at，只在缓存的结果尚未在当前修订确认时调用；
+    // 返回查询的结果以及它的 changed_at。
+    fn memoize<K: Ord, D, V: PartialEq>(
+        &self,
+        table: &RefCell<BTreeMap<K, Memo<V>>>,
+        key: K,
+        label: String,
+        dependency: impl FnOnce() -> (D, u64),
+        compute: impl FnOnce(D) -> V,
+    ) -> (Rc<V>, u64) {
+        if let Some(memo) = table.borrow().get(&key) {
+            if memo.verified_at == self.revision {
+                return (memo.value.clone(), memo.changed_at);
+            }
+        }
+
+        let (input, dependency_changed_at) = dependency();
+
+        let mut table_ref = table.borrow_mut();
+        if let Some(memo) = table_ref.get_mut(&key) {
+            if dependency_changed_at <= memo.verified_at {
+                memo.verified_at = self.revision;
+                return (memo.value.clone(), memo.changed_at);
+            }
+        }
+        drop(table_ref);
+
+        self.executions.borrow_mut().push(label);
+        let value = compute(input);
+
+        let mut table_ref = table.borrow_mut();
+        let changed_at = match table_ref.get(&key) {
+            // 结果没有改变，保留原来的 changed_at
+            Some(memo) if *memo.value == value => memo.changed_at,
+            _ => self.revision,
+        };
+        let value = match table_ref.get(&key) {
+            Some(memo) if changed_at == memo.changed_at => memo.value.clone(),
+            _ => Rc::new(value),
+        };
+
+        table_ref.insert(
+            key,
+            Memo {
+                value: value.clone(),
+                changed_at,
+                verified_at: self.revision,
+            },
+        );
+        (value, changed_at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Database;
+
+    #[test]
+    fn test_memoization() {
+        let mut db = Database::new();
+        db.set_source(0, "function f(Int a) = a + 1\nfunction g(Int b) = b * 2");
+
+        assert!(db.function(0, "f").is_some());
+        assert!(db.function_errors(0, "g").is_empty());
+        assert_eq!(
+            db.take_executions(),
+            vec![
+                "parse(0)",
+                "function(0, f)",
+                "function(0, g)",
+                "function_errors(0, g)"
+            ]
+        );
+
+        // 没有改变时使用缓存的结果
+        db.function_errors(0, "g");
+        db.set_source(0, "function f(Int a) = a + 1\nfunction g(Int b) = b * 2");
+        db.function_errors(0, "g");
+        assert!(db.take_executions().is_empty());
+        assert_eq!(db.revision(), 1);
+    }
+
+    #[test]
+    fn test_early_cutoff() {
+        let mut db = Database::new();
+        db.set_source(0, "function f(Int a) = a + 1\nfunction g(Int b) = b * 2");
+        db.function_errors(0, "f");
+        db.function_errors(0, "g");
+        db.interface(0);
+        db.take_executions();
+
+        // 修改函数 f 的主体，函数 g 的语义检查以及模块接口不需要重新计算
+        db.set_source(0, "function f(Int a) = a + 2\nfunction g(Int b) = b * 2");
+        db.function_errors(0, "f");
+        db.function_errors(0, "g");
+        db.interface(0);
+        assert_eq!(
+            db.take_executions(),
+            vec![
+                "parse(0)",
+                "function(0, f)",
+                "function_errors(0, f)",
+                "function(0, g)",
+                "interface(0)"
+            ]
+        );
+
+        // 函数 f 的主体的长度改变，函数 g 的位置随之改变，但模块接口不变
+        let before = db.interface(0);
+        db.set_source(0, "function f(Int a) = a + 20\nfunction g(Int b) = b * 2");
+        db.function_errors(0, "g");
+        assert_eq!(db.interface(0), before);
+        assert_eq!(
+            db.take_executions(),
+            vec![
+                "parse(0)",
+                "function(0, g)",
+                "function_errors(0, g)",
+                "interface(0)"
+            ]
+        );
+
+        // 修改函数 g 的签名，模块接口改变
+        let before = db.interface(0);
+        db.set_source(0, "function f(Int a) = a + 20\nfunction g(Float b) = b * 2");
+        assert_ne!(db.interface(0), before);
+    }
+
+    #[test]
+    fn test_imports() {
+        let mut db = Database::new();
+        db.set_source(
+            0,
+            "use lib::{add, sub::one as two}\nuse std::io\nuse lib::mul",
+        );
+        assert_eq!(*db.imports(0), vec!["lib", "std"]);
+
+        // 修改函数的主体时导入的模块不变，结果保留原来的 changed_at
+        db.set_source(1, "use lib::add\nfunction f(Int a) = add(a, 1)");
+        db.imports(1);
+        db.take_executions();
+        db.set_source(1, "use lib::add\nfunction f(Int a) = add(a, 2)");
+        assert_eq!(*db.imports(1), vec!["lib"]);
+        assert_eq!(db.take_executions(), vec!["parse(1)", "imports(1)"]);
+
+        db.set_source(1, "let a = (");
+        assert!(db.imports(1).is_empty());
+    }
+
+    #[test]
+    fn test_type_at() {
+        let mut db = Database::new();
+        db.set_source(0, "let a = 1\nlet b = a > 0");
+        assert_eq!(db.type_at(0, 4).unwrap().to_string(), "Int");
+        assert_eq!(db.type_at(0, 18).unwrap().to_string(), "Int");
+        assert_eq!(db.type_at(0, 20).unwrap().to_string(), "Boolean");
+        assert!(db.type_at(0, 7).is_none());
+
+        // 多次查询使用缓存的结果
+        assert_eq!(
+            db.take_executions(),
+            vec!["parse(0)", "expression_types(0)"]
+        );
+
+        db.set_source(0, "let a = 1.5\nlet b = a > 0");
+        assert_eq!(db.type_at(0, 4).unwrap().to_string(), "Float");
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut db = Database::new();
+        db.set_source(0, "let a = (");
+        assert!(db.parse(0).is_err());
+        assert!(db.interface(0).items.is_empty());
+        assert!(db.function(0, "f").is_none());
+
+        // 不存在的源文件
+        assert!(db.parse(1).as_ref().as_ref().unwrap().body.is_empty());
+
+        db.set_source(0, "function f(Int a) = a + 1");
+        assert!(db.function(0, "f").is_some());
+
+        db.remove_source(0);
+        assert!(db.source(0).is_none());
+        assert!(db.function(0, "f").is_none());
+    }
+}
@@ -0,0 +1,258 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 交互式环境（REPL）
+//
+// 目前只有词法分析和语法分析，所以普通的输入只会被解析并显示其 AST，
+// 同时记录到会话里，以便使用 `:env` 查看已定义的名称，以及在 `:type` 里
+// 使用已定义的名称的类型。
+//
+// 元命令：
+//
+// - `:tokens <source>`  显示 Token 序列
+// - `:ast <source>`     显示 AST
+// - `:type <source>`    显示表达式的类型（见 `typed` 模块，目前只能推导局部能够确定的类型）
+// - `:env`              显示当前会话里已定义的名称
+// - `:help`             显示帮助信息
+//
+// 交互式的输入输出由 `bin/repl.rs` 负责，本模块只负责处理每一行输入，
+// 以便于测试。
+
+use crate::{
+    ast::{Expression, Node, Statement},
+    error::Error,
+    lexer, parser, typed,
+};
+
+pub const HELP_TEXT: &str = "\
+:tokens <source>  show the tokens
+:ast <source>     show the AST
+:type <source>    show the type of an expression
+:env              show the names defined in this session
+:help             show this help
+:quit             exit";
+
+#[derive(Debug, Default)]
+pub struct Session {
+    bindings: Vec<Binding>,
+    definitions: String, // 会话里已成功解析的输入，用于推导 `:type` 的表达式的类型
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub name: String,
+    pub kind: BindingKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKind {
+    Variable,
+    Function,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session::default()
+    }
+
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    // 处理一行输入，返回需要显示的文本
+    pub fn eval_line(&mut self, line: &str) -> String {
+        let line = line.trim();
+
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line, ""),
+        };
+
+        let result = match command {
+            ":tokens" => show_tokens(argument),
+            ":ast" => parse_source(argument).map(|node| node.to_string()),
+            ":type" => self.show_type(argument),
+            ":env" => Ok(self.show_env()),
+            ":help" => Ok(HELP_TEXT.to_string()),
+            _ if command.starts_with(':') => Err(Error::ParserError(format!(
+                "unknown command \"{}\", type :help for help",
+                command
+            ))),
+            _ => self.define(line),
+        };
+
+        match result {
+            Ok(text) => text,
+            Err(e) => format!("error: {}", e),
+        }
+    }
+
+    fn define(&mut self, source: &str) -> Result<String, Error> {
+        let node = parse_source(source)?;
+
+        if let Node::Program(program) = &node {
+            for statement in &program.body {
+                collect_bindings(statement, &mut self.bindings);
+            }
+        }
+
+        self.definitions.push_str(source);
+        self.definitions.push('\n');
+
+        Ok(node.to_string())
+    }
+
+    // 推导表达式的类型，表达式可以使用会话里已定义的名称
+    fn show_type(&self, source: &str) -> Result<String, Error> {
+        let Node::Program(program) = parse_source(source)? else {
+            unreachable!()
+        };
+        if !matches!(program.body.as_slice(), [Statement::Expression(_)]) {
+            return Err(Error::ParserError(format!(
+                "\"{}\" is not an expression",
+                source
+            )));
+        }
+
+        // 把表达式附加在已定义的输入之后，表达式的范围在合并后的源代码里是唯一的
+        let Node::Program(program) = parse_source(&format!("{}{}", self.definitions, source))?
+        else {
+            unreachable!()
+        };
+        let Some(Statement::Expression(expression)) = program.body.last() else {
+            unreachable!()
+        };
+
+        typed::expression_types(&program)
+            .into_iter()
+            .find(|(range, _)| range == expression.range())
+            .map(|(_, data_type)| data_type.to_string())
+            .ok_or_else(|| {
+                Error::ParserError(format!("the type of \"{}\" cannot be inferred", source))
+            })
+    }
+
+    fn show_env(&self) -> String {
+        self.bindings
+            .iter()
+            .map(|binding| match binding.kind {
+                BindingKind::Variable => format!("{}: variable", binding.name),
+                BindingKind::Function => format!("{}: function", binding.name),
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+fn show_tokens(source: &str) -> Result<String, Error> {
+    let token_details = lexer::tokenize(source)?;
+    Ok(token_details
+        .iter()
+        .map(|t| format!("{:?}", t.token))
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+fn parse_source(source: &str) -> Result<Node, Error> {
+    let token_details = lexer::tokenize(source)?;
+    parser::parse(&token_details)
+}
+
+fn collect_bindings(statement: &Statement, bindings: &mut Vec<Binding>) {
+    match statement {
+        Statement::FunctionDeclaration(f) => add_binding(bindings, &f.name, BindingKind::Function),
+        Statement::Expression(Expression::LetExpression(l)) => {
+            let mut names = Vec::<String>::new();
+            collect_pattern_names(&l.object, &mut names);
+            for name in names {
+                add_binding(bindings, &name, BindingKind::Variable);
+            }
+        }
+        _ => {}
+    }
+}
+
+// 收集 `let` 表达式左手边值里的名称，比如 `(a, [b, c])` 里的 `a`、`b` 和 `c`
+fn collect_pattern_names(object: &Expression, names: &mut Vec<String>) {
+    match object {
+        Expression::Identifier(i) => names.push(i.name.clone()),
+        Expression::Tuple(t) => t
+            .elements
+            .iter()
+            .for_each(|e| collect_pattern_names(e, names)),
+        Expression::List(l) => l
+            .elements
+            .iter()
+            .for_each(|e| collect_pattern_names(e, names)),
+        Expression::Map(m) => m.elements.iter().for_each(|entry| match &entry.value {
+            Some(value) => collect_pattern_names(value, names),
+            None => collect_pattern_names(&entry.key, names),
+        }),
+        Expression::Ellipsis(e) => names.extend(e.name.clone()),
+        _ => {}
+    }
+}
+
+// 重复定义的名称会覆盖（遮蔽）之前的定义
+fn add_binding(bindings: &mut Vec<Binding>, name: &str, kind: BindingKind) {
+    bindings.retain(|binding| binding.name != name);
+    bindings.push(Binding {
+        name: name.to_string(),
+        kind,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Session;
+
+    #[test]
+    fn test_meta_commands() {
+        let mut s1 = Session::new();
+
+        assert_eq!(
+            s1.eval_line(":tokens let a = 1"),
+            "Let\nIdentifier(\"a\")\nAssign\nInteger(1)"
+        );
+        assert_eq!(s1.eval_line(":ast 1 + 2 * 3"), "(1 + (2 * 3))\n");
+        assert_eq!(s1.eval_line(":type 1 + 2"), "Int");
+        assert_eq!(s1.eval_line(":type 1.5 > 2.0 && true"), "Boolean");
+        assert_eq!(
+            s1.eval_line(":type foo(1)"),
+            "error: the type of \"foo(1)\" cannot be inferred"
+        );
+        assert_eq!(
+            s1.eval_line(":type function f() = 1"),
+            "error: \"function f() = 1\" is not an expression"
+        );
+        assert!(s1.eval_line(":foo").starts_with("error: unknown command"));
+
+        // 定义名称
+        assert_eq!(
+            s1.eval_line("let (a, [b, c]) = x"),
+            "let (a, [b, c,],) = x\n"
+        );
+        s1.eval_line("function add(Int x, Int y) = x + y");
+        s1.eval_line("let a = 2");
+        assert_eq!(
+            s1.eval_line(":env"),
+            "b: variable\nc: variable\nadd: function\na: variable"
+        );
+
+        // 使用已定义的名称的类型
+        assert_eq!(s1.eval_line(":type add(a, 1)"), "Int");
+        s1.eval_line("let Float d = 1.5");
+        assert_eq!(s1.eval_line(":type d"), "Float");
+        assert_eq!(s1.eval_line(":type d + 1.0 > 2.0"), "Boolean");
+
+        // 语法错误不影响会话
+        assert!(s1.eval_line("let = 1").starts_with("error: "));
+        assert_eq!(s1.bindings().len(), 5);
+        assert_eq!(s1.eval_line(":type d"), "Float");
+    }
+}
@@ -0,0 +1,1002 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 名称解析
+//
+// 遍历整个程序，建立符号表，并把表达式里使用的名称解析到具体的定义。
+//
+// 符号表分为两层作用域：
+//
+// - 全局作用域
+//   程序里声明的函数、常量、结构体、联合体、特性、类型别名以及顶层的 `let` 表达式
+//   定义的名称（即 `symbols` 模块记录的符号），以及 `use` 语句导入的名称；
+// - 函数作用域
+//   函数（包括特性的默认函数以及 `impl` 语句里的函数）的参数。
+//
+// 函数主体里的局部名称（`let`、`where`、`each` 等绑定的名称）按照作用域规则
+// 由 `semantics` 模块解析（见 `semantics::unbound_names`），剩余未被绑定的名称
+// 依次在全局作用域以及预置的名称（prelude，比如标准库的函数和类型）里查找。
+//
+// 带有名称空间路径的标识符（比如 `Color::Red`、`math::PI`）按照第一个名称解析，
+// 第一个名称作为一次独立的使用记录（范围只包括该名称）：
+//
+// - 程序里声明的联合体：最后一个名称必须是联合体的成员，成员的使用的范围为整个标识符；
+// - 程序里声明的特性：最后一个名称必须是特性或者其父特性（见 `semantics::supertraits`）
+//   的成员，比如 `Ord::equal` 可以指向父特性 `Eq` 的函数 `equal`；
+// - 程序里声明的其他类型、导入的名称或者预置的名称：其余部分由其他模块定义，这里不检查；
+// - 其他：未定义的名称空间。
+//
+// 名称解析的结果可以用于 "查找所有引用"，见 `Resolution::references_of`。
+//
+// 报告的错误：
+//
+// - 未定义的名称（以及名称空间、联合体成员）；
+// - 重复定义的名称，包括全局作用域里的重复定义（模式函数的多个定义、
+//   函数的前向声明以及定义、签名不同的同名函数除外，顶层的 `let` 表达式可以重新绑定名称），
+//   以及同一个函数里重复的参数名称。
+//
+// 同名的函数（重载）在调用处的名称被解析到第一个定义，之后由 `overload::resolve_overloads`
+// 根据调用的参数选择其中一个。
+//
+// 语法分析阶段无法分辨元组结构体的实例化表达式 `Point(1, 2)` 跟函数调用，
+// 名称解析之后可以使用 `rewrite_tuple_constructors` 把它们转换为正确的节点。
+
+use crate::{
+    ast::{
+        DataType, Expression, FunctionDeclaration, Identifier, PatternExpression, Program, Range,
+        Statement, TraitDeclaration, TraitFunctionItem, Tuple, TupleConstructorExpression,
+        UnionMember,
+    },
+    error::Error,
+    semantics::{self, FreeVariable},
+    symbols::{Symbol, SymbolIndex, SymbolKind},
+};
+
+// `use` 语句导入的名称
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub path: Vec<String>, // 名称的完整路径，比如 `["std", "math", "PI"]`
+    pub name: String,      // 导入之后使用的名称
+    pub range: Range,      // `use` 语句的范围
+}
+
+// 函数作用域
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionScope {
+    pub name: String,
+    pub container: Option<String>, // 所属的特性或者 `impl` 语句的对象的名称
+    pub parameters: Vec<Symbol>,
+    pub range: Range,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolTable {
+    pub globals: Vec<Symbol>, // 包括结构体的成员、联合体的成员等（`container` 不为 None）
+    pub imports: Vec<Import>,
+    pub functions: Vec<FunctionScope>,
+}
+
+// 名称解析的目标
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Symbol(usize), // 全局作用域里的符号，即 `SymbolTable::globals` 的索引
+    Import(usize), // 导入的名称，即 `SymbolTable::imports` 的索引
+    Prelude,       // 预置的名称
+}
+
+// 名称的一次使用以及解析的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub name: String, // 名称，联合体的成员为完整的路径，比如 `Color::Red`
+    pub target: Target,
+    pub range: Range,
+}
+
+#[derive(Debug, Default)]
+pub struct Resolution {
+    pub symbols: SymbolTable,
+    pub references: Vec<Reference>, // 按照语句的顺序排列，不包括局部名称
+    pub errors: Vec<Error>,
+
+    // 重载的函数的每一个候选函数的所有定义（前向声明以及定义、参数个数相同的模式函数子句）
+    // 在 `SymbolTable::globals` 里的索引，由 `overload::resolve_overloads` 填写
+    pub overloads: Vec<Vec<usize>>,
+}
+
+impl SymbolTable {
+    // 在全局作用域里查找名称（不包括成员）
+    pub fn lookup(&self, name: &str) -> Option<Target> {
+        if let Some(index) = self
+            .globals
+            .iter()
+            .position(|s| s.container.is_none() && s.name == name)
+        {
+            return Some(Target::Symbol(index));
+        }
+
+        self.imports
+            .iter()
+            .position(|i| i.name == name)
+            .map(Target::Import)
+    }
+
+    // 查找名称为 name、所属的容器为 container 的成员
+    pub fn lookup_member(&self, container: &str, name: &str) -> Option<usize> {
+        self.globals
+            .iter()
+            .position(|s| s.container.as_deref() == Some(container) && s.name == name)
+    }
+}
+
+impl Resolution {
+    // 查找所有引用，即定义的范围为 definition 的全局符号的所有使用位置，
+    // 包括名称空间路径里的名称（比如 `Color::Red` 里的 `Color`）以及结构体实例化表达式的名称。
+    //
+    // 同名的多个定义（模式函数的多个定义、函数的前向声明以及定义）视为同一个符号，
+    // 结果按照使用的位置排列，definition 不是全局符号的定义时返回空列表。
+    //
+    // 重载的函数只包括被解析到同一个候选函数的调用，所以须在 `overload::resolve_overloads`
+    // 之后调用，否则同名函数的所有调用都被视为第一个定义的引用。
+    pub fn references_of(&self, definition: &Range) -> Vec<Range> {
+        let globals = &self.symbols.globals;
+        let Some(position) = globals.iter().position(|s| s.range == *definition) else {
+            return vec![];
+        };
+        let symbol = &globals[position];
+        let overload = self.overloads.iter().find(|o| o.contains(&position));
+
+        let mut ranges: Vec<Range> = self
+            .references
+            .iter()
+            .filter(|r| match (&r.target, overload) {
+                (Target::Symbol(index), Some(definitions)) => definitions.contains(index),
+                (Target::Symbol(index), None) => {
+                    globals[*index].name == symbol.name
+                        && globals[*index].container == symbol.container
+                }
+                _ => false,
+            })
+            .map(|r| r.range.clone())
+            .collect();
+        ranges.sort_by_key(|r| (r.file_id, r.start));
+        ranges
+    }
+}
+
+// 解析程序里的名称，prelude 为预置的名称（比如 `writeLine`、`Int`），
+// 它们不需要声明或者导入即可使用。
+pub fn resolve(program: &Program, prelude: &[&str]) -> Resolution {
+    let mut resolver = Resolver {
+        prelude,
+        traits: program
+            .body
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::TraitDeclaration(t) => Some(t),
+                _ => None,
+            })
+            .collect(),
+        resolution: Resolution::default(),
+    };
+
+    resolver.collect_globals(program);
+    for statement in &program.body {
+        resolver.resolve_statement(statement);
+    }
+
+    resolver.resolution
+}
+
+struct Resolver<'a> {
+    prelude: &'a [&'a str],
+    traits: Vec<&'a TraitDeclaration>, // 程序里声明的特性，用于查找父特性的成员
+    resolution: Resolution,
+}
+
+// 全局作用域里定义名称的语句的种类，用于判断重复的定义是否允许
+#[derive(PartialEq)]
+enum DefinitionKind {
+    Function(Vec<String>),           // 参数的类型
+    ForwardDeclaration(Vec<String>), // 空函数（包括外部函数）
+    PatternFunction(usize),          // 参数的个数
+    Other,
+}
+
+impl Resolver<'_> {
+    fn collect_globals(&mut self, program: &Program) {
+        let mut index = SymbolIndex::new();
+        index.add_file(program.range.file_id, program);
+        self.resolution.symbols.globals = index.symbols().to_vec();
+
+        let mut definitions: Vec<(&str, DefinitionKind)> = vec![];
+        for statement in &program.body {
+            let (name, kind, range) = match statement {
+                Statement::FunctionDeclaration(f) => {
+                    let types = f.parameters.iter().map(|p| p.data_type.to_string());
+                    (&f.name, DefinitionKind::Function(types.collect()), &f.range)
+                }
+                Statement::EmptyFunctionDeclaration(f) => {
+                    let types = f.parameters.iter().map(|p| p.data_type.to_string());
+                    let kind = DefinitionKind::ForwardDeclaration(types.collect());
+                    (&f.name, kind, &f.range)
+                }
+                Statement::PatternFunctionDeclaration(f) => {
+                    let kind = DefinitionKind::PatternFunction(f.parameters.len());
+                    (&f.name, kind, &f.range)
+                }
+                Statement::ConstDeclaration(c) => (&c.name, DefinitionKind::Other, &c.range),
+                Statement::MemberStructDeclaration(s) => (&s.name, DefinitionKind::Other, &s.range),
+                Statement::TupleStructDeclaration(s) => (&s.name, DefinitionKind::Other, &s.range),
+                Statement::EmptyStructDeclaration(s) => (&s.name, DefinitionKind::Other, &s.range),
+                Statement::UnionDeclaration(u) => (&u.name, DefinitionKind::Other, &u.range),
+                Statement::TraitDeclaration(t) => (&t.name, DefinitionKind::Other, &t.range),
+                Statement::AliasStatement(a) => (&a.name, DefinitionKind::Other, &a.range),
+                Statement::UseStatement(u) => {
+                    for (path, name) in u.imports() {
+                        if definitions.iter().any(|(n, _)| *n == name) {
+                            self.error(format!("duplicate definition \"{}\"", name), &u.range);
+                        }
+                        self.resolution.symbols.imports.push(Import {
+                            path,
+                            name,
+                            range: u.range.clone(),
+                        });
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let is_duplicate = self
+                .resolution
+                .symbols
+                .imports
+                .iter()
+                .any(|i| i.name == *name)
+                || definitions
+                    .iter()
+                    .any(|(n, k)| n == name && !is_compatible_definition(k, &kind));
+            if is_duplicate {
+                self.error(format!("duplicate definition \"{}\"", name), range);
+            }
+
+            definitions.push((name, kind));
+        }
+    }
+
+    fn resolve_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::FunctionDeclaration(f) => self.resolve_function(f, None),
+            Statement::PatternFunctionDeclaration(f) => {
+                // 参数的变量名称以及模式绑定的名称都属于函数作用域
+                let parameters: Vec<(String, &Range)> = f
+                    .parameters
+                    .iter()
+                    .flat_map(|p| {
+                        let mut names: Vec<String> = p.variable.iter().cloned().collect();
+                        if let Some(pattern) = &p.pattern {
+                            names.extend(semantics::match_pattern_names(pattern));
+                        }
+                        names.into_iter().map(move |name| (name, &p.range))
+                    })
+                    .collect();
+                self.add_function_scope(
+                    &f.name,
+                    None,
+                    parameters
+                        .iter()
+                        .map(|(name, range)| (name.as_str(), *range)),
+                    &f.range,
+                );
+
+                let variables: Vec<&str> = f
+                    .parameters
+                    .iter()
+                    .filter_map(|p| p.variable.as_deref())
+                    .collect();
+                let patterns: Vec<&PatternExpression> = f
+                    .parameters
+                    .iter()
+                    .filter_map(|p| p.pattern.as_deref())
+                    .collect();
+
+                let mut expressions: Vec<&Expression> = vec![];
+                for p in &f.parameters {
+                    expressions.extend(p.where_exp.as_deref());
+                    expressions.extend(p.only.as_deref());
+                }
+                expressions.extend(f.only.as_ref());
+                expressions.push(&f.body);
+
+                self.resolve_expressions(&variables, &patterns, &expressions);
+            }
+            Statement::TraitDeclaration(t) => {
+                for supertrait in &t.supertraits {
+                    if let DataType::Identifier(i) = supertrait {
+                        self.resolve_identifier(i);
+                    }
+                }
+                for item in &t.function_items {
+                    if let TraitFunctionItem::Function(f) = item {
+                        self.resolve_function(f, Some(&t.name));
+                    }
+                }
+            }
+            Statement::ImplStatement(i) => {
+                self.resolve_identifier(&i.object);
+                self.resolve_identifier(&i.inherit);
+                for f in &i.function_items {
+                    self.resolve_function(f, Some(&i.object.name));
+                }
+            }
+            _ => self.resolve_expressions(&[], &[], &statement.expressions()),
+        }
+    }
+
+    fn resolve_function(&mut self, function: &FunctionDeclaration, container: Option<&str>) {
+        self.add_function_scope(
+            &function.name,
+            container,
+            function
+                .parameters
+                .iter()
+                .map(|p| (p.name.as_str(), &p.range)),
+            &function.range,
+        );
+
+        // 参数的默认值不能使用其他参数
+        let default_values: Vec<&Expression> = function
+            .parameters
+            .iter()
+            .filter_map(|p| p.value.as_ref())
+            .collect();
+        self.resolve_expressions(&[], &[], &default_values);
+
+        let parameter_names: Vec<&str> = function
+            .parameters
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        self.resolve_expressions(&parameter_names, &[], &[&function.body]);
+    }
+
+    fn add_function_scope<'p>(
+        &mut self,
+        name: &str,
+        container: Option<&str>,
+        parameters: impl Iterator<Item = (&'p str, &'p Range)>,
+        range: &Range,
+    ) {
+        let mut symbols: Vec<Symbol> = vec![];
+        for (parameter_name, parameter_range) in parameters {
+            if symbols.iter().any(|s| s.name == parameter_name) {
+                self.error(
+                    format!(
+                        "duplicate parameter \"{}\" of function \"{}\"",
+                        parameter_name, name
+                    ),
+                    parameter_range,
+                );
+            }
+
+            symbols.push(Symbol {
+                name: parameter_name.to_string(),
+                kind: SymbolKind::Parameter,
+                container: Some(name.to_string()),
+                file_id: parameter_range.file_id,
+                range: parameter_range.clone(),
+            });
+        }
+
+        self.resolution.symbols.functions.push(FunctionScope {
+            name: name.to_string(),
+            container: container.map(str::to_string),
+            parameters: symbols,
+            range: range.clone(),
+        });
+    }
+
+    fn resolve_expressions(
+        &mut self,
+        parameters: &[&str],
+        patterns: &[&PatternExpression],
+        expressions: &[&Expression],
+    ) {
+        for FreeVariable { name, range } in
+            semantics::unbound_names(parameters, patterns, expressions)
+        {
+            match self.lookup(&name) {
+                Some(target) => self.resolution.references.push(Reference {
+                    name,
+                    target,
+                    range,
+                }),
+                None => self.error(format!("undefined name \"{}\"", name), &range),
+            }
+        }
+
+        for expression in expressions {
+            self.resolve_paths(expression);
+        }
+        for pattern in patterns {
+            if let Some(expression) = pattern.expression() {
+                self.resolve_paths(expression);
+            }
+        }
+    }
+
+    // 解析带有名称空间路径的标识符以及结构体实例化表达式的名称
+    fn resolve_paths(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Identifier(i) if !i.dirs.is_empty() => self.resolve_identifier(i),
+            Expression::PrefixIdentifier(p) if !p.identifier.dirs.is_empty() => {
+                self.resolve_identifier(&p.identifier)
+            }
+            Expression::ConstructorExpression(c) => self.resolve_identifier(&c.object),
+            _ => {}
+        }
+
+        for child in expression.children() {
+            self.resolve_paths(child);
+        }
+    }
+
+    fn resolve_identifier(&mut self, identifier: &Identifier) {
+        let full_name = identifier.to_string();
+
+        let Some(first) = identifier.dirs.first() else {
+            match self.lookup(&identifier.name) {
+                Some(target) => self.resolution.references.push(Reference {
+                    name: full_name,
+                    target,
+                    range: identifier.range.clone(),
+                }),
+                None => self.error(
+                    format!("undefined name \"{}\"", identifier.name),
+                    &identifier.range,
+                ),
+            }
+            return;
+        };
+
+        let Some(target) = self.lookup(first) else {
+            self.error(
+                format!("undefined namespace \"{}\" in \"{}\"", first, full_name),
+                &identifier.range,
+            );
+            return;
+        };
+
+        // 标识符从第一个名称开始，所以第一个名称的范围从标识符的开始位置算起
+        self.resolution.references.push(Reference {
+            name: first.clone(),
+            target: target.clone(),
+            range: Range {
+                end: identifier.range.start + first.len(),
+                ..identifier.range.clone()
+            },
+        });
+
+        let Target::Symbol(index) = target else {
+            return;
+        };
+        let kind = match self.resolution.symbols.globals[index].kind {
+            SymbolKind::Union => "union",
+            SymbolKind::Trait => "trait",
+            _ => return,
+        };
+        if identifier.dirs.len() != 1 {
+            return;
+        }
+
+        // 特性的成员包括父特性的成员
+        let mut containers: Vec<&str> = vec![first];
+        if let Some(t) = self.traits.iter().find(|t| t.name == *first) {
+            let (supertraits, _) = semantics::supertraits(t, &self.traits);
+            containers.extend(supertraits.iter().map(|t| t.name.as_str()));
+        }
+
+        match containers.iter().find_map(|container| {
+            self.resolution
+                .symbols
+                .lookup_member(container, &identifier.name)
+        }) {
+            Some(member) => self.resolution.references.push(Reference {
+                name: full_name,
+                target: Target::Symbol(member),
+                range: identifier.range.clone(),
+            }),
+            None => self.error(
+                format!(
+                    "{} \"{}\" has no member \"{}\"",
+                    kind, first, identifier.name
+                ),
+                &identifier.range,
+            ),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<Target> {
+        self.resolution
+            .symbols
+            .lookup(name)
+            .or_else(|| self.prelude.contains(&name).then_some(Target::Prelude))
+    }
+
+    fn error(&mut self, message: String, range: &Range) {
+        self.resolution.errors.push(Error::SemanticError {
+            message,
+            related: vec![],
+            range: range.clone(),
+        });
+    }
+}
+
+// 同名的定义是否允许：
+//
+// - 参数的类型不同的函数（包括前向声明）构成重载；
+// - 模式函数可以有多个定义（子句），参数的个数跟模式函数不同的函数也构成重载；
+// - 函数的前向声明可以跟定义同名。
+fn is_compatible_definition(previous: &DefinitionKind, current: &DefinitionKind) -> bool {
+    match (previous, current) {
+        (DefinitionKind::PatternFunction(_), DefinitionKind::PatternFunction(_)) => true,
+        (DefinitionKind::Function(a), DefinitionKind::Function(b))
+        | (DefinitionKind::ForwardDeclaration(a), DefinitionKind::ForwardDeclaration(b)) => a != b,
+        (DefinitionKind::Function(types), DefinitionKind::PatternFunction(count))
+        | (DefinitionKind::PatternFunction(count), DefinitionKind::Function(types)) => {
+            types.len() != *count
+        }
+        (DefinitionKind::Other, _) | (_, DefinitionKind::Other) => false,
+        (DefinitionKind::ForwardDeclaration(_), _) | (_, DefinitionKind::ForwardDeclaration(_)) => {
+            true
+        }
+    }
+}
+
+// 把被调用者解析为元组结构体（包括联合体的元组型成员）的函数调用表达式
+// 转换为元组结构体实例化表达式，比如 `Point(1, 2)` 以及 `Option::Some(1)`。
+//
+// resolution 须为同一个程序的名称解析结果（见 `resolve`），被局部名称遮盖的名称
+// 不会被解析到元组结构体，所以也不会被转换。
+// 元组结构体实例化表达式不允许使用命名参数，遇到时报告错误并保留原来的函数调用表达式。
+pub fn rewrite_tuple_constructors(program: &mut Program, resolution: &Resolution) -> Vec<Error> {
+    let mut tuple_structs: Vec<usize> = vec![];
+    for statement in &program.body {
+        match statement {
+            Statement::TupleStructDeclaration(s) => {
+                if let Some(Target::Symbol(index)) = resolution.symbols.lookup(&s.name) {
+                    tuple_structs.push(index);
+                }
+            }
+            Statement::UnionDeclaration(u) => {
+                for member in &u.members {
+                    if let UnionMember::Tuple(t) = member {
+                        tuple_structs.extend(resolution.symbols.lookup_member(&u.name, &t.name));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let callees: Vec<&Range> = resolution
+        .references
+        .iter()
+        .filter(|r| matches!(r.target, Target::Symbol(index) if tuple_structs.contains(&index)))
+        .map(|r| &r.range)
+        .collect();
+
+    let mut errors: Vec<Error> = vec![];
+    for statement in &mut program.body {
+        for expression in statement.expressions_mut() {
+            rewrite_tuple_constructor(expression, &callees, &mut errors);
+        }
+    }
+    errors
+}
+
+fn rewrite_tuple_constructor(
+    expression: &mut Expression,
+    callees: &[&Range],
+    errors: &mut Vec<Error>,
+) {
+    for child in expression.children_mut() {
+        rewrite_tuple_constructor(child, callees, errors);
+    }
+
+    let Expression::FunctionCallExpression(call) = expression else {
+        return;
+    };
+    let Expression::Identifier(object) = call.callee.as_ref() else {
+        return;
+    };
+    if !callees.contains(&&object.range) {
+        return;
+    }
+
+    if let Some(argument) = call.arguments.iter().find(|a| a.name.is_some()) {
+        errors.push(Error::SemanticError {
+            message: format!(
+                "tuple struct \"{}\" cannot be constructed with named arguments",
+                object
+            ),
+            related: vec![],
+            range: argument.range.clone(),
+        });
+        return;
+    }
+
+    // 元组的范围为被调用者之后的括号部分
+    let value = Tuple {
+        elements: call.arguments.drain(..).map(|a| *a.value).collect(),
+        range: Range {
+            file_id: call.range.file_id,
+            start: object.range.end,
+            end: call.range.end,
+        },
+    };
+
+    *expression = Expression::TupleConstructorExpression(TupleConstructorExpression {
+        object: object.clone(),
+        value,
+        range: call.range.clone(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Node, Program},
+        lexer, overload, parser,
+    };
+
+    use super::{resolve, rewrite_tuple_constructors, Resolution, Target};
+
+    fn parse(source: &str) -> Program {
+        let token_details = lexer::tokenize(source).unwrap();
+        match parser::parse(&token_details).unwrap() {
+            Node::Program(program) => program,
+            _ => unreachable!(),
+        }
+    }
+
+    fn resolve_from_string(source: &str) -> Resolution {
+        resolve(&parse(source), &["writeLine", "Int"])
+    }
+
+    fn errors(source: &str) -> Vec<String> {
+        resolve_from_string(source)
+            .errors
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_names() {
+        let r1 = resolve_from_string(
+            "function add(Int a, Int b) = a + b\n\
+            const ONE = 1\n\
+            function inc(Int x) = add(x, ONE)\n\
+            writeLine(inc(2))",
+        );
+        assert!(r1.errors.is_empty());
+        assert_eq!(r1.symbols.functions.len(), 2);
+        assert_eq!(
+            r1.symbols.functions[1]
+                .parameters
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["x"]
+        );
+
+        let references: Vec<(&str, &Target)> = r1
+            .references
+            .iter()
+            .map(|r| (r.name.as_str(), &r.target))
+            .collect();
+        let add = r1.symbols.lookup("add").unwrap();
+        let one = r1.symbols.lookup("ONE").unwrap();
+        let inc = r1.symbols.lookup("inc").unwrap();
+        assert_eq!(
+            references,
+            vec![
+                ("add", &add),
+                ("ONE", &one),
+                ("writeLine", &Target::Prelude),
+                ("inc", &inc),
+            ]
+        );
+
+        // 局部名称不需要解析
+        assert!(errors("function f(Int x) = {let y = x\n y}").is_empty());
+
+        // 未定义的名称
+        assert_eq!(
+            errors("function f(Int x) = x + y\nz"),
+            vec!["undefined name \"y\"", "undefined name \"z\""]
+        );
+    }
+
+    #[test]
+    fn test_resolve_imports() {
+        let r1 = resolve_from_string(
+            "use std::math::{PI, sqrt as root}\n\
+            root(PI)",
+        );
+        assert!(r1.errors.is_empty());
+        assert_eq!(r1.symbols.imports.len(), 2);
+        assert_eq!(r1.symbols.imports[1].path, vec!["std", "math", "sqrt"]);
+        assert_eq!(r1.symbols.lookup("root"), Some(Target::Import(1)));
+        assert_eq!(r1.references.len(), 2);
+
+        // 导入的名称作为名称空间
+        assert!(errors("use std::math\nmath::sqrt(2)").is_empty());
+        assert_eq!(
+            errors("io::writeLine(1)"),
+            vec!["undefined namespace \"io\" in \"io::writeLine\""]
+        );
+    }
+
+    #[test]
+    fn test_resolve_union_members() {
+        let r1 = resolve_from_string(
+            "union Color {Red, Green}\n\
+            let c = Color::Red",
+        );
+        assert!(r1.errors.is_empty());
+
+        // 名称空间以及联合体的成员分别作为一次使用
+        assert_eq!(r1.references[0].name, "Color");
+        assert_eq!(r1.references[0].target, r1.symbols.lookup("Color").unwrap());
+        match r1.references[1].target {
+            Target::Symbol(index) => {
+                let symbol = &r1.symbols.globals[index];
+                assert_eq!(symbol.name, "Red");
+                assert_eq!(symbol.container.as_deref(), Some("Color"));
+            }
+            _ => unreachable!(),
+        }
+
+        assert_eq!(
+            errors("union Color {Red, Green}\nColor::Blue"),
+            vec!["union \"Color\" has no member \"Blue\""]
+        );
+    }
+
+    #[test]
+    fn test_resolve_trait_members() {
+        // 特性的成员包括父特性的成员
+        let r1 = resolve_from_string(
+            "trait Eq {\n\
+                empty function equal(Self a, Self b) type Boolean\n\
+            }\n\
+            trait Ord limit Eq {\n\
+                empty function less(Self a, Self b) type Boolean\n\
+            }\n\
+            let f = Ord::equal",
+        );
+        assert!(r1.errors.is_empty());
+
+        // 父特性的名称也是一次使用
+        let names: Vec<&str> = r1.references.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["Eq", "Ord", "Ord::equal"]);
+        match r1.references[2].target {
+            Target::Symbol(index) => {
+                assert_eq!(r1.symbols.globals[index].container.as_deref(), Some("Eq"))
+            }
+            _ => unreachable!(),
+        }
+
+        assert_eq!(
+            errors("trait Ord limit Eq {}\nOrd::less"),
+            vec![
+                "undefined name \"Eq\"",
+                "trait \"Ord\" has no member \"less\""
+            ]
+        );
+    }
+
+    #[test]
+    fn test_template_placeholders() {
+        // 占位符定义的变量不是未定义的名称，重复的占位符由语义检查报告
+        assert!(errors(
+            "function f(String s) = match s {\ncase template \"{a}/{b}\": a ++ b\ndefault: \"\"\n}"
+        )
+        .is_empty());
+        assert!(errors(
+            "function f(String s) = match s {\ncase template \"{a}/{a}\": a\ndefault: \"\"\n}"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_definitions() {
+        assert_eq!(
+            errors("const A = 1\nfunction A() = 2"),
+            vec!["duplicate definition \"A\""]
+        );
+        assert_eq!(
+            errors("use std::List\nstruct List (Int)"),
+            vec!["duplicate definition \"List\""]
+        );
+        assert_eq!(
+            errors("function f(Int a, Int a) = a"),
+            vec!["duplicate parameter \"a\" of function \"f\""]
+        );
+
+        // 模式函数的多个定义以及函数的前向声明
+        assert!(errors(
+            "pattern function f(Int x @ in [1..10]) = x\n\
+            pattern function f(Int y) = y"
+        )
+        .is_empty());
+        assert!(errors(
+            "empty function g(Int a) type Int\n\
+            function g(Int a) type Int = a"
+        )
+        .is_empty());
+
+        // 函数的重载：参数的类型不同，或者跟模式函数的参数个数不同
+        assert!(errors(
+            "function h(Int a) = a\n\
+            function h(String s) = s\n\
+            pattern function h(Int x, Int y) = x"
+        )
+        .is_empty());
+        assert_eq!(
+            errors("function h(Int a) = a\nfunction h(Int b) = b"),
+            vec!["duplicate definition \"h\""]
+        );
+        assert_eq!(
+            errors("pattern function h(Int x) = x\nfunction h(Int a) = a"),
+            vec!["duplicate definition \"h\""]
+        );
+
+        // 顶层的 `let` 表达式可以重新绑定名称
+        assert!(errors("let a = 1\nlet a = a + 1").is_empty());
+    }
+
+    #[test]
+    fn test_rewrite_tuple_constructors() {
+        let rewrite = |source: &str| {
+            let mut program = parse(source);
+            let resolution = resolve(&program, &["writeLine"]);
+            let errors = rewrite_tuple_constructors(&mut program, &resolution);
+            let statement = program.body.last().unwrap();
+            let kinds: Vec<String> = statement
+                .expressions()
+                .iter()
+                .map(|e| format!("{:?}", e).split('(').next().unwrap().to_string())
+                .collect();
+            (kinds, statement.to_string(), errors)
+        };
+
+        let (kinds, text, errors) = rewrite("struct Point (Int, Int)\nPoint(1, 2)");
+        assert_eq!(kinds, vec!["TupleConstructorExpression"]);
+        assert_eq!(text, "Point (1, 2,)\n");
+        assert!(errors.is_empty());
+
+        // 联合体的元组型成员以及嵌套的实例化表达式
+        let (kinds, text, _) = rewrite(
+            "struct Point (Int, Int)\n\
+            union Shape {Dot(Point), Empty}\n\
+            Shape::Dot(Point(1, 2))",
+        );
+        assert_eq!(kinds, vec!["TupleConstructorExpression"]);
+        assert_eq!(text, "Shape::Dot (Point (1, 2,),)\n");
+
+        // 函数调用以及被局部名称遮盖的元组结构体保持不变
+        let (kinds, _, _) = rewrite("function f(Int x) = x\nf(1)");
+        assert_eq!(kinds, vec!["FunctionCallExpression"]);
+
+        let (kinds, text, _) = rewrite(
+            "struct Point (Int, Int)\n\
+            function f(Int Point) = Point(1, 2)",
+        );
+        assert_eq!(kinds, vec!["FunctionCallExpression"]);
+        assert!(text.contains("(Point)(1, 2)"));
+
+        // 不允许使用命名参数
+        let (kinds, _, errors) = rewrite("struct Point (Int, Int)\nPoint(x=1, y=2)");
+        assert_eq!(kinds, vec!["FunctionCallExpression"]);
+        assert_eq!(
+            errors[0].to_string(),
+            "tuple struct \"Point\" cannot be constructed with named arguments"
+        );
+    }
+
+    #[test]
+    fn test_references_of() {
+        let source = "struct User {Int id}\n\
+            union Color {Red, Green}\n\
+            function make(Int id) = User {id: id}\n\
+            let c = Color::Red\n\
+            let u = User::new(make(1))";
+        let r1 = resolve(&parse(source), &[]);
+        assert!(r1.errors.is_empty());
+
+        let definition = |target: Target| match target {
+            Target::Symbol(index) => r1.symbols.globals[index].range.clone(),
+            _ => unreachable!(),
+        };
+        // 使用位置在源代码里的文本
+        let texts = |target: Target| -> Vec<&str> {
+            r1.references_of(&definition(target))
+                .iter()
+                .map(|r| &source[r.start..r.end])
+                .collect()
+        };
+
+        // 结构体实例化表达式以及名称空间路径
+        assert_eq!(
+            texts(r1.symbols.lookup("User").unwrap()),
+            vec!["User", "User"]
+        );
+        assert_eq!(texts(r1.symbols.lookup("Color").unwrap()), vec!["Color"]);
+        assert_eq!(
+            texts(Target::Symbol(
+                r1.symbols.lookup_member("Color", "Red").unwrap()
+            )),
+            vec!["Color::Red"]
+        );
+        assert_eq!(texts(r1.symbols.lookup("make").unwrap()), vec!["make"]);
+        assert!(texts(Target::Symbol(
+            r1.symbols.lookup_member("Color", "Green").unwrap()
+        ))
+        .is_empty());
+
+        // 不是全局符号的定义（函数的参数）
+        assert!(r1
+            .references_of(&r1.symbols.functions[0].parameters[0].range)
+            .is_empty());
+
+        // 模式函数的每一个定义都指向所有的使用
+        let r2 = resolve(
+            &parse(
+                "pattern function f(Int x @ in [1..10]) = x\n\
+                pattern function f(Int y) = y\n\
+                f(1) + f(2)",
+            ),
+            &[],
+        );
+        let clauses: Vec<_> = r2
+            .symbols
+            .globals
+            .iter()
+            .filter(|s| s.name == "f")
+            .collect();
+        assert_eq!(clauses.len(), 2);
+        assert_eq!(r2.references_of(&clauses[1].range).len(), 2);
+
+        // 重载的函数只包括被解析到该函数的调用，前向声明跟定义指向相同的调用
+        let source = "empty function show(Int i) type Int\n\
+            function show(Int i) type Int = i\n\
+            function show(String s) type Int = 0\n\
+            show(1) + show(\"a\") + show(2)";
+        let program = parse(source);
+        let mut r3 = resolve(&program, &["Int", "String"]);
+        assert!(overload::resolve_overloads(&program, &mut r3).is_empty());
+        let columns = |line: usize| -> Vec<usize> {
+            let definition: usize = source.split('\n').take(line - 1).map(|l| l.len() + 1).sum();
+            let symbol = r3
+                .symbols
+                .globals
+                .iter()
+                .find(|s| s.range.start == definition);
+            r3.references_of(&symbol.unwrap().range)
+                .iter()
+                .map(|r| r.start - source.rfind('\n').unwrap() - 1)
+                .collect()
+        };
+        assert_eq!(columns(1), vec![0, 22]);
+        assert_eq!(columns(2), vec![0, 22]);
+        assert_eq!(columns(3), vec![10]);
+    }
+}
@@ -0,0 +1,519 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 保留格式的重写
+//
+// 自动重构以及快速修复（quick fix）的基础：对 AST 节点的编辑（重命名节点、插入函数参数、
+// 修改字面量）被转换为对源代码文本的替换（见 `lexer::TextEdit`），然后按照位置从后往前
+// 依次应用，未被编辑的文本（包括空白和注释）原样保留。
+//
+// 节点使用其在源代码里的范围（`Range`）指定，所以 `program` 须为同一份源代码的语法分析结果；
+// 节点里的名称等具体的 Token 的位置，由源代码的 Token 列表确定。
+//
+// 替换的结果没有重新进行语法分析，比如重命名为关键字，或者插入的参数有语法错误时，
+// 得到的源代码也会有语法错误。
+
+use crate::{
+    ast::{
+        walk_const_declaration, walk_function_declaration, walk_pattern_function_declaration,
+        walk_trait_declaration, AliasStatement, ConstDeclaration, EmptyFunctionDeclaration,
+        EmptyStructDeclaration, Expression, FunctionDeclaration, Identifier, Literal,
+        MemberStructDeclaration, Node, PatternFunctionDeclaration, Program, Range,
+        TraitDeclaration, TupleStructDeclaration, UnionDeclaration, Visitor,
+    },
+    compact,
+    lexer::{self, TextEdit},
+    token::{Token, TokenDetail},
+};
+
+// 对 AST 节点的一项编辑
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    // 把节点的名称修改为 name，节点可以是标识符（名称空间路径只修改最后一个名称，
+    // 比如 `Color::Red` 里的 `Red`）、函数的参数，以及函数、常量、结构体、联合体、
+    // 特性和类型别名的定义语句
+    Rename {
+        node: Range,
+        name: String,
+    },
+
+    // 在函数（包括空函数）的第 index 个参数之前插入参数，index 等于参数的个数时插入到最后，
+    // parameter 为参数的源代码，比如 `Int c = 0`
+    InsertParameter {
+        function: Range,
+        index: usize,
+        parameter: String,
+    },
+
+    // 把字面量修改为 literal（literal 的范围不使用），负数输出为带括号的形式，比如 `(-1)`
+    ChangeLiteral {
+        node: Range,
+        literal: Literal,
+    },
+}
+
+// 应用编辑，返回修改之后的源代码
+pub fn rewrite(source: &str, program: &Program, edits: &[Edit]) -> Result<String, String> {
+    let mut text = source.to_string();
+    for edit in text_edits(source, program, edits)?.iter().rev() {
+        text.replace_range(edit.start..edit.end, &edit.text);
+    }
+    Ok(text)
+}
+
+// 把编辑转换为对源代码文本的替换
+//
+// 结果按照位置排列，各项替换互不重叠（在同一个位置插入的文本按照编辑的顺序排列），
+// 可以交给编辑器应用，或者用于增量词法分析（见 `lexer::relex`）。
+pub fn text_edits(
+    source: &str,
+    program: &Program,
+    edits: &[Edit],
+) -> Result<Vec<TextEdit>, String> {
+    let token_details = lexer::tokenize(source).map_err(|e| e.to_string())?;
+
+    let mut text_edits: Vec<TextEdit> = vec![];
+    for edit in edits {
+        let text_edit = match edit {
+            Edit::Rename { node, name } => rename(&token_details, program, node, name)?,
+            Edit::InsertParameter {
+                function,
+                index,
+                parameter,
+            } => insert_parameter(&token_details, program, function, *index, parameter)?,
+            Edit::ChangeLiteral { node, literal } => match find_node(program, node) {
+                Some(Found::Literal) => TextEdit {
+                    start: node.start,
+                    end: node.end,
+                    text: compact::to_compact_string(&Node::Expression(Expression::Literal(
+                        literal.clone(),
+                    ))),
+                },
+                _ => return Err(not_found("literal", node)),
+            },
+        };
+        text_edits.push(text_edit);
+    }
+
+    // 排序是稳定的，所以同一个位置的插入保持编辑的顺序
+    text_edits.sort_by_key(|e| e.start);
+    for pair in text_edits.windows(2) {
+        if pair[1].start < pair[0].end {
+            return Err(format!(
+                "edits overlap at {}..{} and {}..{}",
+                pair[0].start, pair[0].end, pair[1].start, pair[1].end
+            ));
+        }
+    }
+
+    Ok(text_edits)
+}
+
+fn not_found(kind: &str, range: &Range) -> String {
+    format!("no {} found at {}..{}", kind, range.start, range.end)
+}
+
+fn rename(
+    token_details: &[TokenDetail],
+    program: &Program,
+    node: &Range,
+    name: &str,
+) -> Result<TextEdit, String> {
+    let tokens = tokens_in(token_details, node.start, node.end);
+    let position = match find_node(program, node) {
+        // 名称空间路径的最后一个名称，跳过前面的 `dir::`
+        Some(Found::Identifier) => {
+            let mut position = tokens
+                .iter()
+                .position(|t| matches!(t.token, Token::Identifier(_)));
+            while let Some(index) = position {
+                match (tokens.get(index + 1), tokens.get(index + 2)) {
+                    (
+                        Some(TokenDetail {
+                            token: Token::Separator,
+                            ..
+                        }),
+                        Some(TokenDetail {
+                            token: Token::Identifier(_),
+                            ..
+                        }),
+                    ) => position = Some(index + 2),
+                    _ => break,
+                }
+            }
+            position
+        }
+        // 参数的名称位于数据类型之后
+        Some(Found::Parameter { data_type_end }) => tokens.iter().position(|t| {
+            t.location.start >= data_type_end && matches!(t.token, Token::Identifier(_))
+        }),
+        Some(Found::Declaration { name }) | Some(Found::Function { name, .. }) => tokens
+            .iter()
+            .position(|t| t.token == Token::Identifier(name.clone())),
+        _ => None,
+    };
+
+    match position {
+        Some(index) => Ok(TextEdit {
+            start: tokens[index].location.start,
+            end: tokens[index].location.end,
+            text: name.to_string(),
+        }),
+        None => Err(not_found("named node", node)),
+    }
+}
+
+fn insert_parameter(
+    token_details: &[TokenDetail],
+    program: &Program,
+    function: &Range,
+    index: usize,
+    parameter: &str,
+) -> Result<TextEdit, String> {
+    let Some(Found::Function { name, parameters }) = find_node(program, function) else {
+        return Err(not_found("function", function));
+    };
+
+    let (position, text) = if index < parameters.len() {
+        (parameters[index].start, format!("{}, ", parameter))
+    } else if index > parameters.len() {
+        return Err(format!(
+            "parameter index {} out of range, function \"{}\" has {} parameters",
+            index,
+            name,
+            parameters.len()
+        ));
+    } else if let Some(last) = parameters.last() {
+        (last.end, format!(", {}", parameter))
+    } else {
+        // 没有参数时插入到函数名称（以及泛型列表）之后的左括号之后
+        let tokens = tokens_in(token_details, function.start, function.end);
+        let paren = tokens
+            .iter()
+            .skip_while(|t| t.token != Token::Identifier(name.clone()))
+            .find(|t| t.token == Token::LeftParen);
+        match paren {
+            Some(paren) => (paren.location.end, parameter.to_string()),
+            None => return Err(not_found("parameter list", function)),
+        }
+    };
+
+    Ok(TextEdit {
+        start: position,
+        end: position,
+        text,
+    })
+}
+
+// 位于 [start, end) 范围之内的 Token
+fn tokens_in(token_details: &[TokenDetail], start: usize, end: usize) -> &[TokenDetail] {
+    let first = token_details.partition_point(|t| t.location.start < start);
+    let last = token_details.partition_point(|t| t.location.end <= end);
+    &token_details[first..last.max(first)]
+}
+
+// 范围所指的节点的种类以及重写所需的信息
+#[derive(Debug, PartialEq)]
+enum Found {
+    Identifier,
+    Literal,
+    Parameter {
+        data_type_end: usize,
+    },
+    Declaration {
+        name: String,
+    },
+    Function {
+        name: String,
+        parameters: Vec<Range>,
+    },
+}
+
+fn find_node(program: &Program, range: &Range) -> Option<Found> {
+    let mut finder = Finder { range, found: None };
+    finder.visit_program(program);
+    finder.found
+}
+
+struct Finder<'a> {
+    range: &'a Range,
+    found: Option<Found>,
+}
+
+impl Finder<'_> {
+    fn declaration(&mut self, name: &str, range: &Range) {
+        if range == self.range {
+            self.found = Some(Found::Declaration {
+                name: name.to_string(),
+            });
+        }
+    }
+
+    fn function<'b>(
+        &mut self,
+        name: &str,
+        range: &Range,
+        parameters: impl Iterator<Item = (&'b Range, &'b Range)>,
+    ) {
+        let mut ranges: Vec<Range> = vec![];
+        for (parameter, data_type) in parameters {
+            if parameter == self.range {
+                self.found = Some(Found::Parameter {
+                    data_type_end: data_type.end,
+                });
+            }
+            ranges.push(parameter.clone());
+        }
+
+        if range == self.range {
+            self.found = Some(Found::Function {
+                name: name.to_string(),
+                parameters: ranges,
+            });
+        }
+    }
+}
+
+impl Visitor for Finder<'_> {
+    fn visit_function_declaration(&mut self, v: &FunctionDeclaration) {
+        self.function(
+            &v.name,
+            &v.range,
+            v.parameters.iter().map(|p| (&p.range, p.data_type.range())),
+        );
+        walk_function_declaration(self, v);
+    }
+
+    fn visit_empty_function_declaration(&mut self, v: &EmptyFunctionDeclaration) {
+        self.function(
+            &v.name,
+            &v.range,
+            v.parameters.iter().map(|p| (&p.range, p.data_type.range())),
+        );
+    }
+
+    fn visit_pattern_function_declaration(&mut self, v: &PatternFunctionDeclaration) {
+        self.declaration(&v.name, &v.range);
+        walk_pattern_function_declaration(self, v);
+    }
+
+    fn visit_const_declaration(&mut self, v: &ConstDeclaration) {
+        self.declaration(&v.name, &v.range);
+        walk_const_declaration(self, v);
+    }
+
+    fn visit_member_struct_declaration(&mut self, v: &MemberStructDeclaration) {
+        self.declaration(&v.name, &v.range);
+    }
+
+    fn visit_tuple_struct_declaration(&mut self, v: &TupleStructDeclaration) {
+        self.declaration(&v.name, &v.range);
+    }
+
+    fn visit_empty_struct_declaration(&mut self, v: &EmptyStructDeclaration) {
+        self.declaration(&v.name, &v.range);
+    }
+
+    fn visit_union_declaration(&mut self, v: &UnionDeclaration) {
+        self.declaration(&v.name, &v.range);
+    }
+
+    fn visit_trait_declaration(&mut self, v: &TraitDeclaration) {
+        self.declaration(&v.name, &v.range);
+        walk_trait_declaration(self, v);
+    }
+
+    fn visit_alias_statement(&mut self, v: &AliasStatement) {
+        self.declaration(&v.name, &v.range);
+    }
+
+    fn visit_identifier(&mut self, v: &Identifier) {
+        if v.range == *self.range {
+            self.found = Some(Found::Identifier);
+        }
+    }
+
+    fn visit_literal(&mut self, v: &Literal) {
+        if v.range() == self.range {
+            self.found = Some(Found::Literal);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Expression, Integer, Literal, Node, Program, Range, Statement},
+        lexer, parser,
+    };
+
+    use super::{rewrite, text_edits, Edit};
+
+    fn parse(source: &str) -> Program {
+        let token_details = lexer::tokenize(source).unwrap();
+        match parser::parse(&token_details).unwrap() {
+            Node::Program(program) => program,
+            _ => unreachable!(),
+        }
+    }
+
+    // 源代码里第一次出现 text 的位置开始、长度为 length 的范围
+    fn range_of(source: &str, text: &str, length: usize) -> Range {
+        let start = source.find(text).unwrap();
+        Range {
+            file_id: 0,
+            start,
+            end: start + length,
+        }
+    }
+
+    #[test]
+    fn test_rename() {
+        let source = "// 加法\n\
+            function add(Int a, /* 第二个 */ Int b) =\n    a +  b  // 求和\n\
+            let c = Color::Red";
+        let program = parse(source);
+        let Statement::FunctionDeclaration(f) = &program.body[0] else {
+            unreachable!()
+        };
+
+        let edits = vec![
+            Edit::Rename {
+                node: f.range.clone(),
+                name: "plus".to_string(),
+            },
+            Edit::Rename {
+                node: f.parameters[1].range.clone(),
+                name: "right".to_string(),
+            },
+            Edit::Rename {
+                node: range_of(source, "b  //", 1),
+                name: "right".to_string(),
+            },
+            Edit::Rename {
+                node: range_of(source, "Color::Red", 10),
+                name: "Blue".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            rewrite(source, &program, &edits).unwrap(),
+            "// 加法\n\
+            function plus(Int a, /* 第二个 */ Int right) =\n    a +  right  // 求和\n\
+            let c = Color::Blue"
+        );
+
+        // 范围不是可以重命名的节点（`let` 表达式）
+        let Statement::Expression(let_expression) = &program.body[1] else {
+            unreachable!()
+        };
+        assert_eq!(
+            rewrite(
+                source,
+                &program,
+                &[Edit::Rename {
+                    node: let_expression.range().clone(),
+                    name: "x".to_string(),
+                }]
+            ),
+            Err("no named node found at 77..95".to_string())
+        );
+    }
+
+    #[test]
+    fn test_insert_parameter() {
+        let source = "function f<T>() = 1\n\
+            empty function g(Int a)\n\
+            function h(Int a, Map<Int, String> m = {}) = a";
+        let program = parse(source);
+        let ranges: Vec<Range> = program
+            .body
+            .iter()
+            .map(|s| match s {
+                Statement::FunctionDeclaration(f) => f.range.clone(),
+                Statement::EmptyFunctionDeclaration(f) => f.range.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+        let insert = |function: usize, index: usize, parameter: &str| Edit::InsertParameter {
+            function: ranges[function].clone(),
+            index,
+            parameter: parameter.to_string(),
+        };
+
+        assert_eq!(
+            rewrite(
+                source,
+                &program,
+                &[
+                    insert(0, 0, "T t"),
+                    insert(1, 0, "Int first"),
+                    insert(1, 1, "Int last"),
+                    insert(2, 1, "Int b"),
+                    insert(2, 2, "Int c = 0"),
+                ]
+            )
+            .unwrap(),
+            "function f<T>(T t) = 1\n\
+            empty function g(Int first, Int a, Int last)\n\
+            function h(Int a, Int b, Map<Int, String> m = {}, Int c = 0) = a"
+        );
+
+        // 在同一个位置插入的参数按照编辑的顺序排列
+        assert_eq!(
+            rewrite(
+                source,
+                &program,
+                &[insert(1, 1, "Int x"), insert(1, 1, "Int y")]
+            )
+            .unwrap()
+            .lines()
+            .nth(1),
+            Some("empty function g(Int a, Int x, Int y)")
+        );
+
+        assert_eq!(
+            rewrite(source, &program, &[insert(1, 2, "Int x")]),
+            Err("parameter index 2 out of range, function \"g\" has 1 parameters".to_string())
+        );
+    }
+
+    #[test]
+    fn test_change_literal() {
+        let source = "let a = 1 + /* 注释 */ 2";
+        let program = parse(source);
+        let literal = |value: i64| {
+            Literal::Integer(Integer {
+                value,
+                range: Range::zero(),
+            })
+        };
+        let change = |text: &str, value: i64| Edit::ChangeLiteral {
+            node: range_of(source, text, text.len()),
+            literal: literal(value),
+        };
+
+        assert_eq!(
+            rewrite(source, &program, &[change("2", 20), change("1", -1)]).unwrap(),
+            "let a = (-1) + /* 注释 */ 20"
+        );
+
+        // 重叠的编辑
+        assert_eq!(
+            text_edits(source, &program, &[change("2", 3), change("2", 4)]),
+            Err("edits overlap at 25..26 and 25..26".to_string())
+        );
+
+        // 不是字面量
+        assert!(rewrite(source, &program, &[change("a", 1)]).is_err());
+        assert!(matches!(
+            &program.body[0],
+            Statement::Expression(Expression::LetExpression(_))
+        ));
+    }
+}
@@ -0,0 +1,4028 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 语义分析
+//
+// 在 AST 的基础上进行的分析和检查。
+//
+// 目前包括的检查项目：
+//
+// - 范围表达式
+//   边界的类型必须是整数、浮点数或者字符，两个边界的类型必须相同，
+//   闭区间（`..=`）的边界不能是浮点数；边界均为常量时，范围不能为空（比如 `10..1`）。
+// - `where` 从属表达式的作用域
+//   `where` 从属表达式定义的名称只在所属表达式的条件（testing）以及结果（consequent）
+//   表达式里有效（详细见 `ScopeResolver::visit`），在范围之外使用这些名称是错误的。
+// - 复数运算
+//   复数（常量）不能比较大小，也不能跟非数值进行算术运算（运算规则见 `fold` 模块）。
+// - 比特
+//   比特字面量的数值不能超出其宽度（比如 `4'x1f`），两个比特值之间的运算（连接运算除外）
+//   要求宽度相同（运算规则见 `fold` 模块）。
+// - 模板模式
+//   `case template "..."` 里的占位符的格式必须正确，占位符的名称不能重复，
+//   占位符的正则表达式必须有效。
+// - 正则表达式模式
+//   `case regular "..." (...)` 的正则表达式必须有效，并且名称列表的元素数量必须等于
+//   捕获组的数量加 1（第一个名称绑定整个匹配的文本）。
+// - 解构
+//   `let` 表达式的左手边值是元组、列表、映射表或者结构体实例化表达式（即解构模式）时，
+//   其结构必须跟右手边值一致：元组以及列表的元素数量必须相同（存在剩余项目时不能少于
+//   模式的元素数量），映射表的键以及结构体的成员必须存在，结构体的名称必须相同。
+//   目前只检查右手边值是字面量的情况，其他情况需要等类型推导实现之后再检查。
+// - 模式的类型
+//   `match` 表达式里字面量模式的类型必须相同，当被匹配的对象是常量时，
+//   模式的类型也必须跟对象的类型相同，`regular` 和 `template` 模式要求对象是字符串；
+//   `branch` 表达式分支的条件以及 `match` 分支的 `only` 从属表达式必须是布尔值。
+//   目前只检查能够求值为常量的表达式，其他表达式需要等类型推导实现之后再检查。
+// - `next` 表达式
+//   `next` 表达式只能位于 `for` 表达式的主体里（匿名函数的主体不属于外层的 `for` 表达式），
+//   错误信息会指出 `next` 表达式所在的函数。
+// - `join` 表达式
+//   `join` 表达式的各个子表达式相互独立，可以以任意顺序（或者并行地）求值，
+//   所以子表达式不能使用其他子表达式的 `let` 绑定的名称，同一个名称不能被多个子表达式绑定，
+//   也不能直接包含 `next` 表达式（位于子表达式里的 `for` 表达式以及匿名函数的主体除外）。
+// - 剩余项目
+//   元组、列表、映射表以及结构体实例化表达式里最多只能有一个剩余项目 `...rest`，
+//   并且必须位于最后（语法分析器已经保证这两点，这里检查的是通过其他途径构造的 AST，
+//   比如从 JSON 反序列化的 AST）；在模式（比如 `let` 表达式的
+//   左手边值、`match` 的分支模式）之外，剩余项目必须有名称；模式里绑定的剩余项目名称
+//   必须被使用，不需要时应该使用省略名称的 `...`。
+// - 映射表以及结构体实例化表达式的键
+//   同一个映射表里不能有重复的键（名称或者字面量），结构体实例化表达式里不能有重复的成员；
+//   结构体实例化表达式的成员必须是结构体声明的成员，并且必须给出所有成员的值
+//   （存在剩余项目 `...` 时除外）。
+// - 哈希字符串
+//   哈希字符串（比如 `#foo`）是驻留（interned）的符号，相同的值对应同一个编号
+//   （见 `intern_hash_strings`）。`match` 表达式里同一个哈希字符串只能作为一个分支的模式，
+//   以便代码生成阶段把分支转换为基于编号的跳转表。
+// - 优化提示
+//   函数的 `#[inline]`、`#[inline(always)]`、`#[inline(never)]` 以及 `#[cold]` 属性的参数
+//   必须有效，不能重复，并且 `#[cold]` 不能跟 `#[inline(always)]` 同时使用
+//   （见 `optimization_hints`）。
+// - 外部函数
+//   `#[extern("module", "name")]` 属性只能标注在空函数上，其参数必须是两个字符串，
+//   并且外部函数的参数以及返回值只能是 FFI 允许的类型（见 `extern_function`）。
+// - 构建环境值
+//   `env("NAME")` 表达式的参数必须是唯一的一个字符串字面量，
+//   名称是否已定义由 `check_build_environment` 检查。
+// - 静态断言
+//   `static_assert(condition, "message")` 表达式的参数必须是一个条件以及可选的一个
+//   字符串字面量（断言失败时的信息），条件的值由 `check_static_assertions` 检查。
+// - 前向声明
+//   没有 `extern` 属性的空函数是函数的前向声明，同一个程序里同名函数的定义必须跟声明一致
+//   （见 `check_forward_declarations`）。
+// - 特性的要求
+//   `impl` 语句必须实现特性的所有空函数（即特性要求的函数），只能实现特性声明的函数，
+//   参数的数量以及数据类型必须跟声明相同（`Self` 以及关联类型按照 `impl` 语句的绑定规范化，
+//   见 `normalize_data_type`），必须绑定特性的所有没有默认类型的关联类型，
+//   并且只能绑定特性声明的关联类型；特性不能（直接或者间接地）继承自己，
+//   实现某个特性的类型必须同时实现它的所有父特性（见 `check_trait_requirements`）。
+// - 模式函数的子句
+//   同一个模式函数的子句不能是不可达的，字面量模式不能部分重叠，
+//   并且所有子句必须覆盖所有可能的参数（见 `check_pattern_functions`）。
+
+use crate::{
+    ast::{
+        BinaryExpression, BinaryOperator, BlockExpression, ConstructorExpression, DataType,
+        Ellipsis, EmptyFunctionDeclaration, Expression, FunctionDeclaration, Identifier,
+        ImplStatement,
+        Interval, JoinExpression, List, Literal, Map, MapEntry, MatchExpression,
+        MemberExpression, MemberStructDeclaration, Node, PatternExpression,
+        PatternFunctionDeclaration, PatternFunctionParameter, Range, Sign, SignParameter,
+        Statement, TraitDeclaration, TraitFunctionItem, Tuple, WhichEntry,
+    },
+    error::Error,
+    fold::{self, BuildEnvironment, Constant},
+};
+
+// 检查整个程序（或者语句、表达式），返回所有语义错误
+pub fn check(node: &Node) -> Vec<Error> {
+    let mut errors = Vec::<Error>::new();
+
+    match node {
+        Node::Program(program) => {
+            let structs: Vec<&MemberStructDeclaration> = program
+                .body
+                .iter()
+                .filter_map(|statement| match statement {
+                    Statement::MemberStructDeclaration(s) => Some(s),
+                    _ => None,
+                })
+                .collect();
+
+            for statement in &program.body {
+                check_statement(statement, &mut errors);
+                for expression in statement.expressions() {
+                    check_constructor_fields(expression, &structs, &mut errors);
+                }
+            }
+
+            check_forward_declarations(&program.body, &mut errors);
+            check_trait_requirements(&program.body, &mut errors);
+            check_pattern_functions(&program.body, &mut errors);
+        }
+        Node::Statement(statement) => check_statement(statement, &mut errors),
+        Node::Expression(expression) => {
+            check_expression(expression, &mut errors);
+            check_rest(expression, false, &mut errors);
+            check_next(expression, false, None, &mut errors);
+            check_scopes(&[], &[expression], &mut errors);
+        }
+    }
+
+    errors
+}
+
+fn check_statement(statement: &Statement, errors: &mut Vec<Error>) {
+    let expressions = statement.expressions();
+
+    let function = match statement {
+        Statement::FunctionDeclaration(f) => Some((f.name.as_str(), &f.range)),
+        _ => None,
+    };
+
+    for expression in &expressions {
+        check_expression(expression, errors);
+        check_rest(expression, false, errors);
+        check_next(expression, false, function, errors);
+    }
+
+    match statement {
+        Statement::FunctionDeclaration(f) => {
+            let reason = match optimization_hints(f) {
+                Err(reason) => Some(reason),
+                Ok(_) if f.attributes.iter().any(|a| a.name == EXTERN) => {
+                    Some("attribute \"extern\" can only be applied to empty functions".to_string())
+                }
+                Ok(_) => None,
+            };
+
+            if let Some(reason) = reason {
+                errors.push(new_semantic_error(
+                    format!("invalid attributes of function \"{}\": {}", f.name, reason),
+                    &f.range,
+                ));
+            }
+        }
+        Statement::EmptyFunctionDeclaration(f) => {
+            if let Err(reason) = extern_function(f) {
+                errors.push(new_semantic_error(
+                    format!("invalid extern function \"{}\": {}", f.name, reason),
+                    &f.range,
+                ));
+            }
+        }
+        _ => {}
+    }
+
+    // 函数的参数在函数主体里有效
+    let parameter_names: Vec<&str> = match statement {
+        Statement::FunctionDeclaration(f) => f.parameters.iter().map(|p| p.name.as_str()).collect(),
+        _ => vec![],
+    };
+    check_scopes(&parameter_names, &expressions, errors);
+}
+
+fn check_scopes(bound_names: &[&str], expressions: &[&Expression], errors: &mut Vec<Error>) {
+    let mut resolver = ScopeResolver::default();
+    for name in bound_names {
+        resolver.bind_name(name);
+    }
+    for expression in expressions {
+        resolver.visit(expression);
+    }
+    errors.append(&mut resolver.errors);
+}
+
+fn check_expression(expression: &Expression, errors: &mut Vec<Error>) {
+    match expression {
+        Expression::Interval(interval) => check_interval(interval, errors),
+        Expression::SliceExpression(slice) => check_interval(&slice.interval, errors),
+        Expression::BinaryExpression(binary) => {
+            check_complex_operation(binary, errors);
+            if binary.operator != BinaryOperator::Concat {
+                check_bit_widths(
+                    &binary.left,
+                    &binary.right,
+                    expression,
+                    &binary.range,
+                    errors,
+                );
+            }
+        }
+        Expression::NamedOperatorExpression(v) => {
+            check_bit_widths(&v.left, &v.right, expression, &v.range, errors)
+        }
+        Expression::Literal(Literal::Bit(bit)) => {
+            let bits = significant_bits(&bit.bytes);
+            if bits > bit.width {
+                errors.push(new_semantic_error(
+                    format!(
+                        "bit literal \"{}\" needs {} bits and exceeds its width of {} bits",
+                        bit, bits, bit.width
+                    ),
+                    &bit.range,
+                ));
+            }
+        }
+        Expression::Map(v) => check_duplicate_keys(&v.elements, "key", "", errors),
+        Expression::ConstructorExpression(v) => check_duplicate_keys(
+            &v.value.elements,
+            "field",
+            &format!(" of \"{}\"", v.object),
+            errors,
+        ),
+        Expression::LetExpression(v) => check_destructuring(&v.object, &v.value, errors),
+        Expression::JoinExpression(v) => check_join(v, errors),
+        Expression::FunctionCallExpression(v)
+            if fold::is_env_call(v) && fold::env_name(v).is_none() =>
+        {
+            errors.push(new_semantic_error(
+                format!(
+                    "\"{}\" expects a single string literal as the name \
+                    of the build environment value",
+                    expression
+                ),
+                &v.range,
+            ));
+        }
+        Expression::FunctionCallExpression(v)
+            if fold::is_static_assert_call(v) && fold::static_assertion(v).is_none() =>
+        {
+            errors.push(new_semantic_error(
+                format!(
+                    "\"{}\" expects a condition and an optional string literal message",
+                    expression
+                ),
+                &v.range,
+            ));
+        }
+        Expression::BranchExpression(v) => {
+            for c in &v.cases {
+                check_boolean_condition(&c.testing, "the testing expression", &c.range, errors);
+            }
+        }
+        Expression::MatchExpression(v) => {
+            check_pattern_types(v, errors);
+
+            let mut hash_strings = Vec::<&str>::new();
+            for c in &v.cases {
+                if let Some(pattern) = &c.pattern {
+                    check_match_pattern(pattern, &c.range, errors);
+
+                    if let PatternExpression::Primary(Expression::Literal(Literal::HashString(h))) =
+                        pattern.as_ref()
+                    {
+                        if hash_strings.contains(&h.value.as_str()) {
+                            errors.push(new_semantic_error(
+                                format!("duplicate match case \"{}\"", h),
+                                &c.range,
+                            ));
+                        } else {
+                            hash_strings.push(&h.value);
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+
+    for child in expression.children() {
+        check_expression(child, errors);
+    }
+}
+
+fn check_boolean_condition(
+    expression: &Expression,
+    kind: &str,
+    range: &Range,
+    errors: &mut Vec<Error>,
+) {
+    if let Some(constant) = fold::fold(expression) {
+        if !matches!(constant, Constant::Boolean(_)) {
+            errors.push(new_semantic_error(
+                format!(
+                    "{} \"{}\" must be Boolean, found {}",
+                    kind,
+                    expression,
+                    constant.type_name()
+                ),
+                range,
+            ));
+        }
+    }
+}
+
+// 检查 `match` 表达式的模式跟被匹配的对象的类型是否兼容
+fn check_pattern_types(match_exp: &MatchExpression, errors: &mut Vec<Error>) {
+    let object_type = fold::fold(&match_exp.object).map(|c| c.type_name());
+
+    // 第一个能确定类型的模式
+    let mut expected: Option<(&'static str, String)> = None;
+
+    for c in &match_exp.cases {
+        if let Some(only) = &c.only {
+            check_boolean_condition(only, "the `only` clause", &c.range, errors);
+        }
+
+        let Some(pattern) = &c.pattern else {
+            continue;
+        };
+
+        let pattern_type = match pattern.as_ref() {
+            PatternExpression::Primary(e) => fold::fold(e).map(|c| c.type_name()),
+            PatternExpression::Regular(_, _) | PatternExpression::Template(_) => Some("String"),
+            _ => None,
+        };
+
+        let Some(pattern_type) = pattern_type else {
+            continue;
+        };
+
+        if let Some(object_type) = object_type {
+            if pattern_type != object_type {
+                errors.push(new_semantic_error(
+                    format!(
+                        "pattern \"{}\" has type {}, but the matched object \"{}\" has type {}",
+                        pattern, pattern_type, match_exp.object, object_type
+                    ),
+                    &c.range,
+                ));
+            }
+        } else if let Some((expected_type, expected_pattern)) = &expected {
+            if pattern_type != *expected_type {
+                errors.push(new_semantic_error(
+                    format!(
+                        "pattern \"{}\" has type {}, but the previous pattern \"{}\" has type {}",
+                        pattern, pattern_type, expected_pattern, expected_type
+                    ),
+                    &c.range,
+                ));
+            }
+        } else {
+            expected = Some((pattern_type, pattern.to_string()));
+        }
+    }
+}
+
+// 检查重复的键，只比较名称以及字面量，序号从 1 开始
+fn check_duplicate_keys(entries: &[MapEntry], kind: &str, owner: &str, errors: &mut Vec<Error>) {
+    let keys: Vec<Option<String>> = entries.iter().map(entry_key).collect();
+
+    for (index, key) in keys.iter().enumerate() {
+        let Some(key) = key else {
+            continue;
+        };
+
+        if let Some(first) = keys[..index].iter().position(|k| k.as_ref() == Some(key)) {
+            errors.push(new_semantic_error(
+                format!(
+                    "duplicate {} \"{}\"{} at entries {} and {}",
+                    kind,
+                    key,
+                    owner,
+                    first + 1,
+                    index + 1
+                ),
+                &entries[index].range,
+            ));
+        }
+    }
+}
+
+// 映射表（以及结构体实例化表达式）项目的键，只支持名称以及字面量
+fn entry_key(entry: &MapEntry) -> Option<String> {
+    match entry.key.as_ref() {
+        Expression::Identifier(i) if i.dirs.is_empty() => Some(i.name.clone()),
+        Expression::Literal(l) => Some(l.to_string()),
+        _ => None,
+    }
+}
+
+// 检查解构模式跟（字面量）值的结构是否一致，错误的位置为不一致的子模式
+fn check_destructuring(pattern: &Expression, value: &Expression, errors: &mut Vec<Error>) {
+    match (pattern, value) {
+        (Expression::Tuple(p), Expression::Tuple(v)) => {
+            check_destructuring_elements(&p.elements, &v.elements, pattern, &p.range, errors)
+        }
+        // 列表的元素可能是展开的剩余项目或者范围，此时无法确定元素的数量
+        (Expression::List(p), Expression::List(v))
+            if !v
+                .elements
+                .iter()
+                .any(|e| matches!(e, Expression::Ellipsis(_) | Expression::Interval(_))) =>
+        {
+            check_destructuring_elements(&p.elements, &v.elements, pattern, &p.range, errors)
+        }
+        (Expression::List(_), Expression::List(_)) => {}
+        (Expression::Map(p), Expression::Map(v)) => {
+            check_destructuring_entries(&p.elements, &v.elements, "key", "", errors)
+        }
+        (Expression::ConstructorExpression(p), Expression::ConstructorExpression(v)) => {
+            if p.object.dirs == v.object.dirs && p.object.name == v.object.name {
+                check_destructuring_entries(
+                    &p.value.elements,
+                    &v.value.elements,
+                    "field",
+                    &format!(" of struct \"{}\"", p.object),
+                    errors,
+                )
+            } else {
+                errors.push(new_semantic_error(
+                    format!(
+                        "struct pattern \"{}\" does not match a value of struct \"{}\"",
+                        p.object, v.object
+                    ),
+                    &p.range,
+                ));
+            }
+        }
+        (
+            Expression::Tuple(Tuple { range, .. })
+            | Expression::List(List { range, .. })
+            | Expression::Map(Map { range, .. })
+            | Expression::ConstructorExpression(ConstructorExpression { range, .. }),
+            _,
+        ) => {
+            let value_type = match value {
+                Expression::Tuple(_) => Some("Tuple"),
+                Expression::List(_) => Some("List"),
+                Expression::Map(_) => Some("Map"),
+                Expression::ConstructorExpression(_)
+                | Expression::TupleConstructorExpression(_) => Some("Struct"),
+                _ => fold::fold(value).map(|c| c.type_name()),
+            };
+
+            if let Some(value_type) = value_type {
+                errors.push(new_semantic_error(
+                    format!(
+                        "cannot destructure a value of type {} with pattern \"{}\"",
+                        value_type, pattern
+                    ),
+                    range,
+                ));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_destructuring_elements(
+    patterns: &[Expression],
+    values: &[Expression],
+    pattern: &Expression,
+    range: &Range,
+    errors: &mut Vec<Error>,
+) {
+    let has_rest = matches!(patterns.last(), Some(Expression::Ellipsis(_)));
+    let count = if has_rest {
+        patterns.len() - 1
+    } else {
+        patterns.len()
+    };
+
+    if values.len() < count || (!has_rest && values.len() > count) {
+        errors.push(new_semantic_error(
+            format!(
+                "pattern \"{}\" expects {}{} element(s), found {}",
+                pattern,
+                if has_rest { "at least " } else { "" },
+                count,
+                values.len()
+            ),
+            range,
+        ));
+        return;
+    }
+
+    for (p, v) in patterns.iter().zip(values) {
+        check_destructuring(p, v, errors);
+    }
+}
+
+fn check_destructuring_entries(
+    patterns: &[MapEntry],
+    values: &[MapEntry],
+    kind: &str,
+    owner: &str,
+    errors: &mut Vec<Error>,
+) {
+    // 值里有展开的剩余项目时，无法确定所有的键
+    if values
+        .iter()
+        .any(|e| matches!(e.key.as_ref(), Expression::Ellipsis(_)))
+    {
+        return;
+    }
+
+    for entry in patterns {
+        let Some(key) = entry_key(entry) else {
+            continue;
+        };
+
+        match values.iter().find(|v| entry_key(v).as_ref() == Some(&key)) {
+            Some(value_entry) => {
+                let sub_pattern = entry.value.as_deref().unwrap_or(&entry.key);
+                let sub_value = value_entry.value.as_deref().unwrap_or(&value_entry.key);
+                check_destructuring(sub_pattern, sub_value, errors);
+            }
+            None => errors.push(new_semantic_error(
+                format!("{} \"{}\"{} is not found in the value", kind, key, owner),
+                &entry.range,
+            )),
+        }
+    }
+}
+
+// 检查结构体实例化表达式的成员是否跟结构体的声明一致
+//
+// 注：目前只检查同一个程序里声明的结构体，名称解析实现之后再检查其他模块的结构体。
+fn check_constructor_fields(
+    expression: &Expression,
+    structs: &[&MemberStructDeclaration],
+    errors: &mut Vec<Error>,
+) {
+    if let Expression::ConstructorExpression(constructor) = expression {
+        if let Some(declaration) = find_struct(constructor, structs) {
+            let mut has_rest = false;
+            let mut field_names = Vec::<&str>::new();
+
+            for entry in &constructor.value.elements {
+                match entry.key.as_ref() {
+                    Expression::Ellipsis(_) => has_rest = true,
+                    Expression::Identifier(i) => {
+                        if declaration.members.iter().any(|m| m.name == i.name) {
+                            field_names.push(&i.name);
+                        } else {
+                            errors.push(new_semantic_error(
+                                format!(
+                                    "struct \"{}\" has no field \"{}\"",
+                                    declaration.name, i.name
+                                ),
+                                &entry.range,
+                            ));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            if !has_rest {
+                for member in &declaration.members {
+                    if !field_names.contains(&member.name.as_str()) {
+                        errors.push(new_semantic_error(
+                            format!(
+                                "missing field \"{}\" in the constructor of struct \"{}\"",
+                                member.name, declaration.name
+                            ),
+                            &constructor.range,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    for child in expression.children() {
+        check_constructor_fields(child, structs, errors);
+    }
+}
+
+fn find_struct<'a>(
+    constructor: &ConstructorExpression,
+    structs: &[&'a MemberStructDeclaration],
+) -> Option<&'a MemberStructDeclaration> {
+    if !constructor.object.dirs.is_empty() {
+        return None;
+    }
+
+    structs
+        .iter()
+        .find(|s| s.name == constructor.object.name)
+        .copied()
+}
+
+// 检查函数的定义是否跟前向声明一致，即泛型的数量、参数的数量以及数据类型、
+// 返回值的数据类型必须相同
+//
+// 函数可以重载，所以按照签名（名称以及参数的数据类型）把定义跟前向声明对应起来；
+// 只有当同名的前向声明以及定义都只有一个时，参数不一致的定义才被视为跟前向声明不一致，
+// 否则它是同名函数的另一个重载。
+//
+// 注：前向声明的函数可以在其他模块里定义，所以没有定义的前向声明不是错误，
+// 名称解析实现之后再检查。
+fn check_forward_declarations(body: &[Statement], errors: &mut Vec<Error>) {
+    let declarations: Vec<&EmptyFunctionDeclaration> = body
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::EmptyFunctionDeclaration(f)
+                if !f.attributes.iter().any(|a| a.name == EXTERN) =>
+            {
+                Some(f)
+            }
+            _ => None,
+        })
+        .collect();
+
+    let functions: Vec<&FunctionDeclaration> = body
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::FunctionDeclaration(f) => Some(f),
+            _ => None,
+        })
+        .collect();
+
+    for function in &functions {
+        let candidates: Vec<&&EmptyFunctionDeclaration> = declarations
+            .iter()
+            .filter(|d| d.name == function.name)
+            .collect();
+
+        // 数据类型使用文本比较，以忽略位置信息
+        let types: Vec<String> = function
+            .parameters
+            .iter()
+            .map(|p| p.data_type.to_string())
+            .collect();
+        let declaration = match candidates.iter().find(|d| {
+            d.parameters
+                .iter()
+                .map(|p| p.data_type.to_string())
+                .eq(types.iter().cloned())
+        }) {
+            Some(declaration) => declaration,
+            None if candidates.len() == 1
+                && functions.iter().filter(|f| f.name == function.name).count() == 1 =>
+            {
+                candidates[0]
+            }
+            None => continue,
+        };
+
+        if let Err(reason) = compare_signatures(declaration, function) {
+            errors.push(new_semantic_error(
+                format!(
+                    "function \"{}\" does not match its forward declaration: {}",
+                    function.name, reason
+                ),
+                &function.range,
+            ));
+        }
+    }
+}
+
+// 比较函数的定义以及前向声明的签名，不一致时返回原因
+fn compare_signatures(
+    declaration: &EmptyFunctionDeclaration,
+    function: &FunctionDeclaration,
+) -> Result<(), String> {
+    if declaration.generics.len() != function.generics.len() {
+        return Err(format!(
+            "expected {} generic types, found {}",
+            declaration.generics.len(),
+            function.generics.len()
+        ));
+    }
+
+    if declaration.parameters.len() != function.parameters.len() {
+        return Err(format!(
+            "expected {} parameters, found {}",
+            declaration.parameters.len(),
+            function.parameters.len()
+        ));
+    }
+
+    // 数据类型使用文本比较，以忽略位置信息
+    for (expected, found) in declaration.parameters.iter().zip(&function.parameters) {
+        if expected.data_type.to_string() != found.data_type.to_string() {
+            return Err(format!(
+                "expected type \"{}\" for parameter \"{}\", found \"{}\"",
+                expected.data_type, found.name, found.data_type
+            ));
+        }
+    }
+
+    // 没有返回值等同于返回空元组
+    let format_return_data_type =
+        |d: &Option<DataType>| d.as_ref().map_or("()".to_string(), |d| d.to_string());
+    let expected = format_return_data_type(&declaration.return_data_type);
+    let found = format_return_data_type(&function.return_data_type);
+
+    if expected != found {
+        return Err(format!(
+            "expected return type \"{}\", found \"{}\"",
+            expected, found
+        ));
+    }
+
+    Ok(())
+}
+
+// 检查特性的继承关系以及 `impl` 语句是否满足特性的要求：
+//
+// - 特性不能是自己（直接或者间接）的父特性；
+// - 特性的空函数（即函数签名）都必须实现，有默认实现的函数可以省略；
+// - 只能实现特性声明的函数；
+// - 函数的参数数量必须跟特性的声明相同，参数以及返回值的数据类型规范化之后
+//   （见 `normalize_data_type`）必须相同，省略返回值类型的函数不比较返回值；
+// - 特性的关联类型（没有默认类型的）都必须绑定，只能绑定特性声明的关联类型，并且不能重复；
+// - 同一个类型必须（在其他 `impl` 语句里）实现特性的所有父特性，
+//   父特性的函数由父特性的 `impl` 语句实现。
+//
+// 注：目前只检查同一个程序里声明的特性，泛型的特性以及泛型函数的数据类型
+// 需要等类型推导实现之后再比较。
+fn check_trait_requirements(body: &[Statement], errors: &mut Vec<Error>) {
+    let traits: Vec<&TraitDeclaration> = body
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::TraitDeclaration(t) => Some(t),
+            _ => None,
+        })
+        .collect();
+
+    let impls: Vec<&ImplStatement> = body
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::ImplStatement(i) => Some(i),
+            _ => None,
+        })
+        .collect();
+
+    for trait_declaration in &traits {
+        if supertraits(trait_declaration, &traits).1 {
+            errors.push(new_semantic_error(
+                format!("trait \"{}\" inherits from itself", trait_declaration.name),
+                &trait_declaration.range,
+            ));
+        }
+    }
+
+    for impl_statement in &impls {
+        let Some(trait_declaration) = find_trait(impl_statement, &traits) else {
+            continue;
+        };
+
+        check_trait_implementation(impl_statement, trait_declaration, errors);
+
+        // 数据类型使用文本比较，以忽略位置信息
+        let object = impl_statement.object.to_string();
+        for supertrait in supertraits(trait_declaration, &traits).0 {
+            if !impls.iter().any(|i| {
+                i.object.to_string() == object
+                    && find_trait(i, &traits).is_some_and(|t| t.name == supertrait.name)
+            }) {
+                errors.push(new_semantic_error(
+                    format!(
+                        "\"{}\" implements trait \"{}\" but not its supertrait \"{}\"",
+                        object, trait_declaration.name, supertrait.name
+                    ),
+                    &impl_statement.range,
+                ));
+            }
+        }
+    }
+}
+
+// 特性所有直接以及间接的父特性（不包括特性自己），只包括 traits 里的特性，
+// 即同一个程序里声明的特性，第二个返回值表示特性是否（直接或者间接地）继承了自己。
+//
+// 父特性包括名称之后的 `limit` 列表，以及 `which` 从属表达式里 `Self` 的约束，
+// 比如 `trait Ord which Self: limit Eq {...}`。
+pub fn supertraits<'a>(
+    trait_declaration: &TraitDeclaration,
+    traits: &[&'a TraitDeclaration],
+) -> (Vec<&'a TraitDeclaration>, bool) {
+    let mut closure: Vec<&TraitDeclaration> = vec![];
+    let mut is_cyclic = false;
+
+    let mut pending: Vec<&TraitDeclaration> = vec![trait_declaration];
+    while let Some(current) = pending.pop() {
+        let limits = current.whiches.iter().flat_map(|w| match w {
+            WhichEntry::Limit(l) if l.name == "Self" => l.data_types.as_slice(),
+            _ => &[],
+        });
+
+        for data_type in current.supertraits.iter().chain(limits) {
+            let DataType::Identifier(identifier) = data_type else {
+                continue;
+            };
+            let Some(supertrait) = traits
+                .iter()
+                .find(|t| identifier.dirs.is_empty() && t.name == identifier.name)
+            else {
+                continue;
+            };
+
+            if supertrait.name == trait_declaration.name {
+                is_cyclic = true;
+            } else if !closure.iter().any(|t| t.name == supertrait.name) {
+                closure.push(supertrait);
+                pending.push(supertrait);
+            }
+        }
+    }
+
+    (closure, is_cyclic)
+}
+
+fn check_trait_implementation(
+    impl_statement: &ImplStatement,
+    trait_declaration: &TraitDeclaration,
+    errors: &mut Vec<Error>,
+) {
+    for item in &trait_declaration.function_items {
+        if let TraitFunctionItem::EmptyFunction(required) = item {
+            if !impl_statement
+                .function_items
+                .iter()
+                .any(|f| f.name == required.name)
+            {
+                errors.push(new_semantic_error(
+                    format!(
+                        "missing function \"{}\" in the implementation of trait \"{}\" for \"{}\"",
+                        required.name, trait_declaration.name, impl_statement.object
+                    ),
+                    &impl_statement.range,
+                ));
+            }
+        }
+    }
+
+    // 关联类型的绑定
+    for (index, associated_type) in impl_statement.associated_types.iter().enumerate() {
+        let reason = if impl_statement.associated_types[..index]
+            .iter()
+            .any(|a| a.name == associated_type.name)
+        {
+            format!(
+                "duplicate binding of associated type \"{}\"",
+                associated_type.name
+            )
+        } else if !trait_declaration
+            .associated_types
+            .iter()
+            .any(|a| a.name == associated_type.name)
+        {
+            format!(
+                "associated type \"{}\" is not a member of trait \"{}\"",
+                associated_type.name, trait_declaration.name
+            )
+        } else if associated_type.object_type.is_none() {
+            format!(
+                "associated type \"{}\" of trait \"{}\" must be bound to a type",
+                associated_type.name, trait_declaration.name
+            )
+        } else {
+            continue;
+        };
+        errors.push(new_semantic_error(reason, &associated_type.range));
+    }
+
+    // 没有默认类型的关联类型都必须绑定
+    for required in &trait_declaration.associated_types {
+        if required.object_type.is_none()
+            && !impl_statement
+                .associated_types
+                .iter()
+                .any(|a| a.name == required.name)
+        {
+            errors.push(new_semantic_error(
+                format!(
+                    "missing associated type \"{}\" in the implementation of trait \"{}\" for \"{}\"",
+                    required.name, trait_declaration.name, impl_statement.object
+                ),
+                &impl_statement.range,
+            ));
+        }
+    }
+
+    // 数据类型规范化之后使用文本比较，以忽略位置信息
+    let bindings = associated_type_bindings(impl_statement, Some(trait_declaration));
+    let normalize = |data_type: &DataType| {
+        normalize_data_type(data_type, &impl_statement.object, &bindings).to_string()
+    };
+
+    for function in &impl_statement.function_items {
+        let signature = trait_declaration
+            .function_items
+            .iter()
+            .find_map(|item| match item {
+                TraitFunctionItem::Function(f) if f.name == function.name => Some((
+                    f.parameters
+                        .iter()
+                        .map(|p| &p.data_type)
+                        .collect::<Vec<&DataType>>(),
+                    &f.return_data_type,
+                    f.generics.len(),
+                )),
+                TraitFunctionItem::EmptyFunction(f) if f.name == function.name => Some((
+                    f.parameters.iter().map(|p| &p.data_type).collect(),
+                    &f.return_data_type,
+                    f.generics.len(),
+                )),
+                _ => None,
+            });
+
+        let Some((parameter_types, return_data_type, generic_count)) = signature else {
+            errors.push(new_semantic_error(
+                format!(
+                    "function \"{}\" is not a member of trait \"{}\"",
+                    function.name, trait_declaration.name
+                ),
+                &function.range,
+            ));
+            continue;
+        };
+
+        if parameter_types.len() != function.parameters.len() {
+            errors.push(new_semantic_error(
+                format!(
+                    "function \"{}\" has {} parameters, but trait \"{}\" declares {}",
+                    function.name,
+                    function.parameters.len(),
+                    trait_declaration.name,
+                    parameter_types.len()
+                ),
+                &function.range,
+            ));
+            continue;
+        }
+
+        // 泛型的特性以及泛型函数需要等类型推导实现之后再比较
+        if !trait_declaration.generics.is_empty()
+            || generic_count > 0
+            || !function.generics.is_empty()
+        {
+            continue;
+        }
+
+        for (expected, parameter) in parameter_types.iter().zip(&function.parameters) {
+            let (expected, found) = (normalize(expected), normalize(&parameter.data_type));
+            if expected != found {
+                errors.push(new_semantic_error(
+                    format!(
+                        "parameter \"{}\" of function \"{}\" has type \"{}\", but trait \"{}\" declares \"{}\"",
+                        parameter.name, function.name, found, trait_declaration.name, expected
+                    ),
+                    &parameter.range,
+                ));
+            }
+        }
+
+        // 省略返回值类型的函数由类型推导确定
+        if let (Some(expected), Some(found)) = (return_data_type, &function.return_data_type) {
+            let (expected, found) = (normalize(expected), normalize(found));
+            if expected != found {
+                errors.push(new_semantic_error(
+                    format!(
+                        "function \"{}\" returns \"{}\", but trait \"{}\" declares \"{}\"",
+                        function.name, found, trait_declaration.name, expected
+                    ),
+                    &function.range,
+                ));
+            }
+        }
+    }
+}
+
+// `impl` 语句里关联类型的名称以及绑定的类型，包括 `impl` 语句的绑定，
+// 以及（没有绑定时）所实现的特性声明的默认类型
+pub fn associated_type_bindings(
+    impl_statement: &ImplStatement,
+    trait_declaration: Option<&TraitDeclaration>,
+) -> Vec<(String, Identifier)> {
+    let mut bindings: Vec<(String, Identifier)> = impl_statement
+        .associated_types
+        .iter()
+        .filter_map(|a| a.object_type.clone().map(|t| (a.name.clone(), t)))
+        .collect();
+
+    for associated_type in trait_declaration.iter().flat_map(|t| &t.associated_types) {
+        if let Some(default_type) = &associated_type.object_type {
+            if !bindings
+                .iter()
+                .any(|(name, _)| *name == associated_type.name)
+            {
+                bindings.push((associated_type.name.clone(), default_type.clone()));
+            }
+        }
+    }
+
+    bindings
+}
+
+// 规范化 `impl` 语句（或者所实现的特性的签名）里的数据类型：
+// `Self` 替换为实现特性的类型 object，关联类型的投影 `Self::Item`（以及特性里直接使用的
+// 关联类型名称 `Item`）替换为 bindings 里绑定的类型（见 `associated_type_bindings`），
+// 泛型参数、元组以及函数签名里的类型同样被替换，替换后的类型保留原来的范围。
+pub fn normalize_data_type(
+    data_type: &DataType,
+    object: &Identifier,
+    bindings: &[(String, Identifier)],
+) -> DataType {
+    let normalize = |data_type: &DataType| normalize_data_type(data_type, object, bindings);
+
+    match data_type {
+        DataType::Identifier(i) => DataType::Identifier(normalize_identifier(i, object, bindings)),
+        DataType::Tuple(t) => DataType::Tuple(Tuple {
+            elements: t
+                .elements
+                .iter()
+                .map(|e| match e {
+                    Expression::Identifier(i) => {
+                        Expression::Identifier(normalize_identifier(i, object, bindings))
+                    }
+                    e => e.clone(),
+                })
+                .collect(),
+            range: t.range.clone(),
+        }),
+        DataType::Sign(s) => DataType::Sign(Sign {
+            parameters: s
+                .parameters
+                .iter()
+                .map(|p| SignParameter {
+                    data_type: normalize(&p.data_type),
+                    ..p.clone()
+                })
+                .collect(),
+            return_data_type: s.return_data_type.as_ref().map(|t| Box::new(normalize(t))),
+            ..s.clone()
+        }),
+    }
+}
+
+fn normalize_identifier(
+    identifier: &Identifier,
+    object: &Identifier,
+    bindings: &[(String, Identifier)],
+) -> Identifier {
+    let is_projection = identifier.generics.is_empty()
+        && (identifier.dirs.is_empty() || identifier.dirs == ["Self"]);
+
+    let replacement = if is_projection && identifier.dirs.is_empty() && identifier.name == "Self" {
+        Some(object)
+    } else if is_projection {
+        bindings
+            .iter()
+            .find(|(name, _)| *name == identifier.name)
+            .map(|(_, data_type)| data_type)
+    } else {
+        None
+    };
+
+    match replacement {
+        Some(replacement) => Identifier {
+            range: identifier.range.clone(),
+            ..replacement.clone()
+        },
+        None => Identifier {
+            generics: identifier
+                .generics
+                .iter()
+                .map(|g| normalize_data_type(g, object, bindings))
+                .collect(),
+            ..identifier.clone()
+        },
+    }
+}
+
+fn find_trait<'a>(
+    impl_statement: &ImplStatement,
+    traits: &[&'a TraitDeclaration],
+) -> Option<&'a TraitDeclaration> {
+    if !impl_statement.inherit.dirs.is_empty() {
+        return None;
+    }
+
+    traits
+        .iter()
+        .find(|t| t.name == impl_statement.inherit.name)
+        .copied()
+}
+
+// 检查模式函数的子句
+//
+// 同名并且参数个数相同的模式函数子句构成一个模式函数，调用时按照子句的顺序
+// 选择第一个匹配的子句，每个子句的参数模式看作模式矩阵（pattern matrix）的一行：
+//
+// - 不可达的子句：某个子句能匹配的参数都被之前的某一个子句匹配（比如相同的字面量，
+//   或者之前的子句没有模式）；
+// - 重叠的子句：两个子句的所有参数都是字面量或者 `in` 模式，并且参数的值有交集，
+//   但谁也不包含谁（比如 `in [1..10]` 和 `in [5..15]`），这通常是边界写错了；
+// - 未穷尽：所有子句的模式没有覆盖所有可能的参数，即缺少匹配剩余情况的子句。
+//
+// 带有 `only` 或者 `where` 从属表达式的子句在编译时无法确定是否匹配，所以不参与覆盖的判断，
+// 但它们本身仍然可能是不可达的。
+//
+// 注：目前只有布尔类型的值是有限的，其他类型（比如枚举、联合体）的值需要等类型检查
+// 实现之后才能判断是否被穷尽，只能依靠没有模式的子句（或者不可反驳的解构模式）覆盖。
+fn check_pattern_functions(body: &[Statement], errors: &mut Vec<Error>) {
+    let mut groups: Vec<Vec<&PatternFunctionDeclaration>> = vec![];
+    for statement in body {
+        if let Statement::PatternFunctionDeclaration(f) = statement {
+            match groups
+                .iter_mut()
+                .find(|g| g[0].name == f.name && g[0].parameters.len() == f.parameters.len())
+            {
+                Some(group) => group.push(f),
+                None => groups.push(vec![f]),
+            }
+        }
+    }
+
+    for group in &groups {
+        check_pattern_function_clauses(group, errors);
+    }
+}
+
+// 参数的模式所能匹配的值
+#[derive(Debug, Clone, PartialEq)]
+enum ParameterPattern {
+    Any,                       // 任意值，比如没有模式或者只是绑定名称
+    Values(Vec<PatternValue>), // 字面量以及 `in` 模式的值
+    Other(String),             // 无法在编译时分析的模式，比如 `regular`，保存模式的文本
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PatternValue {
+    Constant(Constant),
+    Integers(i64, Option<i64>), // 整数范围（不包括结束值），None 表示没有上限
+}
+
+fn check_pattern_function_clauses(group: &[&PatternFunctionDeclaration], errors: &mut Vec<Error>) {
+    let rows: Vec<Vec<ParameterPattern>> = group
+        .iter()
+        .map(|f| f.parameters.iter().map(parameter_pattern).collect())
+        .collect();
+    let is_conditional = |f: &PatternFunctionDeclaration| {
+        f.only.is_some()
+            || f.parameters
+                .iter()
+                .any(|p| p.only.is_some() || p.where_exp.is_some())
+    };
+
+    // 子句的序号从 1 开始
+    for (index, clause) in group.iter().enumerate() {
+        let row = &rows[index];
+        let previous = (0..index).filter(|i| !is_conditional(group[*i]));
+
+        if let Some(covering) = previous
+            .clone()
+            .find(|i| rows[*i].iter().zip(row).all(|(a, b)| covers(a, b)))
+        {
+            errors.push(new_semantic_error(
+                format!(
+                    "clause {} of pattern function \"{}\" is unreachable, \
+                    its arguments are all matched by clause {}",
+                    index + 1,
+                    clause.name,
+                    covering + 1
+                ),
+                &clause.range,
+            ));
+        } else if let Some(overlapping) = previous.clone().find(|i| {
+            rows[*i].iter().zip(row).all(|(a, b)| overlaps(a, b))
+                && !rows[*i].iter().zip(row).all(|(a, b)| covers(b, a))
+        }) {
+            errors.push(new_semantic_error(
+                format!(
+                    "clause {} of pattern function \"{}\" partially overlaps clause {}",
+                    index + 1,
+                    clause.name,
+                    overlapping + 1
+                ),
+                &clause.range,
+            ));
+        }
+    }
+
+    let unconditional: Vec<&[ParameterPattern]> = rows
+        .iter()
+        .zip(group)
+        .filter(|(_, f)| !is_conditional(f))
+        .map(|(row, _)| row.as_slice())
+        .collect();
+    if !is_exhaustive(&unconditional) {
+        let last = group.last().unwrap();
+        errors.push(new_semantic_error(
+            format!(
+                "the clauses of pattern function \"{}\" with {} parameter(s) \
+                do not cover all possible arguments",
+                last.name,
+                last.parameters.len()
+            ),
+            &last.range,
+        ));
+    }
+}
+
+fn parameter_pattern(parameter: &PatternFunctionParameter) -> ParameterPattern {
+    let Some(pattern) = &parameter.pattern else {
+        return ParameterPattern::Any;
+    };
+
+    let values = match pattern.as_ref() {
+        PatternExpression::Primary(e) if is_irrefutable(e) => return ParameterPattern::Any,
+        PatternExpression::Primary(e) => fold::fold(e).map(|c| vec![PatternValue::Constant(c)]),
+        PatternExpression::In(Expression::List(list)) => list
+            .elements
+            .iter()
+            .map(pattern_value)
+            .collect::<Option<Vec<PatternValue>>>(),
+        PatternExpression::In(e @ Expression::Interval(_)) => pattern_value(e).map(|v| vec![v]),
+        _ => None,
+    };
+
+    match values {
+        Some(values) => ParameterPattern::Values(values),
+        None => ParameterPattern::Other(pattern.to_string()),
+    }
+}
+
+fn pattern_value(expression: &Expression) -> Option<PatternValue> {
+    let Expression::Interval(interval) = expression else {
+        return fold::fold(expression).map(PatternValue::Constant);
+    };
+
+    let Some(Constant::Integer(from)) = fold::fold(&interval.from) else {
+        return None;
+    };
+    let to = match &interval.to {
+        Some(to) => match fold::fold(to) {
+            Some(Constant::Integer(to)) if interval.is_inclusive => Some(to.checked_add(1)?),
+            Some(Constant::Integer(to)) => Some(to),
+            _ => return None,
+        },
+        None => None,
+    };
+    Some(PatternValue::Integers(from, to))
+}
+
+// 解构模式里只有名称时（比如 `Point (x, y)`）能匹配该类型的所有值
+fn is_irrefutable(expression: &Expression) -> bool {
+    match expression {
+        Expression::Identifier(_) => true,
+        Expression::Tuple(tuple) => tuple.elements.iter().all(is_irrefutable),
+        _ => false,
+    }
+}
+
+fn value_contains(a: &PatternValue, b: &PatternValue) -> bool {
+    match (a, b) {
+        (PatternValue::Constant(a), PatternValue::Constant(b)) => a == b,
+        (PatternValue::Integers(from, to), PatternValue::Constant(Constant::Integer(value))) => {
+            from <= value && to.is_none_or(|to| *value < to)
+        }
+        (PatternValue::Integers(a_from, a_to), PatternValue::Integers(b_from, b_to)) => {
+            a_from <= b_from
+                && match (a_to, b_to) {
+                    (None, _) => true,
+                    (Some(a_to), Some(b_to)) => b_to <= a_to,
+                    (Some(_), None) => false,
+                }
+        }
+        _ => false,
+    }
+}
+
+fn value_intersects(a: &PatternValue, b: &PatternValue) -> bool {
+    match (a, b) {
+        (PatternValue::Integers(a_from, a_to), PatternValue::Integers(b_from, b_to)) => {
+            a_to.is_none_or(|a_to| *b_from < a_to) && b_to.is_none_or(|b_to| *a_from < b_to)
+        }
+        (PatternValue::Constant(_), PatternValue::Integers(..)) => value_contains(b, a),
+        _ => value_contains(a, b),
+    }
+}
+
+// 模式 a 是否匹配模式 b 能匹配的所有值
+fn covers(a: &ParameterPattern, b: &ParameterPattern) -> bool {
+    match (a, b) {
+        (ParameterPattern::Any, _) => true,
+        (ParameterPattern::Values(a), ParameterPattern::Values(b)) => {
+            b.iter().all(|b| a.iter().any(|a| value_contains(a, b)))
+        }
+        (ParameterPattern::Other(a), ParameterPattern::Other(b)) => a == b,
+        _ => false,
+    }
+}
+
+// 模式 a 和 b 是否都是字面量（或者 `in` 模式）并且能匹配共同的值
+fn overlaps(a: &ParameterPattern, b: &ParameterPattern) -> bool {
+    match (a, b) {
+        (ParameterPattern::Values(a), ParameterPattern::Values(b)) => {
+            a.iter().any(|a| b.iter().any(|b| value_intersects(a, b)))
+        }
+        _ => false,
+    }
+}
+
+// 判断模式矩阵的行是否覆盖了所有可能的参数
+//
+// 逐列检查：第一列为任意值的行覆盖了所有剩余的情况时，整个矩阵是穷尽的；
+// 否则第一列必须是值有限的类型（目前只有布尔类型），并且对于每一个值，
+// 能匹配该值的行（去掉第一列）都是穷尽的。
+fn is_exhaustive(rows: &[&[ParameterPattern]]) -> bool {
+    if rows.iter().any(|row| row.is_empty()) {
+        return true;
+    }
+    if rows.is_empty() {
+        return false;
+    }
+
+    let rest = |matches: &dyn Fn(&ParameterPattern) -> bool| -> Vec<&[ParameterPattern]> {
+        rows.iter()
+            .filter(|row| matches(&row[0]))
+            .map(|row| &row[1..])
+            .collect()
+    };
+
+    if is_exhaustive(&rest(&|p| *p == ParameterPattern::Any)) {
+        return true;
+    }
+
+    let has_booleans = rows.iter().any(|row| {
+        matches!(&row[0], ParameterPattern::Values(values)
+            if values.iter().any(|v| matches!(v, PatternValue::Constant(Constant::Boolean(_)))))
+    });
+
+    has_booleans
+        && [true, false].iter().all(|value| {
+            let value = PatternValue::Constant(Constant::Boolean(*value));
+            is_exhaustive(&rest(&|p| match p {
+                ParameterPattern::Any => true,
+                ParameterPattern::Values(values) => values.contains(&value),
+                ParameterPattern::Other(_) => false,
+            }))
+        })
+}
+
+// 检查 `next` 表达式是否位于 `for` 表达式的主体里
+//
+// `function` 为所在的函数的名称以及范围，位于匿名函数里时名称为空字符串，
+// 位于函数之外时为 `None`。错误带有指向所在的函数的次要标签。
+fn check_next(
+    expression: &Expression,
+    in_loop: bool,
+    function: Option<(&str, &Range)>,
+    errors: &mut Vec<Error>,
+) {
+    match expression {
+        Expression::ForExpression(v) => {
+            check_next(&v.initializer.value, in_loop, function, errors);
+            check_next(&v.body, true, function, errors);
+            return;
+        }
+        Expression::AnonymousFunction(v) => {
+            check_next(&v.body, false, Some(("", &v.range)), errors);
+            return;
+        }
+        Expression::NextExpression(v) if !in_loop => {
+            let (location, related) = match function {
+                Some(("", range)) => (
+                    " (in an anonymous function)".to_string(),
+                    vec![("in this anonymous function".to_string(), range.clone())],
+                ),
+                Some((name, range)) => (
+                    format!(" (in function \"{}\")", name),
+                    vec![(format!("in function \"{}\"", name), range.clone())],
+                ),
+                None => ("".to_string(), vec![]),
+            };
+            errors.push(Error::SemanticError {
+                message: format!(
+                    "\"{}\" can only be used inside the body of a `for` expression{}",
+                    expression, location
+                ),
+                related,
+                range: v.range.clone(),
+            });
+        }
+        _ => {}
+    }
+
+    for child in expression.children() {
+        check_next(child, in_loop, function, errors);
+    }
+}
+
+// 检查 `join` 表达式的子表达式是否相互独立
+fn check_join(join: &JoinExpression, errors: &mut Vec<Error>) {
+    // 各个子表达式的 `let` 绑定的名称，以及子表达式的序号
+    let mut bound_names: Vec<(usize, String)> = vec![];
+
+    for (index, expression) in join.body.iter().enumerate() {
+        if let Expression::LetExpression(v) = expression {
+            for name in pattern_names(&v.object) {
+                if bound_names.iter().any(|(_, n)| *n == name) {
+                    errors.push(new_semantic_error(
+                        format!(
+                            "\"{}\" is bound more than once in the same `join` expression",
+                            name
+                        ),
+                        &v.range,
+                    ));
+                } else {
+                    bound_names.push((index, name));
+                }
+            }
+        }
+    }
+
+    for (index, expression) in join.body.iter().enumerate() {
+        for variable in free_variables_of(expression) {
+            if bound_names
+                .iter()
+                .any(|(i, n)| *i != index && *n == variable.name)
+            {
+                errors.push(new_semantic_error(
+                    format!(
+                        "\"{}\" depends on \"{}\" which is bound by another sub-expression \
+                        of the same `join` expression",
+                        expression, variable.name
+                    ),
+                    &variable.range,
+                ));
+            }
+        }
+
+        check_join_next(expression, errors);
+    }
+}
+
+// `join` 表达式的子表达式不能直接包含 `next` 表达式
+fn check_join_next(expression: &Expression, errors: &mut Vec<Error>) {
+    match expression {
+        Expression::ForExpression(v) => {
+            // `for` 表达式主体里的 `next` 表达式属于该 `for` 表达式
+            check_join_next(&v.initializer.value, errors);
+            return;
+        }
+        Expression::AnonymousFunction(_) | Expression::JoinExpression(_) => {
+            // 内层的 `join` 表达式由其自身检查
+            return;
+        }
+        Expression::NextExpression(v) => {
+            errors.push(new_semantic_error(
+                format!(
+                    "\"{}\" cannot be used directly inside a `join` expression",
+                    expression
+                ),
+                &v.range,
+            ));
+        }
+        _ => {}
+    }
+
+    for child in expression.children() {
+        check_join_next(child, errors);
+    }
+}
+
+// 检查剩余项目 `...` 的数量、位置以及名称
+fn check_rest(expression: &Expression, in_pattern: bool, errors: &mut Vec<Error>) {
+    match expression {
+        Expression::LetExpression(v) => {
+            check_rest(&v.object, true, errors);
+            check_rest(&v.value, false, errors);
+            return;
+        }
+        Expression::ForExpression(v) => {
+            check_rest(&v.initializer.object, true, errors);
+            check_rest(&v.initializer.value, false, errors);
+            check_rest(&v.body, false, errors);
+            return;
+        }
+        Expression::EachExpression(v) => {
+            check_rest(&v.variable, true, errors);
+            check_rest(&v.object, false, errors);
+            check_rest(&v.body, false, errors);
+            return;
+        }
+        Expression::MatchExpression(v) => {
+            // 只有普通模式表达式是模式，`in` 模式表达式是普通的表达式
+            check_rest(&v.object, false, errors);
+            for c in &v.cases {
+                match c.pattern.as_deref() {
+                    Some(PatternExpression::Primary(e)) => check_rest(e, true, errors),
+                    Some(PatternExpression::In(e)) => check_rest(e, false, errors),
+                    _ => {}
+                }
+                for e in [&c.only, &c.where_exp].into_iter().flatten() {
+                    check_rest(e, false, errors);
+                }
+                check_rest(&c.consequent, false, errors);
+            }
+            for e in [&v.where_exp, &v.default_exp].into_iter().flatten() {
+                check_rest(e, false, errors);
+            }
+            return;
+        }
+        Expression::Tuple(v) => check_rest_elements(v.elements.iter(), expression, errors),
+        Expression::List(v) => check_rest_elements(v.elements.iter(), expression, errors),
+        Expression::Map(v) => check_rest_elements(
+            v.elements.iter().map(|e| e.key.as_ref()),
+            expression,
+            errors,
+        ),
+        Expression::ConstructorExpression(v) => check_rest_elements(
+            v.value.elements.iter().map(|e| e.key.as_ref()),
+            expression,
+            errors,
+        ),
+        Expression::Ellipsis(Ellipsis { name: None, range }) if !in_pattern => {
+            errors.push(new_semantic_error(
+                "rest element \"...\" without a name can only be used in patterns".to_string(),
+                range,
+            ));
+        }
+        _ => {}
+    }
+
+    for child in expression.children() {
+        check_rest(child, in_pattern, errors);
+    }
+}
+
+fn check_rest_elements<'a>(
+    elements: impl Iterator<Item = &'a Expression>,
+    expression: &Expression,
+    errors: &mut Vec<Error>,
+) {
+    let elements: Vec<&Expression> = elements.collect();
+    let rests: Vec<(usize, &Ellipsis)> = elements
+        .iter()
+        .enumerate()
+        .filter_map(|(index, e)| match e {
+            Expression::Ellipsis(ellipsis) => Some((index, ellipsis)),
+            _ => None,
+        })
+        .collect();
+
+    if let Some((_, ellipsis)) = rests.get(1) {
+        errors.push(new_semantic_error(
+            format!("more than one rest element in \"{}\"", expression),
+            &ellipsis.range,
+        ));
+    } else if let Some((index, ellipsis)) = rests.first() {
+        if *index != elements.len() - 1 {
+            errors.push(new_semantic_error(
+                format!(
+                    "rest element \"{}\" must be the last entry of \"{}\"",
+                    ellipsis, expression
+                ),
+                &ellipsis.range,
+            ));
+        }
+    }
+}
+
+pub const DEFAULT_ENTRY_NAME: &str = "main";
+
+// 检查 `env("NAME")` 表达式读取的构建环境值是否都已定义
+//
+// 注：构建环境由编译驱动程序提供，所以本检查不包括在 `check` 里。
+pub fn check_build_environment(node: &Node, environment: &BuildEnvironment) -> Vec<Error> {
+    let mut errors = Vec::<Error>::new();
+
+    let expressions = match node {
+        Node::Program(program) => program
+            .body
+            .iter()
+            .flat_map(|statement| statement.expressions())
+            .collect(),
+        Node::Statement(statement) => statement.expressions(),
+        Node::Expression(expression) => vec![expression],
+    };
+
+    for expression in expressions {
+        check_env_names(expression, environment, &mut errors);
+    }
+
+    errors
+}
+
+fn check_env_names(
+    expression: &Expression,
+    environment: &BuildEnvironment,
+    errors: &mut Vec<Error>,
+) {
+    if let Expression::FunctionCallExpression(v) = expression {
+        if let Some(name) = fold::env_name(v) {
+            if environment.get(name).is_none() {
+                errors.push(new_semantic_error(
+                    format!("build environment value \"{}\" is not defined", name),
+                    &v.range,
+                ));
+            }
+        }
+    }
+
+    for child in expression.children() {
+        check_env_names(child, environment, errors);
+    }
+}
+
+// 在编译时求值静态断言 `static_assert(condition, "message")`，返回断言失败等错误
+//
+// 断言的条件必须是能够在编译时求值（见 `fold` 模块）的布尔值，
+// 条件可以读取构建环境值，所以本检查跟 `check_build_environment` 一样不包括在 `check` 里。
+// 静态断言不会产生运行时的代码。
+pub fn check_static_assertions(node: &Node, environment: &BuildEnvironment) -> Vec<Error> {
+    let mut errors = Vec::<Error>::new();
+
+    let expressions = match node {
+        Node::Program(program) => program
+            .body
+            .iter()
+            .flat_map(|statement| statement.expressions())
+            .collect(),
+        Node::Statement(statement) => statement.expressions(),
+        Node::Expression(expression) => vec![expression],
+    };
+
+    for expression in expressions {
+        check_static_assertion(expression, environment, &mut errors);
+    }
+
+    errors
+}
+
+fn check_static_assertion(
+    expression: &Expression,
+    environment: &BuildEnvironment,
+    errors: &mut Vec<Error>,
+) {
+    if let Expression::FunctionCallExpression(v) = expression {
+        if let Some((condition, message)) = fold::static_assertion(v) {
+            let error_message = match fold::fold_with_environment(&condition.value, environment) {
+                Some(Constant::Boolean(true)) => None,
+                Some(Constant::Boolean(false)) => Some(match message {
+                    Some(message) => format!("static assertion failed: {}", message),
+                    None => format!("static assertion failed: {}", condition.value),
+                }),
+                Some(constant) => Some(format!(
+                    "the condition \"{}\" of the static assertion must be a Boolean, \
+                    found {}",
+                    condition.value,
+                    constant.type_name()
+                )),
+                None => Some(format!(
+                    "the condition \"{}\" of the static assertion is not a constant expression",
+                    condition.value
+                )),
+            };
+
+            if let Some(error_message) = error_message {
+                errors.push(new_semantic_error(error_message, &condition.range));
+            }
+        }
+    }
+
+    for child in expression.children() {
+        check_static_assertion(child, environment, errors);
+    }
+}
+
+// 检查可执行程序的入口函数，返回入口函数
+//
+// 入口函数的名称默认为 `main`，要求：
+//
+// - 有且只有一个；
+// - 不是泛型函数；
+// - 没有必须提供值的参数（即所有参数都有默认值）；
+// - 没有返回值，或者返回值的类型为 `Int`（作为进程的退出码）或者 `()`。
+//
+// 注：库（非可执行程序）不需要入口函数，所以本检查不包括在 `check` 里，由编译驱动程序调用。
+pub fn check_entry_point<'a>(
+    node: &'a Node,
+    entry_name: &str,
+) -> Result<&'a FunctionDeclaration, Error> {
+    let functions: Vec<&FunctionDeclaration> = match node {
+        Node::Program(program) => program
+            .body
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::FunctionDeclaration(f) if f.name == entry_name => Some(f),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+
+    let function = match functions.as_slice() {
+        [] => {
+            return Err(new_semantic_error(
+                format!(
+                    "no entry function \"{}\" found, an executable program needs \
+                    a function like \"function {}() = ...\"",
+                    entry_name, entry_name
+                ),
+                &Range {
+                    file_id: 0,
+                    start: 0,
+                    end: 0,
+                },
+            ))
+        }
+        [function] => *function,
+        [_, duplicate, ..] => {
+            return Err(new_semantic_error(
+                format!(
+                    "the entry function \"{}\" is defined {} times",
+                    entry_name,
+                    functions.len()
+                ),
+                &duplicate.range,
+            ))
+        }
+    };
+
+    if !function.generics.is_empty() {
+        return Err(new_semantic_error(
+            format!("the entry function \"{}\" must not be generic", entry_name),
+            &function.range,
+        ));
+    }
+
+    if let Some(parameter) = function.parameters.iter().find(|p| p.value.is_none()) {
+        return Err(new_semantic_error(
+            format!(
+                "the entry function \"{}\" must not have required parameters, \
+                found \"{} {}\" (give it a default value or remove it)",
+                entry_name, parameter.data_type, parameter.name
+            ),
+            &parameter.range,
+        ));
+    }
+
+    let is_valid_return_type = match &function.return_data_type {
+        None => true,
+        Some(DataType::Identifier(i)) => {
+            i.dirs.is_empty() && i.generics.is_empty() && i.name == "Int"
+        }
+        Some(DataType::Tuple(t)) => t.elements.is_empty(),
+        Some(_) => false,
+    };
+
+    if !is_valid_return_type {
+        return Err(new_semantic_error(
+            format!(
+                "the entry function \"{}\" must return Int or nothing, found \"{}\"",
+                entry_name,
+                function.return_data_type.as_ref().unwrap()
+            ),
+            &function.range,
+        ));
+    }
+
+    Ok(function)
+}
+
+// 函数的内联提示
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InlineHint {
+    Hint,   // `#[inline]`，由优化器决定
+    Always, // `#[inline(always)]`
+    Never,  // `#[inline(never)]`
+}
+
+// 函数的优化提示，由代码生成阶段（内联以及代码布局）使用
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OptimizationHints {
+    pub inline: Option<InlineHint>,
+    pub cold: bool, // `#[cold]`，函数很少被调用
+}
+
+// 读取函数的优化提示，属性无效时返回错误的原因
+pub fn optimization_hints(function: &FunctionDeclaration) -> Result<OptimizationHints, String> {
+    let mut hints = OptimizationHints::default();
+
+    for attribute in &function.attributes {
+        match attribute.name.as_str() {
+            "inline" => {
+                if hints.inline.is_some() {
+                    return Err("duplicate attribute \"inline\"".to_string());
+                }
+
+                let hint = match attribute.arguments.as_slice() {
+                    [] => InlineHint::Hint,
+                    [(name, None)] if name == "always" => InlineHint::Always,
+                    [(name, None)] if name == "never" => InlineHint::Never,
+                    _ => {
+                        return Err(format!(
+                            "invalid attribute \"{}\", expected \"#[inline]\", \
+                            \"#[inline(always)]\" or \"#[inline(never)]\"",
+                            attribute
+                        ))
+                    }
+                };
+                hints.inline = Some(hint);
+            }
+            "cold" => {
+                if hints.cold {
+                    return Err("duplicate attribute \"cold\"".to_string());
+                }
+                if !attribute.arguments.is_empty() {
+                    return Err("attribute \"cold\" does not take arguments".to_string());
+                }
+                hints.cold = true;
+            }
+            _ => {}
+        }
+    }
+
+    if hints.cold && hints.inline == Some(InlineHint::Always) {
+        return Err("attribute \"cold\" conflicts with \"inline(always)\"".to_string());
+    }
+
+    Ok(hints)
+}
+
+const EXTERN: &str = "extern";
+
+// 外部函数的参数以及返回值允许的数据类型，这些类型的值可以直接
+// 通过寄存器（或者 WASM 的 i64、f64、i32）传递给外部函数
+const FFI_DATA_TYPES: [&str; 4] = ["Int", "Float", "Boolean", "Char"];
+
+// 外部函数，即由运行时或者宿主环境实现的函数，代码生成阶段转换为导入项（import entry）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternFunction {
+    pub module: String, // 外部模块的名称，比如 "wasi"
+    pub name: String,   // 外部函数的名称，比如 "fd_write"
+}
+
+// 读取空函数的 `#[extern("module", "name")]` 属性，
+// 没有该属性时返回 Ok(None)，属性无效或者存在 FFI 不允许的类型时返回错误的原因。
+pub fn extern_function(
+    function: &EmptyFunctionDeclaration,
+) -> Result<Option<ExternFunction>, String> {
+    let mut attributes = function.attributes.iter().filter(|a| a.name == EXTERN);
+
+    let attribute = match attributes.next() {
+        Some(attribute) => attribute,
+        None => return Ok(None),
+    };
+
+    if attributes.next().is_some() {
+        return Err("duplicate attribute \"extern\"".to_string());
+    }
+
+    let arguments = attribute
+        .arguments
+        .iter()
+        .map(|argument| match argument {
+            (name, Some(Expression::Literal(Literal::GeneralString(s))))
+                if name.is_empty() && !s.value.is_empty() =>
+            {
+                Some(s.value.clone())
+            }
+            _ => None,
+        })
+        .collect::<Vec<Option<String>>>();
+
+    let (module, name) = match arguments.as_slice() {
+        [Some(module), Some(name)] => (module.clone(), name.clone()),
+        _ => {
+            return Err(format!(
+                "invalid attribute \"{}\", expected \"#[extern(\"module\", \"name\")]\"",
+                attribute
+            ))
+        }
+    };
+
+    if !function.generics.is_empty() {
+        return Err("extern functions cannot be generic".to_string());
+    }
+
+    for parameter in &function.parameters {
+        if !is_ffi_data_type(&parameter.data_type) {
+            return Err(format!(
+                "type \"{}\" of parameter \"{}\" is not allowed in extern functions, {}",
+                parameter.data_type,
+                parameter.name,
+                expected_ffi_data_types()
+            ));
+        }
+    }
+
+    match &function.return_data_type {
+        Some(DataType::Tuple(t)) if t.elements.is_empty() => {}
+        Some(data_type) if !is_ffi_data_type(data_type) => {
+            return Err(format!(
+                "return type \"{}\" is not allowed in extern functions, {}",
+                data_type,
+                expected_ffi_data_types()
+            ));
+        }
+        _ => {}
+    }
+
+    Ok(Some(ExternFunction { module, name }))
+}
+
+fn is_ffi_data_type(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Identifier(i)
+        if i.dirs.is_empty() && i.generics.is_empty() && FFI_DATA_TYPES.contains(&i.name.as_str()))
+}
+
+fn expected_ffi_data_types() -> String {
+    format!(
+        "expected {} or {}",
+        FFI_DATA_TYPES[..FFI_DATA_TYPES.len() - 1].join(", "),
+        FFI_DATA_TYPES[FFI_DATA_TYPES.len() - 1]
+    )
+}
+
+// 哈希字符串表
+//
+// 按照首次出现的顺序为每个不同的哈希字符串分配编号（从 0 开始），
+// 代码生成阶段使用编号代替字符串，常量池里每个值只保存一份。
+#[derive(Debug, Default, PartialEq)]
+pub struct HashStringTable {
+    values: Vec<String>,
+}
+
+impl HashStringTable {
+    // 返回哈希字符串的编号，如果是新的值则先加入到表里
+    pub fn intern(&mut self, value: &str) -> usize {
+        match self.id(value) {
+            Some(id) => id,
+            None => {
+                self.values.push(value.to_string());
+                self.values.len() - 1
+            }
+        }
+    }
+
+    pub fn id(&self, value: &str) -> Option<usize> {
+        self.values.iter().position(|v| v == value)
+    }
+
+    pub fn value(&self, id: usize) -> Option<&str> {
+        self.values.get(id).map(|v| v.as_str())
+    }
+
+    pub fn values(&self) -> &[String] {
+        &self.values
+    }
+}
+
+// 收集程序（或者语句、表达式）里的所有哈希字符串
+pub fn intern_hash_strings(node: &Node) -> HashStringTable {
+    let mut table = HashStringTable::default();
+
+    match node {
+        Node::Program(program) => {
+            for statement in &program.body {
+                for expression in statement.expressions() {
+                    collect_hash_strings(expression, &mut table);
+                }
+            }
+        }
+        Node::Statement(statement) => {
+            for expression in statement.expressions() {
+                collect_hash_strings(expression, &mut table);
+            }
+        }
+        Node::Expression(expression) => collect_hash_strings(expression, &mut table),
+    }
+
+    table
+}
+
+fn collect_hash_strings(expression: &Expression, table: &mut HashStringTable) {
+    if let Expression::Literal(Literal::HashString(h)) = expression {
+        table.intern(&h.value);
+    }
+
+    for child in expression.children() {
+        collect_hash_strings(child, table);
+    }
+}
+
+fn check_interval(interval: &Interval, errors: &mut Vec<Error>) {
+    let from = fold::fold(&interval.from);
+    let to = interval.to.as_deref().and_then(fold::fold);
+
+    // 检查边界的类型
+    for bound in [&from, &to].into_iter().flatten() {
+        match bound {
+            Constant::Integer(_) | Constant::Char(_) => {}
+            Constant::Float(_) if !interval.is_inclusive => {}
+            Constant::Float(_) => {
+                errors.push(new_semantic_error(
+                    format!(
+                        "the bounds of inclusive interval \"{}\" must not be floats",
+                        interval
+                    ),
+                    &interval.range,
+                ));
+                return;
+            }
+            _ => {
+                errors.push(new_semantic_error(
+                    format!(
+                        "the bounds of interval \"{}\" must be Int, Float or Char, found {}",
+                        interval,
+                        bound.type_name()
+                    ),
+                    &interval.range,
+                ));
+                return;
+            }
+        }
+    }
+
+    if let (Some(from), Some(to)) = (&from, &to) {
+        let is_empty = match (from, to) {
+            (Constant::Integer(l), Constant::Integer(r)) => is_empty_interval(l, r, interval),
+            (Constant::Float(l), Constant::Float(r)) => is_empty_interval(l, r, interval),
+            (Constant::Char(l), Constant::Char(r)) => is_empty_interval(l, r, interval),
+            _ => {
+                errors.push(new_semantic_error(
+                    format!(
+                        "the bounds of interval \"{}\" have incompatible types {} and {}",
+                        interval,
+                        from.type_name(),
+                        to.type_name()
+                    ),
+                    &interval.range,
+                ));
+                return;
+            }
+        };
+
+        if is_empty {
+            errors.push(new_semantic_error(
+                format!("interval \"{}\" is empty", interval),
+                &interval.range,
+            ));
+        }
+    }
+}
+
+fn check_complex_operation(binary: &BinaryExpression, errors: &mut Vec<Error>) {
+    let operands = [fold::fold(&binary.left), fold::fold(&binary.right)];
+    if !operands
+        .iter()
+        .any(|c| matches!(c, Some(Constant::Complex(_, _))))
+    {
+        return;
+    }
+
+    match binary.operator {
+        BinaryOperator::GreaterThan
+        | BinaryOperator::GreaterThanOrEqual
+        | BinaryOperator::LessThan
+        | BinaryOperator::LessThanOrEqual => {
+            errors.push(new_semantic_error(
+                format!("complex numbers cannot be ordered: \"{}\"", binary),
+                &binary.range,
+            ));
+        }
+        BinaryOperator::Plus
+        | BinaryOperator::Minus
+        | BinaryOperator::Asterisk
+        | BinaryOperator::Slash => {
+            if let Some(other) = operands.iter().flatten().find(|c| !c.is_numeric()) {
+                errors.push(new_semantic_error(
+                    format!(
+                        "cannot apply \"{}\" to Complex and {}",
+                        binary.operator,
+                        other.type_name()
+                    ),
+                    &binary.range,
+                ));
+            }
+        }
+        _ => {}
+    }
+}
+
+// 检查两个（常量）比特值的宽度是否相同，
+// 使用 `^` 转换过的操作数不是常量，所以不会被检查。
+fn check_bit_widths(
+    left: &Expression,
+    right: &Expression,
+    expression: &Expression,
+    range: &Range,
+    errors: &mut Vec<Error>,
+) {
+    if let (Some(Constant::Bit(l_width, _)), Some(Constant::Bit(r_width, _))) =
+        (fold::fold(left), fold::fold(right))
+    {
+        if l_width != r_width {
+            errors.push(new_semantic_error(
+                format!(
+                    "mismatched bit widths {} and {} in \"{}\", use \"^\" to cast one of them",
+                    l_width, r_width, expression
+                ),
+                range,
+            ));
+        }
+    }
+}
+
+// 数值的有效二进制位数（不包括高位的 0）
+fn significant_bits(bytes: &[u8]) -> usize {
+    match bytes.iter().position(|byte| *byte != 0) {
+        Some(index) => (bytes.len() - index) * 8 - bytes[index].leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+fn is_empty_interval<T: PartialOrd>(from: &T, to: &T, interval: &Interval) -> bool {
+    if interval.is_inclusive {
+        from > to
+    } else {
+        from >= to
+    }
+}
+
+// 检查 `match` 表达式分支的模式
+//
+// 注：模式表达式里的字符串字面量目前不记录位置，所以错误的位置为整个分支。
+fn check_match_pattern(pattern: &PatternExpression, range: &Range, errors: &mut Vec<Error>) {
+    match pattern {
+        PatternExpression::Regular(regular, names) => match count_regular_captures(regular) {
+            Ok(count) if names.elements.len() != count + 1 => {
+                errors.push(new_semantic_error(
+                    format!(
+                        "regular pattern \"{}\" has {} capture group(s) and expects {} name(s), found {}",
+                        regular,
+                        count,
+                        count + 1,
+                        names.elements.len()
+                    ),
+                    range,
+                ));
+            }
+            Ok(_) => {}
+            Err(reason) => errors.push(new_semantic_error(
+                format!("invalid regular pattern \"{}\": {}", regular, reason),
+                range,
+            )),
+        },
+        PatternExpression::Template(template) => {
+            // 重复的占位符单独报告，它们仍然定义了同名的变量（见 `ScopeResolver`）
+            if let Ok(placeholders) = scan_template(template) {
+                for (index, placeholder) in placeholders.iter().enumerate() {
+                    if placeholders[..index]
+                        .iter()
+                        .any(|p| p.name == placeholder.name)
+                    {
+                        errors.push(new_semantic_error(
+                            format!(
+                                "duplicate placeholder \"{}\" in template pattern \"{}\"",
+                                placeholder.name, template
+                            ),
+                            range,
+                        ));
+                    }
+                }
+            }
+
+            let result = scan_template(template).and_then(|placeholders| {
+                placeholders.iter().try_for_each(|p| {
+                    count_regular_captures(&p.regular)
+                        .map(|_| ())
+                        .map_err(|reason| {
+                            format!(
+                                "placeholder \"{}\" has an invalid regular expression: {}",
+                                p.name, reason
+                            )
+                        })
+                })
+            });
+
+            if let Err(reason) = result {
+                errors.push(new_semantic_error(
+                    format!("invalid template pattern \"{}\": {}", template, reason),
+                    range,
+                ));
+            }
+        }
+        _ => {}
+    }
+}
+
+// 解析正则表达式，返回（显式的）捕获组的数量，正则表达式无效时返回错误的原因
+pub fn count_regular_captures(regular: &str) -> Result<usize, String> {
+    match regex_syntax::Parser::new().parse(regular) {
+        Ok(hir) => Ok(hir.properties().explicit_captures_len()),
+        Err(regex_syntax::Error::Parse(e)) => Err(e.kind().to_string()),
+        Err(regex_syntax::Error::Translate(e)) => Err(e.kind().to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// 模板模式里的占位符
+//
+// 占位符的格式为 `{name}` 或者 `{name:regular}`，其中 regular 为正则表达式，
+// 省略时默认为 `\w+`。正则表达式里可以包含成对的花括号，比如 `{id:\d{3}}`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplatePlaceholder {
+    pub name: String,
+    pub regular: String,
+}
+
+pub const DEFAULT_TEMPLATE_REGULAR: &str = "\\w+";
+
+// 解析模板模式的字符串，返回所有占位符，格式错误或者占位符的名称重复时返回错误的原因
+pub fn parse_template(template: &str) -> Result<Vec<TemplatePlaceholder>, String> {
+    let placeholders = scan_template(template)?;
+
+    for (index, placeholder) in placeholders.iter().enumerate() {
+        if placeholders[..index]
+            .iter()
+            .any(|p| p.name == placeholder.name)
+        {
+            return Err(format!("duplicate placeholder \"{}\"", placeholder.name));
+        }
+    }
+
+    Ok(placeholders)
+}
+
+// 解析模板模式的字符串，只检查格式，名称重复的占位符会被依次列出
+fn scan_template(template: &str) -> Result<Vec<TemplatePlaceholder>, String> {
+    let mut placeholders = Vec::<TemplatePlaceholder>::new();
+    let mut rest = template;
+
+    while let Some(index) = rest.find(['{', '}']) {
+        if rest[index..].starts_with('}') {
+            return Err("unmatched \"}\"".to_string());
+        }
+
+        // 找出对应的 `}`，正则表达式里可能包含成对的花括号
+        let content = &rest[index + 1..];
+        let mut depth = 1;
+        let end = content
+            .char_indices()
+            .find_map(|(i, c)| {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                (depth == 0).then_some(i)
+            })
+            .ok_or_else(|| "unclosed placeholder".to_string())?;
+
+        placeholders.push(parse_template_placeholder(&content[..end])?);
+        rest = &content[end + 1..];
+    }
+
+    Ok(placeholders)
+}
+
+fn parse_template_placeholder(content: &str) -> Result<TemplatePlaceholder, String> {
+    let (name, regular) = match content.split_once(':') {
+        Some((name, regular)) => (name, regular),
+        None => (content, DEFAULT_TEMPLATE_REGULAR),
+    };
+
+    let is_valid_name = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+
+    if !is_valid_name {
+        return Err(format!("invalid placeholder name \"{}\"", name));
+    }
+
+    if regular.is_empty() {
+        return Err(format!(
+            "placeholder \"{}\" has an empty regular expression",
+            name
+        ));
+    }
+
+    Ok(TemplatePlaceholder {
+        name: name.to_string(),
+        regular: regular.to_string(),
+    })
+}
+
+fn new_semantic_error(message: String, range: &Range) -> Error {
+    Error::SemanticError {
+        message,
+        related: vec![],
+        range: range.clone(),
+    }
+}
+
+// 表达式里未被绑定的名称
+#[derive(Debug, Clone, PartialEq)]
+pub struct FreeVariable {
+    pub name: String,
+    pub range: Range,
+}
+
+// 列出范围为 `range` 的表达式里所有未被绑定（即不是在表达式之内定义）的名称，
+// 按照在源代码里出现的顺序排列，同一个名称的每次使用都会被列出。
+// 节点里没有范围恰好为 `range` 的表达式时返回 None。
+//
+// 用于 "提取函数" 重构（自由变量即新函数的参数）、闭包捕获的诊断信息，
+// 以及解释器构建运行环境，这些场合一般只有源代码的范围（比如编辑器里选中的文本）。
+//
+// 注：
+// - 带有名称空间路径的标识符（比如 `std::math::PI`）不是局部变量，不会被列出；
+// - 名称是否指向函数或者常量需要名称解析，目前未区分。
+pub fn free_variables(node: &Node, range: &Range) -> Option<Vec<FreeVariable>> {
+    let expressions = match node {
+        Node::Program(program) => program
+            .body
+            .iter()
+            .flat_map(|statement| statement.expressions())
+            .collect(),
+        Node::Statement(statement) => statement.expressions(),
+        Node::Expression(expression) => vec![expression],
+    };
+
+    expressions
+        .into_iter()
+        .find_map(|expression| find_expression(expression, range))
+        .map(free_variables_of)
+}
+
+// 查找范围为 `range` 的表达式，范围相同时返回最外层的表达式
+fn find_expression<'a>(expression: &'a Expression, range: &Range) -> Option<&'a Expression> {
+    if expression.range() == range {
+        return Some(expression);
+    }
+
+    let current = expression.range();
+    if current.file_id != range.file_id || current.start > range.start || current.end < range.end {
+        return None;
+    }
+
+    expression
+        .children()
+        .into_iter()
+        .find_map(|child| find_expression(child, range))
+}
+
+// 列出表达式里所有未被绑定的名称，见 `free_variables`
+pub(crate) fn free_variables_of(expression: &Expression) -> Vec<FreeVariable> {
+    let mut resolver = ScopeResolver::default();
+    resolver.visit(expression);
+    resolver.free_variables
+}
+
+// 列出表达式里未被绑定的名称，`parameters` 是函数的参数，`patterns` 是模式函数的参数的模式，
+// 它们绑定的名称在表达式里有效。
+//
+// 用于名称解析（见 `resolve` 模块），未被绑定的名称需要在全局作用域里查找。
+pub fn unbound_names(
+    parameters: &[&str],
+    patterns: &[&PatternExpression],
+    expressions: &[&Expression],
+) -> Vec<FreeVariable> {
+    let mut resolver = ScopeResolver::default();
+    for name in parameters {
+        resolver.bind_name(name);
+    }
+    for pattern in patterns {
+        resolver.bind_match_pattern(pattern);
+    }
+    for expression in expressions {
+        resolver.visit(expression);
+    }
+    resolver.free_variables
+}
+
+// 列出 `let` 表达式的左手边值（以及 `each`、`for` 表达式的变量）等模式所绑定的名称
+pub fn pattern_names(pattern: &Expression) -> Vec<String> {
+    let mut resolver = ScopeResolver::default();
+    resolver.bind_pattern(pattern);
+    resolver.bound_names
+}
+
+// 列出 `match` 表达式的分支模式所绑定的名称
+pub fn match_pattern_names(pattern: &PatternExpression) -> Vec<String> {
+    let mut resolver = ScopeResolver::default();
+    resolver.bind_match_pattern(pattern);
+    resolver.bound_names
+}
+
+// 名称的绑定以及使用
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindingEvent {
+    Bind { id: usize, name: String }, // 每次绑定都有不同的编号，即使名称相同
+    Use(usize),                       // 使用了编号为 id 的绑定
+    EnterLoop,                        // 进入 `for`、`each` 表达式的主体
+    LeaveLoop,
+    EnterFunction, // 进入匿名函数的主体
+    LeaveFunction,
+}
+
+// 列出函数主体里名称的绑定以及使用，按照求值的顺序排列。
+//
+// 名称的使用已经按照作用域规则解析到具体的绑定，未被绑定的名称（比如其他函数的名称）
+// 不会被列出。`parameters` 是函数的参数，它们最先被绑定。
+//
+// 用于活跃变量分析（liveness analysis）以及局部变量槽的分配（见 `frame::allocate_slots`）。
+pub fn binding_events(parameters: &[&str], expression: &Expression) -> Vec<BindingEvent> {
+    let mut resolver = ScopeResolver::default();
+    for name in parameters {
+        resolver.bind_name(name);
+    }
+    resolver.visit(expression);
+    resolver.events
+}
+
+// 按照作用域规则遍历表达式，记录未被绑定的名称，
+// 以及在 `where` 从属表达式的有效范围之外使用其定义的名称的错误。
+#[derive(Default)]
+struct ScopeResolver {
+    bound_names: Vec<String>, // 当前作用域（包括外层作用域）里已绑定的名称
+    bound_ids: Vec<usize>,    // 跟 `bound_names` 对应的绑定的编号
+
+    // 在当前表达式块里已经超出了有效范围的、由 `where` 从属表达式定义的名称
+    expired_where_names: Vec<String>,
+
+    // 模式里绑定的、尚未被使用的剩余项目，以及其名称在 `bound_names` 里的位置
+    unused_rests: Vec<(usize, Ellipsis)>,
+
+    free_variables: Vec<FreeVariable>,
+    errors: Vec<Error>,
+    events: Vec<BindingEvent>,
+    binding_count: usize,
+}
+
+impl ScopeResolver {
+    fn use_name(&mut self, name: &str, range: &Range) {
+        if let Some(index) = self.bound_names.iter().rposition(|n| n == name) {
+            self.unused_rests.retain(|(i, _)| *i != index);
+            self.events.push(BindingEvent::Use(self.bound_ids[index]));
+            return;
+        }
+
+        if self.expired_where_names.iter().any(|n| n == name) {
+            self.errors.push(new_semantic_error(
+                format!(
+                    "\"{}\" is defined in a `where` clause and is not visible here",
+                    name
+                ),
+                range,
+            ));
+        }
+
+        self.free_variables.push(FreeVariable {
+            name: name.to_string(),
+            range: range.clone(),
+        });
+    }
+
+    fn bind_name(&mut self, name: &str) {
+        let id = self.binding_count;
+        self.binding_count += 1;
+        self.bound_names.push(name.to_string());
+        self.bound_ids.push(id);
+        self.events.push(BindingEvent::Bind {
+            id,
+            name: name.to_string(),
+        });
+    }
+
+    // 进入新作用域，返回的值用于离开作用域时恢复
+    fn enter_scope(&self) -> usize {
+        self.bound_names.len()
+    }
+
+    fn leave_scope(&mut self, mark: usize) {
+        self.report_unused_rests(mark);
+        self.bound_names.truncate(mark);
+        self.bound_ids.truncate(mark);
+    }
+
+    // 离开 `where` 从属表达式的有效范围，其定义的名称在所在的表达式块里不能再被使用
+    fn leave_where_scope(&mut self, mark: usize) {
+        self.report_unused_rests(mark);
+        let names = self.bound_names.split_off(mark);
+        self.bound_ids.truncate(mark);
+        self.expired_where_names.extend(names);
+    }
+
+    // 注：顶层（语句级别）的 `let` 表达式绑定的名称可能在后续的语句里使用，
+    // 因为目前每个语句单独检查，所以只在离开嵌套的作用域时报告。
+    fn report_unused_rests(&mut self, mark: usize) {
+        let (unused, rests): (Vec<_>, Vec<_>) = std::mem::take(&mut self.unused_rests)
+            .into_iter()
+            .partition(|(index, _)| *index >= mark);
+        self.unused_rests = rests;
+
+        for (_, ellipsis) in unused {
+            self.errors.push(new_semantic_error(
+                format!(
+                    "rest binding \"{}\" is never used, use \"...\" instead",
+                    ellipsis.name.as_deref().unwrap_or_default()
+                ),
+                &ellipsis.range,
+            ));
+        }
+    }
+
+    fn visit(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Identifier(i) => {
+                if i.dirs.is_empty() {
+                    self.use_name(&i.name, &i.range);
+                }
+            }
+            Expression::PrefixIdentifier(p) => {
+                if p.identifier.dirs.is_empty() {
+                    self.use_name(&p.identifier.name, &p.identifier.range);
+                }
+            }
+            Expression::Ellipsis(e) => {
+                if let Some(name) = &e.name {
+                    self.use_name(name, &e.range);
+                }
+            }
+            Expression::BlockExpression(BlockExpression { body, .. })
+            | Expression::JoinExpression(JoinExpression { body, .. }) => {
+                // `let` 表达式定义的名称在表达式块里的后续表达式有效
+                let mark = self.enter_scope();
+                let where_mark = self.expired_where_names.len();
+                for e in body {
+                    self.visit(e);
+                }
+                self.leave_scope(mark);
+                self.expired_where_names.truncate(where_mark);
+            }
+            Expression::LetExpression(v) => {
+                // 名称在右手边值之后才被绑定，绑定一直有效到所在的作用域结束
+                self.visit(&v.value);
+                self.bind_pattern(&v.object);
+            }
+            Expression::IfExpression(v) => {
+                // `where` 定义的名称在 `else` 从属表达式里无效
+                let mark = self.enter_scope();
+                if let Some(where_exp) = &v.where_exp {
+                    self.visit_where(where_exp);
+                }
+                self.visit(&v.testing);
+                self.visit(&v.consequent);
+                self.leave_where_scope(mark);
+
+                if let Some(alternate) = &v.alternate {
+                    self.visit(alternate);
+                }
+            }
+            Expression::ForExpression(v) => {
+                self.visit(&v.initializer.value);
+                let mark = self.enter_scope();
+                self.bind_pattern(&v.initializer.object);
+                self.events.push(BindingEvent::EnterLoop);
+                self.visit(&v.body);
+                self.events.push(BindingEvent::LeaveLoop);
+                self.leave_scope(mark);
+            }
+            Expression::EachExpression(v) => {
+                self.visit(&v.object);
+                let mark = self.enter_scope();
+                self.bind_pattern(&v.variable);
+                self.events.push(BindingEvent::EnterLoop);
+                self.visit(&v.body);
+                self.events.push(BindingEvent::LeaveLoop);
+                self.leave_scope(mark);
+            }
+            Expression::BranchExpression(v) => {
+                // `branch` 的 `where` 定义的名称在所有分支里有效，
+                // 而 `case` 的 `where` 定义的名称只在该分支里有效。
+                let mark = self.enter_scope();
+                if let Some(where_exp) = &v.where_exp {
+                    self.visit_where(where_exp);
+                }
+                for c in &v.cases {
+                    let case_mark = self.enter_scope();
+                    if let Some(where_exp) = &c.where_exp {
+                        self.visit_where(where_exp);
+                    }
+                    self.visit(&c.testing);
+                    self.visit(&c.consequent);
+                    self.leave_where_scope(case_mark);
+                }
+                if let Some(default_exp) = &v.default_exp {
+                    self.visit(default_exp);
+                }
+                self.leave_where_scope(mark);
+            }
+            Expression::MatchExpression(v) => {
+                // 跟 `branch` 表达式相同，另外 `case` 的 `where` 定义的名称
+                // 在该分支的 `only` 从属表达式里也有效。
+                self.visit(&v.object);
+                let mark = self.enter_scope();
+                if let Some(where_exp) = &v.where_exp {
+                    self.visit_where(where_exp);
+                }
+                for c in &v.cases {
+                    let case_mark = self.enter_scope();
+                    if let Some(variable) = &c.variable {
+                        self.bind_name(variable);
+                    }
+                    if let Some(pattern) = &c.pattern {
+                        self.bind_match_pattern(pattern);
+                    }
+                    let where_mark = self.enter_scope();
+                    if let Some(where_exp) = &c.where_exp {
+                        self.visit_where(where_exp);
+                    }
+                    if let Some(only) = &c.only {
+                        self.visit(only);
+                    }
+                    self.visit(&c.consequent);
+                    self.leave_where_scope(where_mark);
+                    self.leave_scope(case_mark);
+                }
+                if let Some(default_exp) = &v.default_exp {
+                    self.visit(default_exp);
+                }
+                self.leave_where_scope(mark);
+            }
+            Expression::MemberExpression(MemberExpression::Property(v)) => {
+                // 属性名称不是变量
+                self.visit(&v.object);
+                if !matches!(v.property.as_ref(), Expression::Identifier(_)) {
+                    self.visit(&v.property);
+                }
+            }
+            Expression::ConstructorExpression(v) => {
+                for entry in &v.value.elements {
+                    // 省略值时，键同时也是同名的变量
+                    match &entry.value {
+                        Some(value) => self.visit(value),
+                        None => self.visit(&entry.key),
+                    }
+                }
+            }
+            Expression::Map(v) => {
+                for entry in &v.elements {
+                    match &entry.value {
+                        Some(value) => {
+                            if !matches!(entry.key.as_ref(), Expression::Identifier(_)) {
+                                self.visit(&entry.key);
+                            }
+                            self.visit(value);
+                        }
+                        None => self.visit(&entry.key),
+                    }
+                }
+            }
+            Expression::AnonymousFunction(v) => {
+                let mark = self.enter_scope();
+                let where_mark = self.expired_where_names.len();
+                self.events.push(BindingEvent::EnterFunction);
+                for parameter in &v.parameters {
+                    self.bind_name(&parameter.name);
+                }
+                self.visit(&v.body);
+                self.events.push(BindingEvent::LeaveFunction);
+                self.leave_scope(mark);
+                self.expired_where_names.truncate(where_mark);
+            }
+            _ => {
+                for child in expression.children() {
+                    self.visit(child);
+                }
+            }
+        }
+    }
+
+    // `where` 从属表达式定义的名称在所属的表达式里有效，
+    // 当从属表达式是表达式块时，块里定义的名称也同样有效。
+    fn visit_where(&mut self, where_exp: &Expression) {
+        match where_exp {
+            Expression::BlockExpression(block) => {
+                for e in &block.body {
+                    self.visit(e);
+                }
+            }
+            _ => self.visit(where_exp),
+        }
+    }
+
+    // 绑定 `let` 表达式左手边值（以及 `each`、`for` 表达式的变量）里的名称
+    fn bind_pattern(&mut self, pattern: &Expression) {
+        match pattern {
+            Expression::Identifier(i) => self.bind_name(&i.name),
+            Expression::Ellipsis(e) => {
+                if let Some(name) = &e.name {
+                    self.unused_rests.push((self.bound_names.len(), e.clone()));
+                    self.bind_name(name);
+                }
+            }
+            Expression::Tuple(t) => t.elements.iter().for_each(|e| self.bind_pattern(e)),
+            Expression::List(l) => l.elements.iter().for_each(|e| self.bind_pattern(e)),
+            Expression::Map(m) => m.elements.iter().for_each(|entry| match &entry.value {
+                Some(value) => self.bind_pattern(value),
+                None => self.bind_pattern(&entry.key),
+            }),
+            Expression::ConstructorExpression(c) => {
+                c.value
+                    .elements
+                    .iter()
+                    .for_each(|entry| match &entry.value {
+                        Some(value) => self.bind_pattern(value),
+                        None => self.bind_pattern(&entry.key),
+                    })
+            }
+            Expression::FunctionCallExpression(f) => {
+                // 元组结构体的模式，比如 `Some(x)`，函数名称是类型而不是变量
+                f.arguments.iter().for_each(|a| self.bind_pattern(&a.value))
+            }
+            Expression::TupleConstructorExpression(c) => {
+                c.value.elements.iter().for_each(|e| self.bind_pattern(e))
+            }
+            _ => {}
+        }
+    }
+
+    fn bind_match_pattern(&mut self, pattern: &PatternExpression) {
+        match pattern {
+            PatternExpression::Primary(e) => self.bind_pattern(e),
+            PatternExpression::In(e) => self.visit(e),
+            PatternExpression::Into(_, name) => self.bind_name(name),
+            PatternExpression::Regular(_, names) => {
+                names.elements.iter().for_each(|e| self.bind_pattern(e))
+            }
+            PatternExpression::Template(template) => {
+                // 模板里的占位符定义了同名的变量，重复的占位符仍然定义变量，
+                // 以免使用它们的地方被当作未定义的名称（格式错误以及重复由 `check` 报告）
+                for placeholder in scan_template(template).unwrap_or_default() {
+                    self.bind_name(&placeholder.name);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            BinaryExpression, BinaryOperator, Bit, DataType, Expression, Identifier, Literal,
+            MemberStructDeclaration, Node, Range, Statement, StructMember,
+        },
+        lexer, parser,
+    };
+
+    use super::{
+        check, check_build_environment, check_entry_point, check_static_assertions,
+        count_regular_captures, extern_function, free_variables, intern_hash_strings,
+        optimization_hints, parse_template, BuildEnvironment, ExternFunction, InlineHint,
+        OptimizationHints, TemplatePlaceholder, DEFAULT_ENTRY_NAME, DEFAULT_TEMPLATE_REGULAR,
+    };
+
+    fn check_source(text: &str) -> Vec<String> {
+        let token_details = lexer::tokenize(text).unwrap();
+        let node = parser::parse(&token_details).unwrap();
+        check(&node).iter().map(|e| e.to_string()).collect()
+    }
+
+    // 第一个表达式语句里的自由变量
+    fn free_variable_names(text: &str) -> Vec<String> {
+        let token_details = lexer::tokenize(text).unwrap();
+        let node = parser::parse(&token_details).unwrap();
+        let range = match &node {
+            Node::Program(program) => match &program.body[0] {
+                Statement::Expression(e) => e.range().clone(),
+                _ => panic!("expected an expression"),
+            },
+            _ => panic!("expected a program"),
+        };
+
+        free_variables(&node, &range)
+            .unwrap()
+            .into_iter()
+            .map(|v| v.name)
+            .collect()
+    }
+
+    fn new_range() -> Range {
+        Range {
+            file_id: 0,
+            start: 0,
+            end: 0,
+        }
+    }
+
+    fn names(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_free_variables() {
+        assert_eq!(free_variable_names("a + b * a"), names(&["a", "b", "a"]));
+        assert_eq!(free_variable_names("123"), names(&[]));
+
+        // 表达式块
+        assert_eq!(
+            free_variable_names("{\nlet a = b + 1\nlet (c, d) = a\nc + d + e\n}"),
+            names(&["b", "e"])
+        );
+        // 名称在右手边值之后才被绑定
+        assert_eq!(free_variable_names("{\nlet a = a + 1\na\n}"), names(&["a"]));
+
+        // 匿名函数
+        assert_eq!(
+            free_variable_names("fn (x) = x + y * z"),
+            names(&["y", "z"])
+        );
+
+        // 属性、结构体实例化
+        assert_eq!(
+            free_variable_names("User {id, name: user.name}"),
+            names(&["id", "user"])
+        );
+
+        // where 从属表达式
+        assert_eq!(
+            free_variable_names("if i > 0 where let i = a + b then i else c"),
+            names(&["a", "b", "c"])
+        );
+        assert_eq!(
+            free_variable_names("if a where let i = 1 then i else i"),
+            names(&["a", "i"])
+        );
+
+        // each 和 for 表达式
+        assert_eq!(
+            free_variable_names("each i in list {\nsum + i\n}"),
+            names(&["list", "sum"])
+        );
+        assert_eq!(
+            free_variable_names("for let i = start {\nnext i + step\n}"),
+            names(&["start", "step"])
+        );
+
+        // match 表达式
+        assert_eq!(
+            free_variable_names(
+                "match v {\ncase (a, b): a + c\ncase t @ into Int n: n + t\ndefault: a\n}"
+            ),
+            names(&["v", "c", "a"])
+        );
+
+        // 名称空间路径
+        let e1 = free_variable_names("std::math::PI * r");
+        assert_eq!(e1, names(&["r"]));
+
+        // 函数主体里的子表达式：外层绑定的名称（包括参数）对子表达式而言是自由变量
+        let text = "function f(Int a) = {\nlet b = a + 1\nb * c\n}";
+        let node = parser::parse(&lexer::tokenize(text).unwrap()).unwrap();
+        let start = text.find("b * c").unwrap();
+        let span = Range {
+            file_id: 0,
+            start,
+            end: start + 5,
+        };
+        let v1 = free_variables(&node, &span).unwrap();
+        assert_eq!(
+            v1.iter().map(|v| v.name.as_str()).collect::<Vec<&str>>(),
+            vec!["b", "c"]
+        );
+        assert_eq!(v1[1].range.start, start + 4);
+
+        // 没有范围恰好相同的表达式
+        let span = Range {
+            file_id: 0,
+            start,
+            end: start + 3,
+        };
+        assert!(free_variables(&node, &span).is_none());
+    }
+
+    #[test]
+    fn test_check_interval() {
+        assert!(check_source("[1..10]").is_empty());
+        assert!(check_source("[1..=1]").is_empty());
+        assert!(check_source("['a'..='z']").is_empty());
+        assert!(check_source("[1.0 .. 2.5]").is_empty());
+        assert!(check_source("[a..b]").is_empty());
+        assert!(check_source("users[1..]").is_empty());
+
+        // 空范围（包括常量折叠之后）
+        assert_eq!(check_source("[10..1]"), vec!["interval \"10..1\" is empty"]);
+        assert_eq!(check_source("[1..1]"), vec!["interval \"1..1\" is empty"]);
+        assert_eq!(
+            check_source("users[5..=(2 + 2)]"),
+            vec!["interval \"5..=(2 + 2)\" is empty"]
+        );
+
+        // 边界的类型
+        assert_eq!(
+            check_source("[1.5 ..= 2.5]"),
+            vec!["the bounds of inclusive interval \"1.5..=2.5\" must not be floats"]
+        );
+        assert_eq!(
+            check_source("[1 .. 2.5]"),
+            vec!["the bounds of interval \"1..2.5\" have incompatible types Int and Float"]
+        );
+        assert_eq!(
+            check_source("[\"a\"..x]"),
+            vec!["the bounds of interval \"\"a\"..x\" must be Int, Float or Char, found String"]
+        );
+
+        // 嵌套在其他表达式里
+        assert_eq!(
+            check_source("function f() = {\neach i in [3..0] {i}\n}").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_check_complex_operation() {
+        assert!(check_source("(1+2i) * 3 + 1.5 - 2i / a").is_empty());
+        assert!(check_source("(1+2i) == 1").is_empty());
+
+        assert_eq!(
+            check_source("(1+2i) < 3"),
+            vec!["complex numbers cannot be ordered: \"(1+2i < 3)\""]
+        );
+        assert_eq!(
+            check_source("2i + \"foo\""),
+            vec!["cannot apply \"+\" to Complex and String"]
+        );
+    }
+
+    #[test]
+    fn test_check_bit() {
+        // 比特字面量目前还不能被词法分析器识别，所以直接构造 AST
+        let new_bit = |width: usize, bytes: Vec<u8>| {
+            Box::new(Expression::Literal(Literal::Bit(Bit {
+                width,
+                bytes,
+                range: new_range(),
+            })))
+        };
+        let new_binary = |operator, left, right| {
+            Node::Expression(Expression::BinaryExpression(BinaryExpression {
+                operator,
+                left,
+                right,
+                range: new_range(),
+            }))
+        };
+        let check_node =
+            |node: &Node| -> Vec<String> { check(node).iter().map(|e| e.to_string()).collect() };
+
+        // 宽度相同，以及连接运算
+        assert!(check_node(&new_binary(
+            BinaryOperator::Equal,
+            new_bit(8, vec![0xff]),
+            new_bit(8, vec![0x01])
+        ))
+        .is_empty());
+        assert!(check_node(&new_binary(
+            BinaryOperator::Concat,
+            new_bit(8, vec![0xff]),
+            new_bit(4, vec![0x01])
+        ))
+        .is_empty());
+
+        // 宽度不同
+        assert_eq!(
+            check_node(&new_binary(
+                BinaryOperator::Equal,
+                new_bit(8, vec![0xff]),
+                new_bit(4, vec![0x01])
+            )),
+            vec!["mismatched bit widths 8 and 4 in \"(8'xff == 4'x01)\", use \"^\" to cast one of them"]
+        );
+
+        // 连接之后的宽度
+        assert_eq!(
+            check_node(&new_binary(
+                BinaryOperator::Equal,
+                Box::new(Expression::BinaryExpression(BinaryExpression {
+                    operator: BinaryOperator::Concat,
+                    left: new_bit(4, vec![0x0f]),
+                    right: new_bit(4, vec![0x01]),
+                    range: new_range(),
+                })),
+                new_bit(16, vec![0x00, 0xf1])
+            ))
+            .len(),
+            1
+        );
+
+        // 数值超出宽度
+        assert_eq!(
+            check_node(&Node::Expression(*new_bit(4, vec![0x1f]))),
+            vec!["bit literal \"4'x1f\" needs 5 bits and exceeds its width of 4 bits"]
+        );
+        assert!(check_node(&Node::Expression(*new_bit(12, vec![0x0f, 0xff]))).is_empty());
+    }
+
+    #[test]
+    fn test_check_join() {
+        assert!(check_source("join {\n\"a\"\nb\neach c in d c\n}").is_empty());
+        assert!(check_source("join {\nlet a = 1\nlet b = 2\n}").is_empty());
+        assert!(
+            check_source("for let i = 0 {\njoin {\nfor let j = i {\nnext j\n}\n}\n}").is_empty()
+        );
+
+        // 依赖其他子表达式绑定的名称
+        assert_eq!(
+            check_source("join {\nlet a = 1\na + 1\n}"),
+            vec![
+                "\"(a + 1)\" depends on \"a\" which is bound by another sub-expression \
+            of the same `join` expression"
+            ]
+        );
+
+        // 重复绑定
+        assert_eq!(
+            check_source("join {\nlet a = 1\nlet (a, b) = (2, 3)\n}"),
+            vec!["\"a\" is bound more than once in the same `join` expression"]
+        );
+
+        // `next` 表达式
+        assert_eq!(
+            check_source("for let i = 0 {\njoin {\nnext i + 1\n}\n}"),
+            vec!["\"next (i + 1)\" cannot be used directly inside a `join` expression"]
+        );
+    }
+
+    #[test]
+    fn test_check_next() {
+        assert!(check_source("for let i = 0 if i < 10 then {\nnext i + 1\n} else i").is_empty());
+        assert!(check_source("for let i = 0 {\neach j in [1, 2] j\nnext i + 1\n}").is_empty());
+
+        assert_eq!(
+            check_source("next 1"),
+            vec!["\"next 1\" can only be used inside the body of a `for` expression"]
+        );
+        assert_eq!(
+            check_source("function f(Int x) = {\nlet y = x\nnext y\n}"),
+            vec!["\"next y\" can only be used inside the body of a `for` expression (in function \"f\")"]
+        );
+        assert_eq!(
+            check_source("for let i = 0 {\nlet g = fn (Int x) next x\nnext i + 1\n}"),
+            vec!["\"next x\" can only be used inside the body of a `for` expression (in an anonymous function)"]
+        );
+
+        // 错误带有指向所在的函数的次要标签
+        let related = |source: &str| -> Vec<(String, String)> {
+            let token_details = lexer::tokenize(source).unwrap();
+            let node = parser::parse(&token_details).unwrap();
+            let errors = check(&node);
+            assert_eq!(errors.len(), 1);
+            errors[0]
+                .related()
+                .iter()
+                .map(|(message, range)| {
+                    (message.clone(), source[range.start..range.end].to_string())
+                })
+                .collect()
+        };
+        assert_eq!(
+            related("function f(Int x) = {\nnext x\n}"),
+            vec![(
+                "in function \"f\"".to_string(),
+                "function f(Int x) = {\nnext x\n}".to_string()
+            )]
+        );
+        assert_eq!(
+            related("let a = 1\nlet g = fn (Int x) next x"),
+            vec![(
+                "in this anonymous function".to_string(),
+                "fn (Int x) next x".to_string()
+            )]
+        );
+        assert!(related("next 1").is_empty());
+    }
+
+    #[test]
+    fn test_check_rest() {
+        assert!(check_source("let [first, ...] = x").is_empty());
+        assert!(check_source("let (a, ...rest) = x").is_empty());
+        assert!(check_source("[1, ...a]").is_empty());
+        assert!(check_source("User {id, ...user001}").is_empty());
+        assert!(check_source("do {\nlet {x, ...rest} = m\nrest\n}").is_empty());
+        assert!(check_source("match v {\ncase [a, ...]: a\ncase [a, ...t]: t\n}").is_empty());
+
+        // 数量以及位置（语法分析器不会产生这样的 AST，所以修改已解析的 AST）
+        let check_list = |update: fn(&mut Vec<Expression>)| -> Vec<String> {
+            let token_details = lexer::tokenize("[a, ...b]").unwrap();
+            let mut node = parser::parse(&token_details).unwrap();
+            if let Node::Program(program) = &mut node {
+                if let Statement::Expression(Expression::List(list)) = &mut program.body[0] {
+                    update(&mut list.elements);
+                }
+            }
+            check(&node).iter().map(|e| e.to_string()).collect()
+        };
+        assert_eq!(
+            check_list(|elements| elements.push(elements[1].clone())),
+            vec!["more than one rest element in \"[a, ...b, ...b,]\""]
+        );
+        assert_eq!(
+            check_list(|elements| elements.swap(0, 1)),
+            vec!["rest element \"...b\" must be the last entry of \"[...b, a,]\""]
+        );
+
+        // 名称
+        assert_eq!(
+            check_source("[1, ...]"),
+            vec!["rest element \"...\" without a name can only be used in patterns"]
+        );
+
+        // 未被使用的名称
+        assert_eq!(
+            check_source("match v {\ncase [a, ...rest]: a\n}"),
+            vec!["rest binding \"rest\" is never used, use \"...\" instead"]
+        );
+        assert_eq!(
+            check_source("do {\nlet (a, ...rest) = x\na\n}"),
+            vec!["rest binding \"rest\" is never used, use \"...\" instead"]
+        );
+    }
+
+    #[test]
+    fn test_check_destructuring() {
+        assert!(check_source("let (a, [b, c], ...) = (1, [2, 3], 4, 5)").is_empty());
+        assert!(check_source("let {x, y: [a, b]} = {x: 1, y: [2, 3], z: 4}").is_empty());
+        assert!(check_source("let User {id, name} = User {id: 1, name: 2}").is_empty());
+        assert!(check_source("let [a, b] = [1, ...c]").is_empty());
+        assert!(check_source("let (a, b) = f(x)").is_empty());
+
+        // 元素数量
+        assert_eq!(
+            check_source("let (a, b) = (1, 2, 3)"),
+            vec!["pattern \"(a, b,)\" expects 2 element(s), found 3"]
+        );
+        assert_eq!(
+            check_source("let (a, [b, c, ...]) = (1, [2])"),
+            vec!["pattern \"[b, c, ...,]\" expects at least 2 element(s), found 1"]
+        );
+
+        // 键以及成员
+        assert_eq!(
+            check_source("let {x, z} = {x: 1, y: 2}"),
+            vec!["key \"z\" is not found in the value"]
+        );
+        assert_eq!(
+            check_source("let User {id, age} = User {id: 1, name: 2}"),
+            vec!["field \"age\" of struct \"User\" is not found in the value"]
+        );
+        assert_eq!(
+            check_source("let Point {x} = User {id: 1}"),
+            vec!["struct pattern \"Point\" does not match a value of struct \"User\""]
+        );
+
+        // 类型
+        assert_eq!(
+            check_source("let (a, b) = [1, 2]"),
+            vec!["cannot destructure a value of type List with pattern \"(a, b,)\""]
+        );
+        assert_eq!(
+            check_source("let {x: (a, b)} = {x: 1.5}"),
+            vec!["cannot destructure a value of type Float with pattern \"(a, b,)\""]
+        );
+    }
+
+    #[test]
+    fn test_check_pattern_types() {
+        assert!(check_source("match v {\ncase 1: a\ncase in [2, 3]: b\ncase x: c\n}").is_empty());
+        assert!(
+            check_source("match \"foo\" {\ncase \"bar\": a\ncase template \"{id}\": id\n}")
+                .is_empty()
+        );
+
+        // 模式之间
+        assert_eq!(
+            check_source("match v {\ncase 1: a\ncase x: b\ncase \"foo\": c\n}"),
+            vec![
+                "pattern \"\"foo\"\" has type String, but the previous pattern \"1\" has type Int"
+            ]
+        );
+
+        // 模式与被匹配的对象
+        assert_eq!(
+            check_source("match 123 {\ncase \"foo\": a\ncase 1.5: b\n}"),
+            vec![
+                "pattern \"\"foo\"\" has type String, but the matched object \"123\" has type Int",
+                "pattern \"1.5\" has type Float, but the matched object \"123\" has type Int"
+            ]
+        );
+        assert_eq!(
+            check_source("match 1 + 2 {\ncase regular \"\\d+\" (s,): s\n}"),
+            vec!["pattern \"regular \"\\d+\" (s,)\" has type String, but the matched object \"(1 + 2)\" has type Int"]
+        );
+
+        // 条件
+        assert_eq!(
+            check_source("branch {\ncase 1 + 1: a\ncase a > b: c\n}"),
+            vec!["the testing expression \"(1 + 1)\" must be Boolean, found Int"]
+        );
+        assert_eq!(
+            check_source("match v {\ncase x only \"yes\": x\n}"),
+            vec!["the `only` clause \"\"yes\"\" must be Boolean, found String"]
+        );
+    }
+
+    #[test]
+    fn test_check_duplicate_keys() {
+        assert!(check_source("{x: 1, y: 2, \"x\": 3}").is_empty());
+        assert_eq!(
+            check_source("{x: 1, y: 2, x: 3}"),
+            vec!["duplicate key \"x\" at entries 1 and 3"]
+        );
+        assert_eq!(
+            check_source("{\"a\": 1, \"a\": 2}"),
+            vec!["duplicate key \"\"a\"\" at entries 1 and 2"]
+        );
+        assert_eq!(
+            check_source("User {id: 1, id: 2}"),
+            vec!["duplicate field \"id\" of \"User\" at entries 1 and 2"]
+        );
+    }
+
+    #[test]
+    fn test_check_constructor_fields() {
+        // 结构体声明目前还不能被语法分析器识别，所以直接构造 AST
+        let check_with_struct = |source: &str| -> Vec<String> {
+            let token_details = lexer::tokenize(source).unwrap();
+            let mut node = parser::parse(&token_details).unwrap();
+            if let Node::Program(program) = &mut node {
+                let members = ["id", "name"]
+                    .iter()
+                    .map(|name| StructMember {
+                        data_type: DataType::Identifier(Identifier {
+                            dirs: vec![],
+                            name: "Int".to_string(),
+                            generics: vec![],
+                            range: new_range(),
+                        }),
+                        name: name.to_string(),
+                        range: new_range(),
+                    })
+                    .collect();
+                program.body.insert(
+                    0,
+                    Statement::MemberStructDeclaration(MemberStructDeclaration {
+                        attributes: vec![],
+                        name: "User".to_string(),
+                        members,
+                        generics: vec![],
+                        whiches: vec![],
+                        range: new_range(),
+                    }),
+                );
+            }
+            check(&node).iter().map(|e| e.to_string()).collect()
+        };
+
+        assert!(check_with_struct("User {id: 1, name: 2}").is_empty());
+        assert!(check_with_struct("User {id: 1, ...user001}").is_empty());
+        assert!(check_with_struct("Other {foo: 1}").is_empty());
+
+        assert_eq!(
+            check_with_struct("User {id: 1, name: 2, age: 3}"),
+            vec!["struct \"User\" has no field \"age\""]
+        );
+        assert_eq!(
+            check_with_struct("f(User {id: 1})"),
+            vec!["missing field \"name\" in the constructor of struct \"User\""]
+        );
+    }
+
+    #[test]
+    fn test_check_entry_point() {
+        let check_entry = |source: &str| -> Result<String, String> {
+            let token_details = lexer::tokenize(source).unwrap();
+            let node = parser::parse(&token_details).unwrap();
+            check_entry_point(&node, DEFAULT_ENTRY_NAME)
+                .map(|f| f.name.clone())
+                .map_err(|e| e.to_string())
+        };
+
+        assert_eq!(check_entry("function main() = 0"), Ok("main".to_string()));
+        assert!(check_entry("function main(Int n = 1) type Int = n").is_ok());
+        assert!(check_entry("function main() type () = 0").is_ok());
+
+        assert_eq!(
+            check_entry("function foo() = 0"),
+            Err(
+                "no entry function \"main\" found, an executable program needs \
+            a function like \"function main() = ...\""
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            check_entry("function main() = 0\nfunction main() = 1"),
+            Err("the entry function \"main\" is defined 2 times".to_string())
+        );
+        assert_eq!(
+            check_entry("function main<T>() = 0"),
+            Err("the entry function \"main\" must not be generic".to_string())
+        );
+        assert_eq!(
+            check_entry("function main(List<String> args) = 0"),
+            Err(
+                "the entry function \"main\" must not have required parameters, \
+            found \"List<String> args\" (give it a default value or remove it)"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            check_entry("function main() type String = \"\""),
+            Err(
+                "the entry function \"main\" must return Int or nothing, found \"String\""
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_optimization_hints() {
+        let hints = |source: &str| {
+            let token_details = lexer::tokenize(source).unwrap();
+            match parser::parse(&token_details).unwrap() {
+                Node::Program(program) => match &program.body[0] {
+                    Statement::FunctionDeclaration(f) => optimization_hints(f),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }
+        };
+
+        assert_eq!(hints("function f() = 1"), Ok(OptimizationHints::default()));
+        assert_eq!(
+            hints("#[inline]\n#[allow(deprecated)]\nfunction f() = 1"),
+            Ok(OptimizationHints {
+                inline: Some(InlineHint::Hint),
+                cold: false
+            })
+        );
+        assert_eq!(
+            hints("#[inline(never)]\n#[cold]\nfunction f() = 1"),
+            Ok(OptimizationHints {
+                inline: Some(InlineHint::Never),
+                cold: true
+            })
+        );
+        assert_eq!(
+            hints("#[inline(always)]\nfunction f() = 1").unwrap().inline,
+            Some(InlineHint::Always)
+        );
+
+        assert_eq!(
+            check_source("#[inline(sometimes)]\nfunction f() = 1"),
+            vec!["invalid attributes of function \"f\": invalid attribute \"#[inline(sometimes)]\", \
+            expected \"#[inline]\", \"#[inline(always)]\" or \"#[inline(never)]\""]
+        );
+        assert_eq!(
+            hints("#[inline]\n#[inline(never)]\nfunction f() = 1"),
+            Err("duplicate attribute \"inline\"".to_string())
+        );
+        assert_eq!(
+            hints("#[cold(yes)]\nfunction f() = 1"),
+            Err("attribute \"cold\" does not take arguments".to_string())
+        );
+        assert_eq!(
+            hints("#[cold]\n#[inline(always)]\nfunction f() = 1"),
+            Err("attribute \"cold\" conflicts with \"inline(always)\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extern_function() {
+        let extern_of = |source: &str| {
+            let token_details = lexer::tokenize(source).unwrap();
+            match parser::parse(&token_details).unwrap() {
+                Node::Program(program) => match &program.body[0] {
+                    Statement::EmptyFunctionDeclaration(f) => extern_function(f),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }
+        };
+
+        assert_eq!(
+            extern_of("#[extern(\"wasi\", \"fd_write\")]\nempty function write(Int fd) type Int"),
+            Ok(Some(ExternFunction {
+                module: "wasi".to_string(),
+                name: "fd_write".to_string()
+            }))
+        );
+
+        // 返回值为空元组
+        assert!(
+            extern_of("#[extern(\"wasi\", \"fd_write\")]\nempty function write(Int fd) type ()")
+                .unwrap()
+                .is_some()
+        );
+
+        // FFI 不允许的类型
+        assert_eq!(
+            check_source(
+                "#[extern(\"wasi\", \"fd_write\")]\nempty function write(Int fd, String data) type Int"
+            ),
+            vec![
+                "invalid extern function \"write\": type \"String\" of parameter \"data\" \
+            is not allowed in extern functions, expected Int, Float, Boolean or Char"
+            ]
+        );
+
+        // 无效的参数
+        assert_eq!(
+            extern_of("#[extern(\"wasi\")]\nempty function write(Int fd) type Int"),
+            Err(
+                "invalid attribute \"#[extern(\"wasi\")]\", expected \"#[extern(\"module\", \"name\")]\""
+                    .to_string()
+            )
+        );
+
+        // 没有 `extern` 属性的空函数
+        assert_eq!(extern_of("empty function write(Int fd) type Int"), Ok(None));
+
+        // 普通函数
+        assert_eq!(
+            check_source("#[extern(\"wasi\", \"fd_write\")]\nfunction f() = 1"),
+            vec![
+                "invalid attributes of function \"f\": \
+            attribute \"extern\" can only be applied to empty functions"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_forward_declarations() {
+        assert!(check_source(
+            "empty function add(Int a, Int b) type Int
+            function add(Int x, Int y) type Int = x + y
+            empty function log(String s)
+            function log(String s) type () = s"
+        )
+        .is_empty());
+
+        // 没有定义的前向声明
+        assert!(check_source("empty function add(Int a, Int b) type Int").is_empty());
+
+        assert_eq!(
+            check_source(
+                "empty function add(Int a, Int b) type Int
+                function add(Int a) type Int = a"
+            ),
+            vec!["function \"add\" does not match its forward declaration: \
+            expected 2 parameters, found 1"]
+        );
+        assert_eq!(
+            check_source(
+                "empty function add(Int a, Int b) type Int
+                function add(Int a, Float b) type Int = a"
+            ),
+            vec!["function \"add\" does not match its forward declaration: \
+            expected type \"Int\" for parameter \"b\", found \"Float\""]
+        );
+        assert_eq!(
+            check_source(
+                "empty function add(Int a, Int b) type Int
+                function add(Int a, Int b) = a"
+            ),
+            vec!["function \"add\" does not match its forward declaration: \
+            expected return type \"Int\", found \"()\""]
+        );
+        assert_eq!(
+            check_source(
+                "empty function first<T>(List<T> l) type T
+                function first(List<Int> l) type Int = 1"
+            ),
+            vec!["function \"first\" does not match its forward declaration: \
+            expected 1 generic types, found 0"]
+        );
+
+        // 重载的函数按照参数的数据类型对应前向声明
+        assert!(check_source(
+            "empty function show(Int i) type String
+            empty function show(Float f) type String
+            function show(Int i) type String = \"int\"
+            function show(Float f) type String = \"float\"
+            function show(Boolean b) type String = \"boolean\""
+        )
+        .is_empty());
+        assert_eq!(
+            check_source(
+                "empty function show(Int i) type String
+                function show(Int i) type Int = i
+                function show(Float f) type String = \"float\""
+            ),
+            vec!["function \"show\" does not match its forward declaration: \
+            expected return type \"String\", found \"Int\""]
+        );
+    }
+
+    #[test]
+    fn test_trait_requirements() {
+        let shape = "trait Shape {
+                empty function area(Self s) type Float
+                function name(Self s) type String = \"shape\"
+            }
+            ";
+
+        // 有默认实现的函数可以省略
+        assert!(check_source(&format!(
+            "{}impl Circle trait Shape {{
+                function area(Self s) type Float = 3.14
+            }}",
+            shape
+        ))
+        .is_empty());
+
+        assert_eq!(
+            check_source(&format!(
+                "{}impl Circle trait Shape {{
+                    function name(Self s) type String = \"circle\"
+                    function radius(Self s) type Float = 1.0
+                }}",
+                shape
+            )),
+            vec![
+                "missing function \"area\" in the implementation of trait \"Shape\" for \"Circle\"",
+                "function \"radius\" is not a member of trait \"Shape\""
+            ]
+        );
+        assert_eq!(
+            check_source(&format!(
+                "{}impl Circle trait Shape {{
+                    function area(Self s, Int scale) type Float = 3.14
+                }}",
+                shape
+            )),
+            vec!["function \"area\" has 2 parameters, but trait \"Shape\" declares 1"]
+        );
+
+        // 其他模块的特性
+        assert!(check_source("impl Circle trait std::Shape {}").is_empty());
+    }
+
+    #[test]
+    fn test_associated_types() {
+        let sequence = "trait Sequence type (Item, Size = Int) {
+                empty function first(Self s) type Self::Item
+                empty function size(Self s) type Size
+            }
+            ";
+
+        // 签名里的 `Self` 以及关联类型按照绑定的类型（或者默认类型）规范化之后比较
+        assert!(check_source(&format!(
+            "{}impl Range trait Sequence type Item = Int {{
+                function first(Range s) type Int = 0
+                function size(Self s) type Self::Size = 1
+            }}",
+            sequence
+        ))
+        .is_empty());
+
+        assert_eq!(
+            check_source(&format!(
+                "{}impl Range trait Sequence type (Size = Int, Count = Int, Size = Float) {{
+                    function first(Self s) type Int = 0
+                    function size(Self s) type Int = 1
+                }}",
+                sequence
+            )),
+            vec![
+                "associated type \"Count\" is not a member of trait \"Sequence\"",
+                "duplicate binding of associated type \"Size\"",
+                "missing associated type \"Item\" in the implementation of trait \"Sequence\" for \"Range\"",
+                "function \"first\" returns \"Int\", but trait \"Sequence\" declares \"Self::Item\""
+            ]
+        );
+        assert_eq!(
+            check_source(&format!(
+                "{}impl Range trait Sequence type Item = String {{
+                    function first(Int s) type String = \"a\"
+                    function size(Self s) type Int = 1
+                }}",
+                sequence
+            )),
+            vec![
+                "parameter \"s\" of function \"first\" has type \"Int\", but trait \"Sequence\" declares \"Range\""
+            ]
+        );
+
+        // 语法分析器保证 `impl` 语句里的关联类型都有类型，这里检查通过其他途径构造的 AST
+        let token_details = lexer::tokenize(&format!(
+            "{}impl Range trait Sequence type Item = Int {{}}",
+            sequence
+        ))
+        .unwrap();
+        let mut node = parser::parse(&token_details).unwrap();
+        if let Node::Program(program) = &mut node {
+            if let Statement::ImplStatement(i) = &mut program.body[1] {
+                i.associated_types[0].object_type = None;
+            }
+        }
+        assert_eq!(
+            check(&node).iter().map(|e| e.to_string()).collect::<Vec<String>>(),
+            vec![
+                "missing function \"first\" in the implementation of trait \"Sequence\" for \"Range\"",
+                "missing function \"size\" in the implementation of trait \"Sequence\" for \"Range\"",
+                "associated type \"Item\" of trait \"Sequence\" must be bound to a type"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_supertraits() {
+        let traits = "trait Eq {}
+            trait Ord limit Eq {}
+            trait Sorted which Self: limit Ord {}
+            ";
+
+        assert!(check_source(&format!(
+            "{}impl Int trait Eq {{}}
+            impl Int trait Ord {{}}
+            impl Int trait Sorted {{}}",
+            traits
+        ))
+        .is_empty());
+
+        // 间接的父特性也必须实现
+        assert_eq!(
+            check_source(&format!(
+                "{}impl Int trait Ord {{}}
+                impl Int trait Sorted {{}}
+                impl String trait Eq {{}}",
+                traits
+            )),
+            vec![
+                "\"Int\" implements trait \"Ord\" but not its supertrait \"Eq\"",
+                "\"Int\" implements trait \"Sorted\" but not its supertrait \"Eq\""
+            ]
+        );
+
+        // 循环继承
+        assert_eq!(
+            check_source(
+                "trait A limit B {}\ntrait B limit C + A {}\ntrait C {}\ntrait D limit D {}"
+            ),
+            vec![
+                "trait \"A\" inherits from itself",
+                "trait \"B\" inherits from itself",
+                "trait \"D\" inherits from itself"
+            ]
+        );
+
+        // 其他模块的父特性
+        assert!(check_source("trait Ord limit std::Eq {}\nimpl Int trait Ord {}").is_empty());
+    }
+
+    #[test]
+    fn test_pattern_functions() {
+        // 最后一个子句匹配剩余的情况，参数个数不同的子句属于不同的模式函数
+        assert!(check_source(
+            "pattern function level(Int i @ in [0..10]) = 0
+            pattern function level(Int i @ in [10..]) = 1
+            pattern function level(Int i) = 2
+            pattern function level(Int a, Int b) = 3"
+        )
+        .is_empty());
+
+        // 布尔值，以及不可反驳的解构模式
+        assert!(check_source(
+            "pattern function f(Boolean true, Int 0) = 0
+            pattern function f(Boolean false, Int i) = 1
+            pattern function f(Boolean b, Int i) = 2
+            pattern function g(Point (x, y)) = x"
+        )
+        .is_empty());
+        assert!(check_source(
+            "pattern function f(Boolean true) = 0
+            pattern function f(Boolean false) = 1"
+        )
+        .is_empty());
+
+        // 不可达的子句
+        assert_eq!(
+            check_source(
+                "pattern function f(Int 1) = 0
+                pattern function f(Int i @ in [0..=5]) = 1
+                pattern function f(Int 3) = 2
+                pattern function f(Int i) = 3
+                pattern function f(Int 9) = 4"
+            ),
+            vec![
+                "clause 3 of pattern function \"f\" is unreachable, \
+                its arguments are all matched by clause 2",
+                "clause 5 of pattern function \"f\" is unreachable, \
+                its arguments are all matched by clause 4"
+            ]
+        );
+        assert_eq!(
+            check_source(
+                "pattern function f(s @ regular \"^a$\" (t,)) = 0
+                pattern function f(s @ regular \"^a$\" (t,)) = 1
+                pattern function f(String s) = 2"
+            ),
+            vec![
+                "clause 2 of pattern function \"f\" is unreachable, \
+                its arguments are all matched by clause 1"
+            ]
+        );
+
+        // 带有 `only` 从属表达式的子句不覆盖之后的子句，也不参与穷尽的判断
+        assert_eq!(
+            check_source(
+                "pattern function f(Int i) only i > 0 = 0
+                pattern function f(Int i @ in [0..10]) = 1"
+            ),
+            vec![
+                "the clauses of pattern function \"f\" with 1 parameter(s) \
+                do not cover all possible arguments"
+            ]
+        );
+
+        // 部分重叠的范围
+        assert_eq!(
+            check_source(
+                "pattern function f(Int i @ in [1..10], Int 0) = 0
+                pattern function f(Int i @ in [5..15], Int 0) = 1
+                pattern function f(Int i @ in [15..20], Int 0) = 2
+                pattern function f(Int a, Int b) = 3"
+            ),
+            vec!["clause 2 of pattern function \"f\" partially overlaps clause 1"]
+        );
+
+        // 缺少匹配剩余情况的子句
+        assert_eq!(
+            check_source(
+                "pattern function f(Boolean true, Int 0) = 0
+                pattern function f(Boolean false, Int i) = 1"
+            ),
+            vec![
+                "the clauses of pattern function \"f\" with 2 parameter(s) \
+                do not cover all possible arguments"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_build_environment() {
+        let token_details =
+            lexer::tokenize("function version() = env(\"NAME\") ++ \" \" ++ env(\"VERSION\")")
+                .unwrap();
+        let node = parser::parse(&token_details).unwrap();
+        assert!(check(&node).is_empty());
+
+        let mut environment = BuildEnvironment::default();
+        environment.set("NAME", "demo");
+        assert_eq!(
+            check_build_environment(&node, &environment)
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>(),
+            vec!["build environment value \"VERSION\" is not defined"]
+        );
+
+        environment.set("VERSION", "1.0.0");
+        assert!(check_build_environment(&node, &environment).is_empty());
+
+        assert_eq!(
+            check_source("env(NAME)"),
+            vec![
+                "\"(env)(NAME)\" expects a single string literal as the name \
+            of the build environment value"
+            ]
+        );
+        assert_eq!(check_source("env(\"A\", \"B\")").len(), 1);
+    }
+
+    #[test]
+    fn test_check_static_assertions() {
+        let static_assertion_errors = |text: &str, environment: &BuildEnvironment| {
+            let token_details = lexer::tokenize(text).unwrap();
+            let node = parser::parse(&token_details).unwrap();
+            check_static_assertions(&node, environment)
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+        };
+
+        let environment = BuildEnvironment::default();
+        assert!(static_assertion_errors(
+            "static_assert(2 * 4 == 8, \"size\")\nstatic_assert(\"a\" < \"b\")",
+            &environment
+        )
+        .is_empty());
+
+        assert_eq!(
+            static_assertion_errors(
+                "static_assert(1 + 1 == 3, \"math is broken\")\n\
+                function f(Int a) = do {\nstatic_assert(1 > 2)\na\n}",
+                &environment
+            ),
+            vec![
+                "static assertion failed: math is broken",
+                "static assertion failed: (1 > 2)"
+            ]
+        );
+
+        assert_eq!(
+            static_assertion_errors("static_assert(1 + 2)\nstatic_assert(a == 1)", &environment),
+            vec![
+                "the condition \"(1 + 2)\" of the static assertion must be a Boolean, found Int",
+                "the condition \"(a == 1)\" of the static assertion is not a constant expression"
+            ]
+        );
+
+        // 读取构建环境值
+        let source = "static_assert(env(\"MODE\") == \"release\", \"release build only\")";
+        let mut environment = BuildEnvironment::default();
+        environment.set("MODE", "debug");
+        assert_eq!(
+            static_assertion_errors(source, &environment),
+            vec!["static assertion failed: release build only"]
+        );
+        environment.set("MODE", "release");
+        assert!(static_assertion_errors(source, &environment).is_empty());
+
+        // 无效的参数
+        assert_eq!(
+            check_source("static_assert(true, message)"),
+            vec![
+                "\"(static_assert)(true, message)\" expects a condition \
+            and an optional string literal message"
+            ]
+        );
+        assert_eq!(check_source("static_assert()").len(), 1);
+    }
+
+    #[test]
+    fn test_intern_hash_strings() {
+        let token_details =
+            lexer::tokenize("match c {\ncase #red: #ff0000\ncase #blue: #red\ndefault: #blue\n}")
+                .unwrap();
+        let node = parser::parse(&token_details).unwrap();
+        let table = intern_hash_strings(&node);
+
+        assert_eq!(table.values(), &["red", "ff0000", "blue"]);
+        assert_eq!(table.id("blue"), Some(2));
+        assert_eq!(table.id("green"), None);
+        assert_eq!(table.value(1), Some("ff0000"));
+    }
+
+    #[test]
+    fn test_check_hash_string_case() {
+        assert!(check_source("match c {\ncase #red: 1\ncase #blue: 2\n}").is_empty());
+        assert_eq!(
+            check_source("match c {\ncase #red: 1\ncase #blue: 2\ncase #red: 3\n}"),
+            vec!["duplicate match case \"#red\""]
+        );
+    }
+
+    #[test]
+    fn test_parse_template() {
+        let p1 = parse_template("/user/{userName}/post/{postId:\\d{3}}").unwrap();
+        assert_eq!(
+            p1,
+            vec![
+                TemplatePlaceholder {
+                    name: "userName".to_string(),
+                    regular: DEFAULT_TEMPLATE_REGULAR.to_string()
+                },
+                TemplatePlaceholder {
+                    name: "postId".to_string(),
+                    regular: "\\d{3}".to_string()
+                }
+            ]
+        );
+        assert_eq!(parse_template("abc"), Ok(vec![]));
+
+        assert_eq!(
+            parse_template("id={id"),
+            Err("unclosed placeholder".to_string())
+        );
+        assert_eq!(parse_template("id=id}"), Err("unmatched \"}\"".to_string()));
+        assert_eq!(
+            parse_template("{1d}"),
+            Err("invalid placeholder name \"1d\"".to_string())
+        );
+        assert_eq!(
+            parse_template("{}"),
+            Err("invalid placeholder name \"\"".to_string())
+        );
+        assert_eq!(
+            parse_template("{id:}"),
+            Err("placeholder \"id\" has an empty regular expression".to_string())
+        );
+        assert_eq!(
+            parse_template("{id}-{id}"),
+            Err("duplicate placeholder \"id\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_check_template_pattern() {
+        assert!(check_source("match s {\ncase template \"id={id}\": id\ndefault: 0\n}").is_empty());
+        assert_eq!(
+            check_source("match s {\ncase template \"id={id\": 1\ndefault: 0\n}"),
+            vec!["invalid template pattern \"id={id\": unclosed placeholder"]
+        );
+
+        // 重复的占位符
+        assert_eq!(
+            check_source("match s {\ncase template \"{id}-{id:\\d+}\": id\ndefault: 0\n}"),
+            vec!["duplicate placeholder \"id\" in template pattern \"{id}-{id:\\d+}\""]
+        );
+        assert_eq!(
+            free_variable_names("match s {\ncase template \"{a}/{a}\": a\n}"),
+            names(&["s"])
+        );
+
+        // 占位符的名称在分支里有效
+        assert_eq!(
+            free_variable_names("match s {\ncase template \"{a}/{b:\\d+}\": a ++ b ++ c\n}"),
+            names(&["s", "c"])
+        );
+    }
+
+    #[test]
+    fn test_check_regular_pattern() {
+        assert_eq!(count_regular_captures("^(.+)@(.+)$"), Ok(2));
+        assert_eq!(count_regular_captures("^(?:a|b)+$"), Ok(0));
+        assert_eq!(
+            count_regular_captures("(a"),
+            Err("unclosed group".to_string())
+        );
+
+        assert!(check_source(
+            "match s {\ncase regular \"^(.+)@(.+)$\" (email, name, domain): name\ndefault: 0\n}"
+        )
+        .is_empty());
+
+        // 名称的数量
+        assert_eq!(
+            check_source("match s {\ncase regular \"^(.+)@(.+)$\" (name, domain): name\ndefault: 0\n}"),
+            vec!["regular pattern \"^(.+)@(.+)$\" has 2 capture group(s) and expects 3 name(s), found 2"]
+        );
+
+        // 无效的正则表达式
+        assert_eq!(
+            check_source("match s {\ncase regular \"[a-\" (a,): a\ndefault: 0\n}"),
+            vec!["invalid regular pattern \"[a-\": unclosed character class"]
+        );
+        assert_eq!(
+            check_source("match s {\ncase template \"{id:[a-}\": id\ndefault: 0\n}"),
+            vec!["invalid template pattern \"{id:[a-}\": placeholder \"id\" has an invalid regular expression: unclosed character class"]
+        );
+    }
+
+    #[test]
+    fn test_check_where_scope() {
+        assert!(check_source("if i > 0 where let i = a + b then i else 0").is_empty());
+        assert!(check_source(
+            "branch where let j = 1 {\ncase i > j where let i = 2: i + j\ndefault: j\n}"
+        )
+        .is_empty());
+        assert!(check_source(
+            "match v {\ncase (a, b) only a > c where let c = 1: a + c\ndefault: 0\n}"
+        )
+        .is_empty());
+
+        let message = "\"i\" is defined in a `where` clause and is not visible here";
+
+        // `else` 从属表达式
+        assert_eq!(
+            check_source("if a where let i = 1 then i else i"),
+            vec![message]
+        );
+
+        // 所属表达式之后的表达式
+        assert_eq!(
+            check_source("{\nlet b = if a where let i = 1 then i else 0\nb + i\n}"),
+            vec![message]
+        );
+
+        // 其他分支
+        assert_eq!(
+            check_source("branch {\ncase a where let i = 1: i\ncase b: i\n}"),
+            vec![message]
+        );
+
+        // 重新定义的名称，以及函数的参数
+        assert!(
+            check_source("{\nlet b = if a where let i = 1 then i else 0\nlet i = 2\ni\n}")
+                .is_empty()
+        );
+        assert!(check_source(
+            "function f(Int i) = {\nlet b = if a where let i = 1 then i else 0\ni\n}"
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 源文件管理
+//
+// 编译整个项目时需要同时处理多个源文件，`SourceManager` 保存各个源文件的路径以及文本，
+// 并为每个源文件分配 id（即加入的顺序，从 0 开始）。
+//
+// 对源文件进行词法分析时，文件 id 被记录在每个 Token 的位置里（见 `LexerOptions::file_id`），
+// 语法分析器再把它传递到 AST 节点的范围里，所以错误以及诊断信息的位置
+// 可以根据 `file_id` 找到所在的源文件，从而换算行号和列号（见 `source_map` 模块）
+// 以及渲染源代码片段。
+//
+// ```ignore
+// let mut sources = SourceManager::new();
+// let main = sources.add("src/main.xuan", "let a = 1");
+// let lib = sources.add_file("src/lib.xuan")?;
+//
+// if let Err(e) = sources.parse(lib) {
+//     eprint!("{}", sources.render(&Diagnostic::from(&e)));
+// }
+// ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    ast::{Node, Program},
+    diagnostic::Diagnostic,
+    error::Error,
+    lexer::{self, LexerOptions, TextEdit},
+    parser,
+    source_map::{Position, SourceMap},
+    token::TokenDetail,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    pub path: PathBuf, // 仅用于显示，不一定是实际存在的文件
+    pub text: String,
+}
+
+#[derive(Debug, Default)]
+pub struct SourceManager {
+    files: Vec<SourceFile>, // 文件 id 即索引
+}
+
+impl SourceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 加入源代码，返回分配的文件 id
+    pub fn add(&mut self, path: impl AsRef<Path>, text: &str) -> usize {
+        self.files.push(SourceFile {
+            path: path.as_ref().to_path_buf(),
+            text: text.to_string(),
+        });
+        self.files.len() - 1
+    }
+
+    // 读取并加入源文件，返回分配的文件 id
+    pub fn add_file(&mut self, path: impl AsRef<Path>) -> Result<usize, Error> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| {
+            Error::ParserError(format!(
+                "failed to read file \"{}\": {}",
+                path.to_string_lossy(),
+                e
+            ))
+        })?;
+        Ok(self.add(path, &text))
+    }
+
+    // 替换源文件的文本（比如文件被修改之后重新读取），返回从原来的文本到新的文本的编辑
+    pub fn set_text(&mut self, file_id: usize, text: &str) -> Result<TextEdit, Error> {
+        let file = self
+            .files
+            .get_mut(file_id)
+            .ok_or_else(|| unknown_file(file_id))?;
+        let edit = TextEdit::between(&file.text, text);
+        file.text = text.to_string();
+        Ok(edit)
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn get(&self, file_id: usize) -> Option<&SourceFile> {
+        self.files.get(file_id)
+    }
+
+    pub fn path(&self, file_id: usize) -> Option<&Path> {
+        self.get(file_id).map(|file| file.path.as_path())
+    }
+
+    pub fn text(&self, file_id: usize) -> Option<&str> {
+        self.get(file_id).map(|file| file.text.as_str())
+    }
+
+    // 根据路径查找文件 id
+    pub fn find(&self, path: impl AsRef<Path>) -> Option<usize> {
+        self.files
+            .iter()
+            .position(|file| file.path == path.as_ref())
+    }
+
+    pub fn source_map(&self, file_id: usize) -> Option<SourceMap<'_>> {
+        self.text(file_id).map(SourceMap::new)
+    }
+
+    // 把源文件里的字节偏移量换算为行号和列号
+    pub fn position(&self, file_id: usize, offset: usize) -> Option<Position> {
+        self.source_map(file_id).map(|map| map.position(offset))
+    }
+
+    // 对源文件进行词法分析，Token 的位置带有文件 id
+    pub fn tokenize(&self, file_id: usize) -> Result<Vec<TokenDetail>, Error> {
+        let text = self.text(file_id).ok_or_else(|| unknown_file(file_id))?;
+        let options = LexerOptions {
+            file_id,
+            ..LexerOptions::default()
+        };
+        let (token_details, _) = lexer::tokenize_with_options(text, &options)?;
+        Ok(token_details)
+    }
+
+    // 源文件被修改之后的增量词法分析（见 `lexer::relex`），
+    // previous 为修改之前的 Token 列表，edit 为 `set_text` 返回的编辑
+    pub fn relex(
+        &self,
+        file_id: usize,
+        previous: &[TokenDetail],
+        edit: &TextEdit,
+    ) -> Result<Vec<TokenDetail>, Error> {
+        let text = self.text(file_id).ok_or_else(|| unknown_file(file_id))?;
+        let options = LexerOptions {
+            file_id,
+            ..LexerOptions::default()
+        };
+        let (token_details, _) = lexer::relex_with_options(text, previous, edit, &options)?;
+        Ok(token_details)
+    }
+
+    // 对源文件进行词法分析以及语法分析，AST 节点的范围带有文件 id
+    pub fn parse(&self, file_id: usize) -> Result<Program, Error> {
+        let token_details = self.tokenize(file_id)?;
+        match parser::parse(&token_details)? {
+            Node::Program(program) => Ok(program),
+            _ => unreachable!(),
+        }
+    }
+
+    // 渲染诊断信息，源文件由标签的文件 id 决定，
+    // 没有标签（或者文件 id 无效）时只渲染错误信息
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let file = diagnostic
+            .primary
+            .as_ref()
+            .or(diagnostic.secondary.first())
+            .and_then(|label| self.get(label.range.file_id));
+
+        match file {
+            Some(file) => diagnostic.render(&file.path.to_string_lossy(), &file.text),
+            None => diagnostic.render("", ""),
+        }
+    }
+}
+
+fn unknown_file(file_id: usize) -> Error {
+    Error::ParserError(format!("unknown source file id {}", file_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::Diagnostic;
+
+    use super::SourceManager;
+
+    #[test]
+    fn test_source_manager() {
+        let mut sources = SourceManager::new();
+        let f0 = sources.add("main.xuan", "let a = 1\nlet b = 2");
+        let f1 = sources.add("lib.xuan", "function f(Int x) = x");
+        assert_eq!((f0, f1), (0, 1));
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources.find("lib.xuan"), Some(1));
+        assert_eq!(sources.text(1), Some("function f(Int x) = x"));
+        assert!(sources.get(2).is_none());
+
+        let position = sources.position(f0, 14).unwrap();
+        assert_eq!((position.line, position.display_column), (2, 5));
+
+        // 位置带有文件 id
+        let tokens = sources.tokenize(f1).unwrap();
+        assert!(tokens.iter().all(|t| t.location.file_id == 1));
+        let program = sources.parse(f1).unwrap();
+        assert_eq!(program.range.file_id, 1);
+
+        assert!(sources.add_file("not_exists.xuan").is_err());
+        assert!(sources.parse(5).is_err());
+
+        // 修改文本之后进行增量词法分析
+        let edit = sources.set_text(f1, "function f(Int y) = y").unwrap();
+        assert_eq!((edit.start, edit.end, edit.text.as_str()), (15, 21, "y) = y"));
+        let tokens = sources.relex(f1, &tokens, &edit).unwrap();
+        assert_eq!(tokens, sources.tokenize(f1).unwrap());
+        assert!(sources.set_text(5, "").is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let mut sources = SourceManager::new();
+        sources.add("a.xuan", "let a = 1");
+        let f1 = sources.add("b.xuan", "let a = 1\nlet b = (1, 2 3)");
+
+        let error = sources.parse(f1).unwrap_err();
+        assert_eq!(
+            sources.render(&Diagnostic::from(&error)),
+            "\
+error[E0102]: expected \",\" or \")\", found \"3\"
+ --> b.xuan:2:15
+  |
+2 | let b = (1, 2 3)
+  |               ^ expected \",\" or \")\"
+  |
+  = help: insert a comma if this is the next item
+"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 源代码位置的换算
+//
+// 将 Location/Range 里的字节偏移量换算为行号和列号，用于显示错误信息。
+//
+// 列号有两种：
+// - 字节列号，即在当前行里的字节偏移量，供需要精确定位的工具使用；
+// - 显示列号，即在编辑器里看到的列，制表符会按照指定的宽度对齐到下一个制表位，
+//   用于在错误信息里显示列号以及绘制指向错误位置的下划线（`^^^`）。
+//
+// 行号和列号均从 1 开始。
+//
+// 另外，语言服务器协议（LSP）使用从 0 开始的行号以及 UTF-16 编码单元的列号（见 `LspPosition`），
+// 比如 emoji 等基本多文种平面之外的字符占 2 个编码单元，`SourceMap` 提供了字节偏移量
+// 跟 LSP 位置以及字符序号（即第几个字符）之间的相互换算。
+
+// 默认的制表符宽度
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub byte_column: usize,
+    pub display_column: usize,
+}
+
+// LSP 的位置，行号以及列号（UTF-16 编码单元）均从 0 开始
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LspPosition {
+    pub line: usize,
+    pub character: usize,
+}
+
+pub struct SourceMap<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>, // 每一行的开始位置（字节偏移量）
+    tab_width: usize,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            source
+                .bytes()
+                .enumerate()
+                .filter(|(_, b)| *b == b'\n')
+                .map(|(index, _)| index + 1),
+        );
+
+        SourceMap {
+            source,
+            line_starts,
+            tab_width: DEFAULT_TAB_WIDTH,
+        }
+    }
+
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        // 宽度为 0 没有意义，视为 1
+        self.tab_width = tab_width.max(1);
+        self
+    }
+
+    pub fn tab_width(&self) -> usize {
+        self.tab_width
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    // 获取指定行（从 1 开始）的文本，不包括行尾的换行符
+    pub fn line_text(&self, line: usize) -> Option<&'a str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = match self.line_starts.get(line) {
+            Some(next_start) => next_start - 1,
+            None => self.source.len(),
+        };
+
+        let text = &self.source[start..end];
+        Some(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    // 将字节偏移量换算为行号和列号
+    //
+    // 超出源代码长度的偏移量视为源代码的末尾，
+    // 位于多字节字符中间的偏移量视为该字符的开始位置。
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = self.floor_char_boundary(offset);
+
+        // line_starts 的第一个元素为 0，所以 index 至少为 1
+        let index = self.line_starts.partition_point(|start| *start <= offset);
+        let line_start = self.line_starts[index - 1];
+
+        let display_width = self.display_width(&self.source[line_start..offset], 0);
+
+        Position {
+            line: index,
+            byte_column: offset - line_start + 1,
+            display_column: display_width + 1,
+        }
+    }
+
+    // 将字节偏移量换算为 LSP 的位置
+    //
+    // 超出源代码长度的偏移量视为源代码的末尾，
+    // 位于多字节字符中间的偏移量视为该字符的开始位置。
+    pub fn lsp_position(&self, offset: usize) -> LspPosition {
+        let offset = self.floor_char_boundary(offset);
+        let index = self.line_starts.partition_point(|start| *start <= offset);
+        let line_start = self.line_starts[index - 1];
+
+        LspPosition {
+            line: index - 1,
+            character: self.source[line_start..offset].encode_utf16().count(),
+        }
+    }
+
+    // 将 LSP 的位置换算为字节偏移量
+    //
+    // 行号超出范围时返回 None；列号超出行的长度时视为行尾（不包括换行符），
+    // 位于代理对（surrogate pair）中间的列号视为该字符的开始位置。
+    pub fn offset_of_lsp_position(&self, position: LspPosition) -> Option<usize> {
+        let line_start = *self.line_starts.get(position.line)?;
+        let line_text = self.line_text(position.line + 1)?;
+
+        let mut character = 0;
+        for (index, c) in line_text.char_indices() {
+            character += c.len_utf16();
+            if character > position.character {
+                return Some(line_start + index);
+            }
+        }
+
+        Some(line_start + line_text.len())
+    }
+
+    // 将字节偏移量换算为字符序号（从 0 开始），即偏移量之前的字符的数量
+    pub fn char_index(&self, offset: usize) -> usize {
+        let offset = self.floor_char_boundary(offset);
+        self.source[..offset].chars().count()
+    }
+
+    // 将字符序号换算为字节偏移量，超出字符数量的序号视为源代码的末尾
+    pub fn offset_of_char_index(&self, char_index: usize) -> usize {
+        self.source
+            .char_indices()
+            .nth(char_index)
+            .map_or(self.source.len(), |(offset, _)| offset)
+    }
+
+    // 生成指向 [start, end) 的下划线，比如 "    ^^^"，
+    // 下划线前面的空白宽度跟源代码所在行的显示宽度一致。
+    //
+    // 如果范围跨越多行，则只标示第一行的部分。
+    pub fn caret_line(&self, start: usize, end: usize) -> String {
+        let start = self.floor_char_boundary(start);
+        let position = self.position(start);
+
+        let line_start = self.line_starts[position.line - 1];
+        let line_end = line_start + self.line_text(position.line).unwrap_or_default().len();
+        let end = self.floor_char_boundary(end.clamp(start, line_end.max(start)));
+
+        let prefix_width = position.display_column - 1;
+        let caret_width = self
+            .display_width(&self.source[start..end], prefix_width)
+            .max(1);
+
+        format!("{}{}", " ".repeat(prefix_width), "^".repeat(caret_width))
+    }
+
+    // 计算文本的显示宽度，制表符会对齐到下一个制表位，
+    // start_width 为文本之前（同一行里）已有的显示宽度。
+    fn display_width(&self, text: &str, start_width: usize) -> usize {
+        let end_width = text.chars().fold(start_width, |width, c| {
+            if c == '\t' {
+                (width / self.tab_width + 1) * self.tab_width
+            } else {
+                width + 1
+            }
+        });
+
+        end_width - start_width
+    }
+
+    fn floor_char_boundary(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LspPosition, Position, SourceMap};
+
+    #[test]
+    fn test_position() {
+        let source = "let a = 1\n\tlet b = a\r\n\t \tc";
+        let m1 = SourceMap::new(source);
+
+        assert_eq!(m1.line_count(), 3);
+        assert_eq!(m1.line_text(2), Some("\tlet b = a"));
+        assert_eq!(m1.line_text(4), None);
+
+        assert_eq!(
+            m1.position(4),
+            Position {
+                line: 1,
+                byte_column: 5,
+                display_column: 5
+            }
+        );
+
+        // `b`
+        assert_eq!(
+            m1.position(15),
+            Position {
+                line: 2,
+                byte_column: 6,
+                display_column: 9
+            }
+        );
+
+        // `c`，"\t \t" 的显示宽度为 8
+        assert_eq!(
+            m1.position(25),
+            Position {
+                line: 3,
+                byte_column: 4,
+                display_column: 9
+            }
+        );
+
+        // 自定义制表符宽度
+        let m2 = SourceMap::new(source).with_tab_width(2);
+        assert_eq!(m2.position(25).display_column, 5);
+
+        // 超出范围
+        assert_eq!(m2.position(100).line, 3);
+    }
+
+    #[test]
+    fn test_caret_line() {
+        let source = "\tlet b = foo\n";
+        let m1 = SourceMap::new(source);
+        assert_eq!(m1.caret_line(9, 12), "            ^^^");
+
+        let m2 = SourceMap::new(source).with_tab_width(8);
+        assert_eq!(m2.caret_line(9, 12), "                ^^^");
+
+        // 空的范围仍然显示一个符号
+        assert_eq!(m1.caret_line(1, 1), "    ^");
+
+        // 跨越多行的范围只标示第一行
+        assert_eq!(m1.caret_line(9, 100), "            ^^^");
+    }
+
+    #[test]
+    fn test_lsp_position() {
+        // "😀" 为 4 个字节、2 个 UTF-16 编码单元，"中" 为 3 个字节、1 个 UTF-16 编码单元
+        let source = "let a = 1\nlet s = \"😀中\" ++ b\r\n";
+        let m1 = SourceMap::new(source);
+
+        let lsp_position = |line, character| LspPosition { line, character };
+
+        // `b`
+        let offset = source.find('b').unwrap();
+        assert_eq!(m1.lsp_position(offset), lsp_position(1, 17));
+        assert_eq!(m1.offset_of_lsp_position(lsp_position(1, 17)), Some(offset));
+
+        // `中`
+        let offset = source.find('中').unwrap();
+        assert_eq!(m1.lsp_position(offset), lsp_position(1, 11));
+        assert_eq!(m1.offset_of_lsp_position(lsp_position(1, 11)), Some(offset));
+
+        // 代理对的中间
+        let offset = source.find('😀').unwrap();
+        assert_eq!(m1.lsp_position(offset + 2), lsp_position(1, 9));
+        assert_eq!(m1.offset_of_lsp_position(lsp_position(1, 10)), Some(offset));
+
+        // 行尾（不包括 "\r\n"）以及超出范围的行
+        assert_eq!(
+            m1.offset_of_lsp_position(lsp_position(1, 100)),
+            Some(source.len() - 2)
+        );
+        assert_eq!(
+            m1.offset_of_lsp_position(lsp_position(2, 0)),
+            Some(source.len())
+        );
+        assert_eq!(m1.offset_of_lsp_position(lsp_position(3, 0)), None);
+    }
+
+    #[test]
+    fn test_char_index() {
+        let source = "a😀中b";
+        let m1 = SourceMap::new(source);
+
+        assert_eq!(m1.char_index(0), 0);
+        assert_eq!(m1.char_index(5), 2);
+        assert_eq!(m1.char_index(8), 3);
+        assert_eq!(m1.char_index(100), 4);
+
+        assert_eq!(m1.offset_of_char_index(2), 5);
+        assert_eq!(m1.offset_of_char_index(3), 8);
+        assert_eq!(m1.offset_of_char_index(100), source.len());
+
+        for offset in [0, 1, 5, 8, 9] {
+            assert_eq!(m1.offset_of_char_index(m1.char_index(offset)), offset);
+        }
+    }
+}
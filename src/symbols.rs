@@ -0,0 +1,353 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 符号索引
+//
+// 记录一个编译单元（多个源文件）里所有声明的符号，包括名称、种类、所属的容器
+// （比如联合体的成员所属的联合体、特性的函数所属的特性）以及位置，用于：
+//
+// - 编辑器的 "按名称打开符号"（workspace symbols），支持模糊查找，见 `SymbolIndex::search`；
+// - 跨文件的 "跳转到定义"，见 `SymbolIndex::definitions`。
+//
+// 索引以文件为单位更新：文件修改之后使用 `add_file` 重新添加，旧的符号会被替换。
+//
+// 注：
+// 顶层的 `let` 表达式定义的名称也作为符号（变量）记录，
+// 函数内部的局部名称不属于符号。
+
+use std::fmt::Display;
+
+use crate::{
+    ast::{Expression, Program, Range, Statement, TraitFunctionItem, UnionMember},
+    semantics::pattern_names,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Struct,
+    Field, // 结构体的成员
+    Union,
+    UnionMember,
+    Trait,
+    TraitFunction,
+    AssociatedType,
+    Const,
+    TypeAlias,
+    Variable,  // 顶层的 `let` 表达式定义的名称
+    Parameter, // 函数的参数，只出现在名称解析的函数作用域里（见 `resolve` 模块）
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub container: Option<String>, // 所属的结构体、联合体或者特性的名称
+    pub file_id: usize,
+    pub range: Range,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SymbolIndex {
+    symbols: Vec<Symbol>,
+}
+
+impl Display for SymbolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            SymbolKind::Function => "function",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Field => "field",
+            SymbolKind::Union => "union",
+            SymbolKind::UnionMember => "union member",
+            SymbolKind::Trait => "trait",
+            SymbolKind::TraitFunction => "trait function",
+            SymbolKind::AssociatedType => "associated type",
+            SymbolKind::Const => "const",
+            SymbolKind::TypeAlias => "type alias",
+            SymbolKind::Variable => "variable",
+            SymbolKind::Parameter => "parameter",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl SymbolIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    // 添加一个文件里的符号，替换该文件之前的符号
+    pub fn add_file(&mut self, file_id: usize, program: &Program) {
+        self.remove_file(file_id);
+
+        let mut collector = SymbolCollector {
+            file_id,
+            symbols: &mut self.symbols,
+        };
+        for statement in &program.body {
+            collector.collect_statement(statement);
+        }
+    }
+
+    pub fn remove_file(&mut self, file_id: usize) {
+        self.symbols.retain(|symbol| symbol.file_id != file_id);
+    }
+
+    // 模糊查找符号，名称不区分大小写，结果按照匹配程度排列：
+    //
+    // 1. 名称相同；
+    // 2. 名称以查询文本开始；
+    // 3. 名称包含查询文本；
+    // 4. 查询文本的字符依次出现在名称里，比如 `usl` 匹配 `user_list`。
+    //
+    // 匹配程度相同时较短的名称排在前面，查询文本为空时返回所有符号。
+    pub fn search(&self, query: &str) -> Vec<&Symbol> {
+        let query = query.to_lowercase();
+
+        let mut matches: Vec<(usize, &Symbol)> = self
+            .symbols
+            .iter()
+            .filter_map(|symbol| match_rank(&query, &symbol.name).map(|rank| (rank, symbol)))
+            .collect();
+
+        // 排序是稳定的，完全相同的符号保持添加的顺序
+        matches.sort_by(|(rank_a, a), (rank_b, b)| {
+            rank_a
+                .cmp(rank_b)
+                .then(a.name.len().cmp(&b.name.len()))
+                .then(a.name.cmp(&b.name))
+        });
+
+        matches.into_iter().map(|(_, symbol)| symbol).collect()
+    }
+
+    // 查找指定名称的定义，用于跳转到定义，`container` 为 None 时只查找不属于容器的符号
+    pub fn definitions(&self, name: &str, container: Option<&str>) -> Vec<&Symbol> {
+        self.symbols
+            .iter()
+            .filter(|symbol| symbol.name == name && symbol.container.as_deref() == container)
+            .collect()
+    }
+}
+
+// 名称跟查询文本（小写）的匹配程度，数值越小越匹配，不匹配时返回 None
+fn match_rank(query: &str, name: &str) -> Option<usize> {
+    let name = name.to_lowercase();
+
+    if name == query {
+        Some(0)
+    } else if name.starts_with(query) {
+        Some(1)
+    } else if name.contains(query) {
+        Some(2)
+    } else {
+        let mut chars = name.chars();
+        query.chars().all(|q| chars.any(|c| c == q)).then_some(3)
+    }
+}
+
+struct SymbolCollector<'a> {
+    file_id: usize,
+    symbols: &'a mut Vec<Symbol>,
+}
+
+impl SymbolCollector<'_> {
+    fn add(&mut self, name: &str, kind: SymbolKind, container: Option<&str>, range: &Range) {
+        self.symbols.push(Symbol {
+            name: name.to_string(),
+            kind,
+            container: container.map(str::to_string),
+            file_id: self.file_id,
+            range: range.clone(),
+        });
+    }
+
+    fn collect_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::FunctionDeclaration(f) => {
+                self.add(&f.name, SymbolKind::Function, None, &f.range)
+            }
+            Statement::EmptyFunctionDeclaration(f) => {
+                self.add(&f.name, SymbolKind::Function, None, &f.range)
+            }
+            Statement::PatternFunctionDeclaration(f) => {
+                self.add(&f.name, SymbolKind::Function, None, &f.range)
+            }
+            Statement::ConstDeclaration(c) => self.add(&c.name, SymbolKind::Const, None, &c.range),
+            Statement::MemberStructDeclaration(s) => {
+                self.add(&s.name, SymbolKind::Struct, None, &s.range);
+                for member in &s.members {
+                    self.add(
+                        &member.name,
+                        SymbolKind::Field,
+                        Some(&s.name),
+                        &member.range,
+                    );
+                }
+            }
+            Statement::TupleStructDeclaration(s) => {
+                self.add(&s.name, SymbolKind::Struct, None, &s.range)
+            }
+            Statement::EmptyStructDeclaration(s) => {
+                self.add(&s.name, SymbolKind::Struct, None, &s.range)
+            }
+            Statement::UnionDeclaration(u) => {
+                self.add(&u.name, SymbolKind::Union, None, &u.range);
+                for member in &u.members {
+                    let (name, range) = match member {
+                        UnionMember::Struct(s) => (&s.name, &s.range),
+                        UnionMember::Tuple(s) => (&s.name, &s.range),
+                        UnionMember::Empty(s) => (&s.name, &s.range),
+                    };
+                    self.add(name, SymbolKind::UnionMember, Some(&u.name), range);
+                }
+            }
+            Statement::TraitDeclaration(t) => {
+                self.add(&t.name, SymbolKind::Trait, None, &t.range);
+                for associated_type in &t.associated_types {
+                    self.add(
+                        &associated_type.name,
+                        SymbolKind::AssociatedType,
+                        Some(&t.name),
+                        &associated_type.range,
+                    );
+                }
+                for item in &t.function_items {
+                    let (name, range) = match item {
+                        TraitFunctionItem::Function(f) => (&f.name, &f.range),
+                        TraitFunctionItem::EmptyFunction(f) => (&f.name, &f.range),
+                    };
+                    self.add(name, SymbolKind::TraitFunction, Some(&t.name), range);
+                }
+            }
+            Statement::AliasStatement(a) => {
+                self.add(&a.name, SymbolKind::TypeAlias, None, &a.range)
+            }
+            Statement::Expression(Expression::LetExpression(l)) => {
+                for name in pattern_names(&l.object) {
+                    self.add(&name, SymbolKind::Variable, None, &l.range);
+                }
+            }
+            Statement::NamespaceStatement(_)
+            | Statement::UseStatement(_)
+            | Statement::ImplStatement(_)
+            | Statement::Expression(_)
+            | Statement::Error(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            EmptyStructDeclaration, Node, Program, Range, Statement, TupleStructDeclaration,
+            UnionDeclaration, UnionMember,
+        },
+        lexer, parser,
+    };
+
+    use super::{SymbolIndex, SymbolKind};
+
+    fn new_range() -> Range {
+        Range {
+            file_id: 0,
+            start: 0,
+            end: 0,
+        }
+    }
+
+    fn parse(source: &str) -> Program {
+        let token_details = lexer::tokenize(source).unwrap();
+        match parser::parse(&token_details).unwrap() {
+            Node::Program(program) => program,
+            _ => unreachable!(),
+        }
+    }
+
+    fn names(symbols: Vec<&super::Symbol>) -> Vec<&str> {
+        symbols.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_symbol_index() {
+        let mut index = SymbolIndex::new();
+
+        index.add_file(
+            0,
+            &parse(
+                "function user_list() = 1\n\
+                function add_user(Int id) = id\n\
+                let (users, count) = (1, 2)\n\
+                user_list()",
+            ),
+        );
+
+        // Option { Some(Int), None }
+        let mut program = parse("function listen() = 1");
+        program
+            .body
+            .push(Statement::UnionDeclaration(UnionDeclaration {
+                name: "Option".to_string(),
+                members: vec![
+                    UnionMember::Tuple(TupleStructDeclaration {
+                        attributes: vec![],
+                        name: "Some".to_string(),
+                        members: vec![],
+                        generics: vec![],
+                        whiches: vec![],
+                        range: new_range(),
+                    }),
+                    UnionMember::Empty(EmptyStructDeclaration {
+                        name: "None".to_string(),
+                        range: new_range(),
+                    }),
+                ],
+                generics: vec![],
+                whiches: vec![],
+                range: new_range(),
+            }));
+        index.add_file(1, &program);
+
+        assert_eq!(index.symbols().len(), 8);
+
+        // 模糊查找
+        assert_eq!(
+            names(index.search("user")),
+            vec!["users", "user_list", "add_user"]
+        );
+        assert_eq!(names(index.search("USL")), vec!["user_list"]);
+        assert_eq!(names(index.search("li")), vec!["listen", "user_list"]);
+        assert_eq!(index.search("").len(), 8);
+        assert!(index.search("xyz").is_empty());
+
+        // 跳转到定义
+        let symbols = index.definitions("None", Some("Option"));
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].kind, SymbolKind::UnionMember);
+        assert_eq!(symbols[0].file_id, 1);
+        assert!(index.definitions("None", None).is_empty());
+
+        let symbols = index.definitions("count", None);
+        assert_eq!(symbols[0].kind, SymbolKind::Variable);
+        assert_eq!(symbols[0].file_id, 0);
+
+        // 重新添加文件时替换旧的符号
+        index.add_file(0, &parse("function user_list() = 2"));
+        assert_eq!(index.symbols().len(), 5);
+        assert_eq!(names(index.search("user")), vec!["user_list"]);
+
+        index.remove_file(1);
+        assert_eq!(names(index.search("")), vec!["user_list"]);
+    }
+}
@@ -9,13 +9,23 @@ use core::fmt;
 use std::fmt::Write;
 
 // 记录 Token 在源文件中的位置
+//
+// 开始以及结束位置是 UTF-8 字节的偏移值，可以直接用于截取源代码的文本；
+// 行号以及列号指开始位置所在的行和列，均从 1 开始，列号按照字符（而不是字节）计算。
+// 行号为 0 表示位置未知（比如由程序构造的 Token）。
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Location {
     pub file_id: usize, // 源文件 id
     pub start: usize,   // 开始位置
     pub end: usize,     // 结束位置（不包括）
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub line: usize, // 开始位置的行号
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub column: usize, // 开始位置的列号
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     NewLine,            // 换行符号，包括 '\r\n', '\n'， '\r' 以及 ';'
@@ -108,7 +118,7 @@ pub enum Token {
     Default,
     Where,
     Only,
-    // As,
+    As,
     Into,
     Regular,
     Template,
@@ -120,7 +130,7 @@ pub enum Token {
     Pattern,
     Limit,
 
-    // Namespace,
+    Namespace,
     Use,
     Const,
     Enum,
@@ -131,26 +141,70 @@ pub enum Token {
     Alias,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct TokenDetail {
     pub location: Location,
     pub token: Token,
 }
 
+// 琐碎内容（trivia），即不影响语法分析的空白和注释
+//
+// 只有 `lexer::tokenize_with_trivia` 才会产生琐碎内容，
+// 每项琐碎内容均保存源代码里的原始文本（包括注释的起止符号），
+// 以便格式化工具和保留格式的重构工具能够原样还原源代码。
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trivia {
+    Whitespace(String),      // 连续的空格和制表符
+    LineComment(String),     // `// ...`，不包括行尾的换行符
+    BlockComment(String),    // `/* ... */`
+    DocumentComment(String), // `'''...'''`
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriviaDetail {
+    pub location: Location,
+    pub trivia: Trivia,
+}
+
+// `lexer::tokenize_with_trivia` 的结果项，
+// 琐碎内容按照其在源代码里的顺序穿插在 Token 之间。
+//
+// 注：换行符本身就是 Token（Token::NewLine），所以不属于琐碎内容。
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenOrTrivia {
+    Token(TokenDetail),
+    Trivia(TriviaDetail),
+}
+
 impl fmt::Display for Location {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "file id: {}, start: {}, end: {}",
-            self.file_id, self.start, self.end
+            "file id: {}, start: {}, end: {}, line: {}, column: {}",
+            self.file_id, self.start, self.end, self.line, self.column
         )
     }
 }
 
+impl fmt::Display for Trivia {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trivia::Whitespace(text)
+            | Trivia::LineComment(text)
+            | Trivia::BlockComment(text)
+            | Trivia::DocumentComment(text) => write!(f, "{}", text),
+        }
+    }
+}
+
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Token::NewLine => write!(f, "\n"),
+            Token::NewLine => writeln!(f),
             Token::Identifier(value) => write!(f, "{}", value),
 
             Token::Integer(value) => write!(f, "{}", value),
@@ -237,7 +291,7 @@ impl fmt::Display for Token {
             Token::Default => write!(f, "default"),
             Token::Where => write!(f, "where"),
             Token::Only => write!(f, "only"),
-            // Token::As => write!(f, "as"),
+            Token::As => write!(f, "as"),
             Token::Into => write!(f, "into"),
             Token::Regular => write!(f, "regular"),
             Token::Template => write!(f, "template"),
@@ -249,7 +303,7 @@ impl fmt::Display for Token {
             Token::Pattern => write!(f, "pattern"),
             Token::Limit => write!(f, "limit"),
 
-            // Token::Namespace => write!(f, "namespace"),
+            Token::Namespace => write!(f, "namespace"),
             Token::Use => write!(f, "use"),
             Token::Const => write!(f, "const"),
             Token::Enum => write!(f, "enum"),
@@ -268,9 +322,54 @@ impl fmt::Display for TokenDetail {
     }
 }
 
+impl Token {
+    // Token 的种类，即枚举成员的名称，比如 `Identifier`、`LeftParen`
+    pub fn kind(&self) -> String {
+        let text = format!("{:?}", self);
+        match text.find('(') {
+            Some(index) => text[..index].to_string(),
+            None => text,
+        }
+    }
+}
+
+// 以表格的形式输出 Token 列表，每行一个 Token，依次为位置、种类以及文本，比如：
+//
+// ```text
+// 0..1         Identifier           a
+// 2..3         Plus                 +
+// ```
+//
+// 文本里的换行符等控制字符会被转义，以保证每个 Token 只占一行。
+// 输出的格式是稳定的，可以直接用于比较词法分析器的测试结果。
+pub fn dump(token_details: &[TokenDetail]) -> String {
+    let mut text = String::new();
+    for token_detail in token_details {
+        let span = format!(
+            "{}..{}",
+            token_detail.location.start, token_detail.location.end
+        );
+        let lexeme = token_detail
+            .token
+            .to_string()
+            .chars()
+            .map(|c| {
+                if c.is_control() {
+                    c.escape_debug().to_string()
+                } else {
+                    c.to_string()
+                }
+            })
+            .collect::<String>();
+        let line = format!("{:<12} {:<20} {}", span, token_detail.token.kind(), lexeme);
+        writeln!(text, "{}", line.trim_end()).unwrap();
+    }
+    text
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Location, Token, TokenDetail};
+    use super::{dump, Location, Token, TokenDetail};
 
     #[test]
     fn test_location_display() {
@@ -278,9 +377,11 @@ mod tests {
             file_id: 1,
             start: 2,
             end: 3,
+            line: 1,
+            column: 3,
         };
 
-        assert_eq!(ca1.to_string(), "file id: 1, start: 2, end: 3");
+        assert_eq!(ca1.to_string(), "file id: 1, start: 2, end: 3, line: 1, column: 3");
     }
 
     #[test]
@@ -289,16 +390,22 @@ mod tests {
             file_id: 1,
             start: 2,
             end: 3,
+            line: 1,
+            column: 3,
         };
         let ca2 = Location {
             file_id: 2,
             start: 2,
             end: 3,
+            line: 1,
+            column: 3,
         };
         let ca3 = Location {
             file_id: 1,
             start: 2,
             end: 3,
+            line: 1,
+            column: 3,
         };
 
         assert_ne!(ca1, ca2);
@@ -392,7 +499,7 @@ mod tests {
         assert_eq!(Token::Default.to_string(), "default");
         assert_eq!(Token::Where.to_string(), "where");
         assert_eq!(Token::Only.to_string(), "only");
-        // assert_eq!(Token::As.to_string(), "as");
+        assert_eq!(Token::As.to_string(), "as");
         assert_eq!(Token::Into.to_string(), "into");
         assert_eq!(Token::Regular.to_string(), "regular");
         assert_eq!(Token::Template.to_string(), "template");
@@ -404,7 +511,7 @@ mod tests {
         assert_eq!(Token::Pattern.to_string(), "pattern");
         assert_eq!(Token::Limit.to_string(), "limit");
 
-        // assert_eq!(Token::Namespace.to_string(), "namespace");
+        assert_eq!(Token::Namespace.to_string(), "namespace");
         assert_eq!(Token::Use.to_string(), "use");
         assert_eq!(Token::Const.to_string(), "const");
         assert_eq!(Token::Enum.to_string(), "enum");
@@ -435,6 +542,8 @@ mod tests {
                 file_id: 1,
                 start: 2,
                 end: 3,
+                line: 1,
+                column: 3,
             },
             token: Token::Plus, // Add
         };
@@ -444,11 +553,42 @@ mod tests {
                 file_id: 1,
                 start: 2,
                 end: 3,
+                line: 1,
+                column: 3,
             },
             token: Token::Minus, // Subtract
         };
 
-        assert_eq!(tk1.to_string(), "[file id: 1, start: 2, end: 3] +");
+        assert_eq!(tk1.to_string(), "[file id: 1, start: 2, end: 3, line: 1, column: 3] +");
         assert_ne!(tk1, tk2);
     }
+
+    #[test]
+    fn test_dump() {
+        let new_token_detail = |token: Token, start: usize, end: usize| TokenDetail {
+            location: Location {
+                file_id: 0,
+                start,
+                end,
+                line: 1,
+                column: start + 1,
+            },
+            token,
+        };
+
+        let token_details = vec![
+            new_token_detail(Token::Identifier("a".to_string()), 0, 1),
+            new_token_detail(Token::Plus, 2, 3),
+            new_token_detail(Token::GeneralString("x\ty".to_string()), 4, 10),
+            new_token_detail(Token::NewLine, 10, 11),
+        ];
+
+        assert_eq!(
+            dump(&token_details),
+            "0..1         Identifier           a\n\
+            2..3         Plus                 +\n\
+            4..10        GeneralString        \"x\\ty\"\n\
+            10..11       NewLine              \\n\n"
+        );
+    }
 }
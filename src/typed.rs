@@ -0,0 +1,505 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 带类型标注的打印
+//
+// 把推导出来的类型作为标注插入到程序里，然后打印程序，比如：
+//
+// - `let i = 1` => `let Int i = 1`
+// - `fn (Int a) = a + 1` => `fn (Int a) type Int = a + 1`
+// - `function f(Int a) = a > 0` => `function f (Int a) type Boolean = ...`
+//
+// 用于教学，以及在测试里验证类型推导的结果。
+//
+// 推导的过程同时记录每个已确定类型的表达式的范围以及类型（见 `expression_types`），
+// 用于按位置查询表达式的类型（`type_at`），比如编辑器的悬停提示以及内嵌提示。
+//
+// 注：
+// 完整的类型检查（类型推导）尚未实现，目前只在局部推导能够确定的类型：
+// 常量表达式（见 `fold` 模块）、已标注类型的名称（参数以及 `let` 表达式）、
+// 比较以及逻辑运算（Boolean）、两侧类型相同的算术运算、两个分支类型相同的 `if` 表达式、
+// 表达式块的最后一个表达式，以及调用已知返回值类型的函数。
+// `impl` 语句里的函数参数的 `Self` 以及关联类型的投影（比如 `Self::Item`）
+// 按照 `impl` 语句的绑定规范化为具体的类型（见 `semantics::normalize_data_type`）。
+// 无法确定类型的地方保持原样，已有的标注不会被修改。
+
+use crate::{
+    ast::{
+        AnonymousFunction, BinaryOperator, DataType, Expression, FunctionDeclaration, Identifier,
+        LetExpression, Node, Program, Range, Statement, TraitDeclaration, UnaryOperator,
+    },
+    fold,
+    semantics::{
+        associated_type_bindings, match_pattern_names, normalize_data_type, pattern_names,
+    },
+};
+
+// 在 AST 里插入推导出来的类型
+pub fn annotate_types(node: &mut Node) {
+    TypeAnnotator::default().annotate_node(node);
+}
+
+// 程序里每个已确定类型的表达式（以及 `let` 表达式绑定的名称）的范围以及类型，
+// 按照推导的顺序（子表达式在前）排列
+pub fn expression_types(program: &Program) -> Vec<(Range, DataType)> {
+    let mut annotator = TypeAnnotator::default();
+    annotator.annotate_node(&mut Node::Program(program.clone()));
+    annotator.types
+}
+
+// 覆盖字节偏移量 offset 的最内层（范围最短）的、已确定类型的表达式的类型
+//
+// types 为 `expression_types` 的结果，没有覆盖 offset 的表达式时返回 None。
+pub fn type_at(types: &[(Range, DataType)], offset: usize) -> Option<&DataType> {
+    types
+        .iter()
+        .filter(|(range, _)| range.start <= offset && offset < range.end)
+        .min_by_key(|(range, _)| range.end - range.start)
+        .map(|(_, data_type)| data_type)
+}
+
+// 打印带有推导出来的类型标注的程序
+pub fn print_with_types(node: &Node) -> String {
+    let mut node = node.clone();
+    annotate_types(&mut node);
+    node.to_string()
+}
+
+#[derive(Default)]
+struct TypeAnnotator {
+    functions: Vec<(String, DataType)>, // 顶层函数的名称以及返回值类型
+    traits: Vec<TraitDeclaration>,      // 程序里声明的特性，用于规范化关联类型
+
+    // 当前作用域（包括外层作用域）里已绑定的名称以及类型（如果已知）
+    bound_names: Vec<(String, Option<DataType>)>,
+
+    types: Vec<(Range, DataType)>, // 已确定类型的表达式的范围以及类型
+}
+
+impl TypeAnnotator {
+    fn annotate_node(&mut self, node: &mut Node) {
+        match node {
+            Node::Program(program) => {
+                // 已标注返回值类型的函数
+                for statement in &program.body {
+                    match statement {
+                        Statement::FunctionDeclaration(f) => {
+                            if let Some(data_type) = &f.return_data_type {
+                                self.functions.push((f.name.clone(), data_type.clone()));
+                            }
+                        }
+                        Statement::TraitDeclaration(t) => self.traits.push(t.clone()),
+                        _ => {}
+                    }
+                }
+
+                for statement in &mut program.body {
+                    self.annotate_statement(statement);
+                }
+            }
+            Node::Statement(statement) => self.annotate_statement(statement),
+            Node::Expression(expression) => {
+                self.annotate(expression);
+            }
+        }
+    }
+
+    fn annotate_statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::FunctionDeclaration(f) => {
+                self.annotate_function(f, &DataType::clone);
+                if let Some(data_type) = &f.return_data_type {
+                    if !self.functions.iter().any(|(name, _)| *name == f.name) {
+                        self.functions.push((f.name.clone(), data_type.clone()));
+                    }
+                }
+            }
+            Statement::ImplStatement(i) => {
+                // 参数的类型 `Self` 以及 `Self::Item` 规范化为具体的类型
+                let trait_declaration = self
+                    .traits
+                    .iter()
+                    .find(|t| i.inherit.dirs.is_empty() && t.name == i.inherit.name);
+                let bindings = associated_type_bindings(i, trait_declaration);
+                let object = i.object.clone();
+                let normalize = |t: &DataType| normalize_data_type(t, &object, &bindings);
+
+                for function in &mut i.function_items {
+                    self.annotate_function(function, &normalize);
+                }
+            }
+            _ => {
+                for expression in statement.expressions_mut() {
+                    self.annotate(expression);
+                }
+            }
+        }
+    }
+
+    // normalize 用于规范化参数的类型（见 `semantics::normalize_data_type`）
+    fn annotate_function(
+        &mut self,
+        function: &mut FunctionDeclaration,
+        normalize: &dyn Fn(&DataType) -> DataType,
+    ) {
+        let mark = self.bound_names.len();
+        for parameter in &function.parameters {
+            self.bind(&parameter.name, Some(normalize(&parameter.data_type)));
+        }
+
+        let body_type = self.annotate(&mut function.body);
+        if function.return_data_type.is_none() {
+            function.return_data_type = body_type;
+        }
+        self.bound_names.truncate(mark);
+    }
+
+    fn annotate_anonymous_function(&mut self, function: &mut AnonymousFunction) {
+        let mark = self.bound_names.len();
+        for parameter in &function.parameters {
+            self.bind(&parameter.name, parameter.data_type.clone());
+        }
+
+        let body_type = self.annotate(&mut function.body);
+        if function.return_data_type.is_none() {
+            function.return_data_type = body_type;
+        }
+        self.bound_names.truncate(mark);
+    }
+
+    fn annotate_let(&mut self, let_exp: &mut LetExpression) {
+        let value_type = self.annotate(&mut let_exp.value);
+
+        match let_exp.object.as_ref() {
+            Expression::Identifier(i) => {
+                if let_exp.data_type.is_none() {
+                    let_exp.data_type = value_type;
+                }
+                if let Some(data_type) = &let_exp.data_type {
+                    self.types.push((i.range.clone(), data_type.clone()));
+                }
+                self.bind(&i.name, let_exp.data_type.clone());
+            }
+            pattern => self.bind_unknown(pattern_names(pattern)),
+        }
+    }
+
+    fn bind(&mut self, name: &str, data_type: Option<DataType>) {
+        self.bound_names.push((name.to_string(), data_type));
+    }
+
+    // 类型未知的名称，比如解构模式里的名称，用于遮盖外层的同名名称
+    fn bind_unknown(&mut self, names: Vec<String>) {
+        self.bound_names
+            .extend(names.into_iter().map(|name| (name, None)));
+    }
+
+    fn lookup(&self, name: &str) -> Option<DataType> {
+        self.bound_names
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .and_then(|(_, data_type)| data_type.clone())
+    }
+
+    // 插入子表达式的类型标注，并返回表达式的类型（如果能够确定）
+    fn annotate(&mut self, expression: &mut Expression) -> Option<DataType> {
+        let data_type = self.infer(expression);
+        if let Some(data_type) = &data_type {
+            self.types
+                .push((expression.range().clone(), data_type.clone()));
+        }
+        data_type
+    }
+
+    fn infer(&mut self, expression: &mut Expression) -> Option<DataType> {
+        if let Some(constant) = fold::fold(expression) {
+            return Some(new_data_type(constant.type_name()));
+        }
+
+        match expression {
+            Expression::Identifier(i) if i.dirs.is_empty() => self.lookup(&i.name),
+            Expression::BlockExpression(v) => {
+                let mark = self.bound_names.len();
+                let mut data_type = None;
+                for e in &mut v.body {
+                    data_type = self.annotate(e);
+                }
+                self.bound_names.truncate(mark);
+                data_type
+            }
+            Expression::LetExpression(v) => {
+                self.annotate_let(v);
+                None
+            }
+            Expression::BinaryExpression(v) => {
+                let left = self.annotate(&mut v.left);
+                let right = self.annotate(&mut v.right);
+
+                match v.operator {
+                    BinaryOperator::LogicOr
+                    | BinaryOperator::LogicAnd
+                    | BinaryOperator::Equal
+                    | BinaryOperator::NotEqual
+                    | BinaryOperator::GreaterThan
+                    | BinaryOperator::GreaterThanOrEqual
+                    | BinaryOperator::LessThan
+                    | BinaryOperator::LessThanOrEqual => Some(new_data_type("Boolean")),
+                    BinaryOperator::Plus
+                    | BinaryOperator::Minus
+                    | BinaryOperator::Asterisk
+                    | BinaryOperator::Slash
+                    | BinaryOperator::Concat => same_type(left, right),
+                    _ => None,
+                }
+            }
+            Expression::UnaryExpression(v) => {
+                let operand = self.annotate(&mut v.operand);
+                match v.operator {
+                    UnaryOperator::Minus => operand,
+                    _ => None,
+                }
+            }
+            Expression::IfExpression(v) => {
+                // `where` 定义的名称在 `else` 从属表达式里无效
+                let mark = self.bound_names.len();
+                if let Some(where_exp) = &mut v.where_exp {
+                    self.annotate(where_exp);
+                }
+                self.annotate(&mut v.testing);
+                let consequent = self.annotate(&mut v.consequent);
+                self.bound_names.truncate(mark);
+
+                let alternate = v.alternate.as_mut().map(|e| self.annotate(e))?;
+                same_type(consequent, alternate)
+            }
+            Expression::ForExpression(v) => {
+                let mark = self.bound_names.len();
+                self.annotate_let(&mut v.initializer);
+                self.annotate(&mut v.body);
+                self.bound_names.truncate(mark);
+                None
+            }
+            Expression::EachExpression(v) => {
+                self.annotate(&mut v.object);
+                let mark = self.bound_names.len();
+                self.bind_unknown(pattern_names(&v.variable));
+                self.annotate(&mut v.body);
+                self.bound_names.truncate(mark);
+                None
+            }
+            Expression::BranchExpression(v) => {
+                let mark = self.bound_names.len();
+                if let Some(where_exp) = &mut v.where_exp {
+                    self.annotate(where_exp);
+                }
+                for c in &mut v.cases {
+                    let case_mark = self.bound_names.len();
+                    if let Some(where_exp) = &mut c.where_exp {
+                        self.annotate(where_exp);
+                    }
+                    self.annotate(&mut c.testing);
+                    self.annotate(&mut c.consequent);
+                    self.bound_names.truncate(case_mark);
+                }
+                if let Some(default_exp) = &mut v.default_exp {
+                    self.annotate(default_exp);
+                }
+                self.bound_names.truncate(mark);
+                None
+            }
+            Expression::MatchExpression(v) => {
+                self.annotate(&mut v.object);
+                let mark = self.bound_names.len();
+                if let Some(where_exp) = &mut v.where_exp {
+                    self.annotate(where_exp);
+                }
+                for c in &mut v.cases {
+                    let case_mark = self.bound_names.len();
+                    self.bind_unknown(c.variable.iter().cloned().collect());
+                    if let Some(pattern) = &c.pattern {
+                        self.bind_unknown(match_pattern_names(pattern));
+                    }
+                    if let Some(where_exp) = &mut c.where_exp {
+                        self.annotate(where_exp);
+                    }
+                    if let Some(only) = &mut c.only {
+                        self.annotate(only);
+                    }
+                    self.annotate(&mut c.consequent);
+                    self.bound_names.truncate(case_mark);
+                }
+                if let Some(default_exp) = &mut v.default_exp {
+                    self.annotate(default_exp);
+                }
+                self.bound_names.truncate(mark);
+                None
+            }
+            Expression::AnonymousFunction(v) => {
+                self.annotate_anonymous_function(v);
+                None
+            }
+            Expression::FunctionCallExpression(v) => {
+                for argument in &mut v.arguments {
+                    self.annotate(&mut argument.value);
+                }
+
+                match v.callee.as_ref() {
+                    // 局部名称遮盖了同名的函数
+                    Expression::Identifier(i)
+                        if i.dirs.is_empty()
+                            && !self.bound_names.iter().any(|(n, _)| *n == i.name) =>
+                    {
+                        self.functions
+                            .iter()
+                            .find(|(name, _)| *name == i.name)
+                            .map(|(_, data_type)| data_type.clone())
+                    }
+                    _ => {
+                        self.annotate(&mut v.callee);
+                        None
+                    }
+                }
+            }
+            _ => {
+                for child in expression.children_mut() {
+                    self.annotate(child);
+                }
+                None
+            }
+        }
+    }
+}
+
+// 两个类型都已知并且相同时返回该类型
+fn same_type(left: Option<DataType>, right: Option<DataType>) -> Option<DataType> {
+    match (left, right) {
+        (Some(left), Some(right)) if left.to_string() == right.to_string() => Some(left),
+        _ => None,
+    }
+}
+
+fn new_data_type(name: &str) -> DataType {
+    DataType::Identifier(Identifier {
+        dirs: vec![],
+        name: name.to_string(),
+        generics: vec![],
+        range: Range {
+            file_id: 0,
+            start: 0,
+            end: 0,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Node, lexer, parser};
+
+    use super::{expression_types, print_with_types, type_at};
+
+    fn print(source: &str) -> String {
+        let token_details = lexer::tokenize(source).unwrap();
+        let node = parser::parse(&token_details).unwrap();
+        print_with_types(&node)
+    }
+
+    #[test]
+    fn test_print_with_types() {
+        assert_eq!(print("let i = 1"), "let Int i = 1\n");
+        assert_eq!(
+            print("let s = \"foo\" ++ \"bar\"\nlet t = s ++ \"baz\""),
+            "let String s = (\"foo\" ++ \"bar\")\nlet String t = (s ++ \"baz\")\n"
+        );
+
+        // 已有的标注以及无法确定的类型保持原样
+        assert_eq!(print("let Float x = f(1)"), "let Float x = (f)(1)\n");
+        assert_eq!(print("let y = f(1)"), "let y = (f)(1)\n");
+
+        // 匿名函数
+        assert_eq!(
+            print("let g = fn (Int a) a + 1"),
+            "let g = fn (Int a) type Int = (a + 1)\n"
+        );
+
+        // 函数的返回值类型，以及调用已知返回值类型的函数
+        assert_eq!(
+            print("function f(Int a) = a > 0\nlet b = f(1)"),
+            "function f (Int a) type Boolean = (a > 0)\nlet Boolean b = (f)(1)\n"
+        );
+    }
+
+    #[test]
+    fn test_print_with_types_scopes() {
+        // 表达式块以及 `if` 表达式
+        assert_eq!(
+            print("let x = do {\nlet a = 1\nif a > 0 then a else 0\n}"),
+            "let Int x = do {\nlet Int a = 1\nif (a > 0) then a else 0\n}\n"
+        );
+
+        // 解构模式以及 `each` 的变量遮盖了外层的同名名称
+        assert_eq!(
+            print("let a = 1\neach a in list do {\nlet b = a\n}"),
+            "let Int a = 1\neach a in list do {\nlet b = a\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_print_with_associated_types() {
+        // `impl` 语句里参数的 `Self::Item` 规范化为绑定的类型，`Self` 规范化为实现特性的类型
+        assert_eq!(
+            print(
+                "trait Sequence type (Item, Size = Int) {}
+                impl Range trait Sequence type Item = Float {
+                    function twice(Self::Item a) = a + a
+                    function count(Size n) = n * 2
+                    function same(Self s, Range t) = s == t
+                }"
+            ),
+            [
+                "trait Sequence type (Item, Size = Int) {",
+                "}",
+                "impl Range trait Sequence type Item = Float {",
+                "function twice (Self::Item a) type Float = (a + a)",
+                "function count (Size n) type Int = (n * 2)",
+                "function same (Self s, Range t) type Boolean = (s == t)",
+                "}",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_type_at() {
+        let source = "function f(Int a) = a > 0\nlet b = f(1)\nlet c = g(b, 2.5)";
+        let token_details = lexer::tokenize(source).unwrap();
+        let Node::Program(program) = parser::parse(&token_details).unwrap() else {
+            unreachable!()
+        };
+        let types = expression_types(&program);
+        let type_name = |text: &str| {
+            let offset = source.find(text).unwrap();
+            type_at(&types, offset).map(|t| t.to_string())
+        };
+
+        // 最内层的表达式
+        assert_eq!(type_name("a > 0"), Some("Int".to_string()));
+        assert_eq!(type_name("> 0"), Some("Boolean".to_string()));
+        assert_eq!(type_name("f(1)"), Some("Boolean".to_string()));
+        assert_eq!(type_name("2.5"), Some("Float".to_string()));
+
+        // `let` 表达式绑定的名称
+        assert_eq!(type_name("b = "), Some("Boolean".to_string()));
+        assert_eq!(type_name("b, "), Some("Boolean".to_string()));
+
+        // 类型无法确定
+        assert_eq!(type_name("g("), None);
+        assert_eq!(type_name("c = "), None);
+    }
+}
@@ -0,0 +1,95 @@
+/*
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// WebAssembly 接口
+//
+// 供在线演练场（playground）在浏览器里实时显示 Token、AST 以及错误信息。
+//
+// 编译方法：
+// $ wasm-pack build --target web -- --features wasm
+//
+// 注：
+// 导出的函数不能 panic（在浏览器里 panic 会导致整个 wasm 实例不可用），
+// 所有错误均以 JavaScript 异常（Error）或者字符串的形式返回。
+
+use wasm_bindgen::prelude::*;
+
+use crate::{error::Error, json, lexer, parser};
+
+// 对源代码进行词法分析，以 JSON 数组的格式返回所有 Token
+#[wasm_bindgen]
+pub fn tokenize(source: &str) -> Result<String, JsError> {
+    tokenize_to_json(source).map_err(|e| JsError::new(&render_error(&e)))
+}
+
+// 对源代码进行语法分析，以 JSON 文档的格式返回 AST（格式见 `docs/ast_json_schema.md`）
+#[wasm_bindgen]
+pub fn parse(source: &str) -> Result<String, JsError> {
+    parse_to_json(source).map_err(|e| JsError::new(&render_error(&e)))
+}
+
+// 对源代码进行词法分析和语法分析，返回渲染好的错误信息，
+// 如果源代码没有错误，则返回空字符串。
+#[wasm_bindgen(js_name = renderDiagnostics)]
+pub fn render_diagnostics(source: &str) -> String {
+    match parse_to_json(source) {
+        Ok(_) => String::new(),
+        Err(e) => render_error(&e),
+    }
+}
+
+fn tokenize_to_json(source: &str) -> Result<String, Error> {
+    let token_details = lexer::tokenize(source)?;
+    Ok(serde_json::to_string(&token_details).unwrap())
+}
+
+fn parse_to_json(source: &str) -> Result<String, Error> {
+    let token_details = lexer::tokenize(source)?;
+    let node = parser::parse(&token_details)?;
+    Ok(json::to_json(&node))
+}
+
+fn render_error(error: &Error) -> String {
+    if error.is_lexer_error() {
+        format!("lexer error: {}", error)
+    } else {
+        format!("parser error: {}", error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_to_json, render_diagnostics, tokenize_to_json};
+
+    #[test]
+    fn test_tokenize_to_json() {
+        let j1 = tokenize_to_json("a+1").unwrap();
+        assert!(j1.contains("\"Identifier\":\"a\""));
+        assert!(j1.contains("\"Integer\":1"));
+
+        assert!(tokenize_to_json("a $ 1").is_err());
+    }
+
+    #[test]
+    fn test_parse_to_json() {
+        let j1 = parse_to_json("a+1").unwrap();
+        assert!(j1.contains("\"BinaryExpression\""));
+
+        assert!(parse_to_json("(1,").is_err());
+    }
+
+    #[test]
+    fn test_render_diagnostics() {
+        assert_eq!(render_diagnostics("1+2"), "");
+        assert_eq!(render_diagnostics("1 $ 2"), "lexer error: invalid char '$'");
+
+        // 无效的语法应该返回错误而不是 panic
+        assert!(render_diagnostics("0x").starts_with("lexer error: "));
+        assert!(render_diagnostics("const a =").starts_with("parser error: "));
+    }
+}